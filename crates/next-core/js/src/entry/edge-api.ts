@@ -0,0 +1,23 @@
+import IPC, { Ipc } from "@vercel/turbopack-next/internal/ipc";
+import {
+  EdgeIpcIncomingMessage,
+  EdgeIpcOutgoingMessage,
+  serveEdgeFunction,
+} from "@vercel/turbopack-next/internal/edge-runtime";
+import { NextRequest } from "next/dist/server/web/spec-extension/request";
+
+import * as allExports from ".";
+
+const ipc = IPC as Ipc<EdgeIpcIncomingMessage, EdgeIpcOutgoingMessage>;
+
+// API routes which export `runtime = "edge"` receive a `NextRequest` and return
+// a `Response`, like in the edge runtime of Next.js.
+serveEdgeFunction(ipc, async (request) => {
+  const handler = (allExports as any).default;
+  if (typeof handler !== "function") {
+    throw new Error("The API route does not export a default function.");
+  }
+  return await handler(new NextRequest(request));
+}).catch((err) => {
+  ipc.sendError(err);
+});
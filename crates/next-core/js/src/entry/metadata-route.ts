@@ -0,0 +1,203 @@
+import type { Ipc } from "@vercel/turbopack-next/internal/ipc";
+
+// Provided by the rust generate code
+type MetadataType =
+  | "robots"
+  | "sitemap"
+  | "manifest"
+  | "icon"
+  | "apple-icon"
+  | "opengraph-image"
+  | "twitter-image";
+declare global {
+  // the metadata file convention of the module
+  const METADATA_TYPE: MetadataType;
+  const IPC: Ipc<unknown, unknown>;
+}
+
+import { Buffer } from "node:buffer";
+import type { RenderData } from "types/turbopack";
+
+import "next/dist/server/node-polyfill-fetch.js";
+
+import * as metadataExports from ".";
+
+// Text files are rendered like pages, images are proxied like API routes, as
+// their body is binary.
+type IpcIncomingMessage =
+  | {
+      type: "headers";
+      data: RenderData;
+    }
+  | {
+      type: "bodyChunk";
+      data: Array<number>;
+    }
+  | { type: "bodyEnd" };
+
+type IpcOutgoingMessage =
+  | {
+      type: "result";
+      result: { body: string; contentType: string };
+    }
+  | {
+      type: "headers";
+      data: { status: number; headers: string[] };
+    }
+  | {
+      type: "body";
+      data: Array<number>;
+    };
+
+const ipc = IPC as Ipc<IpcIncomingMessage, IpcOutgoingMessage>;
+
+const IMAGE_TYPES: MetadataType[] = [
+  "icon",
+  "apple-icon",
+  "opengraph-image",
+  "twitter-image",
+];
+
+(async () => {
+  while (true) {
+    const msg = await ipc.recv();
+
+    let renderData: RenderData;
+    switch (msg.type) {
+      case "headers": {
+        renderData = msg.data;
+        break;
+      }
+      default: {
+        console.error("unexpected message type", msg.type);
+        process.exit(1);
+      }
+    }
+
+    if (IMAGE_TYPES.includes(METADATA_TYPE)) {
+      // The request body is not used.
+      while ((await ipc.recv()).type !== "bodyEnd") {}
+      await sendImage(renderData);
+    } else {
+      await ipc.send({ type: "result", result: await renderText() });
+    }
+  }
+})().catch((err) => {
+  ipc.sendError(err);
+});
+
+/**
+ * Calls an image route, e. g. `opengraph-image.tsx`, which returns a
+ * `Response` like an `ImageResponse` of `next/server`.
+ */
+async function sendImage(renderData: RenderData) {
+  const response: Response = await (metadataExports as any).default({
+    params: renderData.params,
+  });
+  const headers: string[] = [];
+  response.headers.forEach((value, name) => {
+    headers.push(name, value);
+  });
+  const { contentType } = metadataExports as any;
+  if (!response.headers.has("content-type") && contentType != null) {
+    headers.push("content-type", contentType);
+  }
+  await ipc.send({
+    type: "headers",
+    data: { status: response.status, headers },
+  });
+  await ipc.send({
+    type: "body",
+    data: Buffer.from(await response.arrayBuffer()).toJSON().data,
+  });
+}
+
+async function renderText(): Promise<{ body: string; contentType: string }> {
+  const data = await (metadataExports as any).default();
+  switch (METADATA_TYPE) {
+    case "robots":
+      return { body: robotsTxt(data), contentType: "text/plain" };
+    case "sitemap":
+      return { body: sitemapXml(data), contentType: "application/xml" };
+    default:
+      return {
+        body: JSON.stringify(data),
+        contentType: "application/manifest+json",
+      };
+  }
+}
+
+type Robots = {
+  rules: RobotsRule | RobotsRule[];
+  sitemap?: string | string[];
+  host?: string;
+};
+
+type RobotsRule = {
+  userAgent?: string | string[];
+  allow?: string | string[];
+  disallow?: string | string[];
+  crawlDelay?: number;
+};
+
+function robotsTxt(robots: Robots): string {
+  let content = "";
+  for (const rule of [robots.rules].flat()) {
+    for (const userAgent of [rule.userAgent ?? "*"].flat()) {
+      content += `User-Agent: ${userAgent}\n`;
+    }
+    for (const allow of [rule.allow ?? []].flat()) {
+      content += `Allow: ${allow}\n`;
+    }
+    for (const disallow of [rule.disallow ?? []].flat()) {
+      content += `Disallow: ${disallow}\n`;
+    }
+    if (rule.crawlDelay != null) {
+      content += `Crawl-delay: ${rule.crawlDelay}\n`;
+    }
+    content += "\n";
+  }
+  if (robots.host != null) {
+    content += `Host: ${robots.host}\n`;
+  }
+  for (const sitemap of [robots.sitemap ?? []].flat()) {
+    content += `Sitemap: ${sitemap}\n`;
+  }
+  return content;
+}
+
+type Sitemap = Array<{
+  url: string;
+  lastModified?: string | Date;
+  changeFrequency?: string;
+  priority?: number;
+}>;
+
+function sitemapXml(sitemap: Sitemap): string {
+  let content =
+    '<?xml version="1.0" encoding="UTF-8"?>\n' +
+    '<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">\n';
+  for (const entry of sitemap) {
+    content += `<url>\n<loc>${escapeXml(entry.url)}</loc>\n`;
+    if (entry.lastModified != null) {
+      const lastModified = new Date(entry.lastModified).toISOString();
+      content += `<lastmod>${lastModified}</lastmod>\n`;
+    }
+    if (entry.changeFrequency != null) {
+      content += `<changefreq>${entry.changeFrequency}</changefreq>\n`;
+    }
+    if (entry.priority != null) {
+      content += `<priority>${entry.priority}</priority>\n`;
+    }
+    content += "</url>\n";
+  }
+  return content + "</urlset>\n";
+}
+
+function escapeXml(value: string): string {
+  return value
+    .replace(/&/g, "&amp;")
+    .replace(/</g, "&lt;")
+    .replace(/>/g, "&gt;")
+    .replace(/"/g, "&quot;");
+}
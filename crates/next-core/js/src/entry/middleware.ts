@@ -0,0 +1,37 @@
+import IPC, { Ipc } from "@vercel/turbopack-next/internal/ipc";
+import {
+  EdgeIpcIncomingMessage,
+  EdgeIpcOutgoingMessage,
+  serveEdgeFunction,
+} from "@vercel/turbopack-next/internal/edge-runtime";
+import { NextFetchEvent } from "next/dist/server/web/spec-extension/fetch-event";
+import { NextRequest } from "next/dist/server/web/spec-extension/request";
+
+import * as middlewareExports from ".";
+
+const ipc = IPC as Ipc<EdgeIpcIncomingMessage, EdgeIpcOutgoingMessage>;
+
+// The dev server reads the `x-middleware-next` and `x-middleware-rewrite`
+// headers of the response to continue with the pages, and serves any other
+// response as it is.
+serveEdgeFunction(ipc, async (request) => {
+  const handler =
+    (middlewareExports as any).middleware ??
+    (middlewareExports as any).default;
+  if (typeof handler !== "function") {
+    throw new Error(
+      "The middleware must export a `middleware` or a default function."
+    );
+  }
+  const nextRequest = new NextRequest(request);
+  const event = new NextFetchEvent({ request: nextRequest, page: "/" });
+  const response: Response | null | undefined = await handler(
+    nextRequest,
+    event
+  );
+  return (
+    response ?? new Response(null, { headers: { "x-middleware-next": "1" } })
+  );
+}).catch((err) => {
+  ipc.sendError(err);
+});
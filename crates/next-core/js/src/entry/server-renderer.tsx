@@ -6,8 +6,9 @@ import "@vercel/turbopack-next/internal/shims";
 import "next/dist/server/node-polyfill-fetch.js";
 import { renderToHTML } from "next/dist/server/render";
 import type { RenderOpts } from "next/dist/server/render";
-import type { RenderData } from "types/turbopack";
+import type { I18NConfig, RenderData } from "types/turbopack";
 import { ServerResponseShim } from "@vercel/turbopack-next/internal/http";
+import { reactLoadableManifest } from "@vercel/turbopack-next/internal/loadable-manifest";
 
 import App from "@vercel/turbopack-next/pages/_app";
 import Document from "@vercel/turbopack-next/pages/_document";
@@ -27,7 +28,14 @@ type IpcIncomingMessage = {
 
 type IpcOutgoingMessage = {
   type: "result";
-  result: string | { body: string; contentType?: string };
+  result:
+    | string
+    | {
+        body: string;
+        contentType?: string;
+        revalidate?: number;
+        notFound?: boolean;
+      };
 };
 
 (async () => {
@@ -61,9 +69,48 @@ type IpcOutgoingMessage = {
   ipc.sendError(err);
 });
 
+type StaticPaths = {
+  paths: Array<string | { params: Record<string, string | string[]> }>;
+  fallback: boolean | "blocking";
+};
+
+// The process is restarted when the page changes, so `getStaticPaths` only
+// needs to run once.
+let staticPaths: Promise<StaticPaths> | undefined;
+
+/**
+ * Checks whether a dynamic page is prerendered for the path, or rendered on
+ * demand as a fallback.
+ */
+async function isStaticPath(renderData: RenderData): Promise<boolean> {
+  if (
+    !("getStaticPaths" in otherExports) ||
+    Object.keys(renderData.params).length === 0
+  ) {
+    return true;
+  }
+  staticPaths ??= (otherExports as any).getStaticPaths({});
+  const { paths, fallback } = await staticPaths!;
+  if (fallback !== false) {
+    return true;
+  }
+  return paths.some((path) =>
+    typeof path === "string"
+      ? path === renderData.path
+      : Object.entries(path.params).every(
+          ([key, value]) =>
+            [value].flat().join("/") === renderData.params[key]
+        )
+  );
+}
+
 async function runOperation(
   renderData: RenderData
-): Promise<string | undefined> {
+): Promise<IpcOutgoingMessage["result"] | undefined> {
+  if (!(await isStaticPath(renderData))) {
+    return { body: "", notFound: true };
+  }
+
   // TODO(alexkirsz) This is missing *a lot* of data, but it's enough to get a
   // basic render working.
 
@@ -92,13 +139,13 @@ async function runOperation(
     Document,
     pageConfig: {},
     buildManifest,
-    reactLoadableManifest: {},
+    reactLoadableManifest,
     ComponentMod: {
       default: Component,
       ...otherExports,
     },
     pathname: renderData.path,
-    buildId: "",
+    buildId: "development",
 
     /* RenderOptsPartial */
     runtimeConfig: {},
@@ -112,7 +159,9 @@ async function runOperation(
     basePath: "",
     optimizeFonts: false,
     optimizeCss: false,
-    nextScriptWorkers: false,
+    // Partytown is only loaded for pages with `worker` scripts, which are
+    // only kept with `experimental.nextScriptWorkers`.
+    nextScriptWorkers: renderData.scripts.worker.length > 0,
     images: {
       deviceSizes: [],
       imageSizes: [],
@@ -130,6 +179,11 @@ async function runOperation(
     },
   };
 
+  if (renderData.i18n != null) {
+    process.env.__NEXT_I18N_SUPPORT = "true";
+    Object.assign(renderOpts, localeOptions(renderData, renderData.i18n));
+  }
+
   if ("getStaticProps" in otherExports) {
     renderOpts.getStaticProps = otherExports.getStaticProps;
   }
@@ -144,8 +198,11 @@ async function runOperation(
   } as any;
   const res: ServerResponse = new ServerResponseShim(req) as any;
   const query = { ...renderData.query, ...renderData.params };
+  if (renderData.data) {
+    (renderOpts as any).isDataReq = true;
+  }
 
-  return (
+  const html = (
     await renderToHTML(
       /* req: IncomingMessage */
       req,
@@ -159,4 +216,119 @@ async function runOperation(
       renderOpts
     )
   )?.toUnchunkedString();
+
+  // Next.js stores the results of `getStaticProps` on the render options.
+  const { isNotFound, pageData, revalidate } = renderOpts as any;
+  if (isNotFound) {
+    return { body: "", notFound: true };
+  }
+  const result = {
+    revalidate: typeof revalidate === "number" ? revalidate : undefined,
+  };
+  if (renderData.data) {
+    return {
+      ...result,
+      // Data requests of pages without `getStaticProps` already render the
+      // props as JSON.
+      body: pageData !== undefined ? JSON.stringify(pageData) : html ?? "",
+      contentType: "application/json",
+    };
+  }
+  return html == null
+    ? undefined
+    : {
+        ...result,
+        body: addScripts(addPrefetchHints(html, renderData), renderData),
+      };
+}
+
+/**
+ * The locale options of the render for the `i18n` config. The dev server
+ * strips the locale prefix from the path before the page is matched, so the
+ * locale is read from the URL of the request. Without a prefix, the default
+ * locale of the domain is used.
+ */
+function localeOptions(
+  renderData: RenderData,
+  i18n: I18NConfig
+): Partial<RenderOpts> {
+  const pathname = new URL(renderData.url, "http://n").pathname.replace(
+    /^\/_next\/data\/development/,
+    ""
+  );
+  const prefix = pathname.split("/")[1].toLowerCase();
+  const host = renderData.headers.host;
+  const hostname = typeof host === "string" ? host.split(":")[0] : undefined;
+  const domain = i18n.domains.find(
+    (domain) => domain.domain.split(":")[0] === hostname
+  );
+  const defaultLocale = domain?.defaultLocale ?? i18n.defaultLocale;
+  return {
+    locale:
+      i18n.locales.find((locale) => locale.toLowerCase() === prefix) ??
+      defaultLocale,
+    locales: i18n.locales,
+    defaultLocale,
+    domainLocales: i18n.domains.length > 0 ? i18n.domains : undefined,
+  };
+}
+
+/**
+ * Adds `<link rel="prefetch">` hints for the routes which the page links to
+ * with `next/link`. The browser fetches them while idle, which also makes the
+ * dev server compile them before they are navigated to.
+ */
+function addPrefetchHints(html: string, renderData: RenderData): string {
+  const hints = renderData.prefetch
+    .filter((route) => route !== renderData.path)
+    .map((route) => `<link rel="prefetch" href="${escapeAttribute(route)}">`)
+    .join("");
+  return insertBefore(html, "</head>", hints);
+}
+
+/**
+ * Adds the tags for the `next/script` strategies which have to be in the
+ * HTML: `beforeInteractive` scripts run before any script of Next.js, and
+ * `afterInteractive` scripts are preloaded, so they are ready when the page
+ * is hydrated. `lazyOnload` and `worker` scripts are left to the `Script`
+ * component and Partytown. AMP pages can't have custom scripts at all.
+ */
+function addScripts(html: string, renderData: RenderData): string {
+  if ((otherExports as any).config?.amp === true) {
+    return html;
+  }
+  // The `Script` component already renders `beforeInteractive` scripts which
+  // are part of `_document`.
+  const beforeInteractive = renderData.scripts.beforeInteractive
+    .filter((src) => !html.includes(`src="${escapeAttribute(src)}"`))
+    .map(
+      (src) =>
+        `<script src="${escapeAttribute(
+          src
+        )}" data-nscript="beforeInteractive"></script>`
+    )
+    .join("");
+  const preloads = renderData.scripts.afterInteractive
+    .map(
+      (src) => `<link rel="preload" as="script" href="${escapeAttribute(src)}">`
+    )
+    .join("");
+  html = insertBefore(
+    html,
+    html.includes("<script") ? "<script" : "</head>",
+    beforeInteractive
+  );
+  return insertBefore(html, "</head>", preloads);
+}
+
+function insertBefore(html: string, tag: string, content: string): string {
+  const index = html.indexOf(tag);
+  if (content === "" || index === -1) {
+    return html;
+  }
+  return html.slice(0, index) + content + html.slice(index);
+}
+
+function escapeAttribute(value: string): string {
+  return value.replace(/&/g, "&amp;").replace(/"/g, "&quot;");
 }
@@ -0,0 +1,172 @@
+import { Buffer } from "node:buffer";
+import nodeProcess from "node:process";
+
+import type { Ipc } from "@vercel/turbopack-next/internal/ipc";
+import type { RenderData } from "types/turbopack";
+
+import "next/dist/server/node-polyfill-fetch.js";
+
+/**
+ * The globals of Node.js which are not available in the edge runtime.
+ */
+const NODE_GLOBALS = ["Buffer", "setImmediate", "clearImmediate"];
+
+export type EdgeIpcIncomingMessage =
+  | {
+      type: "headers";
+      data: RenderData;
+    }
+  | {
+      type: "bodyChunk";
+      data: Array<number>;
+    }
+  | { type: "bodyEnd" };
+
+export type EdgeIpcOutgoingMessage =
+  | {
+      type: "headers";
+      data: { status: number; headers: string[] };
+    }
+  | {
+      type: "body";
+      data: Array<number>;
+    };
+
+function unsupportedApi(name: string): Error {
+  return new Error(
+    `A Node.js API is used (${name}) which is not supported in the Edge Runtime.`
+  );
+}
+
+/**
+ * The `process` of the edge runtime, which only has `env`.
+ */
+const edgeProcess = new Proxy(
+  {},
+  {
+    get(_target, name) {
+      if (name === "env") {
+        return nodeProcess.env;
+      }
+      if (typeof name === "symbol") {
+        return undefined;
+      }
+      throw unsupportedApi(`process.${name}`);
+    },
+  }
+);
+
+/**
+ * Runs `fn` with the globals of the edge runtime: the Node.js globals it
+ * doesn't support throw when they are used, `process` only has `env`, and
+ * `EdgeRuntime` is defined. The globals are restored afterwards.
+ */
+export async function runInEdgeRuntime<T>(
+  fn: () => T | Promise<T>
+): Promise<T> {
+  const descriptors = new Map<string, PropertyDescriptor | undefined>();
+  const define = (name: string, get: () => unknown) => {
+    descriptors.set(name, Object.getOwnPropertyDescriptor(globalThis, name));
+    Object.defineProperty(globalThis, name, { configurable: true, get });
+  };
+
+  for (const name of NODE_GLOBALS) {
+    define(name, () => {
+      throw unsupportedApi(name);
+    });
+  }
+  define("process", () => edgeProcess);
+  define("EdgeRuntime", () => "edge-runtime");
+
+  try {
+    return await fn();
+  } finally {
+    for (const [name, descriptor] of descriptors) {
+      if (descriptor == null) {
+        delete (globalThis as any)[name];
+      } else {
+        Object.defineProperty(globalThis, name, descriptor);
+      }
+    }
+  }
+}
+
+/**
+ * Serves the requests proxied by the dev server with an edge function, which
+ * receives a WinterCG `Request` and returns a `Response`.
+ */
+export async function serveEdgeFunction(
+  ipc: Ipc<EdgeIpcIncomingMessage, EdgeIpcOutgoingMessage>,
+  handler: (request: Request, renderData: RenderData) => Promise<Response>
+) {
+  while (true) {
+    const msg = await ipc.recv();
+
+    let renderData: RenderData;
+    switch (msg.type) {
+      case "headers": {
+        renderData = msg.data;
+        break;
+      }
+      default: {
+        console.error("unexpected message type", msg.type);
+        nodeProcess.exit(1);
+      }
+    }
+
+    const chunks: Buffer[] = [];
+    loop: while (true) {
+      const msg = await ipc.recv();
+      switch (msg.type) {
+        case "bodyChunk": {
+          chunks.push(Buffer.from(msg.data));
+          break;
+        }
+        case "bodyEnd": {
+          break loop;
+        }
+        default: {
+          console.error("unexpected message type", msg.type);
+          nodeProcess.exit(1);
+        }
+      }
+    }
+
+    const request = createRequest(renderData, Buffer.concat(chunks));
+    const response = await runInEdgeRuntime(() =>
+      handler(request, renderData)
+    );
+
+    const headers: string[] = [];
+    response.headers.forEach((value, name) => {
+      headers.push(name, value);
+    });
+    await ipc.send({
+      type: "headers",
+      data: { status: response.status, headers },
+    });
+    await ipc.send({
+      type: "body",
+      data: Buffer.from(await response.arrayBuffer()).toJSON().data,
+    });
+  }
+}
+
+function createRequest(renderData: RenderData, body: Buffer): Request {
+  const headers = new Headers();
+  for (const [name, value] of Object.entries(renderData.headers)) {
+    for (const item of [value].flat()) {
+      if (typeof item === "string") {
+        headers.append(name, item);
+      }
+    }
+  }
+  const host = headers.get("host") ?? "localhost";
+  const url = new URL(renderData.url, `http://${host}`);
+  const hasBody = renderData.method !== "GET" && renderData.method !== "HEAD";
+  return new Request(url.toString(), {
+    method: renderData.method,
+    headers,
+    body: hasBody && body.length > 0 ? body : undefined,
+  });
+}
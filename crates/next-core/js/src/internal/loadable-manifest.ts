@@ -0,0 +1,18 @@
+/**
+ * The loadable manifest of the server render, which maps the modules loaded
+ * by `next/dynamic` to their client chunks. Modules calling `next/dynamic`
+ * register the modules they load when they are evaluated.
+ */
+export const reactLoadableManifest: Record<
+  string,
+  { id: string; files: string[] }
+> = {};
+
+/**
+ * Registers the client chunks of a module loaded by `next/dynamic`. The key
+ * is also the id of the module on the client, which is passed to
+ * `loadableGenerated.modules`.
+ */
+export function registerLoadable(key: string, chunks: string[]) {
+  reactLoadableManifest[key] = { id: key, files: chunks };
+}
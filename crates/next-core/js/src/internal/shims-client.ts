@@ -19,3 +19,13 @@ globalThis.__webpack_public_path__ = undefined;
 
 // Avoids Next loading _next/static/[buildId]/_devMiddlewareManifest.json
 globalThis.__DEV_MIDDLEWARE_MATCHERS = [];
+
+// Next.js only handles locales when `__NEXT_I18N_SUPPORT` is set, which the
+// server renderer does for the `i18n` config of next.config.js. The client
+// reads the locales from `__NEXT_DATA__`.
+if (typeof document !== "undefined") {
+  const nextData = document.getElementById("__NEXT_DATA__")?.textContent;
+  if (nextData != null && JSON.parse(nextData).locales != null) {
+    process.env.__NEXT_I18N_SUPPORT = "true";
+  }
+}
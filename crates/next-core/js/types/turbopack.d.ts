@@ -5,4 +5,29 @@ export type RenderData = {
   path: string;
   query: NextParsedUrlQuery;
   headers: Record<string, HeaderValue>;
+  data: boolean;
+  prefetch: string[];
+  scripts: PageScripts;
+  i18n: I18NConfig | null;
+};
+
+export type PageScripts = {
+  beforeInteractive: string[];
+  afterInteractive: string[];
+  lazyOnload: string[];
+  worker: string[];
+};
+
+export type I18NConfig = {
+  locales: string[];
+  defaultLocale: string;
+  domains: DomainLocale[];
+  localeDetection: boolean | null;
+};
+
+export type DomainLocale = {
+  domain: string;
+  defaultLocale: string;
+  locales: string[];
+  http: boolean;
 };
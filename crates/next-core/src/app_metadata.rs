@@ -0,0 +1,257 @@
+//! The metadata file conventions of the app directory, e. g. `favicon.ico`,
+//! `opengraph-image.tsx` or `sitemap.ts`. Static files are served as they
+//! are. Dynamic ones are executed in Node.js, and their result is served at
+//! the URL of the static file they replace.
+
+use std::io::Write;
+
+use anyhow::Result;
+use turbo_tasks::Value;
+use turbo_tasks_fs::{rope::RopeBuilder, File, FileContent, FileSystemPathVc};
+use turbopack::ecmascript::EcmascriptInputTransform;
+use turbopack_core::{
+    asset::Asset, chunk::dev::DevChunkingContextVc, context::AssetContextVc,
+    source_asset::SourceAssetVc, virtual_asset::VirtualAssetVc,
+};
+use turbopack_dev_server::{
+    html::DevHtmlAssetVc,
+    source::{
+        asset_graph::AssetGraphContentSourceVc, specificity::SpecificityVc, ContentSourceData,
+        ContentSourceVc,
+    },
+};
+use turbopack_ecmascript::{
+    chunk::EcmascriptChunkPlaceablesVc, utils::stringify_str, EcmascriptInputTransformsVc,
+    EcmascriptModuleAssetType, EcmascriptModuleAssetVc,
+};
+
+use crate::{
+    embed_js::next_js_file,
+    next_config::load_next_config,
+    nodejs::{
+        create_node_api_source, create_node_rendered_source,
+        node_entry::{NodeRenderingEntry, NodeRenderingEntryVc},
+        NodeEntry, NodeEntryVc,
+    },
+    util::regular_expression_for_path,
+};
+
+const IMAGE_EXTENSIONS: &[&str] = &["ico", "jpg", "jpeg", "png", "gif", "svg"];
+const SCRIPT_EXTENSIONS: &[&str] = &["js", "jsx", "ts", "tsx"];
+
+/// The static metadata files: their name, extensions, and whether they are
+/// only supported in the root of the app directory.
+const STATIC_METADATA: &[(&str, &[&str], bool)] = &[
+    ("favicon", &["ico"], true),
+    ("icon", IMAGE_EXTENSIONS, false),
+    ("apple-icon", IMAGE_EXTENSIONS, false),
+    ("opengraph-image", IMAGE_EXTENSIONS, false),
+    ("twitter-image", IMAGE_EXTENSIONS, false),
+    ("robots", &["txt"], true),
+    ("sitemap", &["xml"], false),
+    ("manifest", &["json", "webmanifest"], true),
+];
+
+/// The dynamic metadata files: their name, the name they are served at, and
+/// whether they are only supported in the root of the app directory.
+const DYNAMIC_METADATA: &[(&str, &str, bool)] = &[
+    ("icon", "icon", false),
+    ("apple-icon", "apple-icon", false),
+    ("opengraph-image", "opengraph-image", false),
+    ("twitter-image", "twitter-image", false),
+    ("robots", "robots.txt", true),
+    ("sitemap", "sitemap.xml", false),
+    ("manifest", "manifest.webmanifest", true),
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum MetadataFile {
+    /// Served as it is, at its own name.
+    Static,
+    /// Executed to produce the response served at `url_name`.
+    Dynamic {
+        ty: &'static str,
+        url_name: &'static str,
+    },
+}
+
+impl MetadataFile {
+    fn is_image(&self) -> bool {
+        matches!(
+            self,
+            MetadataFile::Dynamic {
+                ty: "icon" | "apple-icon" | "opengraph-image" | "twitter-image",
+                ..
+            }
+        )
+    }
+}
+
+/// Returns which metadata file convention a file in the app directory
+/// follows, if any.
+pub(crate) fn metadata_file(file_name: &str, is_root: bool) -> Option<MetadataFile> {
+    let (name, extension) = file_name.rsplit_once('.')?;
+    if SCRIPT_EXTENSIONS.contains(&extension) {
+        DYNAMIC_METADATA
+            .iter()
+            .find(|&&(ty, _, root_only)| ty == name && (is_root || !root_only))
+            .map(|&(ty, url_name, _)| MetadataFile::Dynamic { ty, url_name })
+    } else {
+        STATIC_METADATA
+            .iter()
+            .any(|&(ty, extensions, root_only)| {
+                ty == name && extensions.contains(&extension) && (is_root || !root_only)
+            })
+            .then_some(MetadataFile::Static)
+    }
+}
+
+/// Creates a content source serving a metadata file of the directory which
+/// is served at `target`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn create_metadata_source(
+    metadata: MetadataFile,
+    file: FileSystemPathVc,
+    file_name: &str,
+    context: AssetContextVc,
+    project_root: FileSystemPathVc,
+    specificity: SpecificityVc,
+    server_root: FileSystemPathVc,
+    target: FileSystemPathVc,
+    runtime_entries: EcmascriptChunkPlaceablesVc,
+    fallback_page: DevHtmlAssetVc,
+    intermediate_output_path: FileSystemPathVc,
+    trace: bool,
+) -> ContentSourceVc {
+    let is_image = metadata.is_image();
+    let (ty, url_name) = match metadata {
+        MetadataFile::Static => {
+            let asset = VirtualAssetVc::new(
+                target.join(file_name),
+                SourceAssetVc::new(file).as_asset().content(),
+            );
+            return AssetGraphContentSourceVc::new_eager(server_root, asset.into()).into();
+        }
+        MetadataFile::Dynamic { ty, url_name } => (ty, url_name),
+    };
+    let entry = MetadataRouteEntry {
+        context,
+        file,
+        ty: ty.to_string(),
+        project_root,
+        server_root,
+        intermediate_output_path,
+        trace,
+    }
+    .cell()
+    .into();
+    let path_regex = regular_expression_for_path(server_root, target.join(url_name), false);
+    if is_image {
+        create_node_api_source(specificity, server_root, path_regex, entry, runtime_entries)
+    } else {
+        create_node_rendered_source(
+            specificity,
+            server_root,
+            path_regex,
+            entry,
+            runtime_entries,
+            fallback_page,
+            load_next_config(project_root),
+        )
+    }
+}
+
+/// The node.js entry of a dynamic metadata file.
+#[turbo_tasks::value]
+struct MetadataRouteEntry {
+    context: AssetContextVc,
+    file: FileSystemPathVc,
+    ty: String,
+    project_root: FileSystemPathVc,
+    server_root: FileSystemPathVc,
+    intermediate_output_path: FileSystemPathVc,
+    trace: bool,
+}
+
+#[turbo_tasks::value_impl]
+impl NodeEntry for MetadataRouteEntry {
+    #[turbo_tasks::function]
+    async fn entry(&self, _data: Value<ContentSourceData>) -> Result<NodeRenderingEntryVc> {
+        let mut code = RopeBuilder::from(
+            "import IPC, { Ipc } from \"@vercel/turbopack-next/internal/ipc\";\n",
+        );
+        writeln!(code, "const METADATA_TYPE = {};\n", stringify_str(&self.ty))?;
+        if let FileContent::Content(base_file) = &*next_js_file("entry/metadata-route.ts").await? {
+            code += base_file.content()
+        }
+        let asset = VirtualAssetVc::new(
+            self.file.join("metadata-route.ts"),
+            File::from(code.build()).into(),
+        );
+
+        let chunking_context = DevChunkingContextVc::builder(
+            self.project_root,
+            self.intermediate_output_path,
+            self.intermediate_output_path.join("chunks"),
+            self.server_root.join("_next/static/media"),
+        )
+        .layer("ssr")
+        .build();
+
+        Ok(NodeRenderingEntry {
+            module: EcmascriptModuleAssetVc::new(
+                asset.into(),
+                self.context,
+                Value::new(EcmascriptModuleAssetType::Typescript),
+                EcmascriptInputTransformsVc::cell(vec![
+                    EcmascriptInputTransform::React { refresh: false },
+                    EcmascriptInputTransform::TypeScript,
+                ]),
+                self.context.environment(),
+            ),
+            chunking_context,
+            intermediate_output_path: self.intermediate_output_path,
+            trace: self.trace,
+        }
+        .cell())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{metadata_file, MetadataFile};
+
+    #[test]
+    fn recognizes_metadata_files() {
+        assert_eq!(
+            metadata_file("favicon.ico", true),
+            Some(MetadataFile::Static)
+        );
+        assert_eq!(metadata_file("favicon.ico", false), None);
+        assert_eq!(
+            metadata_file("opengraph-image.png", false),
+            Some(MetadataFile::Static)
+        );
+        assert_eq!(
+            metadata_file("opengraph-image.tsx", false),
+            Some(MetadataFile::Dynamic {
+                ty: "opengraph-image",
+                url_name: "opengraph-image"
+            })
+        );
+        assert_eq!(
+            metadata_file("robots.ts", true),
+            Some(MetadataFile::Dynamic {
+                ty: "robots",
+                url_name: "robots.txt"
+            })
+        );
+        assert_eq!(metadata_file("robots.ts", false), None);
+        assert_eq!(
+            metadata_file("sitemap.xml", false),
+            Some(MetadataFile::Static)
+        );
+        assert_eq!(metadata_file("icon.css", false), None);
+        assert_eq!(metadata_file("page.tsx", false), None);
+    }
+}
@@ -39,6 +39,7 @@ use turbopack_ecmascript::{
 use turbopack_env::ProcessEnvAssetVc;
 
 use crate::{
+    app_metadata::{create_metadata_source, metadata_file},
     app_render::{
         next_layout_entry_transition::NextLayoutEntryTransition, LayoutSegment, LayoutSegmentsVc,
     },
@@ -56,6 +57,7 @@ use crate::{
         server_to_client_transition::NextServerToClientTransition,
         ssr_client_module_transition::NextSSRClientModuleTransition,
     },
+    next_config::load_next_config,
     next_server::{
         get_server_environment, get_server_module_options_context,
         get_server_resolve_options_context, ServerContextType,
@@ -130,7 +132,7 @@ fn next_ssr_client_module_transition(
 ) -> TransitionVc {
     let ty = Value::new(ServerContextType::AppSSR { app_dir });
     NextSSRClientModuleTransition {
-        ssr_module_options_context: get_server_module_options_context(ty),
+        ssr_module_options_context: get_server_module_options_context(project_root, ty),
         ssr_resolve_options_context: get_server_resolve_options_context(
             project_root,
             ty,
@@ -154,7 +156,7 @@ fn next_layout_entry_transition(
     let rsc_environment = get_server_environment(ty, process_env);
     let rsc_resolve_options_context =
         get_server_resolve_options_context(project_root, ty, externals);
-    let rsc_module_options_context = get_server_module_options_context(ty);
+    let rsc_module_options_context = get_server_module_options_context(project_root, ty);
 
     NextLayoutEntryTransition {
         rsc_environment,
@@ -204,7 +206,7 @@ fn app_context(
     ModuleAssetContextVc::new(
         TransitionsByNameVc::cell(transitions),
         get_server_environment(ssr_ty, env),
-        get_server_module_options_context(ssr_ty),
+        get_server_module_options_context(project_root, ssr_ty),
         get_server_resolve_options_context(project_root, ssr_ty, externals),
     )
     .into()
@@ -212,6 +214,9 @@ fn app_context(
 
 /// Create a content source serving the `app` or `src/app` directory as
 /// Next.js app folder.
+///
+/// When `trace` is set, an nft.json listing all files needed to run a page is
+/// emitted next to each server entry in the `output_path`.
 #[turbo_tasks::function]
 pub async fn create_app_source(
     project_root: FileSystemPathVc,
@@ -220,6 +225,7 @@ pub async fn create_app_source(
     env: ProcessEnvVc,
     browserslist_query: &str,
     externals: StringsVc,
+    trace: bool,
 ) -> Result<ContentSourceVc> {
     let project_root = wrap_with_next_js_fs(project_root);
 
@@ -270,6 +276,7 @@ pub async fn create_app_source(
         server_root,
         LayoutSegmentsVc::cell(Vec::new()),
         output_path,
+        trace,
     )
     .into())
 }
@@ -288,6 +295,7 @@ async fn create_app_source_for_directory(
     target: FileSystemPathVc,
     layouts: LayoutSegmentsVc,
     intermediate_output_path: FileSystemPathVc,
+    trace: bool,
 ) -> Result<CombinedContentSourceVc> {
     let mut layouts = layouts;
     let mut sources = Vec::new();
@@ -296,6 +304,23 @@ async fn create_app_source_for_directory(
     if let DirectoryContent::Entries(entries) = &*input_dir.read_dir().await? {
         for (name, entry) in entries.iter() {
             if let &DirectoryEntry::File(file) = entry {
+                if let Some(metadata) = metadata_file(name, target == server_root) {
+                    sources.push(create_metadata_source(
+                        metadata,
+                        file,
+                        name,
+                        context_ssr,
+                        project_root,
+                        specificity,
+                        server_root,
+                        target,
+                        runtime_entries,
+                        fallback_page,
+                        intermediate_output_path.join(name),
+                        trace,
+                    ));
+                    continue;
+                }
                 if let Some((name, _)) = name.rsplit_once('.') {
                     match name {
                         "page" => {
@@ -370,11 +395,13 @@ async fn create_app_source_for_directory(
                     target,
                     project_root,
                     intermediate_output_path,
+                    trace,
                 }
                 .cell()
                 .into(),
                 runtime_entries,
                 fallback_page,
+                load_next_config(project_root),
             ));
         }
         for (name, entry) in entries.iter() {
@@ -408,6 +435,7 @@ async fn create_app_source_for_directory(
                         new_target,
                         layouts,
                         intermediate_output_path,
+                        trace,
                     )
                     .into(),
                 );
@@ -427,6 +455,7 @@ struct AppRenderer {
     target: FileSystemPathVc,
     project_root: FileSystemPathVc,
     intermediate_output_path: FileSystemPathVc,
+    trace: bool,
 }
 
 #[turbo_tasks::value_impl]
@@ -558,7 +587,7 @@ import BOOTSTRAP from {};
             self.project_root,
             intermediate_output_path,
             intermediate_output_path.join("chunks"),
-            self.server_root.join("_next/static/assets"),
+            self.server_root.join("_next/static/media"),
         )
         .layer("ssr")
         .css_chunk_root_path(self.server_root.join("_next/static/chunks"))
@@ -577,6 +606,7 @@ import BOOTSTRAP from {};
             ),
             chunking_context,
             intermediate_output_path,
+            trace: self.trace,
         }
         .cell())
     }
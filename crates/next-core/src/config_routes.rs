@@ -0,0 +1,690 @@
+//! The `redirects`, `rewrites` and `headers` options of `next.config.js`,
+//! which are applied to the requests of the dev server before they reach the
+//! pages.
+//!
+//! Routes with conditions on the request need its headers and query, which are
+//! only requested for paths matched by a route, so other paths are cached as
+//! before.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{primitives::StringVc, trace::TraceRawVcs, Value};
+use turbo_tasks_fs::{rope::Rope, FileSystemPathVc};
+use turbopack_core::{
+    introspect::{Introspectable, IntrospectableChildrenVc, IntrospectableVc},
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+};
+use turbopack_dev_server::source::{
+    query::{Query, QueryValue},
+    ContentSource, ContentSourceContent, ContentSourceData, ContentSourceDataFilter,
+    ContentSourceDataVary, ContentSourceResult, ContentSourceResultVc, ContentSourceVc,
+    HeaderValue, ProxyResult,
+};
+
+use crate::{
+    next_config::{load_next_config, Rewrite, RouteHas},
+    path_to_regexp::{compile_destination, PathPattern, ValuePattern},
+    util::{has_data, header},
+};
+
+/// Matches the path and the conditions of a route.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+struct RouteMatcher {
+    source: PathPattern,
+    has: Vec<RouteCondition>,
+    missing: Vec<RouteCondition>,
+}
+
+/// A `has` or `missing` condition of a route, with its value compiled when the
+/// routes are loaded.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+struct RouteCondition {
+    condition: RouteHas,
+    value: Option<ValuePattern>,
+}
+
+impl RouteCondition {
+    fn new(condition: &RouteHas) -> Result<Self> {
+        Ok(RouteCondition {
+            condition: condition.clone(),
+            value: condition_value(condition)
+                .map(ValuePattern::new)
+                .transpose()?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+struct HeaderRoute {
+    matcher: RouteMatcher,
+    /// Arranged as contiguous (name, value) pairs.
+    headers: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+struct RedirectRoute {
+    matcher: RouteMatcher,
+    destination: String,
+    status: u16,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+struct RewriteRoute {
+    matcher: RouteMatcher,
+    destination: String,
+}
+
+/// The compiled routes of `next.config.js`.
+#[turbo_tasks::value(shared)]
+struct ConfigRoutes {
+    headers: Vec<HeaderRoute>,
+    redirects: Vec<RedirectRoute>,
+    before_files: Vec<RewriteRoute>,
+    after_files: Vec<RewriteRoute>,
+    fallback: Vec<RewriteRoute>,
+}
+
+impl ConfigRoutes {
+    fn matches_path(&self, pathname: &str) -> bool {
+        let rewrites = self
+            .before_files
+            .iter()
+            .chain(&self.after_files)
+            .chain(&self.fallback);
+        self.headers
+            .iter()
+            .map(|route| &route.matcher)
+            .chain(self.redirects.iter().map(|route| &route.matcher))
+            .chain(rewrites.map(|route| &route.matcher))
+            .any(|matcher| matcher.source.match_path(pathname).is_some())
+    }
+}
+
+/// Compiles the routes of `next.config.js`. Routes with invalid patterns or
+/// an external destination are reported as issues and skipped.
+#[turbo_tasks::function]
+async fn config_routes(project_path: FileSystemPathVc) -> Result<ConfigRoutesVc> {
+    let next_config = load_next_config(project_path).await?;
+    let config_path = project_path.join("next.config.js");
+    let matcher = |source: &str, has: &[RouteHas], missing: &[RouteHas]| {
+        let result = PathPattern::new(source).and_then(|source| {
+            Ok(RouteMatcher {
+                source,
+                has: has.iter().map(RouteCondition::new).collect::<Result<_>>()?,
+                missing: missing
+                    .iter()
+                    .map(RouteCondition::new)
+                    .collect::<Result<_>>()?,
+            })
+        });
+        match result {
+            Ok(matcher) => Some(matcher),
+            Err(err) => {
+                ConfigRouteIssue {
+                    path: config_path,
+                    source: source.to_string(),
+                    message: format!("{err:#}"),
+                }
+                .cell()
+                .as_issue()
+                .emit();
+                None
+            }
+        }
+    };
+    let rewrites = |rewrites: &[Rewrite]| -> Vec<RewriteRoute> {
+        rewrites
+            .iter()
+            .filter_map(|rewrite| {
+                if rewrite.destination.starts_with("http://")
+                    || rewrite.destination.starts_with("https://")
+                {
+                    ConfigRouteIssue {
+                        path: config_path,
+                        source: rewrite.source.clone(),
+                        message: format!(
+                            "Rewrites to external URLs like {} are not supported by the dev \
+                             server yet.",
+                            rewrite.destination
+                        ),
+                    }
+                    .cell()
+                    .as_issue()
+                    .emit();
+                    return None;
+                }
+                Some(RewriteRoute {
+                    matcher: matcher(&rewrite.source, &rewrite.has, &rewrite.missing)?,
+                    destination: rewrite.destination.clone(),
+                })
+            })
+            .collect()
+    };
+
+    Ok(ConfigRoutes {
+        headers: next_config
+            .headers
+            .iter()
+            .filter_map(|header| {
+                Some(HeaderRoute {
+                    matcher: matcher(&header.source, &header.has, &header.missing)?,
+                    headers: header
+                        .headers
+                        .iter()
+                        .flat_map(|header| [header.key.clone(), header.value.clone()])
+                        .collect(),
+                })
+            })
+            .collect(),
+        redirects: next_config
+            .redirects
+            .iter()
+            .filter_map(|redirect| {
+                Some(RedirectRoute {
+                    matcher: matcher(&redirect.source, &redirect.has, &redirect.missing)?,
+                    destination: redirect.destination.clone(),
+                    status: redirect.status_code.unwrap_or(if redirect.permanent {
+                        308
+                    } else {
+                        307
+                    }),
+                })
+            })
+            .collect(),
+        before_files: rewrites(&next_config.rewrites.before_files),
+        after_files: rewrites(&next_config.rewrites.after_files),
+        fallback: rewrites(&next_config.rewrites.fallback),
+    }
+    .cell())
+}
+
+/// Applies the routes of `next.config.js` to the requests of `source`.
+/// `headers` are added to the responses, `redirects` are answered, and
+/// `rewrites` change the path which is passed to `source`. The `afterFiles`
+/// and `fallback` rewrites are applied when `source` doesn't find the path.
+#[turbo_tasks::function]
+pub fn create_config_routes_source(
+    project_path: FileSystemPathVc,
+    source: ContentSourceVc,
+) -> ContentSourceVc {
+    ConfigRoutesContentSource {
+        source,
+        routes: config_routes(project_path),
+    }
+    .cell()
+    .into()
+}
+
+#[turbo_tasks::value(shared)]
+pub struct ConfigRoutesContentSource {
+    source: ContentSourceVc,
+    routes: ConfigRoutesVc,
+}
+
+/// The data which the conditions of the routes need.
+fn routes_vary() -> ContentSourceDataVary {
+    ContentSourceDataVary {
+        headers: Some(ContentSourceDataFilter::All),
+        query: Some(ContentSourceDataFilter::All),
+        ..Default::default()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for ConfigRoutesContentSource {
+    #[turbo_tasks::function]
+    async fn get(
+        self_vc: ConfigRoutesContentSourceVc,
+        path: &str,
+        data: Value<ContentSourceData>,
+    ) -> Result<ContentSourceResultVc> {
+        let this = self_vc.await?;
+        let routes = this.routes.await?;
+        if !routes.matches_path(&format!("/{path}")) {
+            return Ok(this.source.get(path, data));
+        }
+        let mut request = match (&data.headers, &data.query) {
+            (Some(headers), Some(query)) => RouteRequest {
+                pathname: format!("/{path}"),
+                headers: headers.clone(),
+                query: query.clone(),
+            },
+            _ => {
+                return Ok(ContentSourceResultVc::exact(
+                    ContentSourceContent::NeedData {
+                        source: self_vc.into(),
+                        path: path.to_string(),
+                        vary: routes_vary(),
+                    }
+                    .cell(),
+                ))
+            }
+        };
+
+        let mut response_headers = Vec::new();
+        for route in &routes.headers {
+            if let Some(params) = route.matcher.match_request(&request) {
+                response_headers.extend(
+                    route
+                        .headers
+                        .iter()
+                        .map(|value| compile_destination(value, &params)),
+                );
+            }
+        }
+
+        if let Some((route, params)) =
+            first_match(&routes.redirects, |route| &route.matcher, &request)
+        {
+            let location = redirect_location(&route.destination, &params, &request.query)?;
+            let mut headers = vec!["location".to_string(), location];
+            headers.extend(response_headers);
+            return Ok(ContentSourceResultVc::exact(
+                ContentSourceContent::HttpProxy(
+                    ProxyResult {
+                        status: route.status,
+                        headers,
+                        body: Rope::default(),
+                    }
+                    .cell(),
+                )
+                .cell(),
+            ));
+        }
+
+        let mut data = data.into_value();
+        let mut inner_path = path.to_string();
+        if let Some((route, params)) =
+            first_match(&routes.before_files, |route| &route.matcher, &request)
+        {
+            inner_path = rewrite(&route.destination, &params, &mut data);
+            request.pathname = format!("/{inner_path}");
+        }
+        let mut result = this.source.get(&inner_path, Value::new(data.clone()));
+        for rewrites in [&routes.after_files, &routes.fallback] {
+            if !matches!(
+                &*result.await?.content.await?,
+                ContentSourceContent::NotFound
+            ) {
+                break;
+            }
+            if let Some((route, params)) = first_match(rewrites, |route| &route.matcher, &request) {
+                inner_path = rewrite(&route.destination, &params, &mut data);
+                result = this.source.get(&inner_path, Value::new(data.clone()));
+            }
+        }
+
+        let value = result.await?;
+        Ok(match &*value.content.await? {
+            // The data is requested for this source, so the routes are applied
+            // again when it's there.
+            ContentSourceContent::NeedData { vary, .. } if !has_data(&data, vary) => {
+                let mut vary = vary.clone();
+                vary.extend(&routes_vary());
+                ContentSourceResult {
+                    specificity: value.specificity,
+                    content: ContentSourceContent::NeedData {
+                        source: self_vc.into(),
+                        path: path.to_string(),
+                        vary,
+                    }
+                    .cell(),
+                }
+                .cell()
+            }
+            ContentSourceContent::NotFound | ContentSourceContent::NeedData { .. } => result,
+            _ if response_headers.is_empty() => result,
+            _ => ContentSourceResult {
+                specificity: value.specificity,
+                content: ContentSourceContent::WithHeaders {
+                    content: value.content,
+                    headers: response_headers,
+                }
+                .cell(),
+            }
+            .cell(),
+        })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Introspectable for ConfigRoutesContentSource {
+    #[turbo_tasks::function]
+    fn ty(&self) -> StringVc {
+        StringVc::cell("next.config.js routes content source".to_string())
+    }
+
+    #[turbo_tasks::function]
+    async fn children(&self) -> Result<IntrospectableChildrenVc> {
+        Ok(IntrospectableChildrenVc::cell(
+            IntrospectableVc::resolve_from(self.source)
+                .await?
+                .map(|source| (StringVc::cell("source".to_string()), source))
+                .into_iter()
+                .collect(),
+        ))
+    }
+}
+
+struct RouteRequest {
+    pathname: String,
+    headers: BTreeMap<String, HeaderValue>,
+    query: Query,
+}
+
+fn first_match<'a, T>(
+    routes: &'a [T],
+    matcher: impl Fn(&T) -> &RouteMatcher,
+    request: &RouteRequest,
+) -> Option<(&'a T, IndexMap<String, String>)> {
+    routes
+        .iter()
+        .find_map(|route| Some((route, matcher(route).match_request(request)?)))
+}
+
+/// The `location` of a redirect, which keeps the query of the request.
+fn redirect_location(
+    destination: &str,
+    params: &IndexMap<String, String>,
+    query: &Query,
+) -> Result<String> {
+    let mut location = compile_destination(destination, params);
+    if !query.is_empty() {
+        let separator = if location.contains('?') { '&' } else { '?' };
+        location = format!("{location}{separator}{}", serde_qs::to_string(query)?);
+    }
+    Ok(location)
+}
+
+/// Applies a rewrite to `data` and returns the path to pass to the source.
+/// Like in Next.js, the parameters which aren't used by the destination are
+/// added to the query, as well as the query of the destination.
+fn rewrite(
+    destination: &str,
+    params: &IndexMap<String, String>,
+    data: &mut ContentSourceData,
+) -> String {
+    let compiled = compile_destination(destination, params);
+    let (path, destination_query) = compiled.split_once('?').unwrap_or((&compiled, ""));
+    let query = data.query.get_or_insert_with(Query::default);
+    for (name, value) in params {
+        if !destination.contains(&format!(":{name}")) {
+            query.insert(name.clone(), QueryValue::String(value.clone()));
+        }
+    }
+    for (key, value) in url::form_urlencoded::parse(destination_query.as_bytes()) {
+        query.insert(key.into_owned(), QueryValue::String(value.into_owned()));
+    }
+    path.trim_start_matches('/').to_string()
+}
+
+impl RouteMatcher {
+    /// Matches the request and returns the parameters of the path and the
+    /// `has` conditions.
+    fn match_request(&self, request: &RouteRequest) -> Option<IndexMap<String, String>> {
+        let mut params = self.source.match_path(&request.pathname)?;
+        for condition in &self.has {
+            params.extend(match_condition(condition, request)?);
+        }
+        if self
+            .missing
+            .iter()
+            .any(|condition| match_condition(condition, request).is_some())
+        {
+            return None;
+        }
+        Some(params)
+    }
+}
+
+fn condition_value(condition: &RouteHas) -> Option<&str> {
+    match condition {
+        RouteHas::Header { value, .. }
+        | RouteHas::Cookie { value, .. }
+        | RouteHas::Query { value, .. } => value.as_deref(),
+        RouteHas::Host { value } => Some(value),
+    }
+}
+
+/// Matches a `has` or `missing` condition with the request, and returns the
+/// parameters it defines: the named groups of its regular expression, or the
+/// value of the header, cookie or query key without a regular expression.
+fn match_condition(
+    condition: &RouteCondition,
+    request: &RouteRequest,
+) -> Option<IndexMap<String, String>> {
+    let (key, actual) = match &condition.condition {
+        RouteHas::Header { key, .. } => (Some(key), header(&request.headers, &key.to_lowercase())),
+        RouteHas::Cookie { key, .. } => (
+            Some(key),
+            header(&request.headers, "cookie").and_then(|cookie| {
+                cookie
+                    .split(';')
+                    .find_map(|pair| match pair.trim().split_once('=') {
+                        Some((name, value)) if name == key.as_str() => Some(value),
+                        _ => None,
+                    })
+            }),
+        ),
+        RouteHas::Query { key, .. } => (
+            Some(key),
+            match request.query.get(key) {
+                Some(QueryValue::String(value)) => Some(&**value),
+                Some(QueryValue::Array(values)) => match values.last() {
+                    Some(QueryValue::String(value)) => Some(&**value),
+                    _ => None,
+                },
+                _ => None,
+            },
+        ),
+        RouteHas::Host { .. } => (
+            None,
+            header(&request.headers, "host").map(|host| host.split(':').next().unwrap_or(host)),
+        ),
+    };
+    let actual = actual?;
+    let mut params = IndexMap::new();
+    match &condition.value {
+        None => {
+            if let Some(key) = key {
+                let name: String = key.chars().filter(char::is_ascii_alphabetic).collect();
+                params.insert(name, actual.to_string());
+            }
+        }
+        Some(value) => {
+            params = value.match_value(actual)?;
+            if !value.has_groups() && key.is_none() {
+                params.insert("host".to_string(), actual.to_string());
+            }
+        }
+    }
+    Some(params)
+}
+
+#[turbo_tasks::value(shared)]
+pub struct ConfigRouteIssue {
+    pub path: FileSystemPathVc,
+    pub source: String,
+    pub message: String,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for ConfigRouteIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Warning.into()
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("config".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(format!("The route {} is ignored", self.source))
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(self.message.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use indexmap::IndexMap;
+    use turbopack_dev_server::source::{
+        query::{Query, QueryValue},
+        ContentSourceData, HeaderValue,
+    };
+
+    use super::{redirect_location, rewrite, RouteCondition, RouteMatcher, RouteRequest};
+    use crate::{next_config::RouteHas, path_to_regexp::PathPattern};
+
+    fn request(pathname: &str, headers: &[(&str, &str)], query: &[(&str, &str)]) -> RouteRequest {
+        RouteRequest {
+            pathname: pathname.to_string(),
+            headers: headers
+                .iter()
+                .map(|&(name, value)| {
+                    (
+                        name.to_string(),
+                        HeaderValue::SingleString(value.to_string()),
+                    )
+                })
+                .collect::<BTreeMap<_, _>>(),
+            query: query_of(query),
+        }
+    }
+
+    fn query_of(query: &[(&str, &str)]) -> Query {
+        let mut result = Query::default();
+        for &(key, value) in query {
+            result.insert(key.to_string(), QueryValue::String(value.to_string()));
+        }
+        result
+    }
+
+    fn condition(condition: RouteHas) -> RouteCondition {
+        RouteCondition::new(&condition).unwrap()
+    }
+
+    fn params(params: &[(&str, &str)]) -> IndexMap<String, String> {
+        params
+            .iter()
+            .map(|&(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn matches_conditions() {
+        let matcher = RouteMatcher {
+            source: PathPattern::new("/docs/:path*").unwrap(),
+            has: vec![
+                condition(RouteHas::Cookie {
+                    key: "beta-user".to_string(),
+                    value: None,
+                }),
+                condition(RouteHas::Header {
+                    key: "X-Lang".to_string(),
+                    value: Some("(?<lang>en|fr)".to_string()),
+                }),
+            ],
+            missing: vec![condition(RouteHas::Query {
+                key: "legacy".to_string(),
+                value: None,
+            })],
+        };
+        assert_eq!(
+            matcher.match_request(&request(
+                "/docs/intro",
+                &[("cookie", "theme=dark; beta-user=1"), ("x-lang", "fr")],
+                &[],
+            )),
+            Some(params(&[
+                ("path", "intro"),
+                ("betauser", "1"),
+                ("lang", "fr")
+            ]))
+        );
+        assert_eq!(
+            matcher.match_request(&request(
+                "/docs/intro",
+                &[("cookie", "beta-user=1"), ("x-lang", "de")],
+                &[],
+            )),
+            None
+        );
+        assert_eq!(
+            matcher.match_request(&request(
+                "/docs/intro",
+                &[("cookie", "beta-user=1"), ("x-lang", "en")],
+                &[("legacy", "1")],
+            )),
+            None
+        );
+        assert_eq!(
+            matcher.match_request(&request("/docs/intro", &[("x-lang", "en")], &[])),
+            None
+        );
+
+        let matcher = RouteMatcher {
+            source: PathPattern::new("/(.*)").unwrap(),
+            has: vec![condition(RouteHas::Host {
+                value: "example\\.com".to_string(),
+            })],
+            missing: Vec::new(),
+        };
+        assert_eq!(
+            matcher.match_request(&request("/", &[("host", "example.com:3000")], &[])),
+            Some(params(&[("host", "example.com")]))
+        );
+    }
+
+    #[test]
+    fn rewrites_path_and_query() {
+        let mut data = ContentSourceData {
+            query: Some(query_of(&[("page", "2")])),
+            ..Default::default()
+        };
+        let path = rewrite(
+            "/news/:slug?ref=:source",
+            &params(&[("slug", "hello"), ("source", "blog"), ("id", "7")]),
+            &mut data,
+        );
+        assert_eq!(path, "news/hello");
+        assert_eq!(
+            data.query,
+            Some(query_of(&[("page", "2"), ("id", "7"), ("ref", "blog")]))
+        );
+    }
+
+    #[test]
+    fn omits_empty_optional_segments() {
+        let matcher = PathPattern::new("/docs/:path*").unwrap();
+        let params = matcher.match_path("/docs").unwrap();
+        assert_eq!(
+            redirect_location("/new-docs/:path*", &params, &Query::default()).unwrap(),
+            "/new-docs"
+        );
+        assert_eq!(
+            redirect_location("/new-docs/:path*", &params, &query_of(&[("page", "2")])).unwrap(),
+            "/new-docs?page=2"
+        );
+
+        let mut data = ContentSourceData::default();
+        assert_eq!(rewrite("/en/docs/:path*", &params, &mut data), "en/docs");
+        assert_eq!(data.query, Some(Query::default()));
+    }
+}
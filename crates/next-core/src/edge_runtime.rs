@@ -0,0 +1,444 @@
+//! The edge runtime of middleware and of API routes which export `runtime =
+//! "edge"`. In development they run in Node.js, in a sandbox which only
+//! exposes the globals of the edge runtime (see `internal/edge-runtime.ts`).
+//! The APIs of Node.js which the edge runtime doesn't support are found
+//! statically and reported as issues.
+
+use anyhow::Result;
+use swc_core::ecma::{
+    ast::{
+        CallExpr, Callee, Decl, ExportAll, Expr, ImportDecl, Lit, MemberExpr, MemberProp,
+        ModuleDecl, ModuleItem, NamedExport, NewExpr, Pat, Program, Prop, PropName, PropOrSpread,
+    },
+    visit::{Visit, VisitWith},
+};
+use turbo_tasks::{primitives::StringVc, CompletionVc};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack::resolve::NODE_EXTERNALS;
+use turbopack_core::{
+    asset::Asset,
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+};
+use turbopack_ecmascript::{EcmascriptModuleAssetVc, ParseResult};
+
+use crate::next_link::project_modules;
+
+/// The globals of Node.js which are not available in the edge runtime.
+const NODE_GLOBALS: &[&str] = &["Buffer", "setImmediate", "clearImmediate"];
+
+/// The static config of a route module: its `runtime` export, or the `config`
+/// export of API routes and middleware.
+#[turbo_tasks::value(shared)]
+#[derive(Clone, Debug, Default)]
+pub struct ModuleConfig {
+    pub runtime: Option<String>,
+    /// The paths which middleware runs for, in the syntax of
+    /// `path-to-regexp`. Middleware runs for all paths when it is empty.
+    pub matcher: Vec<String>,
+}
+
+impl ModuleConfig {
+    pub fn is_edge(&self) -> bool {
+        matches!(self.runtime.as_deref(), Some("edge" | "experimental-edge"))
+    }
+}
+
+/// Reads the config of a route module from its exports. Only literals are
+/// supported, like in Next.js.
+#[turbo_tasks::function]
+pub async fn module_config(module: EcmascriptModuleAssetVc) -> Result<ModuleConfigVc> {
+    let parsed = module.parse().await?;
+    Ok(match &*parsed {
+        ParseResult::Ok { program, .. } => config_of_program(program),
+        _ => ModuleConfig::default(),
+    }
+    .cell())
+}
+
+fn config_of_program(program: &Program) -> ModuleConfig {
+    let mut config = ModuleConfig::default();
+    let module = match program {
+        Program::Module(module) => module,
+        Program::Script(_) => return config,
+    };
+    for item in &module.body {
+        let var = match item {
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(export)) => match &export.decl {
+                Decl::Var(var) => var,
+                _ => continue,
+            },
+            _ => continue,
+        };
+        for decl in &var.decls {
+            let (name, init) = match (&decl.name, &decl.init) {
+                (Pat::Ident(ident), Some(init)) => (&*ident.id.sym, &**init),
+                _ => continue,
+            };
+            match (name, init) {
+                ("runtime", init) => config.runtime = string_value(init).map(str::to_string),
+                ("config", Expr::Object(object)) => {
+                    for prop in &object.props {
+                        let (key, value) = match prop {
+                            PropOrSpread::Prop(prop) => match &**prop {
+                                Prop::KeyValue(prop) => (&prop.key, &*prop.value),
+                                _ => continue,
+                            },
+                            _ => continue,
+                        };
+                        match key {
+                            PropName::Ident(ident) if &*ident.sym == "runtime" => {
+                                config.runtime = string_value(value).map(str::to_string)
+                            }
+                            PropName::Ident(ident) if &*ident.sym == "matcher" => {
+                                config.matcher = matcher_value(value)
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    config
+}
+
+fn string_value(expr: &Expr) -> Option<&str> {
+    match expr {
+        Expr::Lit(Lit::Str(str)) => Some(&*str.value),
+        Expr::TsConstAssertion(assertion) => string_value(&assertion.expr),
+        Expr::TsAs(as_expr) => string_value(&as_expr.expr),
+        _ => None,
+    }
+}
+
+/// Reads a matcher: a path, an array of paths, or objects with a `source`
+/// path.
+fn matcher_value(expr: &Expr) -> Vec<String> {
+    match expr {
+        Expr::Array(array) => array
+            .elems
+            .iter()
+            .flatten()
+            .flat_map(|elem| matcher_value(&elem.expr))
+            .collect(),
+        Expr::Object(object) => object
+            .props
+            .iter()
+            .filter_map(|prop| match prop {
+                PropOrSpread::Prop(prop) => match &**prop {
+                    Prop::KeyValue(prop) => match &prop.key {
+                        PropName::Ident(ident) if &*ident.sym == "source" => {
+                            string_value(&prop.value).map(str::to_string)
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect(),
+        expr => string_value(expr).map(str::to_string).into_iter().collect(),
+    }
+}
+
+/// The APIs used by a module which the edge runtime doesn't support.
+#[turbo_tasks::value(shared)]
+#[derive(Clone, Debug, Default)]
+pub struct UnsupportedApis {
+    /// Imported built-in modules of Node.js.
+    pub modules: Vec<String>,
+    /// Used globals of Node.js, and properties of `process` other than
+    /// `env`.
+    pub apis: Vec<String>,
+    /// Whether the module evaluates code with `eval` or `new Function`.
+    pub dynamic_code: bool,
+}
+
+impl UnsupportedApis {
+    fn add_module(&mut self, request: &str) {
+        let name = request.strip_prefix("node:").unwrap_or(request);
+        if NODE_EXTERNALS.contains(&name) && !self.modules.iter().any(|module| module == name) {
+            self.modules.push(name.to_string());
+        }
+    }
+
+    fn add_api(&mut self, api: String) {
+        if !self.apis.contains(&api) {
+            self.apis.push(api);
+        }
+    }
+}
+
+/// Reports the APIs unsupported by the edge runtime which the modules of the
+/// project imported by `entry` use. Packages in `node_modules` aren't checked.
+#[turbo_tasks::function]
+pub async fn check_edge_runtime(entry: EcmascriptModuleAssetVc) -> Result<CompletionVc> {
+    for module in project_modules(entry).await? {
+        let apis = module_unsupported_apis(module).await?;
+        let emit = |severity: IssueSeverity, title: &str, message: String| {
+            EdgeRuntimeIssue {
+                path: module.path(),
+                severity: severity.into(),
+                title: StringVc::cell(title.to_string()),
+                message: StringVc::cell(message),
+            }
+            .cell()
+            .as_issue()
+            .emit()
+        };
+        if !apis.modules.is_empty() {
+            emit(
+                IssueSeverity::Error,
+                "Node.js module is not supported in the Edge Runtime",
+                format!(
+                    "The edge runtime does not support the Node.js modules {}, which are imported \
+                     by this module.",
+                    apis.modules.join(", ")
+                ),
+            );
+        }
+        if apis.dynamic_code {
+            emit(
+                IssueSeverity::Error,
+                "Dynamic code evaluation is not allowed in the Edge Runtime",
+                "The edge runtime does not support evaluating code with `eval` or `new Function`."
+                    .to_string(),
+            );
+        }
+        for api in apis.apis.iter() {
+            emit(
+                IssueSeverity::Warning,
+                "Node.js API is not supported in the Edge Runtime",
+                format!(
+                    "A Node.js API is used ({api}) which is not supported in the Edge Runtime."
+                ),
+            );
+        }
+    }
+    Ok(CompletionVc::new())
+}
+
+/// The unsupported APIs used by a single module.
+#[turbo_tasks::function]
+async fn module_unsupported_apis(module: EcmascriptModuleAssetVc) -> Result<UnsupportedApisVc> {
+    let parsed = module.parse().await?;
+    Ok(match &*parsed {
+        ParseResult::Ok { program, .. } => unsupported_apis_of_program(program),
+        _ => UnsupportedApis::default(),
+    }
+    .cell())
+}
+
+fn unsupported_apis_of_program(program: &Program) -> UnsupportedApis {
+    let mut finder = UnsupportedApiFinder {
+        apis: UnsupportedApis::default(),
+    };
+    program.visit_with(&mut finder);
+    finder.apis
+}
+
+struct UnsupportedApiFinder {
+    apis: UnsupportedApis,
+}
+
+impl UnsupportedApiFinder {
+    fn first_string_arg(call: &CallExpr) -> Option<&str> {
+        call.args.first().and_then(|arg| string_value(&arg.expr))
+    }
+}
+
+impl Visit for UnsupportedApiFinder {
+    fn visit_import_decl(&mut self, import: &ImportDecl) {
+        // Type imports are removed by the TypeScript transform.
+        if !import.type_only {
+            self.apis.add_module(&import.src.value);
+        }
+    }
+
+    fn visit_export_all(&mut self, export: &ExportAll) {
+        self.apis.add_module(&export.src.value);
+    }
+
+    fn visit_named_export(&mut self, export: &NamedExport) {
+        if let Some(src) = &export.src {
+            if !export.type_only {
+                self.apis.add_module(&src.value);
+            }
+        }
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        match &call.callee {
+            Callee::Import(_) => {
+                if let Some(request) = Self::first_string_arg(call) {
+                    self.apis.add_module(request);
+                }
+            }
+            Callee::Expr(callee) => match &**callee {
+                Expr::Ident(ident) if &*ident.sym == "require" => {
+                    if let Some(request) = Self::first_string_arg(call) {
+                        self.apis.add_module(request);
+                    }
+                }
+                Expr::Ident(ident) if &*ident.sym == "eval" || &*ident.sym == "Function" => {
+                    self.apis.dynamic_code = true;
+                }
+                _ => {}
+            },
+            Callee::Super(_) => {}
+        }
+        call.visit_children_with(self);
+    }
+
+    fn visit_new_expr(&mut self, new: &NewExpr) {
+        if matches!(&*new.callee, Expr::Ident(ident) if &*ident.sym == "Function") {
+            self.apis.dynamic_code = true;
+        }
+        new.visit_children_with(self);
+    }
+
+    fn visit_member_expr(&mut self, member: &MemberExpr) {
+        if let (Expr::Ident(object), MemberProp::Ident(prop)) = (&*member.obj, &member.prop) {
+            if &*object.sym == "process" && &*prop.sym != "env" {
+                self.apis.add_api(format!("process.{}", prop.sym));
+            }
+        }
+        member.visit_children_with(self);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let Expr::Ident(ident) = expr {
+            if NODE_GLOBALS.contains(&&*ident.sym) {
+                self.apis.add_api(ident.sym.to_string());
+            }
+        }
+        expr.visit_children_with(self);
+    }
+}
+
+#[turbo_tasks::value(shared)]
+pub struct EdgeRuntimeIssue {
+    pub path: FileSystemPathVc,
+    pub severity: IssueSeverityVc,
+    pub title: StringVc,
+    pub message: StringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for EdgeRuntimeIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        self.severity
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("edge runtime".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        self.title
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        self.message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{FileName, SourceMap},
+        ecma::{
+            ast::{EsVersion, Program},
+            parser::{parse_file_as_program, Syntax, TsConfig},
+        },
+    };
+
+    use super::{config_of_program, unsupported_apis_of_program, ModuleConfig, UnsupportedApis};
+
+    fn parse(code: &str) -> Program {
+        let source_map = SourceMap::default();
+        let file = source_map.new_source_file(FileName::Anon, code.to_string());
+        parse_file_as_program(
+            &file,
+            Syntax::Typescript(TsConfig::default()),
+            EsVersion::latest(),
+            None,
+            &mut vec![],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn reads_module_configs() {
+        let config = config_of_program(&parse(
+            r#"
+                export const runtime = "edge";
+                export default function handler() {}
+            "#,
+        ));
+        assert!(config.is_edge());
+
+        let config = config_of_program(&parse(
+            r#"
+                export const config = {
+                    runtime: "experimental-edge",
+                    matcher: ["/about/:path*", { source: "/dashboard/:path*" }],
+                };
+            "#,
+        ));
+        assert_eq!(
+            config,
+            ModuleConfig {
+                runtime: Some("experimental-edge".to_string()),
+                matcher: vec!["/about/:path*".to_string(), "/dashboard/:path*".to_string()],
+            }
+        );
+
+        let config = config_of_program(&parse(r#"export const config = { matcher: "/api" };"#));
+        assert!(!config.is_edge());
+        assert_eq!(config.matcher, vec!["/api".to_string()]);
+    }
+
+    #[test]
+    fn finds_unsupported_apis() {
+        let apis = unsupported_apis_of_program(&parse(
+            r#"
+                import fs from "node:fs";
+                import type { Stats } from "fs";
+                import { join } from "path";
+                import { NextResponse } from "next/server";
+                const crypto = require("crypto");
+                export default async function handler() {
+                    await import("fs/promises");
+                    setImmediate(() => {});
+                    const cwd = process.cwd();
+                    const secret = process.env.SECRET;
+                    return new Function("return 1")();
+                }
+            "#,
+        ));
+        assert_eq!(
+            apis,
+            UnsupportedApis {
+                modules: vec![
+                    "fs".to_string(),
+                    "path".to_string(),
+                    "crypto".to_string(),
+                    "fs/promises".to_string()
+                ],
+                apis: vec!["setImmediate".to_string(), "process.cwd".to_string()],
+                dynamic_code: true,
+            }
+        );
+    }
+}
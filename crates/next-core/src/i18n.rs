@@ -0,0 +1,305 @@
+//! Locale-prefixed routing of the pages directory, configured by the `i18n`
+//! options of `next.config.js`.
+//!
+//! Pages are served with and without a locale prefix, e. g. `/fr/about` and
+//! `/about`. The prefix is stripped before the pages are matched, and the
+//! renderer reads the locale from the URL of the request. Requests for `/` are
+//! redirected to the locale preferred by the browser.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, Value};
+use turbo_tasks_fs::rope::Rope;
+use turbopack_core::introspect::{Introspectable, IntrospectableChildrenVc, IntrospectableVc};
+use turbopack_dev_server::source::{
+    ContentSource, ContentSourceContent, ContentSourceData, ContentSourceDataFilter,
+    ContentSourceDataVary, ContentSourceResultVc, ContentSourceVc, ProxyResult,
+};
+
+use crate::{
+    next_config::{I18NConfig, NextConfigVc},
+    nodejs::node_rendered_source::DATA_ROUTE_PREFIX,
+    util::header,
+};
+
+/// Serves `source` with the locale prefixes of the `i18n` config. Without an
+/// `i18n` config, requests are passed through.
+#[turbo_tasks::value(shared)]
+pub struct I18nContentSource {
+    pub source: ContentSourceVc,
+    pub next_config: NextConfigVc,
+}
+
+#[turbo_tasks::value_impl]
+impl I18nContentSourceVc {
+    #[turbo_tasks::function]
+    pub fn new(source: ContentSourceVc, next_config: NextConfigVc) -> Self {
+        I18nContentSource {
+            source,
+            next_config,
+        }
+        .cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for I18nContentSource {
+    #[turbo_tasks::function]
+    async fn get(
+        self_vc: I18nContentSourceVc,
+        path: &str,
+        data: Value<ContentSourceData>,
+    ) -> Result<ContentSourceResultVc> {
+        let this = self_vc.await?;
+        let next_config = this.next_config.await?;
+        let i18n = match &next_config.i18n {
+            Some(i18n) => i18n,
+            None => return Ok(this.source.get(path, data)),
+        };
+
+        if let Some(data_path) = path.strip_prefix(DATA_ROUTE_PREFIX) {
+            let data_path = strip_locale(i18n, data_path).unwrap_or(data_path);
+            return Ok(this
+                .source
+                .get(&format!("{DATA_ROUTE_PREFIX}{data_path}"), data));
+        }
+        if let Some(path) = strip_locale(i18n, path) {
+            return Ok(this.source.get(path, data));
+        }
+        if !path.is_empty() || i18n.locale_detection == Some(false) {
+            return Ok(this.source.get(path, data));
+        }
+
+        let headers = match &data.headers {
+            Some(headers) => headers,
+            None => {
+                return Ok(ContentSourceResultVc::exact(
+                    ContentSourceContent::NeedData {
+                        source: self_vc.into(),
+                        path: path.to_string(),
+                        vary: ContentSourceDataVary {
+                            headers: Some(ContentSourceDataFilter::Subset(HashSet::from([
+                                "accept-language".to_string(),
+                                "cookie".to_string(),
+                                "host".to_string(),
+                            ]))),
+                            ..Default::default()
+                        },
+                    }
+                    .cell(),
+                ))
+            }
+        };
+        let location = locale_redirect(
+            i18n,
+            header(headers, "host"),
+            header(headers, "cookie"),
+            header(headers, "accept-language"),
+        );
+        match location {
+            Some(location) => Ok(ContentSourceResultVc::exact(
+                ContentSourceContent::HttpProxy(
+                    ProxyResult {
+                        status: 307,
+                        headers: vec!["location".to_string(), location],
+                        body: Rope::default(),
+                    }
+                    .cell(),
+                )
+                .cell(),
+            )),
+            None => Ok(this.source.get(path, data)),
+        }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Introspectable for I18nContentSource {
+    #[turbo_tasks::function]
+    fn ty(&self) -> StringVc {
+        StringVc::cell("i18n content source".to_string())
+    }
+
+    #[turbo_tasks::function]
+    async fn children(&self) -> Result<IntrospectableChildrenVc> {
+        Ok(IntrospectableChildrenVc::cell(
+            IntrospectableVc::resolve_from(self.source)
+                .await?
+                .map(|source| (StringVc::cell("source".to_string()), source))
+                .into_iter()
+                .collect(),
+        ))
+    }
+}
+
+/// Strips the locale prefix of `path`, e. g. `fr/about` becomes `about`.
+fn strip_locale<'a>(i18n: &I18NConfig, path: &'a str) -> Option<&'a str> {
+    let (first, rest) = path.split_once('/').unwrap_or((path, ""));
+    i18n.locales
+        .iter()
+        .any(|locale| locale.eq_ignore_ascii_case(first))
+        .then_some(rest)
+}
+
+/// Returns where a request for `/` is redirected to, if the browser prefers
+/// another locale than the default locale of the domain. Locales which are
+/// the default of another domain are redirected to that domain.
+fn locale_redirect(
+    i18n: &I18NConfig,
+    host: Option<&str>,
+    cookie: Option<&str>,
+    accept_language: Option<&str>,
+) -> Option<String> {
+    let hostname = host.map(|host| host.split(':').next().unwrap_or(host));
+    let domain = i18n
+        .domains
+        .iter()
+        .find(|domain| Some(domain.domain.split(':').next().unwrap_or_default()) == hostname);
+    let default_locale = domain.map_or(&i18n.default_locale, |domain| &domain.default_locale);
+    let locale = detect_locale(i18n, cookie, accept_language)?;
+    if locale.eq_ignore_ascii_case(default_locale) {
+        return None;
+    }
+    if let Some(other) = i18n.domains.iter().find(|other| {
+        other.default_locale.eq_ignore_ascii_case(locale)
+            && Some(other.domain.as_str()) != domain.map(|domain| domain.domain.as_str())
+    }) {
+        let scheme = if other.http { "http" } else { "https" };
+        return Some(format!("{scheme}://{}/", other.domain));
+    }
+    Some(format!("/{locale}"))
+}
+
+/// The locale preferred by the browser: the one of the `NEXT_LOCALE` cookie,
+/// or the best match for the `Accept-Language` header.
+fn detect_locale<'a>(
+    i18n: &'a I18NConfig,
+    cookie: Option<&str>,
+    accept_language: Option<&str>,
+) -> Option<&'a str> {
+    let find = |language: &str| {
+        i18n.locales
+            .iter()
+            .find(|locale| locale.eq_ignore_ascii_case(language))
+            .map(|locale| &**locale)
+    };
+    if let Some(cookie) = cookie {
+        for pair in cookie.split(';') {
+            if let Some(("NEXT_LOCALE", value)) = pair.trim().split_once('=') {
+                if let Some(locale) = find(value) {
+                    return Some(locale);
+                }
+            }
+        }
+    }
+
+    let mut languages: Vec<(&str, f32)> = accept_language?
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let language = parts.next()?.trim();
+            let quality = parts
+                .find_map(|part| part.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |quality| quality.parse().ok())?;
+            (!language.is_empty() && quality > 0.0).then_some((language, quality))
+        })
+        .collect();
+    // The sort is stable, so languages with the same quality keep their order.
+    languages.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    languages.into_iter().find_map(|(language, _)| {
+        let primary = language.split('-').next().unwrap_or(language);
+        find(language).or_else(|| find(primary)).or_else(|| {
+            i18n.locales
+                .iter()
+                .find(|locale| {
+                    locale
+                        .split('-')
+                        .next()
+                        .map_or(false, |locale| locale.eq_ignore_ascii_case(primary))
+                })
+                .map(|locale| &**locale)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect_locale, locale_redirect, strip_locale};
+    use crate::next_config::{DomainLocale, I18NConfig};
+
+    fn i18n() -> I18NConfig {
+        I18NConfig {
+            locales: vec!["en-US".to_string(), "fr".to_string(), "nl-NL".to_string()],
+            default_locale: "en-US".to_string(),
+            domains: Vec::new(),
+            locale_detection: None,
+        }
+    }
+
+    #[test]
+    fn strips_locales() {
+        let i18n = i18n();
+        assert_eq!(strip_locale(&i18n, "fr/about"), Some("about"));
+        assert_eq!(strip_locale(&i18n, "nl-nl"), Some(""));
+        assert_eq!(strip_locale(&i18n, "about"), None);
+        assert_eq!(strip_locale(&i18n, "french/about"), None);
+    }
+
+    #[test]
+    fn detects_locales() {
+        let i18n = i18n();
+        assert_eq!(
+            detect_locale(&i18n, None, Some("de-DE, fr;q=0.9, en;q=0.8")),
+            Some("fr")
+        );
+        assert_eq!(
+            detect_locale(&i18n, None, Some("nl;q=0.5, en")),
+            Some("en-US")
+        );
+        assert_eq!(detect_locale(&i18n, None, Some("fr-CA")), Some("fr"));
+        assert_eq!(
+            detect_locale(&i18n, Some("theme=dark; NEXT_LOCALE=nl-NL"), Some("fr")),
+            Some("nl-NL")
+        );
+        assert_eq!(detect_locale(&i18n, None, Some("de, *;q=0.1")), None);
+        assert_eq!(detect_locale(&i18n, None, None), None);
+    }
+
+    #[test]
+    fn redirects_to_preferred_locale() {
+        let mut i18n = i18n();
+        assert_eq!(locale_redirect(&i18n, None, None, Some("en")), None);
+        assert_eq!(
+            locale_redirect(&i18n, None, None, Some("fr")).as_deref(),
+            Some("/fr")
+        );
+
+        i18n.domains = vec![
+            DomainLocale {
+                domain: "example.com".to_string(),
+                default_locale: "en-US".to_string(),
+                locales: Vec::new(),
+                http: false,
+            },
+            DomainLocale {
+                domain: "example.fr".to_string(),
+                default_locale: "fr".to_string(),
+                locales: Vec::new(),
+                http: true,
+            },
+        ];
+        assert_eq!(
+            locale_redirect(&i18n, Some("example.com"), None, Some("fr")).as_deref(),
+            Some("http://example.fr/")
+        );
+        assert_eq!(
+            locale_redirect(&i18n, Some("example.fr:3000"), None, Some("fr")),
+            None
+        );
+        assert_eq!(
+            locale_redirect(&i18n, Some("example.fr"), None, Some("nl")).as_deref(),
+            Some("/nl-NL")
+        );
+    }
+}
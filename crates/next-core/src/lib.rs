@@ -1,30 +1,49 @@
 #![feature(async_closure)]
 #![feature(min_specialization)]
 
+mod app_metadata;
 mod app_render;
 mod app_source;
+mod config_routes;
+mod edge_runtime;
 mod embed_js;
 pub mod env;
 mod fallback;
+mod i18n;
+mod middleware;
 pub mod next_client;
 mod next_client_component;
+pub mod next_config;
+mod next_dynamic;
+pub mod next_font_google;
 mod next_import_map;
+mod next_link;
+pub mod next_lint;
+mod next_script;
 pub mod next_server;
 mod nodejs;
 mod path_regex;
+mod path_to_regexp;
+mod public_source;
 pub mod react_refresh;
 mod runtime;
 mod server_rendered_source;
 pub mod source_map;
+pub mod type_check;
 mod util;
 mod web_entry_source;
+pub mod workspace;
 
 pub use app_source::create_app_source;
+pub use config_routes::create_config_routes_source;
+pub use middleware::create_middleware_source;
+pub use public_source::{create_public_source, public_routes};
 pub use server_rendered_source::create_server_rendered_source;
 pub use web_entry_source::create_web_entry_source;
 
 pub fn register() {
     turbo_tasks::register();
+    turbo_tasks_fetch::register();
     turbo_tasks_fs::register();
     turbopack_dev_server::register();
     turbopack::register();
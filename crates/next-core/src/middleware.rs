@@ -0,0 +1,172 @@
+//! The `middleware.ts` of a project, which runs in the edge runtime before the
+//! requests reach the pages. It runs after the `headers` and `redirects` of
+//! `next.config.js`, and is skipped for the assets of Next.js.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use turbo_tasks::{
+    primitives::{StringVc, StringsVc},
+    Value,
+};
+use turbo_tasks_env::ProcessEnvVc;
+use turbo_tasks_fs::{FileSystemEntryType, FileSystemPathVc};
+use turbopack::{transition::TransitionsByNameVc, ModuleAssetContextVc};
+use turbopack_core::{
+    chunk::dev::DevChunkingContextVc,
+    context::AssetContextVc,
+    issue::{IssueSeverity, IssueVc},
+    source_asset::SourceAssetVc,
+    virtual_asset::VirtualAssetVc,
+};
+use turbopack_dev_server::source::{ContentSourceData, ContentSourceVc};
+use turbopack_ecmascript::{
+    chunk::EcmascriptChunkPlaceablesVc, EcmascriptInputTransform, EcmascriptInputTransformsVc,
+    EcmascriptModuleAssetType, EcmascriptModuleAssetVc,
+};
+use turbopack_env::ProcessEnvAssetVc;
+
+use crate::{
+    edge_runtime::{check_edge_runtime, module_config, EdgeRuntimeIssue},
+    embed_js::{next_js_file, wrap_with_next_js_fs},
+    next_server::{
+        get_server_environment, get_server_module_options_context,
+        get_server_resolve_options_context, ServerContextType,
+    },
+    nodejs::{
+        create_node_middleware_source,
+        node_entry::{NodeRenderingEntry, NodeRenderingEntryVc},
+        NodeEntry, NodeEntryVc,
+    },
+    path_to_regexp::PathPattern,
+};
+
+/// Runs the middleware of the project, in its root or `src` directory, before
+/// `source`. Without middleware, `source` is returned.
+#[turbo_tasks::function]
+pub async fn create_middleware_source(
+    project_root: FileSystemPathVc,
+    output_path: FileSystemPathVc,
+    server_root: FileSystemPathVc,
+    env: ProcessEnvVc,
+    source: ContentSourceVc,
+) -> Result<ContentSourceVc> {
+    let project_path = wrap_with_next_js_fs(project_root);
+    let file = match find_middleware(project_path).await? {
+        Some(file) => file,
+        None => return Ok(source),
+    };
+
+    let ty = Value::new(ServerContextType::Middleware);
+    let context: AssetContextVc = ModuleAssetContextVc::new(
+        TransitionsByNameVc::cell(HashMap::new()),
+        get_server_environment(ty, env),
+        get_server_module_options_context(project_path, ty),
+        get_server_resolve_options_context(project_path, ty, StringsVc::empty()),
+    )
+    .into();
+    let module = match EcmascriptModuleAssetVc::resolve_from(
+        context.process(SourceAssetVc::new(file).into()),
+    )
+    .await?
+    {
+        Some(module) => module,
+        None => return Ok(source),
+    };
+
+    let mut matcher = Vec::new();
+    for pattern in module_config(module).await?.matcher.iter() {
+        match PathPattern::new(pattern) {
+            Ok(pattern) => matcher.push(pattern),
+            Err(err) => EdgeRuntimeIssue {
+                path: file,
+                severity: IssueSeverity::Warning.into(),
+                title: StringVc::cell("Invalid middleware matcher".to_string()),
+                message: StringVc::cell(format!("The matcher {pattern} is ignored: {err:#}")),
+            }
+            .cell()
+            .as_issue()
+            .emit(),
+        }
+    }
+
+    let runtime_entries =
+        EcmascriptChunkPlaceablesVc::cell(vec![
+            ProcessEnvAssetVc::new(project_path, env).as_ecmascript_chunk_placeable()
+        ]);
+    let entry = MiddlewareEntry {
+        context,
+        file,
+        module,
+        project_root: project_path,
+        server_root,
+        intermediate_output_path: output_path,
+    }
+    .cell()
+    .into();
+    Ok(create_node_middleware_source(
+        server_root,
+        matcher,
+        entry,
+        runtime_entries,
+        source,
+    ))
+}
+
+async fn find_middleware(project_path: FileSystemPathVc) -> Result<Option<FileSystemPathVc>> {
+    for dir in ["", "src/"] {
+        for extension in ["ts", "js", "tsx", "jsx"] {
+            let file = project_path.join(&format!("{dir}middleware.{extension}"));
+            if *file.get_type().await? == FileSystemEntryType::File {
+                return Ok(Some(file));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// The node.js entry of the middleware, which runs it in the sandbox of the
+/// edge runtime.
+#[turbo_tasks::value]
+struct MiddlewareEntry {
+    context: AssetContextVc,
+    file: FileSystemPathVc,
+    module: EcmascriptModuleAssetVc,
+    project_root: FileSystemPathVc,
+    server_root: FileSystemPathVc,
+    intermediate_output_path: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl NodeEntry for MiddlewareEntry {
+    #[turbo_tasks::function]
+    async fn entry(&self, _data: Value<ContentSourceData>) -> Result<NodeRenderingEntryVc> {
+        check_edge_runtime(self.module).await?;
+        let asset = VirtualAssetVc::new(
+            self.file.join("middleware.ts"),
+            next_js_file("entry/middleware.ts").into(),
+        );
+
+        let chunking_context = DevChunkingContextVc::builder(
+            self.project_root,
+            self.intermediate_output_path,
+            self.intermediate_output_path.join("chunks"),
+            self.server_root.join("_next/static/media"),
+        )
+        .build();
+
+        Ok(NodeRenderingEntry {
+            module: EcmascriptModuleAssetVc::new(
+                asset.into(),
+                self.context,
+                Value::new(EcmascriptModuleAssetType::Typescript),
+                EcmascriptInputTransformsVc::cell(vec![EcmascriptInputTransform::TypeScript]),
+                self.context.environment(),
+            ),
+            chunking_context,
+            intermediate_output_path: self.intermediate_output_path,
+            trace: false,
+        }
+        .cell())
+    }
+}
@@ -27,6 +27,9 @@ use crate::{
     embed_js::attached_next_js_package_path,
     env::filter_for_client,
     next_client::runtime_entry::{RuntimeEntriesVc, RuntimeEntry},
+    next_config::load_next_config,
+    next_dynamic::next_dynamic_transform,
+    next_font_google::transform::next_font_google_transform,
     next_import_map::{
         get_next_client_fallback_import_map, get_next_client_import_map,
         get_next_client_resolved_map,
@@ -94,16 +97,27 @@ pub async fn get_client_module_options_context(
             .await?
             .is_found();
 
+    let mut custom_ecmascript_app_transforms = vec![EcmascriptInputTransform::Custom(
+        next_font_google_transform(),
+    )];
+    if let ContextType::Pages { .. } = *ty {
+        custom_ecmascript_app_transforms.push(EcmascriptInputTransform::Custom(
+            next_dynamic_transform(false),
+        ));
+    }
+
+    let next_config = load_next_config(project_root).await?;
     let module_options_context = ModuleOptionsContext {
         // We don't need to resolve React Refresh for each module. Instead,
         // we try resolve it once at the root and pass down a context to all
         // the modules.
-        enable_emotion: true,
+        enable_emotion: next_config.emotion_transform_config(),
         enable_react_refresh,
-        enable_styled_components: true,
+        enable_styled_components: next_config.styled_components_transform_config(),
         enable_styled_jsx: true,
         enable_typescript_transform: true,
         preset_env_versions: Some(env),
+        custom_ecmascript_app_transforms,
         ..Default::default()
     };
 
@@ -156,12 +170,13 @@ pub fn get_client_asset_context(
 }
 
 #[turbo_tasks::function]
-pub fn get_client_chunking_context(
+pub async fn get_client_chunking_context(
     project_root: FileSystemPathVc,
     server_root: FileSystemPathVc,
     ty: Value<ContextType>,
-) -> ChunkingContextVc {
-    DevChunkingContextVc::builder(
+) -> Result<ChunkingContextVc> {
+    let next_config = load_next_config(project_root).await?;
+    Ok(DevChunkingContextVc::builder(
         project_root,
         server_root,
         match ty.into_value() {
@@ -173,7 +188,8 @@ pub fn get_client_chunking_context(
         get_client_assets_path(server_root, ty),
     )
     .hot_module_replacement()
-    .build()
+    .chunking_config(next_config.chunking_config())
+    .build())
 }
 
 #[turbo_tasks::function]
@@ -183,7 +199,7 @@ pub fn get_client_assets_path(
 ) -> FileSystemPathVc {
     match ty.into_value() {
         ContextType::Pages { .. } | ContextType::App { .. } => {
-            server_root.join("/_next/static/assets")
+            server_root.join("/_next/static/media")
         }
         ContextType::Fallback | ContextType::Other => server_root.join("/_assets"),
     }
@@ -0,0 +1,532 @@
+//! Loading of `next.config.js`. The config is a JavaScript module, which can
+//! export a function or a promise, so it's evaluated by Node.js. Only the
+//! options which can be serialized as JSON are read.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use turbo_tasks::{primitives::StringVc, trace::TraceRawVcs};
+use turbo_tasks_fs::{
+    attach::AttachedFileSystemVc, glob::GlobVc, to_sys_path, FileContent, FileSystemPathVc,
+};
+use turbopack_core::{
+    chunk::config::{ChunkingConfig, ChunkingConfigVc},
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+};
+use turbopack_ecmascript::{
+    EmotionTransformConfig, EmotionTransformConfigVc, StyledComponentsTransformConfig,
+    StyledComponentsTransformConfigVc,
+};
+
+const NEXT_CONFIG_FILES: [&str; 2] = ["next.config.js", "next.config.mjs"];
+
+/// Imports the config, calls it if it's a function, and prints it as JSON.
+/// The routes are returned by async functions, so they are called too.
+const EVALUATE_CONFIG: &str = r#"
+import { pathToFileURL } from "node:url";
+const { default: exported } = await import(pathToFileURL(process.argv[1]).href);
+const config = (await (typeof exported === "function"
+  ? exported("phase-development-server", { defaultConfig: {} })
+  : exported)) ?? {};
+const routes = async (name) =>
+  typeof config[name] === "function" ? await config[name]() : undefined;
+const rewrites = await routes("rewrites");
+process.stdout.write(JSON.stringify({
+  ...config,
+  redirects: await routes("redirects"),
+  rewrites: Array.isArray(rewrites) ? { afterFiles: rewrites } : rewrites,
+  headers: await routes("headers"),
+}));
+"#;
+
+#[turbo_tasks::value(shared)]
+#[derive(Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NextConfig {
+    #[serde(default)]
+    pub compiler: CompilerConfig,
+    #[serde(default)]
+    pub experimental: ExperimentalConfig,
+    pub i18n: Option<I18NConfig>,
+    #[serde(default)]
+    pub redirects: Vec<Redirect>,
+    #[serde(default)]
+    pub rewrites: Rewrites,
+    #[serde(default)]
+    pub headers: Vec<Header>,
+}
+
+/// The `compiler` options of `next.config.js`, which configure the SWC
+/// transforms.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "camelCase")]
+pub struct CompilerConfig {
+    pub emotion: Option<EmotionTransformOptionsOrBool>,
+    pub styled_components: Option<StyledComponentsTransformOptionsOrBool>,
+}
+
+/// The `i18n` options of `next.config.js`, which enable locale-prefixed
+/// routing for the pages directory.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "camelCase")]
+pub struct I18NConfig {
+    pub locales: Vec<String>,
+    pub default_locale: String,
+    #[serde(default)]
+    pub domains: Vec<DomainLocale>,
+    /// Redirects `/` to the locale preferred by the browser, unless it's
+    /// `false`.
+    pub locale_detection: Option<bool>,
+}
+
+/// A domain which serves some locales, and the locale it serves without a
+/// prefix.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "camelCase")]
+pub struct DomainLocale {
+    pub domain: String,
+    pub default_locale: String,
+    #[serde(default)]
+    pub locales: Vec<String>,
+    /// The domain is served over HTTP instead of HTTPS.
+    #[serde(default)]
+    pub http: bool,
+}
+
+/// A condition of a route on the request, e. g. a header which has to be
+/// present. Without a `value`, any value matches. The `value` is a regular
+/// expression, and its named groups are parameters of the route.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RouteHas {
+    Header { key: String, value: Option<String> },
+    Cookie { key: String, value: Option<String> },
+    Query { key: String, value: Option<String> },
+    Host { value: String },
+}
+
+/// An entry of the result of the `redirects` function of `next.config.js`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "camelCase")]
+pub struct Redirect {
+    pub source: String,
+    pub destination: String,
+    #[serde(default)]
+    pub permanent: bool,
+    pub status_code: Option<u16>,
+    #[serde(default)]
+    pub has: Vec<RouteHas>,
+    #[serde(default)]
+    pub missing: Vec<RouteHas>,
+}
+
+/// An entry of the result of the `rewrites` function of `next.config.js`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "camelCase")]
+pub struct Rewrite {
+    pub source: String,
+    pub destination: String,
+    #[serde(default)]
+    pub has: Vec<RouteHas>,
+    #[serde(default)]
+    pub missing: Vec<RouteHas>,
+}
+
+/// The result of the `rewrites` function of `next.config.js`. When it returns
+/// an array, the rewrites are applied after the files.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "camelCase")]
+pub struct Rewrites {
+    /// Applied before the files and pages are matched.
+    #[serde(default)]
+    pub before_files: Vec<Rewrite>,
+    /// Applied when no file or page matches.
+    #[serde(default)]
+    pub after_files: Vec<Rewrite>,
+    /// Applied when nothing else matches, after the `afterFiles` rewrites.
+    #[serde(default)]
+    pub fallback: Vec<Rewrite>,
+}
+
+/// An entry of the result of the `headers` function of `next.config.js`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "camelCase")]
+pub struct Header {
+    pub source: String,
+    pub headers: Vec<HeaderKeyValue>,
+    #[serde(default)]
+    pub has: Vec<RouteHas>,
+    #[serde(default)]
+    pub missing: Vec<RouteHas>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "camelCase")]
+pub struct HeaderKeyValue {
+    pub key: String,
+    pub value: String,
+}
+
+/// The `experimental` options of `next.config.js`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "camelCase")]
+pub struct ExperimentalConfig {
+    /// Loads `next/script` scripts with the `worker` strategy with Partytown.
+    pub next_script_workers: Option<bool>,
+    /// Configures how the client code is split into chunks.
+    pub turbopack_chunking: Option<ChunkingOptions>,
+}
+
+/// The `experimental.turbopackChunking` options of `next.config.js`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkingOptions {
+    /// Places `node_modules` into vendor chunks, which is the default.
+    pub split_vendor: Option<bool>,
+    /// Moves the modules two chunks share into a chunk of their own, when
+    /// there are at least this many.
+    pub min_shared_chunk_items: Option<usize>,
+    /// Globs of paths in the project, whose modules are only placed into
+    /// chunks with modules matching the same glob.
+    #[serde(default)]
+    pub cache_groups: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(untagged)]
+pub enum EmotionTransformOptionsOrBool {
+    Boolean(bool),
+    Options(EmotionTransformOptions),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "camelCase")]
+pub struct EmotionTransformOptions {
+    pub source_map: Option<bool>,
+    pub auto_label: Option<EmotionAutoLabel>,
+    pub label_format: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "kebab-case")]
+pub enum EmotionAutoLabel {
+    DevOnly,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(untagged)]
+pub enum StyledComponentsTransformOptionsOrBool {
+    Boolean(bool),
+    Options(StyledComponentsTransformOptions),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+#[serde(rename_all = "camelCase")]
+pub struct StyledComponentsTransformOptions {
+    pub display_name: Option<bool>,
+    pub ssr: Option<bool>,
+    pub file_name: Option<bool>,
+    pub top_level_import_paths: Option<Vec<String>>,
+    pub meaningless_file_names: Option<Vec<String>>,
+    pub css_prop: Option<bool>,
+    pub namespace: Option<String>,
+}
+
+impl NextConfig {
+    /// The options of the emotion transform, if it's enabled. Labels are
+    /// added by default, as this is a development server.
+    pub fn emotion_transform_config(&self) -> Option<EmotionTransformConfigVc> {
+        let options = match self.compiler.emotion.as_ref()? {
+            EmotionTransformOptionsOrBool::Boolean(false) => return None,
+            EmotionTransformOptionsOrBool::Boolean(true) => EmotionTransformOptions::default(),
+            EmotionTransformOptionsOrBool::Options(options) => options.clone(),
+        };
+        Some(
+            EmotionTransformConfig {
+                sourcemap: Some(options.source_map.unwrap_or(true)),
+                auto_label: Some(!matches!(options.auto_label, Some(EmotionAutoLabel::Never))),
+                label_format: options.label_format,
+            }
+            .cell(),
+        )
+    }
+
+    /// How the client code is split into chunks.
+    pub fn chunking_config(&self) -> ChunkingConfigVc {
+        let options = match &self.experimental.turbopack_chunking {
+            Some(options) => options,
+            None => return ChunkingConfigVc::default(),
+        };
+        let defaults = ChunkingConfig::default();
+        ChunkingConfig {
+            split_vendor: options.split_vendor.unwrap_or(defaults.split_vendor),
+            min_shared_chunk_items: options.min_shared_chunk_items,
+            cache_groups: options
+                .cache_groups
+                .iter()
+                .map(|glob| GlobVc::new(glob))
+                .collect(),
+        }
+        .cell()
+    }
+
+    /// The options of the styled-components transform, if it's enabled.
+    pub fn styled_components_transform_config(&self) -> Option<StyledComponentsTransformConfigVc> {
+        let options = match self.compiler.styled_components.as_ref()? {
+            StyledComponentsTransformOptionsOrBool::Boolean(false) => return None,
+            StyledComponentsTransformOptionsOrBool::Boolean(true) => {
+                StyledComponentsTransformOptions::default()
+            }
+            StyledComponentsTransformOptionsOrBool::Options(options) => options.clone(),
+        };
+        let defaults = StyledComponentsTransformConfig::default();
+        Some(
+            StyledComponentsTransformConfig {
+                display_name: options.display_name.unwrap_or(defaults.display_name),
+                ssr: options.ssr.unwrap_or(defaults.ssr),
+                file_name: options.file_name.unwrap_or(defaults.file_name),
+                top_level_import_paths: options
+                    .top_level_import_paths
+                    .unwrap_or(defaults.top_level_import_paths),
+                meaningless_file_names: options
+                    .meaningless_file_names
+                    .unwrap_or(defaults.meaningless_file_names),
+                css_prop: options.css_prop.unwrap_or(defaults.css_prop),
+                namespace: options.namespace,
+            }
+            .cell(),
+        )
+    }
+}
+
+/// Loads `next.config.js` or `next.config.mjs` of the project. A config which
+/// fails to load is reported as an issue, and the default config is used.
+#[turbo_tasks::function]
+pub async fn load_next_config(project_path: FileSystemPathVc) -> Result<NextConfigVc> {
+    for name in NEXT_CONFIG_FILES {
+        let config_path = project_path.join(name);
+        // Reading the file makes the config depend on it.
+        if !matches!(&*config_path.read().await?, FileContent::Content(_)) {
+            continue;
+        }
+        let result = match disk_path(config_path).await? {
+            Some(sys_path) => evaluate_next_config(&sys_path).await,
+            None => continue,
+        };
+        return Ok(match result {
+            Ok(config) => config.cell(),
+            Err(err) => {
+                NextConfigIssue {
+                    path: config_path,
+                    message: format!("{err:?}"),
+                }
+                .cell()
+                .as_issue()
+                .emit();
+                NextConfig::default().cell()
+            }
+        });
+    }
+    Ok(NextConfig::default().cell())
+}
+
+/// The path of a file on disk. The project is wrapped in a file system to
+/// which the Next.js runtime is attached, so that is looked through.
+async fn disk_path(path: FileSystemPathVc) -> Result<Option<PathBuf>> {
+    let path = match AttachedFileSystemVc::resolve_from(path.fs()).await? {
+        Some(fs) => fs.get_inner_fs_path(path),
+        None => path,
+    };
+    to_sys_path(path).await
+}
+
+async fn evaluate_next_config(sys_path: &Path) -> Result<NextConfig> {
+    let output = Command::new("node")
+        .current_dir(sys_path.parent().context("the config has a directory")?)
+        .args(["--input-type=module", "-e", EVALUATE_CONFIG])
+        .arg(sys_path)
+        .output()
+        .await
+        .context("running node")?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr));
+    }
+    serde_json::from_slice(&output.stdout).context("parsing the evaluated config")
+}
+
+#[turbo_tasks::value(shared)]
+pub struct NextConfigIssue {
+    pub path: FileSystemPathVc,
+    pub message: String,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for NextConfigIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Error.into()
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("config".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Loading the Next.js config failed".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(self.message.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ChunkingOptions, CompilerConfig, EmotionAutoLabel, EmotionTransformOptions,
+        EmotionTransformOptionsOrBool, ExperimentalConfig, Header, HeaderKeyValue, NextConfig,
+        Redirect, Rewrite, Rewrites, RouteHas, StyledComponentsTransformOptionsOrBool,
+    };
+
+    #[test]
+    fn parses_compiler_options() {
+        let config: NextConfig = serde_json::from_str(
+            r#"{
+                "reactStrictMode": true,
+                "experimental": { "nextScriptWorkers": true },
+                "compiler": {
+                    "styledComponents": true,
+                    "emotion": { "autoLabel": "dev-only", "labelFormat": "[local]" }
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.compiler,
+            CompilerConfig {
+                emotion: Some(EmotionTransformOptionsOrBool::Options(
+                    EmotionTransformOptions {
+                        source_map: None,
+                        auto_label: Some(EmotionAutoLabel::DevOnly),
+                        label_format: Some("[local]".to_string()),
+                    }
+                )),
+                styled_components: Some(StyledComponentsTransformOptionsOrBool::Boolean(true)),
+            }
+        );
+        assert_eq!(
+            config.experimental,
+            ExperimentalConfig {
+                next_script_workers: Some(true),
+                turbopack_chunking: None,
+            }
+        );
+
+        let config: NextConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config.compiler, CompilerConfig::default());
+    }
+
+    #[test]
+    fn parses_chunking_options() {
+        let config: NextConfig = serde_json::from_str(
+            r#"{
+                "experimental": {
+                    "turbopackChunking": {
+                        "minSharedChunkItems": 10,
+                        "cacheGroups": ["src/components/**"]
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.experimental.turbopack_chunking,
+            Some(ChunkingOptions {
+                split_vendor: None,
+                min_shared_chunk_items: Some(10),
+                cache_groups: vec!["src/components/**".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn parses_routes() {
+        let config: NextConfig = serde_json::from_str(
+            r#"{
+                "redirects": [
+                    { "source": "/old", "destination": "/new", "permanent": true }
+                ],
+                "rewrites": {
+                    "beforeFiles": [
+                        {
+                            "source": "/:path*",
+                            "destination": "/beta/:path*",
+                            "has": [{ "type": "cookie", "key": "beta" }]
+                        }
+                    ]
+                },
+                "headers": [
+                    {
+                        "source": "/(.*)",
+                        "headers": [{ "key": "x-frame-options", "value": "DENY" }],
+                        "missing": [{ "type": "host", "value": "localhost" }]
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.redirects,
+            vec![Redirect {
+                source: "/old".to_string(),
+                destination: "/new".to_string(),
+                permanent: true,
+                status_code: None,
+                has: Vec::new(),
+                missing: Vec::new(),
+            }]
+        );
+        assert_eq!(
+            config.rewrites,
+            Rewrites {
+                before_files: vec![Rewrite {
+                    source: "/:path*".to_string(),
+                    destination: "/beta/:path*".to_string(),
+                    has: vec![RouteHas::Cookie {
+                        key: "beta".to_string(),
+                        value: None,
+                    }],
+                    missing: Vec::new(),
+                }],
+                after_files: Vec::new(),
+                fallback: Vec::new(),
+            }
+        );
+        assert_eq!(
+            config.headers,
+            vec![Header {
+                source: "/(.*)".to_string(),
+                headers: vec![HeaderKeyValue {
+                    key: "x-frame-options".to_string(),
+                    value: "DENY".to_string(),
+                }],
+                has: Vec::new(),
+                missing: vec![RouteHas::Host {
+                    value: "localhost".to_string(),
+                }],
+            }]
+        );
+    }
+}
@@ -0,0 +1,415 @@
+//! Support for `next/dynamic`. Modules loaded by `dynamic(() => import(...))`
+//! are split into their own chunks by the `import()` already, like the modules
+//! of `React.lazy`. What's left is to tell the server render which chunks a
+//! page needs for its dynamic components, so they are loaded before hydration.
+//!
+//! Each `dynamic` call is given `loadableGenerated: { modules: [key] }`, where
+//! `key` identifies the imported module. While rendering on the server,
+//! `next/dynamic` reports the keys of the rendered components, and the chunks
+//! of these are looked up in the loadable manifest. The server registers the
+//! client chunks of each imported module in that manifest, which it imports
+//! with the `next-client-chunks` transition.
+//!
+//! With `ssr: false`, the component is only rendered on the client, so the
+//! server doesn't import the module at all.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+use swc_core::{
+    common::{Mark, DUMMY_SP},
+    ecma::{
+        ast::{
+            ArrayLit, ArrowExpr, BlockStmtOrExpr, CallExpr, Callee, Expr, ExprOrSpread, ExprStmt,
+            Id, Ident, ImportDecl, ImportNamedSpecifier, ImportSpecifier, KeyValueProp, Lit,
+            Module, ModuleDecl, ModuleExportName, ModuleItem, Null, ObjectLit, Program, Prop,
+            PropName, PropOrSpread, SpreadElement, Stmt, Str,
+        },
+        visit::{Visit, VisitMut, VisitMutWith, VisitWith},
+    },
+};
+use turbopack_ecmascript::{CustomTransformVc, CustomTransformer, TransformContext};
+
+use crate::embed_js::VIRTUAL_PACKAGE_NAME;
+
+/// Compiles the `next/dynamic` calls of a module, see [NextDynamicTransformer].
+#[turbo_tasks::function]
+pub fn next_dynamic_transform(is_server: bool) -> CustomTransformVc {
+    CustomTransformVc::new(NextDynamicTransformer { is_server })
+}
+
+/// Adds the `loadableGenerated` option to `next/dynamic` calls. On the server,
+/// it also registers the client chunks of the imported modules in the
+/// loadable manifest, and removes the import of components with `ssr: false`.
+pub struct NextDynamicTransformer {
+    is_server: bool,
+}
+
+impl CustomTransformer for NextDynamicTransformer {
+    fn transform(&self, program: &mut Program, context: &TransformContext<'_>) -> Result<()> {
+        if let Program::Module(module) = program {
+            transform_module(
+                module,
+                context.file_path_str,
+                self.is_server,
+                context.top_level_mark,
+            );
+        }
+        Ok(())
+    }
+}
+
+fn transform_module(module: &mut Module, file_path: &str, is_server: bool, top_level_mark: Mark) {
+    let mut dynamic_functions = HashSet::new();
+    for item in &module.body {
+        if let ModuleItem::ModuleDecl(ModuleDecl::Import(import)) = item {
+            if &*import.src.value == "next/dynamic" {
+                for specifier in &import.specifiers {
+                    if let ImportSpecifier::Default(default) = specifier {
+                        dynamic_functions.insert(default.local.to_id());
+                    }
+                }
+            }
+        }
+    }
+    if dynamic_functions.is_empty() {
+        return;
+    }
+
+    let mut compiler = DynamicCallCompiler {
+        dynamic_functions,
+        file_path,
+        is_server,
+        server_imports: Vec::new(),
+    };
+    module.visit_mut_with(&mut compiler);
+    if compiler.server_imports.is_empty() {
+        return;
+    }
+
+    let ident = |name: &str| Ident::new(name.into(), DUMMY_SP.apply_mark(top_level_mark));
+    let register = ident("__next_register_loadable__");
+    let mut items = vec![named_import(
+        register.clone(),
+        "registerLoadable",
+        format!("{VIRTUAL_PACKAGE_NAME}/internal/loadable-manifest"),
+    )];
+    for (index, (key, request)) in compiler.server_imports.into_iter().enumerate() {
+        let chunks = ident(&format!("__next_dynamic_chunks_{index}__"));
+        items.push(statement(string(
+            "TURBOPACK { transition: next-client-chunks }",
+        )));
+        items.push(named_import(chunks.clone(), "chunks", request));
+        items.push(statement(Expr::Call(CallExpr {
+            span: DUMMY_SP,
+            callee: Callee::Expr(Box::new(Expr::Ident(register.clone()))),
+            args: vec![argument(string(&key)), argument(Expr::Ident(chunks))],
+            type_args: None,
+        })));
+    }
+    module.body.splice(0..0, items);
+}
+
+/// Compiles the calls of the default export of `next/dynamic`.
+struct DynamicCallCompiler<'a> {
+    dynamic_functions: HashSet<Id>,
+    file_path: &'a str,
+    is_server: bool,
+    /// The keys and requests of the modules which the server needs the client
+    /// chunks of.
+    server_imports: Vec<(String, String)>,
+}
+
+impl VisitMut for DynamicCallCompiler<'_> {
+    fn visit_mut_call_expr(&mut self, call: &mut CallExpr) {
+        call.visit_mut_children_with(self);
+
+        let is_dynamic_call = match &call.callee {
+            Callee::Expr(callee) => match &**callee {
+                Expr::Ident(ident) => self.dynamic_functions.contains(&ident.to_id()),
+                _ => false,
+            },
+            _ => false,
+        };
+        if !is_dynamic_call {
+            return;
+        }
+        // Only loaders which import a module by a literal request can be
+        // resolved at build time.
+        let request = match call.args.first() {
+            Some(loader) if loader.spread.is_none() => match imported_request(&loader.expr) {
+                Some(request) => request,
+                None => return,
+            },
+            _ => return,
+        };
+        let key = format!("{} -> {request}", self.file_path);
+        let loadable_generated = PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+            key: PropName::Ident(Ident::new("loadableGenerated".into(), DUMMY_SP)),
+            value: Box::new(Expr::Object(ObjectLit {
+                span: DUMMY_SP,
+                props: vec![PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+                    key: PropName::Ident(Ident::new("modules".into(), DUMMY_SP)),
+                    value: Box::new(Expr::Array(ArrayLit {
+                        span: DUMMY_SP,
+                        elems: vec![Some(argument(string(&key)))],
+                    })),
+                })))],
+            })),
+        })));
+
+        match call.args.get_mut(1) {
+            Some(options) if options.spread.is_none() => {
+                let ssr_disabled = is_ssr_disabled(&options.expr);
+                if !matches!(&*options.expr, Expr::Object(_)) {
+                    // Spread options which aren't an object literal.
+                    let expr = std::mem::replace(&mut options.expr, Box::new(null()));
+                    options.expr = Box::new(Expr::Object(ObjectLit {
+                        span: DUMMY_SP,
+                        props: vec![PropOrSpread::Spread(SpreadElement {
+                            dot3_token: DUMMY_SP,
+                            expr,
+                        })],
+                    }));
+                }
+                if let Expr::Object(object) = &mut *options.expr {
+                    object.props.push(loadable_generated);
+                }
+                if self.is_server && ssr_disabled {
+                    // `next/dynamic` never calls the loader on the server then.
+                    call.args[0] = argument(Expr::Arrow(ArrowExpr {
+                        span: DUMMY_SP,
+                        params: Vec::new(),
+                        body: BlockStmtOrExpr::Expr(Box::new(null())),
+                        is_async: true,
+                        is_generator: false,
+                        type_params: None,
+                        return_type: None,
+                    }));
+                    return;
+                }
+            }
+            Some(_) => return,
+            None => call.args.push(argument(Expr::Object(ObjectLit {
+                span: DUMMY_SP,
+                props: vec![loadable_generated],
+            }))),
+        }
+        if self.is_server {
+            self.server_imports.push((key, request));
+        }
+    }
+}
+
+/// Returns the request of the first `import("...")` in a loader function.
+fn imported_request(loader: &Expr) -> Option<String> {
+    struct ImportFinder(Option<String>);
+
+    impl Visit for ImportFinder {
+        fn visit_call_expr(&mut self, call: &CallExpr) {
+            if self.0.is_some() {
+                return;
+            }
+            if let Callee::Import(_) = call.callee {
+                if let Some(ExprOrSpread { spread: None, expr }) = call.args.first() {
+                    if let Expr::Lit(Lit::Str(request)) = &**expr {
+                        self.0 = Some(request.value.to_string());
+                        return;
+                    }
+                }
+            }
+            call.visit_children_with(self);
+        }
+    }
+
+    if !matches!(loader, Expr::Arrow(_) | Expr::Fn(_)) {
+        return None;
+    }
+    let mut finder = ImportFinder(None);
+    loader.visit_with(&mut finder);
+    finder.0
+}
+
+/// Whether the options are an object literal with `ssr: false`.
+fn is_ssr_disabled(options: &Expr) -> bool {
+    let object = match options {
+        Expr::Object(object) => object,
+        _ => return false,
+    };
+    object.props.iter().any(|prop| match prop {
+        PropOrSpread::Prop(prop) => match &**prop {
+            Prop::KeyValue(KeyValueProp { key, value }) => {
+                let is_ssr = match key {
+                    PropName::Ident(ident) => &*ident.sym == "ssr",
+                    PropName::Str(str) => &*str.value == "ssr",
+                    _ => false,
+                };
+                is_ssr && matches!(&**value, Expr::Lit(Lit::Bool(value)) if !value.value)
+            }
+            _ => false,
+        },
+        PropOrSpread::Spread(_) => false,
+    })
+}
+
+fn string(value: &str) -> Expr {
+    Expr::Lit(Lit::Str(Str {
+        span: DUMMY_SP,
+        value: value.into(),
+        raw: None,
+    }))
+}
+
+fn null() -> Expr {
+    Expr::Lit(Lit::Null(Null { span: DUMMY_SP }))
+}
+
+fn argument(expr: Expr) -> ExprOrSpread {
+    ExprOrSpread {
+        spread: None,
+        expr: Box::new(expr),
+    }
+}
+
+fn statement(expr: Expr) -> ModuleItem {
+    ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+        span: DUMMY_SP,
+        expr: Box::new(expr),
+    }))
+}
+
+/// Returns `import { imported as local } from "src"`.
+fn named_import(local: Ident, imported: &str, src: String) -> ModuleItem {
+    ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl {
+        specifiers: vec![ImportSpecifier::Named(ImportNamedSpecifier {
+            local,
+            imported: Some(ModuleExportName::Ident(Ident::new(
+                imported.into(),
+                DUMMY_SP,
+            ))),
+            is_type_only: false,
+            span: DUMMY_SP,
+        })],
+        src: Box::new(src.into()),
+        type_only: false,
+        asserts: None,
+        span: DUMMY_SP,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{FileName, Mark, SourceMap, GLOBALS},
+        ecma::{
+            ast::{EsVersion, Expr, ModuleDecl, ModuleItem, Prop, PropOrSpread, Stmt},
+            parser::{parse_file_as_module, EsConfig, Syntax},
+        },
+    };
+
+    use super::transform_module;
+
+    /// Returns the requests imported by the server and the options of the
+    /// `dynamic` calls, as the names of their properties.
+    fn transform(code: &str, is_server: bool) -> (Vec<String>, Vec<Vec<String>>) {
+        GLOBALS.set(&Default::default(), || {
+            let source_map = SourceMap::default();
+            let file = source_map.new_source_file(FileName::Anon, code.to_string());
+            let mut module = parse_file_as_module(
+                &file,
+                Syntax::Es(EsConfig {
+                    jsx: true,
+                    ..Default::default()
+                }),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .unwrap();
+            transform_module(&mut module, "pages/index.js", is_server, Mark::new());
+
+            let mut imports = Vec::new();
+            let mut options = Vec::new();
+            for item in &module.body {
+                match item {
+                    ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => {
+                        imports.push(import.src.value.to_string())
+                    }
+                    ModuleItem::Stmt(Stmt::Decl(decl)) => {
+                        let var = decl.as_var().unwrap();
+                        let call = var.decls[0].init.as_ref().unwrap().as_call().unwrap();
+                        let object = call.args[1].expr.as_object().unwrap();
+                        options.push(
+                            object
+                                .props
+                                .iter()
+                                .map(|prop| match prop {
+                                    PropOrSpread::Prop(prop) => match &**prop {
+                                        Prop::KeyValue(key_value) => {
+                                            key_value.key.as_ident().unwrap().sym.to_string()
+                                        }
+                                        _ => unreachable!(),
+                                    },
+                                    PropOrSpread::Spread(_) => "...".to_string(),
+                                })
+                                .collect(),
+                        );
+                        if let Expr::Arrow(arrow) = &*call.args[0].expr {
+                            if arrow.is_async {
+                                options
+                                    .last_mut()
+                                    .unwrap()
+                                    .push("removed loader".to_string());
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            (imports, options)
+        })
+    }
+
+    const CODE: &str = r#"
+        import dynamic from "next/dynamic";
+        const Chart = dynamic(() => import("../components/chart"));
+        const Map = dynamic(() => import("../components/map"), { ssr: false });
+        const Editor = dynamic(() => import("../components/editor"), options);
+    "#;
+
+    #[test]
+    fn compiles_dynamic_calls_on_server() {
+        let (imports, options) = transform(CODE, true);
+        assert_eq!(
+            imports,
+            vec![
+                "@vercel/turbopack-next/internal/loadable-manifest",
+                "../components/chart",
+                "../components/editor",
+                "next/dynamic",
+            ]
+        );
+        assert_eq!(
+            options,
+            vec![
+                vec!["loadableGenerated"],
+                vec!["ssr", "loadableGenerated", "removed loader"],
+                vec!["...", "loadableGenerated"],
+            ]
+        );
+    }
+
+    #[test]
+    fn compiles_dynamic_calls_on_client() {
+        let (imports, options) = transform(CODE, false);
+        assert_eq!(imports, vec!["next/dynamic"]);
+        assert_eq!(
+            options,
+            vec![
+                vec!["loadableGenerated"],
+                vec!["ssr", "loadableGenerated"],
+                vec!["...", "loadableGenerated"],
+            ]
+        );
+    }
+}
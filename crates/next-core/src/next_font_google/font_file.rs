@@ -0,0 +1,151 @@
+use anyhow::{Context, Result};
+use turbo_tasks::{primitives::StringVc, Value};
+use turbo_tasks_fetch::{fetch, FetchHeaders};
+use turbo_tasks_fs::{File, FileContent, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetContentVc},
+    issue::IssueSeverity,
+    reference::AssetReferencesVc,
+    resolve::{
+        options::{
+            ImportMapResult, ImportMapResultVc, ImportMapping, ImportMappingReplacement,
+            ImportMappingReplacementVc, ImportMappingVc,
+        },
+        parse::RequestVc,
+        ResolveResult,
+    },
+};
+
+/// The host which serves the font files referenced by Google Fonts
+/// stylesheets.
+const GOOGLE_FONTS_FILE_URL: &str = "https://fonts.gstatic.com/";
+
+/// The prefix of the paths by which rewritten stylesheets reference font
+/// files.
+pub const FONT_FILE_PREFIX: &str = "/_next/static/media/";
+
+/// A font file hosted by Google Fonts, which is downloaded at build time. It
+/// is a static asset, so it's emitted and served with the other static assets.
+#[turbo_tasks::value]
+pub struct FontFileAsset {
+    url: StringVc,
+    path: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl FontFileAssetVc {
+    #[turbo_tasks::function]
+    pub async fn new(url: StringVc, project_path: FileSystemPathVc) -> Result<Self> {
+        let url_value = url.await?;
+        let path = font_file_path(&url_value)
+            .with_context(|| format!("{} is not a Google Fonts font file", url_value))?;
+        Ok(Self::cell(FontFileAsset {
+            url,
+            path: project_path.join(path.trim_start_matches('/')),
+        }))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for FontFileAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let response = match &*fetch(self.url, Value::new(FetchHeaders::default())).await? {
+            Ok(response) => *response,
+            Err(error) => {
+                error
+                    .to_issue(IssueSeverity::Error.into(), self.path)
+                    .as_issue()
+                    .emit();
+                return Ok(FileContent::NotFound.into());
+            }
+        };
+        let body = response.await?.body.await?;
+        Ok(FileContent::Content(File::from(body.0.clone())).into())
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+/// Resolves the font file paths of rewritten stylesheets to [FontFileAsset]s,
+/// so the font files are part of the asset graph.
+#[turbo_tasks::value(shared)]
+pub struct NextFontGoogleFontFileReplacer {
+    project_path: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl NextFontGoogleFontFileReplacerVc {
+    #[turbo_tasks::function]
+    pub fn new(project_path: FileSystemPathVc) -> Self {
+        Self::cell(NextFontGoogleFontFileReplacer { project_path })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ImportMappingReplacement for NextFontGoogleFontFileReplacer {
+    #[turbo_tasks::function]
+    fn replace(self_vc: NextFontGoogleFontFileReplacerVc, _capture: &str) -> ImportMappingVc {
+        // The whole request is needed to restore the URL, which is only
+        // available in `result`.
+        ImportMapping::Dynamic(self_vc.into()).into()
+    }
+
+    #[turbo_tasks::function]
+    async fn result(
+        &self,
+        _context: FileSystemPathVc,
+        request: RequestVc,
+    ) -> Result<ImportMapResultVc> {
+        let url = match request.await?.request().as_deref().and_then(font_file_url) {
+            Some(url) => url,
+            None => return Ok(ImportMapResult::NoEntry.into()),
+        };
+        Ok(ImportMapResult::Result(
+            ResolveResult::Single(
+                FontFileAssetVc::new(StringVc::cell(url), self.project_path).into(),
+                vec![],
+            )
+            .into(),
+        )
+        .into())
+    }
+}
+
+/// Returns the path by which a rewritten stylesheet references the font file
+/// at `url`, or `None` if it isn't hosted by Google Fonts.
+///
+/// Google Fonts versions the URLs of font files, so the path changes whenever
+/// the content does.
+pub fn font_file_path(url: &str) -> Option<String> {
+    url.strip_prefix(GOOGLE_FONTS_FILE_URL)
+        .map(|path| format!("{FONT_FILE_PREFIX}{path}"))
+}
+
+/// The inverse of [font_file_path].
+fn font_file_url(path: &str) -> Option<String> {
+    path.strip_prefix(FONT_FILE_PREFIX)
+        .map(|path| format!("{GOOGLE_FONTS_FILE_URL}{path}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{font_file_path, font_file_url};
+
+    #[test]
+    fn test_font_file_path() {
+        let url = "https://fonts.gstatic.com/s/inter/v12/latin.woff2";
+        let path = font_file_path(url).unwrap();
+        assert_eq!(path, "/_next/static/media/s/inter/v12/latin.woff2");
+        assert_eq!(font_file_url(&path).unwrap(), url);
+        assert_eq!(font_file_path("https://example.com/font.woff2"), None);
+    }
+}
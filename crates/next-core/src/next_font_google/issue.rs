@@ -0,0 +1,41 @@
+use turbo_tasks::primitives::StringVc;
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::issue::{Issue, IssueSourceVc, IssueVc, OptionIssueSourceVc};
+
+/// An invalid call of a `@next/font/google` font function.
+#[turbo_tasks::value(shared)]
+pub struct NextFontIssue {
+    pub path: FileSystemPathVc,
+    pub title: StringVc,
+    pub description: StringVc,
+    /// The call, which is shown as a code frame.
+    pub source: IssueSourceVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for NextFontIssue {
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("code generation".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        self.title
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        self.description
+    }
+
+    #[turbo_tasks::function]
+    fn source(&self) -> OptionIssueSourceVc {
+        OptionIssueSourceVc::cell(Some(self.source))
+    }
+}
@@ -0,0 +1,313 @@
+//! Support for `@next/font/google`. Fonts are downloaded from Google Fonts at
+//! build time and served with the other static assets, so the browser never
+//! requests them from Google.
+//!
+//! A font function call like `Inter({ subsets: ["latin"] })` is compiled to an
+//! import of `@next/font/google/target.css?{request}` by [transform], where
+//! `request` is the JSON of a [NextFontRequest]. It resolves to a generated
+//! JavaScript module, which exports the `className`, `style` and `variable` of
+//! the font. These are backed by a generated CSS module with the `@font-face`
+//! rules of the font.
+
+use std::fmt::Write;
+
+use anyhow::{Context, Result};
+use turbo_tasks::Value;
+use turbo_tasks_fs::{File, FileContent, FileSystemPathVc};
+use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
+use turbopack_core::{
+    asset::AssetVc,
+    resolve::{
+        options::{
+            ImportMapResult, ImportMapResultVc, ImportMapping, ImportMappingReplacement,
+            ImportMappingReplacementVc, ImportMappingVc,
+        },
+        parse::RequestVc,
+        ResolveResult,
+    },
+    virtual_asset::VirtualAssetVc,
+};
+use turbopack_ecmascript::utils::stringify_str;
+
+use self::{
+    options::{FontWeights, NextFontGoogleOptions},
+    request::{options_from_request, NextFontRequest},
+    stylesheet::get_stylesheet,
+};
+
+pub mod font_file;
+pub mod issue;
+pub mod options;
+pub mod request;
+pub mod stylesheet;
+pub mod transform;
+
+/// The request of the JavaScript module of a font, followed by `?{request}`.
+pub const FONT_MODULE_REQUEST: &str = "@next/font/google/target.css";
+
+/// The request of the CSS module of a font, followed by `?{request}`. It's
+/// only imported by the JavaScript module.
+pub const FONT_CSS_MODULE_REQUEST: &str = "@next/font/google/cssmodule.module.css";
+
+/// The directory of the project in which the generated modules are placed.
+/// They are never written to disk, but their paths identify them, e. g. for
+/// hot module replacement.
+const FONT_MODULE_DIR: &str = "@next/font/google";
+
+/// Resolves [FONT_MODULE_REQUEST]s to the JavaScript module of the font.
+#[turbo_tasks::value(shared)]
+pub struct NextFontGoogleReplacer {
+    project_path: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl NextFontGoogleReplacerVc {
+    #[turbo_tasks::function]
+    pub fn new(project_path: FileSystemPathVc) -> Self {
+        Self::cell(NextFontGoogleReplacer { project_path })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ImportMappingReplacement for NextFontGoogleReplacer {
+    #[turbo_tasks::function]
+    fn replace(self_vc: NextFontGoogleReplacerVc, _capture: &str) -> ImportMappingVc {
+        ImportMapping::Dynamic(self_vc.into()).into()
+    }
+
+    #[turbo_tasks::function]
+    async fn result(
+        &self,
+        _context: FileSystemPathVc,
+        request: RequestVc,
+    ) -> Result<ImportMapResultVc> {
+        let query = match request_query(request).await? {
+            Some(query) => query,
+            None => return Ok(ImportMapResult::NoEntry.into()),
+        };
+        let options = options_from_request(&NextFontRequest::parse(&query)?)?;
+        Ok(ImportMapResult::Result(
+            ResolveResult::Single(font_module(self.project_path, Value::new(options)), vec![])
+                .into(),
+        )
+        .into())
+    }
+}
+
+/// Resolves [FONT_CSS_MODULE_REQUEST]s to the CSS module of the font.
+#[turbo_tasks::value(shared)]
+pub struct NextFontGoogleCssModuleReplacer {
+    project_path: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl NextFontGoogleCssModuleReplacerVc {
+    #[turbo_tasks::function]
+    pub fn new(project_path: FileSystemPathVc) -> Self {
+        Self::cell(NextFontGoogleCssModuleReplacer { project_path })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ImportMappingReplacement for NextFontGoogleCssModuleReplacer {
+    #[turbo_tasks::function]
+    fn replace(self_vc: NextFontGoogleCssModuleReplacerVc, _capture: &str) -> ImportMappingVc {
+        ImportMapping::Dynamic(self_vc.into()).into()
+    }
+
+    #[turbo_tasks::function]
+    async fn result(
+        &self,
+        _context: FileSystemPathVc,
+        request: RequestVc,
+    ) -> Result<ImportMapResultVc> {
+        let query = match request_query(request).await? {
+            Some(query) => query,
+            None => return Ok(ImportMapResult::NoEntry.into()),
+        };
+        // The JavaScript module passes the options, which are already
+        // validated and canonical.
+        let options: NextFontGoogleOptions =
+            serde_json::from_str(&query).context("invalid @next/font CSS module request")?;
+        Ok(ImportMapResult::Result(
+            ResolveResult::Single(
+                font_css_module(self.project_path, Value::new(options)),
+                vec![],
+            )
+            .into(),
+        )
+        .into())
+    }
+}
+
+/// Returns the part of the request after the `?`.
+async fn request_query(request: RequestVc) -> Result<Option<String>> {
+    Ok(request
+        .await?
+        .request()
+        .and_then(|request| Some(request.split_once('?')?.1.to_string())))
+}
+
+/// Creates the JavaScript module of the font. The module is keyed by the
+/// canonical options, so all requests of the same font share it.
+#[turbo_tasks::function]
+fn font_module(project_path: FileSystemPathVc, options: Value<NextFontGoogleOptions>) -> AssetVc {
+    let options = options.into_value();
+    let path = project_path.join(&format!("{FONT_MODULE_DIR}/{}.js", font_id(&options)));
+    let code = font_module_code(&options);
+    VirtualAssetVc::new(path, FileContent::Content(File::from(code)).into()).into()
+}
+
+/// Creates the CSS module of the font, see [font_module].
+#[turbo_tasks::function]
+async fn font_css_module(
+    project_path: FileSystemPathVc,
+    options: Value<NextFontGoogleOptions>,
+) -> Result<AssetVc> {
+    let path = project_path.join(&format!(
+        "{FONT_MODULE_DIR}/{}.module.css",
+        font_id(&options)
+    ));
+    let stylesheet = get_stylesheet(options.clone(), path).await?;
+    let css = font_css(&options, &stylesheet);
+    Ok(VirtualAssetVc::new(path, FileContent::Content(File::from(css)).into()).into())
+}
+
+/// Returns a name for the modules of the font, which is unique for the
+/// options.
+fn font_id(options: &NextFontGoogleOptions) -> String {
+    format!(
+        "{}_{}",
+        options.font_family.to_lowercase().replace(' ', "_"),
+        encode_hex(hash_xxh3_hash64(options_json(options).as_bytes()))
+    )
+}
+
+fn options_json(options: &NextFontGoogleOptions) -> String {
+    serde_json::to_string(options).expect("font options are serializable")
+}
+
+/// Returns the `font-family` of elements which use the font.
+fn font_family(options: &NextFontGoogleOptions) -> String {
+    let mut font_family = format!("'{}'", options.font_family);
+    for fallback in options.fallback.iter().flatten() {
+        write!(font_family, ", {fallback}").unwrap();
+    }
+    font_family
+}
+
+/// Returns the weight of the font if it's the only one.
+fn single_weight(options: &NextFontGoogleOptions) -> Option<&str> {
+    match &options.weights {
+        FontWeights::Fixed(weights) if weights.len() == 1 => Some(&weights[0]),
+        _ => None,
+    }
+}
+
+/// Returns the style of the font if it's the only one.
+fn single_style(options: &NextFontGoogleOptions) -> Option<&str> {
+    match &*options.styles {
+        [style] => Some(style),
+        _ => None,
+    }
+}
+
+/// Returns the code of the CSS module of the font. Its `className` class
+/// applies the font, and its `variable` class declares the CSS variable.
+fn font_css(options: &NextFontGoogleOptions, stylesheet: &str) -> String {
+    let font_family = font_family(options);
+    let mut css = stylesheet.to_string();
+    writeln!(css, "\n.className {{\n  font-family: {font_family};").unwrap();
+    if let Some(weight) = single_weight(options) {
+        writeln!(css, "  font-weight: {weight};").unwrap();
+    }
+    if let Some(style) = single_style(options) {
+        writeln!(css, "  font-style: {style};").unwrap();
+    }
+    css.push_str("}\n");
+    if let Some(variable) = &options.variable {
+        writeln!(css, "\n.variable {{\n  {variable}: {font_family};\n}}").unwrap();
+    }
+    css
+}
+
+/// Returns the code of the JavaScript module of the font.
+fn font_module_code(options: &NextFontGoogleOptions) -> String {
+    let mut style = format!("fontFamily: {}", stringify_str(&font_family(options)));
+    if let Some(weight) = single_weight(options) {
+        write!(style, ", fontWeight: {weight}").unwrap();
+    }
+    if let Some(style_value) = single_style(options) {
+        write!(style, ", fontStyle: {}", stringify_str(style_value)).unwrap();
+    }
+    let variable = if options.variable.is_some() {
+        "\n  variable: cssModule.variable,"
+    } else {
+        ""
+    };
+    format!(
+        r#"import cssModule from {css_module};
+
+export default {{
+  className: cssModule.className,
+  style: {{ {style} }},{variable}
+}};
+"#,
+        css_module = stringify_str(&format!(
+            "{FONT_CSS_MODULE_REQUEST}?{}",
+            options_json(options)
+        )),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{font_css, font_module_code};
+    use crate::next_font_google::options::{FontWeights, NextFontGoogleOptions};
+
+    fn options() -> NextFontGoogleOptions {
+        NextFontGoogleOptions {
+            font_family: "Inter".to_string(),
+            weights: FontWeights::Fixed(vec!["400".to_string()]),
+            styles: vec!["normal".to_string()],
+            display: "swap".to_string(),
+            preload: true,
+            fallback: Some(vec!["system-ui".to_string()]),
+            variable: Some("--font-inter".to_string()),
+            subsets: Some(vec!["latin".to_string()]),
+            text: None,
+        }
+    }
+
+    #[test]
+    fn test_font_css() {
+        assert_eq!(
+            font_css(&options(), "@font-face {}\n"),
+            r#"@font-face {}
+
+.className {
+  font-family: 'Inter', system-ui;
+  font-weight: 400;
+  font-style: normal;
+}
+
+.variable {
+  --font-inter: 'Inter', system-ui;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_font_module_code() {
+        let code = font_module_code(&options());
+        assert!(code.starts_with(
+            r#"import cssModule from "@next/font/google/cssmodule.module.css?{\"font_family\":\"Inter\","#
+        ));
+        assert!(code.contains(
+            "style: { fontFamily: \"'Inter', system-ui\", fontWeight: 400, fontStyle: \"normal\" \
+             },"
+        ));
+        assert!(code.contains("variable: cssModule.variable,"));
+    }
+}
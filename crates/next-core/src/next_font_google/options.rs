@@ -0,0 +1,176 @@
+use std::fmt::Write;
+
+use url::form_urlencoded;
+
+/// The weights of a font which are requested from Google Fonts.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Clone, Debug, PartialOrd, Ord, Hash)]
+pub enum FontWeights {
+    /// The full weight range of a variable font.
+    Variable,
+    /// Static weights, e. g. `400` or `700`.
+    Fixed(Vec<String>),
+}
+
+/// The options of a `@next/font/google` font, after they have been validated.
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Clone, Debug, PartialOrd, Ord, Hash)]
+pub struct NextFontGoogleOptions {
+    /// The family as it is named by Google Fonts, e. g. `Roboto Mono`.
+    pub font_family: String,
+    pub weights: FontWeights,
+    /// `normal` and/or `italic`.
+    pub styles: Vec<String>,
+    /// The value of the `font-display` descriptor.
+    pub display: String,
+    /// Whether the font files are preloaded.
+    pub preload: bool,
+    pub fallback: Option<Vec<String>>,
+    /// The name of the CSS variable which is set to the font family.
+    pub variable: Option<String>,
+    pub subsets: Option<Vec<String>>,
+    /// Only the glyphs of these characters are requested, which results in
+    /// much smaller font files.
+    pub text: Option<String>,
+}
+
+impl NextFontGoogleOptions {
+    /// Brings options which request the same font into the same order, so
+    /// they are equal and the font is only fetched and generated once, no
+    /// matter how many modules use it.
+    pub fn canonicalize(mut self) -> Self {
+        if let FontWeights::Fixed(weights) = &mut self.weights {
+            weights.sort_by_key(|weight| weight.parse::<u32>().unwrap_or(u32::MAX));
+            weights.dedup();
+        }
+        self.styles.sort_by_key(|style| style != "normal");
+        self.styles.dedup();
+        if let Some(subsets) = &mut self.subsets {
+            subsets.sort();
+            subsets.dedup();
+        }
+        if let Some(text) = &mut self.text {
+            let mut chars: Vec<char> = text.chars().collect();
+            chars.sort_unstable();
+            chars.dedup();
+            *text = chars.into_iter().collect();
+        }
+        self
+    }
+}
+
+/// The [weight range](https://developers.google.com/fonts/docs/css2#axis_ranges)
+/// requested for [FontWeights::Variable].
+const VARIABLE_WEIGHT_RANGE: &str = "100..900";
+
+const GOOGLE_FONTS_STYLESHEET_URL: &str = "https://fonts.googleapis.com/css2";
+
+/// Returns the URL of the [CSS2 API](https://developers.google.com/fonts/docs/css2)
+/// stylesheet for the font.
+pub fn get_stylesheet_url(options: &NextFontGoogleOptions) -> String {
+    let weights = match &options.weights {
+        FontWeights::Variable => vec![VARIABLE_WEIGHT_RANGE],
+        // The API rejects unsorted axis values, canonical options are sorted.
+        FontWeights::Fixed(weights) => weights.iter().map(String::as_str).collect(),
+    };
+
+    let mut url = format!(
+        "{GOOGLE_FONTS_STYLESHEET_URL}?family={}",
+        options.font_family.replace(' ', "+")
+    );
+    if options.styles.iter().any(|style| style == "italic") {
+        url.push_str(":ital,wght@");
+        let italics: &[u8] = if options.styles.iter().any(|style| style == "normal") {
+            &[0, 1]
+        } else {
+            &[1]
+        };
+        let tuples: Vec<String> = italics
+            .iter()
+            .flat_map(|ital| weights.iter().map(move |weight| format!("{ital},{weight}")))
+            .collect();
+        url.push_str(&tuples.join(";"));
+    } else {
+        url.push_str(":wght@");
+        url.push_str(&weights.join(";"));
+    }
+    write!(url, "&display={}", options.display).unwrap();
+    if let Some(text) = &options.text {
+        url.push_str("&text=");
+        url.extend(form_urlencoded::byte_serialize(text.as_bytes()));
+    }
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_stylesheet_url, FontWeights, NextFontGoogleOptions};
+
+    fn options(weights: FontWeights, styles: &[&str]) -> NextFontGoogleOptions {
+        NextFontGoogleOptions {
+            font_family: "Roboto Mono".to_string(),
+            weights,
+            styles: styles.iter().map(|style| style.to_string()).collect(),
+            display: "swap".to_string(),
+            preload: true,
+            fallback: None,
+            variable: None,
+            subsets: None,
+            text: None,
+        }
+    }
+
+    #[test]
+    fn test_stylesheet_url() {
+        assert_eq!(
+            get_stylesheet_url(
+                &options(
+                    FontWeights::Fixed(vec!["700".to_string(), "400".to_string()]),
+                    &["normal"]
+                )
+                .canonicalize()
+            ),
+            "https://fonts.googleapis.com/css2?family=Roboto+Mono:wght@400;700&display=swap"
+        );
+        assert_eq!(
+            get_stylesheet_url(&options(FontWeights::Variable, &["italic", "normal"]).canonicalize()),
+            "https://fonts.googleapis.com/css2?family=Roboto+Mono:ital,wght@0,100..900;1,100..900&display=swap"
+        );
+    }
+
+    #[test]
+    fn test_stylesheet_url_with_text() {
+        let options = NextFontGoogleOptions {
+            text: Some("Hello, World!".to_string()),
+            ..options(FontWeights::Fixed(vec!["400".to_string()]), &["normal"])
+        };
+        assert_eq!(
+            get_stylesheet_url(&options.canonicalize()),
+            "https://fonts.googleapis.com/css2?family=Roboto+Mono:wght@400&display=swap&text=+%21%2CHWdelor"
+        );
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let a = NextFontGoogleOptions {
+            subsets: Some(vec!["latin-ext".to_string(), "latin".to_string()]),
+            ..options(
+                FontWeights::Fixed(vec!["700".to_string(), "400".to_string()]),
+                &["italic", "normal"],
+            )
+        };
+        let b = NextFontGoogleOptions {
+            subsets: Some(vec!["latin".to_string(), "latin-ext".to_string()]),
+            ..options(
+                FontWeights::Fixed(vec![
+                    "400".to_string(),
+                    "700".to_string(),
+                    "400".to_string(),
+                ]),
+                &["normal", "italic"],
+            )
+        };
+        assert_ne!(a, b);
+        assert_eq!(a.canonicalize(), b.canonicalize());
+    }
+}
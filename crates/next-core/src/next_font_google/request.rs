@@ -0,0 +1,193 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::options::{FontWeights, NextFontGoogleOptions};
+
+/// A call of a `@next/font/google` font function, e. g.
+/// `Roboto_Mono({ weight: "400", subsets: ["latin"] })`, as it's encoded in
+/// the query of `@next/font/google/target.css` requests.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NextFontRequest {
+    /// The name of the imported function, e. g. `Roboto_Mono`.
+    pub import: String,
+    pub arguments: Vec<NextFontRequestArguments>,
+    /// The location of the call in the module which contains it.
+    #[serde(default)]
+    pub span: Option<NextFontRequestSpan>,
+}
+
+/// Byte offsets into the source code of a module.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct NextFontRequestSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct NextFontRequestArguments {
+    pub weight: Option<OneOrManyStrings>,
+    pub subsets: Option<Vec<String>>,
+    pub style: Option<OneOrManyStrings>,
+    pub display: Option<String>,
+    pub preload: Option<bool>,
+    pub fallback: Option<Vec<String>>,
+    pub variable: Option<String>,
+    pub text: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum OneOrManyStrings {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrManyStrings {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            OneOrManyStrings::One(value) => vec![value],
+            OneOrManyStrings::Many(values) => values,
+        }
+    }
+}
+
+const ALLOWED_DISPLAY_VALUES: &[&str] = &["auto", "block", "swap", "fallback", "optional"];
+
+impl NextFontRequest {
+    /// Parses the query of a `@next/font/google/target.css` request.
+    pub fn parse(query: &str) -> Result<Self> {
+        serde_json::from_str(query).context("invalid @next/font request")
+    }
+}
+
+/// Validates the arguments of a font function call and applies the defaults
+/// of the omitted ones. The options are canonicalized, so calls with
+/// equivalent arguments result in equal options.
+pub fn options_from_request(request: &NextFontRequest) -> Result<NextFontGoogleOptions> {
+    let font_family = request.import.replace('_', " ");
+    let arguments = match &*request.arguments {
+        [] => NextFontRequestArguments::default(),
+        [arguments] => arguments.clone(),
+        _ => bail!("{font_family} must be called with a single options object"),
+    };
+
+    let weights = match arguments.weight.map(OneOrManyStrings::into_vec) {
+        None => FontWeights::Variable,
+        Some(weights) if weights.iter().any(|weight| weight == "variable") => {
+            if weights.len() > 1 {
+                bail!(
+                    "Unexpected `variable` in weight array for font `{font_family}`. You only \
+                     need `variable`, it includes all available weights."
+                );
+            }
+            FontWeights::Variable
+        }
+        Some(weights) => {
+            for weight in &weights {
+                if !matches!(weight.parse::<u32>(), Ok(100..=900)) {
+                    bail!("Unknown weight `{weight}` for font `{font_family}`");
+                }
+            }
+            FontWeights::Fixed(weights)
+        }
+    };
+
+    let styles = arguments
+        .style
+        .map_or_else(|| vec!["normal".to_string()], OneOrManyStrings::into_vec);
+    for style in &styles {
+        if style != "normal" && style != "italic" {
+            bail!("Unknown style `{style}` for font `{font_family}`");
+        }
+    }
+
+    let display = arguments.display.unwrap_or_else(|| "swap".to_string());
+    if !ALLOWED_DISPLAY_VALUES.contains(&display.as_str()) {
+        bail!(
+            "Invalid display value `{display}` for font `{font_family}`. Available display \
+             values: {}",
+            ALLOWED_DISPLAY_VALUES.join(", ")
+        );
+    }
+
+    if matches!(&arguments.text, Some(text) if text.is_empty()) {
+        bail!("The `text` option of font `{font_family}` must not be empty");
+    }
+
+    // Only the glyphs of `text` are downloaded, so subsets are not needed to
+    // keep the preloaded files small.
+    let preload = arguments.preload.unwrap_or(true);
+    if preload && arguments.subsets.is_none() && arguments.text.is_none() {
+        bail!(
+            "Preload is enabled but no subsets were specified for font `{font_family}`. Please \
+             specify subsets or disable preloading."
+        );
+    }
+
+    if let Some(variable) = &arguments.variable {
+        if !variable.starts_with("--") {
+            bail!("Invalid variable `{variable}`, it must start with `--`");
+        }
+    }
+
+    Ok(NextFontGoogleOptions {
+        font_family,
+        weights,
+        styles,
+        display,
+        preload,
+        fallback: arguments.fallback,
+        variable: arguments.variable,
+        subsets: arguments.subsets,
+        text: arguments.text,
+    }
+    .canonicalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{options_from_request, NextFontRequest};
+    use crate::next_font_google::options::FontWeights;
+
+    #[test]
+    fn test_options_from_request() {
+        let request = NextFontRequest::parse(
+            r#"{"import":"Roboto_Mono","arguments":[{"weight":["400","700"],"subsets":["latin"],"variable":"--font-mono"}]}"#,
+        )
+        .unwrap();
+        let options = options_from_request(&request).unwrap();
+        assert_eq!(options.font_family, "Roboto Mono");
+        assert_eq!(
+            options.weights,
+            FontWeights::Fixed(vec!["400".to_string(), "700".to_string()])
+        );
+        assert_eq!(options.styles, vec!["normal".to_string()]);
+        assert_eq!(options.display, "swap");
+        assert_eq!(options.variable.as_deref(), Some("--font-mono"));
+    }
+
+    #[test]
+    fn test_text_without_subsets() {
+        let request =
+            NextFontRequest::parse(r#"{"import":"Inter","arguments":[{"text":"Welcome"}]}"#)
+                .unwrap();
+        let options = options_from_request(&request).unwrap();
+        assert_eq!(options.text.as_deref(), Some("Wcelmo"));
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        for query in [
+            r#"{"import":"Inter","arguments":[{"weight":"bold","subsets":["latin"]}]}"#,
+            r#"{"import":"Inter","arguments":[{"style":"oblique","subsets":["latin"]}]}"#,
+            r#"{"import":"Inter","arguments":[{"display":"never","subsets":["latin"]}]}"#,
+            r#"{"import":"Inter","arguments":[{}]}"#,
+            r#"{"import":"Inter","arguments":[{"variable":"font","subsets":["latin"]}]}"#,
+            r#"{"import":"Inter","arguments":[{"text":""}]}"#,
+        ] {
+            let request = NextFontRequest::parse(query).unwrap();
+            assert!(options_from_request(&request).is_err(), "{query}");
+        }
+    }
+}
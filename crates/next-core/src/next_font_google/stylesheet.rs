@@ -0,0 +1,116 @@
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, Value};
+use turbo_tasks_fetch::{fetch, FetchHeaders};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::issue::IssueSeverity;
+
+use super::{
+    font_file::font_file_path,
+    options::{get_stylesheet_url, NextFontGoogleOptions},
+};
+
+/// Google Fonts only serves woff2 files to browsers which support them, so
+/// the stylesheet is requested as a recent Chrome.
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, \
+                          like Gecko) Chrome/104.0.0.0 Safari/537.36";
+
+/// Fetches the stylesheet of the font and rewrites the URLs of its font files
+/// to paths which are resolved by [NextFontGoogleFontFileReplacer]. Fetch
+/// errors are reported as issues of `context`, and result in an empty
+/// stylesheet.
+///
+/// [NextFontGoogleFontFileReplacer]: super::font_file::NextFontGoogleFontFileReplacer
+#[turbo_tasks::function]
+pub async fn get_stylesheet(
+    options: Value<NextFontGoogleOptions>,
+    context: FileSystemPathVc,
+) -> Result<StringVc> {
+    let url = get_stylesheet_url(&options);
+    let headers = FetchHeaders(vec![("User-Agent".to_string(), USER_AGENT.to_string())]);
+    let response = match &*fetch(StringVc::cell(url), Value::new(headers)).await? {
+        Ok(response) => *response,
+        Err(error) => {
+            error
+                .to_issue(IssueSeverity::Error.into(), context)
+                .as_issue()
+                .emit();
+            return Ok(StringVc::cell(String::new()));
+        }
+    };
+    let css = response.await?.body.to_string().await?;
+    Ok(StringVc::cell(rewrite_font_file_urls(&css, |url| {
+        font_file_path(url).unwrap_or_else(|| url.to_string())
+    })))
+}
+
+/// Returns the ranges of the arguments of all `url()` functions in `css`,
+/// without quotes.
+fn url_ranges(css: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = css[offset..].find("url(") {
+        let start = offset + start + "url(".len();
+        let end = match css[start..].find(')') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let argument = &css[start..end];
+        let trimmed = argument.trim().trim_matches(|c| c == '"' || c == '\'');
+        let trimmed_start = start + argument.find(trimmed).unwrap_or(0);
+        ranges.push((trimmed_start, trimmed_start + trimmed.len()));
+        offset = end;
+    }
+    ranges
+}
+
+/// Replaces the URLs of all font files with the result of `rewrite`.
+fn rewrite_font_file_urls(css: &str, rewrite: impl Fn(&str) -> String) -> String {
+    let mut result = String::with_capacity(css.len());
+    let mut offset = 0;
+    for (start, end) in url_ranges(css) {
+        result.push_str(&css[offset..start]);
+        result.push_str(&rewrite(&css[start..end]));
+        offset = end;
+    }
+    result.push_str(&css[offset..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rewrite_font_file_urls;
+
+    const STYLESHEET: &str = r#"/* latin */
+@font-face {
+  font-family: 'Inter';
+  font-style: normal;
+  font-weight: 400;
+  font-display: swap;
+  src: url(https://fonts.gstatic.com/s/inter/v12/latin.woff2) format('woff2');
+  unicode-range: U+0000-00FF, U+0131, U+2000-206F;
+}
+/* cyrillic */
+@font-face {
+  font-family: 'Inter';
+  font-style: normal;
+  font-weight: 400;
+  font-display: swap;
+  src: url("https://fonts.gstatic.com/s/inter/v12/cyrillic.woff2") format('woff2');
+}
+"#;
+
+    #[test]
+    fn test_rewrite_font_file_urls() {
+        let css = rewrite_font_file_urls(STYLESHEET, |url| {
+            url.replace(
+                "https://fonts.gstatic.com/s/inter/v12",
+                "/_next/static/media",
+            )
+        });
+        assert!(css.contains("src: url(/_next/static/media/latin.woff2) format('woff2');"));
+        assert!(css.contains("src: url(\"/_next/static/media/cyrillic.woff2\") format('woff2');"));
+        assert!(!css.contains("fonts.gstatic.com"));
+        // Subset stylesheets only cover some characters, which must be kept.
+        assert!(css.contains("unicode-range: U+0000-00FF, U+0131, U+2000-206F;"));
+    }
+}
@@ -0,0 +1,411 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use serde_json::{Map, Number, Value as JsonValue};
+use swc_core::{
+    common::{Span, Spanned, DUMMY_SP},
+    ecma::{
+        ast::{
+            CallExpr, Callee, Decl, ExportDecl, ExportNamedSpecifier, ExportSpecifier, Expr, Id,
+            Ident, ImportDecl, ImportDefaultSpecifier, ImportNamedSpecifier, ImportSpecifier, Lit,
+            ModuleDecl, ModuleExportName, ModuleItem, NamedExport, Pat, Program, Prop, PropName,
+            PropOrSpread, Stmt, VarDecl, VarDeclKind,
+        },
+        visit::{Visit, VisitWith},
+    },
+};
+use turbo_tasks::primitives::StringVc;
+use turbopack_core::issue::IssueSourceVc;
+use turbopack_ecmascript::{CustomTransformVc, CustomTransformer, TransformContext};
+
+use super::{
+    issue::NextFontIssue,
+    request::{
+        options_from_request, NextFontRequest, NextFontRequestArguments, NextFontRequestSpan,
+    },
+    FONT_MODULE_REQUEST,
+};
+
+/// The module from which font functions are imported.
+const FONT_FUNCTIONS_MODULE: &str = "@next/font/google";
+
+/// Compiles the font function calls of a module, see
+/// [NextFontGoogleTransformer].
+#[turbo_tasks::function]
+pub fn next_font_google_transform() -> CustomTransformVc {
+    CustomTransformVc::new(NextFontGoogleTransformer)
+}
+
+/// Replaces `const inter = Inter({ ... })` with
+/// `import inter from "@next/font/google/target.css?{request}"`, where
+/// `request` is the JSON of the [NextFontRequest] of the call.
+///
+/// Font functions must be called with literal arguments in the initializer of
+/// a top level `const`, as the font is downloaded at build time. Other calls
+/// are reported as issues, and leave the module unchanged.
+pub struct NextFontGoogleTransformer;
+
+impl CustomTransformer for NextFontGoogleTransformer {
+    fn transform(&self, program: &mut Program, context: &TransformContext<'_>) -> Result<()> {
+        let module = match program {
+            Program::Module(module) => module,
+            Program::Script(_) => return Ok(()),
+        };
+        let mut reporter = IssueReporter {
+            context,
+            has_errors: false,
+        };
+
+        let mut font_functions = HashMap::new();
+        for item in &module.body {
+            if let ModuleItem::ModuleDecl(ModuleDecl::Import(import)) = item {
+                if &*import.src.value == FONT_FUNCTIONS_MODULE {
+                    collect_font_functions(import, &mut font_functions, &mut reporter);
+                }
+            }
+        }
+        if font_functions.is_empty() {
+            return Ok(());
+        }
+
+        let mut body = Vec::with_capacity(module.body.len());
+        let mut declared_calls = HashSet::new();
+        for item in &module.body {
+            let declaration = match item {
+                ModuleItem::ModuleDecl(ModuleDecl::Import(import))
+                    if &*import.src.value == FONT_FUNCTIONS_MODULE =>
+                {
+                    continue;
+                }
+                ModuleItem::Stmt(Stmt::Decl(Decl::Var(var))) => {
+                    font_declaration(var, &font_functions).map(|declaration| (declaration, false))
+                }
+                ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl {
+                    decl: Decl::Var(var),
+                    ..
+                })) => {
+                    font_declaration(var, &font_functions).map(|declaration| (declaration, true))
+                }
+                _ => None,
+            };
+            let ((local, call, font_function), exported) = match declaration {
+                Some(declaration) => declaration,
+                None => {
+                    body.push(item.clone());
+                    continue;
+                }
+            };
+            declared_calls.insert(call.span);
+            match font_request(call, font_function, &mut reporter) {
+                Some(request) => {
+                    body.push(font_import(local.clone(), request));
+                    if exported {
+                        body.push(export_local(local));
+                    }
+                }
+                None => body.push(item.clone()),
+            }
+        }
+
+        // Calls which aren't the initializer of a font declaration are
+        // misplaced.
+        let mut finder = FontCallFinder {
+            font_functions: &font_functions,
+            declared_calls: &declared_calls,
+            calls: Vec::new(),
+        };
+        body.visit_with(&mut finder);
+        for span in finder.calls {
+            reporter.report(
+                span,
+                "Font loader calls must be assigned to a const in the module scope".to_string(),
+            );
+        }
+
+        if !reporter.has_errors {
+            module.body = body;
+        }
+        Ok(())
+    }
+}
+
+/// Reports invalid font function calls as issues of the transformed module.
+struct IssueReporter<'a, 'b> {
+    context: &'a TransformContext<'b>,
+    has_errors: bool,
+}
+
+impl IssueReporter<'_, '_> {
+    fn byte_offset(&self, span: Span) -> NextFontRequestSpan {
+        let source_map = self.context.source_map;
+        NextFontRequestSpan {
+            start: source_map.lookup_byte_offset(span.lo).pos.to_usize(),
+            end: source_map.lookup_byte_offset(span.hi).pos.to_usize(),
+        }
+    }
+
+    fn report(&mut self, span: Span, description: String) {
+        self.has_errors = true;
+        let NextFontRequestSpan { start, end } = self.byte_offset(span);
+        let source = self.context.source;
+        NextFontIssue {
+            path: source.path(),
+            title: StringVc::cell("Invalid @next/font/google call".to_string()),
+            description: StringVc::cell(description),
+            source: IssueSourceVc::from_byte_offset(source, start, end),
+        }
+        .cell()
+        .as_issue()
+        .emit();
+    }
+}
+
+/// Maps the local names of the font functions imported by `import` to their
+/// imported names, which are the font families.
+fn collect_font_functions(
+    import: &ImportDecl,
+    font_functions: &mut HashMap<Id, String>,
+    reporter: &mut IssueReporter,
+) {
+    for specifier in &import.specifiers {
+        match specifier {
+            ImportSpecifier::Named(ImportNamedSpecifier {
+                local, imported, ..
+            }) => {
+                let imported = match imported {
+                    Some(ModuleExportName::Ident(ident)) => ident.sym.to_string(),
+                    Some(ModuleExportName::Str(str)) => str.value.to_string(),
+                    None => local.sym.to_string(),
+                };
+                font_functions.insert(local.to_id(), imported);
+            }
+            _ => reporter.report(
+                specifier.span(),
+                format!("Font functions must be imported by name from {FONT_FUNCTIONS_MODULE}"),
+            ),
+        }
+    }
+}
+
+/// Returns the local name, the call and the imported name of the font
+/// function if `var` is a `const` declaration of a font.
+fn font_declaration<'a>(
+    var: &'a VarDecl,
+    font_functions: &'a HashMap<Id, String>,
+) -> Option<(Ident, &'a CallExpr, &'a str)> {
+    if var.kind != VarDeclKind::Const {
+        return None;
+    }
+    let declarator = match &*var.decls {
+        [declarator] => declarator,
+        _ => return None,
+    };
+    let local = match &declarator.name {
+        Pat::Ident(binding) => binding.id.clone(),
+        _ => return None,
+    };
+    let call = match declarator.init.as_deref() {
+        Some(Expr::Call(call)) => call,
+        _ => return None,
+    };
+    let font_function = font_functions.get(&font_function_id(call)?)?;
+    Some((local, call, font_function))
+}
+
+/// Returns the request of the font module for `call`, or `None` if the call
+/// is invalid, which is reported.
+fn font_request(
+    call: &CallExpr,
+    font_function: &str,
+    reporter: &mut IssueReporter,
+) -> Option<String> {
+    let mut arguments = Vec::with_capacity(call.args.len());
+    for argument in &call.args {
+        if let Some(spread) = argument.spread {
+            reporter.report(spread, NON_LITERAL_MESSAGE.to_string());
+            return None;
+        }
+        let value = match expr_to_json(&argument.expr) {
+            Ok(value) => value,
+            Err(span) => {
+                reporter.report(span, NON_LITERAL_MESSAGE.to_string());
+                return None;
+            }
+        };
+        match serde_json::from_value::<NextFontRequestArguments>(value) {
+            Ok(value) => arguments.push(value),
+            Err(err) => {
+                reporter.report(
+                    argument.expr.span(),
+                    format!("Invalid options of font `{font_function}`: {err}"),
+                );
+                return None;
+            }
+        }
+    }
+
+    let request = NextFontRequest {
+        import: font_function.to_string(),
+        arguments,
+        span: Some(reporter.byte_offset(call.span)),
+    };
+    if let Err(err) = options_from_request(&request) {
+        reporter.report(call.span, err.to_string());
+        return None;
+    }
+    let request = serde_json::to_string(&request).expect("font requests are serializable");
+    Some(format!("{FONT_MODULE_REQUEST}?{request}"))
+}
+
+const NON_LITERAL_MESSAGE: &str = "Font loader values must be explicitly written literals.";
+
+/// Returns the local name of the function which is called by `call`.
+fn font_function_id(call: &CallExpr) -> Option<Id> {
+    match &call.callee {
+        Callee::Expr(callee) => match &**callee {
+            Expr::Ident(ident) => Some(ident.to_id()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Converts a literal expression to JSON. Returns the span of the first
+/// non-literal subexpression if there is one.
+fn expr_to_json(expr: &Expr) -> Result<JsonValue, Span> {
+    match expr {
+        Expr::Lit(Lit::Str(str)) => Ok(JsonValue::String(str.value.to_string())),
+        Expr::Lit(Lit::Bool(bool)) => Ok(JsonValue::Bool(bool.value)),
+        Expr::Lit(Lit::Null(_)) => Ok(JsonValue::Null),
+        Expr::Lit(Lit::Num(num)) => Number::from_f64(num.value)
+            .map(JsonValue::Number)
+            .ok_or(num.span),
+        Expr::Array(array) => array
+            .elems
+            .iter()
+            .map(|element| match element {
+                Some(element) if element.spread.is_none() => expr_to_json(&element.expr),
+                Some(element) => Err(element.span()),
+                None => Err(array.span),
+            })
+            .collect::<Result<_, _>>()
+            .map(JsonValue::Array),
+        Expr::Object(object) => {
+            let mut map = Map::new();
+            for prop in &object.props {
+                let key_value = match prop {
+                    PropOrSpread::Prop(prop) => match &**prop {
+                        Prop::KeyValue(key_value) => key_value,
+                        _ => return Err(prop.span()),
+                    },
+                    PropOrSpread::Spread(spread) => return Err(spread.dot3_token),
+                };
+                let key = match &key_value.key {
+                    PropName::Ident(ident) => ident.sym.to_string(),
+                    PropName::Str(str) => str.value.to_string(),
+                    key => return Err(key.span()),
+                };
+                map.insert(key, expr_to_json(&key_value.value)?);
+            }
+            Ok(JsonValue::Object(map))
+        }
+        _ => Err(expr.span()),
+    }
+}
+
+/// Returns `import local from "import"`.
+fn font_import(local: Ident, import: String) -> ModuleItem {
+    ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl {
+        specifiers: vec![ImportSpecifier::Default(ImportDefaultSpecifier {
+            local,
+            span: DUMMY_SP,
+        })],
+        src: Box::new(import.into()),
+        type_only: false,
+        asserts: None,
+        span: DUMMY_SP,
+    }))
+}
+
+/// Returns `export { local }`.
+fn export_local(local: Ident) -> ModuleItem {
+    ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(NamedExport {
+        specifiers: vec![ExportSpecifier::Named(ExportNamedSpecifier {
+            orig: ModuleExportName::Ident(local),
+            exported: None,
+            is_type_only: false,
+            span: DUMMY_SP,
+        })],
+        src: None,
+        type_only: false,
+        asserts: None,
+        span: DUMMY_SP,
+    }))
+}
+
+/// Collects the spans of the calls of font functions which aren't declared
+/// calls.
+struct FontCallFinder<'a> {
+    font_functions: &'a HashMap<Id, String>,
+    declared_calls: &'a HashSet<Span>,
+    calls: Vec<Span>,
+}
+
+impl Visit for FontCallFinder<'_> {
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        if let Some(id) = font_function_id(call) {
+            if self.font_functions.contains_key(&id) && !self.declared_calls.contains(&call.span) {
+                self.calls.push(call.span);
+            }
+        }
+        call.visit_children_with(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{FileName, SourceMap},
+        ecma::{
+            ast::{EsVersion, Expr},
+            parser::{parse_file_as_expr, Syntax},
+        },
+    };
+
+    use super::expr_to_json;
+
+    fn parse(code: &str) -> Box<Expr> {
+        let source_map = SourceMap::default();
+        let file = source_map.new_source_file(FileName::Anon, code.to_string());
+        parse_file_as_expr(
+            &file,
+            Syntax::Es(Default::default()),
+            EsVersion::latest(),
+            None,
+            &mut vec![],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_expr_to_json() {
+        let expr = parse(r#"{ weight: ["400", "700"], "subsets": ["latin"], preload: false }"#);
+        assert_eq!(
+            expr_to_json(&expr).unwrap().to_string(),
+            r#"{"weight":["400","700"],"subsets":["latin"],"preload":false}"#
+        );
+    }
+
+    #[test]
+    fn test_expr_to_json_non_literal() {
+        for code in [
+            "{ weight }",
+            "{ weight: weights }",
+            "{ ...options }",
+            r#"{ subsets: ["latin", ...subsets] }"#,
+            "{ display: `${display}` }",
+        ] {
+            assert!(expr_to_json(&parse(code)).is_err(), "{code}");
+        }
+    }
+}
@@ -8,6 +8,11 @@ use turbopack_core::resolve::options::{
 use crate::{
     embed_js::{attached_next_js_package_path, VIRTUAL_PACKAGE_NAME},
     next_client::context::ContextType,
+    next_font_google::{
+        font_file::{NextFontGoogleFontFileReplacerVc, FONT_FILE_PREFIX},
+        NextFontGoogleCssModuleReplacerVc, NextFontGoogleReplacerVc, FONT_CSS_MODULE_REQUEST,
+        FONT_MODULE_REQUEST,
+    },
     next_server::ServerContextType,
 };
 
@@ -21,6 +26,7 @@ pub fn get_next_client_import_map(
     let package_root = attached_next_js_package_path(project_path);
 
     insert_next_shared_aliases(&mut import_map, package_root);
+    insert_next_font_aliases(&mut import_map, project_path);
 
     match ty.into_value() {
         ContextType::Pages { pages_dir } => {
@@ -105,6 +111,7 @@ pub async fn get_next_server_import_map(
     let package_root = attached_next_js_package_path(project_path);
 
     insert_next_shared_aliases(&mut import_map, package_root);
+    insert_next_font_aliases(&mut import_map, project_path);
 
     match ty.into_value() {
         ServerContextType::Pages { pages_dir } => {
@@ -132,6 +139,10 @@ pub async fn get_next_server_import_map(
             import_map.insert_exact_alias("react-dom", ImportMapping::External(None).into());
             import_map.insert_wildcard_alias("react-dom/", ImportMapping::External(None).into());
         }
+        ServerContextType::Middleware => {
+            import_map.insert_exact_alias("next", ImportMapping::External(None).into());
+            import_map.insert_wildcard_alias("next/", ImportMapping::External(None).into());
+        }
         ServerContextType::AppSSR { app_dir } | ServerContextType::AppRSC { app_dir } => {
             import_map.insert_exact_alias(
                 "react",
@@ -231,6 +242,23 @@ pub fn insert_next_shared_aliases(import_map: &mut ImportMap, package_root: File
     );
 }
 
+/// Inserts the aliases which resolve the modules and font files of
+/// `@next/font`.
+fn insert_next_font_aliases(import_map: &mut ImportMap, project_path: FileSystemPathVc) {
+    import_map.insert_wildcard_alias(
+        FONT_MODULE_REQUEST,
+        ImportMapping::Dynamic(NextFontGoogleReplacerVc::new(project_path).into()).into(),
+    );
+    import_map.insert_wildcard_alias(
+        FONT_CSS_MODULE_REQUEST,
+        ImportMapping::Dynamic(NextFontGoogleCssModuleReplacerVc::new(project_path).into()).into(),
+    );
+    import_map.insert_wildcard_alias(
+        FONT_FILE_PREFIX,
+        ImportMapping::Dynamic(NextFontGoogleFontFileReplacerVc::new(project_path).into()).into(),
+    );
+}
+
 /// Inserts an alias to an alternative of import mappings into an import map.
 fn insert_alias_to_alternatives<'a>(
     import_map: &mut ImportMap,
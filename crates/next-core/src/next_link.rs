@@ -0,0 +1,219 @@
+//! Detection of the routes a page links to with `next/link`. They are
+//! rendered as `<link rel="prefetch">` hints into the HTML of the page, so the
+//! browser fetches them while idle, and the dev server compiles them before
+//! they are navigated to.
+
+use std::collections::{BTreeSet, HashSet, VecDeque};
+
+use anyhow::Result;
+use swc_core::ecma::{
+    ast::{
+        Expr, Id, ImportSpecifier, JSXAttrName, JSXAttrOrSpread, JSXAttrValue, JSXElementName,
+        JSXExpr, JSXOpeningElement, KeyValueProp, Lit, ModuleDecl, ModuleItem, Program, Prop,
+        PropName, PropOrSpread,
+    },
+    visit::{Visit, VisitWith},
+};
+use turbo_tasks::{primitives::StringsVc, Value};
+use turbopack_core::{asset::Asset, reference::all_referenced_assets};
+use turbopack_ecmascript::{
+    parse, EcmascriptInputTransformsVc, EcmascriptModuleAssetVc, ParseResult,
+};
+
+/// Returns the routes linked with `next/link` from the modules of the project
+/// which are imported by `entry`. Packages in `node_modules` aren't searched.
+#[turbo_tasks::function]
+pub async fn linked_routes(entry: EcmascriptModuleAssetVc) -> Result<StringsVc> {
+    let mut routes = BTreeSet::new();
+    for module in project_modules(entry).await? {
+        routes.extend(module_links(module).await?.iter().cloned());
+    }
+    Ok(StringsVc::cell(routes.into_iter().collect()))
+}
+
+/// The ecmascript modules of the project which are imported by `entry`,
+/// starting with `entry`. Packages in `node_modules` are skipped.
+pub(crate) async fn project_modules(
+    entry: EcmascriptModuleAssetVc,
+) -> Result<Vec<EcmascriptModuleAssetVc>> {
+    let mut modules = Vec::new();
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    visited.insert(entry);
+    queue.push_back(entry);
+    while let Some(module) = queue.pop_front() {
+        modules.push(module);
+        for referenced in all_referenced_assets(module.into()).await?.iter() {
+            if let Some(referenced) = EcmascriptModuleAssetVc::resolve_from(referenced).await? {
+                if referenced.path().await?.path.contains("node_modules/") {
+                    continue;
+                }
+                if visited.insert(referenced) {
+                    queue.push_back(referenced);
+                }
+            }
+        }
+    }
+    Ok(modules)
+}
+
+/// The routes linked with `next/link` from a single module.
+#[turbo_tasks::function]
+async fn module_links(module: EcmascriptModuleAssetVc) -> Result<StringsVc> {
+    let module = module.await?;
+    // The transforms of the module compile JSX, so the source is parsed
+    // without them.
+    let parsed = parse(
+        module.source,
+        Value::new(module.ty),
+        EcmascriptInputTransformsVc::cell(Vec::new()),
+    )
+    .await?;
+    Ok(StringsVc::cell(match &*parsed {
+        ParseResult::Ok { program, .. } => links_of_program(program),
+        _ => Vec::new(),
+    }))
+}
+
+fn links_of_program(program: &Program) -> Vec<String> {
+    let module = match program {
+        Program::Module(module) => module,
+        Program::Script(_) => return Vec::new(),
+    };
+    let mut link_components = HashSet::new();
+    for item in &module.body {
+        if let ModuleItem::ModuleDecl(ModuleDecl::Import(import)) = item {
+            if &*import.src.value == "next/link" {
+                for specifier in &import.specifiers {
+                    if let ImportSpecifier::Default(default) = specifier {
+                        link_components.insert(default.local.to_id());
+                    }
+                }
+            }
+        }
+    }
+    if link_components.is_empty() {
+        return Vec::new();
+    }
+    let mut finder = LinkFinder {
+        link_components,
+        links: Vec::new(),
+    };
+    program.visit_with(&mut finder);
+    finder.links
+}
+
+/// Collects the routes of `<Link href="...">` elements.
+struct LinkFinder {
+    link_components: HashSet<Id>,
+    links: Vec<String>,
+}
+
+impl Visit for LinkFinder {
+    fn visit_jsx_opening_element(&mut self, element: &JSXOpeningElement) {
+        let is_link = match &element.name {
+            JSXElementName::Ident(ident) => self.link_components.contains(&ident.to_id()),
+            _ => false,
+        };
+        if is_link {
+            if let Some(route) = element.attrs.iter().find_map(|attr| match attr {
+                JSXAttrOrSpread::JSXAttr(attr) => match &attr.name {
+                    JSXAttrName::Ident(ident) if &*ident.sym == "href" => {
+                        attr.value.as_ref().and_then(href_route)
+                    }
+                    _ => None,
+                },
+                JSXAttrOrSpread::SpreadElement(_) => None,
+            }) {
+                self.links.push(route);
+            }
+        }
+        element.visit_children_with(self);
+    }
+}
+
+/// Returns the route of a literal `href`, like `"/about"`, `{"/about"}` or
+/// `{{ pathname: "/about" }}`. Dynamic routes like `/posts/[id]` can't be
+/// prefetched without their parameters, and are skipped.
+fn href_route(href: &JSXAttrValue) -> Option<String> {
+    let href = match href {
+        JSXAttrValue::Lit(Lit::Str(str)) => &*str.value,
+        JSXAttrValue::JSXExprContainer(container) => match &container.expr {
+            JSXExpr::Expr(expr) => match &**expr {
+                Expr::Lit(Lit::Str(str)) => &*str.value,
+                Expr::Object(object) => object.props.iter().find_map(|prop| match prop {
+                    PropOrSpread::Prop(prop) => match &**prop {
+                        Prop::KeyValue(KeyValueProp { key, value }) => match (key, &**value) {
+                            (PropName::Ident(key), Expr::Lit(Lit::Str(str)))
+                                if &*key.sym == "pathname" =>
+                            {
+                                Some(&*str.value)
+                            }
+                            _ => None,
+                        },
+                        _ => None,
+                    },
+                    PropOrSpread::Spread(_) => None,
+                })?,
+                _ => return None,
+            },
+            JSXExpr::JSXEmptyExpr(_) => return None,
+        },
+        _ => return None,
+    };
+    let route = href.split(['#', '?']).next()?;
+    if !route.starts_with('/') || route.starts_with("//") || route.contains('[') {
+        return None;
+    }
+    Some(route.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{FileName, SourceMap},
+        ecma::{
+            ast::{EsVersion, Program},
+            parser::{parse_file_as_program, EsConfig, Syntax},
+        },
+    };
+
+    use super::links_of_program;
+
+    #[test]
+    fn finds_links() {
+        let code = r#"
+            import Link from "next/link";
+            export default function Nav() {
+                return (
+                    <nav>
+                        <Link href="/about">About</Link>
+                        <Link href={"/blog?page=2"}>Blog</Link>
+                        <Link href={{ pathname: "/contact", query: { from: "nav" } }}>Contact</Link>
+                        <Link href="/posts/[id]">Post</Link>
+                        <Link href="https://nextjs.org">Next.js</Link>
+                        <Link href={route}>Dynamic</Link>
+                        <a href="/plain">Plain</a>
+                    </nav>
+                );
+            }
+        "#;
+        let source_map = SourceMap::default();
+        let file = source_map.new_source_file(FileName::Anon, code.to_string());
+        let program: Program = parse_file_as_program(
+            &file,
+            Syntax::Es(EsConfig {
+                jsx: true,
+                ..Default::default()
+            }),
+            EsVersion::latest(),
+            None,
+            &mut vec![],
+        )
+        .unwrap();
+        assert_eq!(
+            links_of_program(&program),
+            vec!["/about", "/blog", "/contact"]
+        );
+    }
+}
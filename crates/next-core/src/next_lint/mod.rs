@@ -0,0 +1,179 @@
+//! Built-in lints for common mistakes in Next.js apps, following the rules of
+//! `eslint-plugin-next`.
+//!
+//! Linting is independent of compilation, so it can run in its own root task
+//! without delaying responses or HMR updates. Every file is linted in its own
+//! task, so only changed files are linted again.
+
+mod rules;
+
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, CompletionVc, Value};
+use turbo_tasks_fs::{DirectoryContent, DirectoryEntry, FileSystemPathVc};
+use turbopack_core::{
+    asset::AssetVc,
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueSourceVc, IssueVc, OptionIssueSourceVc},
+    source_asset::SourceAssetVc,
+};
+use turbopack_ecmascript::{
+    parse, EcmascriptInputTransformsVc, EcmascriptModuleAssetType, ParseResult,
+};
+
+use self::rules::{lint_program, LintFileKind};
+
+/// The directories of a project which are linted, like `next lint` does.
+const LINT_DIRS: [&str; 5] = ["pages", "app", "components", "lib", "src"];
+
+/// Lints the source files of the project and emits the violations as issues.
+#[turbo_tasks::function]
+pub async fn lint_project(project_path: FileSystemPathVc) -> Result<CompletionVc> {
+    let completions: Vec<_> = LINT_DIRS
+        .iter()
+        .map(|dir| lint_directory(project_path.join(dir), project_path))
+        .collect();
+    // The tasks run in parallel once they are called.
+    for completion in completions {
+        completion.await?;
+    }
+    Ok(CompletionVc::new())
+}
+
+#[turbo_tasks::function]
+async fn lint_directory(
+    dir: FileSystemPathVc,
+    project_path: FileSystemPathVc,
+) -> Result<CompletionVc> {
+    let mut completions = Vec::new();
+    if let DirectoryContent::Entries(entries) = &*dir.read_dir().await? {
+        for (name, entry) in entries.iter() {
+            if name.starts_with('.') || name == "node_modules" {
+                continue;
+            }
+            match entry {
+                DirectoryEntry::Directory(path) => {
+                    completions.push(lint_directory(*path, project_path))
+                }
+                DirectoryEntry::File(path) => {
+                    if module_type(name).is_some() {
+                        completions.push(lint_file(*path, project_path))
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    for completion in completions {
+        completion.await?;
+    }
+    Ok(CompletionVc::new())
+}
+
+fn module_type(file_name: &str) -> Option<EcmascriptModuleAssetType> {
+    if file_name.ends_with(".d.ts") {
+        return None;
+    }
+    match file_name.rsplit_once('.')?.1 {
+        "js" | "jsx" | "mjs" | "cjs" => Some(EcmascriptModuleAssetType::Ecmascript),
+        "ts" | "tsx" | "mts" | "cts" => Some(EcmascriptModuleAssetType::Typescript),
+        _ => None,
+    }
+}
+
+#[turbo_tasks::function]
+async fn lint_file(path: FileSystemPathVc, project_path: FileSystemPathVc) -> Result<CompletionVc> {
+    let path_value = path.await?;
+    let ty = match module_type(path_value.file_name()) {
+        Some(ty) => ty,
+        None => return Ok(CompletionVc::new()),
+    };
+    let project_path_value = project_path.await?;
+    let relative = project_path_value
+        .get_path_to(&path_value)
+        .unwrap_or_default();
+    let pages_relative = relative
+        .strip_prefix("pages/")
+        .or_else(|| relative.strip_prefix("src/pages/"));
+    let kind = LintFileKind {
+        in_pages: pages_relative.is_some(),
+        is_document: pages_relative
+            .and_then(|page| page.split_once('.'))
+            .map_or(false, |(stem, _)| stem == "_document"),
+    };
+
+    let source: AssetVc = SourceAssetVc::new(path).into();
+    let parsed = parse(
+        source,
+        Value::new(ty),
+        EcmascriptInputTransformsVc::cell(Vec::new()),
+    )
+    .await?;
+    if let ParseResult::Ok {
+        program,
+        source_map,
+        ..
+    } = &*parsed
+    {
+        for result in lint_program(program, kind) {
+            let start = source_map.lookup_byte_offset(result.span.lo).pos.to_usize();
+            let end = source_map.lookup_byte_offset(result.span.hi).pos.to_usize();
+            LintIssue {
+                path,
+                rule: result.rule.to_string(),
+                message: result.message.to_string(),
+                source: IssueSourceVc::from_byte_offset(source, start, end),
+            }
+            .cell()
+            .as_issue()
+            .emit();
+        }
+    }
+    Ok(CompletionVc::new())
+}
+
+/// A violation of a built-in lint rule.
+#[turbo_tasks::value(shared)]
+pub struct LintIssue {
+    pub path: FileSystemPathVc,
+    pub rule: String,
+    pub message: String,
+    /// The offending code, which is shown as a code frame.
+    pub source: IssueSourceVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for LintIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Warning.into()
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("lint".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(format!("Lint rule {} is violated", self.rule))
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(self.message.clone())
+    }
+
+    #[turbo_tasks::function]
+    fn documentation_link(&self) -> StringVc {
+        StringVc::cell(format!("https://nextjs.org/docs/messages/{}", self.rule))
+    }
+
+    #[turbo_tasks::function]
+    fn source(&self) -> OptionIssueSourceVc {
+        OptionIssueSourceVc::cell(Some(self.source))
+    }
+}
@@ -0,0 +1,240 @@
+use swc_core::{
+    common::Span,
+    ecma::{
+        ast::{
+            ImportDecl, JSXAttrName, JSXAttrOrSpread, JSXAttrValue, JSXElementName,
+            JSXOpeningElement, Lit, Program,
+        },
+        visit::{Visit, VisitWith},
+    },
+};
+
+/// Where a linted file is located, since some rules only apply to pages.
+#[derive(Clone, Copy, Default)]
+pub(super) struct LintFileKind {
+    /// The file is in the `pages` directory.
+    pub in_pages: bool,
+    /// The file is `pages/_document`.
+    pub is_document: bool,
+}
+
+/// A violation of a lint rule.
+pub(super) struct LintResult {
+    pub rule: &'static str,
+    pub message: &'static str,
+    pub span: Span,
+}
+
+/// Checks a module for common mistakes in Next.js apps.
+pub(super) fn lint_program(program: &Program, kind: LintFileKind) -> Vec<LintResult> {
+    let mut linter = Linter {
+        kind,
+        results: Vec::new(),
+    };
+    program.visit_with(&mut linter);
+    linter.results
+}
+
+struct Linter {
+    kind: LintFileKind,
+    results: Vec<LintResult>,
+}
+
+impl Linter {
+    fn report(&mut self, rule: &'static str, message: &'static str, span: Span) {
+        self.results.push(LintResult {
+            rule,
+            message,
+            span,
+        });
+    }
+}
+
+impl Visit for Linter {
+    fn visit_import_decl(&mut self, import: &ImportDecl) {
+        match &*import.src.value {
+            "next/document" if !self.kind.is_document => self.report(
+                "no-document-import-in-page",
+                "`next/document` should only be imported in `pages/_document`, which customizes \
+                 the document of all pages.",
+                import.span,
+            ),
+            "next/head" if self.kind.is_document => self.report(
+                "no-head-import-in-document",
+                "`next/head` should not be imported in `pages/_document`. Use `<Head />` from \
+                 `next/document` instead.",
+                import.span,
+            ),
+            _ => {}
+        }
+    }
+
+    fn visit_jsx_opening_element(&mut self, element: &JSXOpeningElement) {
+        if let JSXElementName::Ident(ident) = &element.name {
+            match &*ident.sym {
+                "img" => self.report(
+                    "no-img-element",
+                    "Using `<img>` could result in a slower LCP and higher bandwidth. Use `<Image \
+                     />` from `next/image` to optimize images.",
+                    element.span,
+                ),
+                "a" => {
+                    if let Some(href) = string_attr(element, "href") {
+                        if href.starts_with('/') && !href.starts_with("//") {
+                            self.report(
+                                "no-html-link-for-pages",
+                                "Use `<Link />` from `next/link` to navigate between pages, which \
+                                 avoids reloading the whole page.",
+                                element.span,
+                            );
+                        }
+                    }
+                }
+                "head" if self.kind.in_pages && !self.kind.is_document => self.report(
+                    "no-head-element",
+                    "Use `<Head />` from `next/head` to add elements to the `<head>` of a page.",
+                    element.span,
+                ),
+                "script"
+                    if has_attr(element, "src")
+                        && !has_attr(element, "async")
+                        && !has_attr(element, "defer") =>
+                {
+                    self.report(
+                        "no-sync-scripts",
+                        "Synchronous scripts block rendering of the page. Use `<Script />` from \
+                         `next/script`, or add `async` or `defer`.",
+                        element.span,
+                    )
+                }
+                _ => {}
+            }
+        }
+        element.visit_children_with(self);
+    }
+}
+
+fn find_attr<'a>(element: &'a JSXOpeningElement, name: &str) -> Option<&'a Option<JSXAttrValue>> {
+    element.attrs.iter().find_map(|attr| match attr {
+        JSXAttrOrSpread::JSXAttr(attr) => match &attr.name {
+            JSXAttrName::Ident(ident) if &*ident.sym == name => Some(&attr.value),
+            _ => None,
+        },
+        JSXAttrOrSpread::SpreadElement(_) => None,
+    })
+}
+
+fn has_attr(element: &JSXOpeningElement, name: &str) -> bool {
+    find_attr(element, name).is_some()
+}
+
+/// The value of an attribute with a string literal, e. g. `href="/about"`.
+fn string_attr<'a>(element: &'a JSXOpeningElement, name: &str) -> Option<&'a str> {
+    match find_attr(element, name)? {
+        Some(JSXAttrValue::Lit(Lit::Str(value))) => Some(&*value.value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{FileName, SourceMap},
+        ecma::{
+            ast::{EsVersion, Program},
+            parser::{parse_file_as_program, EsConfig, Syntax},
+        },
+    };
+
+    use super::{lint_program, LintFileKind};
+
+    fn lint(code: &str, kind: LintFileKind) -> Vec<&'static str> {
+        let source_map = SourceMap::default();
+        let file = source_map.new_source_file(FileName::Anon, code.to_string());
+        let program: Program = parse_file_as_program(
+            &file,
+            Syntax::Es(EsConfig {
+                jsx: true,
+                ..Default::default()
+            }),
+            EsVersion::latest(),
+            None,
+            &mut vec![],
+        )
+        .unwrap();
+        lint_program(&program, kind)
+            .into_iter()
+            .map(|result| result.rule)
+            .collect()
+    }
+
+    #[test]
+    fn reports_elements() {
+        let code = r#"
+            export default function Page() {
+                return (
+                    <div>
+                        <img src="/logo.png" />
+                        <a href="/about">About</a>
+                        <a href="https://nextjs.org">Next.js</a>
+                        <a href="//cdn.example.com">CDN</a>
+                        <script src="/analytics.js" />
+                        <script src="/deferred.js" defer />
+                    </div>
+                );
+            }
+        "#;
+        assert_eq!(
+            lint(code, LintFileKind::default()),
+            vec![
+                "no-img-element",
+                "no-html-link-for-pages",
+                "no-sync-scripts"
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_head_in_pages() {
+        let code = "export default () => <head><title>Page</title></head>;";
+        assert_eq!(lint(code, LintFileKind::default()), Vec::<&str>::new());
+        assert_eq!(
+            lint(
+                code,
+                LintFileKind {
+                    in_pages: true,
+                    is_document: false
+                }
+            ),
+            vec!["no-head-element"]
+        );
+    }
+
+    #[test]
+    fn reports_imports() {
+        let code = r#"
+            import Document from "next/document";
+            import Head from "next/head";
+        "#;
+        assert_eq!(
+            lint(
+                code,
+                LintFileKind {
+                    in_pages: true,
+                    is_document: false
+                }
+            ),
+            vec!["no-document-import-in-page"]
+        );
+        assert_eq!(
+            lint(
+                code,
+                LintFileKind {
+                    in_pages: true,
+                    is_document: true
+                }
+            ),
+            vec!["no-head-import-in-document"]
+        );
+    }
+}
@@ -0,0 +1,278 @@
+//! Handling of the strategies of `next/script`. The scripts aren't part of the
+//! chunks of a page, so the `<Script>` elements of the page are found
+//! statically, and the renderer adds the tags which the strategies need to
+//! the HTML.
+
+use anyhow::Result;
+use swc_core::ecma::{
+    ast::{
+        Id, ImportSpecifier, JSXAttrName, JSXAttrOrSpread, JSXAttrValue, JSXElementName,
+        JSXOpeningElement, Lit, ModuleDecl, ModuleItem, Program,
+    },
+    visit::{Visit, VisitWith},
+};
+use turbo_tasks::{primitives::StringVc, Value};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    asset::Asset,
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+};
+use turbopack_ecmascript::{
+    parse, EcmascriptInputTransformsVc, EcmascriptModuleAssetVc, ParseResult,
+};
+
+use crate::{next_config::NextConfigVc, next_link::project_modules};
+
+/// The `src` of the scripts of a page, by their strategy.
+#[turbo_tasks::value(shared)]
+#[derive(Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PageScripts {
+    /// Loaded before any code of Next.js, from a tag in `<head>`.
+    pub before_interactive: Vec<String>,
+    /// Loaded after hydration by the `Script` component. The HTML preloads
+    /// them.
+    pub after_interactive: Vec<String>,
+    /// Loaded when the browser is idle by the `Script` component.
+    pub lazy_onload: Vec<String>,
+    /// Loaded in a web worker by Partytown. Only used with
+    /// `experimental.nextScriptWorkers`.
+    pub worker: Vec<String>,
+}
+
+impl PageScripts {
+    fn add(&mut self, strategy: &str, src: &str) {
+        let scripts = match strategy {
+            "beforeInteractive" => &mut self.before_interactive,
+            "lazyOnload" => &mut self.lazy_onload,
+            "worker" => &mut self.worker,
+            // `afterInteractive` is the default strategy.
+            _ => &mut self.after_interactive,
+        };
+        if !scripts.iter().any(|script| script == src) {
+            scripts.push(src.to_string());
+        }
+    }
+
+    fn extend(&mut self, other: &PageScripts) {
+        for (strategy, scripts) in [
+            ("beforeInteractive", &other.before_interactive),
+            ("afterInteractive", &other.after_interactive),
+            ("lazyOnload", &other.lazy_onload),
+            ("worker", &other.worker),
+        ] {
+            for src in scripts {
+                self.add(strategy, src);
+            }
+        }
+    }
+}
+
+/// Returns the scripts of the modules of the project which are imported by
+/// `entry`. Without `experimental.nextScriptWorkers`, Partytown isn't loaded,
+/// so scripts with the `worker` strategy fall back to `lazyOnload`.
+#[turbo_tasks::function]
+pub async fn page_scripts(
+    entry: EcmascriptModuleAssetVc,
+    next_config: NextConfigVc,
+) -> Result<PageScriptsVc> {
+    let next_script_workers = next_config
+        .await?
+        .experimental
+        .next_script_workers
+        .unwrap_or(false);
+    let mut page_scripts = PageScripts::default();
+    for module in project_modules(entry).await? {
+        let mut scripts = module_scripts(module).await?.clone_value();
+        if !next_script_workers && !scripts.worker.is_empty() {
+            NextScriptIssue {
+                path: module.path(),
+                message: StringVc::cell(format!(
+                    "The worker strategy of next/script requires `experimental.nextScriptWorkers` \
+                     in next.config.js. These scripts are loaded with the lazyOnload strategy \
+                     instead: {}",
+                    scripts.worker.join(", ")
+                )),
+            }
+            .cell()
+            .as_issue()
+            .emit();
+            scripts.lazy_onload.append(&mut scripts.worker);
+        }
+        page_scripts.extend(&scripts);
+    }
+    Ok(page_scripts.cell())
+}
+
+/// The scripts of a single module.
+#[turbo_tasks::function]
+async fn module_scripts(module: EcmascriptModuleAssetVc) -> Result<PageScriptsVc> {
+    let module = module.await?;
+    // The transforms of the module compile JSX, so the source is parsed
+    // without them.
+    let parsed = parse(
+        module.source,
+        Value::new(module.ty),
+        EcmascriptInputTransformsVc::cell(Vec::new()),
+    )
+    .await?;
+    Ok(match &*parsed {
+        ParseResult::Ok { program, .. } => scripts_of_program(program),
+        _ => PageScripts::default(),
+    }
+    .cell())
+}
+
+fn scripts_of_program(program: &Program) -> PageScripts {
+    let mut finder = ScriptFinder {
+        script_components: Vec::new(),
+        scripts: PageScripts::default(),
+    };
+    if let Program::Module(module) = program {
+        for item in &module.body {
+            if let ModuleItem::ModuleDecl(ModuleDecl::Import(import)) = item {
+                if &*import.src.value == "next/script" {
+                    for specifier in &import.specifiers {
+                        if let ImportSpecifier::Default(default) = specifier {
+                            finder.script_components.push(default.local.to_id());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    if !finder.script_components.is_empty() {
+        program.visit_with(&mut finder);
+    }
+    finder.scripts
+}
+
+/// Collects the `src` and `strategy` of `<Script>` elements. Only string
+/// literals are supported, scripts with other values are left to the `Script`
+/// component.
+struct ScriptFinder {
+    script_components: Vec<Id>,
+    scripts: PageScripts,
+}
+
+impl Visit for ScriptFinder {
+    fn visit_jsx_opening_element(&mut self, element: &JSXOpeningElement) {
+        let is_script = match &element.name {
+            JSXElementName::Ident(ident) => self.script_components.contains(&ident.to_id()),
+            _ => false,
+        };
+        if is_script {
+            let mut src = None;
+            let mut strategy = Some("afterInteractive");
+            for attr in &element.attrs {
+                if let JSXAttrOrSpread::JSXAttr(attr) = attr {
+                    if let JSXAttrName::Ident(ident) = &attr.name {
+                        let value = match &attr.value {
+                            Some(JSXAttrValue::Lit(Lit::Str(str))) => Some(&*str.value),
+                            _ => None,
+                        };
+                        match &*ident.sym {
+                            "src" => src = value,
+                            "strategy" => strategy = value,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            if let (Some(src), Some(strategy)) = (src, strategy) {
+                self.scripts.add(strategy, src);
+            }
+        }
+        element.visit_children_with(self);
+    }
+}
+
+#[turbo_tasks::value(shared)]
+pub struct NextScriptIssue {
+    pub path: FileSystemPathVc,
+    pub message: StringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for NextScriptIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Warning.into()
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("next/script".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Unsupported next/script strategy".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        self.message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{FileName, SourceMap},
+        ecma::{
+            ast::{EsVersion, Program},
+            parser::{parse_file_as_program, EsConfig, Syntax},
+        },
+    };
+
+    use super::{scripts_of_program, PageScripts};
+
+    #[test]
+    fn finds_scripts() {
+        let code = r#"
+            import Script from "next/script";
+            export default function Page() {
+                return (
+                    <>
+                        <Script src="/polyfills.js" strategy="beforeInteractive" />
+                        <Script src="https://example.com/analytics.js" />
+                        <Script src="/chat.js" strategy="lazyOnload" />
+                        <Script src="/chat.js" strategy="lazyOnload" />
+                        <Script src="/tracking.js" strategy="worker" />
+                        <Script src={src} strategy="lazyOnload" />
+                        <Script src="/dynamic.js" strategy={strategy} />
+                        <script src="/plain.js" />
+                    </>
+                );
+            }
+        "#;
+        let source_map = SourceMap::default();
+        let file = source_map.new_source_file(FileName::Anon, code.to_string());
+        let program: Program = parse_file_as_program(
+            &file,
+            Syntax::Es(EsConfig {
+                jsx: true,
+                ..Default::default()
+            }),
+            EsVersion::latest(),
+            None,
+            &mut vec![],
+        )
+        .unwrap();
+        assert_eq!(
+            scripts_of_program(&program),
+            PageScripts {
+                before_interactive: vec!["/polyfills.js".to_string()],
+                after_interactive: vec!["https://example.com/analytics.js".to_string()],
+                lazy_onload: vec!["/chat.js".to_string()],
+                worker: vec!["/tracking.js".to_string()],
+            }
+        );
+    }
+}
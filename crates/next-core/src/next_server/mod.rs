@@ -1,3 +1,4 @@
+use anyhow::Result;
 use turbo_tasks::{
     primitives::{StringVc, StringsVc},
     Value,
@@ -13,7 +14,11 @@ use turbopack_core::environment::{
 };
 use turbopack_ecmascript::EcmascriptInputTransform;
 
-use crate::next_import_map::get_next_server_import_map;
+use crate::{
+    next_config::load_next_config, next_dynamic::next_dynamic_transform,
+    next_font_google::transform::next_font_google_transform,
+    next_import_map::get_next_server_import_map,
+};
 
 #[turbo_tasks::value(serialization = "auto_for_input")]
 #[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord)]
@@ -21,6 +26,7 @@ pub enum ServerContextType {
     Pages { pages_dir: FileSystemPathVc },
     AppSSR { app_dir: FileSystemPathVc },
     AppRSC { app_dir: FileSystemPathVc },
+    Middleware,
 }
 
 #[turbo_tasks::function]
@@ -31,19 +37,19 @@ pub fn get_server_resolve_options_context(
 ) -> ResolveOptionsContextVc {
     let next_server_import_map = get_next_server_import_map(project_path, ty, externals);
     match ty.into_value() {
-        ServerContextType::Pages { .. } | ServerContextType::AppSSR { .. } => {
-            ResolveOptionsContext {
-                enable_typescript: true,
-                enable_react: true,
-                enable_node_modules: true,
-                enable_node_externals: true,
-                enable_node_native_modules: true,
-                custom_conditions: vec!["development".to_string()],
-                import_map: Some(next_server_import_map),
-                module: true,
-                ..Default::default()
-            }
-        }
+        ServerContextType::Pages { .. }
+        | ServerContextType::AppSSR { .. }
+        | ServerContextType::Middleware => ResolveOptionsContext {
+            enable_typescript: true,
+            enable_react: true,
+            enable_node_modules: true,
+            enable_node_externals: true,
+            enable_node_native_modules: true,
+            custom_conditions: vec!["development".to_string()],
+            import_map: Some(next_server_import_map),
+            module: true,
+            ..Default::default()
+        },
         ServerContextType::AppRSC { .. } => ResolveOptionsContext {
             enable_typescript: true,
             enable_react: true,
@@ -72,21 +78,40 @@ pub fn get_server_environment(
             ServerContextType::Pages { .. } => Value::new(EnvironmentIntention::ServerRendering),
             ServerContextType::AppSSR { .. } => Value::new(EnvironmentIntention::Prerendering),
             ServerContextType::AppRSC { .. } => Value::new(EnvironmentIntention::ServerRendering),
+            ServerContextType::Middleware => Value::new(EnvironmentIntention::ServerRendering),
         },
     )
 }
 
 #[turbo_tasks::function]
-pub fn get_server_module_options_context(ty: Value<ServerContextType>) -> ModuleOptionsContextVc {
-    match ty.into_value() {
+pub async fn get_server_module_options_context(
+    project_path: FileSystemPathVc,
+    ty: Value<ServerContextType>,
+) -> Result<ModuleOptionsContextVc> {
+    let next_font_transform = EcmascriptInputTransform::Custom(next_font_google_transform());
+    // The CSS-in-JS transforms have to match the ones of the client, so the
+    // class names of the server render are hydrated.
+    let next_config = load_next_config(project_path).await?;
+    let enable_emotion = next_config.emotion_transform_config();
+    let enable_styled_components = next_config.styled_components_transform_config();
+    Ok(match ty.into_value() {
         ServerContextType::Pages { .. } => ModuleOptionsContext {
+            enable_emotion,
+            enable_styled_components,
             enable_typescript_transform: true,
             enable_styled_jsx: true,
+            custom_ecmascript_app_transforms: vec![
+                next_font_transform,
+                EcmascriptInputTransform::Custom(next_dynamic_transform(true)),
+            ],
             ..Default::default()
         },
         ServerContextType::AppSSR { .. } => ModuleOptionsContext {
+            enable_emotion,
+            enable_styled_components,
             enable_styled_jsx: true,
             enable_typescript_transform: true,
+            custom_ecmascript_app_transforms: vec![next_font_transform],
             ..Default::default()
         },
         ServerContextType::AppRSC { .. } => ModuleOptionsContext {
@@ -94,8 +119,13 @@ pub fn get_server_module_options_context(ty: Value<ServerContextType>) -> Module
             custom_ecmascript_transforms: vec![EcmascriptInputTransform::ClientDirective(
                 StringVc::cell("server-to-client".to_string()),
             )],
+            custom_ecmascript_app_transforms: vec![next_font_transform],
+            ..Default::default()
+        },
+        ServerContextType::Middleware => ModuleOptionsContext {
+            enable_typescript_transform: true,
             ..Default::default()
         },
     }
-    .cell()
+    .cell())
 }
@@ -2,6 +2,7 @@ use std::{
     collections::{BTreeMap, HashMap, HashSet},
     fmt::Write as _,
     path::PathBuf,
+    time::Duration,
 };
 
 use anyhow::{anyhow, bail, Context, Result};
@@ -10,10 +11,14 @@ use indexmap::{IndexMap, IndexSet};
 use mime::TEXT_HTML_UTF_8;
 pub use node_api_source::create_node_api_source;
 pub use node_entry::{NodeEntry, NodeEntryVc};
+pub use node_middleware_source::create_node_middleware_source;
 pub use node_rendered_source::create_node_rendered_source;
 use serde::{Deserialize, Serialize};
-use turbo_tasks::{primitives::StringVc, CompletionVc, CompletionsVc, TryJoinIterExt};
+use turbo_tasks::{
+    get_invalidator, primitives::StringVc, CompletionVc, CompletionsVc, TryJoinIterExt,
+};
 use turbo_tasks_fs::{to_sys_path, File, FileContent, FileSystemPathVc};
+use turbopack::{duplicate_packages::check_duplicate_packages, nft_json::NftJsonAssetVc};
 use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc, AssetsSetVc},
     chunk::{ChunkGroupVc, ChunkingContextVc},
@@ -31,12 +36,17 @@ use self::{
     issue::RenderingIssue,
     pool::{NodeJsOperation, NodeJsPool, NodeJsPoolVc},
 };
-use crate::source_map::{SourceMapTraceVc, StackFrame, TraceResult};
+use crate::{
+    next_config::I18NConfig,
+    next_script::PageScripts,
+    source_map::{SourceMapTraceVc, StackFrame, TraceResult},
+};
 
 pub(crate) mod bootstrap;
 pub(crate) mod issue;
 pub(crate) mod node_api_source;
 pub(crate) mod node_entry;
+pub(crate) mod node_middleware_source;
 pub(crate) mod node_rendered_source;
 pub(crate) mod pool;
 
@@ -178,10 +188,14 @@ async fn separate_assets(
 }
 
 /// Creates a node.js renderer pool for an entrypoint.
+///
+/// When `trace` is set, an `index.js.nft.json` listing all files needed to
+/// run the entrypoint is emitted next to it.
 #[turbo_tasks::function]
 async fn get_renderer_pool(
     intermediate_asset: AssetVc,
     intermediate_output_path: FileSystemPathVc,
+    trace: bool,
 ) -> Result<NodeJsPoolVc> {
     // Emit a basic package.json that sets the type of the package to commonjs.
     // Currently code generated for Node is CommonJS, while authored code may be
@@ -201,6 +215,11 @@ async fn get_renderer_pool(
 
     emit(intermediate_asset, intermediate_output_path).await?;
 
+    if trace {
+        let nft_json = NftJsonAssetVc::new(intermediate_asset);
+        nft_json.content().write(nft_json.path()).await?;
+    }
+
     if let Some(dir) = to_sys_path(intermediate_output_path).await? {
         let entrypoint = dir.join("index.js");
         let pool = NodeJsPool::new(dir, entrypoint, HashMap::new(), 4);
@@ -218,6 +237,7 @@ pub async fn get_intermediate_asset(
     chunking_context: ChunkingContextVc,
     intermediate_output_path: FileSystemPathVc,
 ) -> Result<AssetVc> {
+    check_duplicate_packages(entry_module.into()).await?;
     let chunk = entry_module.as_evaluated_chunk(chunking_context, Some(runtime_entries));
     let chunk_group = ChunkGroupVc::from_chunk(chunk);
     Ok(NodeJsBootstrapAsset {
@@ -236,6 +256,18 @@ pub(super) struct RenderData {
     query: Query,
     headers: BTreeMap<String, HeaderValue>,
     path: String,
+    /// Renders the props of the page as JSON instead of the HTML, like
+    /// Next.js does for `/_next/data/*` requests.
+    data: bool,
+    /// The routes linked with `next/link` from the page, which are added to
+    /// the HTML as prefetch hints.
+    prefetch: Vec<String>,
+    /// The scripts of the page loaded with `next/script`, which are added to
+    /// the HTML depending on their strategy.
+    scripts: PageScripts,
+    /// The `i18n` config of `next.config.js`. The renderer reads the locale
+    /// of the page from the URL.
+    i18n: Option<I18NConfig>,
 }
 
 #[derive(Deserialize)]
@@ -246,6 +278,13 @@ pub enum RenderResult {
         body: String,
         #[serde(rename = "contentType")]
         content_type: Option<String>,
+        /// The number of seconds after which the page is rendered again, as
+        /// returned by `getStaticProps`.
+        revalidate: Option<u64>,
+        /// Set when `getStaticProps` returned `notFound` or the path isn't
+        /// returned by `getStaticPaths`.
+        #[serde(rename = "notFound", default)]
+        not_found: bool,
     },
 }
 
@@ -271,6 +310,7 @@ async fn render_static(
     fallback_page: DevHtmlAssetVc,
     chunking_context: ChunkingContextVc,
     intermediate_output_path: FileSystemPathVc,
+    trace: bool,
     data: RenderDataVc,
 ) -> Result<AssetContentVc> {
     let intermediate_asset = get_intermediate_asset(
@@ -279,7 +319,7 @@ async fn render_static(
         chunking_context,
         intermediate_output_path,
     );
-    let renderer_pool = get_renderer_pool(intermediate_asset, intermediate_output_path);
+    let renderer_pool = get_renderer_pool(intermediate_asset, intermediate_output_path, trace);
     // Read this strongly consistent, since we don't want to run inconsistent
     // node.js code.
     let pool = renderer_pool.strongly_consistent().await?;
@@ -322,12 +362,32 @@ async fn run_static_operation(
             result: RenderResult::Simple(body),
         } => Ok(FileContent::Content(File::from(body).with_content_type(TEXT_HTML_UTF_8)).into()),
         RenderStaticIncomingMessage::Result {
-            result: RenderResult::Advanced { body, content_type },
-        } => Ok(FileContent::Content(
-            File::from(body)
-                .with_content_type(content_type.map_or(Ok(TEXT_HTML_UTF_8), |c| c.parse())?),
-        )
-        .into()),
+            result:
+                RenderResult::Advanced {
+                    body,
+                    content_type,
+                    revalidate,
+                    not_found,
+                },
+        } => {
+            if let Some(revalidate) = revalidate {
+                // Incremental static regeneration: render the page again once
+                // the data is stale.
+                let invalidator = get_invalidator();
+                tokio::spawn(async move {
+                    tokio::time::sleep(Duration::from_secs(revalidate)).await;
+                    invalidator.invalidate();
+                });
+            }
+            if not_found {
+                return Ok(FileContent::NotFound.into());
+            }
+            Ok(FileContent::Content(
+                File::from(body)
+                    .with_content_type(content_type.map_or(Ok(TEXT_HTML_UTF_8), |c| c.parse())?),
+            )
+            .into())
+        }
         RenderStaticIncomingMessage::Error(error) => {
             bail!(trace_stack(error, intermediate_asset, intermediate_output_path).await?)
         }
@@ -487,6 +547,7 @@ async fn render_proxy(
     runtime_entries: EcmascriptChunkPlaceablesVc,
     chunking_context: ChunkingContextVc,
     intermediate_output_path: FileSystemPathVc,
+    trace: bool,
     data: RenderDataVc,
     body: BodyVc,
 ) -> Result<ProxyResultVc> {
@@ -496,7 +557,7 @@ async fn render_proxy(
         chunking_context,
         intermediate_output_path,
     );
-    let renderer_pool = get_renderer_pool(intermediate_asset, intermediate_output_path);
+    let renderer_pool = get_renderer_pool(intermediate_asset, intermediate_output_path, trace);
     let pool = renderer_pool.await?;
     let mut operation = match pool.operation().await {
         Ok(operation) => operation,
@@ -92,6 +92,7 @@ impl ContentSource for NodeApiContentSource {
                         this.runtime_entries,
                         entry.chunking_context,
                         entry.intermediate_output_path,
+                        entry.trace,
                         RenderData {
                             params,
                             method: method.clone(),
@@ -99,6 +100,10 @@ impl ContentSource for NodeApiContentSource {
                             query: query.clone(),
                             headers: headers.clone(),
                             path: format!("/{path}"),
+                            data: false,
+                            prefetch: Vec::new(),
+                            scripts: Default::default(),
+                            i18n: None,
                         }
                         .cell(),
                         *body,
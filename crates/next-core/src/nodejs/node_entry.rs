@@ -10,6 +10,9 @@ pub struct NodeRenderingEntry {
     pub module: EcmascriptModuleAssetVc,
     pub chunking_context: ChunkingContextVc,
     pub intermediate_output_path: FileSystemPathVc,
+    /// Emit an `index.js.nft.json` next to the entry in the
+    /// `intermediate_output_path`, which lists all files it depends on.
+    pub trace: bool,
 }
 
 #[turbo_tasks::value(transparent)]
@@ -0,0 +1,361 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use turbo_tasks::{primitives::StringVc, Value};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    asset::Asset,
+    introspect::{Introspectable, IntrospectableChildrenVc, IntrospectableVc},
+    issue::{IssueSeverity, IssueVc},
+};
+use turbopack_dev_server::source::{
+    query::{Query, QueryValue},
+    ContentSource, ContentSourceContent, ContentSourceData, ContentSourceDataFilter,
+    ContentSourceDataVary, ContentSourceResult, ContentSourceResultVc, ContentSourceVc,
+    HeaderValue, ProxyResult,
+};
+use turbopack_ecmascript::chunk::EcmascriptChunkPlaceablesVc;
+
+use super::{node_rendered_source::DATA_ROUTE_PREFIX, render_proxy, NodeEntryVc, RenderData};
+use crate::{
+    edge_runtime::EdgeRuntimeIssue,
+    path_to_regexp::PathPattern,
+    util::{has_data, header},
+};
+
+/// Creates a [NodeMiddlewareContentSource].
+pub fn create_node_middleware_source(
+    server_root: FileSystemPathVc,
+    matcher: Vec<PathPattern>,
+    entry: NodeEntryVc,
+    runtime_entries: EcmascriptChunkPlaceablesVc,
+    source: ContentSourceVc,
+) -> ContentSourceVc {
+    NodeMiddlewareContentSource {
+        server_root,
+        matcher,
+        entry,
+        runtime_entries,
+        source,
+    }
+    .cell()
+    .into()
+}
+
+/// A content source which runs middleware in Node.js before the requests reach
+/// `source`. The middleware continues with `source` by responding with the
+/// `x-middleware-next` or `x-middleware-rewrite` headers, and any other
+/// response is served as it is.
+///
+/// Middleware runs for every request, so the requests it runs for are passed
+/// to `source` with all of their data.
+#[turbo_tasks::value]
+struct NodeMiddlewareContentSource {
+    server_root: FileSystemPathVc,
+    /// The paths the middleware runs for. It runs for all paths when this is
+    /// empty.
+    matcher: Vec<PathPattern>,
+    entry: NodeEntryVc,
+    runtime_entries: EcmascriptChunkPlaceablesVc,
+    source: ContentSourceVc,
+}
+
+impl NodeMiddlewareContentSource {
+    /// Whether the middleware runs for a path. The assets of Next.js are
+    /// skipped, and data requests are matched with the path of their page.
+    fn is_middleware_path(&self, path: &str) -> bool {
+        let page_path = match path.strip_prefix(DATA_ROUTE_PREFIX) {
+            Some(data_path) => {
+                let page = data_path.strip_suffix(".json").unwrap_or(data_path);
+                page.strip_suffix("index").unwrap_or(page)
+            }
+            None if path.starts_with("_next/") || path.starts_with("__nextjs") => return false,
+            None => path,
+        };
+        let page_path = format!("/{}", page_path.trim_end_matches('/'));
+        self.matcher.is_empty()
+            || self
+                .matcher
+                .iter()
+                .any(|pattern| pattern.match_path(&page_path).is_some())
+    }
+}
+
+/// The data which the middleware needs: the whole request. The cache buster
+/// runs it again for every request.
+fn middleware_vary() -> ContentSourceDataVary {
+    ContentSourceDataVary {
+        method: true,
+        url: true,
+        headers: Some(ContentSourceDataFilter::All),
+        query: Some(ContentSourceDataFilter::All),
+        body: true,
+        cache_buster: true,
+        ..Default::default()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for NodeMiddlewareContentSource {
+    #[turbo_tasks::function]
+    async fn get(
+        self_vc: NodeMiddlewareContentSourceVc,
+        path: &str,
+        data: Value<ContentSourceData>,
+    ) -> Result<ContentSourceResultVc> {
+        let this = self_vc.await?;
+        if !this.is_middleware_path(path) {
+            return Ok(this.source.get(path, data));
+        }
+        let (method, url, query, headers, body) = match &*data {
+            ContentSourceData {
+                method: Some(method),
+                url: Some(url),
+                query: Some(query),
+                headers: Some(headers),
+                body: Some(body),
+                ..
+            } => (
+                method.clone(),
+                url.clone(),
+                query.clone(),
+                headers.clone(),
+                *body,
+            ),
+            _ => {
+                return Ok(ContentSourceResultVc::exact(
+                    ContentSourceContent::NeedData {
+                        source: self_vc.into(),
+                        path: path.to_string(),
+                        vary: middleware_vary(),
+                    }
+                    .cell(),
+                ))
+            }
+        };
+
+        let entry = this.entry.entry(data.clone()).await?;
+        let result = render_proxy(
+            this.server_root.join(path),
+            entry.module,
+            this.runtime_entries,
+            entry.chunking_context,
+            entry.intermediate_output_path,
+            entry.trace,
+            RenderData {
+                params: IndexMap::new(),
+                method,
+                url,
+                query,
+                headers: headers.clone(),
+                path: format!("/{path}"),
+                data: false,
+                prefetch: Vec::new(),
+                scripts: Default::default(),
+                i18n: None,
+            }
+            .cell(),
+            body,
+        );
+        let result = result.await?;
+        let response = MiddlewareResponse::new(&result);
+        if !response.next && response.rewrite.is_none() {
+            return Ok(ContentSourceResultVc::exact(
+                ContentSourceContent::HttpProxy(
+                    ProxyResult {
+                        status: result.status,
+                        headers: response.headers,
+                        body: result.body.clone(),
+                    }
+                    .cell(),
+                )
+                .cell(),
+            ));
+        }
+
+        let mut data = data.into_value();
+        if let Some(request_headers) = response.request_headers {
+            data.headers = Some(request_headers);
+        }
+        let mut inner_path = path.to_string();
+        if let Some(rewrite) = &response.rewrite {
+            let rewrite_url = url::Url::parse(rewrite)?;
+            let host = header(&headers, "host").unwrap_or_default();
+            if rewrite_url.host_str() != host.split(':').next() {
+                EdgeRuntimeIssue {
+                    path: entry.module.path(),
+                    severity: IssueSeverity::Warning.into(),
+                    title: StringVc::cell("External rewrites are not supported".to_string()),
+                    message: StringVc::cell(format!(
+                        "The middleware rewrote the request for /{path} to {rewrite}. Rewrites to \
+                         other hosts are not supported by the dev server yet."
+                    )),
+                }
+                .cell()
+                .as_issue()
+                .emit();
+                return Ok(ContentSourceResultVc::not_found());
+            }
+            inner_path = rewrite_url.path().trim_start_matches('/').to_string();
+            let query = data.query.get_or_insert_with(Query::default);
+            for (key, value) in rewrite_url.query_pairs() {
+                query.insert(key.into_owned(), QueryValue::String(value.into_owned()));
+            }
+        }
+
+        let result = this.source.get(&inner_path, Value::new(data.clone()));
+        let value = result.await?;
+        Ok(match &*value.content.await? {
+            // The data is requested for this source, so the middleware runs
+            // again when it's there.
+            ContentSourceContent::NeedData { vary, .. } if !has_data(&data, vary) => {
+                let mut vary = vary.clone();
+                vary.extend(&middleware_vary());
+                ContentSourceResult {
+                    specificity: value.specificity,
+                    content: ContentSourceContent::NeedData {
+                        source: self_vc.into(),
+                        path: path.to_string(),
+                        vary,
+                    }
+                    .cell(),
+                }
+                .cell()
+            }
+            ContentSourceContent::NotFound | ContentSourceContent::NeedData { .. } => result,
+            _ if response.headers.is_empty() => result,
+            _ => ContentSourceResult {
+                specificity: value.specificity,
+                content: ContentSourceContent::WithHeaders {
+                    content: value.content,
+                    headers: response.headers,
+                }
+                .cell(),
+            }
+            .cell(),
+        })
+    }
+}
+
+/// The response of middleware, with the internal `x-middleware-*` headers
+/// taken out of the headers of the response.
+struct MiddlewareResponse {
+    /// Continue with the requested path.
+    next: bool,
+    /// Continue with the path of this URL.
+    rewrite: Option<String>,
+    /// The headers of the request which are passed on, if the middleware
+    /// changed them.
+    request_headers: Option<BTreeMap<String, HeaderValue>>,
+    /// Arranged as contiguous (name, value) pairs.
+    headers: Vec<String>,
+}
+
+impl MiddlewareResponse {
+    fn new(result: &ProxyResult) -> Self {
+        let mut response = MiddlewareResponse {
+            next: false,
+            rewrite: None,
+            request_headers: None,
+            headers: Vec::new(),
+        };
+        let mut overridden = None;
+        let mut request_headers = BTreeMap::new();
+        for pair in result.headers.chunks_exact(2) {
+            let (name, value) = (pair[0].to_ascii_lowercase(), &pair[1]);
+            if name == "x-middleware-next" {
+                response.next = true;
+            } else if name == "x-middleware-rewrite" {
+                response.rewrite = Some(value.clone());
+            } else if name == "x-middleware-override-headers" {
+                overridden = Some(value.clone());
+            } else if let Some(name) = name.strip_prefix("x-middleware-request-") {
+                request_headers.insert(name.to_string(), HeaderValue::SingleString(value.clone()));
+            } else if !name.starts_with("x-middleware-") {
+                response.headers.push(name);
+                response.headers.push(value.clone());
+            }
+        }
+        if let Some(overridden) = overridden {
+            let names: Vec<&str> = overridden.split(',').map(str::trim).collect();
+            request_headers.retain(|name, _| names.contains(&name.as_str()));
+            response.request_headers = Some(request_headers);
+        }
+        response
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Introspectable for NodeMiddlewareContentSource {
+    #[turbo_tasks::function]
+    fn ty(&self) -> StringVc {
+        StringVc::cell("node middleware content source".to_string())
+    }
+
+    #[turbo_tasks::function]
+    async fn children(&self) -> Result<IntrospectableChildrenVc> {
+        Ok(IntrospectableChildrenVc::cell(
+            IntrospectableVc::resolve_from(self.source)
+                .await?
+                .map(|source| (StringVc::cell("source".to_string()), source))
+                .into_iter()
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use turbo_tasks_fs::rope::Rope;
+    use turbopack_dev_server::source::{HeaderValue, ProxyResult};
+
+    use super::MiddlewareResponse;
+
+    fn proxy_result(headers: &[&str]) -> ProxyResult {
+        ProxyResult {
+            status: 200,
+            headers: headers.iter().map(|header| header.to_string()).collect(),
+            body: Rope::default(),
+        }
+    }
+
+    #[test]
+    fn reads_middleware_headers() {
+        let response = MiddlewareResponse::new(&proxy_result(&[
+            "x-middleware-rewrite",
+            "http://localhost:3000/about?from=home",
+            "X-Custom",
+            "1",
+        ]));
+        assert!(!response.next);
+        assert_eq!(
+            response.rewrite.as_deref(),
+            Some("http://localhost:3000/about?from=home")
+        );
+        assert_eq!(response.request_headers, None);
+        assert_eq!(response.headers, vec!["x-custom", "1"]);
+
+        let response = MiddlewareResponse::new(&proxy_result(&[
+            "x-middleware-next",
+            "1",
+            "x-middleware-override-headers",
+            "authorization, x-user",
+            "x-middleware-request-x-user",
+            "jane",
+            "x-middleware-request-x-ignored",
+            "1",
+        ]));
+        assert!(response.next);
+        assert_eq!(
+            response.request_headers,
+            Some(BTreeMap::from([(
+                "x-user".to_string(),
+                HeaderValue::SingleString("jane".to_string())
+            )]))
+        );
+        assert!(response.headers.is_empty());
+    }
+}
@@ -26,7 +26,10 @@ use turbopack_ecmascript::chunk::EcmascriptChunkPlaceablesVc;
 use super::{
     external_asset_entrypoints, get_intermediate_asset, render_static, NodeEntryVc, RenderData,
 };
-use crate::path_regex::PathRegexVc;
+use crate::{
+    next_config::NextConfigVc, next_link::linked_routes, next_script::page_scripts,
+    path_regex::PathRegexVc,
+};
 
 /// Creates a content source that renders something in Node.js with the passed
 /// `entry` when it matches a `path_regex`. Once rendered it serves
@@ -42,6 +45,7 @@ pub fn create_node_rendered_source(
     entry: NodeEntryVc,
     runtime_entries: EcmascriptChunkPlaceablesVc,
     fallback_page: DevHtmlAssetVc,
+    next_config: NextConfigVc,
 ) -> ContentSourceVc {
     let source = NodeRenderContentSource {
         specificity,
@@ -50,6 +54,7 @@ pub fn create_node_rendered_source(
         entry,
         runtime_entries,
         fallback_page,
+        next_config,
     }
     .cell();
     ConditionalContentSourceVc::new(
@@ -72,6 +77,7 @@ struct NodeRenderContentSource {
     entry: NodeEntryVc,
     runtime_entries: EcmascriptChunkPlaceablesVc,
     fallback_page: DevHtmlAssetVc,
+    next_config: NextConfigVc,
 }
 
 impl NodeRenderContentSource {
@@ -143,21 +149,33 @@ impl ContentSource for NodeRenderContentSource {
         data: turbo_tasks::Value<ContentSourceData>,
     ) -> Result<ContentSourceResultVc> {
         let this = self_vc.await?;
-        if this.is_matching_path(path).await? {
-            if let Some(params) = this.get_matches(path).await? {
+        let (page_path, is_data_request) = page_path(path);
+        if this.is_matching_path(page_path).await? {
+            if let Some(params) = this.get_matches(page_path).await? {
                 let content = if data.method.is_some()
                     && data.url.is_some()
                     && data.headers.is_some()
                     && data.query.is_some()
                 {
                     let entry = this.entry.entry(data.clone()).await?;
+                    let (prefetch, scripts) = if is_data_request {
+                        (Vec::new(), Default::default())
+                    } else {
+                        (
+                            linked_routes(entry.module).await?.clone_value(),
+                            page_scripts(entry.module, this.next_config)
+                                .await?
+                                .clone_value(),
+                        )
+                    };
                     let asset = render_static(
-                        this.server_root.join(path),
+                        this.server_root.join(page_path),
                         entry.module,
                         this.runtime_entries,
                         this.fallback_page,
                         entry.chunking_context,
                         entry.intermediate_output_path,
+                        entry.trace,
                         RenderData {
                             params,
                             method: data
@@ -176,7 +194,11 @@ impl ContentSource for NodeRenderContentSource {
                                 .headers
                                 .clone()
                                 .ok_or_else(|| anyhow!("headers needs to be provided"))?,
-                            path: format!("/{path}"),
+                            path: format!("/{page_path}"),
+                            data: is_data_request,
+                            prefetch,
+                            scripts,
+                            i18n: this.next_config.await?.i18n.clone(),
                         }
                         .cell(),
                     );
@@ -206,6 +228,24 @@ impl ContentSource for NodeRenderContentSource {
     }
 }
 
+/// The prefix of the paths from which Next.js fetches the props of a page on
+/// client side navigation. The build id is always `development`.
+pub(crate) const DATA_ROUTE_PREFIX: &str = "_next/data/development/";
+
+/// Maps a `_next/data/development/<page>.json` path to the path of the page
+/// and whether the data of the page is requested. Other paths are returned as
+/// is.
+fn page_path(path: &str) -> (&str, bool) {
+    if let Some(page) = path
+        .strip_prefix(DATA_ROUTE_PREFIX)
+        .and_then(|page| page.strip_suffix(".json"))
+    {
+        (if page == "index" { "" } else { page }, true)
+    } else {
+        (path, false)
+    }
+}
+
 #[turbo_tasks::function]
 fn introspectable_type() -> StringVc {
     StringVc::cell("node render content source".to_string())
@@ -0,0 +1,436 @@
+//! Compiles the path patterns of the `redirects`, `rewrites` and `headers`
+//! options of `next.config.js`. They use the syntax of `path-to-regexp`, e. g.
+//! `/blog/:slug`, `/docs/:path*` or `/post/:id(\\d+)`, and match paths case
+//! insensitively and strictly, like Next.js does.
+
+use anyhow::{bail, Context, Result};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{primitives::Regex, trace::TraceRawVcs};
+
+/// The pattern of a parameter without a custom pattern, which matches a single
+/// segment.
+const DEFAULT_PATTERN: &str = "[^/#?]+?";
+
+/// A compiled `path-to-regexp` pattern.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub struct PathPattern {
+    regex: Regex,
+    /// The names of the parameters, in the order of their capture groups.
+    /// Unnamed parameters, e. g. `(.*)`, are named by their index.
+    params: Vec<String>,
+}
+
+/// A compiled regular expression of a `has` or `missing` condition of a
+/// route, which has to match the whole value.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+pub struct ValuePattern {
+    regex: Regex,
+}
+
+enum Token {
+    Text(String),
+    Param {
+        name: String,
+        prefix: String,
+        pattern: String,
+        modifier: Option<char>,
+    },
+}
+
+impl PathPattern {
+    pub fn new(pattern: &str) -> Result<Self> {
+        let mut regex_str = "(?i)^".to_string();
+        let mut params = Vec::new();
+        for token in tokenize(pattern)? {
+            match token {
+                Token::Text(text) => regex_str += &regex::escape(&text),
+                Token::Param {
+                    name,
+                    prefix,
+                    pattern,
+                    modifier,
+                } => {
+                    let prefix = regex::escape(&prefix);
+                    regex_str += &match modifier {
+                        None => format!("{prefix}({pattern})"),
+                        Some('?') => format!("(?:{prefix}({pattern}))?"),
+                        Some(modifier) => {
+                            let repeated =
+                                format!("(?:{prefix}((?:{pattern})(?:{prefix}(?:{pattern}))*))");
+                            if modifier == '*' {
+                                repeated + "?"
+                            } else {
+                                repeated
+                            }
+                        }
+                    };
+                    params.push(name);
+                }
+            }
+        }
+        regex_str += "$";
+        Ok(PathPattern {
+            regex: Regex(
+                regex::Regex::new(&regex_str)
+                    .with_context(|| format!("invalid pattern of the path {pattern}"))?,
+            ),
+            params,
+        })
+    }
+
+    /// Matches `path` and returns the values of the named parameters which
+    /// matched.
+    pub fn match_path(&self, path: &str) -> Option<IndexMap<String, String>> {
+        let captures = self.regex.captures(path)?;
+        Some(
+            self.params
+                .iter()
+                .enumerate()
+                .filter(|(_, name)| !is_unnamed(name))
+                .filter_map(|(index, name)| {
+                    let value = captures.get(index + 1)?;
+                    Some((name.clone(), value.as_str().to_string()))
+                })
+                .collect(),
+        )
+    }
+}
+
+impl ValuePattern {
+    pub fn new(pattern: &str) -> Result<Self> {
+        Ok(ValuePattern {
+            regex: Regex(
+                regex::Regex::new(&format!("^(?:{})$", js_regex(pattern)))
+                    .with_context(|| format!("invalid pattern of the value {pattern}"))?,
+            ),
+        })
+    }
+
+    /// Whether the pattern has named groups, which become parameters.
+    pub fn has_groups(&self) -> bool {
+        self.regex.capture_names().flatten().next().is_some()
+    }
+
+    /// Matches `value` and returns the values of the named groups which
+    /// matched.
+    pub fn match_value(&self, value: &str) -> Option<IndexMap<String, String>> {
+        let captures = self.regex.captures(value)?;
+        Some(
+            self.regex
+                .capture_names()
+                .flatten()
+                .filter_map(|name| {
+                    let value = captures.name(name)?;
+                    Some((name.to_string(), value.as_str().to_string()))
+                })
+                .collect(),
+        )
+    }
+}
+
+fn is_unnamed(name: &str) -> bool {
+    name.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+fn is_name_char(char: char) -> bool {
+    char.is_ascii_alphanumeric() || char == '_'
+}
+
+fn tokenize(pattern: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let mut unnamed = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let name = match chars[i] {
+            '\\' => {
+                text.extend(chars.get(i + 1));
+                i += 2;
+                continue;
+            }
+            ':' => {
+                let start = i + 1;
+                i = start;
+                while i < chars.len() && is_name_char(chars[i]) {
+                    i += 1;
+                }
+                if i == start {
+                    bail!("missing a parameter name at {start} in {pattern}");
+                }
+                chars[start..i].iter().collect()
+            }
+            '(' => {
+                unnamed += 1;
+                (unnamed - 1).to_string()
+            }
+            char => {
+                text.push(char);
+                i += 1;
+                continue;
+            }
+        };
+        let pattern = if chars.get(i) == Some(&'(') {
+            let (custom, end) = custom_pattern(&chars, i, pattern)?;
+            i = end;
+            custom
+        } else {
+            DEFAULT_PATTERN.to_string()
+        };
+        let modifier = match chars.get(i) {
+            Some(&modifier @ ('?' | '*' | '+')) => {
+                i += 1;
+                Some(modifier)
+            }
+            _ => None,
+        };
+        let prefix = match text.chars().last() {
+            Some(char @ ('/' | '.')) => {
+                text.pop();
+                char.to_string()
+            }
+            _ => String::new(),
+        };
+        if !text.is_empty() {
+            tokens.push(Token::Text(std::mem::take(&mut text)));
+        }
+        tokens.push(Token::Param {
+            name,
+            prefix,
+            pattern,
+            modifier,
+        });
+    }
+    if !text.is_empty() {
+        tokens.push(Token::Text(text));
+    }
+    Ok(tokens)
+}
+
+/// Reads the custom pattern in parentheses which starts at `start`, and
+/// returns it with the index after it. Like `path-to-regexp`, capturing groups
+/// are not allowed in it.
+fn custom_pattern(chars: &[char], start: usize, pattern: &str) -> Result<(String, usize)> {
+    let mut depth = 1;
+    let mut custom = String::new();
+    let mut i = start + 1;
+    if chars.get(i) == Some(&'?') {
+        bail!("the pattern at {i} in {pattern} cannot start with \"?\"");
+    }
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => {
+                custom.push('\\');
+                custom.extend(chars.get(i + 1));
+                i += 2;
+                continue;
+            }
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    if custom.is_empty() {
+                        bail!("missing a pattern at {start} in {pattern}");
+                    }
+                    return Ok((custom, i + 1));
+                }
+            }
+            '(' => {
+                depth += 1;
+                if chars.get(i + 1) != Some(&'?') {
+                    bail!("capturing groups are not allowed at {i} in {pattern}");
+                }
+            }
+            _ => {}
+        }
+        custom.push(chars[i]);
+        i += 1;
+    }
+    bail!("unbalanced pattern at {start} in {pattern}")
+}
+
+/// Replaces the `:name` parameters in a destination with their values. The
+/// modifiers of the parameters, e. g. `:path*`, are dropped. Optional
+/// parameters without a value are omitted together with their leading `/`.
+pub fn compile_destination(destination: &str, params: &IndexMap<String, String>) -> String {
+    let mut result = String::new();
+    let mut rest = destination;
+    while let Some(index) = rest.find(':') {
+        result += &rest[..index];
+        let after = &rest[index + 1..];
+        let name_len = after
+            .find(|char| !is_name_char(char))
+            .unwrap_or(after.len());
+        let modifier = destination_modifier(&after[name_len..]);
+        let after_param = &after[name_len + modifier.map_or(0, char::len_utf8)..];
+        match params.get(&after[..name_len]) {
+            Some(value) if name_len > 0 => {
+                result += value;
+                rest = after_param;
+            }
+            None if name_len > 0 && matches!(modifier, Some('?' | '*')) => {
+                // `/docs/:path*` becomes `/docs`, but `/:path*` stays `/`.
+                if result.len() > 1 && result.ends_with('/') {
+                    result.pop();
+                }
+                rest = after_param;
+            }
+            _ => {
+                result.push(':');
+                rest = after;
+            }
+        }
+    }
+    result + rest
+}
+
+/// The modifier after the name of a parameter in a destination. A `?` is
+/// only a modifier at the end of a path segment, as it starts the query
+/// otherwise.
+fn destination_modifier(rest: &str) -> Option<char> {
+    let mut chars = rest.chars();
+    match chars.next()? {
+        modifier @ ('*' | '+') => Some(modifier),
+        '?' if matches!(chars.next(), None | Some('/' | '#')) => Some('?'),
+        _ => None,
+    }
+}
+
+/// Converts a JavaScript regular expression to the syntax of the `regex`
+/// crate, which names groups with `(?P<name>...)`.
+pub fn js_regex(regex: &str) -> String {
+    let mut result = String::with_capacity(regex.len());
+    let mut rest = regex;
+    while let Some(index) = rest.find("(?<") {
+        result += &rest[..index];
+        rest = &rest[index..];
+        if rest.starts_with("(?<=") || rest.starts_with("(?<!") {
+            // Lookbehinds aren't supported, so compiling the regex fails.
+            result += "(?<";
+        } else {
+            result += "(?P<";
+        }
+        rest = &rest[3..];
+    }
+    result + rest
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+
+    use super::{compile_destination, js_regex, PathPattern, ValuePattern};
+
+    fn params(params: &[(&str, &str)]) -> IndexMap<String, String> {
+        params
+            .iter()
+            .map(|&(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn matches_paths() {
+        let pattern = PathPattern::new("/blog/:slug").unwrap();
+        assert_eq!(
+            pattern.match_path("/blog/hello"),
+            Some(params(&[("slug", "hello")]))
+        );
+        assert_eq!(
+            pattern.match_path("/BLOG/hello"),
+            Some(params(&[("slug", "hello")]))
+        );
+        assert_eq!(pattern.match_path("/blog/hello/world"), None);
+        assert_eq!(pattern.match_path("/blog/hello/"), None);
+        assert_eq!(pattern.match_path("/blog"), None);
+
+        let pattern = PathPattern::new("/docs/:path*").unwrap();
+        assert_eq!(pattern.match_path("/docs"), Some(params(&[])));
+        assert_eq!(
+            pattern.match_path("/docs/a/b"),
+            Some(params(&[("path", "a/b")]))
+        );
+
+        let pattern = PathPattern::new("/docs/:path+").unwrap();
+        assert_eq!(pattern.match_path("/docs"), None);
+
+        let pattern = PathPattern::new("/post/:id(\\d{1,})/:lang?").unwrap();
+        assert_eq!(
+            pattern.match_path("/post/12"),
+            Some(params(&[("id", "12")]))
+        );
+        assert_eq!(
+            pattern.match_path("/post/12/en"),
+            Some(params(&[("id", "12"), ("lang", "en")]))
+        );
+        assert_eq!(pattern.match_path("/post/abc"), None);
+
+        let pattern = PathPattern::new("/old/(.*)").unwrap();
+        assert_eq!(pattern.match_path("/old/a/b"), Some(params(&[])));
+
+        let pattern = PathPattern::new("/file\\:name.:ext").unwrap();
+        assert_eq!(
+            pattern.match_path("/file:name.json"),
+            Some(params(&[("ext", "json")]))
+        );
+
+        assert!(PathPattern::new("/:").is_err());
+        assert!(PathPattern::new("/:id((\\d+))").is_err());
+        assert!(PathPattern::new("/:id(\\d+").is_err());
+    }
+
+    #[test]
+    fn compiles_destinations() {
+        let params = params(&[("slug", "hello"), ("path", "a/b")]);
+        assert_eq!(compile_destination("/news/:slug", &params), "/news/hello");
+        assert_eq!(
+            compile_destination("/docs/:path*?from=:slug", &params),
+            "/docs/a/b?from=hello"
+        );
+        assert_eq!(
+            compile_destination("https://example.com:8080/:other", &params),
+            "https://example.com:8080/:other"
+        );
+
+        // Optional parameters without a value.
+        assert_eq!(
+            compile_destination("/new-docs/:path*", &params),
+            "/new-docs/a/b"
+        );
+        let params = IndexMap::new();
+        assert_eq!(
+            compile_destination("/new-docs/:path*", &params),
+            "/new-docs"
+        );
+        assert_eq!(compile_destination("/:path*", &params), "/");
+        assert_eq!(
+            compile_destination("/post/:lang?/comments", &params),
+            "/post/comments"
+        );
+        assert_eq!(
+            compile_destination("/docs/:path*?from=:slug", &params),
+            "/docs?from=:slug"
+        );
+        assert_eq!(compile_destination("/docs/:path+", &params), "/docs/:path+");
+    }
+
+    #[test]
+    fn converts_js_regexes() {
+        assert_eq!(js_regex("(?<lang>en|fr)"), "(?P<lang>en|fr)");
+        assert_eq!(js_regex("(?:a)(?<=b)"), "(?:a)(?<=b)");
+    }
+
+    #[test]
+    fn matches_whole_values() {
+        let pattern = ValuePattern::new("(?<lang>en|fr)").unwrap();
+        assert!(pattern.has_groups());
+        assert_eq!(pattern.match_value("fr"), Some(params(&[("lang", "fr")])));
+        assert_eq!(pattern.match_value("french"), None);
+
+        let pattern = ValuePattern::new("example\\.com").unwrap();
+        assert!(!pattern.has_groups());
+        assert_eq!(pattern.match_value("example.com"), Some(params(&[])));
+        assert_eq!(pattern.match_value("example.com.evil"), None);
+
+        assert!(ValuePattern::new("(?<=a)b").is_err());
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use turbo_tasks::primitives::{StringVc, StringsVc};
+use turbo_tasks_fs::{DirectoryContent, DirectoryEntry, FileSystemEntryType, FileSystemPathVc};
+use turbopack_core::issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc};
+use turbopack_dev_server::source::{static_assets::StaticAssetsContentSourceVc, ContentSourceVc};
+
+/// Creates a content source serving the files in the `public` directory at the
+/// root path, without any processing. Public files which are served at the
+/// same path as a page are reported as issues, and the public file is served.
+#[turbo_tasks::function]
+pub async fn create_public_source(project_path: FileSystemPathVc) -> Result<ContentSourceVc> {
+    let public_dir = project_path.join("public");
+
+    let mut routes = HashMap::new();
+    for dir in ["pages", "src/pages"] {
+        let pages_dir = project_path.join(dir);
+        if *pages_dir.get_type().await? == FileSystemEntryType::Directory {
+            for (path, file) in files_in_dir(pages_dir).await? {
+                if let Some(route) = page_route(&path) {
+                    routes.insert(route, file);
+                }
+            }
+            break;
+        }
+    }
+    for dir in ["app", "src/app"] {
+        let app_dir = project_path.join(dir);
+        if *app_dir.get_type().await? == FileSystemEntryType::Directory {
+            for (path, file) in files_in_dir(app_dir).await? {
+                if let Some(route) = app_route(&path) {
+                    routes.insert(route, file);
+                }
+            }
+            break;
+        }
+    }
+
+    if !routes.is_empty() {
+        for (path, public_file) in files_in_dir(public_dir).await? {
+            if let Some(&page_file) = routes.get(&format!("/{path}")) {
+                PublicFileConflictIssue {
+                    public_file,
+                    page_file,
+                }
+                .cell()
+                .as_issue()
+                .emit();
+            }
+        }
+    }
+
+    Ok(StaticAssetsContentSourceVc::new(String::new(), public_dir).into())
+}
+
+/// The routes of all files in the `public` directory. A static export starts
+/// from them in addition to the pages, so public files which aren't linked
+/// from a page are copied too, under their own name.
+#[turbo_tasks::function]
+pub async fn public_routes(project_path: FileSystemPathVc) -> Result<StringsVc> {
+    let mut routes: Vec<_> = files_in_dir(project_path.join("public"))
+        .await?
+        .into_iter()
+        .map(|(path, _)| path)
+        .collect();
+    routes.sort();
+    Ok(StringsVc::cell(routes))
+}
+
+/// Lists all files in `dir` and its subdirectories with their `/`-separated
+/// path relative to `dir`.
+async fn files_in_dir(dir: FileSystemPathVc) -> Result<Vec<(String, FileSystemPathVc)>> {
+    let mut files = Vec::new();
+    let mut queue = vec![(String::new(), dir)];
+    while let Some((prefix, dir)) = queue.pop() {
+        if let DirectoryContent::Entries(entries) = &*dir.read_dir().await? {
+            for (name, entry) in entries.iter() {
+                match entry {
+                    DirectoryEntry::File(path) | DirectoryEntry::Symlink(path) => {
+                        files.push((format!("{prefix}{name}"), *path))
+                    }
+                    DirectoryEntry::Directory(path) => {
+                        queue.push((format!("{prefix}{name}/"), *path))
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// The route of a file in the `pages` directory, e. g. `/blog` for
+/// `blog/index.tsx`. Dynamic routes match more than one path, and don't
+/// conflict with a single public file, so they are skipped like special pages
+/// such as `_app`.
+fn page_route(path: &str) -> Option<String> {
+    let (path, extension) = path.rsplit_once('.')?;
+    if !matches!(extension, "js" | "ts" | "jsx" | "tsx") || path.contains('[') {
+        return None;
+    }
+    let route = match path.rsplit_once('/') {
+        Some((dir, "index")) => dir,
+        None if path == "index" => "",
+        _ => path,
+    };
+    if route.split('/').any(|segment| segment.starts_with('_')) {
+        return None;
+    }
+    Some(format!("/{route}"))
+}
+
+/// The route of a `page` or `route` file in the `app` directory, e. g.
+/// `/about` for `(marketing)/about/page.tsx`. Route groups in parentheses
+/// aren't part of the route.
+fn app_route(path: &str) -> Option<String> {
+    let (path, extension) = path.rsplit_once('.')?;
+    if !matches!(extension, "js" | "ts" | "jsx" | "tsx") || path.contains('[') {
+        return None;
+    }
+    let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+    if name != "page" && name != "route" {
+        return None;
+    }
+    let segments: Vec<_> = dir
+        .split('/')
+        .filter(|segment| !segment.is_empty() && !segment.starts_with('('))
+        .collect();
+    Some(format!("/{}", segments.join("/")))
+}
+
+#[turbo_tasks::value(shared)]
+pub struct PublicFileConflictIssue {
+    pub public_file: FileSystemPathVc,
+    pub page_file: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for PublicFileConflictIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Error.into()
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.public_file
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("public".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("A conflicting public file and page file was detected".to_string())
+    }
+
+    #[turbo_tasks::function]
+    async fn description(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "The public file {} is served at the same path as the page {}. Only the public file \
+             is served, rename or remove one of them.",
+            self.public_file.await?.path,
+            self.page_file.await?.path,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{app_route, page_route};
+
+    #[test]
+    fn computes_page_routes() {
+        assert_eq!(page_route("index.tsx").as_deref(), Some("/"));
+        assert_eq!(page_route("about.js").as_deref(), Some("/about"));
+        assert_eq!(page_route("blog/index.jsx").as_deref(), Some("/blog"));
+        assert_eq!(page_route("api/hello.ts").as_deref(), Some("/api/hello"));
+        assert_eq!(page_route("blog/[slug].tsx"), None);
+        assert_eq!(page_route("_app.tsx"), None);
+        assert_eq!(page_route("styles.css"), None);
+    }
+
+    #[test]
+    fn computes_app_routes() {
+        assert_eq!(app_route("page.tsx").as_deref(), Some("/"));
+        assert_eq!(
+            app_route("(marketing)/about/page.js").as_deref(),
+            Some("/about")
+        );
+        assert_eq!(
+            app_route("api/hello/route.ts").as_deref(),
+            Some("/api/hello")
+        );
+        assert_eq!(app_route("about/layout.tsx"), None);
+        assert_eq!(app_route("blog/[slug]/page.tsx"), None);
+    }
+}
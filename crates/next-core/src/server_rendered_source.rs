@@ -31,8 +31,10 @@ use turbopack_ecmascript::{
 use turbopack_env::ProcessEnvAssetVc;
 
 use crate::{
+    edge_runtime::{check_edge_runtime, module_config},
     embed_js::{next_js_file, wrap_with_next_js_fs},
     fallback::get_fallback_page,
+    i18n::I18nContentSourceVc,
     next_client::{
         context::{
             add_next_transforms_to_pages, get_client_assets_path, get_client_chunking_context,
@@ -41,6 +43,8 @@ use crate::{
         },
         NextClientTransition,
     },
+    next_client_component::client_chunks_transition::NextClientChunksTransition,
+    next_config::load_next_config,
     next_server::{
         get_server_environment, get_server_module_options_context,
         get_server_resolve_options_context, ServerContextType,
@@ -55,6 +59,9 @@ use crate::{
 
 /// Create a content source serving the `pages` or `src/pages` directory as
 /// Next.js pages folder.
+///
+/// When `trace` is set, an nft.json listing all files needed to run a page is
+/// emitted next to each server entry in the `output_path`.
 #[turbo_tasks::function]
 pub async fn create_server_rendered_source(
     project_root: FileSystemPathVc,
@@ -62,6 +69,7 @@ pub async fn create_server_rendered_source(
     server_root: FileSystemPathVc,
     env: ProcessEnvVc,
     browserslist_query: &str,
+    trace: bool,
 ) -> Result<ContentSourceVc> {
     let project_path = wrap_with_next_js_fs(project_root);
 
@@ -100,12 +108,28 @@ pub async fn create_server_rendered_source(
     .cell()
     .into();
 
+    // The client chunks of the modules loaded by `next/dynamic`, see
+    // [crate::next_dynamic].
+    let next_client_chunks_transition = NextClientChunksTransition {
+        client_chunking_context,
+        client_module_options_context,
+        client_resolve_options_context,
+        client_environment,
+        server_root: server_root.join("_next"),
+    }
+    .cell()
+    .into();
+
     let mut transitions = HashMap::new();
     transitions.insert("next-client".to_string(), next_client_transition);
+    transitions.insert(
+        "next-client-chunks".to_string(),
+        next_client_chunks_transition,
+    );
     let context: AssetContextVc = ModuleAssetContextVc::new(
         TransitionsByNameVc::cell(transitions),
         get_server_environment(server_ty, env),
-        get_server_module_options_context(server_ty),
+        get_server_module_options_context(project_path, server_ty),
         get_server_resolve_options_context(project_path, server_ty, StringsVc::empty()),
     )
     .into();
@@ -128,15 +152,17 @@ pub async fn create_server_rendered_source(
         server_root,
         server_root.join("api"),
         output_path,
+        trace,
     );
     let fallback_source =
         AssetGraphContentSourceVc::new_eager(server_root, fallback_page.as_asset());
 
-    Ok(CombinedContentSource {
+    let source: ContentSourceVc = CombinedContentSource {
         sources: vec![server_rendered_source.into(), fallback_source.into()],
     }
     .cell()
-    .into())
+    .into();
+    Ok(I18nContentSourceVc::new(source, load_next_config(project_path)).into())
 }
 
 /// Handles a single page file in the pages directory
@@ -153,6 +179,7 @@ async fn create_server_rendered_source_for_file(
     server_path: FileSystemPathVc,
     is_api_path: BoolVc,
     intermediate_output_path: FileSystemPathVc,
+    trace: bool,
 ) -> Result<ContentSourceVc> {
     let source_asset = SourceAssetVc::new(page_file).into();
     let entry_asset = context.process(source_asset);
@@ -176,6 +203,7 @@ async fn create_server_rendered_source_for_file(
                 is_api_path,
                 chunking_context,
                 intermediate_output_path,
+                trace,
             }
             .cell()
             .into(),
@@ -192,11 +220,13 @@ async fn create_server_rendered_source_for_file(
                 is_api_path,
                 chunking_context,
                 intermediate_output_path,
+                trace,
             }
             .cell()
             .into(),
             runtime_entries,
             fallback_page,
+            load_next_config(context_path),
         )
     })
 }
@@ -218,6 +248,7 @@ async fn create_server_rendered_source_for_directory(
     server_path: FileSystemPathVc,
     server_api_path: FileSystemPathVc,
     intermediate_output_path: FileSystemPathVc,
+    trace: bool,
 ) -> Result<CombinedContentSourceVc> {
     let mut sources = vec![];
     let dir_content = input_dir.read_dir().await?;
@@ -271,6 +302,7 @@ async fn create_server_rendered_source_for_directory(
                                         dev_server_path,
                                         dev_server_path.is_inside(server_api_path),
                                         intermediate_output_path,
+                                        trace,
                                     ),
                                 ));
                             }
@@ -294,6 +326,7 @@ async fn create_server_rendered_source_for_directory(
                             server_path.join(name),
                             server_api_path,
                             intermediate_output_path.join(name),
+                            trace,
                         )
                         .into(),
                     ));
@@ -320,6 +353,7 @@ struct SsrEntry {
     is_api_path: BoolVc,
     chunking_context: ChunkingContextVc,
     intermediate_output_path: FileSystemPathVc,
+    trace: bool,
 }
 
 #[turbo_tasks::value_impl]
@@ -327,10 +361,22 @@ impl NodeEntry for SsrEntry {
     #[turbo_tasks::function]
     async fn entry(&self, _data: Value<ContentSourceData>) -> Result<NodeRenderingEntryVc> {
         let virtual_asset = if *self.is_api_path.await? {
-            VirtualAssetVc::new(
-                self.entry_asset.path().join("server-api.tsx"),
-                next_js_file("entry/server-api.tsx").into(),
-            )
+            let edge_module = match EcmascriptModuleAssetVc::resolve_from(self.entry_asset).await? {
+                Some(module) if module_config(module).await?.is_edge() => Some(module),
+                _ => None,
+            };
+            if let Some(module) = edge_module {
+                check_edge_runtime(module).await?;
+                VirtualAssetVc::new(
+                    self.entry_asset.path().join("edge-api.ts"),
+                    next_js_file("entry/edge-api.ts").into(),
+                )
+            } else {
+                VirtualAssetVc::new(
+                    self.entry_asset.path().join("server-api.tsx"),
+                    next_js_file("entry/server-api.tsx").into(),
+                )
+            }
         } else {
             VirtualAssetVc::new(
                 self.entry_asset.path().join("server-renderer.tsx"),
@@ -351,6 +397,7 @@ impl NodeEntry for SsrEntry {
             ),
             chunking_context: self.chunking_context,
             intermediate_output_path: self.intermediate_output_path,
+            trace: self.trace,
         }
         .cell())
     }
@@ -0,0 +1,370 @@
+//! Type checking of TypeScript projects with the `tsc` of the project.
+//!
+//! Bundling only strips types, so type errors are found separately: `tsc
+//! --noEmit` runs in the background when a TypeScript file changes. The
+//! diagnostics of the last finished run are reported as issues by a
+//! [TypeCheckContentSource], so they show up in the error overlay without
+//! waiting for a running type check.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use turbo_tasks::{
+    get_invalidator, primitives::StringVc, trace::TraceRawVcs, CompletionVc, Invalidator,
+    TransientInstance, Value,
+};
+use turbo_tasks_fs::{
+    to_sys_path, DirectoryContent, DirectoryEntry, FileContent, FileSystemPathVc,
+};
+use turbopack_core::{
+    issue::{
+        Issue, IssueSeverity, IssueSeverityVc, IssueSource, IssueSourceVc, IssueVc,
+        OptionIssueSourceVc,
+    },
+    source_asset::SourceAssetVc,
+    source_pos::SourcePos,
+};
+use turbopack_dev_server::source::{
+    ContentSource, ContentSourceData, ContentSourceResultVc, ContentSourceVc,
+};
+
+/// A diagnostic reported by `tsc`.
+#[derive(PartialEq, Eq, Clone, Debug, TraceRawVcs, Serialize, Deserialize)]
+pub struct TsDiagnostic {
+    /// The `/`-separated path of the file relative to the project directory.
+    /// `None` for diagnostics about the configuration.
+    pub path: Option<String>,
+    /// The 0-indexed position in the file.
+    pub line: usize,
+    pub column: usize,
+    pub code: u32,
+    pub message: String,
+    pub is_error: bool,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct TsDiagnostics(Vec<TsDiagnostic>);
+
+/// Runs `tsc --noEmit` in the project directory, if the project has a
+/// `tsconfig.json` and TypeScript is installed. It runs again when a
+/// TypeScript file of the project changes.
+#[turbo_tasks::function]
+pub async fn type_check(project_path: FileSystemPathVc) -> Result<TsDiagnosticsVc> {
+    let tsconfig = project_path.join("tsconfig.json");
+    if matches!(&*tsconfig.read().await?, FileContent::NotFound) {
+        return Ok(TsDiagnosticsVc::cell(Vec::new()));
+    }
+    typescript_sources(project_path).await?;
+
+    let project_dir = to_sys_path(project_path)
+        .await?
+        .context("type checking requires the project to be on disk")?;
+    let tsc = match find_tsc(&project_dir) {
+        Some(tsc) => tsc,
+        None => {
+            TypeCheckIssue {
+                path: tsconfig,
+                title: "TypeScript is not installed".to_string(),
+                message: "Install the typescript package to type check the project.".to_string(),
+                severity: IssueSeverity::Warning.into(),
+                source: None,
+            }
+            .cell()
+            .as_issue()
+            .emit();
+            return Ok(TsDiagnosticsVc::cell(Vec::new()));
+        }
+    };
+    let output = Command::new("node")
+        .current_dir(&project_dir)
+        .arg(tsc)
+        .args(["--noEmit", "--pretty", "false"])
+        .output()
+        .await
+        .context("running tsc")?;
+    Ok(TsDiagnosticsVc::cell(parse_tsc_output(
+        &String::from_utf8_lossy(&output.stdout),
+    )))
+}
+
+/// Reads all TypeScript files and configs in `dir`, so the type check depends
+/// on them.
+#[turbo_tasks::function]
+async fn typescript_sources(dir: FileSystemPathVc) -> Result<CompletionVc> {
+    let mut completions = Vec::new();
+    if let DirectoryContent::Entries(entries) = &*dir.read_dir().await? {
+        for (name, entry) in entries.iter() {
+            if name.starts_with('.') || name == "node_modules" {
+                continue;
+            }
+            match entry {
+                DirectoryEntry::Directory(path) => completions.push(typescript_sources(*path)),
+                DirectoryEntry::File(path) => {
+                    let extension = name.rsplit_once('.').map(|(_, ext)| ext);
+                    if matches!(extension, Some("ts" | "tsx" | "mts" | "cts" | "json")) {
+                        path.read().await?;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    for completion in completions {
+        completion.await?;
+    }
+    Ok(CompletionVc::new())
+}
+
+/// Finds the `tsc` of the `typescript` package, which might be hoisted to the
+/// root of a workspace.
+fn find_tsc(project_dir: &Path) -> Option<PathBuf> {
+    project_dir
+        .ancestors()
+        .map(|dir| dir.join("node_modules/typescript/bin/tsc"))
+        .find(|tsc| tsc.is_file())
+}
+
+/// Parses the output of `tsc --pretty false`, e. g.
+/// `pages/index.tsx(3,7): error TS2322: Type 'string' is not assignable...`.
+/// Messages can continue on indented lines.
+fn parse_tsc_output(output: &str) -> Vec<TsDiagnostic> {
+    let mut diagnostics: Vec<TsDiagnostic> = Vec::new();
+    for line in output.lines() {
+        if line.starts_with(char::is_whitespace) {
+            if let Some(diagnostic) = diagnostics.last_mut() {
+                diagnostic.message.push('\n');
+                diagnostic.message.push_str(line);
+            }
+            continue;
+        }
+        let (location, rest) = match line.split_once("): ") {
+            Some((location, rest)) => match location.rsplit_once('(') {
+                Some((path, position)) => (Some((path, position)), rest),
+                None => continue,
+            },
+            None => (None, line),
+        };
+        let (category, rest) = match rest.split_once(" TS") {
+            Some(split) => split,
+            None => continue,
+        };
+        let (code, message) = match rest.split_once(": ") {
+            Some((code, message)) => match code.parse() {
+                Ok(code) => (code, message),
+                Err(_) => continue,
+            },
+            None => continue,
+        };
+        let (path, line, column) = match location {
+            Some((path, position)) => {
+                let (line, column) = position.split_once(',').unwrap_or((position, "1"));
+                (
+                    Some(path.replace('\\', "/")),
+                    line.parse::<usize>().unwrap_or(1).saturating_sub(1),
+                    column.parse::<usize>().unwrap_or(1).saturating_sub(1),
+                )
+            }
+            None => (None, 0, 0),
+        };
+        diagnostics.push(TsDiagnostic {
+            path,
+            line,
+            column,
+            code,
+            message: message.to_string(),
+            is_error: category == "error",
+        });
+    }
+    diagnostics
+}
+
+/// The diagnostics of the last finished type check. Tasks reading them are
+/// invalidated when a type check finishes.
+#[derive(Default)]
+pub struct TypeCheckResults {
+    inner: Mutex<(Vec<TsDiagnostic>, HashSet<Invalidator>)>,
+}
+
+impl TypeCheckResults {
+    pub fn set(&self, diagnostics: Vec<TsDiagnostic>) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.0 == diagnostics {
+            return;
+        }
+        inner.0 = diagnostics;
+        for invalidator in std::mem::take(&mut inner.1) {
+            invalidator.invalidate();
+        }
+    }
+
+    fn get(&self) -> Vec<TsDiagnostic> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.1.insert(get_invalidator());
+        inner.0.clone()
+    }
+}
+
+/// Reports the diagnostics of the last type check as issues of the content
+/// served by `source`.
+#[turbo_tasks::value(serialization = "none", eq = "manual", cell = "new")]
+pub struct TypeCheckContentSource {
+    source: ContentSourceVc,
+    project_path: FileSystemPathVc,
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    results: Arc<TypeCheckResults>,
+}
+
+#[turbo_tasks::value_impl]
+impl TypeCheckContentSourceVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        source: ContentSourceVc,
+        project_path: FileSystemPathVc,
+        results: TransientInstance<TypeCheckResults>,
+    ) -> Self {
+        TypeCheckContentSource {
+            source,
+            project_path,
+            results: results.into(),
+        }
+        .cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for TypeCheckContentSource {
+    #[turbo_tasks::function]
+    async fn get(
+        &self,
+        path: &str,
+        data: Value<ContentSourceData>,
+    ) -> Result<ContentSourceResultVc> {
+        for diagnostic in self.results.get() {
+            let (file, source) = match &diagnostic.path {
+                Some(path) => match *self.project_path.try_join(path).await? {
+                    Some(file) => {
+                        let position = SourcePos {
+                            line: diagnostic.line,
+                            column: diagnostic.column,
+                        };
+                        let source = IssueSource {
+                            asset: SourceAssetVc::new(file).into(),
+                            start: position,
+                            end: position,
+                        };
+                        (file, Some(source.cell()))
+                    }
+                    None => (self.project_path, None),
+                },
+                None => (self.project_path.join("tsconfig.json"), None),
+            };
+            TypeCheckIssue {
+                path: file,
+                title: format!("TS{}", diagnostic.code),
+                message: diagnostic.message,
+                severity: if diagnostic.is_error {
+                    IssueSeverity::Error.into()
+                } else {
+                    IssueSeverity::Warning.into()
+                },
+                source,
+            }
+            .cell()
+            .as_issue()
+            .emit();
+        }
+        Ok(self.source.get(path, data))
+    }
+}
+
+#[turbo_tasks::value(shared)]
+pub struct TypeCheckIssue {
+    pub path: FileSystemPathVc,
+    pub title: String,
+    pub message: String,
+    pub severity: IssueSeverityVc,
+    pub source: Option<IssueSourceVc>,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for TypeCheckIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        self.severity
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("type check".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(self.title.clone())
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(self.message.clone())
+    }
+
+    #[turbo_tasks::function]
+    fn source(&self) -> OptionIssueSourceVc {
+        OptionIssueSourceVc::cell(self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_tsc_output, TsDiagnostic};
+
+    #[test]
+    fn parses_tsc_output() {
+        let output = "pages/index.tsx(5,7): error TS2322: Type 'string' is not assignable to type \
+                      'number'.\nlib\\util.ts(1,1): error TS2345: Argument of type '{}' is not \
+                      assignable.\n  Property 'a' is missing in type '{}'.\nerror TS18003: No \
+                      inputs were found in config file.\n";
+        assert_eq!(
+            parse_tsc_output(output),
+            vec![
+                TsDiagnostic {
+                    path: Some("pages/index.tsx".to_string()),
+                    line: 4,
+                    column: 6,
+                    code: 2322,
+                    message: "Type 'string' is not assignable to type 'number'.".to_string(),
+                    is_error: true,
+                },
+                TsDiagnostic {
+                    path: Some("lib/util.ts".to_string()),
+                    line: 0,
+                    column: 0,
+                    code: 2345,
+                    message: "Argument of type '{}' is not assignable.\n  Property 'a' is missing \
+                              in type '{}'."
+                        .to_string(),
+                    is_error: true,
+                },
+                TsDiagnostic {
+                    path: None,
+                    line: 0,
+                    column: 0,
+                    code: 18003,
+                    message: "No inputs were found in config file.".to_string(),
+                    is_error: true,
+                },
+            ]
+        );
+    }
+}
@@ -1,6 +1,9 @@
+use std::collections::BTreeMap;
+
 use anyhow::{anyhow, bail, Result};
 use turbo_tasks::ValueToString;
 use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_dev_server::source::{ContentSourceData, ContentSourceDataVary, HeaderValue};
 
 use crate::path_regex::{PathRegexBuilder, PathRegexVc};
 
@@ -72,3 +75,22 @@ pub async fn regular_expression_for_path(
     }
     Ok(PathRegexVc::cell(path_regex.build()?))
 }
+
+/// The value of a request header, if it's valid UTF-8. Of headers which occur
+/// multiple times, the first value is returned.
+pub fn header<'a>(headers: &'a BTreeMap<String, HeaderValue>, name: &str) -> Option<&'a str> {
+    match headers.get(name)? {
+        HeaderValue::SingleString(value) => Some(value),
+        HeaderValue::MultiStrings(values) => values.first().map(|value| &**value),
+        _ => None,
+    }
+}
+
+/// Whether `data` has the values which `vary` requests.
+pub fn has_data(data: &ContentSourceData, vary: &ContentSourceDataVary) -> bool {
+    (!vary.method || data.method.is_some())
+        && (!vary.url || data.url.is_some())
+        && (vary.query.is_none() || data.query.is_some())
+        && (vary.headers.is_none() || data.headers.is_some())
+        && (!vary.body || data.body.is_some())
+}
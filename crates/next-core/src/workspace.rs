@@ -0,0 +1,184 @@
+//! Detection of the workspace (monorepo) a Next.js project belongs to.
+//!
+//! In a workspace, dependencies are usually hoisted into the `node_modules`
+//! directory of the workspace root and shared configs like a base
+//! `tsconfig.json` live outside of the project directory. The file system
+//! needs to be rooted at the workspace root so these can be accessed.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value as JsonValue;
+
+const LOCKFILES: [&str; 3] = ["pnpm-lock.yaml", "yarn.lock", "package-lock.json"];
+
+/// Finds the root directory of the workspace containing `project_dir`.
+///
+/// The nearest ancestor with a `pnpm-workspace.yaml` or a `package.json` with
+/// `workspaces`, which includes the project, is the workspace root. Otherwise
+/// the nearest directory containing a lockfile is used. Returns `None` if
+/// neither is found.
+pub fn find_workspace_root(project_dir: &Path) -> Option<PathBuf> {
+    let mut lockfile_dir = None;
+    for dir in project_dir.ancestors() {
+        let relative = project_dir
+            .strip_prefix(dir)
+            .ok()?
+            .to_string_lossy()
+            .replace('\\', "/");
+        if let Some(patterns) = workspace_patterns(dir) {
+            if relative.is_empty() || matches_workspace_patterns(&patterns, &relative) {
+                return Some(dir.to_path_buf());
+            }
+        }
+        if lockfile_dir.is_none() && LOCKFILES.iter().any(|name| dir.join(name).is_file()) {
+            lockfile_dir = Some(dir.to_path_buf());
+        }
+    }
+    lockfile_dir
+}
+
+/// Reads the patterns of the workspace packages defined in `dir`, if it's the
+/// root of a pnpm, yarn or npm workspace.
+fn workspace_patterns(dir: &Path) -> Option<Vec<String>> {
+    if let Ok(content) = fs::read_to_string(dir.join("pnpm-workspace.yaml")) {
+        return Some(parse_pnpm_workspace(&content));
+    }
+    let content = fs::read_to_string(dir.join("package.json")).ok()?;
+    let json: JsonValue = serde_json::from_str(&content).ok()?;
+    // Yarn also allows `{ "packages": [...], "nohoist": [...] }`.
+    let workspaces = match &json["workspaces"] {
+        JsonValue::Array(workspaces) => workspaces,
+        JsonValue::Object(workspaces) => workspaces.get("packages")?.as_array()?,
+        _ => return None,
+    };
+    Some(
+        workspaces
+            .iter()
+            .filter_map(|pattern| pattern.as_str().map(|s| s.to_string()))
+            .collect(),
+    )
+}
+
+/// Extracts the `packages` list of a `pnpm-workspace.yaml`. Only the block
+/// sequence form used by pnpm is supported, which avoids a YAML parser.
+fn parse_pnpm_workspace(content: &str) -> Vec<String> {
+    let mut patterns = Vec::new();
+    let mut in_packages = false;
+    for line in content.lines() {
+        let line = line.split(" #").next().unwrap_or_default().trim_end();
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) && !line.starts_with('-') {
+            in_packages = line.trim() == "packages:";
+            continue;
+        }
+        if in_packages {
+            if let Some(pattern) = line.trim().strip_prefix('-') {
+                let pattern = pattern.trim().trim_matches(|c| c == '"' || c == '\'');
+                if !pattern.is_empty() {
+                    patterns.push(pattern.to_string());
+                }
+            }
+        }
+    }
+    patterns
+}
+
+/// Checks if the `relative` path of a package matches the workspace patterns.
+/// Patterns prefixed with `!` exclude packages.
+fn matches_workspace_patterns(patterns: &[String], relative: &str) -> bool {
+    let path: Vec<&str> = relative.split('/').collect();
+    let mut matches = false;
+    for pattern in patterns {
+        let (exclude, pattern) = match pattern.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern.as_str()),
+        };
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+        let segments: Vec<&str> = pattern.split('/').collect();
+        if matches_segments(&segments, &path) {
+            matches = !exclude;
+        }
+    }
+    matches
+}
+
+fn matches_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (Some(&"**"), _) => {
+            matches_segments(&pattern[1..], path)
+                || (!path.is_empty() && matches_segments(pattern, &path[1..]))
+        }
+        (Some(segment), Some(name)) => {
+            matches_segment(segment, name) && matches_segments(&pattern[1..], &path[1..])
+        }
+        _ => false,
+    }
+}
+
+/// Matches a single path segment against a pattern segment containing `*`
+/// wildcards.
+fn matches_segment(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match name.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let parts: Vec<&str> = parts.collect();
+    let (last, middle) = match parts.split_last() {
+        Some(split) => split,
+        None => return rest.is_empty(),
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{matches_workspace_patterns, parse_pnpm_workspace};
+
+    #[test]
+    fn parses_pnpm_workspace() {
+        let content = r#"
+packages:
+  # all apps
+  - 'apps/*'
+  - "packages/**"
+  - '!**/test/**' # no tests
+catalog:
+  - react
+"#;
+        assert_eq!(
+            parse_pnpm_workspace(content),
+            vec!["apps/*", "packages/**", "!**/test/**"]
+        );
+    }
+
+    #[test]
+    fn matches_patterns() {
+        let patterns = vec![
+            "apps/*".to_string(),
+            "./packages/**".to_string(),
+            "sites/next-*".to_string(),
+            "!apps/legacy".to_string(),
+        ];
+        assert!(matches_workspace_patterns(&patterns, "apps/web"));
+        assert!(!matches_workspace_patterns(&patterns, "apps/web/nested"));
+        assert!(!matches_workspace_patterns(&patterns, "apps/legacy"));
+        assert!(matches_workspace_patterns(&patterns, "packages/ui/docs"));
+        assert!(matches_workspace_patterns(&patterns, "sites/next-blog"));
+        assert!(!matches_workspace_patterns(&patterns, "sites/blog"));
+        assert!(!matches_workspace_patterns(&patterns, "docs"));
+    }
+}
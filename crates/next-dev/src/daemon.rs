@@ -0,0 +1,259 @@
+use std::{
+    env::{args_os, current_exe},
+    fs::{self, File},
+    io::SeekFrom,
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufReader},
+    net::{unix::OwnedWriteHalf, UnixListener, UnixStream},
+    sync::Notify,
+};
+
+/// How long to wait for a spawned daemon to start listening.
+const START_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often attached clients are sent the new output of the daemon.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What a daemon reports about itself on its control socket.
+#[derive(Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub pid: u32,
+    pub address: String,
+    pub url: Option<String>,
+    pub uptime_secs: u64,
+    /// The arguments the daemon was started with, see [server_args].
+    pub args: Vec<String>,
+}
+
+/// The arguments of this process without the daemon flags, so a daemon and
+/// the invocation which started it have the same.
+fn server_args() -> Vec<String> {
+    args_os()
+        .skip(1)
+        .filter(|arg| *arg != "--daemon" && *arg != "--daemon-process")
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect()
+}
+
+/// The control socket of the daemon of a project. Only one daemon can run
+/// for a project.
+fn socket_path(project_dir: &str) -> PathBuf {
+    Path::new(project_dir).join(".next/cache/turbopack/daemon.sock")
+}
+
+fn log_path(project_dir: &str) -> PathBuf {
+    Path::new(project_dir).join(".next/cache/turbopack/daemon.log")
+}
+
+/// Sends `command` to the daemon of the project and returns its answer, or
+/// `None` when no daemon is running.
+async fn request(project_dir: &str, command: &str) -> Result<Option<String>> {
+    let mut stream = match UnixStream::connect(socket_path(project_dir)).await {
+        Ok(stream) => stream,
+        Err(_) => return Ok(None),
+    };
+    stream.write_all(format!("{command}\n").as_bytes()).await?;
+    let mut answer = String::new();
+    BufReader::new(stream).read_line(&mut answer).await?;
+    Ok(Some(answer))
+}
+
+pub async fn status(project_dir: &str) -> Result<Option<DaemonStatus>> {
+    Ok(match request(project_dir, "status").await? {
+        Some(answer) => Some(serde_json::from_str(&answer)?),
+        None => None,
+    })
+}
+
+/// Asks the daemon of the project to shut down. Returns `false` when no
+/// daemon is running.
+pub async fn stop(project_dir: &str) -> Result<bool> {
+    Ok(request(project_dir, "stop").await?.is_some())
+}
+
+/// A connection to the daemon of a project, which receives the output of the
+/// daemon. The daemon keeps running when it's dropped.
+pub struct Attachment {
+    pub status: DaemonStatus,
+    output: BufReader<UnixStream>,
+}
+
+impl Attachment {
+    /// Copies the output of the daemon to stdout until the daemon stops.
+    pub async fn forward_output(mut self) -> Result<()> {
+        tokio::io::copy(&mut self.output, &mut tokio::io::stdout()).await?;
+        Ok(())
+    }
+}
+
+/// Attaches to the daemon of the project, or returns `None` when no daemon is
+/// running.
+async fn attach(project_dir: &str) -> Result<Option<Attachment>> {
+    let mut stream = match UnixStream::connect(socket_path(project_dir)).await {
+        Ok(stream) => stream,
+        Err(_) => return Ok(None),
+    };
+    stream.write_all(b"attach\n").await?;
+    let mut output = BufReader::new(stream);
+    let mut status = String::new();
+    output.read_line(&mut status).await?;
+    Ok(Some(Attachment {
+        status: serde_json::from_str(&status)?,
+        output,
+    }))
+}
+
+/// Starts a daemon for the project by running this executable again with
+/// the same arguments in the background, unless one is running already. A
+/// running daemon must have been started with the same arguments.
+/// Returns the attachment to the daemon and whether it was already running.
+pub async fn start(project_dir: &str) -> Result<(Attachment, bool)> {
+    if let Some(attachment) = attach(project_dir).await? {
+        // Attaching would silently ignore a different port or other options.
+        if attachment.status.args != server_args() {
+            bail!(
+                "the daemon of {project_dir} was started with different options ({}), stop it \
+                 with --daemon-stop first",
+                attachment.status.args.join(" ")
+            );
+        }
+        return Ok((attachment, true));
+    }
+    let log_path = log_path(project_dir);
+    if let Some(dir) = log_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let log = File::create(&log_path)
+        .with_context(|| format!("creating the daemon log {}", log_path.display()))?;
+    let mut child = Command::new(current_exe()?)
+        .args(args_os().skip(1).filter(|arg| *arg != "--daemon"))
+        .arg("--daemon-process")
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        // Closing the terminal must not stop the daemon.
+        .process_group(0)
+        .spawn()
+        .context("spawning the daemon")?;
+    let start = Instant::now();
+    loop {
+        if let Some(attachment) = attach(project_dir).await? {
+            return Ok((attachment, false));
+        }
+        if let Some(exit_status) = child.try_wait()? {
+            bail!(
+                "the daemon exited with {exit_status} before it started, see {}",
+                log_path.display()
+            );
+        }
+        if start.elapsed() > START_TIMEOUT {
+            bail!(
+                "the daemon didn't start within {}s, see {}",
+                START_TIMEOUT.as_secs(),
+                log_path.display()
+            );
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+/// Answers `status`, `stop` and `attach` commands on the control socket of
+/// the project, in the daemon process. `stop_requested` is notified on
+/// `stop`.
+pub fn serve(
+    project_dir: &str,
+    address: String,
+    url: Option<String>,
+    stop_requested: Arc<Notify>,
+) -> Result<()> {
+    let socket_path = socket_path(project_dir);
+    if let Some(dir) = socket_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // A socket left over by a daemon which crashed would fail the bind.
+    let _ = fs::remove_file(&socket_path);
+    let listener = UnixListener::bind(&socket_path)
+        .with_context(|| format!("binding the daemon socket {}", socket_path.display()))?;
+    let log_path = log_path(project_dir);
+    let start = Instant::now();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let address = address.clone();
+            let url = url.clone();
+            let stop_requested = stop_requested.clone();
+            let log_path = log_path.clone();
+            // Attached clients stay connected, so every connection is handled
+            // concurrently.
+            tokio::spawn(async move {
+                let (read, mut write) = stream.into_split();
+                let mut read = BufReader::new(read);
+                let mut command = String::new();
+                read.read_line(&mut command).await?;
+                let status = || {
+                    serde_json::to_string(&DaemonStatus {
+                        pid: std::process::id(),
+                        address: address.clone(),
+                        url: url.clone(),
+                        uptime_secs: start.elapsed().as_secs(),
+                        args: server_args(),
+                    })
+                };
+                match command.trim() {
+                    "status" => {
+                        write
+                            .write_all(format!("{}\n", status()?).as_bytes())
+                            .await?
+                    }
+                    "stop" => {
+                        stop_requested.notify_one();
+                        write.write_all(b"stopping\n").await?;
+                    }
+                    "attach" => {
+                        write
+                            .write_all(format!("{}\n", status()?).as_bytes())
+                            .await?;
+                        tokio::select! {
+                            result = follow_log(&log_path, &mut write) => result?,
+                            // The client detached.
+                            _ = read.read_u8() => {}
+                        }
+                    }
+                    _ => write.write_all(b"unknown command\n").await?,
+                }
+                anyhow::Ok(())
+            });
+        }
+    });
+    Ok(())
+}
+
+/// Sends the output which the daemon writes to its log from now on.
+async fn follow_log(log_path: &Path, write: &mut OwnedWriteHalf) -> Result<()> {
+    let mut log = tokio::fs::File::open(log_path)
+        .await
+        .with_context(|| format!("opening the daemon log {}", log_path.display()))?;
+    log.seek(SeekFrom::End(0)).await?;
+    let mut buf = vec![0; 8192];
+    loop {
+        let len = log.read(&mut buf).await?;
+        if len == 0 {
+            tokio::time::sleep(FOLLOW_INTERVAL).await;
+        } else {
+            write.write_all(&buf[..len]).await?;
+        }
+    }
+}
+
+/// Removes the control socket once the daemon has stopped.
+pub fn cleanup(project_dir: &str) {
+    let _ = fs::remove_file(socket_path(project_dir));
+}
@@ -4,6 +4,8 @@ use std::{net::IpAddr, path::PathBuf};
 use clap::Parser;
 use turbopack_cli_utils::issue::IssueSeverityCliOption;
 
+const DEFAULT_PORT_RETRIES: u16 = 10;
+
 #[derive(Debug)]
 #[cfg_attr(feature = "cli", derive(Parser))]
 #[cfg_attr(feature = "cli", clap(author, version, about, long_about = None))]
@@ -18,7 +20,8 @@ pub struct DevServerOptions {
 
     /// The root directory of the project. Nothing outside of this directory can
     /// be accessed. e. g. the monorepo root.
-    /// If no directory is provided, `dir` will be used.
+    /// If no directory is provided, the root of the pnpm, yarn or npm workspace
+    /// containing `dir` will be used, falling back to `dir`.
     #[cfg_attr(feature = "cli", clap(long, value_parser))]
     #[cfg_attr(feature = "serializable", serde(default))]
     pub root: Option<PathBuf>,
@@ -34,7 +37,17 @@ pub struct DevServerOptions {
     #[cfg_attr(feature = "serializable", serde(default = "default_port"))]
     pub port: u16,
 
-    /// Hostname on which to start the application
+    /// How many of the following ports to try when the port is already in
+    /// use, e. g. 3001 to 3010 for port 3000.
+    #[cfg_attr(
+        feature = "cli",
+        clap(long, value_parser, default_value_t = DEFAULT_PORT_RETRIES)
+    )]
+    #[cfg_attr(feature = "serializable", serde(default = "default_port_retries"))]
+    pub port_retries: u16,
+
+    /// Hostname on which to start the application. `::` accepts IPv4 and
+    /// IPv6 connections.
     #[cfg_attr(
         feature = "cli",
         clap(short = 'H', long, value_parser, default_value = "0.0.0.0")
@@ -42,22 +55,80 @@ pub struct DevServerOptions {
     #[cfg_attr(feature = "serializable", serde(default = "default_host"))]
     pub hostname: IpAddr,
 
+    /// Listen on a unix domain socket at this path instead of a port, e. g.
+    /// for a reverse proxy. A socket file left behind by a previous server is
+    /// replaced.
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub unix_socket: Option<PathBuf>,
+
+    /// Allow requests for this host name, in addition to `localhost` and IP
+    /// addresses. Other host names are rejected to prevent DNS rebinding
+    /// attacks. A leading `.` allows all subdomains, e. g. `.example.com`.
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub allowed_host: Vec<String>,
+
+    /// Add CORS headers to responses for requests from other local origins,
+    /// e. g. another dev server which loads assets from this one.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub cors: bool,
+
+    /// Don't check the `Host` and `Origin` headers of requests, e. g. when the
+    /// server is reached through a proxy which rewrites them.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub disable_host_check: bool,
+
     /// Compile all, instead of only compiling referenced assets when their
     /// parent asset is requested
     #[cfg_attr(feature = "cli", clap(long))]
     #[cfg_attr(feature = "serializable", serde(default))]
     pub eager_compile: bool,
 
+    /// Write an nft.json next to each compiled server entry in `.next/server`,
+    /// which lists all files needed to run it, e. g. for serverless packaging
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub trace_server_entries: bool,
+
+    /// Lint the project with the built-in rules of eslint-plugin-next while
+    /// developing. Violations are reported as warnings.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub lint: bool,
+
+    /// Type check the project with `tsc` in the background while developing.
+    /// Type errors are shown in the error overlay, but don't block updates.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub type_check: bool,
+
     /// Display version of the binary. Noop if used in library mode.
     #[cfg_attr(feature = "cli", clap(long))]
     #[cfg_attr(feature = "serializable", serde(default))]
     pub display_version: bool,
 
     /// Don't open the browser automatically when the dev server has started.
+    /// Otherwise it's opened once the root route has been compiled.
     #[cfg_attr(feature = "cli", clap(long))]
     #[cfg_attr(feature = "serializable", serde(default))]
     pub no_open: bool,
 
+    /// Compile pages linked from served pages while the server is idle, so
+    /// navigating to them is faster.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub warmup: bool,
+
+    /// Get file changes from a running Watchman service instead of watching
+    /// the files directly. Useful in large repositories, which exceed the
+    /// number of files the OS can watch.
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    pub watchman: bool,
+
     #[cfg_attr(feature = "cli", clap(short, long))]
     #[cfg_attr(feature = "serializable", serde(default))]
     /// Filter by issue severity.
@@ -73,11 +144,87 @@ pub struct DevServerOptions {
     /// Expand the log details.
     pub log_detail: bool,
 
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Log every request with the time spent compiling and reading the
+    /// request, instead of only failed requests.
+    pub log_requests: bool,
+
+    #[cfg_attr(feature = "cli", clap(long, conflicts_with = "event_socket"))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Print machine readable events as JSON lines to stdout instead of the
+    /// log output, for editors and other tools.
+    pub json: bool,
+
+    #[cfg_attr(feature = "cli", clap(long, value_parser))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Write machine readable events as JSON lines to the unix socket at this
+    /// path, which must be listened on by the consumer.
+    pub event_socket: Option<PathBuf>,
+
     #[cfg_attr(feature = "cli", clap(long))]
     #[cfg_attr(feature = "serializable", serde(default))]
     /// Whether to enable full task stats recording in Turbo Engine.
     pub full_stats: bool,
 
+    #[cfg_attr(
+        feature = "cli",
+        clap(long, value_name = "PATH", num_args = 0..=1, default_missing_value = "")
+    )]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Log why tasks are recomputed, as the chain of invalidations from the
+    /// changed file to the recomputed task. With a path, only changes of
+    /// files whose path contains it are logged. The chains are also listed
+    /// at /__turbo_tasks__/invalidations.
+    pub trace_invalidation: Option<String>,
+
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Log functions of Turbo Engine which are executed multiple times for
+    /// equal arguments, because Vcs in their arguments aren't resolved. Slows
+    /// down compilation, so it's meant for debugging Turbopack itself.
+    pub detect_duplicate_work: bool,
+
+    #[cfg_attr(
+        feature = "cli",
+        clap(
+            long,
+            value_name = "DIR",
+            num_args = 0..=1,
+            default_missing_value = "out",
+            conflicts_with_all = ["daemon", "daemon_status", "daemon_stop"]
+        )
+    )]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Write a static export of the application to this directory, `out` by
+    /// default, instead of starting the server. Pages are exported starting
+    /// at `/`, following their links. Routes which need a server, like API
+    /// routes, are reported and skipped.
+    pub export: Option<PathBuf>,
+
+    #[cfg_attr(feature = "cli", clap(long))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Run the server in a background process, which keeps its caches when
+    /// the terminal is closed. Later invocations with `--daemon` and the same
+    /// options attach to the running daemon instead of starting another
+    /// server, and print its output until they are detached with Ctrl-C.
+    pub daemon: bool,
+
+    #[cfg_attr(feature = "cli", clap(long, conflicts_with_all = ["daemon", "daemon_stop"]))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Print the status of the daemon of the project.
+    pub daemon_status: bool,
+
+    #[cfg_attr(feature = "cli", clap(long, conflicts_with = "daemon"))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Stop the daemon of the project.
+    pub daemon_stop: bool,
+
+    #[cfg_attr(feature = "cli", clap(long, hide = true))]
+    #[cfg_attr(feature = "serializable", serde(default))]
+    /// Set in the background process started by `--daemon`.
+    pub daemon_process: bool,
+
     // Inherited options from next-dev, need revisit later.
     #[cfg_attr(feature = "cli", clap(long))]
     #[cfg_attr(feature = "serializable", serde(default))]
@@ -107,6 +254,11 @@ fn default_port() -> u16 {
         .unwrap_or(3000)
 }
 
+#[cfg(feature = "serializable")]
+fn default_port_retries() -> u16 {
+    DEFAULT_PORT_RETRIES
+}
+
 #[cfg(feature = "serializable")]
 fn default_host() -> IpAddr {
     IpAddr::V4(std::net::Ipv4Addr::new(0, 0, 0, 0))
@@ -1,15 +1,16 @@
-#![feature(future_join)]
 #![feature(min_specialization)]
 
+#[cfg(unix)]
+mod daemon;
 pub mod devserver_options;
 mod turbo_tasks_viz;
 
 use std::{
     collections::HashSet,
     env::current_dir,
-    future::{join, Future},
+    future::Future,
     net::{IpAddr, SocketAddr},
-    path::MAIN_SEPARATOR,
+    path::{Path, PathBuf, MAIN_SEPARATOR},
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -17,28 +18,45 @@ use std::{
 use anyhow::{anyhow, Context, Result};
 use devserver_options::DevServerOptions;
 use next_core::{
-    create_app_source, create_server_rendered_source, create_web_entry_source, env::load_env,
+    create_app_source, create_config_routes_source, create_middleware_source, create_public_source,
+    create_server_rendered_source, create_web_entry_source,
+    env::load_env,
+    next_lint::lint_project,
     source_map::NextSourceMapTraceContentSourceVc,
+    type_check::{type_check, TsDiagnosticsVc, TypeCheckContentSourceVc, TypeCheckResults},
+    workspace::find_workspace_root,
 };
 use owo_colors::OwoColorize;
+use tokio::{sync::Notify, task::JoinHandle};
 use turbo_tasks::{
-    primitives::StringsVc, util::FormatDuration, RawVc, StatsType, TransientInstance,
-    TransientValue, TurboTasks, TurboTasksBackendApi, Value,
+    primitives::StringsVc, registry, run_once, util::FormatDuration, CompletionVc, NothingVc,
+    RawVc, StatsType, TransientInstance, TransientValue, TurboTasks, TurboTasksBackendApi, Value,
+    WeakVc,
+};
+use turbo_tasks_fs::{
+    rope::Rope, DiskFileSystemOptions, DiskFileSystemVc, FileSystemPathVc, FileSystemVc,
+    WatchBackend,
 };
-use turbo_tasks_fs::{DiskFileSystemVc, FileSystemVc};
 use turbo_tasks_memory::MemoryBackend;
-use turbopack_cli_utils::issue::{ConsoleUi, ConsoleUiVc, LogOptions};
+use turbopack_cli_utils::{
+    events::{Event, EventSink},
+    issue::{ConsoleUi, ConsoleUiVc, IssueCounts, LogOptions},
+};
 use turbopack_core::{issue::IssueSeverity, resolve::parse::RequestVc};
 use turbopack_dev_server::{
+    export::{export, ExportReport},
     fs::DevServerFileSystemVc,
     introspect::IntrospectionSource,
-    source::{
-        combined::CombinedContentSource, router::RouterContentSource,
-        static_assets::StaticAssetsContentSourceVc, ContentSourceVc,
-    },
-    DevServer,
+    security::SecurityOptions,
+    source::{combined::CombinedContentSource, prefix::PrefixedContentSourceVc, ContentSourceVc},
+    warmup::{compile_route, WarmupOptions},
+    DevServer, ServerAddr, ShutdownHandle,
 };
 
+/// Strings and ropes with at least this many bytes are stored compressed in
+/// memory, e. g. source maps and generated code.
+const CELL_COMPRESSION_THRESHOLD: usize = 64 * 1024;
+
 pub struct NextDevServerBuilder {
     turbo_tasks: Arc<TurboTasks<MemoryBackend>>,
     project_dir: String,
@@ -46,13 +64,23 @@ pub struct NextDevServerBuilder {
     entry_requests: Vec<String>,
     server_component_externals: Vec<String>,
     eager_compile: bool,
+    trace_server_entries: bool,
+    lint: bool,
+    type_check: bool,
+    watch_backend: WatchBackend,
     hostname: Option<IpAddr>,
     port: Option<u16>,
+    unix_socket: Option<PathBuf>,
     browserslist_query: String,
     log_level: IssueSeverity,
     show_all: bool,
     log_detail: bool,
-    allow_retry: bool,
+    log_requests: bool,
+    port_retries: u16,
+    security: SecurityOptions,
+    warmup: bool,
+    json: bool,
+    events: Option<EventSink>,
 }
 
 impl NextDevServerBuilder {
@@ -68,15 +96,25 @@ impl NextDevServerBuilder {
             entry_requests: vec![],
             server_component_externals: vec![],
             eager_compile: false,
+            trace_server_entries: false,
+            lint: false,
+            type_check: false,
+            watch_backend: WatchBackend::Notify,
             hostname: None,
             port: None,
+            unix_socket: None,
             browserslist_query: "last 1 Chrome versions, last 1 Firefox versions, last 1 Safari \
                                  versions, last 1 Edge versions"
                 .to_owned(),
             log_level: IssueSeverity::Warning,
             show_all: false,
             log_detail: false,
-            allow_retry: false,
+            log_requests: false,
+            port_retries: 0,
+            security: SecurityOptions::default(),
+            warmup: false,
+            json: false,
+            events: None,
         }
     }
 
@@ -95,6 +133,26 @@ impl NextDevServerBuilder {
         self
     }
 
+    pub fn trace_server_entries(mut self, trace_server_entries: bool) -> NextDevServerBuilder {
+        self.trace_server_entries = trace_server_entries;
+        self
+    }
+
+    pub fn lint(mut self, lint: bool) -> NextDevServerBuilder {
+        self.lint = lint;
+        self
+    }
+
+    pub fn type_check(mut self, type_check: bool) -> NextDevServerBuilder {
+        self.type_check = type_check;
+        self
+    }
+
+    pub fn watch_backend(mut self, watch_backend: WatchBackend) -> NextDevServerBuilder {
+        self.watch_backend = watch_backend;
+        self
+    }
+
     pub fn hostname(mut self, hostname: IpAddr) -> NextDevServerBuilder {
         self.hostname = Some(hostname);
         self
@@ -105,6 +163,14 @@ impl NextDevServerBuilder {
         self
     }
 
+    /// Listens on a unix domain socket at `path` instead of a TCP port, e. g.
+    /// for a reverse proxy on the same machine. The hostname and port are
+    /// ignored then.
+    pub fn unix_socket(mut self, path: PathBuf) -> NextDevServerBuilder {
+        self.unix_socket = Some(path);
+        self
+    }
+
     pub fn browserslist_query(mut self, browserslist_query: String) -> NextDevServerBuilder {
         self.browserslist_query = browserslist_query;
         self
@@ -120,8 +186,30 @@ impl NextDevServerBuilder {
         self
     }
 
-    pub fn allow_retry(mut self, allow_retry: bool) -> NextDevServerBuilder {
-        self.allow_retry = allow_retry;
+    /// Tries up to `port_retries` of the following ports when the port is
+    /// already in use.
+    pub fn port_retries(mut self, port_retries: u16) -> NextDevServerBuilder {
+        self.port_retries = port_retries;
+        self
+    }
+
+    /// Allows requesting the server with `host`, in addition to `localhost`
+    /// and IP addresses. A leading `.` allows all subdomains.
+    pub fn allowed_host(mut self, host: String) -> NextDevServerBuilder {
+        self.security.allowed_hosts.push(host);
+        self
+    }
+
+    /// Adds CORS headers to responses for requests from other local origins.
+    pub fn cors(mut self, cors: bool) -> NextDevServerBuilder {
+        self.security.cors = cors;
+        self
+    }
+
+    /// Disables the checks of the `Host` and `Origin` headers of requests,
+    /// which protect from DNS rebinding attacks.
+    pub fn disable_host_check(mut self, disable_host_check: bool) -> NextDevServerBuilder {
+        self.security.disable_host_check = disable_host_check;
         self
     }
 
@@ -130,84 +218,184 @@ impl NextDevServerBuilder {
         self
     }
 
-    pub async fn build(self) -> Result<DevServer> {
-        let turbo_tasks = self.turbo_tasks;
+    pub fn log_requests(mut self, log_requests: bool) -> NextDevServerBuilder {
+        self.log_requests = log_requests;
+        self
+    }
+
+    pub fn warmup(mut self, warmup: bool) -> NextDevServerBuilder {
+        self.warmup = warmup;
+        self
+    }
+
+    pub fn json(mut self, json: bool) -> NextDevServerBuilder {
+        self.json = json;
+        self
+    }
+
+    /// Emits machine readable events to `events`, in addition to the log
+    /// output.
+    pub fn events(mut self, events: EventSink) -> NextDevServerBuilder {
+        self.events = Some(events);
+        self
+    }
 
-        let project_dir = self.project_dir;
-        let root_dir = self.root_dir;
-        let entry_requests = self.entry_requests;
-        let server_component_externals = self.server_component_externals;
+    /// Starts compiling the project in the background. Returns the console
+    /// which reports its issues and its content source.
+    #[allow(clippy::type_complexity)]
+    fn compile(
+        &mut self,
+    ) -> (
+        Arc<ConsoleUi>,
+        Arc<dyn Fn() -> ContentSourceVc + Send + Sync>,
+    ) {
+        let turbo_tasks = self.turbo_tasks.clone();
+
+        let project_dir = self.project_dir.clone();
+        let root_dir = self.root_dir.clone();
+        let entry_requests = self.entry_requests.clone();
+        let server_component_externals = self.server_component_externals.clone();
         let eager_compile = self.eager_compile;
+        let trace_server_entries = self.trace_server_entries;
+        let watchman = self.watch_backend == WatchBackend::Watchman;
         let show_all = self.show_all;
         let log_detail = self.log_detail;
-        let browserslist_query = self.browserslist_query;
+        let browserslist_query = self.browserslist_query.clone();
         let log_options = LogOptions {
             current_dir: current_dir().unwrap(),
             show_all,
             log_detail,
             log_level: self.log_level,
+            json: self.json,
+            log_requests: self.log_requests,
         };
-        let console_ui = Arc::new(ConsoleUi::new(log_options));
+        let mut console_ui = ConsoleUi::new(log_options);
+        if let Some(events) = self.events.take() {
+            console_ui = console_ui.with_events(events);
+        }
+        let console_ui = Arc::new(console_ui);
         let console_ui_to_dev_server = console_ui.clone();
 
-        let start_port = self.port.context("port must be set")?;
-        let host = self.hostname.context("hostname must be set")?;
+        if self.lint {
+            let root_dir = root_dir.clone();
+            let project_dir = project_dir.clone();
+            let console_ui = console_ui.clone();
+            turbo_tasks.spawn_root_task(move || {
+                let root_dir = root_dir.clone();
+                let project_dir = project_dir.clone();
+                let console_ui = console_ui.clone();
+                Box::pin(async move {
+                    lint(root_dir, project_dir, watchman, console_ui.into()).await?;
+                    Ok(NothingVc::new().into())
+                })
+            });
+        }
 
-        let mut err: Option<anyhow::Error> = None;
+        let type_check = self.type_check;
+        let type_check_results = Arc::new(TypeCheckResults::default());
+        if type_check {
+            let root_dir = root_dir.clone();
+            let project_dir = project_dir.clone();
+            let console_ui = console_ui.clone();
+            let type_check_results = type_check_results.clone();
+            turbo_tasks.spawn_root_task(move || {
+                let root_dir = root_dir.clone();
+                let project_dir = project_dir.clone();
+                let console_ui = console_ui.clone();
+                let type_check_results = type_check_results.clone();
+                Box::pin(async move {
+                    let diagnostics =
+                        type_check_project(root_dir, project_dir, watchman, console_ui.into())
+                            .await?;
+                    type_check_results.set(diagnostics.clone_value());
+                    Ok(NothingVc::new().into())
+                })
+            });
+        }
 
-        let tasks = turbo_tasks.clone();
-        let source = move || {
+        let source: Arc<dyn Fn() -> ContentSourceVc + Send + Sync> = Arc::new(move || {
             source(
                 root_dir.clone(),
                 project_dir.clone(),
                 entry_requests.clone(),
                 eager_compile,
+                watchman,
                 turbo_tasks.clone().into(),
                 console_ui.clone().into(),
                 browserslist_query.clone(),
                 server_component_externals.clone(),
+                trace_server_entries,
+                type_check,
+                type_check_results.clone().into(),
             )
-        };
+        });
 
-        // Retry to listen on the different port if the port is already in use.
-        for retry_count in 0..10 {
-            let current_port = start_port + retry_count;
-            let addr = SocketAddr::new(host, current_port);
+        (console_ui_to_dev_server, source)
+    }
+
+    /// Starts the server. The server runs until [NextDevServer::shutdown] is
+    /// called.
+    pub async fn build(mut self) -> Result<NextDevServer> {
+        let warmup_profile = Path::new(&self.project_dir).join(".next/cache/turbopack/warmup.json");
+        let (console_ui, source) = self.compile();
+        let tasks = self.turbo_tasks.clone();
 
-            let listen_result = DevServer::listen(
+        let listen = |addr: ServerAddr| {
+            DevServer::listen(
                 tasks.clone(),
-                source.clone(),
+                {
+                    let source = source.clone();
+                    move || source()
+                },
                 addr,
-                console_ui_to_dev_server.clone(),
+                console_ui.clone(),
+                self.warmup.then(|| WarmupOptions {
+                    profile: Some(warmup_profile.clone()),
+                    ..Default::default()
+                }),
+                self.security.clone(),
+            )
+            .map(|server| NextDevServer {
+                addr: server.addr,
+                turbo_tasks: tasks.clone(),
+                source: source.clone(),
+                console_ui: console_ui.clone(),
+                shutdown: server.shutdown,
+                future: Some(tokio::spawn(server.future)),
+            })
+        };
+
+        if let Some(path) = self.unix_socket {
+            #[cfg(unix)]
+            return listen(ServerAddr::Unix(path));
+            #[cfg(not(unix))]
+            anyhow::bail!(
+                "can't listen on {}, unix domain sockets are not supported on this platform",
+                path.display()
             );
+        }
 
-            match listen_result {
-                Ok(server) => {
-                    return Ok(server);
-                }
+        let start_port = self.port.context("port must be set")?;
+        let host = self.hostname.context("hostname must be set")?;
+
+        let mut err: Option<anyhow::Error> = None;
+
+        // Retry to listen on the different port if the port is already in use.
+        let end_port = start_port.saturating_add(self.port_retries);
+        for current_port in start_port..=end_port {
+            let addr = SocketAddr::new(host, current_port);
+            match listen(ServerAddr::Tcp(addr)) {
+                Ok(server) => return Ok(server),
                 Err(e) => {
-                    let should_retry = if self.allow_retry {
-                        // Returned error from `listen` is not `std::io::Error` but `anyhow::Error`,
-                        // so we need to access its source to check if it is
-                        // `std::io::ErrorKind::AddrInUse`.
-                        e.source()
-                            .map(|e| {
-                                e.source()
-                                    .map(|e| {
-                                        e.downcast_ref::<std::io::Error>()
-                                            .map(|e| e.kind() == std::io::ErrorKind::AddrInUse)
-                                            == Some(true)
-                                    })
-                                    .unwrap_or_else(|| false)
-                            })
-                            .unwrap_or_else(|| false)
-                    } else {
-                        false
-                    };
+                    let should_retry = current_port < end_port
+                        && e.chain().any(|e| {
+                            e.downcast_ref::<std::io::Error>()
+                                .map_or(false, |e| e.kind() == std::io::ErrorKind::AddrInUse)
+                        });
 
                     if !should_retry {
                         return Err(e);
-                    } else {
+                    } else if !self.json {
                         println!(
                             "{} - Port {} is in use, trying {} instead",
                             "warn ".yellow(),
@@ -223,6 +411,85 @@ impl NextDevServerBuilder {
 
         Err(err.expect("Should have an error if we get here"))
     }
+
+    /// Writes a static export of the project to `output_dir` instead of
+    /// serving it. The export starts at the root route and follows the links
+    /// of the exported pages.
+    pub async fn export(mut self, output_dir: PathBuf) -> Result<ExportReport> {
+        std::fs::create_dir_all(&output_dir)
+            .with_context(|| format!("can't create {}", output_dir.display()))?;
+        let output_dir = output_dir
+            .to_str()
+            .context("output directory contains invalid characters")?
+            .to_string();
+        let (console_ui, source) = self.compile();
+        run_once(self.turbo_tasks.clone(), async move {
+            let output_fs = DiskFileSystemVc::new("export".to_string(), output_dir);
+            export(
+                source(),
+                output_fs.root(),
+                vec![String::new()],
+                (*console_ui).clone().cell(),
+            )
+            .await
+        })
+        .await
+    }
+}
+
+/// A running next-dev server.
+pub struct NextDevServer {
+    addr: ServerAddr,
+    turbo_tasks: Arc<TurboTasks<MemoryBackend>>,
+    source: Arc<dyn Fn() -> ContentSourceVc + Send + Sync>,
+    console_ui: Arc<ConsoleUi>,
+    shutdown: ShutdownHandle,
+    future: Option<JoinHandle<Result<()>>>,
+}
+
+impl NextDevServer {
+    /// The address the server is listening on.
+    pub fn addr(&self) -> &ServerAddr {
+        &self.addr
+    }
+
+    /// Waits until the content source of the server has been compiled, so
+    /// requests don't need to wait for the initial compilation.
+    pub async fn wait_until_ready(&self) -> Result<()> {
+        let source = self.source.clone();
+        run_once(self.turbo_tasks.clone(), async move {
+            source().resolve_strongly_consistent().await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Compiles the route at `path`, like a request to it would, without
+    /// serving it. Fails when the compilation fails.
+    pub fn compile_route(&self, path: &str) -> impl Future<Output = Result<()>> + Send + 'static {
+        let source = self.source.clone();
+        let path = path.to_string();
+        run_once(self.turbo_tasks.clone(), async move {
+            compile_route(source(), path).await
+        })
+    }
+
+    /// Stops accepting connections. [NextDevServer::wait] returns once all
+    /// in-flight requests have been answered.
+    pub fn shutdown(&self) {
+        self.shutdown.shutdown();
+    }
+
+    /// Waits until the server has stopped, either because of
+    /// [NextDevServer::shutdown] or an error.
+    pub async fn wait(&mut self) -> Result<()> {
+        if let Some(future) = &mut self.future {
+            let result = future.await;
+            self.future = None;
+            result??;
+        }
+        Ok(())
+    }
 }
 
 async fn handle_issues<T: Into<RawVc>>(source: T, console_ui: ConsoleUiVc) -> Result<()> {
@@ -237,45 +504,126 @@ async fn handle_issues<T: Into<RawVc>>(source: T, console_ui: ConsoleUiVc) -> Re
     }
 }
 
+/// The project files. `output_dir` is relative to `project_dir` and isn't
+/// watched, since writing the output would trigger rebuilds.
 #[turbo_tasks::function]
-async fn project_fs(project_dir: &str, console_ui: ConsoleUiVc) -> Result<FileSystemVc> {
-    let disk_fs = DiskFileSystemVc::new("project".to_string(), project_dir.to_string());
+async fn project_fs(
+    project_dir: &str,
+    output_dir: &str,
+    watchman: bool,
+    console_ui: ConsoleUiVc,
+) -> Result<FileSystemVc> {
+    let disk_fs = DiskFileSystemVc::new_with_options(
+        "project".to_string(),
+        project_dir.to_string(),
+        Value::new(DiskFileSystemOptions {
+            ignore: vec![".git".to_string(), output_dir.to_string()],
+            max_depth: None,
+        }),
+    );
     handle_issues(disk_fs, console_ui).await?;
-    disk_fs.await?.start_watching()?;
+    disk_fs
+        .await?
+        .start_watching_with(watch_backend(watchman), None)?;
     Ok(disk_fs.into())
 }
 
+/// The `.next` directory of the project, where the output is written to.
 #[turbo_tasks::function]
 async fn output_fs(project_dir: &str, console_ui: ConsoleUiVc) -> Result<FileSystemVc> {
-    let disk_fs = DiskFileSystemVc::new("output".to_string(), project_dir.to_string());
+    let output_dir = Path::new(project_dir).join(".next");
+    let disk_fs = DiskFileSystemVc::new_output(
+        "output".to_string(),
+        output_dir.to_string_lossy().to_string(),
+    );
     handle_issues(disk_fs, console_ui).await?;
-    disk_fs.await?.start_watching()?;
     Ok(disk_fs.into())
 }
 
+/// [WatchBackend] isn't a valid task input, so tasks receive whether to use
+/// Watchman instead.
+fn watch_backend(watchman: bool) -> WatchBackend {
+    if watchman {
+        WatchBackend::Watchman
+    } else {
+        WatchBackend::Notify
+    }
+}
+
+/// The directory of the project in the project files.
+fn project_path(
+    root_dir: &str,
+    project_dir: &str,
+    watchman: bool,
+    console_ui: ConsoleUiVc,
+) -> FileSystemPathVc {
+    let project_relative = project_dir.strip_prefix(root_dir).unwrap();
+    let project_relative = project_relative
+        .strip_prefix(MAIN_SEPARATOR)
+        .unwrap_or(project_relative);
+    let output_dir = if project_relative.is_empty() {
+        ".next".to_string()
+    } else {
+        format!("{}/.next", project_relative.replace(MAIN_SEPARATOR, "/"))
+    };
+    let fs = project_fs(root_dir, &output_dir, watchman, console_ui);
+    fs.root().join(project_relative)
+}
+
+/// Lints the project with the built-in rules and displays the violations. It
+/// runs in its own root task, so it doesn't delay compilation and is repeated
+/// when files change.
+#[turbo_tasks::function]
+async fn lint(
+    root_dir: String,
+    project_dir: String,
+    watchman: bool,
+    console_ui: TransientInstance<ConsoleUi>,
+) -> Result<CompletionVc> {
+    let console_ui = (*console_ui).clone().cell();
+    let project_path = project_path(&root_dir, &project_dir, watchman, console_ui);
+    handle_issues(lint_project(project_path), console_ui).await?;
+    Ok(CompletionVc::new())
+}
+
+/// Type checks the project with `tsc`. It runs in its own root task, which
+/// passes the diagnostics to the [TypeCheckContentSourceVc] when it finishes.
+#[turbo_tasks::function]
+async fn type_check_project(
+    root_dir: String,
+    project_dir: String,
+    watchman: bool,
+    console_ui: TransientInstance<ConsoleUi>,
+) -> Result<TsDiagnosticsVc> {
+    let console_ui = (*console_ui).clone().cell();
+    let project_path = project_path(&root_dir, &project_dir, watchman, console_ui);
+    let diagnostics = type_check(project_path);
+    handle_issues(diagnostics, console_ui).await?;
+    Ok(diagnostics)
+}
+
 #[turbo_tasks::function]
 async fn source(
     root_dir: String,
     project_dir: String,
     entry_requests: Vec<String>,
     eager_compile: bool,
+    watchman: bool,
     turbo_tasks: TransientInstance<TurboTasks<MemoryBackend>>,
     console_ui: TransientInstance<ConsoleUi>,
     browserslist_query: String,
     server_component_externals: Vec<String>,
+    trace_server_entries: bool,
+    type_check: bool,
+    type_check_results: TransientInstance<TypeCheckResults>,
 ) -> Result<ContentSourceVc> {
     let console_ui = (*console_ui).clone().cell();
     let output_fs = output_fs(&project_dir, console_ui);
-    let fs = project_fs(&root_dir, console_ui);
-    let project_relative = project_dir.strip_prefix(&root_dir).unwrap();
-    let project_relative = project_relative
-        .strip_prefix(MAIN_SEPARATOR)
-        .unwrap_or(project_relative);
-    let project_path = fs.root().join(project_relative);
+    let project_path = project_path(&root_dir, &project_dir, watchman, console_ui);
 
     let env = load_env(project_path);
 
-    let output_root = output_fs.root().join("/.next/server");
+    let output_root = output_fs.root().join("server");
 
     let dev_server_fs = DevServerFileSystemVc::new().as_file_system();
     let dev_server_root = dev_server_fs.root();
@@ -297,6 +645,7 @@ async fn source(
         dev_server_root,
         env,
         &browserslist_query,
+        trace_server_entries,
     );
     let app_source = create_app_source(
         project_path,
@@ -305,41 +654,63 @@ async fn source(
         env,
         &browserslist_query,
         StringsVc::cell(server_component_externals),
+        trace_server_entries,
     );
     let viz = turbo_tasks_viz::TurboTasksSource {
         turbo_tasks: turbo_tasks.into(),
     }
     .cell()
     .into();
-    let static_source =
-        StaticAssetsContentSourceVc::new(String::new(), project_path.join("public")).into();
+    let static_source = create_public_source(project_path);
     let main_source = CombinedContentSource {
-        sources: vec![static_source, app_source, rendered_source, web_source],
+        sources: vec![
+            static_source.into(),
+            app_source,
+            rendered_source,
+            web_source,
+        ],
     }
     .cell();
     let introspect = IntrospectionSource {
-        roots: HashSet::from([main_source.into()]),
+        roots: HashSet::from([WeakVc::new(main_source.into())]),
     }
     .cell()
     .into();
     let source_map_trace = NextSourceMapTraceContentSourceVc::new(main_source.into()).into();
-    let source = RouterContentSource {
-        routes: vec![
-            ("__turbopack__/".to_string(), introspect),
-            ("__turbo_tasks__/".to_string(), viz),
-            (
+    let middleware_source = create_middleware_source(
+        project_path,
+        output_root.join("middleware"),
+        dev_server_root,
+        env,
+        main_source.into(),
+    );
+    let routed_source = create_config_routes_source(project_path, middleware_source);
+    let source = CombinedContentSource {
+        sources: vec![
+            PrefixedContentSourceVc::new("__turbopack__".to_string(), introspect).into(),
+            PrefixedContentSourceVc::new("__turbo_tasks__".to_string(), viz).into(),
+            PrefixedContentSourceVc::new(
                 "__nextjs_original-stack-frame".to_string(),
                 source_map_trace,
-            ),
+            )
+            .into(),
+            if type_check {
+                TypeCheckContentSourceVc::new(routed_source, project_path, type_check_results)
+                    .into()
+            } else {
+                routed_source
+            },
         ],
-        fallback: main_source.into(),
     }
     .cell()
     .into();
 
     handle_issues(dev_server_fs, console_ui).await?;
+    handle_issues(static_source, console_ui).await?;
     handle_issues(web_source, console_ui).await?;
     handle_issues(rendered_source, console_ui).await?;
+    handle_issues(middleware_source, console_ui).await?;
+    handle_issues(routed_source, console_ui).await?;
 
     Ok(source)
 }
@@ -347,6 +718,10 @@ async fn source(
 pub fn register() {
     next_core::register();
     include!(concat!(env!("OUT_DIR"), "/register.rs"));
+    registry::register_cell_compression::<turbo_tasks::primitives::String>(
+        CELL_COMPRESSION_THRESHOLD,
+    );
+    registry::register_cell_compression::<Rope>(CELL_COMPRESSION_THRESHOLD);
 }
 
 /// Start a devserver with the given options.
@@ -374,9 +749,18 @@ pub async fn start_server(options: &DevServerOptions) -> Result<()> {
             .context("root directory contains invalid characters")?
             .to_string()
     } else {
-        dir.clone()
+        find_workspace_root(Path::new(&dir))
+            .and_then(|root| root.to_str().map(|root| root.to_string()))
+            .unwrap_or_else(|| dir.clone())
     };
 
+    if options.daemon || options.daemon_status || options.daemon_stop {
+        return daemon_command(options, &dir).await;
+    }
+    #[cfg(unix)]
+    let project_dir = dir.clone();
+    let export_dir = options.export.as_ref().map(|out| Path::new(&dir).join(out));
+
     let tt = TurboTasks::new(MemoryBackend::new());
 
     let stats_type = match options.full_stats {
@@ -384,79 +768,340 @@ pub async fn start_server(options: &DevServerOptions) -> Result<()> {
         false => StatsType::Essential,
     };
     tt.set_stats_type(stats_type);
+    if let Some(filter) = &options.trace_invalidation {
+        tt.enable_invalidation_tracing((!filter.is_empty()).then(|| filter.clone()));
+    }
+    if options.detect_duplicate_work {
+        tt.enable_duplicate_work_detection();
+    }
 
     let tt_clone = tt.clone();
+    let tt_stop = tt.clone();
 
-    #[allow(unused_mut)]
     let mut server = NextDevServerBuilder::new(tt, dir, root_dir)
         .entry_request("src/index".into())
         .eager_compile(options.eager_compile)
+        .trace_server_entries(options.trace_server_entries)
+        .lint(options.lint)
+        .type_check(options.type_check)
+        .watch_backend(watch_backend(options.watchman))
         .hostname(options.hostname)
         .port(options.port)
+        .port_retries(options.port_retries)
+        .cors(options.cors)
+        .disable_host_check(options.disable_host_check)
         .log_detail(options.log_detail)
+        .log_requests(options.log_requests)
         .show_all(options.show_all)
+        .warmup(options.warmup)
+        .json(options.json)
         .log_level(
             options
                 .log_level
                 .map_or_else(|| IssueSeverity::Warning, |l| l.0),
         );
 
+    let events = if options.json {
+        Some(EventSink::stdout())
+    } else if let Some(event_socket) = &options.event_socket {
+        Some(EventSink::connect_unix(event_socket)?)
+    } else {
+        None
+    };
+    if let Some(events) = &events {
+        server = server.events(events.clone());
+    }
+    for host in &options.allowed_host {
+        server = server.allowed_host(host.clone());
+    }
+    if let Some(unix_socket) = &options.unix_socket {
+        server = server.unix_socket(unix_socket.clone());
+    }
+
     #[cfg(feature = "serializable")]
     {
-        server = server.allow_retry(options.allow_retry);
+        if !options.allow_retry {
+            server = server.port_retries(0);
+        }
 
         for package in options.server_components_external_packages.iter() {
             server = server.server_component_external(package.to_string());
         }
     }
 
-    let server = server.build().await?;
+    if let Some(export_dir) = export_dir {
+        let report = server.export(export_dir.clone()).await?;
+        if !options.json {
+            for route in &report.not_found {
+                println!(
+                    "{event_type} - /{route} is linked, but was not found",
+                    event_type = "warn ".yellow(),
+                );
+            }
+            println!(
+                "{event_type} - exported {pages} and {assets} to {dir} in {elapsed}",
+                event_type = "ready".green(),
+                pages = plural(report.pages.len(), "page"),
+                assets = plural(report.assets.len(), "asset"),
+                dir = export_dir.display(),
+                elapsed = FormatDuration(start.elapsed()),
+            );
+        }
+        return Ok(());
+    }
+
+    let mut server = server.build().await?;
+    let console_ui = server.console_ui.clone();
+    let json = options.json;
+    let stop_requested = Arc::new(Notify::new());
 
     {
-        let index_uri = if server.addr.ip().is_loopback() || server.addr.ip().is_unspecified() {
-            format!("http://localhost:{}", server.addr.port())
-        } else {
-            format!("http://{}", server.addr)
-        };
-        println!(
-            "{} - started server on {}:{}, url: {}",
-            "ready".green(),
-            server.addr.ip(),
-            server.addr.port(),
-            index_uri
-        );
-        if !options.no_open {
-            let _ = webbrowser::open(&index_uri);
+        let index_uri = server.addr.url();
+        if let Some(events) = &events {
+            events.emit(&Event::Ready {
+                address: server.addr.to_string(),
+                url: index_uri.as_deref(),
+            });
+        }
+        if !json {
+            match &index_uri {
+                Some(index_uri) => println!(
+                    "{} - started server on {}, url: {}",
+                    "ready".green(),
+                    server.addr,
+                    index_uri
+                ),
+                None => println!("{} - started server on {}", "ready".green(), server.addr),
+            }
+        }
+        #[cfg(unix)]
+        if options.daemon_process {
+            daemon::serve(
+                &project_dir,
+                server.addr.to_string(),
+                index_uri.clone(),
+                stop_requested.clone(),
+            )?;
+        }
+        if let (false, Some(index_uri)) = (options.no_open, index_uri) {
+            let compile_root = server.compile_route("");
+            tokio::spawn(async move {
+                // A failed compilation is shown in the browser like on any
+                // other request.
+                let _ = compile_root.await;
+                let _ = webbrowser::open(&index_uri);
+            });
         }
     }
 
     let stats_future = async move {
-        println!(
-            "{event_type} - initial compilation {start}",
-            event_type = "event".purple(),
-            start = FormatDuration(start.elapsed()),
-        );
+        if let Some(events) = &events {
+            events.emit(&Event::InitialCompilation {
+                duration_ms: start.elapsed().as_millis() as u64,
+            });
+        }
+        if !json {
+            println!(
+                "{event_type} - initial compilation {start}",
+                event_type = "event".purple(),
+                start = FormatDuration(start.elapsed()),
+            );
+        }
 
+        let mut printed_traces = 0;
+        let mut printed_reports = 0;
         loop {
             let update_future = profile_timeout(
                 tt_clone.as_ref(),
                 tt_clone.get_or_wait_update_info(Duration::from_millis(100)),
             );
 
-            let (elapsed, _count) = update_future.await;
-            println!(
-                "{event_type} - updated in {elapsed}",
-                event_type = "event".purple(),
-                elapsed = FormatDuration(elapsed),
-            );
+            let (elapsed, count) = update_future.await;
+            let IssueCounts { errors, warnings } = console_ui.issue_counts();
+            if let Some(events) = &events {
+                events.emit(&Event::Updated {
+                    duration_ms: elapsed.as_millis() as u64,
+                    tasks: count,
+                    errors,
+                    warnings,
+                });
+            }
+            if !json {
+                println!(
+                    "{event_type} - updated in {elapsed} ({count} tasks, {errors}, {warnings})",
+                    event_type = "event".purple(),
+                    elapsed = FormatDuration(elapsed),
+                    errors = plural(errors, "error"),
+                    warnings = plural(warnings, "warning"),
+                );
+                for trace in tt_clone.invalidation_traces() {
+                    if trace.id >= printed_traces {
+                        printed_traces = trace.id + 1;
+                        println!("{event_type} - {trace}", event_type = "trace".cyan());
+                    }
+                }
+                for report in tt_clone.duplicate_work().into_iter().skip(printed_reports) {
+                    printed_reports += 1;
+                    println!("{event_type} - {report}", event_type = "warn ".yellow());
+                }
+            }
         }
     };
 
-    join!(stats_future, async { server.future.await.unwrap() }).await;
+    let signal = tokio::select! {
+        _ = stats_future => unreachable!("the stats loop never ends"),
+        result = server.wait() => {
+            result?;
+            None
+        }
+        signal = async {
+            tokio::select! {
+                signal = shutdown_signal() => signal.map(Some),
+                _ = stop_requested.notified() => Ok(None),
+            }
+        } => {
+            let signal = signal?;
+            if !json {
+                println!(
+                    "{event_type} - shutting down, press Ctrl-C again to exit immediately",
+                    event_type = "event".purple(),
+                );
+            }
+            server.shutdown();
+            tokio::select! {
+                // Answer in-flight requests and let pending tasks finish, so the backend can
+                // write its state to disk consistently.
+                result = async {
+                    server.wait().await?;
+                    tt_stop.stop_and_wait().await;
+                    anyhow::Ok(())
+                } => result?,
+                signal = shutdown_signal() => std::process::exit(signal?.exit_code()),
+            }
+            signal
+        }
+    };
+
+    #[cfg(unix)]
+    if options.daemon_process {
+        daemon::cleanup(&project_dir);
+    }
+    if let Some(signal) = signal {
+        std::process::exit(signal.exit_code());
+    }
+    Ok(())
+}
 
+/// Handles `--daemon`, `--daemon-status` and `--daemon-stop`.
+#[cfg(unix)]
+async fn daemon_command(options: &DevServerOptions, dir: &str) -> Result<()> {
+    if options.daemon_stop {
+        if daemon::stop(dir).await? {
+            println!("{} - stopping the daemon", "event".purple());
+        } else {
+            println!("no daemon is running for {dir}");
+        }
+    } else if options.daemon_status {
+        match daemon::status(dir).await? {
+            Some(status) => println!(
+                "daemon running on {} (pid {}, up for {}s){}",
+                status.address,
+                status.pid,
+                status.uptime_secs,
+                status
+                    .url
+                    .map(|url| format!(", url: {url}"))
+                    .unwrap_or_default()
+            ),
+            None => println!("no daemon is running for {dir}"),
+        }
+    } else {
+        let (attachment, already_running) = daemon::start(dir).await?;
+        attach_to_daemon(attachment, already_running).await?;
+    }
     Ok(())
 }
 
+/// Prints the output of the daemon until it stops. Ctrl-C only detaches from
+/// the daemon, which keeps running.
+#[cfg(unix)]
+async fn attach_to_daemon(attachment: daemon::Attachment, already_running: bool) -> Result<()> {
+    println!(
+        "{} - {} daemon on {}{}, press Ctrl-C to detach",
+        "ready".green(),
+        if already_running {
+            "attached to the running"
+        } else {
+            "started"
+        },
+        attachment.status.address,
+        attachment
+            .status
+            .url
+            .as_ref()
+            .map(|url| format!(", url: {url}"))
+            .unwrap_or_default()
+    );
+    tokio::select! {
+        result = attachment.forward_output() => result?,
+        result = tokio::signal::ctrl_c() => result?,
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn daemon_command(_options: &DevServerOptions, _dir: &str) -> Result<()> {
+    anyhow::bail!("the daemon mode is only supported on unix platforms")
+}
+
+/// The signal by which the process was asked to terminate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ShutdownSignal {
+    Interrupt,
+    Terminate,
+}
+
+impl ShutdownSignal {
+    /// The exit code of a process which was terminated by the signal, 128
+    /// plus the number of the signal like in shells.
+    fn exit_code(self) -> i32 {
+        match self {
+            ShutdownSignal::Interrupt => 130,
+            ShutdownSignal::Terminate => 143,
+        }
+    }
+}
+
+/// Resolves when the process is asked to terminate, by Ctrl-C or SIGTERM.
+async fn shutdown_signal() -> Result<ShutdownSignal> {
+    #[cfg(unix)]
+    let signal = {
+        let mut terminate =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => {
+                result?;
+                ShutdownSignal::Interrupt
+            }
+            _ = terminate.recv() => ShutdownSignal::Terminate,
+        }
+    };
+    #[cfg(not(unix))]
+    let signal = {
+        tokio::signal::ctrl_c().await?;
+        ShutdownSignal::Interrupt
+    };
+    Ok(signal)
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
 #[cfg(feature = "profile")]
 // When profiling, exits the process when no new updates have been received for
 // a given timeout and there are no more tasks in progress.
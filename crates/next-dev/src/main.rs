@@ -16,6 +16,8 @@ fn main() -> Result<()> {
 #[tokio::main]
 #[cfg(feature = "cli")]
 async fn main() -> Result<()> {
+    turbo_tasks::worker::run_worker_if_requested()?;
+
     let options = next_dev::devserver_options::DevServerOptions::parse();
 
     if options.display_version {
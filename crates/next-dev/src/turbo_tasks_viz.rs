@@ -104,6 +104,14 @@ impl ContentSource for TurboTasksSource {
                     ));
                 }
             }
+            "invalidations" => {
+                let list = viz::invalidations::create_list(&tt.invalidation_traces());
+                viz::invalidations::wrap_html(&list)
+            }
+            "storage" => {
+                let table = viz::storage::create_table(&tt.backend().storage_stats());
+                viz::storage::wrap_html(&table)
+            }
             "reset" => {
                 let b = tt.backend();
                 b.with_all_cached_tasks(|task| {
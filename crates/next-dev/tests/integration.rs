@@ -129,7 +129,7 @@ async fn run_test(resource: &str) -> JestRunResult {
     let project_dir = workspace_root.join("crates/next-dev/tests");
     let workspace_root = workspace_root.to_string_lossy().to_string();
     let requested_addr = get_free_local_addr().unwrap();
-    let server = NextDevServerBuilder::new(
+    let mut server = NextDevServerBuilder::new(
         TurboTasks::new(MemoryBackend::new()),
         project_dir.to_string_lossy().to_string(),
         workspace_root,
@@ -148,12 +148,12 @@ async fn run_test(resource: &str) -> JestRunResult {
     println!(
         "{event_type} - server started at http://{address}",
         event_type = "ready".green(),
-        address = server.addr
+        address = server.addr()
     );
 
     tokio::select! {
-        r = run_browser(server.addr) => r.unwrap(),
-        _ = server.future => panic!("Never resolves"),
+        r = run_browser(server.addr().tcp().unwrap()) => r.unwrap(),
+        _ = server.wait() => panic!("Never resolves"),
     }
 }
 
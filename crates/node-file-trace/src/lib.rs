@@ -1,6 +1,5 @@
 #![feature(min_specialization)]
 
-mod nft_json;
 use std::{
     collections::{BTreeSet, HashMap},
     env::current_dir,
@@ -25,14 +24,15 @@ use turbo_tasks::{
     NothingVc, TaskId, TransientInstance, TransientValue, TurboTasks, TurboTasksBackendApi, Value,
 };
 use turbo_tasks_fs::{
-    glob::GlobVc, DirectoryEntry, DiskFileSystemVc, FileSystemVc, ReadGlobResultVc,
+    glob::GlobVc, DirectoryEntry, DiskFileSystemVc, FileSystemVc, FileVerification,
+    ReadGlobResultVc,
 };
 use turbo_tasks_memory::{
     stats::{ReferenceType, Stats},
     viz, MemoryBackend,
 };
 use turbopack::{
-    emit_asset, emit_with_completion, rebase::RebasedAssetVc,
+    emit_asset, emit_with_completion, nft_json::NftJsonAssetVc, rebase::RebasedAssetVc,
     resolve_options_context::ResolveOptionsContext, transition::TransitionsByNameVc,
     ModuleAssetContextVc,
 };
@@ -47,8 +47,6 @@ use turbopack_core::{
     source_asset::SourceAssetVc,
 };
 
-use crate::nft_json::NftJsonAssetVc;
-
 #[cfg(feature = "persistent_cache")]
 #[cfg_attr(feature = "cli", derive(clap::Args))]
 #[cfg_attr(
@@ -63,6 +61,36 @@ struct CacheArgs {
 
     #[clap(long)]
     cache_fully: bool,
+
+    /// Compare the content of all files read from the cache with the files on
+    /// disk, instead of only their size and modification time.
+    #[clap(long)]
+    cache_verify_content: bool,
+
+    /// Delete the cache before running.
+    #[clap(long)]
+    clean: bool,
+
+    /// Delete the cache before running when it's larger than this many
+    /// megabytes.
+    #[clap(long)]
+    cache_max_size_mb: Option<u64>,
+
+    /// Delete the cache before running when it hasn't been written for this
+    /// many days.
+    #[clap(long)]
+    cache_max_age_days: Option<u64>,
+}
+
+#[cfg(feature = "persistent_cache")]
+impl CacheArgs {
+    fn file_verification(&self) -> FileVerification {
+        if self.cache_verify_content {
+            FileVerification::Content
+        } else {
+            FileVerification::Metadata
+        }
+    }
 }
 
 #[cfg(not(feature = "persistent_cache"))]
@@ -75,6 +103,13 @@ struct CacheArgs {
 #[derive(Debug, Clone, Default)]
 pub struct CacheArgs {}
 
+#[cfg(not(feature = "persistent_cache"))]
+impl CacheArgs {
+    fn file_verification(&self) -> FileVerification {
+        FileVerification::Metadata
+    }
+}
+
 #[cfg_attr(feature = "cli", derive(clap::Args))]
 #[cfg_attr(
     feature = "node-api",
@@ -185,12 +220,19 @@ impl Args {
     }
 }
 
-async fn create_fs(name: &str, context: &str, watch: bool) -> Result<FileSystemVc> {
+async fn create_fs(
+    name: &str,
+    context: &str,
+    watch: bool,
+    verification: FileVerification,
+) -> Result<FileSystemVc> {
     let fs = DiskFileSystemVc::new(name.to_string(), context.to_string());
+    // A file system restored from the persistent cache still knows about the
+    // files read in the previous run, so only the changed ones are read again.
     if watch {
-        fs.await?.start_watching()?;
+        fs.await?.start_watching_and_verify(verification)?;
     } else {
-        fs.await?.invalidate();
+        fs.await?.invalidate_changed(verification);
     }
     Ok(fs.into())
 }
@@ -325,17 +367,36 @@ pub async fn start(args: Arc<Args>) -> Result<Vec<String>> {
     let &CommonArgs {
         visualize_graph,
         #[cfg(feature = "persistent_cache")]
-            cache: CacheArgs {
-            ref cache,
-            ref cache_fully,
-        },
+            cache:
+            CacheArgs {
+                ref cache,
+                ref cache_fully,
+                clean,
+                cache_max_size_mb,
+                cache_max_age_days,
+                ..
+            },
         ..
     } = args.common();
     #[cfg(feature = "persistent_cache")]
     if let Some(cache) = cache {
         use tokio::time::timeout;
         use turbo_tasks_memory::MemoryBackendWithPersistedGraph;
-        use turbo_tasks_rocksdb::RocksDbPersistedGraph;
+        use turbo_tasks_rocksdb::{clean_cache, evict, EvictionPolicy, RocksDbPersistedGraph};
+
+        let cache_path = Path::new(cache);
+        if clean {
+            let freed = clean_cache(cache_path)?;
+            println!("cleaned cache ({} MB freed)", freed / 1_000_000);
+        } else if let Some(reason) = evict(
+            cache_path,
+            EvictionPolicy {
+                max_size: cache_max_size_mb.map(|mb| mb * 1_000_000),
+                max_age: cache_max_age_days.map(|days| Duration::from_secs(days * 24 * 60 * 60)),
+            },
+        )? {
+            println!("evicted cache ({reason:?})");
+        }
 
         run(
             &args,
@@ -460,6 +521,8 @@ async fn run<B: Backend + 'static, F: Future<Output = ()>>(
         show_all,
         log_detail,
         log_level: log_level.map_or_else(|| IssueSeverity::Error, |l| l.0),
+        json: false,
+        log_requests: false,
     }));
     let task = tt.spawn_root_task(move || {
         let dir = dir.clone();
@@ -505,8 +568,10 @@ async fn main_operation(
         exact,
         ref context_directory,
         ref process_cwd,
+        ref cache,
         ..
     } = args.common();
+    let verification = cache.file_verification();
     let context = process_context(&dir, context_directory.as_ref()).unwrap();
     let process_cwd = process_cwd
         .clone()
@@ -515,7 +580,7 @@ async fn main_operation(
         Args::Print { common: _ } => {
             let input = process_input(&dir, &context, input).unwrap();
             let mut result = BTreeSet::new();
-            let fs = create_fs("context directory", &context, watch).await?;
+            let fs = create_fs("context directory", &context, watch, verification).await?;
             let modules = input_to_modules(fs, input, process_cwd, exact).await?;
             for module in modules.iter() {
                 let set = all_assets(*module);
@@ -531,7 +596,7 @@ async fn main_operation(
         }
         Args::Annotate { common: _ } => {
             let input = process_input(&dir, &context, input).unwrap();
-            let fs = create_fs("context directory", &context, watch).await?;
+            let fs = create_fs("context directory", &context, watch, verification).await?;
             let mut output_nft_assets = Vec::new();
             let mut emits = Vec::new();
             for module in input_to_modules(fs, input, process_cwd, exact)
@@ -539,7 +604,7 @@ async fn main_operation(
                 .iter()
             {
                 let nft_asset = NftJsonAssetVc::new(*module);
-                let path = nft_asset.path().await?.path.clone();
+                let path = nft_asset.path().await?.path.to_string();
                 output_nft_assets.push(path);
                 emits.push(emit_asset(nft_asset.into()));
             }
@@ -555,8 +620,8 @@ async fn main_operation(
         } => {
             let output = process_context(&dir, Some(output_directory)).unwrap();
             let input = process_input(&dir, &context, input).unwrap();
-            let fs = create_fs("context directory", &context, watch).await?;
-            let out_fs = create_fs("output directory", &output, watch).await?;
+            let fs = create_fs("context directory", &context, watch, verification).await?;
+            let out_fs = create_fs("output directory", &output, watch, verification).await?;
             let input_dir = fs.root();
             let output_dir = out_fs.root();
             let mut emits = Vec::new();
@@ -26,6 +26,7 @@ pub fn generate_register() {
         .map(PathBuf::from)
         .unwrap_or_else(|| crate_dir.clone());
     let crate_name = env::var("CARGO_PKG_NAME").unwrap();
+    let crate_version = env::var("CARGO_PKG_VERSION").unwrap();
     let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
 
     let src_dir = crate_dir.join("src");
@@ -87,10 +88,10 @@ pub fn generate_register() {
     }
 
     for (filename, entry) in entries {
-        // TODO hash src dir
-        let hash = "TODO";
-
-        let prefix = format!("{crate_name}@{hash}::");
+        // Global names are namespaced by crate and version, so two versions of a
+        // crate in the same build don't clash and persisted names of an
+        // outdated version are not resolved to changed items.
+        let prefix = format!("{crate_name}@{crate_version}::");
 
         let mut register_code = String::new();
         let mut values = HashMap::new();
@@ -28,8 +28,8 @@ impl EnvMapVc {
 #[turbo_tasks::value_trait]
 pub trait ProcessEnv {
     // TODO SECURITY: From security perspective it's not good that we read *all* env
-    // vars into the cache. This might store secrects into the persistent cache
-    // which we want to avoid.
+    // vars into the cache. [EnvMap]s are redacted, so they are not stored in
+    // the persistent cache, but the values read from them still might be.
     // Instead we should use only `read_prefix` to read all env vars with a specific
     // prefix.
     /// Reads all env variables into a Map
@@ -61,4 +61,6 @@ pub static GLOBAL_ENV_LOCK: Mutex<()> = Mutex::new(());
 pub fn register() {
     turbo_tasks::register();
     include!(concat!(env!("OUT_DIR"), "/register.rs"));
+    // Env maps contain all env variables, including secrets.
+    turbo_tasks::registry::register_redacted::<EnvMap>();
 }
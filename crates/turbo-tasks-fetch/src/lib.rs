@@ -0,0 +1,309 @@
+#![feature(min_specialization)]
+
+mod mock;
+mod remote_cache;
+
+use std::time::Duration;
+
+use anyhow::Result;
+use lazy_static::lazy_static;
+use turbo_tasks::{
+    get_invalidator, primitives::StringVc, with_concurrency_limit, ConcurrencyCategory, Value,
+};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::issue::{Issue, IssueSeverityVc, IssueVc};
+
+pub use self::{
+    mock::{mock_response, reset_mocks},
+    remote_cache::HttpRemoteCache,
+};
+
+pub fn register() {
+    turbo_tasks::register();
+    turbo_tasks_fs::register();
+    turbopack_core::register();
+    include!(concat!(env!("OUT_DIR"), "/register.rs"));
+}
+
+lazy_static! {
+    /// Shared between all requests, so connections are reused. Proxies are
+    /// configured from the `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and
+    /// `NO_PROXY` env vars.
+    static ref CLIENT: reqwest::Client = reqwest::Client::new();
+}
+
+/// Headers which are sent with a request.
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(Debug, Clone, Default, PartialOrd, Ord, Hash)]
+pub struct FetchHeaders(pub Vec<(String, String)>);
+
+/// How long a response is reused before the request is sent again.
+///
+/// Requests are always deduplicated and cached by turbo-tasks. The policy
+/// only decides when the cached response expires.
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(Debug, Clone, Copy, Default, PartialOrd, Ord, Hash)]
+pub enum FetchCachePolicy {
+    /// The response is reused until the process exits.
+    #[default]
+    Forever,
+    /// The response is fetched again after the given number of seconds.
+    MaxAge(u64),
+    /// Uses the `max-age` of the `Cache-Control` header of the response, and
+    /// reuses the response forever without one.
+    FromResponse,
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct FetchResult(Result<HttpResponseVc, FetchErrorVc>);
+
+#[turbo_tasks::value(shared)]
+#[derive(Debug)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: HttpResponseBodyVc,
+}
+
+#[turbo_tasks::value(shared)]
+#[derive(Debug)]
+pub struct HttpResponseBody(pub Vec<u8>);
+
+#[turbo_tasks::value_impl]
+impl HttpResponseBodyVc {
+    #[turbo_tasks::function]
+    pub async fn to_string(self) -> Result<StringVc> {
+        let this = &*self.await?;
+        Ok(StringVc::cell(
+            String::from_utf8_lossy(&this.0).into_owned(),
+        ))
+    }
+}
+
+/// Fetches `url` with a GET request. The response is cached forever, see
+/// [fetch_with_policy].
+#[turbo_tasks::function]
+pub fn fetch(url: StringVc, headers: Value<FetchHeaders>) -> FetchResultVc {
+    fetch_with_policy(url, headers, Value::new(FetchCachePolicy::Forever))
+}
+
+/// Fetches `url` with a GET request. Requests which fail or have an
+/// unsuccessful status are returned as [FetchError], which can be reported as
+/// an issue. Identical requests are only sent once.
+#[turbo_tasks::function]
+pub async fn fetch_with_policy(
+    url: StringVc,
+    headers: Value<FetchHeaders>,
+    cache_policy: Value<FetchCachePolicy>,
+) -> Result<FetchResultVc> {
+    let url = &*url.await?;
+    let response = match mock::get(url) {
+        Some(response) => response,
+        None => with_concurrency_limit(ConcurrencyCategory::Network, send(url, &headers.0)).await,
+    };
+    let response = match response {
+        Ok(response) => response,
+        Err(SendError { kind, detail }) => {
+            return Ok(FetchResultVc::cell(Err(FetchError {
+                url: StringVc::cell(url.clone()),
+                kind: kind.cell(),
+                detail: StringVc::cell(detail),
+            }
+            .cell())))
+        }
+    };
+
+    let max_age = match cache_policy.into_value() {
+        FetchCachePolicy::Forever => None,
+        FetchCachePolicy::MaxAge(seconds) => Some(seconds),
+        FetchCachePolicy::FromResponse => response.max_age,
+    };
+    if let Some(max_age) = max_age {
+        let invalidator = get_invalidator();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(max_age)).await;
+            invalidator.invalidate();
+        });
+    }
+
+    if !(200..300).contains(&response.status) {
+        return Ok(FetchResultVc::cell(Err(FetchError {
+            url: StringVc::cell(url.clone()),
+            kind: FetchErrorKind::Status(response.status).cell(),
+            detail: StringVc::cell(String::from_utf8_lossy(&response.body).into_owned()),
+        }
+        .cell())));
+    }
+
+    Ok(FetchResultVc::cell(Ok(HttpResponse {
+        status: response.status,
+        body: HttpResponseBody(response.body).cell(),
+    }
+    .cell())))
+}
+
+/// A response as received, before it's stored in cells.
+struct RawResponse {
+    status: u16,
+    body: Vec<u8>,
+    /// The `max-age` of the `Cache-Control` header, in seconds.
+    max_age: Option<u64>,
+}
+
+/// A request which didn't receive a response.
+struct SendError {
+    kind: FetchErrorKind,
+    detail: String,
+}
+
+impl From<reqwest::Error> for SendError {
+    fn from(error: reqwest::Error) -> Self {
+        let kind = if error.is_connect() {
+            FetchErrorKind::Connect
+        } else if error.is_timeout() {
+            FetchErrorKind::Timeout
+        } else {
+            FetchErrorKind::Other
+        };
+        SendError {
+            kind,
+            detail: error.to_string(),
+        }
+    }
+}
+
+async fn send(url: &str, headers: &[(String, String)]) -> Result<RawResponse, SendError> {
+    let mut request = CLIENT.get(url);
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    let response = request.send().await?;
+    let status = response.status().as_u16();
+    let max_age = response
+        .headers()
+        .get(reqwest::header::CACHE_CONTROL)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_max_age);
+    let body = response.bytes().await?.to_vec();
+    Ok(RawResponse {
+        status,
+        body,
+        max_age,
+    })
+}
+
+fn parse_max_age(cache_control: &str) -> Option<u64> {
+    cache_control.split(',').find_map(|directive| {
+        let (name, value) = directive.trim().split_once('=')?;
+        if name.eq_ignore_ascii_case("max-age") {
+            value.trim().parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+#[turbo_tasks::value(shared)]
+#[derive(Debug)]
+pub enum FetchErrorKind {
+    Connect,
+    Timeout,
+    Status(u16),
+    Other,
+}
+
+#[turbo_tasks::value(shared)]
+#[derive(Debug)]
+pub struct FetchError {
+    pub url: StringVc,
+    pub kind: FetchErrorKindVc,
+    pub detail: StringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl FetchErrorVc {
+    /// Creates an issue for the failed request, which was made on behalf of
+    /// `context`.
+    #[turbo_tasks::function]
+    pub async fn to_issue(
+        self,
+        severity: IssueSeverityVc,
+        context: FileSystemPathVc,
+    ) -> Result<FetchIssueVc> {
+        let this = &*self.await?;
+        Ok(FetchIssue {
+            context,
+            severity,
+            url: this.url,
+            kind: this.kind,
+            detail: this.detail,
+        }
+        .cell())
+    }
+}
+
+#[turbo_tasks::value(shared)]
+pub struct FetchIssue {
+    pub context: FileSystemPathVc,
+    pub severity: IssueSeverityVc,
+    pub url: StringVc,
+    pub kind: FetchErrorKindVc,
+    pub detail: StringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for FetchIssue {
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        self.severity
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("fetch".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Error while requesting resource".to_string())
+    }
+
+    #[turbo_tasks::function]
+    async fn description(&self) -> Result<StringVc> {
+        let url = &*self.url.await?;
+        Ok(StringVc::cell(match &*self.kind.await? {
+            FetchErrorKind::Connect => {
+                format!("There was an issue establishing a connection while requesting {url}.")
+            }
+            FetchErrorKind::Timeout => format!("Connection timed out when requesting {url}."),
+            FetchErrorKind::Status(status) => {
+                format!("Received response with status {status} when requesting {url}.")
+            }
+            FetchErrorKind::Other => format!("There was an issue requesting {url}."),
+        }))
+    }
+
+    #[turbo_tasks::function]
+    fn detail(&self) -> StringVc {
+        self.detail
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_max_age;
+
+    #[test]
+    fn test_parse_max_age() {
+        assert_eq!(parse_max_age("max-age=60"), Some(60));
+        assert_eq!(
+            parse_max_age("public, max-age=86400, immutable"),
+            Some(86400)
+        );
+        assert_eq!(parse_max_age("no-store"), None);
+    }
+}
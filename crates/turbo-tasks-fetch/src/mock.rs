@@ -0,0 +1,47 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use lazy_static::lazy_static;
+
+use crate::{FetchErrorKind, RawResponse, SendError};
+
+lazy_static! {
+    /// `None` until the first mock is added. Afterwards requests never go to
+    /// the network.
+    static ref MOCKS: Mutex<Option<HashMap<String, (u16, Vec<u8>)>>> = Mutex::new(None);
+}
+
+/// Responds to requests for `url` with the given response instead of sending
+/// them, e. g. in tests. Once a response is mocked, requests for URLs without
+/// a mock fail until [reset_mocks] is called.
+///
+/// Responses are cached by turbo-tasks, so mocks should be set up before the
+/// first request.
+pub fn mock_response(url: impl Into<String>, status: u16, body: impl Into<Vec<u8>>) {
+    MOCKS
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(url.into(), (status, body.into()));
+}
+
+/// Removes all mocks and sends requests to the network again.
+pub fn reset_mocks() {
+    *MOCKS.lock().unwrap() = None;
+}
+
+/// Returns the mocked response for `url`, or `None` when mocking is disabled.
+pub(crate) fn get(url: &str) -> Option<Result<RawResponse, SendError>> {
+    let mocks = MOCKS.lock().unwrap();
+    let mocks = mocks.as_ref()?;
+    Some(match mocks.get(url) {
+        Some((status, body)) => Ok(RawResponse {
+            status: *status,
+            body: body.clone(),
+            max_age: None,
+        }),
+        None => Err(SendError {
+            kind: FetchErrorKind::Connect,
+            detail: format!("no mock response for {url}"),
+        }),
+    })
+}
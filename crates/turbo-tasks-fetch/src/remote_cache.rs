@@ -0,0 +1,55 @@
+use reqwest::StatusCode;
+use turbo_tasks::remote_cache::{CacheFuture, RemoteCache};
+
+/// A [RemoteCache] on an HTTP server, e. g. a storage bucket. Entries are
+/// stored with `PUT {base_url}/{key}` and read with `GET {base_url}/{key}`,
+/// which responds with 404 for unknown keys.
+pub struct HttpRemoteCache {
+    base_url: String,
+    token: Option<String>,
+}
+
+impl HttpRemoteCache {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            token: None,
+        }
+    }
+
+    /// Sends `token` as bearer token with all requests.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    fn request(&self, method: reqwest::Method, key: &str) -> reqwest::RequestBuilder {
+        let request = crate::CLIENT.request(method, format!("{}/{}", self.base_url, key));
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+impl RemoteCache for HttpRemoteCache {
+    fn get(&self, key: &str) -> CacheFuture<'_, Option<Vec<u8>>> {
+        let request = self.request(reqwest::Method::GET, key);
+        Box::pin(async move {
+            let response = request.send().await?;
+            if response.status() == StatusCode::NOT_FOUND {
+                return anyhow::Ok(None);
+            }
+            let body = response.error_for_status()?.bytes().await?;
+            anyhow::Ok(Some(body.to_vec()))
+        })
+    }
+
+    fn put(&self, key: String, data: Vec<u8>) -> CacheFuture<'_, ()> {
+        let request = self.request(reqwest::Method::PUT, &key).body(data);
+        Box::pin(async move {
+            request.send().await?.error_for_status()?;
+            anyhow::Ok(())
+        })
+    }
+}
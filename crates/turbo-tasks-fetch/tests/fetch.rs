@@ -0,0 +1,40 @@
+#![feature(min_specialization)]
+
+use turbo_tasks::{primitives::StringVc, Value};
+use turbo_tasks_fetch::{fetch, mock_response, FetchErrorKind, FetchHeaders};
+use turbo_tasks_testing::{register, run};
+
+register!();
+
+#[tokio::test]
+async fn mocked_responses() {
+    turbo_tasks_fetch::register();
+    mock_response("https://example.com/ok", 200, "hello");
+    mock_response("https://example.com/missing", 404, "not found");
+    run! {
+        let result = &*fetch(
+            StringVc::cell("https://example.com/ok".to_string()),
+            Value::new(FetchHeaders::default()),
+        )
+        .await?;
+        let response = result.as_ref().unwrap().await?;
+        assert_eq!(response.status, 200);
+        assert_eq!(*response.body.to_string().await?, "hello");
+
+        let result = &*fetch(
+            StringVc::cell("https://example.com/missing".to_string()),
+            Value::new(FetchHeaders::default()),
+        )
+        .await?;
+        let error = result.as_ref().unwrap_err().await?;
+        assert_eq!(*error.kind.await?, FetchErrorKind::Status(404));
+
+        let result = &*fetch(
+            StringVc::cell("https://example.com/unmocked".to_string()),
+            Value::new(FetchHeaders::default()),
+        )
+        .await?;
+        let error = result.as_ref().unwrap_err().await?;
+        assert_eq!(*error.kind.await?, FetchErrorKind::Connect);
+    }
+}
@@ -0,0 +1,80 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use hyper::{
+    header::AUTHORIZATION,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use turbo_tasks::remote_cache::RemoteCache;
+use turbo_tasks_fetch::HttpRemoteCache;
+
+type Entries = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+/// Starts a server which stores entries below `/cache/`, like a storage
+/// bucket.
+fn serve(entries: Entries) -> SocketAddr {
+    let make_service = make_service_fn(move |_| {
+        let entries = entries.clone();
+        async move { anyhow::Ok(service_fn(move |request| handle(entries.clone(), request))) }
+    });
+    let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+    let addr = server.local_addr();
+    tokio::spawn(server);
+    addr
+}
+
+async fn handle(entries: Entries, request: Request<Body>) -> Result<Response<Body>> {
+    let status = |status| Response::builder().status(status).body(Body::empty());
+    if request
+        .headers()
+        .get(AUTHORIZATION)
+        .map(|value| value.as_bytes())
+        != Some(b"Bearer secret")
+    {
+        return Ok(status(StatusCode::UNAUTHORIZED)?);
+    }
+    let key = match request.uri().path().strip_prefix("/cache/") {
+        Some(key) => key.to_string(),
+        None => return Ok(status(StatusCode::NOT_FOUND)?),
+    };
+    Ok(match *request.method() {
+        Method::PUT => {
+            let body = hyper::body::to_bytes(request.into_body()).await?;
+            entries.lock().unwrap().insert(key, body.to_vec());
+            status(StatusCode::CREATED)?
+        }
+        Method::GET => match entries.lock().unwrap().get(&key) {
+            Some(data) => Response::new(Body::from(data.clone())),
+            None => status(StatusCode::NOT_FOUND)?,
+        },
+        _ => status(StatusCode::METHOD_NOT_ALLOWED)?,
+    })
+}
+
+#[tokio::test]
+async fn stores_and_reads_entries() -> Result<()> {
+    let entries = Entries::default();
+    let addr = serve(entries.clone());
+    let cache = HttpRemoteCache::new(format!("http://{addr}/cache/")).with_token("secret");
+
+    assert_eq!(cache.get("abc").await?, None);
+    cache.put("abc".to_string(), b"result".to_vec()).await?;
+    assert_eq!(cache.get("abc").await?, Some(b"result".to_vec()));
+    assert_eq!(entries.lock().unwrap().len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn fails_on_errors() -> Result<()> {
+    let addr = serve(Entries::default());
+    let cache = HttpRemoteCache::new(format!("http://{addr}/cache"));
+
+    assert!(cache.get("abc").await.is_err());
+    assert!(cache.put("abc".to_string(), Vec::new()).await.is_err());
+    Ok(())
+}
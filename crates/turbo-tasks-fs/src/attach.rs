@@ -31,7 +31,7 @@ impl AttachedFileSystemVc {
 
         Ok(AttachedFileSystem {
             root_fs: child_path.fs,
-            child_path: child_path.path.clone(),
+            child_path: child_path.path.to_string(),
             child_fs,
         }
         .cell())
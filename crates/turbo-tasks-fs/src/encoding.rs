@@ -0,0 +1,187 @@
+use std::borrow::Cow;
+
+use crate::rope::Rope;
+
+const UTF8_BOM: &[u8] = &[0xef, 0xbb, 0xbf];
+const UTF16_LE_BOM: &[u8] = &[0xff, 0xfe];
+const UTF16_BE_BOM: &[u8] = &[0xfe, 0xff];
+
+/// How many bytes at the start of a file are checked for NUL bytes to tell
+/// binary files apart from text, like git does.
+pub(crate) const BINARY_DETECTION_LENGTH: usize = 8000;
+
+/// The encoding of a file on disk.
+///
+/// The content of a [File](crate::File) is always UTF-8, files in other
+/// encodings are converted when read. The original encoding is kept in the
+/// [FileMeta](crate::FileMeta), so writing the file, e. g. when copying an
+/// asset, restores the original bytes.
+#[derive(Clone, Copy, Debug)]
+#[turbo_tasks::value(shared)]
+pub enum FileEncoding {
+    Utf8,
+    /// UTF-8 with a byte order mark, which is removed from the content.
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    /// Not text, the content is kept as is.
+    Binary,
+}
+
+impl Default for FileEncoding {
+    fn default() -> Self {
+        FileEncoding::Utf8
+    }
+}
+
+impl FileEncoding {
+    /// Detects the encoding of `bytes` from its byte order mark. Content
+    /// without one and without a NUL byte in the first
+    /// [BINARY_DETECTION_LENGTH] bytes is UTF-8. Otherwise it's only taken as
+    /// UTF-16 when most of the odd (little endian) or even (big endian) bytes
+    /// in that window are NUL and none of the others are, which is what ASCII
+    /// text looks like. Everything else, e. g. fonts or WebAssembly, is
+    /// binary.
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(UTF8_BOM) {
+            return FileEncoding::Utf8Bom;
+        }
+        if bytes.starts_with(UTF16_LE_BOM) {
+            return FileEncoding::Utf16Le;
+        }
+        if bytes.starts_with(UTF16_BE_BOM) {
+            return FileEncoding::Utf16Be;
+        }
+        let prefix = &bytes[..bytes.len().min(BINARY_DETECTION_LENGTH)];
+        if !prefix.contains(&0) {
+            return FileEncoding::Utf8;
+        }
+        if bytes.len() % 2 != 0 {
+            return FileEncoding::Binary;
+        }
+        let units = prefix.len() / 2;
+        let (mut even_nuls, mut odd_nuls) = (0, 0);
+        for unit in prefix.chunks_exact(2) {
+            even_nuls += (unit[0] == 0) as usize;
+            odd_nuls += (unit[1] == 0) as usize;
+        }
+        if even_nuls == 0 && odd_nuls * 2 > units {
+            FileEncoding::Utf16Le
+        } else if odd_nuls == 0 && even_nuls * 2 > units {
+            FileEncoding::Utf16Be
+        } else {
+            FileEncoding::Binary
+        }
+    }
+
+    /// Whether the content needs no conversion from or to this encoding.
+    pub fn is_verbatim(&self) -> bool {
+        matches!(self, FileEncoding::Utf8 | FileEncoding::Binary)
+    }
+
+    /// Converts `bytes` in this encoding to UTF-8, without a byte order mark.
+    /// Invalid UTF-16 sequences are replaced with U+FFFD.
+    pub fn decode<'a>(&self, bytes: &'a [u8]) -> Cow<'a, [u8]> {
+        match self {
+            FileEncoding::Utf8 | FileEncoding::Binary => Cow::Borrowed(bytes),
+            FileEncoding::Utf8Bom => Cow::Borrowed(bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes)),
+            FileEncoding::Utf16Le => decode_utf16(
+                bytes.strip_prefix(UTF16_LE_BOM).unwrap_or(bytes),
+                u16::from_le_bytes,
+            ),
+            FileEncoding::Utf16Be => decode_utf16(
+                bytes.strip_prefix(UTF16_BE_BOM).unwrap_or(bytes),
+                u16::from_be_bytes,
+            ),
+        }
+    }
+
+    /// Converts UTF-8 `content` back to this encoding. Returns `None` for
+    /// UTF-8 and binary content, which need no conversion.
+    pub fn encode(&self, content: &Rope) -> Option<Vec<u8>> {
+        if self.is_verbatim() {
+            return None;
+        }
+        let bytes = content.to_bytes();
+        match self {
+            FileEncoding::Utf8 | FileEncoding::Binary => None,
+            FileEncoding::Utf8Bom => Some([UTF8_BOM, &*bytes].concat()),
+            FileEncoding::Utf16Le => Some(encode_utf16(&bytes, UTF16_LE_BOM, u16::to_le_bytes)),
+            FileEncoding::Utf16Be => Some(encode_utf16(&bytes, UTF16_BE_BOM, u16::to_be_bytes)),
+        }
+    }
+}
+
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> Cow<'static, [u8]> {
+    let units = bytes
+        .chunks_exact(2)
+        .map(|unit| from_bytes([unit[0], unit[1]]));
+    let mut decoded: String = char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect();
+    if bytes.len() % 2 != 0 {
+        decoded.push(char::REPLACEMENT_CHARACTER);
+    }
+    Cow::Owned(decoded.into_bytes())
+}
+
+fn encode_utf16(bytes: &[u8], bom: &[u8], to_bytes: fn(u16) -> [u8; 2]) -> Vec<u8> {
+    let content = String::from_utf8_lossy(bytes);
+    let mut encoded = bom.to_vec();
+    for unit in content.encode_utf16() {
+        encoded.extend_from_slice(&to_bytes(unit));
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileEncoding;
+    use crate::rope::Rope;
+
+    #[test]
+    fn detects_encodings() {
+        assert_eq!(FileEncoding::detect(b"abc"), FileEncoding::Utf8);
+        assert_eq!(
+            FileEncoding::detect(b"\xef\xbb\xbfabc"),
+            FileEncoding::Utf8Bom
+        );
+        assert_eq!(FileEncoding::detect(b"\xff\xfea\0"), FileEncoding::Utf16Le);
+        assert_eq!(FileEncoding::detect(b"\xfe\xff\0a"), FileEncoding::Utf16Be);
+        assert_eq!(FileEncoding::detect(b"a\0b\0"), FileEncoding::Utf16Le);
+        assert_eq!(FileEncoding::detect(b"\0a\0b"), FileEncoding::Utf16Be);
+        assert_eq!(
+            FileEncoding::detect(b"\x89PNG\r\n\x1a\n\0\0"),
+            FileEncoding::Binary
+        );
+    }
+
+    #[test]
+    fn detects_binary_headers_as_binary() {
+        // TrueType font, which starts with the version 1.0 and the table count.
+        assert_eq!(
+            FileEncoding::detect(b"\0\x01\0\0\0\x0c\0\x80\0\x03\0\x40OS/2"),
+            FileEncoding::Binary
+        );
+        // WebAssembly module, which starts with `\0asm` and the version 1.
+        assert_eq!(
+            FileEncoding::detect(b"\0asm\x01\0\0\0\x01\x07\x01\x60\x02\x7f\x7f\x01"),
+            FileEncoding::Binary
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        for original in [
+            &b"\xef\xbb\xbfconst a = \"\xc3\xa4\";"[..],
+            b"\xff\xfea\0=\0\xe4\0",
+            b"\xfe\xff\0a\0=\0\xe4",
+        ] {
+            let encoding = FileEncoding::detect(original);
+            let decoded = encoding.decode(original);
+            assert!(std::str::from_utf8(&decoded).unwrap().contains('a'));
+            let encoded = encoding.encode(&Rope::from(decoded.into_owned()));
+            assert_eq!(encoded.as_deref(), Some(original));
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    collections::HashMap,
+    fs::Metadata,
+    io::ErrorKind,
+    path::Path,
+    sync::{LockResult, Mutex, MutexGuard},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+use turbo_tasks_hash::hash_xxh3_hash64;
+
+use crate::rope::Rope;
+
+/// How [crate::DiskFileSystem::invalidate_changed] decides whether a file
+/// changed since it was read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileVerification {
+    /// Only files with a different size or modification time are hashed
+    /// again. That's fast, but misses changes which preserve both, e. g.
+    /// when the modification time is restored by a tool.
+    Metadata,
+    /// All files are read and hashed again.
+    Content,
+}
+
+/// The size, modification time and content hash of a file at the time it was
+/// read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileFingerprint {
+    size: u64,
+    modified: Option<SystemTime>,
+    hash: u64,
+}
+
+impl FileFingerprint {
+    pub fn new(metadata: &Metadata, content: &Rope) -> Self {
+        Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            hash: hash_xxh3_hash64(content),
+        }
+    }
+
+    fn has_same_metadata(&self, metadata: &Metadata) -> bool {
+        self.modified.is_some()
+            && self.size == metadata.len()
+            && self.modified == metadata.modified().ok()
+    }
+
+    /// Compares the fingerprint with the file on disk. A file which is
+    /// missing now, or can't be read, is considered changed.
+    fn matches(&self, path: &Path, verification: FileVerification) -> bool {
+        let metadata = match std::fs::metadata(path) {
+            Ok(metadata) if metadata.is_file() => metadata,
+            _ => return false,
+        };
+        if verification == FileVerification::Metadata && self.has_same_metadata(&metadata) {
+            return true;
+        }
+        if self.size != metadata.len() {
+            return false;
+        }
+        match std::fs::read(path) {
+            Ok(content) => self.hash == hash_xxh3_hash64(&Rope::from(content)),
+            Err(_) => false,
+        }
+    }
+}
+
+/// The fingerprints of all files read by a [crate::DiskFileSystem], keyed
+/// like its invalidators. It's serialized with the file system, so a restored
+/// file system can tell which of the reads of a previous session are still
+/// valid.
+#[derive(Default, Serialize, Deserialize)]
+pub struct FingerprintMap {
+    /// `None` means the file didn't exist when it was read.
+    map: Mutex<HashMap<String, Option<FileFingerprint>>>,
+}
+
+impl FingerprintMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn lock(&self) -> LockResult<MutexGuard<'_, HashMap<String, Option<FileFingerprint>>>> {
+        self.map.lock()
+    }
+
+    pub fn insert(&self, key: String, fingerprint: Option<FileFingerprint>) {
+        self.map.lock().unwrap().insert(key, fingerprint);
+    }
+}
+
+/// Whether the file at `path` still matches the fingerprint recorded when it
+/// was read. Without a recorded fingerprint, the file is considered changed.
+pub fn is_unchanged(
+    path: &Path,
+    fingerprint: Option<&Option<FileFingerprint>>,
+    verification: FileVerification,
+) -> bool {
+    match fingerprint {
+        Some(Some(fingerprint)) => fingerprint.matches(path, verification),
+        Some(None) => matches!(
+            std::fs::symlink_metadata(path),
+            Err(e) if e.kind() == ErrorKind::NotFound
+        ),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{is_unchanged, FileFingerprint, FileVerification};
+    use crate::rope::Rope;
+
+    #[test]
+    fn test_fingerprint() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        fs::write(&path, "hello").unwrap();
+        let fingerprint = Some(Some(FileFingerprint::new(
+            &fs::metadata(&path).unwrap(),
+            &Rope::from(b"hello".to_vec()),
+        )));
+        let verifications = [FileVerification::Metadata, FileVerification::Content];
+
+        for verification in verifications {
+            assert!(is_unchanged(&path, fingerprint.as_ref(), verification));
+        }
+
+        // Writing the same content again might touch the modification time,
+        // but the hash still matches.
+        fs::write(&path, "hello").unwrap();
+        for verification in verifications {
+            assert!(is_unchanged(&path, fingerprint.as_ref(), verification));
+        }
+
+        fs::write(&path, "hello world").unwrap();
+        for verification in verifications {
+            assert!(!is_unchanged(&path, fingerprint.as_ref(), verification));
+        }
+
+        fs::remove_file(&path).unwrap();
+        for verification in verifications {
+            assert!(!is_unchanged(&path, fingerprint.as_ref(), verification));
+            assert!(is_unchanged(&path, Some(&None), verification));
+            assert!(!is_unchanged(&path, None, verification));
+        }
+    }
+}
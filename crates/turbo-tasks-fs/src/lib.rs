@@ -7,19 +7,22 @@
 
 pub mod attach;
 pub mod embed;
+mod encoding;
+mod fingerprint;
 pub mod glob;
-mod invalidator_map;
 mod read_glob;
 mod retry;
 pub mod rope;
 pub mod util;
+#[cfg(unix)]
+mod watchman;
+pub mod zip;
 
 use std::{
     collections::{HashMap, HashSet},
     fmt::{self, Debug, Display, Formatter},
     fs::FileType,
     io::{self, ErrorKind},
-    mem::take,
     path::{Path, PathBuf, MAIN_SEPARATOR},
     sync::{
         mpsc::{channel, RecvError, TryRecvError},
@@ -30,8 +33,10 @@ use std::{
 
 use anyhow::{anyhow, bail, Context, Result};
 use bitflags::bitflags;
-use glob::GlobVc;
-use invalidator_map::InvalidatorMap;
+pub use encoding::FileEncoding;
+pub use fingerprint::FileVerification;
+use fingerprint::{is_unchanged, FileFingerprint, FingerprintMap};
+use glob::{Glob, GlobVc};
 use jsonc_parser::{parse_to_serde_value, ParseOptions};
 use mime::Mime;
 use notify::{watcher, DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
@@ -39,21 +44,29 @@ use read_glob::read_glob;
 pub use read_glob::{ReadGlobResult, ReadGlobResultVc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tokio::{fs, io::AsyncReadExt};
+use tokio::{
+    fs,
+    io::{AsyncReadExt, AsyncWriteExt},
+};
 use turbo_tasks::{
     primitives::{BoolVc, StringReadRef, StringVc},
     spawn_thread,
     trace::TraceRawVcs,
-    CompletionVc, Invalidator, ValueToString, ValueToStringVc,
+    with_concurrency_limit, Atom, CompletionVc, ConcurrencyCategory, InvalidationMap,
+    ValueToString, ValueToStringVc,
 };
 use turbo_tasks_hash::hash_xxh3_hash64;
 use util::{join_path, normalize_path, sys_to_unix, unix_to_sys};
 
 #[cfg(target_family = "windows")]
-use crate::util::is_windows_raw_path;
+use crate::util::{
+    is_windows_reserved_name, normalize_windows_path, strip_windows_root, to_windows_long_path,
+};
+#[cfg(unix)]
+use crate::watchman::WatchmanWatcher;
 use crate::{
     retry::{retry_blocking, retry_future},
-    rope::{Rope, RopeReadRef, RopeReader},
+    rope::{Rope, RopeBuilder, RopeReadRef, RopeReader},
 };
 
 #[turbo_tasks::value_trait]
@@ -75,37 +88,188 @@ pub struct DiskFileSystem {
     pub name: String,
     pub root: String,
     #[turbo_tasks(debug_ignore, trace_ignore)]
-    invalidator_map: Arc<InvalidatorMap>,
+    invalidator_map: Arc<InvalidationMap>,
     #[turbo_tasks(debug_ignore, trace_ignore)]
-    dir_invalidator_map: Arc<InvalidatorMap>,
+    dir_invalidator_map: Arc<InvalidationMap>,
+    /// Fingerprints of the files read, to tell which reads of a previous
+    /// session are still valid.
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    file_fingerprints: Arc<FingerprintMap>,
     #[turbo_tasks(debug_ignore, trace_ignore)]
     #[serde(skip)]
-    watcher: Mutex<Option<RecommendedWatcher>>,
+    watcher: Arc<Mutex<Option<DiskWatcher>>>,
+    watch_scope: WatchScope,
+    /// Output file systems are only written, never watched, see
+    /// [DiskFileSystemVc::new_output].
+    output: bool,
+}
+
+/// Options for a [DiskFileSystem], see [DiskFileSystemVc::new_with_options].
+#[turbo_tasks::value(serialization = "auto_for_input")]
+#[derive(Clone, Debug, Default, PartialOrd, Ord, Hash)]
+pub struct DiskFileSystemOptions {
+    /// Globs of paths relative to the root which aren't watched, e. g. `.git`
+    /// or `**/node_modules`. Nothing below an ignored directory is watched.
+    /// Ignored files can still be read, but changes to them are missed.
+    pub ignore: Vec<String>,
+    /// Directories nested deeper than this below the root aren't watched.
+    /// With `0`, only the entries of the root directory are watched.
+    pub max_depth: Option<usize>,
+}
+
+/// The paths below the root of a [DiskFileSystem] which are watched.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs)]
+struct WatchScope {
+    ignore: Vec<Glob>,
+    max_depth: Option<usize>,
+}
+
+impl WatchScope {
+    fn is_unrestricted(&self) -> bool {
+        self.ignore.is_empty() && self.max_depth.is_none()
+    }
+
+    /// Whether changes to `path` are watched. The entries of a directory are
+    /// one level deeper than the directory, so `is_dir` tells whether `path`
+    /// is a directory which is watched itself.
+    fn includes(&self, root: &Path, path: &Path, is_dir: bool) -> bool {
+        let relative = match path.strip_prefix(root).ok().and_then(|p| p.to_str()) {
+            Some(relative) => sys_to_unix(relative),
+            None => return true,
+        };
+        let segments = relative
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+        let max_len = self.max_depth.map(|max| if is_dir { max } else { max + 1 });
+        if max_len.map_or(false, |max_len| segments.len() > max_len) {
+            return false;
+        }
+        (1..=segments.len()).all(|len| {
+            let ancestor = segments[..len].join("/");
+            !self.ignore.iter().any(|glob| glob.execute(&ancestor))
+        })
+    }
+}
+
+/// Watches `dir` and the directories below it which are in `scope`, one by
+/// one, since a recursive watch can't skip ignored directories.
+fn watch_dir(
+    watcher: &mut RecommendedWatcher,
+    root: &Path,
+    dir: &Path,
+    scope: &WatchScope,
+) -> Result<()> {
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    for entry in std::fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        if entry.file_type().map_or(false, |t| t.is_dir()) && scope.includes(root, &path, true) {
+            // Directories which can't be watched, e. g. due to permissions, are
+            // skipped.
+            let _ = watch_dir(watcher, root, &path, scope);
+        }
+    }
+    Ok(())
+}
+
+/// Where a [DiskFileSystem] gets notified about file changes from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WatchBackend {
+    /// The native file watching API of the platform.
+    #[default]
+    Notify,
+    /// A running [Watchman](https://facebook.github.io/watchman/) service.
+    /// Unlike inotify on Linux, it's not limited in the number of watched
+    /// directories, and it shares its watches with other tools.
+    Watchman,
+}
+
+/// Keeps the file watching alive, until it's dropped.
+enum DiskWatcher {
+    Notify(RecommendedWatcher),
+    #[cfg(unix)]
+    Watchman(WatchmanWatcher),
 }
 
 impl DiskFileSystem {
     /// registers the path as an invalidator for the current task,
     /// has to be called within a turbo-tasks function
     fn register_invalidator(&self, path: impl AsRef<Path>, file: bool) {
-        let invalidator = turbo_tasks::get_invalidator();
         if file {
-            self.invalidator_map.insert(path_to_key(path), invalidator);
+            self.invalidator_map.register(path_to_key(path));
         } else {
-            self.dir_invalidator_map
-                .insert(path_to_key(path), invalidator);
+            self.dir_invalidator_map.register(path_to_key(path));
         }
     }
 
     pub fn invalidate(&self) {
-        for (_, invalidators) in take(&mut *self.invalidator_map.lock().unwrap()).into_iter() {
-            invalidators.into_iter().for_each(|i| i.invalidate());
-        }
-        for (_, invalidators) in take(&mut *self.dir_invalidator_map.lock().unwrap()).into_iter() {
-            invalidators.into_iter().for_each(|i| i.invalidate());
+        self.invalidator_map.invalidate_all();
+        self.dir_invalidator_map.invalidate_all();
+    }
+
+    /// The paths of the files and directories which were read and are
+    /// invalidated when they change.
+    pub fn watched_paths(&self) -> Vec<String> {
+        let mut paths = self.invalidator_map.keys();
+        paths.extend(self.dir_invalidator_map.keys());
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    /// Invalidates the reads of files which changed since they were read.
+    /// Unlike [DiskFileSystem::invalidate], the work depending on unchanged
+    /// files stays valid. This is meant for a file system restored from a
+    /// persistent cache, which would otherwise need to read and hash all
+    /// files again.
+    ///
+    /// Directory contents and links aren't fingerprinted, so all reads of
+    /// them are invalidated.
+    pub fn invalidate_changed(&self, verification: FileVerification) {
+        let changed = {
+            let mut invalidator_map = self.invalidator_map.lock().unwrap();
+            let mut file_fingerprints = self.file_fingerprints.lock().unwrap();
+            let changed = invalidator_map
+                .drain_filter(|key, _| {
+                    !is_unchanged(Path::new(key), file_fingerprints.get(key), verification)
+                })
+                .collect::<Vec<_>>();
+            file_fingerprints.retain(|key, _| invalidator_map.contains_key(key));
+            changed
+        };
+        for (key, invalidators) in changed.into_iter() {
+            invalidators
+                .into_iter()
+                .for_each(|i| i.invalidate_with_reason(&key));
         }
+        self.dir_invalidator_map.invalidate_all();
     }
 
     pub fn start_watching(&self) -> Result<()> {
+        self.start_watching_with(WatchBackend::Notify, None)
+    }
+
+    /// Like [DiskFileSystem::start_watching], but only invalidates the reads
+    /// before watching if the files changed since. See
+    /// [DiskFileSystem::invalidate_changed].
+    pub fn start_watching_and_verify(&self, verification: FileVerification) -> Result<()> {
+        self.start_watching_with(WatchBackend::Notify, Some(verification))
+    }
+
+    /// Starts watching with the given [WatchBackend]. With a `verification`,
+    /// only the reads of files changed before watching are invalidated, see
+    /// [DiskFileSystem::start_watching_and_verify].
+    pub fn start_watching_with(
+        &self,
+        backend: WatchBackend,
+        verification: Option<FileVerification>,
+    ) -> Result<()> {
+        if self.output {
+            bail!(
+                "{} is an output file system, which is never watched",
+                self.name
+            );
+        }
         let mut watcher_guard = self.watcher.lock().unwrap();
         if watcher_guard.is_some() {
             return Ok(());
@@ -113,22 +277,37 @@ impl DiskFileSystem {
         let invalidator_map = self.invalidator_map.clone();
         let dir_invalidator_map = self.dir_invalidator_map.clone();
         let root = self.root.clone();
+        let scope = self.watch_scope.clone();
+        let watcher_ref = Arc::downgrade(&self.watcher);
         // Create a channel to receive the events.
         let (tx, rx) = channel();
-        // Create a watcher object, delivering debounced events.
-        // The notification back-end is selected based on the platform.
-        let mut watcher = watcher(tx, Duration::from_millis(1))?;
-        // Add a path to be watched. All files and directories at that path and
-        // below will be monitored for changes.
-        watcher.watch(&root, RecursiveMode::Recursive)?;
+        let watcher = match backend {
+            WatchBackend::Notify => {
+                // Create a watcher object, delivering debounced events.
+                // The notification back-end is selected based on the platform.
+                let mut watcher = watcher(tx, Duration::from_millis(1))?;
+                if scope.is_unrestricted() {
+                    // Add a path to be watched. All files and directories at that path and
+                    // below will be monitored for changes.
+                    watcher.watch(&root, RecursiveMode::Recursive)?;
+                } else {
+                    watch_dir(&mut watcher, Path::new(&root), Path::new(&root), &scope)?;
+                }
+                DiskWatcher::Notify(watcher)
+            }
+            #[cfg(unix)]
+            WatchBackend::Watchman => {
+                DiskWatcher::Watchman(WatchmanWatcher::new(Path::new(&root), tx)?)
+            }
+            #[cfg(not(unix))]
+            WatchBackend::Watchman => bail!("Watchman is only supported on unix"),
+        };
 
         // We need to invalidate all reads that happened before watching
         // Best is to start_watching before starting to read
-        for (_, invalidators) in take(&mut *invalidator_map.lock().unwrap()).into_iter() {
-            invalidators.into_iter().for_each(|i| i.invalidate());
-        }
-        for (_, invalidators) in take(&mut *dir_invalidator_map.lock().unwrap()).into_iter() {
-            invalidators.into_iter().for_each(|i| i.invalidate());
+        match verification {
+            Some(verification) => self.invalidate_changed(verification),
+            None => self.invalidate(),
         }
 
         watcher_guard.replace(watcher);
@@ -145,10 +324,31 @@ impl DiskFileSystem {
                 });
                 loop {
                     match event {
+                        Ok(DebouncedEvent::Write(path))
+                        | Ok(DebouncedEvent::Create(path))
+                        | Ok(DebouncedEvent::Remove(path))
+                            if !scope.includes(Path::new(&root), &path, false) =>
+                        {
+                            // outside of the watched scope
+                        }
                         Ok(DebouncedEvent::Write(path)) => {
                             batched_invalidate_path.insert(path);
                         }
                         Ok(DebouncedEvent::Create(path)) | Ok(DebouncedEvent::Remove(path)) => {
+                            // Directories are watched one by one when the scope is
+                            // restricted, so new ones need to be added.
+                            if !scope.is_unrestricted()
+                                && path.is_dir()
+                                && scope.includes(Path::new(&root), &path, true)
+                            {
+                                if let Some(watcher) = watcher_ref.upgrade() {
+                                    if let Some(DiskWatcher::Notify(watcher)) =
+                                        &mut *watcher.lock().unwrap()
+                                    {
+                                        let _ = watch_dir(watcher, Path::new(&root), &path, &scope);
+                                    }
+                                }
+                            }
                             batched_invalidate_path_and_children.insert(path.clone());
                             batched_invalidate_path_and_children_dir.insert(path.clone());
                             if let Some(parent) = path.parent() {
@@ -202,48 +402,32 @@ impl DiskFileSystem {
                     event = rx.try_recv();
                 }
                 fn invalidate_path(
-                    invalidator_map: &mut HashMap<String, HashSet<Invalidator>>,
+                    invalidator_map: &InvalidationMap,
                     paths: impl Iterator<Item = PathBuf>,
                 ) {
                     for path in paths {
-                        let key = path_to_key(path);
-                        if let Some(invalidators) = invalidator_map.remove(&key) {
-                            invalidators.into_iter().for_each(|i| i.invalidate());
-                        }
+                        invalidator_map.invalidate(&path_to_key(path));
                     }
                 }
                 fn invalidate_path_and_children_execute(
-                    invalidator_map: &mut HashMap<String, HashSet<Invalidator>>,
+                    invalidator_map: &InvalidationMap,
                     paths: &mut HashSet<PathBuf>,
                 ) {
-                    for (_, invalidators) in invalidator_map.drain_filter(|key, _| {
-                        paths
-                            .iter()
-                            .any(|path_key| key.starts_with(&path_to_key(path_key)))
-                    }) {
-                        invalidators.into_iter().for_each(|i| i.invalidate());
-                    }
-                    paths.clear()
-                }
-                {
-                    let mut invalidator_map = invalidator_map.lock().unwrap();
-                    invalidate_path(&mut invalidator_map, batched_invalidate_path.drain());
-                    invalidate_path_and_children_execute(
-                        &mut invalidator_map,
-                        &mut batched_invalidate_path_and_children,
-                    );
-                }
-                {
-                    let mut dir_invalidator_map = dir_invalidator_map.lock().unwrap();
-                    invalidate_path(
-                        &mut dir_invalidator_map,
-                        batched_invalidate_path_dir.drain(),
-                    );
-                    invalidate_path_and_children_execute(
-                        &mut dir_invalidator_map,
-                        &mut batched_invalidate_path_and_children_dir,
-                    );
+                    let path_keys = paths.drain().map(path_to_key).collect::<Vec<_>>();
+                    invalidator_map.invalidate_where(|key| {
+                        path_keys.iter().any(|path_key| key.starts_with(path_key))
+                    });
                 }
+                invalidate_path(&invalidator_map, batched_invalidate_path.drain());
+                invalidate_path_and_children_execute(
+                    &invalidator_map,
+                    &mut batched_invalidate_path_and_children,
+                );
+                invalidate_path(&dir_invalidator_map, batched_invalidate_path_dir.drain());
+                invalidate_path_and_children_execute(
+                    &dir_invalidator_map,
+                    &mut batched_invalidate_path_and_children_dir,
+                );
             }
         });
         Ok(())
@@ -257,28 +441,133 @@ impl DiskFileSystem {
     }
 
     pub async fn to_sys_path(&self, fs_path: FileSystemPathVc) -> Result<PathBuf> {
-        let path = Path::new(&self.root).join(&*unix_to_sys(&fs_path.await?.path));
+        let fs_path = fs_path.await?;
+        // Paths are normalized by `/`, but on Windows `\` is a separator too,
+        // so a `..\` in a file name would leave the root.
+        if MAIN_SEPARATOR != '/'
+            && fs_path
+                .path
+                .split(['/', MAIN_SEPARATOR])
+                .any(|segment| segment == "..")
+        {
+            return Err(PathOutsideRootError {
+                base: None,
+                path: fs_path.path.to_string(),
+            }
+            .into());
+        }
+        #[cfg(target_family = "windows")]
+        if let Some(segment) = fs_path
+            .path
+            .split('/')
+            .find(|segment| is_windows_reserved_name(segment))
+        {
+            bail!(
+                "{} can't be accessed on Windows, {} is a reserved name",
+                fs_path.path,
+                segment
+            );
+        }
+        let path = Path::new(&self.root).join(&*unix_to_sys(&fs_path.path));
+        #[cfg(target_family = "windows")]
+        let path = to_windows_long_path(path);
         Ok(path)
     }
+
+    /// Returns the /-separated path of the absolute system path `sys_path`
+    /// within this file system, or `None` when it's outside of the root.
+    pub fn to_fs_path(&self, sys_path: &str) -> Option<String> {
+        #[cfg(target_family = "windows")]
+        {
+            strip_windows_root(&self.root, sys_path)
+        }
+        #[cfg(not(target_family = "windows"))]
+        {
+            let relative = Path::new(sys_path).strip_prefix(&self.root).ok()?;
+            normalize_path(relative.to_str()?)
+        }
+    }
 }
 
 pub fn path_to_key(path: impl AsRef<Path>) -> String {
-    path.as_ref().to_string_lossy().to_string()
+    // Long paths are prefixed with `\\?\`, while the watcher reports them
+    // without.
+    #[cfg(target_family = "windows")]
+    {
+        normalize_windows_path(&path.as_ref().to_string_lossy())
+    }
+    #[cfg(not(target_family = "windows"))]
+    {
+        path.as_ref().to_string_lossy().to_string()
+    }
 }
 
 #[turbo_tasks::value_impl]
 impl DiskFileSystemVc {
     #[turbo_tasks::function]
-    pub async fn new(name: String, root: String) -> Result<Self> {
+    pub fn new(name: String, root: String) -> Self {
+        Self::new_with_options(
+            name,
+            root,
+            turbo_tasks::Value::new(DiskFileSystemOptions::default()),
+        )
+    }
+
+    /// Creates a file system whose watching is limited by `options`, e. g. to
+    /// not watch `.git` or build output directories.
+    #[turbo_tasks::function]
+    pub async fn new_with_options(
+        name: String,
+        root: String,
+        options: turbo_tasks::Value<DiskFileSystemOptions>,
+    ) -> Result<Self> {
+        let options = options.into_value();
+        let watch_scope = WatchScope {
+            ignore: options
+                .ignore
+                .iter()
+                .map(|glob| Glob::try_from(glob.as_str()))
+                .collect::<Result<_>>()?,
+            max_depth: options.max_depth,
+        };
+        Self::create(name, root, watch_scope, false).await
+    }
+
+    /// Creates a file system for emitted assets, which is never watched.
+    ///
+    /// Writing build output into a watched directory invalidates the reads
+    /// of it, which can cause endless rebuilds. The output directory should
+    /// therefore be ignored by all watched file systems, see
+    /// [DiskFileSystemOptions::ignore].
+    #[turbo_tasks::function]
+    pub async fn new_output(name: String, root: String) -> Result<Self> {
+        Self::create(name, root, WatchScope::default(), true).await
+    }
+}
+
+impl DiskFileSystemVc {
+    async fn create(
+        name: String,
+        root: String,
+        watch_scope: WatchScope,
+        output: bool,
+    ) -> Result<Self> {
+        // Windows roots are normalized, so paths reported by the watcher and
+        // link targets can be compared with them.
+        #[cfg(target_family = "windows")]
+        let root = normalize_windows_path(&root);
         // create the directory for the filesystem on disk, if it doesn't exist
         fs::create_dir_all(&root).await?;
 
         let instance = DiskFileSystem {
             name,
             root,
-            invalidator_map: Arc::new(InvalidatorMap::new()),
-            dir_invalidator_map: Arc::new(InvalidatorMap::new()),
-            watcher: Mutex::new(None),
+            invalidator_map: Arc::new(InvalidationMap::new()),
+            dir_invalidator_map: Arc::new(InvalidationMap::new()),
+            file_fingerprints: Arc::new(FingerprintMap::new()),
+            watcher: Arc::new(Mutex::new(None)),
+            watch_scope,
+            output,
         };
 
         Ok(Self::cell(instance))
@@ -298,9 +587,21 @@ impl FileSystem for DiskFileSystem {
         let full_path = self.to_sys_path(fs_path).await?;
         self.register_invalidator(&full_path, true);
 
-        let content = match retry_future(|| File::from_path(full_path.clone())).await {
-            Ok(file) => FileContent::new(file),
-            Err(e) if e.kind() == ErrorKind::NotFound => FileContent::NotFound,
+        let content = match with_concurrency_limit(
+            ConcurrencyCategory::FsIo,
+            retry_future(|| File::from_path(full_path.clone())),
+        )
+        .await
+        {
+            Ok((file, fingerprint)) => {
+                self.file_fingerprints
+                    .insert(path_to_key(&full_path), Some(fingerprint));
+                FileContent::new(file)
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                self.file_fingerprints.insert(path_to_key(&full_path), None);
+                FileContent::NotFound
+            }
             Err(e) => {
                 bail!(anyhow!(e).context(format!("reading file {}", full_path.display())))
             }
@@ -398,16 +699,7 @@ impl FileSystem for DiskFileSystem {
         // 1. ensure the linked path is under the root
         // 2. strip the root path if the linked path is absolute
         #[cfg(target_family = "windows")]
-        let result = {
-            let root_path = Path::new(&self.root);
-            if is_windows_raw_path(root_path) && !is_windows_raw_path(&file) {
-                file.strip_prefix(Path::new(&self.root[4..]))
-            } else {
-                file.strip_prefix(root_path)
-            }
-        };
-
-        #[cfg(not(target_family = "windows"))]
+        let file = PathBuf::from(normalize_windows_path(&file.to_string_lossy()));
         let result = file.strip_prefix(Path::new(&self.root));
         let relative_to_root_path = match result {
             Ok(file) => PathBuf::from(sys_to_unix(&file.to_string_lossy()).as_ref()),
@@ -459,16 +751,30 @@ impl FileSystem for DiskFileSystem {
     ) -> Result<CompletionVc> {
         let full_path = self.to_sys_path(fs_path).await?;
         let content = content.await?;
-        let old_content = fs_path
-            .read()
-            .await
-            .with_context(|| format!("reading old content of {}", full_path.display()))?;
+        let old_content = if self.output {
+            // Reads of output file systems are never invalidated, so the
+            // content on disk is compared instead.
+            match File::from_path(full_path.clone()).await {
+                Ok((file, _)) => FileContent::Content(file),
+                Err(err) if err.kind() == ErrorKind::NotFound => FileContent::NotFound,
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("reading old content of {}", full_path.display()))
+                }
+            }
+        } else {
+            (*fs_path
+                .read()
+                .await
+                .with_context(|| format!("reading old content of {}", full_path.display()))?)
+            .clone()
+        };
 
-        if *content == *old_content {
+        if *content == old_content {
             return Ok(CompletionVc::new());
         }
 
-        let create_directory = *old_content == FileContent::NotFound;
+        let create_directory = old_content == FileContent::NotFound;
         match &*content {
             FileContent::Content(file) => {
                 if create_directory {
@@ -485,17 +791,26 @@ impl FileSystem for DiskFileSystem {
                     }
                 }
                 // println!("write {} bytes to {}", buffer.len(), full_path.display());
+                let encoded = file.meta.encoding.encode(&file.content);
                 let full_path_to_write = full_path.clone();
-                retry_future(move || {
-                    let full_path = full_path_to_write.clone();
-                    async move {
-                        let mut f = fs::File::create(&full_path).await?;
-                        tokio::io::copy(&mut file.read(), &mut f).await?;
-                        #[cfg(target_family = "unix")]
-                        f.set_permissions(file.meta.permissions.into()).await?;
-                        Ok::<(), io::Error>(())
-                    }
-                })
+                with_concurrency_limit(
+                    ConcurrencyCategory::FsIo,
+                    retry_future(move || {
+                        let full_path = full_path_to_write.clone();
+                        async move {
+                            let mut f = fs::File::create(&full_path).await?;
+                            match &encoded {
+                                Some(encoded) => f.write_all(encoded).await?,
+                                None => {
+                                    tokio::io::copy(&mut file.read(), &mut f).await?;
+                                }
+                            }
+                            #[cfg(target_family = "unix")]
+                            f.set_permissions(file.meta.permissions.into()).await?;
+                            Ok::<(), io::Error>(())
+                        }
+                    }),
+                )
                 .await
                 .with_context(|| format!("failed to write to {}", full_path.display()))?;
             }
@@ -618,12 +933,14 @@ impl ValueToString for DiskFileSystem {
 #[derive(Debug, Clone)]
 pub struct FileSystemPath {
     pub fs: FileSystemVc,
-    pub path: String,
+    /// The `/` separated path inside of the file system, without a leading
+    /// `/`. The root is an empty path.
+    pub path: Atom,
 }
 
 impl FileSystemPath {
     pub fn is_inside(&self, context: &FileSystemPath) -> bool {
-        if self.fs == context.fs && self.path.starts_with(&context.path) {
+        if self.fs == context.fs && self.path.starts_with(context.path.as_str()) {
             if context.path.is_empty() {
                 true
             } else {
@@ -635,7 +952,7 @@ impl FileSystemPath {
     }
 
     pub fn is_inside_or_equal(&self, context: &FileSystemPath) -> bool {
-        if self.fs == context.fs && self.path.starts_with(&context.path) {
+        if self.fs == context.fs && self.path.starts_with(context.path.as_str()) {
             if context.path.is_empty() {
                 true
             } else {
@@ -657,7 +974,7 @@ impl FileSystemPath {
         if self.fs != inner.fs {
             return None;
         }
-        let path = inner.path.strip_prefix(&self.path)?;
+        let path = inner.path.strip_prefix(self.path.as_str())?;
         if self.path.is_empty() {
             Some(path)
         } else if let Some(stripped) = path.strip_prefix('/') {
@@ -721,6 +1038,31 @@ impl FileSystemPath {
 #[turbo_tasks::value(transparent)]
 pub struct FileSystemPathOption(Option<FileSystemPathVc>);
 
+/// The error when a path would leave the root of its file system, e. g.
+/// because it has too many `..` segments. Callers can `downcast_ref` to it to
+/// tell invalid paths apart from other errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathOutsideRootError {
+    /// The path the other path was joined to, if the path was joined at all.
+    pub base: Option<String>,
+    pub path: String,
+}
+
+impl Display for PathOutsideRootError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.base {
+            Some(base) => write!(
+                f,
+                "FileSystemPathVc(\"{}\").join(\"{}\") leaves the filesystem root",
+                base, self.path
+            ),
+            None => write!(f, "\"{}\" leaves the filesystem root", self.path),
+        }
+    }
+}
+
+impl std::error::Error for PathOutsideRootError {}
+
 #[turbo_tasks::value_impl]
 impl FileSystemPathVc {
     /// Create a new FileSystemPathVc from a path withing a FileSystem. The
@@ -741,7 +1083,10 @@ impl FileSystemPathVc {
             "path {} must be normalized",
             path,
         );
-        Self::cell(FileSystemPath { fs, path })
+        Self::cell(FileSystemPath {
+            fs,
+            path: path.into(),
+        })
     }
 
     /// Adds a subpath to the current path. The /-separate path argument might
@@ -753,11 +1098,11 @@ impl FileSystemPathVc {
         if let Some(path) = join_path(&this.path, path) {
             Ok(Self::new_normalized(this.fs, path))
         } else {
-            bail!(
-                "FileSystemPathVc(\"{}\").join(\"{}\") leaves the filesystem root",
-                this.path,
-                path
-            );
+            Err(PathOutsideRootError {
+                base: Some(this.path.to_string()),
+                path: path.to_string(),
+            }
+            .into())
         }
     }
 
@@ -825,7 +1170,7 @@ impl FileSystemPathVc {
     pub async fn try_join_inside(self, path: &str) -> Result<FileSystemPathOptionVc> {
         let this = self.await?;
         if let Some(path) = join_path(&this.path, path) {
-            if path.starts_with(&this.path) {
+            if is_path_inside_or_equal(&path, this.path.as_str()) {
                 return Ok(FileSystemPathOptionVc::cell(Some(Self::new_normalized(
                     this.fs, path,
                 ))));
@@ -834,6 +1179,30 @@ impl FileSystemPathVc {
         Ok(FileSystemPathOptionVc::cell(None))
     }
 
+    /// Similar to [FileSystemPathVc::try_join_inside], but symlinks can't be
+    /// used to leave the current path either. The joined path is only returned
+    /// when it's still inside of the current path after following all
+    /// symlinks of both.
+    #[turbo_tasks::function]
+    pub async fn try_join_inside_real(self, path: &str) -> Result<FileSystemPathOptionVc> {
+        let joined = if let Some(joined) = *self.try_join_inside(path).await? {
+            joined
+        } else {
+            return Ok(FileSystemPathOptionVc::cell(None));
+        };
+        let (real_base, real_joined) = if let (Some(base), Some(joined)) = (
+            contained_realpath(self).await?,
+            contained_realpath(joined).await?,
+        ) {
+            (base.await?, joined.await?)
+        } else {
+            return Ok(FileSystemPathOptionVc::cell(None));
+        };
+        Ok(FileSystemPathOptionVc::cell(
+            real_joined.is_inside_or_equal(&real_base).then_some(joined),
+        ))
+    }
+
     #[turbo_tasks::function]
     pub async fn read_glob(self, glob: GlobVc, include_dot_files: bool) -> ReadGlobResultVc {
         read_glob(self, glob, include_dot_files)
@@ -884,12 +1253,12 @@ pub async fn rebase(
     let new_path;
     if old_base.path.is_empty() {
         if new_base.path.is_empty() {
-            new_path = fs_path.path.clone();
+            new_path = fs_path.path.to_string();
         } else {
-            new_path = [new_base.path.as_str(), "/", &fs_path.path].concat();
+            new_path = [new_base.path.as_str(), "/", fs_path.path.as_str()].concat();
         }
     } else {
-        let base_path = [&old_base.path, "/"].concat();
+        let base_path = [old_base.path.as_str(), "/"].concat();
         if !fs_path.path.starts_with(&base_path) {
             bail!(
                 "rebasing {} from {} onto {} doesn't work because it's not part of the source path",
@@ -928,6 +1297,8 @@ impl FileSystemPathVc {
     ///
     /// DETERMINISM: Result is in random order. Either sort result or do not
     /// depend on the order.
+    ///
+    /// Use [DirectoryContentVc::entry] to only depend on a single entry.
     #[turbo_tasks::function]
     pub async fn read_dir(self) -> DirectoryContentVc {
         self.fs().read_dir(self)
@@ -977,24 +1348,18 @@ impl FileSystemPathVc {
             return Ok(FileSystemEntryTypeVc::cell(FileSystemEntryType::Directory));
         }
         let parent = self.parent().resolve().await?;
-        let dir_content = parent.read_dir().await?;
-        match &*dir_content {
-            DirectoryContent::NotFound => {
-                Ok(FileSystemEntryTypeVc::cell(FileSystemEntryType::NotFound))
-            }
-            DirectoryContent::Entries(entries) => {
-                let basename = if let Some((_, basename)) = this.path.rsplit_once('/') {
-                    basename
-                } else {
-                    &this.path
-                };
-                if let Some(entry) = entries.get(basename) {
-                    Ok(FileSystemEntryTypeVc::cell(entry.into()))
-                } else {
-                    Ok(FileSystemEntryTypeVc::cell(FileSystemEntryType::NotFound))
-                }
-            }
-        }
+        let basename = if let Some((_, basename)) = this.path.rsplit_once('/') {
+            basename
+        } else {
+            this.path.as_str()
+        };
+        // Only depends on this entry, so adding other files to the directory
+        // doesn't invalidate the type of every path in it.
+        let entry = parent.read_dir().entry(basename).await?;
+        Ok(FileSystemEntryTypeVc::cell(match &*entry {
+            Some(entry) => entry.into(),
+            None => FileSystemEntryType::NotFound,
+        }))
     }
 
     #[turbo_tasks::function]
@@ -1046,6 +1411,52 @@ impl FileSystemPathVc {
     }
 }
 
+/// The real path of `path` like [FileSystemPathVc::realpath], or `None` when a
+/// symlink points outside of the file system or can't be followed.
+async fn contained_realpath(path: FileSystemPathVc) -> Result<Option<FileSystemPathVc>> {
+    /// Gives up on symlink loops, like Linux does.
+    const MAX_LINKS: usize = 40;
+    let mut current = path.root();
+    let mut links = 0;
+    for segment in path.await?.path.split('/').filter(|s| !s.is_empty()) {
+        current = current.join(segment);
+        while let FileSystemEntryType::Symlink = &*current.get_type().await? {
+            links += 1;
+            if links > MAX_LINKS {
+                return Ok(None);
+            }
+            // Links which leave the root of a disk file system are invalid.
+            let target =
+                if let LinkContent::Link { target, link_type } = &*current.read_link().await? {
+                    if link_type.contains(LinkType::ABSOLUTE) {
+                        current.root()
+                    } else {
+                        current.parent()
+                    }
+                    .try_join(target)
+                    .await?
+                } else {
+                    return Ok(None);
+                };
+            current = if let Some(target) = *target {
+                target
+            } else {
+                return Ok(None);
+            };
+        }
+    }
+    Ok(Some(current))
+}
+
+/// Whether the `/` separated `path` is `dir` or inside of it. `a/bc` starts
+/// with `a/b`, but isn't inside of it.
+fn is_path_inside_or_equal(path: &str, dir: &str) -> bool {
+    dir.is_empty()
+        || path
+            .strip_prefix(dir)
+            .map_or(false, |rest| rest.is_empty() || rest.starts_with('/'))
+}
+
 impl FileSystemPathVc {
     pub fn rebase(
         fs_path: FileSystemPathVc,
@@ -1177,6 +1588,13 @@ pub enum LinkContent {
     NotFound,
 }
 
+/// The maximum size of a single chunk of bytes when reading a [File].
+const READ_CHUNK_SIZE: u64 = 1024 * 1024;
+
+/// Binary files of at least this size are memory-mapped instead of read.
+#[cfg(feature = "unsafe_mmap")]
+const MMAP_THRESHOLD: u64 = 16 * 1024 * 1024;
+
 #[turbo_tasks::value(shared)]
 #[derive(Clone)]
 pub struct File {
@@ -1186,18 +1604,62 @@ pub struct File {
 }
 
 impl File {
-    /// Reads a [File] from the given path
-    async fn from_path(p: PathBuf) -> io::Result<Self> {
+    /// Reads a [File] from the given path, together with its fingerprint.
+    ///
+    /// The content is read in chunks of at most [READ_CHUNK_SIZE] bytes, so
+    /// huge files don't need a large contiguous allocation. With the
+    /// `unsafe_mmap` feature, large binary files are memory-mapped instead,
+    /// so they aren't held in memory at all.
+    async fn from_path(p: PathBuf) -> io::Result<(Self, FileFingerprint)> {
         let mut file = fs::File::open(p).await?;
         let metadata = file.metadata().await?;
 
-        let mut output = Vec::with_capacity(metadata.len() as usize);
-        file.read_to_end(&mut output).await?;
+        #[cfg(feature = "unsafe_mmap")]
+        if metadata.len() >= MMAP_THRESHOLD {
+            let std_file = file.try_clone().await?.into_std().await;
+            // SAFETY: The file must not be modified while it's mapped, which is
+            // why mapping is opt-in. The watcher invalidates the read when it
+            // changes, which drops the mapping soon after.
+            let mmap = unsafe { memmap2::Mmap::map(&std_file)? };
+            if FileEncoding::detect(&mmap) == FileEncoding::Binary {
+                let content = Rope::from_mmap(mmap);
+                let fingerprint = FileFingerprint::new(&metadata, &content);
+                let mut meta: FileMeta = metadata.into();
+                meta.encoding = FileEncoding::Binary;
+                return Ok((File { meta, content }, fingerprint));
+            }
+        }
 
-        Ok(File {
-            meta: metadata.into(),
-            content: Rope::from(output),
-        })
+        let mut content = RopeBuilder::default();
+        let mut remaining = metadata.len();
+        loop {
+            let capacity = remaining.clamp(1, READ_CHUNK_SIZE);
+            let mut chunk = Vec::with_capacity(capacity as usize);
+            (&mut file)
+                .take(READ_CHUNK_SIZE)
+                .read_to_end(&mut chunk)
+                .await?;
+            if chunk.is_empty() {
+                break;
+            }
+            remaining = remaining.saturating_sub(chunk.len() as u64);
+            content.push_chunk(chunk.into());
+        }
+
+        let content = content.build();
+        let fingerprint = FileFingerprint::new(&metadata, &content);
+        let mut meta: FileMeta = metadata.into();
+        // The first chunk is large enough to detect the encoding.
+        meta.encoding = content
+            .read()
+            .next()
+            .map_or(FileEncoding::Utf8, |first| FileEncoding::detect(&first));
+        let content = if meta.encoding.is_verbatim() {
+            content
+        } else {
+            Rope::from(meta.encoding.decode(&content.to_bytes()).into_owned())
+        };
+        Ok((File { meta, content }, fingerprint))
     }
 
     /// Creates a [File] from raw bytes.
@@ -1225,6 +1687,11 @@ impl File {
         self
     }
 
+    /// Whether the file is binary, i.e. not text in any supported encoding.
+    pub fn is_binary(&self) -> bool {
+        self.meta.encoding == FileEncoding::Binary
+    }
+
     /// Returns a Read/AsyncRead/Stream/Iterator to access the File's contents.
     pub fn read(&self) -> RopeReader {
         self.content.read()
@@ -1356,6 +1823,9 @@ pub struct FileMeta {
     #[serde(with = "mime_option_serde")]
     #[turbo_tasks(trace_ignore)]
     content_type: Option<Mime>,
+    /// The encoding of the file on disk. The content is always UTF-8.
+    #[serde(default)]
+    encoding: FileEncoding,
 }
 
 impl From<std::fs::Metadata> for FileMeta {
@@ -1365,10 +1835,18 @@ impl From<std::fs::Metadata> for FileMeta {
         Self {
             permissions,
             content_type: None,
+            encoding: FileEncoding::Utf8,
         }
     }
 }
 
+impl FileMeta {
+    /// The encoding of the file on disk.
+    pub fn encoding(&self) -> FileEncoding {
+        self.encoding
+    }
+}
+
 impl FileContent {
     pub fn new(file: File) -> Self {
         FileContent::Content(file)
@@ -1559,6 +2037,25 @@ impl DirectoryContentVc {
     }
 }
 
+#[turbo_tasks::value_impl]
+impl DirectoryContentVc {
+    /// Looks up a single entry of the directory, `None` when the directory
+    /// or the entry doesn't exist.
+    ///
+    /// Unlike reading the whole listing, callers are only invalidated when
+    /// this entry changes, not when other entries are added or removed.
+    #[turbo_tasks::function]
+    pub async fn entry(self, name: &str) -> Result<OptionDirectoryEntryVc> {
+        Ok(OptionDirectoryEntryVc::cell(match &*self.await? {
+            DirectoryContent::Entries(entries) => entries.get(name).copied(),
+            DirectoryContent::NotFound => None,
+        }))
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionDirectoryEntry(Option<DirectoryEntry>);
+
 #[turbo_tasks::value(shared)]
 pub struct NullFileSystem;
 
@@ -1601,7 +2098,89 @@ pub async fn to_sys_path(path: FileSystemPathVc) -> Result<Option<PathBuf>> {
     Ok(None)
 }
 
+/// Returns the path of the absolute system path `sys_path` in `fs`, or `None`
+/// when `fs` is not a [DiskFileSystem] or the path is outside of its root.
+pub async fn from_sys_path(fs: FileSystemVc, sys_path: &str) -> Result<Option<FileSystemPathVc>> {
+    if let Some(disk_fs) = DiskFileSystemVc::resolve_from(fs).await? {
+        if let Some(path) = disk_fs.await?.to_fs_path(sys_path) {
+            return Ok(Some(FileSystemPathVc::new_normalized(fs, path)));
+        }
+    }
+    Ok(None)
+}
+
 pub fn register() {
     turbo_tasks::register();
     include!(concat!(env!("OUT_DIR"), "/register.rs"));
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use turbo_tasks::TurboTasks;
+    use turbo_tasks_memory::MemoryBackend;
+
+    use super::{is_path_inside_or_equal, DiskFileSystemVc, PathOutsideRootError};
+
+    #[test]
+    fn path_inside_or_equal() {
+        assert!(is_path_inside_or_equal("a/b", "a/b"));
+        assert!(is_path_inside_or_equal("a/b/c", "a/b"));
+        assert!(is_path_inside_or_equal("a", ""));
+        assert!(!is_path_inside_or_equal("a/bc", "a/b"));
+        assert!(!is_path_inside_or_equal("a", "a/b"));
+    }
+
+    #[test]
+    fn path_outside_root_error() {
+        let joined = PathOutsideRootError {
+            base: Some("a".to_string()),
+            path: "../..".to_string(),
+        };
+        assert_eq!(
+            joined.to_string(),
+            "FileSystemPathVc(\"a\").join(\"../..\") leaves the filesystem root"
+        );
+        let unjoined = PathOutsideRootError {
+            base: None,
+            path: "a/../..".to_string(),
+        };
+        assert_eq!(
+            unjoined.to_string(),
+            "\"a/../..\" leaves the filesystem root"
+        );
+    }
+
+    #[cfg(target_family = "unix")]
+    #[tokio::test]
+    async fn try_join_inside_real_follows_symlinks() -> Result<()> {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir()?;
+        std::fs::create_dir_all(dir.path().join("root/public/nested"))?;
+        std::fs::create_dir(dir.path().join("root/private"))?;
+        std::fs::write(dir.path().join("root/private/secret"), "secret")?;
+        std::fs::write(dir.path().join("outside"), "outside")?;
+        symlink("../private", dir.path().join("root/public/escape"))?;
+        symlink("../../outside", dir.path().join("root/public/external"))?;
+        symlink("nested", dir.path().join("root/public/inner"))?;
+
+        crate::register();
+        let tt = TurboTasks::new(MemoryBackend::new());
+        let root = dir.path().join("root").to_string_lossy().to_string();
+        tt.run_once(async move {
+            let public = DiskFileSystemVc::new("root".to_string(), root)
+                .root()
+                .join("public");
+            assert!(public.try_join_inside_real("inner").await?.is_some());
+            assert!(public
+                .try_join_inside_real("escape/secret")
+                .await?
+                .is_none());
+            assert!(public.try_join_inside_real("external").await?.is_none());
+            assert!(public.try_join_inside_real("../private").await?.is_none());
+            Ok(())
+        })
+        .await
+    }
+}
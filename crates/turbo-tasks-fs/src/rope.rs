@@ -12,13 +12,20 @@ use std::{
 use anyhow::{Context, Result};
 use bytes::{Buf, Bytes};
 use futures::Stream;
+#[cfg(feature = "unsafe_mmap")]
+use memmap2::Mmap;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use tokio::io::{AsyncRead, ReadBuf};
+use turbo_tasks::CompressibleValue;
 use turbo_tasks_hash::{DeterministicHash, DeterministicHasher};
 use RopeElem::{Local, Shared};
 
 static EMPTY_BUF: &[u8] = &[];
 
+/// The size of the chunks in which memory-mapped sections are read.
+#[cfg(feature = "unsafe_mmap")]
+const MAPPED_CHUNK_SIZE: usize = 1024 * 1024;
+
 /// A Rope provides an efficient structure for sharing bytes/strings between
 /// multiple sources. Cloning a Rope is extremely cheap (Arc and usize), and
 /// the sharing contents of one Rope can be shared by just cloning an Arc.
@@ -48,6 +55,11 @@ enum RopeElem {
 
     /// Shared holds the Arc container of another rope.
     Shared(InnerRope),
+
+    /// Mapped bytes are a memory-mapped file, which is paged in by the OS
+    /// when read instead of being held in memory.
+    #[cfg(feature = "unsafe_mmap")]
+    Mapped(Arc<Mmap>),
 }
 
 /// RopeBuilder provides a mutable container to append bytes/strings. This can
@@ -83,15 +95,27 @@ impl Rope {
         RopeReader::new(&self.data)
     }
 
+    /// Returns all bytes of the rope. This only copies the bytes when the rope
+    /// consists of multiple sections.
+    pub fn to_bytes(&self) -> Cow<'_, [u8]> {
+        if let Some(bytes) = self.single_section() {
+            return Cow::Borrowed(bytes);
+        }
+
+        let mut bytes = Vec::with_capacity(self.len());
+        for chunk in self.read() {
+            bytes.extend_from_slice(&chunk);
+        }
+        Cow::Owned(bytes)
+    }
+
     /// Returns a String instance of all bytes.
     pub fn to_str(&self) -> Result<Cow<'_, str>> {
-        if self.data.len() == 1 {
-            if let Local(bytes) = &self.data[0] {
-                let utf8 = std::str::from_utf8(bytes);
-                return utf8
-                    .context("failed to convert rope into string")
-                    .map(Cow::Borrowed);
-            }
+        if let Some(bytes) = self.single_section() {
+            let utf8 = std::str::from_utf8(bytes);
+            return utf8
+                .context("failed to convert rope into string")
+                .map(Cow::Borrowed);
         }
 
         let mut read = self.read();
@@ -100,6 +124,32 @@ impl Rope {
         res.context("failed to convert rope into string")?;
         Ok(Cow::Owned(string))
     }
+
+    /// Creates a rope backed by a memory-mapped file.
+    ///
+    /// The file must not be modified while the rope is alive, the contents
+    /// would change and truncating the file makes reading it crash the
+    /// process.
+    #[cfg(feature = "unsafe_mmap")]
+    pub fn from_mmap(mmap: Mmap) -> Self {
+        Rope {
+            length: mmap.len(),
+            data: InnerRope::from(vec![RopeElem::Mapped(Arc::new(mmap))]),
+        }
+    }
+
+    /// Returns the bytes of the rope when it consists of a single section.
+    fn single_section(&self) -> Option<&[u8]> {
+        if self.data.len() != 1 {
+            return None;
+        }
+        match &self.data[0] {
+            Local(bytes) => Some(&bytes[..]),
+            #[cfg(feature = "unsafe_mmap")]
+            RopeElem::Mapped(mmap) => Some(&mmap[..]),
+            Shared(_) => None,
+        }
+    }
 }
 
 impl<T: Into<Bytes>> From<T> for Rope {
@@ -141,6 +191,23 @@ impl RopeBuilder {
         self.committed.push(Local(bytes.into()));
     }
 
+    /// Push an owned chunk of bytes into the Rope.
+    ///
+    /// Unlike [push_bytes], the chunk is kept as its own section instead of
+    /// being copied into a contiguous buffer, so large contents can be built
+    /// from many chunks without a large allocation.
+    pub fn push_chunk(&mut self, bytes: Bytes) {
+        if bytes.is_empty() {
+            return;
+        }
+
+        // We may have pending bytes from a prior push.
+        self.finish();
+
+        self.length += bytes.len();
+        self.committed.push(Local(bytes));
+    }
+
     /// Concatenate another Rope instance into our builder.
     ///
     /// This is much more efficient than pushing actual bytes, since we can
@@ -227,6 +294,17 @@ impl ops::AddAssign<&Rope> for RopeBuilder {
     }
 }
 
+/// A decompressed rope no longer shares its contents with other ropes.
+impl CompressibleValue for Rope {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Rope::to_bytes(self)
+    }
+
+    fn from_bytes(bytes: Vec<u8>) -> Self {
+        Rope::from(bytes)
+    }
+}
+
 impl DeterministicHash for Rope {
     /// Ropes with similar contents hash the same, regardless of their
     /// structure.
@@ -237,18 +315,17 @@ impl DeterministicHash for Rope {
 }
 
 impl Serialize for Rope {
-    /// Ropes are always serialized into contiguous strings, because
+    /// Ropes are always serialized into contiguous bytes, because
     /// deserialization won't deduplicate and share the Arcs (being the only
-    /// possible owner of a individual "shared" data doesn't make sense).
+    /// possible owner of a individual "shared" data doesn't make sense). The
+    /// contents might be binary, so they are not required to be valid UTF-8.
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        use serde::ser::Error;
-        let s = self.to_str().map_err(Error::custom)?;
-        serializer.serialize_str(&s)
+        serializer.serialize_bytes(&self.to_bytes())
     }
 }
 
 impl<'de> Deserialize<'de> for Rope {
-    /// Deserializes strings into a contiguous, immutable Rope.
+    /// Deserializes bytes into a contiguous, immutable Rope.
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
         let bytes = <Vec<u8>>::deserialize(deserializer)?;
         Ok(Rope::from(bytes))
@@ -330,6 +407,8 @@ impl DeterministicHash for RopeElem {
         match self {
             Local(bytes) => state.write_bytes(bytes),
             Shared(inner) => inner.deterministic_hash(state),
+            #[cfg(feature = "unsafe_mmap")]
+            RopeElem::Mapped(mmap) => state.write_bytes(mmap),
         }
     }
 }
@@ -347,6 +426,9 @@ pub struct RopeReader {
 enum StackElem {
     Local(Bytes),
     Shared(InnerRope, usize),
+    /// A memory-mapped file and the offset of the next chunk to read from it.
+    #[cfg(feature = "unsafe_mmap")]
+    Mapped(Arc<Mmap>, usize),
 }
 
 impl RopeReader {
@@ -386,13 +468,26 @@ impl Iterator for RopeReader {
     type Item = Bytes;
 
     /// Iterates the rope's elements recursively until we find the next Local
-    /// section, returning its Bytes.
+    /// section, returning its Bytes. Memory-mapped sections are returned in
+    /// copied chunks.
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             let (inner, mut index) = match self.stack.pop() {
                 None => return None,
                 Some(StackElem::Local(b)) => return Some(b),
                 Some(StackElem::Shared(r, i)) => (r, i),
+                #[cfg(feature = "unsafe_mmap")]
+                Some(StackElem::Mapped(mmap, offset)) => {
+                    let end = min(offset + MAPPED_CHUNK_SIZE, mmap.len());
+                    let chunk = Bytes::copy_from_slice(&mmap[offset..end]);
+                    if end < mmap.len() {
+                        self.stack.push(StackElem::Mapped(mmap, end));
+                    }
+                    if chunk.is_empty() {
+                        continue;
+                    }
+                    return Some(chunk);
+                }
             };
 
             let el = inner[index].clone();
@@ -489,6 +584,8 @@ impl From<RopeElem> for StackElem {
         match el {
             Local(bytes) => Self::Local(bytes),
             Shared(inner) => Self::Shared(inner, 0),
+            #[cfg(feature = "unsafe_mmap")]
+            RopeElem::Mapped(mmap) => Self::Mapped(mmap, 0),
         }
     }
 }
@@ -1,6 +1,6 @@
 use std::borrow::Cow;
 #[cfg(target_family = "windows")]
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Joins two /-separated paths into a normalized path.
 /// Paths are concatenated with /.
@@ -125,3 +125,177 @@ pub fn is_windows_raw_path(path: impl AsRef<Path>) -> bool {
     // So we use `String::starts_with` here
     path.as_ref().to_string_lossy().starts_with("\\\\?\\")
 }
+
+/// The prefix of paths in the Win32 file namespace, see [is_windows_raw_path].
+#[cfg(target_family = "windows")]
+const WINDOWS_RAW_PREFIX: &str = "\\\\?\\";
+
+/// The maximum length of a path which can be passed to the Windows APIs
+/// without the `\\?\` prefix.
+#[cfg(target_family = "windows")]
+const WINDOWS_MAX_PATH: usize = 260;
+
+#[cfg(target_family = "windows")]
+/// Normalizes an absolute Windows path to the form used in the rest of the
+/// file system: the `\\?\` prefix is removed (`\\?\UNC\server\share` becomes
+/// `\\server\share`), `/` is replaced by `\`, the drive letter is uppercased
+/// and trailing separators are removed.
+pub fn normalize_windows_path(path: &str) -> String {
+    let path = path.replace('/', "\\");
+    let path = match path.strip_prefix(WINDOWS_RAW_PREFIX) {
+        Some(raw) => match raw.strip_prefix("UNC\\") {
+            Some(unc) => format!("\\\\{unc}"),
+            None => raw.to_string(),
+        },
+        None => path,
+    };
+    let mut path = match path.as_bytes() {
+        [drive, b':', ..] if drive.is_ascii_alphabetic() => {
+            format!("{}{}", drive.to_ascii_uppercase() as char, &path[1..])
+        }
+        _ => path,
+    };
+    // `C:\` is the root of the drive, while `C:` is the current directory on
+    // that drive.
+    while path.ends_with('\\') && !(path.len() == 3 && path.as_bytes()[1] == b':') {
+        path.pop();
+    }
+    path
+}
+
+#[cfg(target_family = "windows")]
+/// Checks if `segment` is a reserved device name like `CON` or `com1.txt`,
+/// which can't be used as a file name on Windows.
+pub fn is_windows_reserved_name(segment: &str) -> bool {
+    const RESERVED: [&str; 4] = ["CON", "PRN", "AUX", "NUL"];
+    let stem = segment
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(' ');
+    if RESERVED.iter().any(|name| stem.eq_ignore_ascii_case(name)) {
+        return true;
+    }
+    match stem.as_bytes() {
+        [a, b, c, digit] => {
+            let prefix = [*a, *b, *c];
+            (prefix.eq_ignore_ascii_case(b"COM") || prefix.eq_ignore_ascii_case(b"LPT"))
+                && (b'1'..=b'9').contains(digit)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(target_family = "windows")]
+/// Adds the `\\?\` prefix to paths which exceed the length limit of the
+/// Windows APIs, which lifts the limit.
+pub fn to_windows_long_path(path: PathBuf) -> PathBuf {
+    if path.as_os_str().len() < WINDOWS_MAX_PATH || is_windows_raw_path(&path) {
+        return path;
+    }
+    let path = path.to_string_lossy();
+    PathBuf::from(match path.strip_prefix("\\\\") {
+        Some(unc) => format!("{WINDOWS_RAW_PREFIX}UNC\\{unc}"),
+        None => format!("{WINDOWS_RAW_PREFIX}{path}"),
+    })
+}
+
+#[cfg(target_family = "windows")]
+/// Returns the /-separated path of the absolute Windows path `path` relative
+/// to `root`, or `None` when it's not inside of `root`. Paths are compared
+/// case-insensitively, like the Windows file system does.
+pub fn strip_windows_root(root: &str, path: &str) -> Option<String> {
+    let root = normalize_windows_path(root);
+    let path = normalize_windows_path(path);
+    if path.len() < root.len() || !path.is_char_boundary(root.len()) {
+        return None;
+    }
+    let (prefix, rest) = path.split_at(root.len());
+    if !prefix.eq_ignore_ascii_case(&root) {
+        return None;
+    }
+    let rest = if root.ends_with('\\') || rest.is_empty() {
+        rest
+    } else {
+        rest.strip_prefix('\\')?
+    };
+    normalize_path(&sys_to_unix(rest))
+}
+
+#[cfg(all(test, target_family = "windows"))]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{
+        is_windows_reserved_name, normalize_windows_path, strip_windows_root, to_windows_long_path,
+    };
+
+    #[test]
+    fn normalizes_windows_paths() {
+        assert_eq!(normalize_windows_path("c:\\workspace\\"), "C:\\workspace");
+        assert_eq!(
+            normalize_windows_path("c:/workspace/app"),
+            "C:\\workspace\\app"
+        );
+        assert_eq!(
+            normalize_windows_path("\\\\?\\d:\\workspace"),
+            "D:\\workspace"
+        );
+        assert_eq!(
+            normalize_windows_path("\\\\?\\UNC\\server\\share\\app"),
+            "\\\\server\\share\\app"
+        );
+        assert_eq!(normalize_windows_path("C:\\"), "C:\\");
+    }
+
+    #[test]
+    fn detects_reserved_names() {
+        assert!(is_windows_reserved_name("con"));
+        assert!(is_windows_reserved_name("NUL.txt"));
+        assert!(is_windows_reserved_name("com1.js"));
+        assert!(is_windows_reserved_name("LPT9"));
+        assert!(!is_windows_reserved_name("console.js"));
+        assert!(!is_windows_reserved_name("com0"));
+        assert!(!is_windows_reserved_name("index.js"));
+    }
+
+    #[test]
+    fn prefixes_long_paths() {
+        let short = PathBuf::from("C:\\workspace\\index.js");
+        assert_eq!(to_windows_long_path(short.clone()), short);
+
+        let name = "a".repeat(300);
+        assert_eq!(
+            to_windows_long_path(PathBuf::from(format!("C:\\{name}"))),
+            PathBuf::from(format!("\\\\?\\C:\\{name}"))
+        );
+        assert_eq!(
+            to_windows_long_path(PathBuf::from(format!("\\\\server\\share\\{name}"))),
+            PathBuf::from(format!("\\\\?\\UNC\\server\\share\\{name}"))
+        );
+    }
+
+    #[test]
+    fn strips_windows_roots() {
+        assert_eq!(
+            strip_windows_root("C:\\workspace", "c:\\Workspace\\src\\index.js").as_deref(),
+            Some("src/index.js")
+        );
+        assert_eq!(
+            strip_windows_root("\\\\?\\C:\\workspace", "C:\\workspace").as_deref(),
+            Some("")
+        );
+        assert_eq!(
+            strip_windows_root("\\\\server\\share", "\\\\?\\UNC\\server\\share\\a.js").as_deref(),
+            Some("a.js")
+        );
+        assert_eq!(
+            strip_windows_root("C:\\workspace", "C:\\workspace2\\a.js"),
+            None
+        );
+        assert_eq!(
+            strip_windows_root("C:\\", "C:\\a.js").as_deref(),
+            Some("a.js")
+        );
+    }
+}
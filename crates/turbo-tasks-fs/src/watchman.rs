@@ -0,0 +1,198 @@
+use std::{
+    env,
+    io::{BufRead, BufReader, Write},
+    net::Shutdown,
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+        Arc,
+    },
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use notify::DebouncedEvent;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use turbo_tasks::spawn_thread;
+
+const SUBSCRIPTION_NAME: &str = "turbo-tasks-fs";
+
+/// Receives file changes from a running Watchman service. The changes are
+/// reported as [DebouncedEvent]s, so they are handled exactly like the ones
+/// from the `notify` watcher.
+///
+/// Watching stops when this is dropped.
+pub struct WatchmanWatcher {
+    stream: UnixStream,
+    stopped: Arc<AtomicBool>,
+}
+
+impl WatchmanWatcher {
+    /// Subscribes to all changes below `root` since now.
+    pub fn new(root: &Path, tx: Sender<DebouncedEvent>) -> Result<Self> {
+        let stream = UnixStream::connect(sockname()?).context("connecting to Watchman")?;
+        let mut connection = Connection {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream.try_clone()?,
+        };
+
+        let watch: WatchProjectResponse = connection.command(json!(["watch-project", root]))?;
+        let clock: ClockResponse = connection.command(json!(["clock", watch.watch]))?;
+        let mut query = json!({
+            "since": clock.clock,
+            "fields": ["name", "exists", "new"],
+            "empty_on_fresh_instance": true,
+        });
+        if let Some(relative_path) = &watch.relative_path {
+            query["relative_root"] = json!(relative_path);
+        }
+        connection.command::<Value>(json!(["subscribe", watch.watch, SUBSCRIPTION_NAME, query]))?;
+
+        let root = root.to_path_buf();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread_stopped = stopped.clone();
+        spawn_thread(move || {
+            if let Err(err) = connection.forward_events(&root, &tx) {
+                // Reading fails when the stream is shut down on drop, which
+                // is no error.
+                if !thread_stopped.load(Ordering::Acquire) {
+                    let _ = tx.send(DebouncedEvent::Error(
+                        notify::Error::Generic(format!("{err:#}")),
+                        None,
+                    ));
+                }
+            }
+        });
+
+        Ok(Self { stream, stopped })
+    }
+}
+
+impl Drop for WatchmanWatcher {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Release);
+        // Unblocks the thread reading from the stream, which drops the sender.
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// Watchman's socket is either passed by Watchman itself, when running as a
+/// trigger, or has to be asked from the CLI.
+fn sockname() -> Result<PathBuf> {
+    if let Some(sock) = env::var_os("WATCHMAN_SOCK") {
+        return Ok(sock.into());
+    }
+    let output = Command::new("watchman")
+        .args(["--output-encoding=json", "--no-pretty", "get-sockname"])
+        .output()
+        .context("running `watchman get-sockname`, is Watchman installed?")?;
+    if !output.status.success() {
+        bail!(
+            "`watchman get-sockname` failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    let response: SocknameResponse = serde_json::from_slice(&output.stdout)?;
+    Ok(response.sockname)
+}
+
+/// Watchman's JSON protocol: every message is a JSON value on a single line.
+struct Connection {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl Connection {
+    fn command<T: for<'de> Deserialize<'de>>(&mut self, command: Value) -> Result<T> {
+        serde_json::to_writer(&mut self.writer, &command)?;
+        self.writer.write_all(b"\n")?;
+        loop {
+            let response = self.read()?;
+            // Log messages can arrive at any time.
+            if response.get("unilateral").is_some() {
+                continue;
+            }
+            if let Some(error) = response.get("error") {
+                bail!("Watchman command {command} failed: {error}");
+            }
+            return Ok(serde_json::from_value(response)?);
+        }
+    }
+
+    fn read(&mut self) -> Result<Value> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            bail!("Watchman closed the connection");
+        }
+        Ok(serde_json::from_str(&line)?)
+    }
+
+    fn forward_events(&mut self, root: &Path, tx: &Sender<DebouncedEvent>) -> Result<()> {
+        loop {
+            let message = self.read()?;
+            if message.get("subscription").and_then(Value::as_str) != Some(SUBSCRIPTION_NAME) {
+                continue;
+            }
+            let message: SubscriptionMessage = serde_json::from_value(message)?;
+            let events = if message.is_fresh_instance {
+                // Watchman lost track of the changes, e. g. because it was
+                // restarted, so anything could have changed.
+                vec![DebouncedEvent::Rescan]
+            } else {
+                message
+                    .files
+                    .into_iter()
+                    .map(|file| {
+                        let path = root.join(&file.name);
+                        if !file.exists {
+                            DebouncedEvent::Remove(path)
+                        } else if file.new {
+                            DebouncedEvent::Create(path)
+                        } else {
+                            DebouncedEvent::Write(path)
+                        }
+                    })
+                    .collect()
+            };
+            for event in events {
+                tx.send(event)
+                    .map_err(|_| anyhow!("file system was dropped"))?;
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SocknameResponse {
+    sockname: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct WatchProjectResponse {
+    watch: PathBuf,
+    relative_path: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct ClockResponse {
+    clock: String,
+}
+
+#[derive(Deserialize)]
+struct SubscriptionMessage {
+    #[serde(default)]
+    is_fresh_instance: bool,
+    #[serde(default)]
+    files: Vec<SubscriptionFile>,
+}
+
+#[derive(Deserialize)]
+struct SubscriptionFile {
+    name: String,
+    exists: bool,
+    #[serde(default)]
+    new: bool,
+}
@@ -0,0 +1,344 @@
+use std::{collections::HashMap, io::Read};
+
+use anyhow::{bail, Context, Result};
+use flate2::{read::DeflateDecoder, Crc};
+use turbo_tasks::{primitives::StringVc, CompletionVc, ValueToString, ValueToStringVc};
+
+use crate::{
+    util::normalize_path, DirectoryContent, DirectoryContentVc, DirectoryEntry, File, FileContent,
+    FileContentVc, FileMeta, FileMetaVc, FileSystem, FileSystemPathVc, FileSystemVc, LinkContent,
+    LinkContentVc,
+};
+
+/// A read-only [FileSystem] for the content of a zip archive, e. g. a package
+/// in the cache of Yarn Plug'n'Play.
+///
+/// The archive is read from another file system, so it's invalidated when the
+/// archive changes.
+#[turbo_tasks::value]
+pub struct ZipFileSystem {
+    archive: FileSystemPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ZipFileSystemVc {
+    #[turbo_tasks::function]
+    pub fn new(archive: FileSystemPathVc) -> Self {
+        Self::cell(ZipFileSystem { archive })
+    }
+
+    /// The path of the archive, on the file system it's read from.
+    #[turbo_tasks::function]
+    pub async fn archive(self) -> Result<FileSystemPathVc> {
+        Ok(self.await?.archive)
+    }
+
+    #[turbo_tasks::function]
+    async fn index(self) -> Result<ZipIndexVc> {
+        let archive = self.await?.archive;
+        let mut dirs: HashMap<String, HashMap<String, bool>> = HashMap::new();
+        let mut files = HashMap::new();
+        if let FileContent::Content(file) = &*archive.read().await? {
+            let archive_name = archive.to_string().await?;
+            let bytes = file.content().to_bytes();
+            let zip = ZipArchive::new(&bytes)
+                .with_context(|| format!("reading zip archive {archive_name}"))?;
+            dirs.insert(String::new(), HashMap::new());
+            for entry in zip.entries.iter() {
+                let is_dir = entry.is_dir();
+                let path = match normalize_path(entry.name) {
+                    Some(path) if !path.is_empty() => path,
+                    _ => continue,
+                };
+                let mut name = path.as_str();
+                let mut entry_is_dir = is_dir;
+                // Not every archive contains entries for its directories, so
+                // they are created for all parents of an entry.
+                loop {
+                    let (parent, file_name) = name.rsplit_once('/').unwrap_or(("", name));
+                    let entries = dirs.entry(parent.to_string()).or_default();
+                    let existed = entries.contains_key(file_name);
+                    if !existed || entry_is_dir {
+                        entries.insert(file_name.to_string(), entry_is_dir);
+                    }
+                    if existed || parent.is_empty() {
+                        break;
+                    }
+                    name = parent;
+                    entry_is_dir = true;
+                }
+                if is_dir {
+                    dirs.entry(path).or_default();
+                } else {
+                    files.insert(path, entry.name.to_string());
+                }
+            }
+        }
+        Ok(ZipIndex { dirs, files }.cell())
+    }
+}
+
+#[turbo_tasks::value]
+struct ZipIndex {
+    /// The directories of the archive, with the names of their entries and
+    /// whether these are directories.
+    dirs: HashMap<String, HashMap<String, bool>>,
+    /// The names of the files in the archive by their normalized path.
+    files: HashMap<String, String>,
+}
+
+impl ZipIndex {
+    fn entry(&self, path: &str) -> Option<bool> {
+        if path.is_empty() {
+            return (!self.dirs.is_empty()).then_some(true);
+        }
+        let (parent, name) = path.rsplit_once('/').unwrap_or(("", path));
+        self.dirs.get(parent)?.get(name).copied()
+    }
+}
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x04034b50;
+const CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+
+/// A minimal reader for the archives of package managers, which only contain
+/// stored or deflated entries. Zip64 and encryption are not supported.
+struct ZipArchive<'a> {
+    bytes: &'a [u8],
+    entries: Vec<ZipEntry<'a>>,
+}
+
+/// An entry of the central directory of a [ZipArchive].
+struct ZipEntry<'a> {
+    name: &'a str,
+    flags: u16,
+    method: u16,
+    crc32: u32,
+    compressed_size: usize,
+    size: usize,
+    local_header_offset: usize,
+}
+
+impl ZipEntry<'_> {
+    fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16> {
+    match bytes.get(offset..offset + 2) {
+        Some(b) => Ok(u16::from_le_bytes([b[0], b[1]])),
+        None => bail!("unexpected end of zip archive"),
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32> {
+    match bytes.get(offset..offset + 4) {
+        Some(b) => Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+        None => bail!("unexpected end of zip archive"),
+    }
+}
+
+impl<'a> ZipArchive<'a> {
+    fn new(bytes: &'a [u8]) -> Result<Self> {
+        // The end of central directory record is only followed by a comment
+        // of up to 64 KiB.
+        let last = bytes
+            .len()
+            .checked_sub(END_OF_CENTRAL_DIRECTORY_SIZE)
+            .context("not a zip archive")?;
+        let first = last.saturating_sub(u16::MAX as usize);
+        let end = (first..=last)
+            .rev()
+            .find(|&offset| {
+                read_u32(bytes, offset).ok() == Some(END_OF_CENTRAL_DIRECTORY_SIGNATURE)
+            })
+            .context("not a zip archive")?;
+        let count = read_u16(bytes, end + 10)?;
+        let offset = read_u32(bytes, end + 16)?;
+        if count == u16::MAX || offset == u32::MAX {
+            bail!("zip64 archives are not supported");
+        }
+        let mut offset = offset as usize;
+        let mut entries = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            if read_u32(bytes, offset)? != CENTRAL_DIRECTORY_SIGNATURE {
+                bail!("invalid central directory of zip archive");
+            }
+            let name_len = read_u16(bytes, offset + 28)? as usize;
+            let extra_len = read_u16(bytes, offset + 30)? as usize;
+            let comment_len = read_u16(bytes, offset + 32)? as usize;
+            let name = bytes
+                .get(offset + 46..offset + 46 + name_len)
+                .context("unexpected end of zip archive")?;
+            entries.push(ZipEntry {
+                name: std::str::from_utf8(name).context("zip entry name is not UTF-8")?,
+                flags: read_u16(bytes, offset + 8)?,
+                method: read_u16(bytes, offset + 10)?,
+                crc32: read_u32(bytes, offset + 16)?,
+                compressed_size: read_u32(bytes, offset + 20)? as usize,
+                size: read_u32(bytes, offset + 24)? as usize,
+                local_header_offset: read_u32(bytes, offset + 42)? as usize,
+            });
+            offset += 46 + name_len + extra_len + comment_len;
+        }
+        Ok(ZipArchive { bytes, entries })
+    }
+
+    fn by_name(&self, name: &str) -> Option<&ZipEntry<'a>> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    fn read(&self, entry: &ZipEntry) -> Result<Vec<u8>> {
+        if entry.flags & 1 != 0 {
+            bail!("encrypted zip entry {} is not supported", entry.name);
+        }
+        let offset = entry.local_header_offset;
+        if read_u32(self.bytes, offset)? != LOCAL_HEADER_SIGNATURE {
+            bail!("invalid local header of zip entry {}", entry.name);
+        }
+        // The sizes in the local header are zero when they follow the data,
+        // so the ones from the central directory are used.
+        let start = offset
+            + 30
+            + read_u16(self.bytes, offset + 26)? as usize
+            + read_u16(self.bytes, offset + 28)? as usize;
+        let data = self
+            .bytes
+            .get(start..start + entry.compressed_size)
+            .context("unexpected end of zip archive")?;
+        let content = match entry.method {
+            0 => data.to_vec(),
+            8 => {
+                let mut content = Vec::with_capacity(entry.size);
+                DeflateDecoder::new(data).read_to_end(&mut content)?;
+                content
+            }
+            method => bail!(
+                "compression method {method} of zip entry {} is not supported",
+                entry.name
+            ),
+        };
+        let mut crc = Crc::new();
+        crc.update(&content);
+        if content.len() != entry.size || crc.sum() != entry.crc32 {
+            bail!("zip entry {} is corrupted", entry.name);
+        }
+        Ok(content)
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl FileSystem for ZipFileSystem {
+    #[turbo_tasks::function]
+    async fn read(self_vc: ZipFileSystemVc, fs_path: FileSystemPathVc) -> Result<FileContentVc> {
+        let path = fs_path.await?;
+        let index = self_vc.index().await?;
+        let name = match index.files.get(&path.path) {
+            Some(name) => name,
+            None => return Ok(FileContent::NotFound.cell()),
+        };
+        let archive = self_vc.await?.archive;
+        let content = archive.read().await?;
+        let bytes = match &*content {
+            FileContent::Content(file) => file.content().to_bytes(),
+            FileContent::NotFound => return Ok(FileContent::NotFound.cell()),
+        };
+        let zip = ZipArchive::new(&bytes)?;
+        let entry = match zip.by_name(name) {
+            Some(entry) => entry,
+            None => return Ok(FileContent::NotFound.cell()),
+        };
+        Ok(File::from(zip.read(entry)?).into())
+    }
+
+    #[turbo_tasks::function]
+    fn read_link(&self, _fs_path: FileSystemPathVc) -> LinkContentVc {
+        LinkContent::NotFound.cell()
+    }
+
+    #[turbo_tasks::function]
+    async fn read_dir(
+        self_vc: ZipFileSystemVc,
+        fs_path: FileSystemPathVc,
+    ) -> Result<DirectoryContentVc> {
+        let index = self_vc.index().await?;
+        let path = fs_path.await?;
+        let entries = match index.dirs.get(&path.path) {
+            Some(entries) => entries,
+            None => return Ok(DirectoryContent::NotFound.cell()),
+        };
+        Ok(DirectoryContentVc::new(
+            entries
+                .iter()
+                .map(|(name, &is_dir)| {
+                    let entry_path = fs_path.join(name);
+                    (
+                        name.clone(),
+                        if is_dir {
+                            DirectoryEntry::Directory(entry_path)
+                        } else {
+                            DirectoryEntry::File(entry_path)
+                        },
+                    )
+                })
+                .collect(),
+        ))
+    }
+
+    #[turbo_tasks::function]
+    fn write(&self, _fs_path: FileSystemPathVc, _content: FileContentVc) -> Result<CompletionVc> {
+        bail!("Writing is not possible to a zip archive")
+    }
+
+    #[turbo_tasks::function]
+    fn write_link(
+        &self,
+        _fs_path: FileSystemPathVc,
+        _target: LinkContentVc,
+    ) -> Result<CompletionVc> {
+        bail!("Writing is not possible to a zip archive")
+    }
+
+    #[turbo_tasks::function]
+    async fn metadata(self_vc: ZipFileSystemVc, fs_path: FileSystemPathVc) -> Result<FileMetaVc> {
+        if self_vc.index().await?.entry(&fs_path.await?.path).is_none() {
+            bail!("path not found, can't read metadata");
+        }
+        Ok(FileMeta::default().cell())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for ZipFileSystem {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "zip {}",
+            self.archive.to_string().await?
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ZipArchive;
+
+    #[test]
+    fn test_zip_archive() {
+        // Contains a directory entry, a stored file and a deflated file.
+        let bytes = include_bytes!("../tests/fixtures/package.zip");
+        let zip = ZipArchive::new(bytes).unwrap();
+        let names: Vec<_> = zip.entries.iter().map(|entry| entry.name).collect();
+        assert_eq!(names, ["pkg/", "pkg/a.txt", "pkg/b.txt"]);
+        assert!(zip.by_name("pkg/").unwrap().is_dir());
+
+        let read = |name| zip.read(zip.by_name(name).unwrap()).unwrap();
+        assert_eq!(read("pkg/a.txt"), b"hello");
+        assert_eq!(read("pkg/b.txt"), "abc".repeat(20).as_bytes());
+        assert!(zip.by_name("pkg/c.txt").is_none());
+
+        assert!(ZipArchive::new(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
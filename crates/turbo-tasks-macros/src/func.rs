@@ -1,12 +1,15 @@
-use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use proc_macro2::{Ident, Literal, Span, TokenStream as TokenStream2};
 use proc_macro_error::abort;
 use quote::quote;
 use syn::{
     punctuated::Punctuated, spanned::Spanned, FnArg, Pat, PatIdent, PatType, Receiver, ReturnType,
-    Signature, Token, Type, TypePath, TypeReference,
+    Signature, Token, Type, TypePath, TypeReference, TypeSlice,
 };
 
-use crate::util::*;
+use crate::{
+    function_arguments::{FunctionArguments, RetryArguments},
+    util::*,
+};
 
 /// The underlying type of the `self` identifier.
 pub enum SelfType<'a> {
@@ -17,6 +20,8 @@ pub enum SelfType<'a> {
 
 pub fn gen_native_function_code(
     name_code: TokenStream2,
+    // The path of the function in its module, as used in its global name.
+    global_path: TokenStream2,
     original_function: TokenStream2,
     function_ident: &Ident,
     function_id_ident: &Ident,
@@ -24,6 +29,7 @@ pub fn gen_native_function_code(
     inputs: &Punctuated<FnArg, Token![,]>,
     output_type: &Type,
     self_ref_type: Option<(&Ident, SelfType<'_>)>,
+    arguments: &FunctionArguments,
 ) -> (TokenStream2, Vec<TokenStream2>) {
     let mut input_extraction = Vec::new();
     let mut input_convert = Vec::new();
@@ -31,8 +37,10 @@ pub fn gen_native_function_code(
     let mut input_final = Vec::new();
     let mut input_arguments = Vec::new();
     let mut input_raw_vc_arguments = Vec::new();
+    let mut argument_metadata = Vec::new();
 
     let mut index: i32 = 1;
+    let mut key_missing = arguments.keyed;
 
     for input in inputs {
         match input {
@@ -60,7 +68,16 @@ pub fn gen_native_function_code(
                         .ok_or_else(|| anyhow::anyhow!("{}() self argument missing", #name_code))?;
                 });
                 input_convert.push(quote! {
-                    let __self: #self_ref_ident = turbo_tasks::FromTaskInput::try_from(__self)?;
+                    let __self: #self_ref_ident = anyhow::Context::context(
+                        turbo_tasks::FromTaskInput::try_from(__self),
+                        concat!("self argument (", stringify!(#self_ref_ident), ") has an invalid value"),
+                    )?;
+                });
+                argument_metadata.push(quote! {
+                    turbo_tasks::NativeFunctionArgument {
+                        name: "self",
+                        ty: stringify!(#self_ref_ident),
+                    }
                 });
                 input_clone.push(quote! {
                     let __self = std::clone::Clone::clone(&__self);
@@ -99,51 +116,78 @@ pub fn gen_native_function_code(
                         .ok_or_else(|| anyhow::anyhow!(concat!("{}() argument ", stringify!(#index), " (", stringify!(#pat), ") missing"), #name_code))?;
                 });
                 input_final.push(quote! {});
-                if let Type::Reference(TypeReference {
+                let custom_self_type = if let Pat::Ident(PatIdent { ident, .. }) = &**pat {
+                    ident == "self_vc"
+                } else {
+                    false
+                };
+                let is_key = key_missing && !custom_self_type;
+                if is_key {
+                    key_missing = false;
+                    if let Type::Reference(_) = &**ty {
+                        abort!(
+                            ty.span(),
+                            "the key of a keyed function must be an owned value"
+                        );
+                    }
+                    // The key is passed as a transient value, so it doesn't need
+                    // a value type.
+                    let key_ty = quote! { turbo_tasks::TransientValue<#ty> };
+                    input_convert.push(convert_argument(index, pat, &key_ty));
+                    input_clone.push(quote! {
+                        let #pat = std::clone::Clone::clone(&#pat);
+                    });
+                    input_arguments.push(quote! {
+                        #pat.into_value()
+                    });
+                    argument_metadata.push(argument_metadata_code(pat, &quote! { #ty }));
+                } else if let Some((shared_ty, borrowed_ty)) = get_shared_borrowed_type(ty) {
+                    // Borrowed strings and bytes are converted to a shared
+                    // `Arc` once, so executions of the task only clone the
+                    // reference.
+                    input_convert.push(convert_argument(index, pat, &shared_ty));
+                    input_clone.push(quote! {
+                        let #pat = std::clone::Clone::clone(&#pat);
+                    });
+                    input_arguments.push(quote! {
+                        &*#pat
+                    });
+                    argument_metadata.push(argument_metadata_code(pat, &borrowed_ty));
+                } else if let Type::Reference(TypeReference {
                     and_token,
                     lifetime: _,
                     mutability,
                     elem,
                 }) = &**ty
                 {
-                    let ty = if let Type::Path(TypePath { qself: None, path }) = &**elem {
-                        if path.is_ident("str") {
-                            quote! { String }
-                        } else {
-                            quote! { #elem }
-                        }
-                    } else {
-                        quote! { #elem }
-                    };
-                    input_convert.push(quote! {
-                        let #pat: #ty = turbo_tasks::FromTaskInput::try_from(#pat)?;
-                    });
+                    let ty = quote! { #elem };
+                    input_convert.push(convert_argument(index, pat, &ty));
                     input_clone.push(quote! {
                         let #pat = std::clone::Clone::clone(&#pat);
                     });
                     input_arguments.push(quote! {
                         #and_token #mutability #pat
                     });
+                    argument_metadata.push(argument_metadata_code(pat, &ty));
                 } else {
-                    input_convert.push(quote! {
-                        let #pat: #ty = turbo_tasks::FromTaskInput::try_from(#pat)?;
-                    });
+                    let ty = quote! { #ty };
+                    input_convert.push(convert_argument(index, pat, &ty));
                     input_clone.push(quote! {
                         let #pat = std::clone::Clone::clone(&#pat);
                     });
                     input_arguments.push(quote! {
                         #pat
                     });
+                    argument_metadata.push(argument_metadata_code(pat, &ty));
                 }
-                let custom_self_type = if let Pat::Ident(PatIdent { ident, .. }) = &**pat {
-                    ident == "self_vc"
-                } else {
-                    false
-                };
                 if custom_self_type {
                     input_raw_vc_arguments.push(quote! {
                         self.into()
                     });
+                } else if is_key {
+                    input_raw_vc_arguments.push(quote! {
+                        turbo_tasks::TransientValue::new(#pat).into()
+                    });
                 } else {
                     input_raw_vc_arguments.push(quote! {
                         #pat.into()
@@ -153,6 +197,12 @@ pub fn gen_native_function_code(
             }
         }
     }
+    if key_missing {
+        abort!(
+            inputs.span(),
+            "a keyed function needs an argument for the key"
+        );
+    }
     let original_call_code = if async_function {
         quote! { #original_function(#(#input_arguments),*).await }
     } else {
@@ -170,6 +220,33 @@ pub fn gen_native_function_code(
         },
         (false, false) => quote! { Ok(#original_call_code.into()) },
     };
+    let resolve_values = arguments.resolve_values;
+    let execution_code = match &arguments.retry {
+        None => quote! {
+            Box::pin(async move {
+                #(#input_final)*
+                #original_call_code
+            })
+        },
+        Some(RetryArguments {
+            max_attempts,
+            backoff_ms,
+        }) => quote! {
+            Box::pin(async move {
+                let policy = turbo_tasks::RetryPolicy {
+                    max_attempts: #max_attempts,
+                    backoff: std::time::Duration::from_millis(#backoff_ms),
+                };
+                policy.run(|| {
+                    #(#input_clone)*
+                    async move {
+                        #(#input_final)*
+                        #original_call_code
+                    }
+                }).await
+            })
+        },
+    };
     (
         quote! {
             #[doc(hidden)]
@@ -184,14 +261,21 @@ pub fn gen_native_function_code(
                         #(#input_convert)*
                         Ok(Box::new(move || {
                             #(#input_clone)*
-                            Box::pin(async move {
-                                #(#input_final)*
-                                #original_call_code
-                            })
+                            #execution_code
                         }))
                     })
+                    .with_arguments(vec![#(#argument_metadata),*])
+                    .with_file(file!())
+                    .with_resolve_values(#resolve_values)
                 });
 
+            turbo_tasks::macro_helpers::auto_register! {
+                turbo_tasks::macro_helpers::Registration::Function {
+                    name: turbo_tasks::macro_helpers::registration_name!(#global_path),
+                    function: || &*#function_ident,
+                }
+            }
+
             #[doc(hidden)]
             pub(crate) static #function_id_ident: turbo_tasks::macro_helpers::Lazy<turbo_tasks::FunctionId> =
                 turbo_tasks::macro_helpers::Lazy::new(|| {
@@ -202,6 +286,53 @@ pub fn gen_native_function_code(
     )
 }
 
+/// Returns the shared type which a `&str` or `&[u8]` argument with any
+/// lifetime is converted to, and the borrowed type without the lifetime.
+fn get_shared_borrowed_type(ty: &Type) -> Option<(TokenStream2, TokenStream2)> {
+    let elem = match ty {
+        Type::Reference(TypeReference {
+            mutability: None,
+            elem,
+            ..
+        }) => elem,
+        _ => return None,
+    };
+    match &**elem {
+        Type::Path(TypePath { qself: None, path }) if path.is_ident("str") => {
+            Some((quote! { std::sync::Arc<str> }, quote! { &str }))
+        }
+        Type::Slice(TypeSlice { elem, .. }) => match &**elem {
+            Type::Path(TypePath { qself: None, path }) if path.is_ident("u8") => {
+                Some((quote! { std::sync::Arc<[u8]> }, quote! { &[u8] }))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Converts a task input to the type of the argument, with an error which
+/// names the argument when it has a different type. `NativeFunction::bind`
+/// adds the signature of the function to the error.
+fn convert_argument(index: i32, pat: &Pat, ty: &TokenStream2) -> TokenStream2 {
+    let index = Literal::i32_unsuffixed(index);
+    quote! {
+        let #pat: #ty = anyhow::Context::context(
+            turbo_tasks::FromTaskInput::try_from(#pat),
+            concat!("argument ", stringify!(#index), " (", stringify!(#pat), ": ", stringify!(#ty), ") has an invalid value"),
+        )?;
+    }
+}
+
+fn argument_metadata_code(pat: &Pat, ty: &TokenStream2) -> TokenStream2 {
+    quote! {
+        turbo_tasks::NativeFunctionArgument {
+            name: stringify!(#pat),
+            ty: stringify!(#ty),
+        }
+    }
+}
+
 pub fn split_signature(sig: &Signature) -> (Signature, Signature, Type, TokenStream2) {
     let output_type = get_return_type(&sig.output);
     let inline_ident = get_internal_function_ident(&sig.ident);
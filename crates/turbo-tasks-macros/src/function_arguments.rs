@@ -0,0 +1,115 @@
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Attribute, Lit, Meta, MetaNameValue, NestedMeta, Token,
+};
+
+/// Arguments to the `#[turbo_tasks::function]` attribute macro.
+#[derive(Debug, Default)]
+pub struct FunctionArguments {
+    /// Retries the function when it fails, e. g. because it depends on the
+    /// network. Set by `retry` or `retry(max_attempts = 3, backoff_ms = 100)`.
+    pub retry: Option<RetryArguments>,
+    /// Resolves the Vcs in the values of the arguments, e. g. in a
+    /// `Value<T>`, before calling the function, so calls with equal values
+    /// share a task. Set by `resolve_values`.
+    pub resolve_values: bool,
+    /// Memoizes the function per value of its first argument, which can be
+    /// any owned value, without declaring a value type for it. Set by
+    /// `keyed`.
+    pub keyed: bool,
+}
+
+#[derive(Debug)]
+pub struct RetryArguments {
+    /// How often the function is called before its error is returned.
+    pub max_attempts: u32,
+    /// The delay before the second attempt, which doubles for every further
+    /// attempt.
+    pub backoff_ms: u64,
+}
+
+impl Default for RetryArguments {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff_ms: 100,
+        }
+    }
+}
+
+impl FunctionArguments {
+    /// Parses the arguments of a `#[turbo_tasks::function]` attribute on a
+    /// method in a `#[turbo_tasks::value_impl]`.
+    pub fn from_attribute(attr: &Attribute) -> syn::Result<Self> {
+        if attr.tokens.is_empty() {
+            Ok(Self::default())
+        } else {
+            attr.parse_args_with(Self::parse)
+        }
+    }
+}
+
+impl Parse for FunctionArguments {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut result = Self::default();
+        if input.is_empty() {
+            return Ok(result);
+        }
+
+        let punctuated: Punctuated<Meta, Token![,]> = input.parse_terminated(Meta::parse)?;
+        for meta in punctuated {
+            match &meta {
+                Meta::Path(path) if path.is_ident("retry") => {
+                    result.retry = Some(RetryArguments::default());
+                }
+                Meta::Path(path) if path.is_ident("resolve_values") => {
+                    result.resolve_values = true;
+                }
+                Meta::Path(path) if path.is_ident("keyed") => {
+                    result.keyed = true;
+                }
+                Meta::List(list) if list.path.is_ident("retry") => {
+                    let mut retry = RetryArguments::default();
+                    for nested in &list.nested {
+                        match nested {
+                            NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                                path,
+                                lit: Lit::Int(value),
+                                ..
+                            })) if path.is_ident("max_attempts") => {
+                                retry.max_attempts = value.base10_parse()?;
+                                if retry.max_attempts == 0 {
+                                    return Err(syn::Error::new_spanned(
+                                        value,
+                                        "max_attempts must be at least 1",
+                                    ));
+                                }
+                            }
+                            NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                                path,
+                                lit: Lit::Int(value),
+                                ..
+                            })) if path.is_ident("backoff_ms") => {
+                                retry.backoff_ms = value.base10_parse()?;
+                            }
+                            _ => {
+                                return Err(syn::Error::new_spanned(
+                                    nested,
+                                    "unknown retry parameter, expected max_attempts = <int> or \
+                                     backoff_ms = <int>",
+                                ));
+                            }
+                        }
+                    }
+                    result.retry = Some(retry);
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(meta, "unknown parameter"));
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
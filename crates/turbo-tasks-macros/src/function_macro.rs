@@ -4,7 +4,10 @@ use quote::quote;
 use syn::{parse_macro_input, ItemFn};
 use turbo_tasks_macros_shared::get_function_ident;
 
-use crate::func::{gen_native_function_code, split_signature};
+use crate::{
+    func::{gen_native_function_code, split_signature},
+    function_arguments::FunctionArguments,
+};
 
 fn get_function_id_ident(ident: &Ident) -> Ident {
     Ident::new(
@@ -13,7 +16,8 @@ fn get_function_id_ident(ident: &Ident) -> Ident {
     )
 }
 
-pub fn function(_args: TokenStream, input: TokenStream) -> TokenStream {
+pub fn function(args: TokenStream, input: TokenStream) -> TokenStream {
+    let arguments = parse_macro_input!(args as FunctionArguments);
     let item = parse_macro_input!(input as ItemFn);
     let ItemFn {
         attrs,
@@ -28,6 +32,7 @@ pub fn function(_args: TokenStream, input: TokenStream) -> TokenStream {
     let inline_ident = &inline_sig.ident;
 
     let (native_function_code, input_raw_vc_arguments) = gen_native_function_code(
+        quote! { stringify!(#ident) },
         quote! { stringify!(#ident) },
         quote! { #inline_ident },
         &function_ident,
@@ -36,6 +41,7 @@ pub fn function(_args: TokenStream, input: TokenStream) -> TokenStream {
         &sig.inputs,
         &output_type,
         None,
+        &arguments,
     );
 
     quote! {
@@ -4,6 +4,7 @@
 
 mod derive;
 mod func;
+mod function_arguments;
 mod function_macro;
 mod util;
 mod value_impl_macro;
@@ -52,6 +53,12 @@ pub fn derive_deterministic_hash(input: TokenStream) -> TokenStream {
 /// `into: shared`: Compares with the existing value in the cell, before
 /// overriding it. Requires Value to implement [Eq].
 ///
+/// `cell: new`: Cells of the value always invalidate the tasks reading them
+/// when they are assigned. Otherwise the content of a cell is kept when an
+/// equal value is assigned to it. This can be changed per value type with
+/// `turbo_tasks::registry::register_cell_equality` and
+/// `turbo_tasks::registry::unregister_cell_equality`.
+///
 /// TODO: add more documentation: presets, traits
 #[allow_internal_unstable(min_specialization, into_future, trivial_bounds)]
 #[proc_macro_error]
@@ -67,6 +74,34 @@ pub fn value_trait(args: TokenStream, input: TokenStream) -> TokenStream {
     value_trait_macro::value_trait(args, input)
 }
 
+/// Turns a function into a turbo-tasks function, which is executed as a task
+/// and cached until its inputs change.
+///
+/// Arguments can be borrowed as `&str` or `&[u8]`, with any lifetime. They are
+/// stored in the task once, and shared by all executions of it.
+///
+/// `retry` argument (`#[turbo_tasks::function(retry(max_attempts = 3,
+/// backoff_ms = 100))]`)
+///
+/// Calls the function again when it fails, with an exponential backoff, and
+/// only returns the error of the last attempt. Useful for functions which
+/// depend on flaky resources, like the network. `retry` alone uses the values
+/// above.
+///
+/// `resolve_values` argument (`#[turbo_tasks::function(resolve_values)]`)
+///
+/// Resolves the Vcs in the values of the arguments, e. g. the Vc fields of a
+/// `Value<T>`, before the function is called. Without it, values which only
+/// differ in unresolved Vcs pointing to the same cell create separate tasks,
+/// which do the same work. Only serializable values are resolved.
+///
+/// `keyed` argument (`#[turbo_tasks::function(keyed)]`)
+///
+/// Memoizes the function per value of its first argument, the key. The key can
+/// be any owned value which implements `Debug`, `Clone`, `Eq`, `Ord` and
+/// `Hash`, e. g. an enum of file extensions, without declaring a value type for
+/// it. Like `TransientValue`s, keys aren't stored in persistent caches. Not
+/// supported for trait methods.
 #[allow_internal_unstable(min_specialization, into_future, trivial_bounds)]
 #[proc_macro_error]
 #[proc_macro_attribute]
@@ -1,5 +1,6 @@
 use proc_macro::TokenStream;
 use proc_macro2::{Ident, Literal, Span, TokenStream as TokenStream2};
+use proc_macro_error::abort;
 use quote::quote;
 use syn::{
     parse_macro_input, spanned::Spanned, Attribute, FnArg, ImplItem, ImplItemMethod, ItemImpl,
@@ -12,6 +13,7 @@ use turbo_tasks_macros_shared::{
 
 use crate::{
     func::{gen_native_function_code, split_signature, SelfType},
+    function_arguments::FunctionArguments,
     util::*,
 };
 
@@ -91,6 +93,11 @@ pub fn value_impl(_args: TokenStream, input: TokenStream) -> TokenStream {
                         .cloned()
                         .collect()
                 };
+                let arguments = function_attr
+                    .map(FunctionArguments::from_attribute)
+                    .transpose()
+                    .unwrap_or_else(|err| abort!(err.span(), "{}", err))
+                    .unwrap_or_default();
                 let Signature { ident, .. } = sig;
 
                 let (external_sig, inline_sig, output_type, convert_result_code) =
@@ -103,6 +110,7 @@ pub fn value_impl(_args: TokenStream, input: TokenStream) -> TokenStream {
                 let (native_function_code, input_raw_vc_arguments) = gen_native_function_code(
                     // use const string
                     quote! { format!(concat!("{}::", stringify!(#ident)), std::any::type_name::<#vc_ident>()) },
+                    quote! { concat!(stringify!(#vc_ident), "::", stringify!(#ident)) },
                     quote! { #vc_ident::#inline_ident },
                     &function_ident,
                     &function_id_ident,
@@ -110,6 +118,7 @@ pub fn value_impl(_args: TokenStream, input: TokenStream) -> TokenStream {
                     &sig.inputs,
                     &output_type,
                     Some((vc_ident, SelfType::Ref)),
+                    &arguments,
                 );
 
                 functions.push(quote! {
@@ -168,6 +177,11 @@ pub fn value_impl(_args: TokenStream, input: TokenStream) -> TokenStream {
                         .cloned()
                         .collect()
                 };
+                let arguments = function_attr
+                    .map(FunctionArguments::from_attribute)
+                    .transpose()
+                    .unwrap_or_else(|err| abort!(err.span(), "{}", err))
+                    .unwrap_or_default();
                 let Signature {
                     ident,
                     inputs,
@@ -175,6 +189,10 @@ pub fn value_impl(_args: TokenStream, input: TokenStream) -> TokenStream {
                     asyncness,
                     ..
                 } = sig;
+                if arguments.keyed {
+                    // Trait calls don't know which argument is the key.
+                    abort!(ident.span(), "trait methods can't be keyed");
+                }
                 let output_type = get_return_type(output);
                 let function_ident =
                     get_trait_impl_function_ident(struct_ident, trait_ident, ident);
@@ -188,6 +206,7 @@ pub fn value_impl(_args: TokenStream, input: TokenStream) -> TokenStream {
                 let name = Literal::string(&(struct_ident.to_string() + "::" + &ident.to_string()));
                 let (native_function_code, mut input_raw_vc_arguments) = gen_native_function_code(
                     quote! { #name },
+                    quote! { concat!(stringify!(#struct_ident), "::", stringify!(#trait_ident), "::", stringify!(#ident)) },
                     quote! { #struct_ident::#internal_function_ident },
                     &function_ident,
                     &function_id_ident,
@@ -195,6 +214,7 @@ pub fn value_impl(_args: TokenStream, input: TokenStream) -> TokenStream {
                     inputs,
                     &output_type,
                     Some((&ref_ident, SelfType::Value(struct_ident))),
+                    &arguments,
                 );
                 let mut new_sig = sig.clone();
                 new_sig.ident = internal_function_ident;
@@ -251,6 +271,13 @@ pub fn value_impl(_args: TokenStream, input: TokenStream) -> TokenStream {
                 #(#trait_registers)*
             }
 
+            turbo_tasks::macro_helpers::auto_register! {
+                turbo_tasks::macro_helpers::Registration::TraitImpl {
+                    value: turbo_tasks::macro_helpers::registration_name!(stringify!(#struct_ident)),
+                    register: #register,
+                }
+            }
+
             #(#impl_functions)*
 
             impl #ref_ident {
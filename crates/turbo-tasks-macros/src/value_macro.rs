@@ -551,7 +551,7 @@ pub fn value(args: TokenStream, input: TokenStream) -> TokenStream {
                     panic!(
                         concat!(
                             stringify!(#value_type_ident),
-                            " has not been initialized (this should happen via the generated register function)"
+                            " has not been initialized (this should happen via the generated register function or the auto_register feature of turbo-tasks)"
                         )
                     )
                 })
@@ -564,7 +564,7 @@ pub fn value(args: TokenStream, input: TokenStream) -> TokenStream {
         #[doc(hidden)]
         #[allow(non_snake_case)]
         pub(crate) fn #register_value_type_ident(
-            global_name: &'static str,
+            global_name: &str,
             f: impl FnOnce(&mut turbo_tasks::ValueType),
         ) {
             #value_type_init_ident.get_or_init(|| {
@@ -573,6 +573,12 @@ pub fn value(args: TokenStream, input: TokenStream) -> TokenStream {
                 value
             }).register(global_name);
         }
+        turbo_tasks::macro_helpers::auto_register! {
+            turbo_tasks::macro_helpers::Registration::ValueType {
+                name: turbo_tasks::macro_helpers::registration_name!(stringify!(#ident)),
+                register: |global_name, f| #register_value_type_ident(global_name, f),
+            }
+        }
 
         impl turbo_tasks::Typed for #ident {
             fn get_value_type_id() -> turbo_tasks::ValueTypeId {
@@ -623,6 +629,12 @@ pub fn value(args: TokenStream, input: TokenStream) -> TokenStream {
                 Ok(raw_vc.map(|raw_vc| #ref_ident { node: raw_vc }))
             }
 
+            /// Casts to the value trait `T`, if this value implements it.
+            /// see [turbo_tasks::RawVc::try_cast]
+            pub async fn try_cast<T: turbo_tasks::ValueTraitVc>(self) -> Result<Option<T>, turbo_tasks::ResolveTypeError> {
+                self.node.try_cast().await
+            }
+
             #strongly_consistent
         }
 
@@ -654,6 +666,7 @@ pub fn value(args: TokenStream, input: TokenStream) -> TokenStream {
             type Error = turbo_tasks::Error;
 
             fn try_from(value: &turbo_tasks::TaskInput) -> Result<Self, Self::Error> {
+                value.ensure_value_type(*#value_type_id_ident, std::any::type_name::<Self>())?;
                 Ok(Self { node: value.try_into()? })
             }
         }
@@ -11,6 +11,7 @@ use turbo_tasks_macros_shared::{
 
 use crate::{
     func::{gen_native_function_code, split_signature, SelfType},
+    function_arguments::FunctionArguments,
     util::*,
 };
 
@@ -108,6 +109,7 @@ pub fn value_trait(args: TokenStream, input: TokenStream) -> TokenStream {
 
             let (native_function_code, input_raw_vc_arguments) = gen_native_function_code(
                 quote! { format!(concat!("{}::", stringify!(#method_ident)), std::any::type_name::<#ref_ident>()) },
+                quote! { concat!(stringify!(#ident), "::", stringify!(#method_ident)) },
                 quote! { #ref_ident::#inline_ident },
                 &function_ident,
                 &function_id_ident,
@@ -115,6 +117,7 @@ pub fn value_trait(args: TokenStream, input: TokenStream) -> TokenStream {
                 inputs,
                 &output_type,
                 Some((&ref_ident, SelfType::ValueTrait)),
+                &FunctionArguments::default(),
             );
 
             trait_fns.push(quote! {
@@ -190,6 +193,12 @@ pub fn value_trait(args: TokenStream, input: TokenStream) -> TokenStream {
                 #(#default_method_registers)*
                 trait_type
             });
+        turbo_tasks::macro_helpers::auto_register! {
+            turbo_tasks::macro_helpers::Registration::TraitType {
+                name: turbo_tasks::macro_helpers::registration_name!(stringify!(#ident)),
+                trait_type: || &*#trait_type_ident,
+            }
+        }
         #[doc(hidden)]
         static #trait_type_id_ident: turbo_tasks::macro_helpers::Lazy<turbo_tasks::TraitTypeId> =
             turbo_tasks::macro_helpers::Lazy::new(|| {
@@ -223,6 +232,18 @@ pub fn value_trait(args: TokenStream, input: TokenStream) -> TokenStream {
                 Ok(raw_vc.map(|raw_vc| #ref_ident { node: raw_vc }))
             }
 
+            /// Casts to the value trait `T`, if the value implements it.
+            /// see [turbo_tasks::RawVc::try_cast]
+            pub async fn try_cast<T: turbo_tasks::ValueTraitVc>(self) -> Result<Option<T>, turbo_tasks::ResolveTypeError> {
+                self.node.try_cast().await
+            }
+
+            /// Casts to the concrete value type `T`, if the value has that type.
+            /// see [turbo_tasks::RawVc::downcast]
+            pub async fn downcast<T: turbo_tasks::ValueVc>(self) -> Result<Option<T>, turbo_tasks::ResolveTypeError> {
+                self.node.downcast().await
+            }
+
             pub fn cast_from(super_trait_vc: impl std::convert::Into<turbo_tasks::RawVc>) -> Self {
                 let raw_vc: turbo_tasks::RawVc = super_trait_vc.into();
                 #ref_ident { node: raw_vc }
@@ -270,6 +291,7 @@ pub fn value_trait(args: TokenStream, input: TokenStream) -> TokenStream {
             type Error = turbo_tasks::Error;
 
             fn try_from(value: &turbo_tasks::TaskInput) -> Result<Self, Self::Error> {
+                value.ensure_trait_type(*#trait_type_id_ident, std::any::type_name::<Self>())?;
                 Ok(Self { node: std::convert::TryFrom::try_from(value)? })
             }
         }
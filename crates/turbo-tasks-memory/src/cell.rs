@@ -1,32 +1,158 @@
-use std::{collections::HashSet, fmt::Debug};
+use std::{
+    any::Any,
+    collections::HashSet,
+    fmt::{self, Debug, Formatter},
+    io::{Read, Write},
+    sync::{Arc, Weak},
+};
 
-use turbo_tasks::{backend::CellContent, TaskId, TurboTasksBackendApi};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use turbo_tasks::{
+    backend::CellContent, registry, CellCompression, SharedReference, TaskId, TurboTasksBackendApi,
+    ValueTypeId,
+};
 
 #[derive(Default, Debug)]
 pub struct Cell {
-    content: CellContent,
+    content: StoredContent,
     updates: u32,
     pub(crate) dependent_tasks: HashSet<TaskId>,
 }
 
+/// The content of a cell, compressed when the value type has a
+/// [CellCompression] and the value is large enough.
+#[derive(Default)]
+enum StoredContent {
+    #[default]
+    Empty,
+    Value(SharedReference),
+    Compressed {
+        type_id: ValueTypeId,
+        compression: CellCompression,
+        bytes: Box<[u8]>,
+        /// The last decompressed value, which is reused as long as a reader
+        /// holds it.
+        decompressed: Weak<dyn Any + Send + Sync>,
+    },
+}
+
+impl Debug for StoredContent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            StoredContent::Empty => write!(f, "Empty"),
+            StoredContent::Value(value) => f.debug_tuple("Value").field(value).finish(),
+            StoredContent::Compressed { type_id, bytes, .. } => f
+                .debug_struct("Compressed")
+                .field("type_id", type_id)
+                .field("len", &bytes.len())
+                .finish(),
+        }
+    }
+}
+
+impl StoredContent {
+    fn new(content: CellContent) -> Self {
+        let shared_ref = match content.0 {
+            Some(shared_ref) => shared_ref,
+            None => return StoredContent::Empty,
+        };
+        if let Some(type_id) = shared_ref.0 {
+            if let Some(compression) = registry::get_cell_compression(type_id) {
+                let bytes = compression.to_bytes(&*shared_ref.1);
+                if bytes.len() >= compression.threshold {
+                    let compressed = compress(&bytes);
+                    // Incompressible values are stored as they are.
+                    if compressed.len() < bytes.len() {
+                        return StoredContent::Compressed {
+                            type_id,
+                            compression,
+                            bytes: compressed.into_boxed_slice(),
+                            decompressed: Arc::downgrade(&shared_ref.1),
+                        };
+                    }
+                }
+            }
+        }
+        StoredContent::Value(shared_ref)
+    }
+
+    /// Decompresses the value lazily, when it's read.
+    fn read(&self) -> (CellContent, Option<Weak<dyn Any + Send + Sync>>) {
+        match self {
+            StoredContent::Empty => (CellContent(None), None),
+            StoredContent::Value(value) => (CellContent(Some(value.clone())), None),
+            StoredContent::Compressed {
+                type_id,
+                compression,
+                bytes,
+                decompressed,
+            } => {
+                if let Some(value) = decompressed.upgrade() {
+                    return (
+                        CellContent(Some(SharedReference(Some(*type_id), value))),
+                        None,
+                    );
+                }
+                let value = compression.from_bytes(decompress(bytes));
+                let weak = Arc::downgrade(&value);
+                (
+                    CellContent(Some(SharedReference(Some(*type_id), value))),
+                    Some(weak),
+                )
+            }
+        }
+    }
+}
+
+/// Compresses with the fastest level, as cells are written on the hot path
+/// and most of the savings come from the repetitiveness of generated code.
+fn compress(bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+    encoder
+        .write_all(bytes)
+        .expect("compressing into memory can't fail");
+    encoder
+        .finish()
+        .expect("compressing into memory can't fail")
+}
+
+fn decompress(bytes: &[u8]) -> Vec<u8> {
+    let mut decompressed = Vec::new();
+    DeflateDecoder::new(bytes)
+        .read_to_end(&mut decompressed)
+        .expect("cell content was compressed by the memory backend");
+    decompressed
+}
+
 impl Cell {
     pub fn read_content(&mut self, reader: TaskId) -> CellContent {
         self.dependent_tasks.insert(reader);
-        self.read_content_untracked()
+        let (content, decompressed) = self.content.read();
+        if let (Some(new_decompressed), StoredContent::Compressed { decompressed, .. }) =
+            (decompressed, &mut self.content)
+        {
+            *decompressed = new_decompressed;
+        }
+        content
     }
 
     /// INVALIDATION: Be careful with this, it will not track dependencies, so
     /// using it could break cache invalidation.
     pub fn read_content_untracked(&self) -> CellContent {
-        self.content.clone()
+        self.content.read().0
     }
 
     pub fn track_read(&mut self, reader: TaskId) {
         self.dependent_tasks.insert(reader);
     }
 
+    /// The number of times the cell was assigned.
+    pub fn updates(&self) -> u32 {
+        self.updates
+    }
+
     pub fn assign(&mut self, content: CellContent, turbo_tasks: &dyn TurboTasksBackendApi) {
-        self.content = content;
+        self.content = StoredContent::new(content);
         self.updates += 1;
         // notify
         if !self.dependent_tasks.is_empty() {
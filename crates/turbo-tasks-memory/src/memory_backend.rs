@@ -5,6 +5,7 @@ use std::{
     future::Future,
     hash::BuildHasherDefault,
     pin::Pin,
+    sync::atomic::{AtomicUsize, Ordering},
     time::{Duration, Instant},
 };
 
@@ -14,8 +15,8 @@ use rustc_hash::FxHasher;
 use tokio::task::futures::TaskLocalFuture;
 use turbo_tasks::{
     backend::{
-        Backend, BackendJobId, CellContent, PersistentTaskType, TaskExecutionSpec,
-        TransientTaskType,
+        Backend, BackendJobId, CellContent, PersistentTaskType, SnapshotOutput, TaskExecutionSpec,
+        TaskSnapshot, TrackedRead, TransientTaskType,
     },
     event::EventListener,
     util::{IdFactory, NoMoveVec},
@@ -25,6 +26,7 @@ use turbo_tasks::{
 use crate::{
     output::Output,
     scope::{TaskScope, TaskScopeId},
+    stats::{CellUpdateStats, StorageStats},
     task::{
         run_add_to_scope_queue, run_remove_from_scope_queue, Task, TaskDependency,
         DEPENDENCIES_TO_TRACK,
@@ -39,6 +41,11 @@ pub struct MemoryBackend {
     backend_jobs: NoMoveVec<Job>,
     backend_job_id_factory: IdFactory<BackendJobId>,
     task_cache: DashMap<PersistentTaskType, TaskId, BuildHasherDefault<FxHasher>>,
+    freed_bytes: AtomicUsize,
+    shrinks: AtomicUsize,
+    cell_updates: AtomicUsize,
+    unchanged_cell_updates: AtomicUsize,
+    spared_invalidations: AtomicUsize,
 }
 
 impl Default for MemoryBackend {
@@ -63,6 +70,54 @@ impl MemoryBackend {
             backend_jobs: NoMoveVec::new(),
             backend_job_id_factory: IdFactory::new(),
             task_cache: DashMap::default(),
+            freed_bytes: AtomicUsize::new(0),
+            shrinks: AtomicUsize::new(0),
+            cell_updates: AtomicUsize::new(0),
+            unchanged_cell_updates: AtomicUsize::new(0),
+            spared_invalidations: AtomicUsize::new(0),
+        }
+    }
+
+    /// Frees the memory of storage segments which only contain removed tasks,
+    /// scopes or jobs.
+    ///
+    /// Returns the number of bytes freed.
+    ///
+    /// # Safety
+    /// There must not be a concurrent operation on the backend and no
+    /// reference to a task, scope or job must be alive.
+    unsafe fn shrink(&self) -> usize {
+        let freed = unsafe {
+            self.memory_tasks.shrink()
+                + self.memory_task_scopes.shrink()
+                + self.backend_jobs.shrink()
+        };
+        self.freed_bytes.fetch_add(freed, Ordering::Relaxed);
+        self.shrinks.fetch_add(1, Ordering::Relaxed);
+        freed
+    }
+
+    /// The memory used by the storage of tasks, scopes and jobs.
+    pub fn storage_stats(&self) -> StorageStats {
+        StorageStats {
+            task_capacity: self.memory_tasks.capacity(),
+            scope_capacity: self.memory_task_scopes.capacity(),
+            job_capacity: self.backend_jobs.capacity(),
+            allocated_bytes: self.memory_tasks.allocated_bytes()
+                + self.memory_task_scopes.allocated_bytes()
+                + self.backend_jobs.allocated_bytes(),
+            freed_bytes: self.freed_bytes.load(Ordering::Relaxed),
+            shrinks: self.shrinks.load(Ordering::Relaxed),
+        }
+    }
+
+    /// How often assigning an equal value to a cell spared invalidating the
+    /// tasks which read it.
+    pub fn cell_update_stats(&self) -> CellUpdateStats {
+        CellUpdateStats {
+            updates: self.cell_updates.load(Ordering::Relaxed),
+            unchanged: self.unchanged_cell_updates.load(Ordering::Relaxed),
+            spared_invalidations: self.spared_invalidations.load(Ordering::Relaxed),
         }
     }
 
@@ -187,6 +242,20 @@ impl MemoryBackend {
 }
 
 impl Backend for MemoryBackend {
+    fn stop(&self, _turbo_tasks: &dyn TurboTasksBackendApi) {
+        // SAFETY: `stop` is only called once no task and no background job is
+        // running anymore, and a stopped TurboTasks ignores invalidations and
+        // disposals, so nothing accesses the storage concurrently. Only
+        // segments without any live task, scope or job are freed, so even a
+        // call which was already in flight when TurboTasks was stopped can
+        // only reach items which are not freed. No reference into the storage
+        // outlives the call which created it, as tasks only hand out clones
+        // of cell contents.
+        unsafe {
+            self.shrink();
+        }
+    }
+
     fn invalidate_task(&self, task: TaskId, turbo_tasks: &dyn TurboTasksBackendApi) {
         self.with_task(task, |task| task.invalidate(self, turbo_tasks));
     }
@@ -222,6 +291,7 @@ impl Backend for MemoryBackend {
             if task.execution_started(self, turbo_tasks) {
                 Some(TaskExecutionSpec {
                     future: task.execute(turbo_tasks),
+                    function: task.function(),
                 })
             } else {
                 None
@@ -350,6 +420,99 @@ impl Backend for MemoryBackend {
         }
     }
 
+    fn try_read_task_output_batched(
+        &self,
+        task: TaskId,
+        reader: TaskId,
+        strongly_consistent: bool,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Result<Result<(RawVc, TrackedRead), EventListener>> {
+        if task == reader {
+            bail!("reading it's own output is not possible");
+        }
+        self.try_get_output(
+            task,
+            strongly_consistent,
+            move || format!("reading task output from {reader}"),
+            turbo_tasks,
+            |output| {
+                let read = TrackedRead {
+                    task,
+                    cell: None,
+                    version: output.updates(),
+                };
+                match output.read_untracked() {
+                    Ok(vc) => Ok((vc, read)),
+                    Err(err) => {
+                        // The error is returned instead of the read, so the
+                        // dependency can't be batched
+                        Task::add_dependency_to_current(TaskDependency::TaskOutput(task));
+                        output.track_read(reader);
+                        Err(err)
+                    }
+                }
+            },
+        )
+    }
+
+    fn try_read_task_cell_batched(
+        &self,
+        task: TaskId,
+        index: CellId,
+        _reader: TaskId,
+        _turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Result<Result<(CellContent, TrackedRead), EventListener>> {
+        // Only a read lock is needed, as the reader is registered later
+        Ok(Ok(self.with_task(task, |task_ref| {
+            task_ref.with_cell(index, |cell| {
+                let read = TrackedRead {
+                    task,
+                    cell: Some(index),
+                    version: cell.updates(),
+                };
+                (cell.read_content_untracked(), read)
+            })
+        })))
+    }
+
+    fn track_reads(
+        &self,
+        reader: TaskId,
+        mut reads: Vec<TrackedRead>,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) {
+        // Reading own cells doesn't create a dependency
+        reads.retain(|read| read.task != reader);
+        if reads.is_empty() {
+            return;
+        }
+        reads.sort_unstable_by_key(|read| read.task);
+        let mut changed = false;
+        let mut start = 0;
+        while start < reads.len() {
+            let task = reads[start].task;
+            let end = start
+                + reads[start..]
+                    .iter()
+                    .position(|read| read.task != task)
+                    .unwrap_or(reads.len() - start);
+            let task_reads = &reads[start..end];
+            for read in task_reads {
+                Task::add_dependency_to_current(match read.cell {
+                    Some(index) => TaskDependency::TaskCell(task, index),
+                    None => TaskDependency::TaskOutput(task),
+                });
+            }
+            changed |= self.with_task(task, |task| task.track_reads(reader, task_reads));
+            start = end;
+        }
+        if changed {
+            // The reader wasn't notified about these changes, as it wasn't
+            // registered yet
+            turbo_tasks.schedule_notify_tasks(&[reader]);
+        }
+    }
+
     fn try_read_task_collectibles(
         &self,
         id: TaskId,
@@ -395,7 +558,18 @@ impl Backend for MemoryBackend {
     ) {
         self.with_task(task, |task| {
             task.with_cell_mut(index, |cell| cell.assign(content, turbo_tasks))
-        })
+        });
+        self.cell_updates.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn keep_task_cell(&self, task: TaskId, index: CellId) {
+        let dependent_tasks = self.with_task(task, |task| {
+            task.with_cell_mut(index, |cell| cell.dependent_tasks.len())
+        });
+        self.cell_updates.fetch_add(1, Ordering::Relaxed);
+        self.unchanged_cell_updates.fetch_add(1, Ordering::Relaxed);
+        self.spared_invalidations
+            .fetch_add(dependent_tasks, Ordering::Relaxed);
     }
 
     /// SAFETY: Must only called once with the same id
@@ -434,25 +608,7 @@ impl Backend for MemoryBackend {
         } else {
             // slow pass with key lock
             let id = turbo_tasks.get_fresh_task_id();
-            let task = match &task_type {
-                PersistentTaskType::Native(fn_id, inputs) => {
-                    // TODO inputs doesn't need to be cloned when are would be able to get a
-                    // reference to the task type stored inside of the task
-                    Task::new_native(id, inputs.clone(), *fn_id, turbo_tasks.stats_type())
-                }
-                PersistentTaskType::ResolveNative(fn_id, inputs) => {
-                    Task::new_resolve_native(id, inputs.clone(), *fn_id, turbo_tasks.stats_type())
-                }
-                PersistentTaskType::ResolveTrait(trait_type, trait_fn_name, inputs) => {
-                    Task::new_resolve_trait(
-                        id,
-                        *trait_type,
-                        trait_fn_name.clone(),
-                        inputs.clone(),
-                        turbo_tasks.stats_type(),
-                    )
-                }
-            };
+            let task = new_persistent_task(id, &task_type, turbo_tasks);
             // Safety: We have a fresh task id that nobody knows about yet
             unsafe {
                 self.memory_tasks.insert(*id, task);
@@ -502,6 +658,120 @@ impl Backend for MemoryBackend {
         println!("new {scope} for {task}");
         id
     }
+
+    fn dispose_root_task(&self, task: TaskId, turbo_tasks: &dyn TurboTasksBackendApi) {
+        self.with_task(task, |task| {
+            task.remove_root_or_initial_scope(self, turbo_tasks)
+        });
+    }
+
+    fn get_persistent_task_type(&self, task: TaskId) -> Option<PersistentTaskType> {
+        self.with_task(task, |task| task.get_persistent_task_type())
+    }
+
+    fn snapshot_task(
+        &self,
+        task: TaskId,
+        _turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Option<TaskSnapshot> {
+        self.with_task(task, |task| task.snapshot())
+    }
+
+    fn restore_task(
+        &self,
+        snapshot: TaskSnapshot,
+        parent_task: Option<TaskId>,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Option<TaskId> {
+        let TaskSnapshot {
+            task_type,
+            cells,
+            children,
+            output,
+            dependencies,
+        } = snapshot;
+        if let Some(task) = self.task_cache.get(&task_type).map(|task| *task) {
+            // The task already exists, its state is more recent than the snapshot
+            if let Some(parent_task) = parent_task {
+                self.connect_task_child(parent_task, task, turbo_tasks);
+            }
+            return Some(task);
+        }
+        let id = turbo_tasks.get_fresh_task_id();
+        let task = new_persistent_task(id, &task_type, turbo_tasks);
+        let output = output.map(|output| match output {
+            SnapshotOutput::OwnCell(index) => RawVc::TaskCell(id, index),
+            SnapshotOutput::Link(output) => output,
+        });
+        task.restore(cells, output, &dependencies, turbo_tasks);
+        // Safety: We have a fresh task id that nobody knows about yet
+        unsafe {
+            self.memory_tasks.insert(*id, task);
+        }
+        match self.task_cache.entry(task_type) {
+            Entry::Vacant(entry) => {
+                entry.insert(id);
+            }
+            Entry::Occupied(entry) => {
+                // Safety: We have a fresh task id that nobody knows about yet
+                unsafe {
+                    self.memory_tasks.remove(*id);
+                    turbo_tasks.reuse_task_id(id);
+                }
+                let task = *entry.get();
+                drop(entry);
+                if let Some(parent_task) = parent_task {
+                    self.connect_task_child(parent_task, task, turbo_tasks);
+                }
+                return Some(task);
+            }
+        }
+        if output.is_some() {
+            for dependency in dependencies {
+                match dependency {
+                    RawVc::TaskOutput(task) => self.with_task(task, |task| {
+                        task.with_output_mut(|output| output.track_read(id))
+                    }),
+                    RawVc::TaskCell(task, index) => self.with_task(task, |task| {
+                        task.with_cell_mut(index, |cell| cell.track_read(id))
+                    }),
+                }
+            }
+        }
+        for child in children {
+            self.connect_task_child(id, child, turbo_tasks);
+        }
+        if let Some(parent_task) = parent_task {
+            self.connect_task_child(parent_task, id, turbo_tasks);
+        }
+        Some(id)
+    }
+}
+
+fn new_persistent_task(
+    id: TaskId,
+    task_type: &PersistentTaskType,
+    turbo_tasks: &dyn TurboTasksBackendApi,
+) -> Task {
+    match task_type {
+        PersistentTaskType::Native(fn_id, inputs) => {
+            // TODO inputs doesn't need to be cloned when are would be able to get a
+            // reference to the task type stored inside of the task
+            Task::new_native(id, inputs.clone(), *fn_id, turbo_tasks.stats_type())
+        }
+        PersistentTaskType::ResolveNative(fn_id, inputs) => {
+            Task::new_resolve_native(id, inputs.clone(), *fn_id, turbo_tasks.stats_type())
+        }
+        PersistentTaskType::ResolveTrait(trait_type, trait_fn_name, inputs) => {
+            Task::new_resolve_trait(
+                id,
+                *trait_type,
+                trait_fn_name.clone(),
+                inputs.clone(),
+                turbo_tasks.stats_type(),
+            )
+        }
+    }
 }
 
 pub(crate) enum Job {
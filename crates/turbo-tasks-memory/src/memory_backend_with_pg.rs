@@ -1068,7 +1068,11 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
                 )
             }
         };
-        Some(TaskExecutionSpec { future })
+        let function = match &task_info.task_type {
+            TaskType::Persistent(PersistentTaskType::Native(function, _)) => Some(*function),
+            _ => None,
+        };
+        Some(TaskExecutionSpec { future, function })
     }
 
     fn task_execution_result(
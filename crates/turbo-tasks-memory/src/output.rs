@@ -62,6 +62,11 @@ impl Output {
         self.dependent_tasks.insert(reader);
     }
 
+    /// The number of times the output was assigned.
+    pub fn updates(&self) -> u32 {
+        self.updates
+    }
+
     pub fn link(&mut self, target: RawVc, turbo_tasks: &dyn TurboTasksBackendApi) {
         let change;
         let mut _type_change = false;
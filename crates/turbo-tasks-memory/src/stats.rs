@@ -87,6 +87,36 @@ impl Default for ExportedTaskStats {
     }
 }
 
+/// The memory used by the storage of the [MemoryBackend], see
+/// [MemoryBackend::storage_stats].
+#[derive(Default, Clone, Debug)]
+pub struct StorageStats {
+    /// The number of tasks which fit into the allocated storage.
+    pub task_capacity: usize,
+    pub scope_capacity: usize,
+    pub job_capacity: usize,
+    /// The bytes allocated for the storage of tasks, scopes and jobs. Doesn't
+    /// include memory allocated by the tasks themselves.
+    pub allocated_bytes: usize,
+    /// The bytes freed by shrinking the storage when the backend is stopped.
+    pub freed_bytes: usize,
+    pub shrinks: usize,
+}
+
+/// How often the [MemoryBackend] kept the content of a cell, because an equal
+/// value was assigned to it, see [MemoryBackend::cell_update_stats].
+#[derive(Default, Clone, Debug)]
+pub struct CellUpdateStats {
+    /// The number of assignments to cells.
+    pub updates: usize,
+    /// The number of assignments of values which were equal to the content of
+    /// the cell.
+    pub unchanged: usize,
+    /// The number of tasks which read the cells of unchanged assignments, and
+    /// weren't invalidated by them.
+    pub spared_invalidations: usize,
+}
+
 pub struct Stats {
     tasks: HashMap<TaskType, ExportedTaskStats>,
 }
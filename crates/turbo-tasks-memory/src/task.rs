@@ -15,7 +15,7 @@ use anyhow::Result;
 use parking_lot::{Mutex, RwLock, RwLockWriteGuard};
 use tokio::task_local;
 use turbo_tasks::{
-    backend::PersistentTaskType,
+    backend::{CellContent, PersistentTaskType, SnapshotOutput, TaskSnapshot, TrackedRead},
     event::{Event, EventListener},
     get_invalidator, registry, CellId, FunctionId, Invalidator, RawVc, StatsType, TaskId,
     TaskInput, TraitTypeId, TurboTasksBackendApi, ValueTypeId,
@@ -270,7 +270,7 @@ use crate::{
     cell::Cell,
     count_hash_set::CountHashSet,
     memory_backend::Job,
-    output::Output,
+    output::{Output, OutputContent},
     scope::{ScopeChildChangeEffect, TaskScopeId, TaskScopes},
     stats::{self, StatsReferences},
     task_stats::TaskStats,
@@ -355,13 +355,19 @@ impl Task {
             TaskType::Root(..) => format!("[{}] root", self.id),
             TaskType::Once(..) => format!("[{}] once", self.id),
             TaskType::Native(native_fn, _) => {
-                format!("[{}] {}", self.id, registry::get_function(*native_fn).name)
+                format!(
+                    "[{}] {}{}",
+                    self.id,
+                    registry::get_function(*native_fn).name,
+                    self.get_inputs_description()
+                )
             }
             TaskType::ResolveNative(native_fn) => {
                 format!(
-                    "[{}] [resolve] {}",
+                    "[{}] [resolve] {}{}",
                     self.id,
-                    registry::get_function(*native_fn).name
+                    registry::get_function(*native_fn).name,
+                    self.get_inputs_description()
                 )
             }
             TaskType::ResolveTrait(trait_type, fn_name) => {
@@ -375,6 +381,20 @@ impl Task {
         }
     }
 
+    /// The inputs of the task for its description. Values are shown with the
+    /// concise representation or redacted, as configured in the registry.
+    fn get_inputs_description(&self) -> String {
+        if self.inputs.is_empty() {
+            return String::new();
+        }
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|input| input.to_string())
+            .collect::<Vec<_>>();
+        format!("({})", inputs.join(", "))
+    }
+
     pub(crate) fn remove_dependency(dep: TaskDependency, reader: TaskId, backend: &MemoryBackend) {
         match dep {
             TaskDependency::TaskOutput(task) => {
@@ -1146,6 +1166,14 @@ impl Task {
         }
     }
 
+    /// The native function executed by the task, if any.
+    pub(crate) fn function(&self) -> Option<FunctionId> {
+        match &self.ty {
+            TaskType::Native(function, _) => Some(*function),
+            _ => None,
+        }
+    }
+
     /// Get an [Invalidator] that can be used to invalidate the current [Task]
     /// based on external events.
     pub fn get_invalidator() -> Invalidator {
@@ -1179,6 +1207,32 @@ impl Task {
         func(&mut list[i])
     }
 
+    /// Registers `reader` as dependent of the output and the cells of this
+    /// task it read. Returns true when any of them changed since it was read.
+    pub(crate) fn track_reads(&self, reader: TaskId, reads: &[TrackedRead]) -> bool {
+        let mut state = self.state.write();
+        let mut changed = false;
+        for read in reads {
+            let version = match read.cell {
+                Some(index) => {
+                    let list = state.cells.entry(index.type_id).or_default();
+                    let i = index.index as usize;
+                    if list.len() <= i {
+                        list.resize_with(i + 1, Default::default);
+                    }
+                    list[i].track_read(reader);
+                    list[i].updates()
+                }
+                None => {
+                    state.output.track_read(reader);
+                    state.output.updates()
+                }
+            };
+            changed |= version != read.version;
+        }
+        changed
+    }
+
     /// Access to a cell.
     pub(crate) fn with_cell<T>(&self, index: CellId, func: impl FnOnce(&Cell) -> T) -> T {
         let state = self.state.read();
@@ -1190,6 +1244,108 @@ impl Task {
         func(&Default::default())
     }
 
+    /// Returns the key of the task. Returns `None` for transient tasks.
+    pub(crate) fn get_persistent_task_type(&self) -> Option<PersistentTaskType> {
+        Some(match &self.ty {
+            TaskType::Root(..) | TaskType::Once(..) => return None,
+            TaskType::Native(native_fn, _) => {
+                PersistentTaskType::Native(*native_fn, self.inputs.clone())
+            }
+            TaskType::ResolveNative(native_fn) => {
+                PersistentTaskType::ResolveNative(*native_fn, self.inputs.clone())
+            }
+            TaskType::ResolveTrait(trait_type, fn_name) => {
+                PersistentTaskType::ResolveTrait(*trait_type, fn_name.clone(), self.inputs.clone())
+            }
+        })
+    }
+
+    /// Returns the key, the cells, the children and, when the task is done, the
+    /// output and the dependencies of the task. Returns `None` for transient
+    /// tasks.
+    pub(crate) fn snapshot(&self) -> Option<TaskSnapshot> {
+        let task_type = self.get_persistent_task_type()?;
+        let state = self.state.read();
+        let cells = state
+            .cells
+            .iter()
+            .flat_map(|(&type_id, list)| {
+                list.iter().enumerate().map(move |(index, cell)| {
+                    (
+                        CellId {
+                            type_id,
+                            index: index as u32,
+                        },
+                        cell.read_content_untracked(),
+                    )
+                })
+            })
+            .collect();
+        let (output, dependencies) = match (&state.state_type, &state.output.content) {
+            // Emitted collectibles are not part of the snapshot, so the task needs to
+            // be executed again to emit them.
+            (Done { dependencies }, OutputContent::Link(output))
+                if state.collectibles.as_ref().is_none() =>
+            {
+                let output = match *output {
+                    RawVc::TaskCell(task, index) if task == self.id => {
+                        SnapshotOutput::OwnCell(index)
+                    }
+                    output => SnapshotOutput::Link(output),
+                };
+                dependencies
+                    .iter()
+                    .map(|dependency| match *dependency {
+                        TaskDependency::TaskOutput(task) => Some(RawVc::TaskOutput(task)),
+                        TaskDependency::TaskCell(task, index) => Some(RawVc::TaskCell(task, index)),
+                        TaskDependency::ScopeChildren(_)
+                        | TaskDependency::ScopeCollectibles(..) => None,
+                    })
+                    .collect::<Option<Vec<_>>>()
+                    .map_or((None, Vec::new()), |dependencies| {
+                        (Some(output), dependencies)
+                    })
+            }
+            _ => (None, Vec::new()),
+        };
+        Some(TaskSnapshot {
+            task_type,
+            cells,
+            children: state.children.iter().copied().collect(),
+            output,
+            dependencies,
+        })
+    }
+
+    /// Assigns restored cells and output to a new task, which nobody knows
+    /// about yet. With an output the task is done and depends on
+    /// `dependencies`, otherwise it stays dirty and is executed when it becomes
+    /// active.
+    pub(crate) fn restore(
+        &self,
+        cells: Vec<(CellId, CellContent)>,
+        output: Option<RawVc>,
+        dependencies: &[RawVc],
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) {
+        for (index, content) in cells {
+            self.with_cell_mut(index, |cell| cell.assign(content, turbo_tasks));
+        }
+        if let Some(output) = output {
+            let mut state = self.state.write();
+            state.output.link(output, turbo_tasks);
+            state.state_type = Done {
+                dependencies: dependencies
+                    .iter()
+                    .map(|dependency| match *dependency {
+                        RawVc::TaskOutput(task) => TaskDependency::TaskOutput(task),
+                        RawVc::TaskCell(task, index) => TaskDependency::TaskCell(task, index),
+                    })
+                    .collect(),
+            };
+        }
+    }
+
     /// For testing purposes
     pub fn reset_executions(&self) {
         let mut state = self.state.write();
@@ -0,0 +1,55 @@
+use std::time::SystemTime;
+
+use turbo_tasks::{util::FormatDuration, InvalidationTrace};
+
+use super::*;
+
+pub fn wrap_html(list_html: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+  <meta charset="utf-8">
+  <title>turbo-tasks invalidations</title>
+  <style>
+    body {{ margin: 0; font-family: monospace; }}
+    ol {{ margin: 0; padding: 1rem 1rem 1rem 3rem; }}
+    li {{ padding: 0.4rem 0; }}
+    .time {{ opacity: 0.6; }}
+    .reason {{ font-weight: bold; }}
+    .disclaimer {{ font-size: 0.8rem; opacity: 0.6; font-style: italic; margin: 0; padding: 0.8rem 1rem; }}
+  </style>
+</head>
+<body>
+  {list_html}
+</body>
+</html>"#
+    )
+}
+
+/// Lists the recorded recomputations, newest first, with the chain of
+/// invalidations which caused each of them.
+pub fn create_list(traces: &[InvalidationTrace]) -> String {
+    if traces.is_empty() {
+        return r#"<p class="disclaimer">No invalidations were recorded. Run with --trace-invalidation to record them.</p>"#.to_string();
+    }
+    let mut out = String::from("<ol reversed>");
+    for trace in traces.iter().rev() {
+        let ago = SystemTime::now()
+            .duration_since(trace.time)
+            .unwrap_or_default();
+        write!(
+            out,
+            r#"<li><span class="time">{ago} ago</span> <span class="reason">{reason}</span>"#,
+            ago = FormatDuration(ago),
+            reason = escape_html(trace.reason.as_deref().unwrap_or("unknown change")),
+        )
+        .unwrap();
+        for task in &trace.tasks {
+            write!(out, " → {}", escape_html(task)).unwrap();
+        }
+        out += "</li>";
+    }
+    out += "</ol>";
+    out
+}
@@ -0,0 +1,55 @@
+use super::*;
+use crate::stats::StorageStats;
+
+pub fn wrap_html(table_html: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+  <meta charset="utf-8">
+  <title>turbo-tasks storage</title>
+  <style>
+    body {{ margin: 0; font-family: monospace; }}
+    table {{ margin: 1rem; border-collapse: collapse; }}
+    td {{ padding: 0.4rem 1rem; }}
+    td:last-child {{ text-align: right; }}
+  </style>
+</head>
+<body>
+  {table_html}
+</body>
+</html>"#
+    )
+}
+
+/// Lists the capacity, the allocated and the freed memory of the storage of
+/// the [crate::MemoryBackend].
+pub fn create_table(stats: &StorageStats) -> String {
+    let StorageStats {
+        task_capacity,
+        scope_capacity,
+        job_capacity,
+        allocated_bytes,
+        freed_bytes,
+        shrinks,
+    } = stats;
+    let mut out = String::from("<table>");
+    for (name, value) in [
+        ("task capacity", task_capacity.to_string()),
+        ("scope capacity", scope_capacity.to_string()),
+        ("job capacity", job_capacity.to_string()),
+        (
+            "allocated",
+            format!("{:.1} MiB", *allocated_bytes as f64 / (1024.0 * 1024.0)),
+        ),
+        (
+            "freed",
+            format!("{:.1} MiB", *freed_bytes as f64 / (1024.0 * 1024.0)),
+        ),
+        ("shrinks", shrinks.to_string()),
+    ] {
+        write!(out, "<tr><td>{name}</td><td>{value}</td></tr>").unwrap();
+    }
+    out += "</table>";
+    out
+}
@@ -0,0 +1,51 @@
+#![feature(min_specialization)]
+
+use anyhow::Result;
+use turbo_tasks::RawVc;
+use turbo_tasks_testing::{register, run};
+
+register!();
+
+#[tokio::test]
+async fn wrong_argument_type() {
+    run! {
+        let text = TextVc::cell("not a number".to_string()).resolve().await?;
+        let number = NumberVc::from(RawVc::from(text));
+        let error = double(number).await.unwrap_err();
+        let message = format!("{error:#}");
+        assert!(message.contains("double(number: NumberVc)"), "{message}");
+        assert!(message.contains("argument 1 (number: NumberVc)"), "{message}");
+        assert!(message.contains("got a cell of"), "{message}");
+    }
+}
+
+#[tokio::test]
+async fn borrowed_arguments() {
+    run! {
+        assert_eq!(*join("src", "index.js").await?, "src/index.js");
+        let path = "index.js".to_string();
+        assert_eq!(*join("src", &path).await?, "src/index.js");
+        assert_eq!(*checksum(b"turbo").await?, 556);
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+struct Number(u32);
+
+#[turbo_tasks::value(transparent)]
+struct Text(String);
+
+#[turbo_tasks::function]
+async fn double(number: NumberVc) -> Result<NumberVc> {
+    Ok(NumberVc::cell(*number.await? * 2))
+}
+
+#[turbo_tasks::function]
+fn join<'a>(base: &'a str, path: &'a str) -> TextVc {
+    TextVc::cell(format!("{base}/{path}"))
+}
+
+#[turbo_tasks::function]
+fn checksum(bytes: &[u8]) -> NumberVc {
+    NumberVc::cell(bytes.iter().map(|&byte| byte as u32).sum())
+}
@@ -0,0 +1,36 @@
+#![feature(min_specialization)]
+
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, TurboTasks, ValueToString, ValueToStringVc};
+use turbo_tasks_memory::MemoryBackend;
+
+// No `register()` is called in this test, everything is registered by the
+// `auto_register` feature of turbo-tasks.
+#[tokio::test]
+async fn without_register_calls() {
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let result = tt
+        .run_once(async {
+            let greeting: ValueToStringVc = greet("world".to_string()).into();
+            anyhow::Ok(greeting.to_string().await?.clone())
+        })
+        .await
+        .unwrap();
+    assert_eq!(result, "hello world");
+}
+
+#[turbo_tasks::value]
+struct Greeting(String);
+
+#[turbo_tasks::value_impl]
+impl ValueToString for Greeting {
+    #[turbo_tasks::function]
+    fn to_string(&self) -> StringVc {
+        StringVc::cell(format!("hello {}", self.0))
+    }
+}
+
+#[turbo_tasks::function]
+fn greet(name: String) -> Result<GreetingVc> {
+    Ok(GreetingVc::cell(Greeting(name)))
+}
@@ -0,0 +1,41 @@
+#![feature(min_specialization)]
+
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks_testing::{register, run};
+
+register!();
+
+#[tokio::test]
+async fn try_cast_and_downcast() {
+    run! {
+        let named: NamedVc = NamedValueVc::cell(NamedValue("a".to_string())).into();
+        let to_string = named.try_cast::<ValueToStringVc>().await?.unwrap();
+        assert_eq!(*to_string.to_string().await?, "a");
+        assert!(named.downcast::<NamedValueVc>().await?.is_some());
+        assert!(named.downcast::<UnnamedValueVc>().await?.is_none());
+
+        let unnamed = UnnamedValueVc::cell(UnnamedValue);
+        assert!(unnamed.try_cast::<NamedVc>().await?.is_none());
+    }
+}
+
+#[turbo_tasks::value_trait]
+trait Named {}
+
+#[turbo_tasks::value]
+struct NamedValue(String);
+
+#[turbo_tasks::value_impl]
+impl Named for NamedValue {}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for NamedValue {
+    #[turbo_tasks::function]
+    fn to_string(&self) -> StringVc {
+        StringVc::cell(self.0.clone())
+    }
+}
+
+#[turbo_tasks::value]
+struct UnnamedValue;
@@ -0,0 +1,33 @@
+#![feature(min_specialization)]
+
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, registry};
+use turbo_tasks_testing::{register, run};
+
+register!();
+
+const THRESHOLD: usize = 1024;
+
+#[tokio::test]
+async fn compressed_cells() {
+    run! {
+        registry::register_cell_compression::<turbo_tasks::primitives::String>(THRESHOLD);
+
+        let large = repeated(THRESHOLD * 10).await?;
+        assert_eq!(large.len(), THRESHOLD * 10);
+        assert!(large.bytes().all(|byte| byte == b'a'));
+
+        // Each read decompresses the content again, once the previous value
+        // was dropped.
+        drop(large);
+        assert_eq!(*repeated(THRESHOLD * 10).await?, "a".repeat(THRESHOLD * 10));
+
+        let small = repeated(THRESHOLD / 2).await?;
+        assert_eq!(*small, "a".repeat(THRESHOLD / 2));
+    }
+}
+
+#[turbo_tasks::function]
+fn repeated(len: usize) -> Result<StringVc> {
+    Ok(StringVc::cell("a".repeat(len)))
+}
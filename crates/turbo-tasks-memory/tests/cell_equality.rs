@@ -0,0 +1,77 @@
+#![feature(min_specialization)]
+
+use anyhow::Result;
+use turbo_tasks::{
+    primitives::StringVc,
+    registry,
+    test_utils::{ExecutionCounter, MockInput},
+    TurboTasks,
+};
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::register;
+register!();
+
+static INPUT: MockInput<u32> = MockInput::new(2);
+static DESCRIBE_EXECUTIONS: ExecutionCounter = ExecutionCounter::new();
+
+#[tokio::test]
+async fn cuts_off_equal_cells() {
+    *REGISTER;
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let mut updates = tt.run_once_with_updates(|| async {
+        let number = input();
+        let description = describe(parity(number)).await?;
+        let _ = *parity(number).await?;
+        anyhow::Ok(format!("{} is {}", *number.await?, *description))
+    });
+    assert_eq!(updates.next_update().await.unwrap().unwrap(), "2 is even");
+    DESCRIBE_EXECUTIONS.assert_executions(1);
+
+    // Values placed with `cell = "new"` always invalidate the readers. The
+    // description is equal, so its cell is kept.
+    INPUT.set(4);
+    assert_eq!(updates.next_update().await.unwrap().unwrap(), "4 is even");
+    DESCRIBE_EXECUTIONS.assert_executions(1);
+    assert_eq!(tt.backend().cell_update_stats().unchanged, 1);
+
+    registry::register_cell_equality::<Parity>();
+    INPUT.set(6);
+    assert_eq!(updates.next_update().await.unwrap().unwrap(), "6 is even");
+    DESCRIBE_EXECUTIONS.assert_executions(0);
+    let stats = tt.backend().cell_update_stats();
+    assert_eq!(stats.unchanged, 2);
+    assert!(stats.spared_invalidations >= 2);
+
+    INPUT.set(7);
+    assert_eq!(updates.next_update().await.unwrap().unwrap(), "7 is odd");
+    DESCRIBE_EXECUTIONS.assert_executions(1);
+
+    registry::unregister_cell_equality::<Parity>();
+    INPUT.set(9);
+    assert_eq!(updates.next_update().await.unwrap().unwrap(), "9 is odd");
+    DESCRIBE_EXECUTIONS.assert_executions(1);
+    assert_eq!(tt.backend().cell_update_stats().unchanged, 3);
+}
+
+#[turbo_tasks::value(transparent)]
+struct Number(u32);
+
+#[turbo_tasks::value(transparent, cell = "new")]
+struct Parity(bool);
+
+#[turbo_tasks::function]
+fn input() -> Result<NumberVc> {
+    Ok(NumberVc::cell(INPUT.get()))
+}
+
+#[turbo_tasks::function]
+async fn parity(number: NumberVc) -> Result<ParityVc> {
+    Ok(ParityVc::cell(*number.await? % 2 == 0))
+}
+
+#[turbo_tasks::function]
+async fn describe(parity: ParityVc) -> Result<StringVc> {
+    DESCRIBE_EXECUTIONS.count();
+    let description = if *parity.await? { "even" } else { "odd" };
+    Ok(StringVc::cell(description.to_string()))
+}
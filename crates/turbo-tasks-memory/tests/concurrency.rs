@@ -0,0 +1,34 @@
+#![feature(min_specialization)]
+
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use turbo_tasks::{with_concurrency_limit, ConcurrencyCategory, ConcurrencyLimits, TurboTasks};
+use turbo_tasks_memory::MemoryBackend;
+
+#[tokio::test]
+async fn limits_concurrent_operations() {
+    let tt = TurboTasks::with_concurrency_limits(
+        MemoryBackend::new(),
+        ConcurrencyLimits::new().limit(ConcurrencyCategory::Network, 2),
+    );
+    let running = AtomicUsize::new(0);
+    let max_running = AtomicUsize::new(0);
+    let operation = || {
+        with_concurrency_limit(ConcurrencyCategory::Network, async {
+            let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(now_running, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+        })
+    };
+    tt.run_once(async {
+        tokio::join!(operation(), operation(), operation(), operation());
+        anyhow::Ok(())
+    })
+    .await
+    .unwrap();
+    assert_eq!(max_running.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,73 @@
+#![feature(min_specialization)]
+
+use anyhow::Result;
+use turbo_tasks::{test_utils::ExecutionCounter, TurboTasks, Value};
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::{register, run};
+register!();
+
+static DOUBLE_EXECUTIONS: ExecutionCounter = ExecutionCounter::new();
+static RESOLVED_DOUBLE_EXECUTIONS: ExecutionCounter = ExecutionCounter::new();
+
+#[tokio::test]
+async fn reports_duplicate_work() {
+    *REGISTER;
+    let tt = TurboTasks::new(MemoryBackend::new());
+    tt.enable_duplicate_work_detection();
+    tt.run_once(async {
+        let unresolved = number();
+        let resolved = unresolved.resolve().await?;
+        assert_eq!(
+            *double(Value::new(Options { number: unresolved })).await?,
+            42
+        );
+        assert_eq!(*double(Value::new(Options { number: resolved })).await?, 42);
+        DOUBLE_EXECUTIONS.assert_executions(2);
+        Ok(())
+    })
+    .await
+    .unwrap();
+    tt.wait_foreground_done().await;
+
+    let reports = tt.duplicate_work();
+    assert_eq!(reports.len(), 1);
+    assert!(reports[0].function.starts_with("double("));
+    assert!(reports[0].to_string().contains("resolve_values"));
+}
+
+#[tokio::test]
+async fn resolves_values() {
+    run! {
+        let unresolved = number();
+        let resolved = unresolved.resolve().await?;
+        assert_eq!(*resolved_double(Value::new(Options { number: unresolved })).await?, 42);
+        assert_eq!(*resolved_double(Value::new(Options { number: resolved })).await?, 42);
+        RESOLVED_DOUBLE_EXECUTIONS.assert_executions(1);
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+struct Number(u32);
+
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(Debug, Clone, PartialOrd, Ord, Hash)]
+struct Options {
+    number: NumberVc,
+}
+
+#[turbo_tasks::function]
+fn number() -> NumberVc {
+    NumberVc::cell(21)
+}
+
+#[turbo_tasks::function]
+async fn double(options: Value<Options>) -> Result<NumberVc> {
+    DOUBLE_EXECUTIONS.count();
+    Ok(NumberVc::cell(*options.number.await? * 2))
+}
+
+#[turbo_tasks::function(resolve_values)]
+async fn resolved_double(options: Value<Options>) -> Result<NumberVc> {
+    RESOLVED_DOUBLE_EXECUTIONS.count();
+    Ok(NumberVc::cell(*options.number.await? * 2))
+}
@@ -0,0 +1,80 @@
+#![feature(min_specialization)]
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Result};
+use turbo_tasks::{
+    ExecutionListener, FunctionId, TaskExecution, TaskExecutionOutcome, TaskId, TurboTasks,
+};
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::register;
+register!();
+
+#[derive(Default)]
+struct RecordingListener {
+    started: Mutex<Vec<(TaskId, Option<FunctionId>)>>,
+    finished: Mutex<Vec<TaskExecution>>,
+}
+
+impl ExecutionListener for RecordingListener {
+    fn execution_started(
+        &self,
+        task: TaskId,
+        function: Option<FunctionId>,
+        _scheduled_by: Option<TaskId>,
+    ) {
+        self.started.lock().unwrap().push((task, function));
+    }
+
+    fn execution_finished(&self, execution: &TaskExecution) {
+        self.finished.lock().unwrap().push(*execution);
+    }
+}
+
+#[tokio::test]
+async fn reports_executions() {
+    *REGISTER;
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let listener = Arc::new(RecordingListener::default());
+    tt.add_execution_listener(listener.clone());
+    tt.run_once(async {
+        assert_eq!(*succeeding().await?, 42);
+        assert!(failing().await.is_err());
+        anyhow::Ok(())
+    })
+    .await
+    .unwrap();
+
+    let outcome = |function: FunctionId| {
+        let finished = listener.finished.lock().unwrap();
+        let execution = finished
+            .iter()
+            .find(|execution| execution.function == Some(function))
+            .copied()
+            .expect("the execution of the function was not reported");
+        assert!(listener
+            .started
+            .lock()
+            .unwrap()
+            .contains(&(execution.task, Some(function))));
+        execution.outcome
+    };
+    assert_eq!(
+        outcome(*SUCCEEDING_FUNCTION_ID),
+        TaskExecutionOutcome::Success
+    );
+    assert_eq!(outcome(*FAILING_FUNCTION_ID), TaskExecutionOutcome::Error);
+}
+
+#[turbo_tasks::value(transparent)]
+struct Number(u32);
+
+#[turbo_tasks::function]
+fn succeeding() -> NumberVc {
+    NumberVc::cell(42)
+}
+
+#[turbo_tasks::function]
+fn failing() -> Result<NumberVc> {
+    bail!("failed")
+}
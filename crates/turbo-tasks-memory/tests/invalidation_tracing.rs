@@ -0,0 +1,79 @@
+#![feature(min_specialization)]
+
+use anyhow::Result;
+use turbo_tasks::{test_utils::MockInput, TurboTasks};
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::register;
+register!();
+
+static INPUT: MockInput<u32> = MockInput::new(1);
+static FILTERED_INPUT: MockInput<u32> = MockInput::new(1);
+
+#[tokio::test]
+async fn records_invalidation_chains() {
+    *REGISTER;
+    let tt = TurboTasks::new(MemoryBackend::new());
+    tt.enable_invalidation_tracing(None);
+    let mut updates = tt.run_once_with_updates(|| async { anyhow::Ok(*double(input()).await?) });
+    assert_eq!(updates.next_update().await.unwrap().unwrap(), 2);
+    // Initial executions weren't caused by invalidations.
+    assert!(tt.invalidation_traces().is_empty());
+
+    INPUT.set_with_reason(21, "src/input.txt");
+    assert_eq!(updates.next_update().await.unwrap().unwrap(), 42);
+
+    let traces = tt.invalidation_traces();
+    let trace = traces
+        .iter()
+        .find(|trace| {
+            trace
+                .tasks
+                .last()
+                .map_or(false, |task| task.contains("double"))
+        })
+        .expect("the recomputation of double is recorded");
+    assert_eq!(trace.reason.as_deref(), Some("src/input.txt"));
+    assert_eq!(trace.tasks.len(), 2);
+    assert!(trace.tasks[0].contains("input"));
+    assert!(trace.to_string().starts_with("src/input.txt → "));
+}
+
+#[tokio::test]
+async fn filters_invalidation_chains() {
+    *REGISTER;
+    let tt = TurboTasks::new(MemoryBackend::new());
+    tt.enable_invalidation_tracing(Some("src/".to_string()));
+    let mut updates =
+        tt.run_once_with_updates(|| async { anyhow::Ok(*double(filtered_input()).await?) });
+    assert_eq!(updates.next_update().await.unwrap().unwrap(), 2);
+
+    FILTERED_INPUT.set_with_reason(2, "node_modules/input.txt");
+    assert_eq!(updates.next_update().await.unwrap().unwrap(), 4);
+    assert!(tt.invalidation_traces().is_empty());
+
+    FILTERED_INPUT.set_with_reason(3, "src/input.txt");
+    assert_eq!(updates.next_update().await.unwrap().unwrap(), 6);
+    let traces = tt.invalidation_traces();
+    assert!(!traces.is_empty());
+    assert!(traces
+        .iter()
+        .all(|trace| trace.reason.as_deref() == Some("src/input.txt")));
+}
+
+#[turbo_tasks::value(transparent)]
+struct Number(u32);
+
+#[turbo_tasks::function]
+fn input() -> Result<NumberVc> {
+    Ok(NumberVc::cell(INPUT.get()))
+}
+
+#[turbo_tasks::function]
+fn filtered_input() -> Result<NumberVc> {
+    Ok(NumberVc::cell(FILTERED_INPUT.get()))
+}
+
+#[turbo_tasks::function]
+async fn double(number: NumberVc) -> Result<NumberVc> {
+    Ok(NumberVc::cell(*number.await? * 2))
+}
@@ -0,0 +1,62 @@
+#![feature(min_specialization)]
+
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, test_utils::ExecutionCounter};
+use turbo_tasks_testing::{register, run};
+
+register!();
+
+static OPTIONS_EXECUTIONS: ExecutionCounter = ExecutionCounter::new();
+static TRANSFORM_EXECUTIONS: ExecutionCounter = ExecutionCounter::new();
+
+#[tokio::test]
+async fn memoizes_per_key() {
+    run! {
+        assert_eq!(*transform_options(Extension::Ts).await?, "typescript");
+        assert_eq!(*transform_options(Extension::Ts).await?, "typescript");
+        assert_eq!(*transform_options(Extension::Js).await?, "ecmascript");
+        OPTIONS_EXECUTIONS.assert_executions(2);
+    }
+}
+
+#[tokio::test]
+async fn keys_with_further_arguments() {
+    run! {
+        let source = StringVc::cell("a".to_string());
+        assert_eq!(*transform(Extension::Ts, source).await?, "typescript: a");
+        assert_eq!(*transform(Extension::Ts, source).await?, "typescript: a");
+        assert_eq!(*transform(Extension::Js, source).await?, "ecmascript: a");
+        TRANSFORM_EXECUTIONS.assert_executions(2);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Extension {
+    Js,
+    Ts,
+}
+
+fn language(extension: Extension) -> &'static str {
+    match extension {
+        Extension::Js => "ecmascript",
+        Extension::Ts => "typescript",
+    }
+}
+
+#[turbo_tasks::function(keyed)]
+fn transform_options(extension: Extension) -> StringVc {
+    OPTIONS_EXECUTIONS.count();
+    StringVc::cell(language(extension).to_string())
+}
+
+// Doesn't call `transform_options`, so each test only executes the function it
+// counts, as tests run in parallel.
+#[turbo_tasks::function(keyed)]
+async fn transform(extension: Extension, source: StringVc) -> Result<StringVc> {
+    TRANSFORM_EXECUTIONS.count();
+    Ok(StringVc::cell(format!(
+        "{}: {}",
+        language(extension),
+        *source.await?
+    )))
+}
@@ -0,0 +1,34 @@
+#![feature(min_specialization)]
+
+use anyhow::Result;
+use tokio::sync::Notify;
+use turbo_tasks::{turbo_tasks, RawVc};
+use turbo_tasks_testing::{register, run};
+
+register!();
+
+static GATE: Notify = Notify::const_new();
+
+#[tokio::test]
+async fn reads_without_waiting() {
+    run! {
+        let tt = turbo_tasks();
+        let vc: RawVc = gated().into();
+        assert!(vc.peek::<Number>(&*tt)?.ready().is_none());
+        assert!(vc.try_read_now::<Number>(&*tt)?.ready().is_none());
+
+        GATE.notify_one();
+        assert_eq!(*gated().await?, 42);
+        assert_eq!(vc.peek::<Number>(&*tt)?.ready().unwrap().0, 42);
+        assert_eq!(vc.try_read_now::<Number>(&*tt)?.ready().unwrap().0, 42);
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+struct Number(u32);
+
+#[turbo_tasks::function]
+async fn gated() -> Result<NumberVc> {
+    GATE.notified().await;
+    Ok(NumberVc::cell(42))
+}
@@ -0,0 +1,131 @@
+#![feature(min_specialization)]
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use turbo_tasks::{
+    remote_cache::{CacheFuture, RemoteCache, RemoteCacheBackend},
+    test_utils::ExecutionCounter,
+    TurboTasks,
+};
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::register;
+register!();
+
+static SQUARE_EXECUTIONS: ExecutionCounter = ExecutionCounter::new();
+
+#[derive(Clone, Default)]
+struct InMemoryCache {
+    entries: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+    uploads: Arc<AtomicUsize>,
+}
+
+impl RemoteCache for InMemoryCache {
+    fn get(&self, key: &str) -> CacheFuture<'_, Option<Vec<u8>>> {
+        let entry = self.entries.lock().unwrap().get(key).cloned();
+        Box::pin(async move { Ok(entry) })
+    }
+
+    fn put(&self, key: String, data: Vec<u8>) -> CacheFuture<'_, ()> {
+        self.uploads.fetch_add(1, Ordering::SeqCst);
+        self.entries.lock().unwrap().insert(key, data);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+impl InMemoryCache {
+    async fn wait_for_uploads(&self, uploads: usize) {
+        // Uploads happen in the background.
+        for _ in 0..100 {
+            if self.uploads.load(Ordering::SeqCst) >= uploads {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(self.uploads.load(Ordering::SeqCst), uploads);
+    }
+
+    fn entries(&self) -> Vec<(String, Vec<u8>)> {
+        self.entries.lock().unwrap().clone().into_iter().collect()
+    }
+}
+
+async fn square_in_new_session(cache: &InMemoryCache) -> Result<u32> {
+    let tt = TurboTasks::new(RemoteCacheBackend::new(
+        MemoryBackend::new(),
+        cache.clone(),
+        [*SQUARE_FUNCTION_ID],
+    ));
+    tt.run_once(async { Ok(*square(7).await?) }).await
+}
+
+#[tokio::test]
+async fn shares_results_between_sessions() -> Result<()> {
+    *REGISTER;
+    let cache = InMemoryCache::default();
+    assert_eq!(square_in_new_session(&cache).await?, 49);
+    SQUARE_EXECUTIONS.assert_executions(1);
+    cache.wait_for_uploads(1).await;
+    let entries = cache.entries();
+    // A hex encoded SHA-256 hash.
+    assert_eq!(entries[0].0.len(), 64);
+
+    assert_eq!(square_in_new_session(&cache).await?, 49);
+    SQUARE_EXECUTIONS.assert_executions(0);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(cache.uploads.load(Ordering::SeqCst), 1);
+    Ok(())
+}
+
+async fn cube_in_new_session(cache: &InMemoryCache, n: u32) -> Result<u32> {
+    let tt = TurboTasks::new(RemoteCacheBackend::new(
+        MemoryBackend::new(),
+        cache.clone(),
+        [*CUBE_FUNCTION_ID],
+    ));
+    tt.run_once(async move { Ok(*cube(n).await?) }).await
+}
+
+#[tokio::test]
+async fn ignores_misplaced_entries() -> Result<()> {
+    *REGISTER;
+    let cache = InMemoryCache::default();
+    assert_eq!(cube_in_new_session(&cache, 2).await?, 8);
+    cache.wait_for_uploads(1).await;
+    let (_, entry_of_2) = cache.entries().remove(0);
+    assert_eq!(cube_in_new_session(&cache, 3).await?, 27);
+    cache.wait_for_uploads(2).await;
+
+    // The result of `cube(2)` stored for the key of `cube(3)` is not
+    // restored.
+    for (key, _) in cache.entries() {
+        cache
+            .entries
+            .lock()
+            .unwrap()
+            .insert(key, entry_of_2.clone());
+    }
+    assert_eq!(cube_in_new_session(&cache, 3).await?, 27);
+    Ok(())
+}
+
+#[turbo_tasks::value(transparent)]
+struct Number(u32);
+
+#[turbo_tasks::function]
+fn square(n: u32) -> NumberVc {
+    SQUARE_EXECUTIONS.count();
+    NumberVc::cell(n * n)
+}
+
+#[turbo_tasks::function]
+fn cube(n: u32) -> NumberVc {
+    NumberVc::cell(n * n * n)
+}
@@ -0,0 +1,47 @@
+#![feature(min_specialization)]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{bail, Result};
+use turbo_tasks::test_utils::ExecutionCounter;
+use turbo_tasks_testing::{register, run};
+
+register!();
+
+static FLAKY_ATTEMPTS: AtomicUsize = AtomicUsize::new(0);
+static FAILING_EXECUTIONS: ExecutionCounter = ExecutionCounter::new();
+
+#[tokio::test]
+async fn retries_until_success() {
+    run! {
+        assert_eq!(*flaky().await?, 3);
+        assert_eq!(FLAKY_ATTEMPTS.load(Ordering::SeqCst), 3);
+    }
+}
+
+#[tokio::test]
+async fn returns_last_error() {
+    run! {
+        let error = failing().await.unwrap_err();
+        assert!(format!("{error:#}").contains("failed after 2 attempts"));
+        FAILING_EXECUTIONS.assert_executions(2);
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+struct Attempts(usize);
+
+#[turbo_tasks::function(retry(max_attempts = 5, backoff_ms = 1))]
+fn flaky() -> Result<AttemptsVc> {
+    let attempt = FLAKY_ATTEMPTS.fetch_add(1, Ordering::SeqCst) + 1;
+    if attempt < 3 {
+        bail!("attempt {attempt} failed");
+    }
+    Ok(AttemptsVc::cell(attempt))
+}
+
+#[turbo_tasks::function(retry(max_attempts = 2, backoff_ms = 1))]
+fn failing() -> Result<AttemptsVc> {
+    FAILING_EXECUTIONS.count();
+    bail!("always fails")
+}
@@ -0,0 +1,52 @@
+#![feature(min_specialization)]
+
+use std::time::Duration;
+
+use anyhow::Result;
+use turbo_tasks::{test_utils::MockInput, TurboTasks};
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::register;
+register!();
+
+static INPUT: MockInput<u32> = MockInput::new(1);
+static DISPOSED_INPUT: MockInput<u32> = MockInput::new(1);
+
+#[tokio::test]
+async fn receives_recomputed_results() {
+    *REGISTER;
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let mut updates = tt.run_once_with_updates(|| async { anyhow::Ok(*input().await? * 2) });
+    assert_eq!(updates.next_update().await.unwrap().unwrap(), 2);
+
+    INPUT.set(21);
+    assert_eq!(updates.next_update().await.unwrap().unwrap(), 42);
+}
+
+#[tokio::test]
+async fn disposes_the_root_task_once_dropped() {
+    *REGISTER;
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let mut updates = tt.run_once_with_updates(|| async { anyhow::Ok(*disposed_input().await?) });
+    assert_eq!(updates.next_update().await.unwrap().unwrap(), 1);
+    // Waits until all tasks are done.
+    tt.get_or_wait_update_info(Duration::ZERO).await;
+    drop(updates);
+
+    // The input is no longer kept active by the root task, so it's not
+    // recomputed.
+    DISPOSED_INPUT.set(2);
+    assert_eq!(tt.get_in_progress_count(), 0);
+}
+
+#[turbo_tasks::value(transparent)]
+struct Number(u32);
+
+#[turbo_tasks::function]
+fn input() -> Result<NumberVc> {
+    Ok(NumberVc::cell(INPUT.get()))
+}
+
+#[turbo_tasks::function]
+fn disposed_input() -> Result<NumberVc> {
+    Ok(NumberVc::cell(DISPOSED_INPUT.get()))
+}
@@ -0,0 +1,64 @@
+#![feature(min_specialization)]
+
+use anyhow::Result;
+use turbo_tasks::{
+    backend::TaskSnapshot, test_utils::ExecutionCounter, NothingVc, RawVc, TaskId, TurboTasks,
+};
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::register;
+register!();
+
+static ADD_EXECUTIONS: ExecutionCounter = ExecutionCounter::new();
+static DOUBLE_EXECUTIONS: ExecutionCounter = ExecutionCounter::new();
+
+#[tokio::test]
+async fn restores_tasks_without_executing_them() -> Result<()> {
+    *REGISTER;
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let result = tt
+        .run_once(async {
+            let result = forward(1, 20);
+            assert_eq!(*result.await?, 42);
+            Ok(RawVc::from(result))
+        })
+        .await?;
+    ADD_EXECUTIONS.assert_executions(1);
+    DOUBLE_EXECUTIONS.assert_executions(1);
+    let snapshots = tt.snapshot_tasks([result.get_task_id()]);
+    assert_eq!(snapshots.len(), 3);
+    assert!(snapshots
+        .iter()
+        .all(|(_, snapshot)| snapshot.output.is_some()));
+    let data = serde_json::to_vec(&snapshots)?;
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let parent = tt.spawn_root_task(|| Box::pin(async { Ok(NothingVc::new().into()) }));
+    tt.wait_task_completion(parent, true).await?;
+    let snapshots: Vec<(TaskId, TaskSnapshot)> = serde_json::from_slice(&data)?;
+    assert_eq!(tt.restore_tasks(snapshots, parent).len(), 3);
+    let result = tt.run_once(async { Ok(*forward(1, 20).await?) }).await?;
+    assert_eq!(result, 42);
+    ADD_EXECUTIONS.assert_executions(0);
+    DOUBLE_EXECUTIONS.assert_executions(0);
+    Ok(())
+}
+
+#[turbo_tasks::value(transparent)]
+struct Number(u32);
+
+#[turbo_tasks::function]
+fn add(a: u32, b: u32) -> NumberVc {
+    ADD_EXECUTIONS.count();
+    NumberVc::cell(a + b)
+}
+
+#[turbo_tasks::function]
+async fn double_sum(a: u32, b: u32) -> Result<NumberVc> {
+    DOUBLE_EXECUTIONS.count();
+    Ok(NumberVc::cell(*add(a, b).await? * 2))
+}
+
+#[turbo_tasks::function]
+fn forward(a: u32, b: u32) -> NumberVc {
+    double_sum(a, b)
+}
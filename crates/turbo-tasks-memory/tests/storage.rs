@@ -0,0 +1,29 @@
+#![feature(min_specialization)]
+
+use anyhow::Result;
+use turbo_tasks::TurboTasks;
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::register;
+register!();
+
+#[tokio::test]
+async fn shrinks_the_storage_when_stopped() -> Result<()> {
+    *REGISTER;
+    let tt = TurboTasks::new(MemoryBackend::new());
+    assert_eq!(tt.run_once(async { Ok(*double(21).await?) }).await?, 42);
+    assert_eq!(tt.backend().storage_stats().shrinks, 0);
+
+    tt.stop_and_wait().await;
+    let stats = tt.backend().storage_stats();
+    assert_eq!(stats.shrinks, 1);
+    assert!(stats.allocated_bytes > 0);
+    Ok(())
+}
+
+#[turbo_tasks::value(transparent)]
+struct Number(u32);
+
+#[turbo_tasks::function]
+fn double(n: u32) -> NumberVc {
+    NumberVc::cell(n * 2)
+}
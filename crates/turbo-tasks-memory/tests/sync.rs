@@ -0,0 +1,38 @@
+#![feature(min_specialization)]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use turbo_tasks::{TaskMutex, TaskOnceMap};
+use turbo_tasks_testing::{register, run};
+
+register!();
+
+#[tokio::test]
+async fn relocking_fails_instead_of_deadlocking() {
+    run! {
+        let mutex = TaskMutex::new(0);
+        let guard = mutex.lock().await?;
+        assert!(mutex.lock().await.is_err());
+        drop(guard);
+        *mutex.lock().await? += 1;
+        assert_eq!(mutex.into_inner(), 1);
+    }
+}
+
+#[tokio::test]
+async fn computes_once_per_key() {
+    run! {
+        let map = &TaskOnceMap::new();
+        let calls = &AtomicUsize::new(0);
+        let download = move |url: &'static str| {
+            map.get_or_try_init(url, move || async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(url.len())
+            })
+        };
+        let (a, b, c) = tokio::join!(download("a"), download("a"), download("bc"));
+        assert_eq!((a?, b?, c?), (1, 1, 2));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(map.get(&"a"), Some(1));
+    }
+}
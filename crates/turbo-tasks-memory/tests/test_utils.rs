@@ -0,0 +1,41 @@
+#![feature(min_specialization)]
+
+use anyhow::Result;
+use turbo_tasks::test_utils::{ExecutionCounter, MockInput};
+use turbo_tasks_testing::{register, run};
+register!();
+
+static INPUT: MockInput<u32> = MockInput::new(1);
+static DOUBLE_EXECUTIONS: ExecutionCounter = ExecutionCounter::new();
+
+#[tokio::test]
+async fn recompute_after_invalidation() {
+    run! {
+        let result = double(input());
+        assert_eq!(*result.strongly_consistent().await?, 2);
+        DOUBLE_EXECUTIONS.assert_executions(1);
+
+        INPUT.set(21);
+        assert_eq!(*result.strongly_consistent().await?, 42);
+        DOUBLE_EXECUTIONS.assert_executions(1);
+
+        // The input task is executed again, but its result doesn't change.
+        INPUT.set(21);
+        assert_eq!(*result.strongly_consistent().await?, 42);
+        DOUBLE_EXECUTIONS.assert_executions(0);
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+struct Number(u32);
+
+#[turbo_tasks::function]
+fn input() -> NumberVc {
+    NumberVc::cell(INPUT.get())
+}
+
+#[turbo_tasks::function]
+async fn double(number: NumberVc) -> Result<NumberVc> {
+    DOUBLE_EXECUTIONS.count();
+    Ok(NumberVc::cell(*number.await? * 2))
+}
@@ -0,0 +1,144 @@
+#![feature(min_specialization)]
+
+use anyhow::Result;
+use tokio::sync::Notify;
+use turbo_tasks::test_utils::{ExecutionCounter, MockInput};
+use turbo_tasks_testing::{register, run};
+
+register!();
+
+#[turbo_tasks::value(transparent)]
+struct Number(u32);
+
+static BATCHED_INPUT: MockInput<u32> = MockInput::new(1);
+static OTHER_INPUT: MockInput<u32> = MockInput::new(10);
+static SUM_EXECUTIONS: ExecutionCounter = ExecutionCounter::new();
+
+#[tokio::test]
+async fn invalidation_through_batched_reads() {
+    run! {
+        let result = sum_of_reads();
+        assert_eq!(*result.strongly_consistent().await?, 12);
+        SUM_EXECUTIONS.assert_executions(1);
+
+        BATCHED_INPUT.set(2);
+        assert_eq!(*result.strongly_consistent().await?, 14);
+        SUM_EXECUTIONS.assert_executions(1);
+
+        OTHER_INPUT.set(20);
+        assert_eq!(*result.strongly_consistent().await?, 24);
+        SUM_EXECUTIONS.assert_executions(1);
+    }
+}
+
+#[turbo_tasks::function]
+fn batched_input() -> NumberVc {
+    NumberVc::cell(BATCHED_INPUT.get())
+}
+
+#[turbo_tasks::function]
+fn other_input() -> NumberVc {
+    NumberVc::cell(OTHER_INPUT.get())
+}
+
+/// Reads one task twice and another one once, so the batch of reads contains
+/// several reads of the same task.
+#[turbo_tasks::function]
+async fn sum_of_reads() -> Result<NumberVc> {
+    SUM_EXECUTIONS.count();
+    let first = *batched_input().await?;
+    let second = *batched_input().await?;
+    Ok(NumberVc::cell(first + second + *other_input().await?))
+}
+
+static RACING_INPUT: MockInput<u32> = MockInput::new(1);
+static RACING_READ: Notify = Notify::const_new();
+static RACING_GATE: Notify = Notify::const_new();
+
+#[tokio::test]
+async fn changes_before_tracking_invalidate_the_reader() {
+    run! {
+        let result = read_then_wait();
+        // Changes the input after it was read, but before the read is tracked
+        // at the end of the execution.
+        let change = tokio::spawn(async {
+            RACING_READ.notified().await;
+            RACING_INPUT.set(2);
+            RACING_GATE.notify_one();
+        });
+        assert_eq!(*result.strongly_consistent().await?, 2);
+        change.await?;
+    }
+}
+
+#[turbo_tasks::function]
+fn racing_input() -> NumberVc {
+    NumberVc::cell(RACING_INPUT.get())
+}
+
+#[turbo_tasks::function]
+async fn read_then_wait() -> Result<NumberVc> {
+    let value = *racing_input().await?;
+    if value == 1 {
+        RACING_READ.notify_one();
+        RACING_GATE.notified().await;
+    }
+    Ok(NumberVc::cell(value))
+}
+
+static CHAIN_INPUT: MockInput<u32> = MockInput::new(0);
+
+#[tokio::test]
+async fn strongly_consistent_reads() {
+    run! {
+        let result = chain_end();
+        for value in [1, 2, 3] {
+            CHAIN_INPUT.set(value);
+            assert_eq!(*result.strongly_consistent().await?, value + 2);
+        }
+    }
+}
+
+#[turbo_tasks::function]
+fn chain_start() -> NumberVc {
+    NumberVc::cell(CHAIN_INPUT.get())
+}
+
+#[turbo_tasks::function]
+async fn chain_middle() -> Result<NumberVc> {
+    Ok(NumberVc::cell(*chain_start().await? + 1))
+}
+
+#[turbo_tasks::function]
+async fn chain_end() -> Result<NumberVc> {
+    Ok(NumberVc::cell(*chain_middle().await? + 1))
+}
+
+static FRAGILE_INPUT: MockInput<u32> = MockInput::new(0);
+
+/// An execution which panics is cancelled, but the reads it did until then
+/// are still tracked, so it recovers when they change.
+#[tokio::test]
+async fn reads_of_cancelled_executions_are_tracked() {
+    run! {
+        let result = fragile();
+        assert!(result.strongly_consistent().await.is_err());
+
+        FRAGILE_INPUT.set(1);
+        assert_eq!(*result.strongly_consistent().await?, 1);
+    }
+}
+
+#[turbo_tasks::function]
+fn fragile_input() -> NumberVc {
+    NumberVc::cell(FRAGILE_INPUT.get())
+}
+
+#[turbo_tasks::function]
+async fn fragile() -> Result<NumberVc> {
+    let value = *fragile_input().await?;
+    if value == 0 {
+        panic!("the execution is cancelled");
+    }
+    Ok(NumberVc::cell(value))
+}
@@ -0,0 +1,80 @@
+#![feature(min_specialization)]
+
+use std::fmt;
+
+use anyhow::Result;
+use turbo_tasks::{
+    backend::Backend, primitives::StringVc, registry, ConciseDebug, RawVc, TaskInput, TurboTasks,
+    Value,
+};
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::register;
+register!();
+
+#[tokio::test]
+async fn concise_debug() {
+    *REGISTER;
+    registry::register_concise_debug::<Path>();
+    let input = TaskInput::from(Value::new(Path::new(&["src", "index.js"])));
+    assert_eq!(input.to_string(), "value src/index.js");
+    assert!(format!("{input:?}").contains("src/index.js"));
+
+    let tt = TurboTasks::new(MemoryBackend::new());
+    let vc = tt
+        .run_once(async {
+            let vc: RawVc = read(Value::new(Path::new(&["src", "index.js"]))).into();
+            anyhow::Ok(vc)
+        })
+        .await
+        .unwrap();
+    let task = match vc {
+        RawVc::TaskOutput(task) => task,
+        _ => panic!("read is called without resolving"),
+    };
+    assert!(tt
+        .backend()
+        .get_task_description(task)
+        .ends_with("read(value src/index.js)"));
+}
+
+#[tokio::test]
+async fn redacted() {
+    *REGISTER;
+    registry::register_redacted::<Secret>();
+    let input = TaskInput::from(Value::new(Secret {
+        token: "hunter2".to_string(),
+    }));
+    assert_eq!(input.to_string(), "value <redacted>");
+    assert!(!format!("{input:?}").contains("hunter2"));
+}
+
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(Debug, Clone, PartialOrd, Ord, Hash)]
+struct Path {
+    segments: Vec<String>,
+}
+
+impl Path {
+    fn new(segments: &[&str]) -> Self {
+        Self {
+            segments: segments.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+impl ConciseDebug for Path {
+    fn fmt_concise(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.segments.join("/"))
+    }
+}
+
+#[turbo_tasks::value(shared, serialization = "auto_for_input")]
+#[derive(Debug, Clone, PartialOrd, Ord, Hash)]
+struct Secret {
+    token: String,
+}
+
+#[turbo_tasks::function]
+fn read(path: Value<Path>) -> Result<StringVc> {
+    Ok(StringVc::cell(path.segments.join("/")))
+}
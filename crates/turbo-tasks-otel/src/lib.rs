@@ -0,0 +1,337 @@
+//! Exports the task executions of turbo-tasks as OpenTelemetry spans to a
+//! collector, using OTLP over HTTP with JSON encoding.
+//!
+//! ```ignore
+//! let exporter = OtelExporter::new(OtelExporterOptions::default())?;
+//! turbo_tasks.add_execution_listener(exporter);
+//! ```
+
+mod otlp;
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Context, Result};
+use hyper::{client::HttpConnector, header::CONTENT_TYPE, Body, Client, Request, Uri};
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    time::timeout,
+};
+use turbo_tasks::{
+    registry, ExecutionListener, FunctionId, TaskExecution, TaskExecutionOutcome, TaskId,
+};
+
+use self::otlp::{ExportTraceServiceRequest, KeyValue, Resource, Span};
+
+/// Spans are sent in batches of at most this size.
+const MAX_BATCH_SIZE: usize = 512;
+/// The longest time a span waits for more spans before it's sent.
+const MAX_EXPORT_DELAY: Duration = Duration::from_secs(1);
+
+pub struct OtelExporterOptions {
+    /// The base URL of the OTLP/HTTP endpoint of the collector. Spans are
+    /// sent to `{endpoint}/v1/traces`. Only `http` URLs are supported.
+    pub endpoint: String,
+    /// The `service.name` of the spans.
+    pub service_name: String,
+    /// Additional attributes of all spans, e. g. the name of the project.
+    pub resource_attributes: Vec<(String, String)>,
+}
+
+impl Default for OtelExporterOptions {
+    fn default() -> Self {
+        OtelExporterOptions {
+            endpoint: "http://localhost:4318".to_string(),
+            service_name: "turbo-tasks".to_string(),
+            resource_attributes: Vec::new(),
+        }
+    }
+}
+
+/// An [ExecutionListener] which exports every task execution as a span.
+///
+/// The span of a task execution is the child of the span of the execution
+/// which scheduled it, so a build and the invalidations caused by a change
+/// show up as a tree. Spans of tasks scheduled from outside of a task start
+/// a new trace.
+///
+/// Spans are sent from a separate thread, which stops when the exporter is
+/// dropped. Failed exports are reported as a `tracing` warning.
+pub struct OtelExporter {
+    /// Makes the trace ids of different processes unique.
+    trace_id_prefix: u64,
+    next_id: AtomicU64,
+    /// The spans of the running executions. A span is removed when it's
+    /// exported, so tasks scheduled after their parent finished, e. g. by an
+    /// invalidation, start a new trace.
+    spans: Mutex<HashMap<TaskId, TaskSpan>>,
+    sender: UnboundedSender<Span>,
+}
+
+#[derive(Clone, Copy)]
+struct SpanContext {
+    trace_id: u128,
+    span_id: u64,
+}
+
+struct TaskSpan {
+    context: SpanContext,
+    parent_span_id: Option<u64>,
+    start_time: SystemTime,
+    start: Instant,
+}
+
+impl OtelExporter {
+    pub fn new(options: OtelExporterOptions) -> Result<Arc<Self>> {
+        let endpoint = format!("{}/v1/traces", options.endpoint.trim_end_matches('/'));
+        let uri: Uri = endpoint
+            .parse()
+            .with_context(|| format!("invalid OTLP endpoint {endpoint}"))?;
+        if uri.scheme_str() != Some("http") {
+            bail!("only http OTLP endpoints are supported, got {endpoint}");
+        }
+
+        let mut attributes = vec![KeyValue::string("service.name", options.service_name)];
+        attributes.extend(
+            options
+                .resource_attributes
+                .into_iter()
+                .map(|(key, value)| KeyValue::string(key, value)),
+        );
+        let resource = Resource { attributes };
+
+        let (sender, receiver) = unbounded_channel();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        thread::Builder::new()
+            .name("turbo-tasks-otel".to_string())
+            .spawn(move || runtime.block_on(export_spans(receiver, uri, resource)))?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Ok(Arc::new(OtelExporter {
+            trace_id_prefix: now.as_nanos() as u64 ^ ((std::process::id() as u64) << 32),
+            next_id: AtomicU64::new(1),
+            spans: Default::default(),
+            sender,
+        }))
+    }
+
+    fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+impl ExecutionListener for OtelExporter {
+    fn execution_started(
+        &self,
+        task: TaskId,
+        _function: Option<FunctionId>,
+        scheduled_by: Option<TaskId>,
+    ) {
+        let mut spans = self.spans.lock().unwrap();
+        let parent = scheduled_by
+            .and_then(|parent| spans.get(&parent))
+            .map(|parent| parent.context);
+        let context = SpanContext {
+            trace_id: match parent {
+                Some(parent) => parent.trace_id,
+                None => ((self.trace_id_prefix as u128) << 64) | self.next_id() as u128,
+            },
+            span_id: self.next_id(),
+        };
+        spans.insert(
+            task,
+            TaskSpan {
+                context,
+                parent_span_id: parent.map(|parent| parent.span_id),
+                start_time: SystemTime::now(),
+                start: Instant::now(),
+            },
+        );
+    }
+
+    fn execution_finished(&self, execution: &TaskExecution) {
+        let span = match self.spans.lock().unwrap().remove(&execution.task) {
+            Some(span) => span,
+            None => return,
+        };
+        let context = span.context;
+        let start_time = span
+            .start_time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let end_time = start_time + span.start.elapsed().as_nanos();
+
+        let mut attributes = vec![
+            KeyValue::int("turbo_tasks.task_id", *execution.task as u64),
+            KeyValue::int(
+                "turbo_tasks.execution_time_ns",
+                execution.duration.as_nanos() as u64,
+            ),
+        ];
+        let name = match execution.function {
+            Some(function) => {
+                let native_function = registry::get_function(function);
+                // e. g. `turbopack-core@0.1.0::resolve::resolve`
+                let global_name = registry::get_function_global_name(function);
+                if let Some((namespace, _)) = global_name.rsplit_once("::") {
+                    attributes.push(KeyValue::string("code.namespace", namespace));
+                }
+                if let Some((crate_name, _)) = global_name.split_once('@') {
+                    attributes.push(KeyValue::string("turbo_tasks.crate", crate_name));
+                }
+                if let Some(file) = native_function.file {
+                    attributes.push(KeyValue::string("code.filepath", file));
+                }
+                attributes.push(KeyValue::string(
+                    "code.function",
+                    native_function.name.clone(),
+                ));
+                native_function.name.clone()
+            }
+            None => "task".to_string(),
+        };
+        let error = match execution.outcome {
+            TaskExecutionOutcome::Success => None,
+            TaskExecutionOutcome::Error => Some("error"),
+            TaskExecutionOutcome::Panic => Some("panic"),
+        };
+
+        // Sending only fails when the export thread panicked.
+        let _ = self.sender.send(Span::new(
+            context.trace_id,
+            context.span_id,
+            span.parent_span_id,
+            name,
+            start_time,
+            end_time,
+            attributes,
+            error,
+        ));
+    }
+}
+
+/// Sends the received spans in batches, until the exporter is dropped.
+async fn export_spans(mut receiver: UnboundedReceiver<Span>, uri: Uri, resource: Resource) {
+    let client = Client::new();
+    let mut batch = Vec::new();
+    let mut failed = false;
+    loop {
+        let span = if batch.is_empty() {
+            receiver.recv().await
+        } else {
+            match timeout(MAX_EXPORT_DELAY, receiver.recv()).await {
+                Ok(span) => span,
+                Err(_) => {
+                    export(&client, &uri, &resource, &mut batch, &mut failed).await;
+                    continue;
+                }
+            }
+        };
+        match span {
+            Some(span) => {
+                batch.push(span);
+                if batch.len() >= MAX_BATCH_SIZE {
+                    export(&client, &uri, &resource, &mut batch, &mut failed).await;
+                }
+            }
+            None => {
+                if !batch.is_empty() {
+                    export(&client, &uri, &resource, &mut batch, &mut failed).await;
+                }
+                return;
+            }
+        }
+    }
+}
+
+/// Sends and clears the batch. Failures are reported once, so an unreachable
+/// collector doesn't flood the output.
+async fn export(
+    client: &Client<HttpConnector>,
+    uri: &Uri,
+    resource: &Resource,
+    batch: &mut Vec<Span>,
+    failed: &mut bool,
+) {
+    let result = send(client, uri, resource, batch).await;
+    batch.clear();
+    if let Err(err) = result {
+        if !*failed {
+            tracing::warn!("failed to export spans to {uri}: {err:#}");
+            *failed = true;
+        }
+    }
+}
+
+async fn send(
+    client: &Client<HttpConnector>,
+    uri: &Uri,
+    resource: &Resource,
+    spans: &[Span],
+) -> Result<()> {
+    let body = serde_json::to_vec(&ExportTraceServiceRequest::new(resource, spans))?;
+    let request = Request::post(uri)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))?;
+    let response = client.request(request).await?;
+    if !response.status().is_success() {
+        bail!("the collector responded with {}", response.status());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use anyhow::Result;
+    use turbo_tasks::{ExecutionListener, TaskExecution, TaskExecutionOutcome, TaskId};
+
+    use super::{OtelExporter, OtelExporterOptions};
+
+    fn finished(task: TaskId) -> TaskExecution {
+        TaskExecution {
+            task,
+            function: None,
+            duration: Duration::ZERO,
+            outcome: TaskExecutionOutcome::Success,
+        }
+    }
+
+    #[test]
+    fn removes_exported_spans() -> Result<()> {
+        let exporter = OtelExporter::new(OtelExporterOptions::default())?;
+        let parent = TaskId::from(1);
+        let child = TaskId::from(2);
+        exporter.execution_started(parent, None, None);
+        exporter.execution_started(child, None, Some(parent));
+        {
+            let spans = exporter.spans.lock().unwrap();
+            let parent = &spans[&parent].context;
+            let child = &spans[&child];
+            assert_eq!(child.context.trace_id, parent.trace_id);
+            assert_eq!(child.parent_span_id, Some(parent.span_id));
+        }
+
+        exporter.execution_finished(&finished(child));
+        exporter.execution_finished(&finished(parent));
+        assert!(exporter.spans.lock().unwrap().is_empty());
+
+        // A task scheduled by a finished execution starts a new trace.
+        exporter.execution_started(child, None, Some(parent));
+        assert_eq!(exporter.spans.lock().unwrap()[&child].parent_span_id, None);
+        Ok(())
+    }
+}
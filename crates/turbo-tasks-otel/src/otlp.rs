@@ -0,0 +1,202 @@
+//! The subset of the OTLP JSON encoding needed to export spans, see
+//! <https://opentelemetry.io/docs/specs/otlp/#json-protobuf-encoding>.
+//!
+//! Ids are hex encoded and 64 bit integers are strings, as required by the
+//! OTLP JSON encoding.
+
+use serde::Serialize;
+
+const SPAN_KIND_INTERNAL: u8 = 1;
+const STATUS_CODE_UNSET: u8 = 0;
+const STATUS_CODE_ERROR: u8 = 2;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportTraceServiceRequest<'a> {
+    resource_spans: [ResourceSpans<'a>; 1],
+}
+
+impl<'a> ExportTraceServiceRequest<'a> {
+    pub fn new(resource: &'a Resource, spans: &'a [Span]) -> Self {
+        ExportTraceServiceRequest {
+            resource_spans: [ResourceSpans {
+                resource,
+                scope_spans: [ScopeSpans {
+                    scope: Scope {
+                        name: env!("CARGO_PKG_NAME"),
+                        version: env!("CARGO_PKG_VERSION"),
+                    },
+                    spans,
+                }],
+            }],
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResourceSpans<'a> {
+    resource: &'a Resource,
+    scope_spans: [ScopeSpans<'a>; 1],
+}
+
+/// Describes the process which produced the spans.
+#[derive(Serialize)]
+pub struct Resource {
+    pub attributes: Vec<KeyValue>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScopeSpans<'a> {
+    scope: Scope,
+    spans: &'a [Span],
+}
+
+#[derive(Serialize)]
+struct Scope {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Span {
+    trace_id: String,
+    span_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent_span_id: Option<String>,
+    name: String,
+    kind: u8,
+    start_time_unix_nano: String,
+    end_time_unix_nano: String,
+    attributes: Vec<KeyValue>,
+    status: Status,
+}
+
+impl Span {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        trace_id: u128,
+        span_id: u64,
+        parent_span_id: Option<u64>,
+        name: String,
+        start_time_unix_nano: u128,
+        end_time_unix_nano: u128,
+        attributes: Vec<KeyValue>,
+        error: Option<&str>,
+    ) -> Self {
+        Span {
+            trace_id: format!("{trace_id:032x}"),
+            span_id: format!("{span_id:016x}"),
+            parent_span_id: parent_span_id.map(|id| format!("{id:016x}")),
+            name,
+            kind: SPAN_KIND_INTERNAL,
+            start_time_unix_nano: start_time_unix_nano.to_string(),
+            end_time_unix_nano: end_time_unix_nano.to_string(),
+            attributes,
+            status: match error {
+                Some(message) => Status {
+                    code: STATUS_CODE_ERROR,
+                    message: Some(message.to_string()),
+                },
+                None => Status {
+                    code: STATUS_CODE_UNSET,
+                    message: None,
+                },
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Status {
+    code: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct KeyValue {
+    key: String,
+    value: AnyValue,
+}
+
+impl KeyValue {
+    pub fn string(key: impl Into<String>, value: impl Into<String>) -> Self {
+        KeyValue {
+            key: key.into(),
+            value: AnyValue::StringValue(value.into()),
+        }
+    }
+
+    pub fn int(key: impl Into<String>, value: u64) -> Self {
+        KeyValue {
+            key: key.into(),
+            value: AnyValue::IntValue(value.to_string()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum AnyValue {
+    StringValue(String),
+    IntValue(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{ExportTraceServiceRequest, KeyValue, Resource, Span};
+
+    #[test]
+    fn test_encoding() {
+        let resource = Resource {
+            attributes: vec![KeyValue::string("service.name", "next-dev")],
+        };
+        let spans = [Span::new(
+            1,
+            2,
+            Some(3),
+            "resolve".to_string(),
+            10,
+            20,
+            vec![KeyValue::int("turbo_tasks.task_id", 4)],
+            Some("panic"),
+        )];
+        let request = ExportTraceServiceRequest::new(&resource, &spans);
+        assert_eq!(
+            serde_json::to_value(request).unwrap(),
+            json!({
+                "resourceSpans": [{
+                    "resource": {
+                        "attributes": [
+                            { "key": "service.name", "value": { "stringValue": "next-dev" } },
+                        ],
+                    },
+                    "scopeSpans": [{
+                        "scope": {
+                            "name": env!("CARGO_PKG_NAME"),
+                            "version": env!("CARGO_PKG_VERSION"),
+                        },
+                        "spans": [{
+                            "traceId": "00000000000000000000000000000001",
+                            "spanId": "0000000000000002",
+                            "parentSpanId": "0000000000000003",
+                            "name": "resolve",
+                            "kind": 1,
+                            "startTimeUnixNano": "10",
+                            "endTimeUnixNano": "20",
+                            "attributes": [
+                                { "key": "turbo_tasks.task_id", "value": { "intValue": "4" } },
+                            ],
+                            "status": { "code": 2, "message": "panic" },
+                        }],
+                    }],
+                }],
+            })
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{bail, Context, Result};
+
+/// Limits for an on-disk cache, checked before it's opened.
+///
+/// The persisted graph can't drop single tasks without breaking the
+/// dependencies of the remaining ones, so a cache which exceeds a limit is
+/// evicted as a whole.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EvictionPolicy {
+    /// Evict the cache when its files are larger than this many bytes.
+    pub max_size: Option<u64>,
+    /// Evict the cache when it hasn't been written for this long.
+    pub max_age: Option<Duration>,
+}
+
+/// Why a cache was evicted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionReason {
+    TooLarge { size: u64 },
+    Expired { age: Duration },
+}
+
+/// Size and last write of an on-disk cache.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheUsage {
+    pub size: u64,
+    pub last_write: SystemTime,
+}
+
+/// Returns whether `path` contains a RocksDB database. Only such directories
+/// are ever deleted, so a wrong path can't wipe user files.
+pub fn is_cache_dir(path: &Path) -> bool {
+    path.join("CURRENT").is_file() && path.join("IDENTITY").is_file()
+}
+
+/// Measures the cache at `path`. Returns `None` when there is no cache yet.
+pub fn cache_usage(path: &Path) -> Result<Option<CacheUsage>> {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).context(format!("reading cache {}", path.display())),
+    };
+    let mut usage = CacheUsage {
+        size: 0,
+        last_write: SystemTime::UNIX_EPOCH,
+    };
+    for entry in entries {
+        let metadata = entry?.metadata()?;
+        if metadata.is_file() {
+            usage.size += metadata.len();
+            usage.last_write = usage.last_write.max(metadata.modified()?);
+        }
+    }
+    Ok(Some(usage))
+}
+
+/// Deletes the cache at `path` and returns the number of bytes freed. Fails
+/// when `path` is not a cache directory.
+pub fn clean_cache(path: &Path) -> Result<u64> {
+    let usage = match cache_usage(path)? {
+        Some(usage) => usage,
+        None => return Ok(0),
+    };
+    if !is_cache_dir(path) {
+        bail!(
+            "{} doesn't look like a cache directory, refusing to delete it",
+            path.display()
+        );
+    }
+    fs::remove_dir_all(path).with_context(|| format!("deleting cache {}", path.display()))?;
+    Ok(usage.size)
+}
+
+/// Deletes the cache at `path` when it exceeds a limit of `policy`.
+pub fn evict(path: &Path, policy: EvictionPolicy) -> Result<Option<EvictionReason>> {
+    let usage = match cache_usage(path)? {
+        Some(usage) => usage,
+        None => return Ok(None),
+    };
+    let age = SystemTime::now()
+        .duration_since(usage.last_write)
+        .unwrap_or_default();
+    let reason = if policy.max_size.map_or(false, |max| usage.size > max) {
+        EvictionReason::TooLarge { size: usage.size }
+    } else if policy.max_age.map_or(false, |max| age > max) {
+        EvictionReason::Expired { age }
+    } else {
+        return Ok(None);
+    };
+    clean_cache(path)?;
+    Ok(Some(reason))
+}
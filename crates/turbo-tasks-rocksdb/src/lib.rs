@@ -2,9 +2,13 @@
 #![deny(unsafe_op_in_unsafe_fn)]
 
 mod db;
+mod eviction;
 mod persisted_graph;
 mod table;
 
+pub use eviction::{
+    cache_usage, clean_cache, evict, is_cache_dir, CacheUsage, EvictionPolicy, EvictionReason,
+};
 pub use persisted_graph::RocksDbPersistedGraph;
 
 #[doc(hidden)]
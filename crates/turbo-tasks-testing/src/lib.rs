@@ -103,6 +103,15 @@ impl TurboTasksApi for VcStorage {
         unreachable!()
     }
 
+    fn dispose_root_task(&self, _task: TaskId) {
+        unreachable!()
+    }
+
+    fn task_id_generation(&self, _task: TaskId) -> u32 {
+        // Task ids are never reused.
+        0
+    }
+
     fn notify_scheduled_tasks(&self) {
         // ignore
     }
@@ -202,6 +211,8 @@ impl TurboTasksApi for VcStorage {
         let cell = map.entry((task, index)).or_default();
         *cell = content;
     }
+
+    fn keep_current_task_cell(&self, _index: CellId) {}
 }
 
 impl VcStorage {
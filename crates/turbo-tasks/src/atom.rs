@@ -0,0 +1,291 @@
+use std::{
+    cmp::Ordering,
+    ffi::OsStr,
+    fmt::{self, Debug, Display, Formatter},
+    hash::{Hash, Hasher},
+    ops::Deref,
+    path::Path,
+    sync::{Arc, Weak},
+};
+
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use turbo_tasks_hash::{hash_xxh3_hash64, DeterministicHash, DeterministicHasher};
+
+/// The living atoms by the hash of their contents. Atoms remove themselves
+/// when they are dropped.
+static ATOMS: Lazy<DashMap<u64, Vec<Weak<AtomInner>>>> = Lazy::new(DashMap::new);
+
+/// An interned string, e. g. a path or a module specifier.
+///
+/// All living atoms with the same contents share one allocation, so cloning,
+/// comparing and hashing an atom is as cheap as for a pointer. An atom is
+/// freed when its last clone is dropped.
+#[derive(Clone)]
+pub struct Atom(Arc<AtomInner>);
+
+struct AtomInner {
+    /// The hash of the contents, which is the same in all processes.
+    hash: u64,
+    value: Box<str>,
+}
+
+impl Drop for AtomInner {
+    fn drop(&mut self) {
+        if let Some(mut atoms) = ATOMS.get_mut(&self.hash) {
+            // Another atom with the same contents might have been created
+            // meanwhile, only dead entries are removed.
+            atoms.retain(|atom| atom.strong_count() > 0);
+            if !atoms.is_empty() {
+                return;
+            }
+        }
+        ATOMS.remove_if(&self.hash, |_, atoms| atoms.is_empty());
+    }
+}
+
+impl Atom {
+    pub fn new(s: &str) -> Self {
+        let hash = hash_xxh3_hash64(s.as_bytes());
+        // Atoms which only share the hash are dropped after the map is
+        // unlocked, as dropping the last clone of an atom locks the map.
+        let mut collisions = Vec::new();
+        let mut atoms = ATOMS.entry(hash).or_default();
+        for atom in atoms.iter() {
+            if let Some(atom) = atom.upgrade() {
+                if &*atom.value == s {
+                    return Atom(atom);
+                }
+                collisions.push(atom);
+            }
+        }
+        let atom = Arc::new(AtomInner {
+            hash,
+            value: s.into(),
+        });
+        atoms.retain(|atom| atom.strong_count() > 0);
+        atoms.push(Arc::downgrade(&atom));
+        drop(atoms);
+        drop(collisions);
+        Atom(atom)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0.value
+    }
+}
+
+impl Default for Atom {
+    fn default() -> Self {
+        Atom::new("")
+    }
+}
+
+impl Deref for Atom {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Atom {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<Path> for Atom {
+    fn as_ref(&self) -> &Path {
+        Path::new(self.as_str())
+    }
+}
+
+impl AsRef<OsStr> for Atom {
+    fn as_ref(&self) -> &OsStr {
+        OsStr::new(self.as_str())
+    }
+}
+
+impl AsRef<[u8]> for Atom {
+    fn as_ref(&self) -> &[u8] {
+        self.as_str().as_bytes()
+    }
+}
+
+impl From<&str> for Atom {
+    fn from(s: &str) -> Self {
+        Atom::new(s)
+    }
+}
+
+impl From<String> for Atom {
+    fn from(s: String) -> Self {
+        Atom::new(&s)
+    }
+}
+
+impl From<&String> for Atom {
+    fn from(s: &String) -> Self {
+        Atom::new(s)
+    }
+}
+
+impl From<Atom> for String {
+    fn from(atom: Atom) -> Self {
+        atom.as_str().to_string()
+    }
+}
+
+impl PartialEq for Atom {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Atom {}
+
+impl PartialEq<str> for Atom {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Atom {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Atom {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<Atom> for str {
+    fn eq(&self, other: &Atom) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<Atom> for &str {
+    fn eq(&self, other: &Atom) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl PartialEq<Atom> for String {
+    fn eq(&self, other: &Atom) -> bool {
+        self == other.as_str()
+    }
+}
+
+/// Atoms are hashed by their contents, so the hash is the same in all
+/// processes.
+impl Hash for Atom {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash.hash(state);
+    }
+}
+
+/// Atoms are ordered by their contents, so the order is the same in all
+/// processes.
+impl PartialOrd for Atom {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Atom {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self == other {
+            return Ordering::Equal;
+        }
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl DeterministicHash for Atom {
+    fn deterministic_hash<H: DeterministicHasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        state.write_bytes(self.as_bytes());
+    }
+}
+
+impl Debug for Atom {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl Display for Atom {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(self.as_str(), f)
+    }
+}
+
+impl Serialize for Atom {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Atom {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(Atom::from(s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+        sync::Arc,
+    };
+
+    use super::{Atom, ATOMS};
+
+    #[test]
+    fn interned() {
+        let a = Atom::new("node_modules/react/index.js");
+        let b = Atom::from("node_modules/react/index.js".to_string());
+        assert_eq!(a, b);
+        assert_eq!(a.as_ptr(), b.as_ptr());
+        assert_ne!(a, Atom::new("node_modules/react"));
+        assert_eq!(a, "node_modules/react/index.js");
+        assert!(Atom::new("a") < Atom::new("b"));
+    }
+
+    #[test]
+    fn hashed_by_contents() {
+        let a = Atom::new("src/atom.rs/hashed_by_contents");
+        let hash = hash_of(&a);
+        drop(a);
+        // A new allocation for the same contents has the same hash.
+        assert_eq!(hash_of(&Atom::new("src/atom.rs/hashed_by_contents")), hash);
+    }
+
+    #[test]
+    fn freed_when_dropped() {
+        let contents = "src/atom.rs/freed_when_dropped";
+        let a = Atom::new(contents);
+        let hash = a.0.hash;
+        let weak = Arc::downgrade(&a.0);
+        let b = a.clone();
+        drop(a);
+        assert!(weak.upgrade().is_some());
+        drop(b);
+        assert!(weak.upgrade().is_none());
+        assert!(!ATOMS.contains_key(&hash));
+    }
+
+    fn hash_of(atom: &Atom) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        atom.hash(&mut hasher);
+        hasher.finish()
+    }
+}
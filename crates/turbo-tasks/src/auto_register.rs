@@ -0,0 +1,143 @@
+//! Registration of value types, value traits and functions at startup, without
+//! calling the `register()` functions generated by `turbo-tasks-build`.
+//!
+//! The turbo_tasks macros submit a [Registration] for every item from a
+//! constructor function in a linker section via `ctor`, which runs before
+//! `main`. [auto_register] collects them from all linked crates. This module is
+//! only compiled with the `auto_register` feature. Without it, only the
+//! generated `register()` functions register items.
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, Once},
+};
+
+use crate::{NativeFunction, TraitType, ValueType};
+
+/// The parts of the global name of an item which are known to the macros.
+#[doc(hidden)]
+pub struct RegistrationName {
+    /// `CARGO_PKG_NAME@CARGO_PKG_VERSION` of the declaring crate.
+    pub package: &'static str,
+    /// `module_path!()` of the declaration.
+    pub module_path: &'static str,
+    /// The path of the item in its module, e. g. `Struct::Trait::method`.
+    pub path: &'static str,
+}
+
+impl RegistrationName {
+    /// Returns the same global name as `turbo-tasks-build` generates, so
+    /// persisted names are compatible between both ways of registration.
+    fn global_name(&self) -> String {
+        // The crate name at the start of the module path is replaced by the
+        // package.
+        let module_path = self
+            .module_path
+            .find("::")
+            .map_or("", |index| &self.module_path[index..]);
+        format!("{}::{}::{}", self.package, module_path, self.path)
+    }
+}
+
+/// An item declared by the turbo_tasks macros.
+#[doc(hidden)]
+pub enum Registration {
+    Function {
+        name: RegistrationName,
+        function: fn() -> &'static NativeFunction,
+    },
+    TraitType {
+        name: RegistrationName,
+        trait_type: fn() -> &'static TraitType,
+    },
+    ValueType {
+        name: RegistrationName,
+        /// Initializes and registers the value type, after the callback has
+        /// registered its trait implementations.
+        register: fn(&str, &mut dyn FnMut(&mut ValueType)),
+    },
+    /// The implementation of a value trait for a value type, which is
+    /// declared in the same module as the value type.
+    TraitImpl {
+        value: RegistrationName,
+        register: fn(&mut ValueType),
+    },
+}
+
+static REGISTRATIONS: Mutex<Vec<&'static Registration>> = Mutex::new(Vec::new());
+
+/// Called by the constructor functions generated by `__auto_register!`.
+#[doc(hidden)]
+pub fn submit(registration: &'static Registration) {
+    REGISTRATIONS.lock().unwrap().push(registration);
+}
+
+/// Registers all items of all linked crates. Only the first call has an
+/// effect.
+pub(crate) fn auto_register() {
+    static REGISTERED: Once = Once::new();
+    REGISTERED.call_once(|| {
+        let registrations = REGISTRATIONS.lock().unwrap();
+        // Value traits reference their default method functions, and trait
+        // implementations reference both, so the order matters.
+        for registration in registrations.iter() {
+            if let Registration::Function { name, function } = registration {
+                function().register(&name.global_name());
+            }
+        }
+        for registration in registrations.iter() {
+            if let Registration::TraitType { name, trait_type } = registration {
+                trait_type().register(&name.global_name());
+            }
+        }
+        let mut trait_impls: HashMap<String, Vec<fn(&mut ValueType)>> = HashMap::new();
+        for registration in registrations.iter() {
+            if let Registration::TraitImpl { value, register } = registration {
+                trait_impls
+                    .entry(value.global_name())
+                    .or_default()
+                    .push(*register);
+            }
+        }
+        for registration in registrations.iter() {
+            if let Registration::ValueType { name, register } = registration {
+                let global_name = name.global_name();
+                let impls = trait_impls.remove(&global_name).unwrap_or_default();
+                register(&global_name, &mut |value| {
+                    for register_impl in &impls {
+                        register_impl(value);
+                    }
+                });
+            }
+        }
+    });
+}
+
+/// Submits a [Registration]. Without the `auto_register` feature, the macro
+/// of the same name in the crate root discards it.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __auto_register {
+    ($registration:expr) => {
+        const _: () = {
+            static REGISTRATION: $crate::macro_helpers::Registration = $registration;
+            #[$crate::macro_helpers::ctor]
+            fn submit_registration() {
+                $crate::macro_helpers::submit_registration(&REGISTRATION);
+            }
+        };
+    };
+}
+
+/// Creates the [RegistrationName] of an item declared in the calling module.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __registration_name {
+    ($path:expr) => {
+        $crate::macro_helpers::RegistrationName {
+            package: concat!(env!("CARGO_PKG_NAME"), "@", env!("CARGO_PKG_VERSION")),
+            module_path: module_path!(),
+            path: $path,
+        }
+    };
+}
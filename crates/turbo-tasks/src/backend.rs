@@ -107,6 +107,9 @@ impl PersistentTaskType {
 
 pub struct TaskExecutionSpec {
     pub future: Pin<Box<dyn Future<Output = Result<RawVc>> + Send>>,
+    /// The native function executed by the task, reported to
+    /// [crate::ExecutionListener]s.
+    pub function: Option<FunctionId>,
 }
 
 // TODO technically CellContent is already indexed by the ValueTypeId, so we
@@ -149,6 +152,49 @@ impl CellContent {
     }
 }
 
+/// The cells of a persistent task together with the key of the task.
+///
+/// Since the [PersistentTaskType] identifies the task independently of the
+/// process, a snapshot can be serialized and restored into a different
+/// [Backend]. [TaskId]s contained in the snapshot are serialized via the
+/// current [IdMapping](crate::IdMapping), see
+/// [with_task_id_mapping](crate::with_task_id_mapping).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskSnapshot {
+    pub task_type: PersistentTaskType,
+    pub cells: Vec<(CellId, CellContent)>,
+    pub children: Vec<TaskId>,
+    /// The output of the task, when it has finished executing and only read
+    /// outputs and cells of other tasks. A task restored without an output is
+    /// executed again.
+    pub output: Option<SnapshotOutput>,
+    /// The outputs ([RawVc::TaskOutput]) and cells ([RawVc::TaskCell]) the
+    /// task read during its last execution.
+    pub dependencies: Vec<RawVc>,
+}
+
+/// The output of a snapshotted task.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum SnapshotOutput {
+    /// One of the cells of the task itself.
+    OwnCell(CellId),
+    /// The output or a cell of another task.
+    Link(RawVc),
+}
+
+/// A read of a task output or cell by a task execution. The dependency of the
+/// reading task on it is registered in a batch with [Backend::track_reads]
+/// when the execution has finished.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TrackedRead {
+    pub task: TaskId,
+    /// The cell which was read, or `None` when the output was read.
+    pub cell: Option<CellId>,
+    /// The version of the output or cell which was read, so the backend can
+    /// detect changes between the read and [Backend::track_reads].
+    pub version: u32,
+}
+
 pub trait Backend: Sync + Send {
     #[allow(unused_variables)]
     fn initialize(&mut self, task_id_provider: &dyn TaskIdProvider) {}
@@ -156,6 +202,9 @@ pub trait Backend: Sync + Send {
     #[allow(unused_variables)]
     fn startup(&self, turbo_tasks: &dyn TurboTasksBackendApi) {}
 
+    /// Called by [TurboTasks::stop_and_wait](crate::TurboTasks::stop_and_wait)
+    /// once no task and no background job is running anymore. The backend
+    /// is not used afterwards.
     #[allow(unused_variables)]
     fn stop(&self, turbo_tasks: &dyn TurboTasksBackendApi) {}
 
@@ -265,6 +314,69 @@ pub trait Backend: Sync + Send {
         turbo_tasks: &dyn TurboTasksBackendApi,
     );
 
+    /// Like [Backend::try_read_task_output], but the dependency of `reader`
+    /// may only be registered when the returned [TrackedRead] is passed to
+    /// [Backend::track_reads].
+    ///
+    /// By default the dependency is registered immediately.
+    fn try_read_task_output_batched(
+        &self,
+        task: TaskId,
+        reader: TaskId,
+        strongly_consistent: bool,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Result<Result<(RawVc, TrackedRead), EventListener>> {
+        Ok(self
+            .try_read_task_output(task, reader, strongly_consistent, turbo_tasks)?
+            .map(|vc| {
+                let read = TrackedRead {
+                    task,
+                    cell: None,
+                    version: 0,
+                };
+                (vc, read)
+            }))
+    }
+
+    /// Like [Backend::try_read_task_cell], but the dependency of `reader` may
+    /// only be registered when the returned [TrackedRead] is passed to
+    /// [Backend::track_reads].
+    ///
+    /// By default the dependency is registered immediately.
+    fn try_read_task_cell_batched(
+        &self,
+        task: TaskId,
+        index: CellId,
+        reader: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Result<Result<(CellContent, TrackedRead), EventListener>> {
+        Ok(self
+            .try_read_task_cell(task, index, reader, turbo_tasks)?
+            .map(|content| {
+                let read = TrackedRead {
+                    task,
+                    cell: Some(index),
+                    version: 0,
+                };
+                (content, read)
+            }))
+    }
+
+    /// Registers the dependencies of `reader` on everything it read during
+    /// one execution with the `*_batched` methods. Called once per execution,
+    /// before [Backend::task_execution_result].
+    ///
+    /// When a read output or cell has changed since it was read, `reader` must
+    /// be invalidated, as it wasn't notified about the change.
+    #[allow(unused_variables)]
+    fn track_reads(
+        &self,
+        reader: TaskId,
+        reads: Vec<TrackedRead>,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) {
+    }
+
     fn try_read_task_collectibles(
         &self,
         task: TaskId,
@@ -297,6 +409,12 @@ pub trait Backend: Sync + Send {
         turbo_tasks: &dyn TurboTasksBackendApi,
     );
 
+    /// Called instead of [Backend::update_task_cell] when the content
+    /// assigned to the cell was equal to its existing content, so the cell
+    /// was kept as it is. See [crate::CellEquality].
+    #[allow(unused_variables)]
+    fn keep_task_cell(&self, task: TaskId, index: CellId) {}
+
     fn get_or_create_persistent_task(
         &self,
         task_type: PersistentTaskType,
@@ -309,6 +427,48 @@ pub trait Backend: Sync + Send {
         task_type: TransientTaskType,
         turbo_tasks: &dyn TurboTasksBackendApi,
     ) -> TaskId;
+
+    /// Disposes the root task `task` created by
+    /// [Backend::create_transient_task]. It's no longer executed when
+    /// something it has read changes, and no longer keeps the tasks it has
+    /// called active.
+    #[allow(unused_variables)]
+    fn dispose_root_task(&self, task: TaskId, turbo_tasks: &dyn TurboTasksBackendApi) {}
+
+    /// Returns the key of `task`. Returns `None` for transient tasks or when
+    /// the backend doesn't support it.
+    #[allow(unused_variables)]
+    fn get_persistent_task_type(&self, task: TaskId) -> Option<PersistentTaskType> {
+        None
+    }
+
+    /// Returns the key, the cells and the children of `task`. Returns `None`
+    /// for transient tasks or when the backend doesn't support snapshots.
+    #[allow(unused_variables)]
+    fn snapshot_task(
+        &self,
+        task: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Option<TaskSnapshot> {
+        None
+    }
+
+    /// Creates the task described by `snapshot`, if it doesn't exist yet, and
+    /// assigns the snapshotted cells and output to it. A task restored with an
+    /// output is not executed until one of its dependencies changes. The
+    /// [TaskId]s in the snapshot must refer to restored tasks. The task is
+    /// connected as child of `parent_task`, if any, and of the restored tasks
+    /// listing it as child. Returns `None` when the backend doesn't support
+    /// restoring tasks.
+    #[allow(unused_variables)]
+    fn restore_task(
+        &self,
+        snapshot: TaskSnapshot,
+        parent_task: Option<TaskId>,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Option<TaskId> {
+        None
+    }
 }
 
 impl PersistentTaskType {
@@ -317,9 +477,15 @@ impl PersistentTaskType {
         inputs: Vec<TaskInput>,
         turbo_tasks: Arc<dyn TurboTasksBackendApi>,
     ) -> Result<RawVc> {
+        let resolve_values = registry::get_function(fn_id).resolve_values;
         let mut resolved_inputs = Vec::with_capacity(inputs.len());
         for input in inputs.into_iter() {
-            resolved_inputs.push(input.resolve().await?)
+            let input = input.resolve().await?;
+            resolved_inputs.push(if resolve_values {
+                input.resolve_values().await?
+            } else {
+                input
+            })
         }
         Ok(turbo_tasks.native_call(fn_id, resolved_inputs))
     }
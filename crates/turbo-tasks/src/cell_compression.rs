@@ -0,0 +1,61 @@
+use std::{
+    any::{type_name, Any},
+    borrow::Cow,
+    sync::Arc,
+};
+
+/// A value type whose values can be converted to bytes and back, so backends
+/// can store large values compressed in cells. Compression is enabled per
+/// value type with [crate::registry::register_cell_compression].
+pub trait CompressibleValue: Any + Send + Sync {
+    fn to_bytes(&self) -> Cow<'_, [u8]>;
+
+    /// Restores a value from the bytes returned by [Self::to_bytes].
+    fn from_bytes(bytes: Vec<u8>) -> Self;
+}
+
+type ToBytesFn = fn(&(dyn Any + Send + Sync)) -> Cow<'_, [u8]>;
+type FromBytesFn = fn(Vec<u8>) -> Arc<dyn Any + Send + Sync>;
+
+/// How the values of a value type are compressed in cells, see
+/// [CompressibleValue].
+#[derive(Clone, Copy)]
+pub struct CellCompression {
+    /// Values with fewer bytes are stored uncompressed, as compressing them
+    /// doesn't save enough memory to be worth the time.
+    pub threshold: usize,
+    to_bytes: ToBytesFn,
+    from_bytes: FromBytesFn,
+}
+
+fn any_to_bytes<T: CompressibleValue>(value: &(dyn Any + Send + Sync)) -> Cow<'_, [u8]> {
+    if let Some(value) = value.downcast_ref::<T>() {
+        return value.to_bytes();
+    }
+    panic!(
+        "any_to_bytes::<{}> called with invalid type",
+        type_name::<T>()
+    );
+}
+
+fn any_from_bytes<T: CompressibleValue>(bytes: Vec<u8>) -> Arc<dyn Any + Send + Sync> {
+    Arc::new(T::from_bytes(bytes))
+}
+
+impl CellCompression {
+    pub fn new<T: CompressibleValue>(threshold: usize) -> Self {
+        Self {
+            threshold,
+            to_bytes: any_to_bytes::<T>,
+            from_bytes: any_from_bytes::<T>,
+        }
+    }
+
+    pub fn to_bytes<'a>(&self, value: &'a (dyn Any + Send + Sync)) -> Cow<'a, [u8]> {
+        (self.to_bytes)(value)
+    }
+
+    pub fn from_bytes(&self, bytes: Vec<u8>) -> Arc<dyn Any + Send + Sync> {
+        (self.from_bytes)(bytes)
+    }
+}
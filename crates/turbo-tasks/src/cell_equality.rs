@@ -0,0 +1,36 @@
+use std::any::{type_name, Any};
+
+type EqFn = fn(&(dyn Any + Send + Sync), &(dyn Any + Send + Sync)) -> bool;
+
+/// How the values of a value type are compared, so the existing value of a
+/// cell is kept when an equal value is assigned to it, instead of
+/// invalidating all tasks which read the cell.
+///
+/// Value types which don't use `cell = "new"` are always compared by their
+/// [PartialEq]. The comparison is configurable per value type with
+/// [crate::registry::register_cell_equality] and
+/// [crate::registry::unregister_cell_equality].
+#[derive(Clone, Copy)]
+pub struct CellEquality {
+    eq: EqFn,
+}
+
+fn any_eq<T: PartialEq + Any + Send + Sync>(
+    a: &(dyn Any + Send + Sync),
+    b: &(dyn Any + Send + Sync),
+) -> bool {
+    if let (Some(a), Some(b)) = (a.downcast_ref::<T>(), b.downcast_ref::<T>()) {
+        return a == b;
+    }
+    panic!("any_eq::<{}> called with invalid type", type_name::<T>());
+}
+
+impl CellEquality {
+    pub fn new<T: PartialEq + Any + Send + Sync>() -> Self {
+        Self { eq: any_eq::<T> }
+    }
+
+    pub fn eq(&self, a: &(dyn Any + Send + Sync), b: &(dyn Any + Send + Sync)) -> bool {
+        (self.eq)(a, b)
+    }
+}
@@ -20,7 +20,13 @@ pub async fn read_collectibles(raw: RawVc, trait_type: usize) -> Result<RawVcSet
     }
 }
 
+/// Collects the collectibles of a value trait which were emitted with
+/// [emit](crate::emit) by a task and all its transitive children.
 pub trait CollectiblesSource {
+    /// Returns the collectibles and removes them, so they don't bubble up to
+    /// the parents of the task.
     fn take_collectibles<T: turbo_tasks::ValueTraitVc>(self) -> CollectiblesFuture<T>;
+    /// Returns the collectibles, which still bubble up to the parents of the
+    /// task.
     fn peek_collectibles<T: turbo_tasks::ValueTraitVc>(self) -> CollectiblesFuture<T>;
 }
@@ -0,0 +1,96 @@
+use std::{future::Future, sync::Arc};
+
+use tokio::sync::Semaphore;
+
+use crate::manager::turbo_tasks;
+
+/// A category of work whose concurrency can be limited independently of the
+/// number of running tasks, e. g. to not exceed the rate limit of a server.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ConcurrencyCategory {
+    /// Reading and writing files.
+    FsIo,
+    /// Requests to other servers.
+    Network,
+    /// CPU heavy work, like transforming or minifying code.
+    Cpu,
+    /// Operations in Node.js processes.
+    NodeBridge,
+}
+
+impl ConcurrencyCategory {
+    const COUNT: usize = 4;
+
+    fn index(self) -> usize {
+        match self {
+            ConcurrencyCategory::FsIo => 0,
+            ConcurrencyCategory::Network => 1,
+            ConcurrencyCategory::Cpu => 2,
+            ConcurrencyCategory::NodeBridge => 3,
+        }
+    }
+}
+
+/// The maximum number of concurrent operations per [ConcurrencyCategory].
+/// Categories are unlimited unless a limit is set.
+#[derive(Clone, Debug, Default)]
+pub struct ConcurrencyLimits {
+    limits: [Option<usize>; ConcurrencyCategory::COUNT],
+}
+
+impl ConcurrencyLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows at most `max` concurrent operations of `category`.
+    pub fn limit(mut self, category: ConcurrencyCategory, max: usize) -> Self {
+        self.limits[category.index()] = Some(max);
+        self
+    }
+
+    pub fn get(&self, category: ConcurrencyCategory) -> Option<usize> {
+        self.limits[category.index()]
+    }
+}
+
+/// The semaphores enforcing [ConcurrencyLimits] in a turbo-tasks instance.
+pub(crate) struct ConcurrencySemaphores {
+    semaphores: [Option<Arc<Semaphore>>; ConcurrencyCategory::COUNT],
+}
+
+impl ConcurrencySemaphores {
+    pub(crate) fn new(limits: &ConcurrencyLimits) -> Self {
+        Self {
+            semaphores: limits
+                .limits
+                .map(|limit| limit.map(|limit| Arc::new(Semaphore::new(limit)))),
+        }
+    }
+
+    pub(crate) fn get(&self, category: ConcurrencyCategory) -> Option<Arc<Semaphore>> {
+        self.semaphores[category.index()].clone()
+    }
+}
+
+/// Runs `future` once less operations of `category` than the limit
+/// configured for the current turbo-tasks instance are running.
+///
+/// Only the operation itself should be wrapped, not reading other tasks,
+/// since waiting for them while holding a permit could exhaust the limit.
+pub async fn with_concurrency_limit<T>(
+    category: ConcurrencyCategory,
+    future: impl Future<Output = T>,
+) -> T {
+    let semaphore = turbo_tasks().concurrency_semaphore(category);
+    let _permit = match &semaphore {
+        Some(semaphore) => Some(
+            semaphore
+                .acquire()
+                .await
+                .expect("concurrency semaphores are never closed"),
+        ),
+        None => None,
+    };
+    future.await
+}
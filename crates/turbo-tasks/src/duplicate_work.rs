@@ -0,0 +1,108 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use anyhow::Result;
+
+use crate::{registry, FunctionId, TaskId, TaskInput};
+
+/// A function which was executed in separate tasks for arguments which are
+/// equal once they are resolved, because some of them contain Vcs pointing to
+/// task outputs. Reported when duplicate work detection is enabled with
+/// [crate::TurboTasks::enable_duplicate_work_detection].
+#[derive(Clone, Debug)]
+pub struct DuplicateWork {
+    /// Increases with every report, so new reports can be told apart from
+    /// ones which were seen before.
+    pub id: usize,
+    /// The signature of the function.
+    pub function: String,
+    /// The source file declaring the function.
+    pub file: Option<&'static str>,
+}
+
+impl Display for DuplicateWork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.function)?;
+        if let Some(file) = self.file {
+            write!(f, " in {file}")?;
+        }
+        write!(
+            f,
+            " was executed multiple times for equal arguments, as some of the Vcs in its \
+             arguments were not resolved. Resolve them with `.resolve().await?` before calling \
+             it, or declare it with `#[turbo_tasks::function(resolve_values)]`."
+        )
+    }
+}
+
+/// Compares the resolved arguments of new tasks, to find functions which do
+/// the same work in separate tasks.
+#[derive(Default)]
+pub(crate) struct DuplicateWorkDetector {
+    enabled: AtomicBool,
+    /// The tasks which were already checked.
+    checked: Mutex<HashSet<TaskId>>,
+    /// The tasks of each function by their resolved arguments.
+    tasks: Mutex<HashMap<(FunctionId, Vec<TaskInput>), TaskId>>,
+    reports: Mutex<Vec<DuplicateWork>>,
+    reported: Mutex<HashSet<FunctionId>>,
+}
+
+impl DuplicateWorkDetector {
+    pub fn enable(&self) {
+        self.enabled.store(true, Ordering::Release);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Acquire)
+    }
+
+    /// Whether the inputs of `task` need to be checked. Each task is only
+    /// checked once.
+    pub fn should_check(&self, task: TaskId) -> bool {
+        self.is_enabled() && self.checked.lock().unwrap().insert(task)
+    }
+
+    /// Resolves the inputs of `task`, and reports `function` when another task
+    /// of it has equal resolved inputs. Has to be called with a turbo-tasks
+    /// instance, but outside of tasks.
+    pub async fn check(&self, function: FunctionId, task: TaskId, inputs: Vec<TaskInput>) {
+        let inputs = match resolve_untracked(inputs).await {
+            Ok(inputs) => inputs,
+            // The inputs can't be compared, e. g. because a task failed.
+            Err(_) => return,
+        };
+        let duplicate = {
+            let mut tasks = self.tasks.lock().unwrap();
+            *tasks.entry((function, inputs)).or_insert(task) != task
+        };
+        if duplicate && self.reported.lock().unwrap().insert(function) {
+            let native_fn = registry::get_function(function);
+            let mut reports = self.reports.lock().unwrap();
+            let id = reports.len();
+            reports.push(DuplicateWork {
+                id,
+                function: native_fn.signature(),
+                file: native_fn.file,
+            });
+        }
+    }
+
+    pub fn reports(&self) -> Vec<DuplicateWork> {
+        self.reports.lock().unwrap().clone()
+    }
+}
+
+async fn resolve_untracked(inputs: Vec<TaskInput>) -> Result<Vec<TaskInput>> {
+    let mut resolved = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        resolved.push(input.resolve_values_untracked().await?);
+    }
+    Ok(resolved)
+}
@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use crate::{FunctionId, TaskId};
+
+/// How a task execution ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskExecutionOutcome {
+    Success,
+    /// The task returned an error.
+    Error,
+    /// The task panicked.
+    Panic,
+}
+
+/// A finished task execution, as reported to [ExecutionListener]s.
+#[derive(Clone, Copy, Debug)]
+pub struct TaskExecution {
+    pub task: TaskId,
+    /// The native function executed by the task. Root tasks, and tasks which
+    /// only resolve the arguments of a call, don't execute one.
+    pub function: Option<FunctionId>,
+    /// The time spent executing the task, without the time it waited for
+    /// other tasks.
+    pub duration: Duration,
+    pub outcome: TaskExecutionOutcome,
+}
+
+/// Is notified about every task execution of a [crate::TurboTasks] instance,
+/// e. g. to collect telemetry or to find tasks which fail intermittently.
+/// Listeners are added with [crate::TurboTasks::add_execution_listener].
+///
+/// The methods are called on the thread executing the task, so they should
+/// return quickly.
+pub trait ExecutionListener: Send + Sync {
+    /// `scheduled_by` is the task whose execution caused this one to be
+    /// scheduled, e. g. by calling it or by changing a value it read. Tasks
+    /// scheduled from outside of a task have none.
+    fn execution_started(
+        &self,
+        _task: TaskId,
+        _function: Option<FunctionId>,
+        _scheduled_by: Option<TaskId>,
+    ) {
+    }
+
+    fn execution_finished(&self, execution: &TaskExecution);
+}
@@ -1,13 +1,27 @@
 use std::{
     marker::PhantomData,
     ops::Deref,
-    sync::atomic::{AtomicUsize, Ordering},
+    sync::{
+        atomic::{AtomicU32, AtomicUsize, Ordering},
+        Mutex,
+    },
 };
 
+use once_cell::sync::OnceCell;
+
+use crate::no_move_vec::NoMoveVec;
+
 pub struct IdFactory<T> {
     next_id: AtomicUsize,
-    // TODO
-    // free_ids: AtomicPtr<...>
+    /// Ids passed to [IdFactory::reuse], which are handed out again before new
+    /// ids are allocated.
+    free_ids: Mutex<Vec<usize>>,
+    /// The length of `free_ids`, to avoid locking it when it's empty.
+    free_ids_len: AtomicUsize,
+    /// The generation of each id, indexed by the id, see
+    /// [IdFactory::generation]. It's odd while the id is free. Allocated when
+    /// the first id is reused.
+    generations: OnceCell<NoMoveVec<AtomicU32>>,
     phantom_data: PhantomData<T>,
 }
 
@@ -21,18 +35,110 @@ impl<T: From<usize> + Deref<Target = usize>> IdFactory<T> {
     pub const fn new() -> Self {
         Self {
             next_id: AtomicUsize::new(1),
+            free_ids: Mutex::new(Vec::new()),
+            free_ids_len: AtomicUsize::new(0),
+            generations: OnceCell::new(),
             phantom_data: PhantomData,
         }
     }
 
+    /// Returns an id which is not in use. Reused ids are returned before new
+    /// ones.
+    ///
+    /// Panics when all ids are in use, instead of wrapping around and
+    /// returning an id which is still in use.
     pub fn get(&self) -> T {
-        self.next_id.fetch_add(1, Ordering::Relaxed).into()
+        if self.free_ids_len.load(Ordering::Acquire) > 0 {
+            let mut free_ids = self.free_ids.lock().unwrap();
+            if let Some(id) = free_ids.pop() {
+                self.free_ids_len.store(free_ids.len(), Ordering::Release);
+                drop(free_ids);
+                self.generation_slot(id).fetch_add(1, Ordering::AcqRel);
+                return id.into();
+            }
+        }
+        self.next_id
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |id| id.checked_add(1))
+            .expect("IdFactory ran out of ids")
+            .into()
+    }
+
+    fn generation_slot(&self, id: usize) -> &AtomicU32 {
+        self.generations
+            .get_or_init(NoMoveVec::new_init_default)
+            .get_init_default(id)
+    }
+
+    /// Changes when the id is reused and again when it's handed out again.
+    /// Together with the id, it identifies one use of the id, e. g. to check
+    /// whether a stored id still refers to the same item (see
+    /// [Invalidator](crate::Invalidator)). It doesn't lock.
+    ///
+    /// The generation wraps around after 2^31 reuses of the same id.
+    pub fn generation(&self, id: &T) -> u32 {
+        self.generations
+            .get()
+            .and_then(|generations| generations.get(**id))
+            .map_or(0, |generation| generation.load(Ordering::Acquire))
     }
 
     /// # Safety
     ///
     /// It must be ensured that the id is no longer used
-    pub unsafe fn reuse(&self, _id: T) {
-        // TODO
+    ///
+    /// Panics when the id is free already, as handing it out twice would make
+    /// two items share it.
+    pub unsafe fn reuse(&self, id: T) {
+        let id = *id;
+        let previous = self.generation_slot(id).fetch_add(1, Ordering::AcqRel);
+        if previous % 2 == 1 {
+            panic!("IdFactory::reuse called twice for the id {id}");
+        }
+        let mut free_ids = self.free_ids.lock().unwrap();
+        free_ids.push(id);
+        self.free_ids_len.store(free_ids.len(), Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IdFactory;
+    use crate::TaskId;
+
+    #[test]
+    fn reuses_ids() {
+        let factory = IdFactory::<TaskId>::new();
+        let a = factory.get();
+        let b = factory.get();
+        assert_ne!(a, b);
+        assert_eq!(factory.generation(&a), 0);
+
+        unsafe { factory.reuse(a) };
+        assert_eq!(factory.generation(&a), 1);
+        assert_eq!(factory.get(), a);
+        assert_eq!(factory.generation(&a), 2);
+        assert_eq!(factory.generation(&b), 0);
+        assert_eq!(*factory.get(), *b + 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "IdFactory::reuse called twice")]
+    fn panics_on_double_reuse() {
+        let factory = IdFactory::<TaskId>::new();
+        let a = factory.get();
+        unsafe {
+            factory.reuse(a);
+            factory.reuse(a);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "IdFactory ran out of ids")]
+    fn panics_on_overflow() {
+        let factory = IdFactory::<TaskId>::new();
+        factory
+            .next_id
+            .store(usize::MAX, std::sync::atomic::Ordering::Relaxed);
+        factory.get();
     }
 }
@@ -0,0 +1,153 @@
+use std::{
+    collections::{HashMap, HashSet},
+    mem::take,
+    sync::{LockResult, Mutex, MutexGuard},
+};
+
+use concurrent_queue::ConcurrentQueue;
+use serde::{de::Visitor, Deserialize, Serialize};
+
+use crate::{get_invalidator, Invalidator};
+
+/// The key under which tasks are registered in an [InvalidationMap], e. g. a
+/// file path or the name of a config file.
+pub type InvalidationKey = String;
+
+/// [Invalidator]s of tasks registered under [InvalidationKey]s, so an
+/// integration with an external resource can invalidate the tasks which used
+/// a part of it when it changes, e. g. a file system watcher or a config
+/// reloader.
+///
+/// Invalidating a key removes its invalidators, as the tasks register again
+/// when they are reexecuted.
+pub struct InvalidationMap {
+    /// Registrations are queued without locking, as they happen in tasks.
+    queue: ConcurrentQueue<(InvalidationKey, Invalidator)>,
+    map: Mutex<HashMap<InvalidationKey, HashSet<Invalidator>>>,
+}
+
+impl Default for InvalidationMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InvalidationMap {
+    pub fn new() -> Self {
+        Self {
+            queue: ConcurrentQueue::unbounded(),
+            map: Default::default(),
+        }
+    }
+
+    /// Registers the current task under `key`. Has to be called within a
+    /// turbo-tasks function.
+    pub fn register(&self, key: impl Into<InvalidationKey>) {
+        self.insert(key.into(), get_invalidator());
+    }
+
+    #[allow(unused_must_use)]
+    pub fn insert(&self, key: InvalidationKey, invalidator: Invalidator) {
+        self.queue.push((key, invalidator));
+    }
+
+    /// Gives access to all registrations, e. g. to invalidate them in a
+    /// custom way.
+    pub fn lock(
+        &self,
+    ) -> LockResult<MutexGuard<'_, HashMap<InvalidationKey, HashSet<Invalidator>>>> {
+        let mut guard = self.map.lock()?;
+        while let Ok((key, value)) = self.queue.pop() {
+            guard.entry(key).or_default().insert(value);
+        }
+        Ok(guard)
+    }
+
+    /// Invalidates the tasks registered under `key`. Returns the number of
+    /// invalidated tasks.
+    pub fn invalidate(&self, key: &str) -> usize {
+        let invalidators = self.lock().unwrap().remove(key);
+        invalidate_all(key, invalidators)
+    }
+
+    /// Invalidates the tasks registered under all keys matching `filter`.
+    /// Returns the number of invalidated tasks.
+    pub fn invalidate_where(&self, mut filter: impl FnMut(&str) -> bool) -> usize {
+        let invalidators = self
+            .lock()
+            .unwrap()
+            .drain_filter(|key, _| filter(key))
+            .collect::<Vec<_>>();
+        invalidators
+            .into_iter()
+            .map(|(key, invalidators)| invalidate_all(&key, invalidators))
+            .sum()
+    }
+
+    /// Invalidates all registered tasks. Returns the number of invalidated
+    /// tasks.
+    pub fn invalidate_all(&self) -> usize {
+        let map = take(&mut *self.lock().unwrap());
+        map.into_iter()
+            .map(|(key, invalidators)| invalidate_all(&key, invalidators))
+            .sum()
+    }
+
+    /// The keys which currently have tasks registered, in no particular
+    /// order.
+    pub fn keys(&self) -> Vec<InvalidationKey> {
+        self.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// The number of tasks registered under `key`.
+    pub fn count(&self, key: &str) -> usize {
+        self.lock().unwrap().get(key).map_or(0, |set| set.len())
+    }
+}
+
+/// Invalidates `invalidators` with `key` as the reason, so invalidation
+/// traces show which key changed.
+fn invalidate_all(key: &str, invalidators: impl IntoIterator<Item = Invalidator>) -> usize {
+    invalidators
+        .into_iter()
+        .map(|invalidator| invalidator.invalidate_with_reason(key))
+        .count()
+}
+
+impl Serialize for InvalidationMap {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_newtype_struct("InvalidationMap", &*self.lock().unwrap())
+    }
+}
+
+impl<'de> Deserialize<'de> for InvalidationMap {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct V;
+
+        impl<'de> Visitor<'de> for V {
+            type Value = InvalidationMap;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "an InvalidationMap")
+            }
+
+            fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                Ok(InvalidationMap {
+                    queue: ConcurrentQueue::unbounded(),
+                    map: Mutex::new(Deserialize::deserialize(deserializer)?),
+                })
+            }
+        }
+
+        deserializer.deserialize_newtype_struct("InvalidationMap", V)
+    }
+}
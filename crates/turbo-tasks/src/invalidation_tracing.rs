@@ -0,0 +1,154 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::{self, Display},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::SystemTime,
+};
+
+use crate::TaskId;
+
+/// How many recomputations are kept.
+const MAX_TRACES: usize = 1000;
+
+/// Chains are cut off after this many tasks, e. g. when tasks invalidate each
+/// other in a cycle.
+const MAX_CHAIN_LENGTH: usize = 64;
+
+/// Why a task was invalidated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InvalidationCause {
+    /// Invalidated from outside of turbo-tasks, e. g. by a file watcher. The
+    /// reason describes the change, e. g. the changed path.
+    External(Option<String>),
+    /// The execution of this task changed a value which the task read.
+    Task(TaskId),
+}
+
+/// A recomputation of a task, with the chain of invalidations which caused
+/// it. Recorded when invalidation tracing is enabled with
+/// [crate::TurboTasks::enable_invalidation_tracing].
+#[derive(Clone, Debug)]
+pub struct InvalidationTrace {
+    /// Increases with every recorded trace, so new traces can be told apart
+    /// from ones which were seen before.
+    pub id: usize,
+    pub time: SystemTime,
+    /// The external change which started the chain. Unknown when it was
+    /// invalidated without a reason, or the chain was cut off.
+    pub reason: Option<String>,
+    /// The descriptions of the invalidated tasks, from the one which was
+    /// affected by the change to the one which was recomputed.
+    pub tasks: Vec<String>,
+}
+
+impl Display for InvalidationTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.reason.as_deref().unwrap_or("unknown change"))?;
+        for task in &self.tasks {
+            write!(f, " → {task}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Records the causes of invalidations, and follows them back to the
+/// external change when the invalidated tasks are recomputed.
+#[derive(Default)]
+pub(crate) struct InvalidationTracer {
+    enabled: AtomicBool,
+    /// Only chains started by a change whose reason contains the filter are
+    /// recorded.
+    filter: Mutex<Option<String>>,
+    /// The causes of invalidated tasks which weren't recomputed yet. A task
+    /// keeps the cause which invalidated it first.
+    pending: Mutex<HashMap<TaskId, InvalidationCause>>,
+    /// The causes of the last recomputation of each task, to follow the
+    /// chains through the tasks.
+    executed: Mutex<HashMap<TaskId, InvalidationCause>>,
+    traces: Mutex<VecDeque<InvalidationTrace>>,
+    next_id: AtomicUsize,
+}
+
+impl InvalidationTracer {
+    pub fn enable(&self, filter: Option<String>) {
+        *self.filter.lock().unwrap() = filter;
+        self.enabled.store(true, Ordering::Release);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Acquire)
+    }
+
+    pub fn invalidated(&self, tasks: &[TaskId], cause: impl Fn() -> InvalidationCause) {
+        if !self.is_enabled() {
+            return;
+        }
+        let cause = cause();
+        let mut pending = self.pending.lock().unwrap();
+        for &task in tasks {
+            if cause != InvalidationCause::Task(task) {
+                pending.entry(task).or_insert_with(|| cause.clone());
+            }
+        }
+    }
+
+    /// Records the chain of a recomputation of `task`, if it was invalidated.
+    pub fn execution_started(&self, task: TaskId, describe: impl Fn(TaskId) -> String) {
+        if !self.is_enabled() {
+            return;
+        }
+        let cause = match self.pending.lock().unwrap().remove(&task) {
+            Some(cause) => cause,
+            None => return,
+        };
+        let mut chain = vec![task];
+        let reason = {
+            let mut executed = self.executed.lock().unwrap();
+            executed.insert(task, cause.clone());
+            let mut visited = HashSet::from([task]);
+            let mut cause = cause;
+            loop {
+                match cause {
+                    InvalidationCause::External(reason) => break reason,
+                    InvalidationCause::Task(parent) => {
+                        if chain.len() >= MAX_CHAIN_LENGTH || !visited.insert(parent) {
+                            break None;
+                        }
+                        chain.push(parent);
+                        match executed.get(&parent) {
+                            Some(parent_cause) => cause = parent_cause.clone(),
+                            None => break None,
+                        }
+                    }
+                }
+            }
+        };
+        if let Some(filter) = &*self.filter.lock().unwrap() {
+            if !reason
+                .as_deref()
+                .map_or(false, |reason| reason.contains(filter.as_str()))
+            {
+                return;
+            }
+        }
+
+        let trace = InvalidationTrace {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            time: SystemTime::now(),
+            reason,
+            tasks: chain.into_iter().rev().map(describe).collect(),
+        };
+        let mut traces = self.traces.lock().unwrap();
+        if traces.len() >= MAX_TRACES {
+            traces.pop_front();
+        }
+        traces.push_back(trace);
+    }
+
+    pub fn traces(&self) -> Vec<InvalidationTrace> {
+        self.traces.lock().unwrap().iter().cloned().collect()
+    }
+}
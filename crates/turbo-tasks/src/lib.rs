@@ -33,14 +33,24 @@
 #![feature(provide_any)]
 #![feature(new_uninit)]
 
+mod atom;
+#[cfg(feature = "auto_register")]
+mod auto_register;
 pub mod backend;
+mod cell_compression;
+mod cell_equality;
 mod collectibles;
 mod completion;
+mod concurrency;
 pub mod debug;
 mod display;
+mod duplicate_work;
 pub mod event;
+mod execution_listener;
 mod id;
 mod id_factory;
+mod invalidation;
+mod invalidation_tracing;
 mod join_iter_ext;
 mod magic_any;
 mod manager;
@@ -50,47 +60,86 @@ mod nothing;
 mod once_map;
 pub mod persisted_graph;
 pub mod primitives;
+mod priority;
 mod raw_vc;
 mod read_ref;
 pub mod registry;
+pub mod remote_cache;
+mod retry;
+mod root_updates;
 pub mod small_duration;
+mod sync;
 mod task_input;
+mod task_panic;
+pub mod test_utils;
 mod timed_future;
 pub mod trace;
 pub mod util;
 mod value;
+mod value_diagnostics;
 mod value_type;
+mod weak_vc;
+pub mod worker;
 
 pub use anyhow::{Error, Result};
+pub use atom::Atom;
+pub use cell_compression::{CellCompression, CompressibleValue};
+pub use cell_equality::CellEquality;
 pub use collectibles::CollectiblesSource;
 pub use completion::{Completion, CompletionVc, CompletionsVc};
+pub use concurrency::{with_concurrency_limit, ConcurrencyCategory, ConcurrencyLimits};
 pub use display::{ValueToString, ValueToStringVc};
+pub use duplicate_work::DuplicateWork;
+pub use execution_listener::{ExecutionListener, TaskExecution, TaskExecutionOutcome};
 pub use id::{
     with_task_id_mapping, without_task_id_mapping, FunctionId, IdMapping, TaskId, TraitTypeId,
     ValueTypeId,
 };
+pub use invalidation::{InvalidationKey, InvalidationMap};
+pub use invalidation_tracing::{InvalidationCause, InvalidationTrace};
 pub use join_iter_ext::{JoinIterExt, TryJoinIterExt};
 pub use manager::{
     dynamic_call, emit, get_invalidator, run_once, spawn_blocking, spawn_thread, trait_call,
     turbo_tasks, Invalidator, StatsType, TaskIdProvider, TurboTasks, TurboTasksApi,
     TurboTasksBackendApi, TurboTasksCallApi,
 };
-pub use native_function::{NativeFunction, NativeFunctionVc};
+pub use native_function::{NativeFunction, NativeFunctionArgument, NativeFunctionVc};
 pub use nothing::{Nothing, NothingVc};
-pub use raw_vc::{CellId, CollectiblesFuture, RawVc, ReadRawVcFuture, ResolveTypeError};
+pub use priority::{current_priority, with_priority, TaskPriority};
+pub use raw_vc::{CellId, CollectiblesFuture, RawVc, ReadNow, ReadRawVcFuture, ResolveTypeError};
 pub use read_ref::ReadRef;
+pub use retry::RetryPolicy;
+pub use root_updates::RootUpdates;
+pub use sync::{TaskMutex, TaskMutexGuard, TaskOnceMap};
 pub use task_input::{FromTaskInput, SharedReference, SharedValue, TaskInput};
 pub use turbo_tasks_macros::{function, value, value_impl, value_trait};
 pub use value::{TransientInstance, TransientValue, Value};
+pub use value_diagnostics::ConciseDebug;
 pub use value_type::{
     TraitMethod, TraitType, Typed, TypedForInput, ValueTraitVc, ValueType, ValueVc,
 };
+pub use weak_vc::WeakVc;
 
 #[doc(hidden)]
 pub mod macro_helpers {
+    #[cfg(feature = "auto_register")]
+    pub use ctor::ctor;
     pub use once_cell::sync::{Lazy, OnceCell};
 
-    pub use super::manager::find_cell_by_type;
+    pub use super::{__auto_register as auto_register, manager::find_cell_by_type};
+    #[cfg(feature = "auto_register")]
+    pub use super::{
+        __registration_name as registration_name,
+        auto_register::{submit as submit_registration, Registration, RegistrationName},
+    };
+}
+
+/// Discards registrations when the `auto_register` feature is disabled.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "auto_register"))]
+macro_rules! __auto_register {
+    ($registration:expr) => {};
 }
 
 pub mod test_helpers {
@@ -98,5 +147,7 @@ pub mod test_helpers {
 }
 
 pub fn register() {
+    #[cfg(feature = "auto_register")]
+    auto_register::auto_register();
     include!(concat!(env!("OUT_DIR"), "/register.rs"));
 }
@@ -1,14 +1,14 @@
 use std::{
     borrow::Cow,
     cell::RefCell,
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     future::Future,
     hash::Hash,
     panic::AssertUnwindSafe,
     pin::Pin,
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering},
-        Arc, Mutex, Weak,
+        Arc, Mutex, RwLock, Weak,
     },
     thread,
     time::{Duration, Instant},
@@ -18,19 +18,29 @@ use anyhow::{anyhow, Result};
 use futures::FutureExt;
 use nohash_hasher::BuildNoHashHasher;
 use serde::{de::Visitor, Deserialize, Serialize};
-use tokio::{runtime::Handle, select, task_local};
+use tokio::{runtime::Handle, select, sync::Semaphore, task_local};
 
 use crate::{
-    backend::{Backend, CellContent, PersistentTaskType, TransientTaskType},
+    backend::{
+        Backend, CellContent, PersistentTaskType, SnapshotOutput, TaskSnapshot, TrackedRead,
+        TransientTaskType,
+    },
+    concurrency::{ConcurrencyCategory, ConcurrencyLimits, ConcurrencySemaphores},
+    duplicate_work::{DuplicateWork, DuplicateWorkDetector},
     event::{Event, EventListener},
+    execution_listener::{ExecutionListener, TaskExecution, TaskExecutionOutcome},
     id::{BackendJobId, FunctionId, TraitTypeId},
     id_factory::IdFactory,
+    invalidation_tracing::{InvalidationCause, InvalidationTrace, InvalidationTracer},
+    priority::{current_priority, PriorityGate, TaskPriority},
     raw_vc::{CellId, RawVc},
+    registry,
     task_input::{SharedReference, TaskInput},
+    task_panic::{self, CaptureBacktrace},
     timed_future::{self, TimedFuture},
     trace::TraceRawVcs,
     util::FormatDuration,
-    Nothing, NothingVc, TaskId, ValueTraitVc, ValueTypeId,
+    Nothing, NothingVc, RootUpdates, TaskId, ValueTraitVc, ValueTypeId,
 };
 
 pub trait TurboTasksCallApi: Sync + Send {
@@ -56,6 +66,19 @@ pub trait TurboTasksCallApi: Sync + Send {
 pub trait TurboTasksApi: TurboTasksCallApi + Sync + Send {
     fn invalidate(&self, task: TaskId);
 
+    /// Disposes a root task, see [Backend::dispose_root_task].
+    fn dispose_root_task(&self, task: TaskId);
+
+    /// Changes when the id of the task is reused for another task, see
+    /// [IdFactory::generation].
+    fn task_id_generation(&self, task: TaskId) -> u32;
+
+    /// Like [TurboTasksApi::invalidate], but records `reason` as the change
+    /// which caused it when invalidation tracing is enabled.
+    fn invalidate_with_reason(&self, task: TaskId, _reason: &str) {
+        self.invalidate(task);
+    }
+
     /// Eagerly notifies all tasks that were scheduled for notifications via
     /// `schedule_notify_tasks_set()`
     fn notify_scheduled_tasks(&self);
@@ -108,6 +131,14 @@ pub trait TurboTasksApi: TurboTasksCallApi + Sync + Send {
 
     fn read_current_task_cell(&self, index: CellId) -> Result<CellContent>;
     fn update_current_task_cell(&self, index: CellId, content: CellContent);
+    /// Reports that an equal value was assigned to the cell, so it was kept.
+    fn keep_current_task_cell(&self, index: CellId);
+
+    /// Returns the semaphore limiting the concurrency of `category`, or `None`
+    /// when it's unlimited. See [crate::with_concurrency_limit].
+    fn concurrency_semaphore(&self, _category: ConcurrencyCategory) -> Option<Arc<Semaphore>> {
+        None
+    }
 }
 
 /// The type of stats reporting.
@@ -215,6 +246,11 @@ pub struct TurboTasks<B: Backend + 'static> {
     // locking overhead.
     enable_full_stats: AtomicBool,
     program_start: Instant,
+    priority_gate: Arc<PriorityGate>,
+    execution_listeners: RwLock<Vec<Arc<dyn ExecutionListener>>>,
+    concurrency_semaphores: ConcurrencySemaphores,
+    invalidation_tracer: InvalidationTracer,
+    duplicate_work_detector: DuplicateWorkDetector,
 }
 
 // TODO implement our own thread pool and make these thread locals instead
@@ -230,6 +266,10 @@ task_local! {
     /// These tasks will be invalidated when the execution finishes
     /// or before reading a cell value
     static TASKS_TO_NOTIFY: RefCell<Vec<TaskId>>;
+
+    /// Reads of the current task execution, which are passed to the backend
+    /// in one batch when the execution has finished
+    static READS_TO_TRACK: RefCell<Vec<TrackedRead>>;
 }
 
 impl<B: Backend> TurboTasks<B> {
@@ -238,7 +278,16 @@ impl<B: Backend> TurboTasks<B> {
     // that should be safe as long tasks can't outlife turbo task
     // so we probably want to make sure that all tasks are joined
     // when trying to drop turbo tasks
-    pub fn new(mut backend: B) -> Arc<Self> {
+    pub fn new(backend: B) -> Arc<Self> {
+        Self::with_concurrency_limits(backend, ConcurrencyLimits::default())
+    }
+
+    /// Creates an instance which limits the concurrency of categories of work
+    /// wrapped in [crate::with_concurrency_limit].
+    pub fn with_concurrency_limits(mut backend: B, limits: ConcurrencyLimits) -> Arc<Self> {
+        #[cfg(feature = "auto_register")]
+        crate::auto_register::auto_register();
+        task_panic::install_hook();
         let task_id_factory = IdFactory::new();
         backend.initialize(&task_id_factory);
         let this = Arc::new_cyclic(|this| Self {
@@ -257,6 +306,11 @@ impl<B: Backend> TurboTasks<B> {
             event_background: Event::new(|| "TurboTasks::event_background".to_string()),
             enable_full_stats: AtomicBool::new(false),
             program_start: Instant::now(),
+            priority_gate: Arc::new(PriorityGate::new()),
+            execution_listeners: Default::default(),
+            concurrency_semaphores: ConcurrencySemaphores::new(&limits),
+            invalidation_tracer: Default::default(),
+            duplicate_work_detector: Default::default(),
         });
         this.backend.startup(&*this);
         this
@@ -266,6 +320,55 @@ impl<B: Backend> TurboTasks<B> {
         self.this.upgrade().unwrap()
     }
 
+    /// Notifies `listener` about all following task executions.
+    pub fn add_execution_listener(&self, listener: Arc<dyn ExecutionListener>) {
+        self.execution_listeners.write().unwrap().push(listener);
+    }
+
+    fn notify_execution_listeners(&self, notify: impl Fn(&dyn ExecutionListener)) {
+        for listener in self.execution_listeners.read().unwrap().iter() {
+            notify(&**listener);
+        }
+    }
+
+    /// Records the chains of invalidations which cause recomputations from
+    /// now on. With a `filter`, only the chains started by changes whose
+    /// reason contains it are recorded, e. g. a part of a file path.
+    pub fn enable_invalidation_tracing(&self, filter: Option<String>) {
+        self.invalidation_tracer.enable(filter);
+    }
+
+    /// The most recent recomputations recorded since
+    /// [TurboTasks::enable_invalidation_tracing], oldest first.
+    pub fn invalidation_traces(&self) -> Vec<InvalidationTrace> {
+        self.invalidation_tracer.traces()
+    }
+
+    /// Reports functions which are executed in separate tasks for arguments
+    /// which are equal once the Vcs in them are resolved, from now on. See
+    /// [TurboTasks::duplicate_work].
+    pub fn enable_duplicate_work_detection(&self) {
+        self.duplicate_work_detector.enable();
+    }
+
+    /// The functions found since
+    /// [TurboTasks::enable_duplicate_work_detection], in the order they were
+    /// found.
+    pub fn duplicate_work(&self) -> Vec<DuplicateWork> {
+        self.duplicate_work_detector.reports()
+    }
+
+    /// Invalidates `tasks`, recording the current task as the cause when
+    /// called during a task execution.
+    fn invalidate_tasks(&self, tasks: Vec<TaskId>) {
+        self.invalidation_tracer
+            .invalidated(&tasks, || match CURRENT_TASK_ID.try_with(|id| *id) {
+                Ok(id) => InvalidationCause::Task(id),
+                Err(_) => InvalidationCause::External(None),
+            });
+        self.backend.invalidate_tasks(tasks, self);
+    }
+
     /// Creates a new root task
     pub fn spawn_root_task(
         &self,
@@ -314,20 +417,69 @@ impl<B: Backend> TurboTasks<B> {
         Ok(rx.await?)
     }
 
+    /// Creates a root task which executes `func` and is executed again
+    /// whenever something it has read changes. The result of every execution
+    /// is sent to the returned [RootUpdates], so long-lived consumers don't
+    /// need to poll for changes.
+    ///
+    /// Once the [RootUpdates] is dropped, the root task is disposed, so it's
+    /// not executed again and the tasks it has called are no longer kept
+    /// active.
+    pub fn run_once_with_updates<T, F, Fut>(&self, func: F) -> RootUpdates<T>
+    where
+        T: TraceRawVcs + Send + 'static,
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let func = Arc::new(func);
+        let task = self.spawn_root_task(move || {
+            let tx = tx.clone();
+            let func = func.clone();
+            Box::pin(async move {
+                if !tx.is_closed() {
+                    // The receiver might be dropped concurrently, which is fine.
+                    let _ = tx.send(func().await);
+                }
+                Ok(NothingVc::new().into())
+            })
+        });
+        RootUpdates {
+            task,
+            receiver: rx,
+            turbo_tasks: self.pin(),
+        }
+    }
+
     /// Call a native function with arguments.
     /// All inputs must be resolved.
     pub(crate) fn native_call(&self, func: FunctionId, inputs: Vec<TaskInput>) -> RawVc {
-        RawVc::TaskOutput(self.backend.get_or_create_persistent_task(
+        let checked_inputs = self
+            .duplicate_work_detector
+            .is_enabled()
+            .then(|| inputs.clone());
+        let task = self.backend.get_or_create_persistent_task(
             PersistentTaskType::Native(func, inputs),
             current_task("turbo_function calls"),
             self,
-        ))
+        );
+        if let Some(inputs) = checked_inputs {
+            if self.duplicate_work_detector.should_check(task) {
+                self.schedule_foreground_job(move |this| async move {
+                    this.duplicate_work_detector.check(func, task, inputs).await;
+                });
+            }
+        }
+        RawVc::TaskOutput(task)
     }
 
     /// Calls a native function with arguments. Resolves arguments when needed
-    /// with a wrapper [Task].
+    /// with a wrapper [Task], including the Vcs in values for functions with
+    /// `resolve_values`.
     pub fn dynamic_call(&self, func: FunctionId, inputs: Vec<TaskInput>) -> RawVc {
-        if inputs.iter().all(|i| i.is_resolved() && !i.is_nothing()) {
+        let unresolved_values = registry::get_function(func).resolve_values
+            && inputs.iter().any(|i| i.has_unresolved_values());
+        if !unresolved_values && inputs.iter().all(|i| i.is_resolved() && !i.is_nothing()) {
             self.native_call(func, inputs)
         } else {
             RawVc::TaskOutput(self.backend.get_or_create_persistent_task(
@@ -361,22 +513,47 @@ impl<B: Backend> TurboTasks<B> {
         #[cfg(feature = "tokio_tracing")]
         let description = self.backend.get_task_description(task_id);
 
+        // Tasks inherit the priority of the task or root invocation scheduling
+        // them.
+        let priority = current_priority();
+        if priority == TaskPriority::Background {
+            self.priority_gate.register(task_id);
+        }
+        let scheduled_by = CURRENT_TASK_ID.try_with(|id| *id).ok();
         let this = self.pin();
         let future = async move {
+            let mut priority = priority;
             loop {
+                if priority == TaskPriority::Background {
+                    // A boosted task runs with the priority of the work waiting
+                    // for it, which is passed on to the tasks it waits for.
+                    priority = this.priority_gate.wait_for_interactive(task_id).await;
+                }
                 if this.stopped.load(Ordering::Acquire) {
                     break;
                 }
                 if let Some(execution) = this.backend.try_start_task_execution(task_id, &*this) {
+                    let function = execution.function;
+                    this.invalidation_tracer
+                        .execution_started(task_id, |task| this.backend.get_task_description(task));
+                    this.notify_execution_listeners(|listener| {
+                        listener.execution_started(task_id, function, scheduled_by)
+                    });
                     // Setup thread locals
                     let (result, duration, instant) = CELL_COUNTERS
                         .scope(Default::default(), async {
-                            let (result, duration, instant) =
-                                TimedFuture::new(AssertUnwindSafe(execution.future).catch_unwind())
-                                    .await;
+                            let (result, duration, instant) = TimedFuture::new(
+                                AssertUnwindSafe(CaptureBacktrace::new(
+                                    this.priority_gate.track(priority, execution.future),
+                                ))
+                                .catch_unwind(),
+                            )
+                            .await;
                             (result, duration, instant)
                         })
                         .await;
+                    let reads = READS_TO_TRACK.with(|reads| reads.take());
+                    this.backend.track_reads(task_id, reads, &*this);
                     if cfg!(feature = "log_function_stats") && duration.as_millis() > 1000 {
                         println!(
                             "{} took {}",
@@ -384,14 +561,26 @@ impl<B: Backend> TurboTasks<B> {
                             FormatDuration(duration)
                         )
                     }
-                    let result = result.map_err(|any| match any.downcast::<String>() {
-                        Ok(owned) => Some(Cow::Owned(*owned)),
-                        Err(any) => match any.downcast::<&'static str>() {
-                            Ok(str) => Some(Cow::Borrowed(*str)),
-                            Err(_) => None,
-                        },
+                    let result = result.map_err(|payload| {
+                        Some(task_panic::describe_panic(
+                            payload,
+                            &this.backend.get_task_description(task_id),
+                        ))
                     });
+                    let outcome = match &result {
+                        Ok(Ok(_)) => TaskExecutionOutcome::Success,
+                        Ok(Err(_)) => TaskExecutionOutcome::Error,
+                        Err(_) => TaskExecutionOutcome::Panic,
+                    };
                     this.backend.task_execution_result(task_id, result, &*this);
+                    this.notify_execution_listeners(|listener| {
+                        listener.execution_finished(&TaskExecution {
+                            task: task_id,
+                            function,
+                            duration,
+                            outcome,
+                        })
+                    });
                     this.notify_scheduled_tasks_internal();
                     let reexecute = this
                         .backend
@@ -413,10 +602,14 @@ impl<B: Backend> TurboTasks<B> {
                 task_id,
                 TASKS_TO_NOTIFY.scope(
                     Default::default(),
-                    self.backend.execution_scope(task_id, future),
+                    READS_TO_TRACK.scope(
+                        Default::default(),
+                        self.backend.execution_scope(task_id, future),
+                    ),
                 ),
             ),
         );
+        let future = self.priority_gate.track(priority, future);
 
         #[cfg(feature = "tokio_tracing")]
         tokio::task::Builder::new()
@@ -427,6 +620,14 @@ impl<B: Backend> TurboTasks<B> {
         tokio::task::spawn(future);
     }
 
+    /// Boosts `task` when the current work has to wait for it, so it isn't
+    /// held back as background work.
+    fn boost_if_waiting<T>(&self, task: TaskId, result: &Result<Result<T, EventListener>>) {
+        if let Ok(Err(_)) = result {
+            self.priority_gate.boost(task, current_priority());
+        }
+    }
+
     fn begin_primary_job(&self) {
         if self
             .currently_scheduled_tasks
@@ -622,13 +823,139 @@ impl<B: Backend> TurboTasks<B> {
             if tasks.is_empty() {
                 return;
             }
-            self.backend.invalidate_tasks(tasks, self);
+            self.invalidate_tasks(tasks);
         });
     }
 
     pub fn backend(&self) -> &B {
         &self.backend
     }
+
+    /// Snapshots the cells of the `roots` and all their (transitive) children.
+    /// Transient tasks and cells containing values which are not serializable
+    /// are skipped. Tasks with skipped cells are snapshotted without output, so
+    /// they are executed again when restored. Parents are returned before
+    /// their children.
+    pub fn snapshot_tasks(
+        &self,
+        roots: impl IntoIterator<Item = TaskId>,
+    ) -> Vec<(TaskId, TaskSnapshot)> {
+        let mut visited = HashSet::new();
+        let mut queue = roots.into_iter().collect::<VecDeque<_>>();
+        let mut snapshots = Vec::new();
+        while let Some(task) = queue.pop_front() {
+            if !visited.insert(task) {
+                continue;
+            }
+            if let Some(mut snapshot) = self.backend.snapshot_task(task, self) {
+                let cells = snapshot.cells.len();
+                snapshot.cells.retain(|(_, content)| match &content.0 {
+                    Some(SharedReference(Some(ty), _)) => {
+                        registry::get_value_type(*ty).is_serializable()
+                            && !registry::is_redacted(*ty)
+                    }
+                    Some(_) => false,
+                    None => true,
+                });
+                if snapshot.cells.len() != cells {
+                    snapshot.output = None;
+                }
+                queue.extend(snapshot.children.iter().copied());
+                snapshots.push((task, snapshot));
+            }
+        }
+        snapshots
+    }
+
+    /// Restores tasks snapshotted by [TurboTasks::snapshot_tasks]. Snapshotted
+    /// root tasks become children of `parent_task`. Tasks are restored after
+    /// the tasks they reference, so their outputs and dependencies can be
+    /// mapped to the restored tasks. Tasks whose output or dependencies can't
+    /// be mapped, e. g. because of a cycle, are restored without output and
+    /// executed again. Returns a mapping from the snapshotted task ids to the
+    /// restored task ids.
+    pub fn restore_tasks(
+        &self,
+        snapshots: Vec<(TaskId, TaskSnapshot)>,
+        parent_task: TaskId,
+    ) -> HashMap<TaskId, TaskId> {
+        fn visit(
+            task: TaskId,
+            snapshots: &HashMap<TaskId, TaskSnapshot>,
+            visited: &mut HashSet<TaskId>,
+            order: &mut Vec<TaskId>,
+        ) {
+            if !visited.insert(task) {
+                return;
+            }
+            if let Some(snapshot) = snapshots.get(&task) {
+                let output = match snapshot.output {
+                    Some(SnapshotOutput::Link(raw_vc)) => Some(raw_vc.get_task_id()),
+                    _ => None,
+                };
+                let references = snapshot.children.iter().copied().chain(output).chain(
+                    snapshot
+                        .dependencies
+                        .iter()
+                        .map(|raw_vc| raw_vc.get_task_id()),
+                );
+                for reference in references {
+                    visit(reference, snapshots, visited, order);
+                }
+                order.push(task);
+            }
+        }
+
+        let children = snapshots
+            .iter()
+            .flat_map(|(_, snapshot)| snapshot.children.iter().copied())
+            .collect::<HashSet<_>>();
+        let tasks = snapshots.iter().map(|(task, _)| *task).collect::<Vec<_>>();
+        let mut snapshots = snapshots.into_iter().collect::<HashMap<_, _>>();
+        let mut visited = HashSet::new();
+        let mut order = Vec::new();
+        for task in tasks {
+            visit(task, &snapshots, &mut visited, &mut order);
+        }
+
+        let mut restored = HashMap::new();
+        for task in order {
+            let mut snapshot = snapshots.remove(&task).unwrap();
+            let map = |raw_vc: RawVc| match raw_vc {
+                RawVc::TaskOutput(task) => restored.get(&task).map(|&task| RawVc::TaskOutput(task)),
+                RawVc::TaskCell(task, index) => restored
+                    .get(&task)
+                    .map(|&task| RawVc::TaskCell(task, index)),
+            };
+            let output = match snapshot.output {
+                Some(SnapshotOutput::Link(raw_vc)) => map(raw_vc).map(SnapshotOutput::Link),
+                output => output,
+            };
+            let dependencies = snapshot
+                .dependencies
+                .iter()
+                .map(|&raw_vc| map(raw_vc))
+                .collect::<Option<Vec<_>>>();
+            (snapshot.output, snapshot.dependencies) = match (output, dependencies) {
+                (Some(output), Some(dependencies)) => (Some(output), dependencies),
+                _ => (None, Vec::new()),
+            };
+            snapshot.children = snapshot
+                .children
+                .iter()
+                .filter_map(|child| restored.get(child).copied())
+                .collect();
+            let parent = if children.contains(&task) {
+                None
+            } else {
+                Some(parent_task)
+            };
+            if let Some(new_task) = self.backend.restore_task(snapshot, parent, self) {
+                restored.insert(task, new_task);
+            }
+        }
+        restored
+    }
 }
 
 impl<B: Backend> TurboTasksCallApi for TurboTasks<B> {
@@ -675,6 +1002,33 @@ impl<B: Backend> TurboTasksCallApi for TurboTasks<B> {
 
 impl<B: Backend> TurboTasksApi for TurboTasks<B> {
     fn invalidate(&self, task: TaskId) {
+        // The backend is not used after it was stopped.
+        if self.stopped.load(Ordering::Acquire) {
+            return;
+        }
+        self.invalidation_tracer
+            .invalidated(&[task], || InvalidationCause::External(None));
+        self.backend.invalidate_task(task, self);
+    }
+
+    fn dispose_root_task(&self, task: TaskId) {
+        if self.stopped.load(Ordering::Acquire) {
+            return;
+        }
+        self.backend.dispose_root_task(task, self);
+    }
+
+    fn task_id_generation(&self, task: TaskId) -> u32 {
+        self.task_id_factory.generation(&task)
+    }
+
+    fn invalidate_with_reason(&self, task: TaskId, reason: &str) {
+        if self.stopped.load(Ordering::Acquire) {
+            return;
+        }
+        self.invalidation_tracer.invalidated(&[task], || {
+            InvalidationCause::External(Some(reason.to_string()))
+        });
         self.backend.invalidate_task(task, self);
     }
 
@@ -684,7 +1038,7 @@ impl<B: Backend> TurboTasksApi for TurboTasks<B> {
             if tasks.is_empty() {
                 return;
             }
-            self.backend.invalidate_tasks(tasks, self);
+            self.invalidate_tasks(tasks);
         });
     }
 
@@ -693,12 +1047,25 @@ impl<B: Backend> TurboTasksApi for TurboTasks<B> {
         task: TaskId,
         strongly_consistent: bool,
     ) -> Result<Result<RawVc, EventListener>> {
-        self.backend.try_read_task_output(
-            task,
-            current_task("reading Vcs"),
-            strongly_consistent,
-            self,
-        )
+        let reader = current_task("reading Vcs");
+        let result = READS_TO_TRACK.try_with(|reads| {
+            Ok(self
+                .backend
+                .try_read_task_output_batched(task, reader, strongly_consistent, self)?
+                .map(|(vc, read)| {
+                    reads.borrow_mut().push(read);
+                    vc
+                }))
+        });
+        let result = match result {
+            Ok(result) => result,
+            // Outside of a task execution there is no batch to add the read to
+            Err(_) => self
+                .backend
+                .try_read_task_output(task, reader, strongly_consistent, self),
+        };
+        self.boost_if_waiting(task, &result);
+        result
     }
 
     fn try_read_task_output_untracked(
@@ -706,8 +1073,11 @@ impl<B: Backend> TurboTasksApi for TurboTasks<B> {
         task: TaskId,
         strongly_consistent: bool,
     ) -> Result<Result<RawVc, EventListener>> {
-        self.backend
-            .try_read_task_output_untracked(task, strongly_consistent, self)
+        let result = self
+            .backend
+            .try_read_task_output_untracked(task, strongly_consistent, self);
+        self.boost_if_waiting(task, &result);
+        result
     }
 
     fn try_read_task_cell(
@@ -715,8 +1085,23 @@ impl<B: Backend> TurboTasksApi for TurboTasks<B> {
         task: TaskId,
         index: CellId,
     ) -> Result<Result<CellContent, EventListener>> {
-        self.backend
-            .try_read_task_cell(task, index, current_task("reading Vcs"), self)
+        let reader = current_task("reading Vcs");
+        let result = READS_TO_TRACK.try_with(|reads| {
+            Ok(self
+                .backend
+                .try_read_task_cell_batched(task, index, reader, self)?
+                .map(|(content, read)| {
+                    reads.borrow_mut().push(read);
+                    content
+                }))
+        });
+        let result = match result {
+            Ok(result) => result,
+            // Outside of a task execution there is no batch to add the read to
+            Err(_) => self.backend.try_read_task_cell(task, index, reader, self),
+        };
+        self.boost_if_waiting(task, &result);
+        result
     }
 
     fn try_read_task_cell_untracked(
@@ -724,7 +1109,9 @@ impl<B: Backend> TurboTasksApi for TurboTasks<B> {
         task: TaskId,
         index: CellId,
     ) -> Result<Result<CellContent, EventListener>> {
-        self.backend.try_read_task_cell_untracked(task, index, self)
+        let result = self.backend.try_read_task_cell_untracked(task, index, self);
+        self.boost_if_waiting(task, &result);
+        result
     }
 
     fn try_read_own_task_cell_untracked(
@@ -791,6 +1178,15 @@ impl<B: Backend> TurboTasksApi for TurboTasks<B> {
             self,
         );
     }
+
+    fn keep_current_task_cell(&self, index: CellId) {
+        self.backend
+            .keep_task_cell(current_task("cellting turbo_tasks values"), index);
+    }
+
+    fn concurrency_semaphore(&self, category: ConcurrencyCategory) -> Option<Arc<Semaphore>> {
+        self.concurrency_semaphores.get(category)
+    }
 }
 
 impl<B: Backend> TurboTasksBackendApi for TurboTasks<B> {
@@ -837,7 +1233,7 @@ impl<B: Backend> TurboTasksBackendApi for TurboTasks<B> {
             list.extend(tasks.iter());
         });
         if result.is_err() {
-            self.backend.invalidate_tasks(tasks.to_vec(), self);
+            self.invalidate_tasks(tasks.to_vec());
         }
     }
 
@@ -849,8 +1245,7 @@ impl<B: Backend> TurboTasksBackendApi for TurboTasks<B> {
             list.extend(tasks.iter());
         });
         if result.is_err() {
-            self.backend
-                .invalidate_tasks(tasks.iter().copied().collect(), self);
+            self.invalidate_tasks(tasks.iter().copied().collect());
         };
     }
 
@@ -888,6 +1283,10 @@ impl<B: Backend> TaskIdProvider for TurboTasks<B> {
     }
 }
 
+pub(crate) fn try_current_task() -> Option<TaskId> {
+    CURRENT_TASK_ID.try_with(|id| *id).ok()
+}
+
 fn current_task(from: &str) -> TaskId {
     match CURRENT_TASK_ID.try_with(|id| *id) {
         Ok(id) => id,
@@ -900,6 +1299,9 @@ fn current_task(from: &str) -> TaskId {
 
 pub struct Invalidator {
     task: TaskId,
+    /// The generation of the id of the task, so a task which was garbage
+    /// collected is not confused with a later task with the same id.
+    generation: u32,
     turbo_tasks: Weak<dyn TurboTasksApi>,
     handle: Handle,
 }
@@ -922,12 +1324,32 @@ impl Invalidator {
     pub fn invalidate(self) {
         let Invalidator {
             task,
+            generation,
+            turbo_tasks,
+            handle,
+        } = self;
+        let _ = handle.enter();
+        if let Some(turbo_tasks) = turbo_tasks.upgrade() {
+            if turbo_tasks.task_id_generation(task) == generation {
+                turbo_tasks.invalidate(task);
+            }
+        }
+    }
+
+    /// Like [Invalidator::invalidate], but records `reason` as the change
+    /// which caused it, e. g. the changed path, for invalidation tracing.
+    pub fn invalidate_with_reason(self, reason: &str) {
+        let Invalidator {
+            task,
+            generation,
             turbo_tasks,
             handle,
         } = self;
         let _ = handle.enter();
         if let Some(turbo_tasks) = turbo_tasks.upgrade() {
-            turbo_tasks.invalidate(task);
+            if turbo_tasks.task_id_generation(task) == generation {
+                turbo_tasks.invalidate_with_reason(task, reason);
+            }
         }
     }
 }
@@ -965,8 +1387,10 @@ impl<'de> Deserialize<'de> for Invalidator {
             where
                 D: serde::Deserializer<'de>,
             {
+                let task = TaskId::deserialize(deserializer)?;
                 Ok(Invalidator {
-                    task: TaskId::deserialize(deserializer)?,
+                    task,
+                    generation: with_turbo_tasks(|tt| tt.task_id_generation(task)),
                     turbo_tasks: weak_turbo_tasks(),
                     handle: tokio::runtime::Handle::current(),
                 })
@@ -1042,13 +1466,19 @@ pub fn current_task_for_testing() -> TaskId {
 /// based on external events.
 pub fn get_invalidator() -> Invalidator {
     let handle = Handle::current();
+    let task = current_task("turbo_tasks::get_invalidator()");
     Invalidator {
-        task: current_task("turbo_tasks::get_invalidator()"),
+        task,
+        generation: with_turbo_tasks(|tt| tt.task_id_generation(task)),
         turbo_tasks: weak_turbo_tasks(),
         handle,
     }
 }
 
+/// Emits a collectible from the current task. Collectibles are auxiliary
+/// values, e. g. Issues, which are tagged with the value trait `T` and bubble
+/// up the call graph, so parents can collect them from all transitive
+/// children with [CollectiblesSource](crate::CollectiblesSource).
 pub fn emit<T: ValueTraitVc>(collectible: T) {
     with_turbo_tasks(|tt| tt.emit_collectible(T::get_trait_type_id(), collectible.into()))
 }
@@ -1173,18 +1603,49 @@ impl CurrentCellRef {
         }
     }
 
+    /// Keeps the content of the cell when it's equal to `new_content`, so the
+    /// tasks which read it are not invalidated. The comparison can be
+    /// disabled per value type, see [crate::CellEquality].
     pub fn compare_and_update_shared<T: PartialEq + Send + Sync + 'static>(&self, new_content: T) {
+        if registry::is_cell_equality_disabled(self.index.type_id) {
+            return self.update_shared_unconditionally(new_content);
+        }
+        self.compare_and_update_shared_with(new_content, |old, new| old == new);
+    }
+
+    /// Like [CurrentCellRef::compare_and_update_shared] for value types
+    /// without a [PartialEq] or with `cell = "new"`, which only compares when
+    /// a [crate::CellEquality] is registered for the value type.
+    pub fn update_shared<T: Send + Sync + 'static>(&self, new_content: T) {
+        match registry::get_cell_equality(self.index.type_id) {
+            Some(equality) => {
+                self.compare_and_update_shared_with(new_content, |old, new| equality.eq(old, new))
+            }
+            None => self.update_shared_unconditionally(new_content),
+        }
+    }
+
+    fn compare_and_update_shared_with<T: Send + Sync + 'static>(
+        &self,
+        new_content: T,
+        eq: impl FnOnce(&T, &T) -> bool,
+    ) {
+        let mut unchanged = false;
         self.conditional_update_shared(|old_content| {
             if let Some(old_content) = old_content {
-                if PartialEq::eq(&new_content, old_content) {
+                if eq(old_content, &new_content) {
+                    unchanged = true;
                     return None;
                 }
             }
             Some(new_content)
         });
+        if unchanged {
+            turbo_tasks().keep_current_task_cell(self.index);
+        }
     }
 
-    pub fn update_shared<T: Send + Sync + 'static>(&self, new_content: T) {
+    fn update_shared_unconditionally<T: Send + Sync + 'static>(&self, new_content: T) {
         let tt = turbo_tasks();
         tt.update_current_task_cell(
             self.index,
@@ -32,12 +32,31 @@ pub struct NativeFunction {
     /// A counter that tracks total executions of that function
     #[turbo_tasks(debug_ignore, trace_ignore)]
     pub executed_count: AtomicUsize,
+    /// The arguments the function expects, for error messages.
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    pub arguments: Vec<NativeFunctionArgument>,
+    /// The source file declaring the function, for reporting purposes.
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    pub file: Option<&'static str>,
+    /// Whether the Vcs in the values of the arguments are resolved before the
+    /// function is called, so calls with equal values share a task. Set by
+    /// `#[turbo_tasks::function(resolve_values)]`.
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    pub resolve_values: bool,
+}
+
+/// An argument of a [NativeFunction] as declared in source code.
+#[derive(Debug, Clone)]
+pub struct NativeFunctionArgument {
+    pub name: &'static str,
+    pub ty: &'static str,
 }
 
 impl Debug for NativeFunction {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("NativeFunction")
             .field("name", &self.name)
+            .field("arguments", &self.arguments)
             .finish_non_exhaustive()
     }
 }
@@ -51,9 +70,38 @@ impl NativeFunction {
             name,
             bind_fn: Box::new(bind_fn),
             executed_count: AtomicUsize::new(0),
+            arguments: Vec::new(),
+            file: None,
+            resolve_values: false,
         }
     }
 
+    pub fn with_arguments(mut self, arguments: Vec<NativeFunctionArgument>) -> Self {
+        self.arguments = arguments;
+        self
+    }
+
+    pub fn with_file(mut self, file: &'static str) -> Self {
+        self.file = Some(file);
+        self
+    }
+
+    pub fn with_resolve_values(mut self, resolve_values: bool) -> Self {
+        self.resolve_values = resolve_values;
+        self
+    }
+
+    /// Returns the signature of the function, e. g. `resolve(self: AssetVc,
+    /// request: RequestVc)`.
+    pub fn signature(&self) -> String {
+        let arguments = self
+            .arguments
+            .iter()
+            .map(|argument| format!("{}: {}", argument.name, argument.ty))
+            .collect::<Vec<_>>();
+        format!("{}({})", self.name, arguments.join(", "))
+    }
+
     /// Creates a functor for execution from a fixed set of inputs.
     pub fn bind(&'static self, inputs: &Vec<TaskInput>) -> NativeTaskFn {
         match (self.bind_fn)(inputs) {
@@ -68,7 +116,9 @@ impl NativeFunction {
                 r
             }),
             Err(err) => {
-                let err = SharedError::new(err);
+                let err = SharedError::new(
+                    err.context(format!("invalid arguments for {}", self.signature())),
+                );
                 Box::new(move || {
                     let err = err.clone();
                     Box::pin(async { Err(err.into()) })
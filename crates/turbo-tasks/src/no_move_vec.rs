@@ -1,4 +1,5 @@
 use std::{
+    mem::size_of,
     ptr::null_mut,
     slice::from_raw_parts_mut,
     sync::{
@@ -155,8 +156,9 @@ impl<T, const INITIAL_CAPACITY_BITS: u32> NoMoveVec<T, INITIAL_CAPACITY_BITS> {
     pub unsafe fn insert(&self, idx: usize, value: T) -> &T {
         let bucket_idx = get_bucket_index::<INITIAL_CAPACITY_BITS>(idx);
         let bucket = unsafe { self.buckets.get_unchecked(bucket_idx as usize) };
-        // SAFETY: This is safe to be relaxed as the bucket will never become null
-        // again. We perform a acquire load when it's null.
+        // SAFETY: This is safe to be relaxed as the bucket only becomes null
+        // again in `shrink`, which must not run concurrently. We perform a
+        // acquire load when it's null.
         let mut bucket_ptr = bucket.0.load(Ordering::Relaxed);
         if bucket_ptr.is_null() {
             bucket_ptr = bucket.0.load(Ordering::Acquire);
@@ -206,6 +208,56 @@ impl<T, const INITIAL_CAPACITY_BITS: u32> NoMoveVec<T, INITIAL_CAPACITY_BITS> {
         // To sync with any acquire load of the bucket ptr
         bucket.0.store(bucket_ptr, Ordering::Release);
     }
+
+    /// The number of items which fit into the allocated buckets.
+    pub fn capacity(&self) -> usize {
+        self.allocated_buckets()
+            .map(|(bucket_index, _)| get_bucket_size::<INITIAL_CAPACITY_BITS>(bucket_index))
+            .sum()
+    }
+
+    /// The number of bytes used by the allocated buckets.
+    pub fn allocated_bytes(&self) -> usize {
+        self.capacity() * size_of::<COption<T>>()
+    }
+
+    /// Frees all buckets which only contain removed items, except for the
+    /// first one. Items inserted later allocate the bucket again.
+    ///
+    /// Returns the number of bytes freed.
+    ///
+    /// # Safety
+    /// There must not be a concurrent operation on this vec and no reference
+    /// returned by it must be alive.
+    pub unsafe fn shrink(&self) -> usize {
+        let mut freed = 0;
+        for (bucket_index, bucket_ptr) in self
+            .allocated_buckets()
+            .filter(|(bucket_index, _)| *bucket_index != 0)
+        {
+            let bucket_size = get_bucket_size::<INITIAL_CAPACITY_BITS>(bucket_index);
+            let slice = unsafe { from_raw_parts_mut(bucket_ptr, bucket_size) };
+            if slice.iter().all(|item| item.as_option_ref().is_none()) {
+                let bucket = &self.buckets[bucket_index as usize];
+                let _lock = bucket.1.lock();
+                bucket.0.store(null_mut(), Ordering::Release);
+                drop(unsafe { Box::from_raw(slice) });
+                freed += bucket_size * size_of::<COption<T>>();
+            }
+        }
+        freed
+    }
+
+    fn allocated_buckets(&self) -> impl Iterator<Item = (u32, *mut COption<T>)> + '_ {
+        self.buckets
+            .iter()
+            .take((usize::BITS + 1 - INITIAL_CAPACITY_BITS) as usize)
+            .enumerate()
+            .filter_map(|(bucket_index, (bucket, _))| {
+                let bucket_ptr = bucket.load(Ordering::Acquire);
+                (!bucket_ptr.is_null()).then_some((bucket_index as u32, bucket_ptr))
+            })
+    }
 }
 
 impl<T: Default, const INITIAL_CAPACITY_BITS: u32> NoMoveVec<T, INITIAL_CAPACITY_BITS> {
@@ -219,8 +271,9 @@ impl<T: Default, const INITIAL_CAPACITY_BITS: u32> NoMoveVec<T, INITIAL_CAPACITY
     pub fn get_init_default(&self, idx: usize) -> &T {
         let bucket_idx = get_bucket_index::<INITIAL_CAPACITY_BITS>(idx);
         let bucket = unsafe { self.buckets.get_unchecked(bucket_idx as usize) };
-        // SAFETY: This is safe to be relaxed as the bucket will never become null
-        // again. We perform a acquire load when it's null.
+        // SAFETY: This is safe to be relaxed as the bucket only becomes null
+        // again in `shrink`, which must not run concurrently. We perform a
+        // acquire load when it's null.
         let mut bucket_ptr = bucket.0.load(Ordering::Relaxed);
         if bucket_ptr.is_null() {
             bucket_ptr = bucket.0.load(Ordering::Acquire);
@@ -271,7 +324,9 @@ impl<T, const INITIAL_CAPACITY_BITS: u32> Drop for NoMoveVec<T, INITIAL_CAPACITY
 
 #[cfg(test)]
 mod tests {
-    use super::NoMoveVec;
+    use std::mem::size_of;
+
+    use super::{COption, NoMoveVec};
 
     #[test]
     fn basic_operations() {
@@ -302,4 +357,40 @@ mod tests {
         assert_eq!(v.get(1000000), Some(&(0, 0)));
         assert_eq!(v.get(10000), None);
     }
+
+    #[test]
+    fn shrink() {
+        let v = NoMoveVec::<usize, 2>::new();
+        for i in 0..100 {
+            unsafe {
+                v.insert(i, i);
+            }
+        }
+        let capacity = v.capacity();
+        assert!(capacity >= 100);
+
+        for i in 4..100 {
+            unsafe {
+                v.remove(i);
+            }
+        }
+        unsafe {
+            v.insert(70, 70);
+        }
+        let freed = unsafe { v.shrink() };
+        assert!(freed > 0);
+        assert!(v.capacity() < capacity);
+        assert_eq!(
+            v.allocated_bytes(),
+            capacity * size_of::<COption<usize>>() - freed
+        );
+
+        assert_eq!(v.get(3), Some(&3));
+        assert_eq!(v.get(50), None);
+        assert_eq!(v.get(70), Some(&70));
+        unsafe {
+            v.insert(50, 50);
+        }
+        assert_eq!(v.get(50), Some(&50));
+    }
 }
@@ -1,8 +1,8 @@
-use std::{collections::HashSet, ops::Deref};
+use std::{borrow::Cow, collections::HashSet, ops::Deref};
 
 use anyhow::Result;
 
-use crate::{self as turbo_tasks, RawVc, ValueToString, ValueToStringVc};
+use crate::{self as turbo_tasks, CompressibleValue, RawVc, ValueToString, ValueToStringVc};
 
 #[turbo_tasks::value(transparent)]
 pub struct String(std::string::String);
@@ -15,6 +15,16 @@ impl StringVc {
     }
 }
 
+impl CompressibleValue for String {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(self.0.as_bytes())
+    }
+
+    fn from_bytes(bytes: Vec<u8>) -> Self {
+        String(std::string::String::from_utf8(bytes).expect("the bytes of a string are UTF-8"))
+    }
+}
+
 #[turbo_tasks::value(transparent)]
 pub struct U64(u64);
 
@@ -35,6 +45,16 @@ impl StringsVc {
 #[turbo_tasks::value(transparent)]
 pub struct Bytes(Vec<u8>);
 
+impl CompressibleValue for Bytes {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(&self.0)
+    }
+
+    fn from_bytes(bytes: Vec<u8>) -> Self {
+        Bytes(bytes)
+    }
+}
+
 #[turbo_tasks::value(transparent)]
 pub struct Bool(bool);
 
@@ -0,0 +1,258 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use dashmap::DashMap;
+use pin_project_lite::pin_project;
+use tokio::{task::futures::TaskLocalFuture, task_local};
+
+use crate::{event::Event, TaskId};
+
+/// The scheduling priority of a task.
+///
+/// Tasks inherit the priority of the task or the root invocation that
+/// scheduled them, so the priority is propagated to all dependencies which are
+/// computed for a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TaskPriority {
+    /// Work something is waiting for, e. g. a page the browser requested.
+    Interactive,
+    /// All other work, which runs alongside interactive work.
+    #[default]
+    Normal,
+    /// Work which can be delayed, e. g. precompilation or updates nobody is
+    /// waiting for yet. It only starts while no interactive work is running,
+    /// unless other work depends on it.
+    Background,
+}
+
+task_local! {
+    static PRIORITY: TaskPriority;
+}
+
+/// Returns the priority of the current task or root invocation.
+pub fn current_priority() -> TaskPriority {
+    PRIORITY.try_with(|p| *p).unwrap_or_default()
+}
+
+/// Runs `future` with the given priority. Tasks scheduled by `future`, e. g.
+/// by `run_once`, and their dependencies get this priority.
+pub async fn with_priority<T>(priority: TaskPriority, future: impl Future<Output = T>) -> T {
+    PRIORITY.scope(priority, future).await
+}
+
+/// Keeps background tasks from starting while interactive tasks are making
+/// progress.
+///
+/// Interactive tasks are only counted while they are queued or polled. An
+/// interactive task waiting for another task doesn't block background tasks,
+/// as it might wait for one of them. Background tasks which other work reads
+/// from are boosted to the priority of the reader, so they start right away.
+pub(crate) struct PriorityGate {
+    interactive: AtomicUsize,
+    /// Background tasks which didn't start yet, with the priority they were
+    /// boosted to.
+    waiting: DashMap<TaskId, TaskPriority>,
+    event: Event,
+}
+
+impl PriorityGate {
+    pub(crate) fn new() -> Self {
+        Self {
+            interactive: AtomicUsize::new(0),
+            waiting: DashMap::new(),
+            event: Event::new(|| "PriorityGate::event".to_string()),
+        }
+    }
+
+    /// Registers a background task, so it can be boosted before it starts to
+    /// wait.
+    pub(crate) fn register(&self, task: TaskId) {
+        self.waiting.entry(task).or_insert(TaskPriority::Background);
+    }
+
+    /// Called when `priority` work waits for `task`. When `task` is a waiting
+    /// background task, it starts with `priority` instead.
+    pub(crate) fn boost(&self, task: TaskId, priority: TaskPriority) {
+        if priority == TaskPriority::Background {
+            return;
+        }
+        if let Some(mut waiting) = self.waiting.get_mut(&task) {
+            if *waiting != TaskPriority::Interactive {
+                *waiting = priority;
+                drop(waiting);
+                self.event.notify(usize::MAX);
+            }
+        }
+    }
+
+    fn enter(&self) {
+        self.interactive.fetch_add(1, Ordering::AcqRel);
+    }
+
+    fn leave(&self) {
+        if self.interactive.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.event.notify(usize::MAX);
+        }
+    }
+
+    /// Waits until no interactive task is queued or polled, or until the
+    /// background `task` is boosted. Returns the priority `task` runs with.
+    pub(crate) async fn wait_for_interactive(&self, task: TaskId) -> TaskPriority {
+        self.register(task);
+        loop {
+            if let Some(priority) = self.try_start(task) {
+                return priority;
+            }
+            let listener = self.event.listen();
+            if let Some(priority) = self.try_start(task) {
+                return priority;
+            }
+            listener.await;
+        }
+    }
+
+    fn try_start(&self, task: TaskId) -> Option<TaskPriority> {
+        let priority = *self.waiting.get(&task)?;
+        if priority == TaskPriority::Background && self.interactive.load(Ordering::Acquire) > 0 {
+            return None;
+        }
+        self.waiting.remove(&task);
+        Some(priority)
+    }
+
+    /// Wraps the execution of a task. Interactive tasks are counted as queued
+    /// from now on.
+    pub(crate) fn track<F: Future>(
+        self: &Arc<Self>,
+        priority: TaskPriority,
+        future: F,
+    ) -> PriorityFuture<F> {
+        let gate = (priority == TaskPriority::Interactive).then(|| {
+            self.enter();
+            self.clone()
+        });
+        PriorityFuture {
+            entered: gate.is_some(),
+            gate,
+            future: PRIORITY.scope(priority, future),
+        }
+    }
+}
+
+pin_project! {
+    pub(crate) struct PriorityFuture<F> {
+        gate: Option<Arc<PriorityGate>>,
+        entered: bool,
+        #[pin]
+        future: TaskLocalFuture<TaskPriority, F>,
+    }
+
+    impl<F> PinnedDrop for PriorityFuture<F> {
+        fn drop(this: Pin<&mut Self>) {
+            let this = this.project();
+            if let Some(gate) = this.gate {
+                if *this.entered {
+                    gate.leave();
+                }
+            }
+        }
+    }
+}
+
+impl<F: Future> Future for PriorityFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let gate = if let Some(gate) = this.gate {
+            gate
+        } else {
+            return this.future.poll(cx);
+        };
+        if !*this.entered {
+            gate.enter();
+            *this.entered = true;
+        }
+        let result = this.future.poll(cx);
+        *this.entered = false;
+        gate.leave();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::{current_priority, with_priority, PriorityGate, TaskPriority};
+    use crate::TaskId;
+
+    #[tokio::test]
+    async fn defaults_to_normal_priority() {
+        assert_eq!(current_priority(), TaskPriority::Normal);
+        assert_eq!(
+            with_priority(TaskPriority::Background, async { current_priority() }).await,
+            TaskPriority::Background
+        );
+    }
+
+    #[tokio::test]
+    async fn background_tasks_wait_for_interactive_tasks() {
+        let gate = Arc::new(PriorityGate::new());
+        let task = TaskId::from(1);
+        assert_eq!(
+            gate.wait_for_interactive(task).await,
+            TaskPriority::Background
+        );
+
+        let interactive = gate.track(TaskPriority::Interactive, async {});
+        gate.register(task);
+        let waiting = tokio::spawn({
+            let gate = gate.clone();
+            async move { gate.wait_for_interactive(task).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+        interactive.await;
+        assert_eq!(waiting.await.unwrap(), TaskPriority::Background);
+    }
+
+    #[tokio::test]
+    async fn boosts_waiting_background_tasks() {
+        let gate = Arc::new(PriorityGate::new());
+        let task = TaskId::from(1);
+        let interactive = gate.track(TaskPriority::Interactive, async {});
+        // Boosting works as soon as the task is scheduled, even when it didn't
+        // start to wait yet.
+        gate.register(task);
+        gate.boost(task, TaskPriority::Background);
+        gate.boost(task, TaskPriority::Interactive);
+        assert_eq!(
+            gate.wait_for_interactive(task).await,
+            TaskPriority::Interactive
+        );
+
+        let other = TaskId::from(2);
+        gate.register(other);
+        let waiting = tokio::spawn({
+            let gate = gate.clone();
+            async move { gate.wait_for_interactive(other).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+        gate.boost(other, TaskPriority::Normal);
+        assert_eq!(waiting.await.unwrap(), TaskPriority::Normal);
+
+        // Tasks which aren't waiting aren't tracked.
+        gate.boost(TaskId::from(3), TaskPriority::Interactive);
+        assert!(gate.waiting.is_empty());
+        drop(interactive);
+    }
+}
@@ -24,7 +24,7 @@ use crate::{
     primitives::{RawVcSet, RawVcSetVc},
     registry::{self, get_value_type},
     turbo_tasks,
-    value_type::ValueTraitVc,
+    value_type::{ValueTraitVc, ValueVc},
     CollectiblesSource, ReadRef, SharedReference, TaskId, TraitTypeId, ValueTypeId,
 };
 
@@ -40,6 +40,24 @@ pub enum ResolveTypeError {
     ReadError { source: anyhow::Error },
 }
 
+/// The result of a read which doesn't wait, see [RawVc::try_read_now] and
+/// [RawVc::peek].
+#[derive(Debug)]
+pub enum ReadNow<T> {
+    Ready(T),
+    /// The value is not computed yet, or is being recomputed.
+    NotReady,
+}
+
+impl<T> ReadNow<T> {
+    pub fn ready(self) -> Option<T> {
+        match self {
+            ReadNow::Ready(value) => Some(value),
+            ReadNow::NotReady => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct CellId {
     pub type_id: ValueTypeId,
@@ -137,6 +155,71 @@ impl RawVc {
             .ok_or_else(|| anyhow!("failed to read cell content into hash"))
     }
 
+    /// Reads the current value without waiting for tasks to finish. Returns
+    /// [ReadNow::NotReady] instead of waiting. The current task depends on
+    /// the read value, like for an awaited read.
+    ///
+    /// Tasks scheduled by the current task are not notified, so this doesn't
+    /// start any work.
+    pub fn try_read_now<T: Any + Send + Sync>(
+        self,
+        turbo_tasks: &dyn TurboTasksApi,
+    ) -> Result<ReadNow<ReadRef<T>>> {
+        match self.read_now_internal(true, turbo_tasks)? {
+            ReadNow::Ready(content) => Ok(ReadNow::Ready(content.cast::<T>()?)),
+            ReadNow::NotReady => Ok(ReadNow::NotReady),
+        }
+    }
+
+    /// Like [RawVc::try_read_now], but doesn't register a dependency, so it
+    /// can be used outside of tasks, e. g. to show the current state in a UI.
+    ///
+    /// INVALIDATION: Be careful with this, it will not track dependencies, so
+    /// using it in a task could break cache invalidation.
+    pub fn peek<T: Any + Send + Sync>(
+        self,
+        turbo_tasks: &dyn TurboTasksApi,
+    ) -> Result<ReadNow<ReadRef<T>>> {
+        match self.read_now_internal(false, turbo_tasks)? {
+            ReadNow::Ready(content) => Ok(ReadNow::Ready(content.cast::<T>()?)),
+            ReadNow::NotReady => Ok(ReadNow::NotReady),
+        }
+    }
+
+    fn read_now_internal(
+        self,
+        tracked: bool,
+        turbo_tasks: &dyn TurboTasksApi,
+    ) -> Result<ReadNow<CellContent>> {
+        let mut current = self;
+        loop {
+            match current {
+                RawVc::TaskOutput(task) => {
+                    let result = if tracked {
+                        turbo_tasks.try_read_task_output(task, false)?
+                    } else {
+                        turbo_tasks.try_read_task_output_untracked(task, false)?
+                    };
+                    match result {
+                        Ok(vc) => current = vc,
+                        Err(_listener) => return Ok(ReadNow::NotReady),
+                    }
+                }
+                RawVc::TaskCell(task, index) => {
+                    let result = if tracked {
+                        turbo_tasks.try_read_task_cell(task, index)?
+                    } else {
+                        turbo_tasks.try_read_task_cell_untracked(task, index)?
+                    };
+                    return Ok(match result {
+                        Ok(content) => ReadNow::Ready(content),
+                        Err(_listener) => ReadNow::NotReady,
+                    });
+                }
+            }
+        }
+    }
+
     async fn into_read_untracked_internal(
         self,
         strongly_consistent: bool,
@@ -229,6 +312,32 @@ impl RawVc {
         }
     }
 
+    /// Resolves the reference and casts it to the value trait `T`. Returns
+    /// `None` when the value doesn't implement `T`.
+    ///
+    /// Unlike [RawVc::resolve_trait] this can only be called with a Vc type
+    /// generated by `#[turbo_tasks::value_trait]`, so the trait is known to be
+    /// registered.
+    pub async fn try_cast<T: ValueTraitVc>(self) -> Result<Option<T>, ResolveTypeError> {
+        Ok(self
+            .resolve_trait(T::get_trait_type_id())
+            .await?
+            .map(T::from))
+    }
+
+    /// Resolves the reference and casts it to the concrete value type `T`.
+    /// Returns `None` when the value has a different type.
+    ///
+    /// Unlike [RawVc::resolve_value] this can only be called with a Vc type
+    /// generated by `#[turbo_tasks::value]`, so the type is known to be
+    /// registered.
+    pub async fn downcast<T: ValueVc>(self) -> Result<Option<T>, ResolveTypeError> {
+        Ok(self
+            .resolve_value(T::get_value_type_id())
+            .await?
+            .map(T::from))
+    }
+
     /// Resolve the reference until it points to a cell directly.
     ///
     /// Resolving will wait for task execution to be finished, so that the
@@ -1,4 +1,8 @@
-use std::{fmt::Debug, hash::Hash, ops::Deref};
+use std::{
+    fmt::{Debug, Write},
+    hash::Hash,
+    ops::Deref,
+};
 
 use dashmap::{mapref::entry::Entry, DashMap};
 use once_cell::sync::Lazy;
@@ -7,7 +11,9 @@ use crate::{
     id::{FunctionId, TraitTypeId, ValueTypeId},
     id_factory::IdFactory,
     no_move_vec::NoMoveVec,
-    NativeFunction, TraitType, ValueType,
+    value_diagnostics::ValueDiagnostics,
+    CellCompression, CellEquality, CompressibleValue, ConciseDebug, NativeFunction, TraitType,
+    Typed, ValueType,
 };
 
 static FUNCTION_ID_FACTORY: IdFactory<FunctionId> = IdFactory::new();
@@ -22,15 +28,64 @@ static VALUE_TYPES_BY_VALUE: Lazy<DashMap<&'static ValueType, ValueTypeId>> =
     Lazy::new(DashMap::new);
 static VALUE_TYPES: Lazy<NoMoveVec<(&'static ValueType, String)>> = Lazy::new(NoMoveVec::new);
 
+static CELL_COMPRESSIONS: Lazy<DashMap<ValueTypeId, CellCompression>> = Lazy::new(DashMap::new);
+/// Overrides the [CellEquality] of the value types. `None` disables it.
+static CELL_EQUALITIES: Lazy<DashMap<ValueTypeId, Option<CellEquality>>> = Lazy::new(DashMap::new);
+static VALUE_DIAGNOSTICS: Lazy<DashMap<ValueTypeId, ValueDiagnostics>> = Lazy::new(DashMap::new);
+
 static TRAIT_TYPE_ID_FACTORY: IdFactory<TraitTypeId> = IdFactory::new();
 static TRAIT_TYPES_BY_NAME: Lazy<DashMap<String, TraitTypeId>> = Lazy::new(DashMap::new);
 static TRAIT_TYPES_BY_VALUE: Lazy<DashMap<&'static TraitType, TraitTypeId>> =
     Lazy::new(DashMap::new);
 static TRAIT_TYPES: Lazy<NoMoveVec<(&'static TraitType, String)>> = Lazy::new(NoMoveVec::new);
 
+/// Something which can be registered in the registry.
+trait RegistryItem: Debug {
+    /// The kind of the item, used in error messages.
+    const KIND: &'static str;
+
+    /// The name of the item in source code.
+    fn name(&self) -> &str;
+
+    /// The crate which declares the item, if it is known.
+    fn crate_name(&self) -> Option<&str> {
+        let (crate_name, _) = self.name().split_once("::")?;
+        Some(crate_name)
+    }
+}
+
+impl RegistryItem for &'static NativeFunction {
+    const KIND: &'static str = "function";
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn crate_name(&self) -> Option<&str> {
+        // Function names are not qualified by their crate.
+        None
+    }
+}
+
+impl RegistryItem for &'static ValueType {
+    const KIND: &'static str = "value type";
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl RegistryItem for &'static TraitType {
+    const KIND: &'static str = "value trait";
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
 fn register_thing<
     K: From<usize> + Deref<Target = usize> + Sync + Send + Copy,
-    V: Clone + Hash + Ord + Eq + Sync + Send + Copy,
+    V: RegistryItem + Clone + Hash + Ord + Eq + Sync + Send + Copy,
     const INITIAL_CAPACITY_BITS: u32,
 >(
     global_name: &str,
@@ -40,31 +95,114 @@ fn register_thing<
     map_by_name: &DashMap<String, K>,
     map_by_value: &DashMap<V, K>,
 ) {
+    // Registering a value again is fine, e. g. when `register()` is called
+    // multiple times.
     if let Entry::Vacant(e) = map_by_value.entry(value) {
         let new_id = id_factory.get();
         // SAFETY: this is a fresh id
         unsafe {
             store.insert(*new_id, (value, global_name.to_string()));
         }
-        map_by_name.insert(global_name.to_string(), new_id);
+        if let Some(existing_id) = map_by_name.insert(global_name.to_string(), new_id) {
+            let (existing, _) = store.get(*existing_id).unwrap();
+            panic!(
+                "Two different {}s are registered under the global name {}: {:?} and {:?}. This \
+                 happens when the same crate is linked twice in the same version, or when a \
+                 generated register file is out of date.",
+                V::KIND,
+                global_name,
+                existing,
+                value
+            );
+        }
         e.insert(new_id);
     }
 }
 
 fn get_thing_id<
     K: From<usize> + Deref<Target = usize> + Sync + Send + Copy,
-    V: Clone + Hash + Ord + Eq + Debug + Sync + Send,
+    V: RegistryItem + Clone + Hash + Ord + Eq + Sync + Send,
 >(
     value: V,
+    map_by_name: &DashMap<String, K>,
     map_by_value: &DashMap<V, K>,
 ) -> K {
     if let Some(id) = map_by_value.get(&value) {
         *id
     } else {
-        panic!("Use of unregistered {:?}", value);
+        panic!("{}", unregistered_message(&value, map_by_name));
     }
 }
 
+/// The number of similar names listed when an unregistered item is used.
+const MAX_SUGGESTIONS: usize = 3;
+
+fn unregistered_message<K, V: RegistryItem>(value: &V, map_by_name: &DashMap<String, K>) -> String {
+    let mut message = format!("Use of unregistered {} {}.", V::KIND, value.name());
+    match value.crate_name() {
+        Some(crate_name) => write!(
+            message,
+            " It is declared in the crate {crate_name}, so {crate_name}::register() must be \
+             called before it is used."
+        ),
+        None => write!(
+            message,
+            " The register() function of the crate which declares it must be called before it is \
+             used."
+        ),
+    }
+    .unwrap();
+
+    let name = short_name(value.name());
+    let mut candidates = map_by_name
+        .iter()
+        .map(|entry| {
+            let global_name = entry.key().clone();
+            (edit_distance(name, short_name(&global_name)), global_name)
+        })
+        .collect::<Vec<_>>();
+    candidates.sort();
+    let suggestions = candidates
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, global_name)| global_name)
+        .collect::<Vec<_>>();
+    if !suggestions.is_empty() {
+        write!(
+            message,
+            " The closest registered names are: {}",
+            suggestions.join(", ")
+        )
+        .unwrap();
+    }
+    message
+}
+
+/// Strips the crate and module path from a name.
+fn short_name(name: &str) -> &str {
+    // Global names have the form `crate@version::mod::Item`, type names
+    // `crate::mod::Item`.
+    let name = name.rsplit_once("::").map_or(name, |(_, name)| name);
+    name.strip_suffix("Vc").unwrap_or(name)
+}
+
+/// The Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let cost = usize::from(a != *b);
+            let next = (row[j] + 1).min(row[j + 1] + 1).min(diagonal + cost);
+            diagonal = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
+
 pub fn register_function(global_name: &str, func: &'static NativeFunction) {
     register_thing(
         global_name,
@@ -77,7 +215,7 @@ pub fn register_function(global_name: &str, func: &'static NativeFunction) {
 }
 
 pub fn get_function_id(func: &'static NativeFunction) -> FunctionId {
-    get_thing_id(func, &FUNCTIONS_BY_VALUE)
+    get_thing_id(func, &FUNCTIONS_BY_NAME, &FUNCTIONS_BY_VALUE)
 }
 
 pub fn get_function_id_by_global_name(global_name: &str) -> Option<FunctionId> {
@@ -104,7 +242,7 @@ pub fn register_value_type(global_name: &str, ty: &'static ValueType) {
 }
 
 pub fn get_value_type_id(func: &'static ValueType) -> ValueTypeId {
-    get_thing_id(func, &VALUE_TYPES_BY_VALUE)
+    get_thing_id(func, &VALUE_TYPES_BY_NAME, &VALUE_TYPES_BY_VALUE)
 }
 
 pub fn get_value_type_id_by_global_name(global_name: &str) -> Option<ValueTypeId> {
@@ -119,6 +257,60 @@ pub fn get_value_type_global_name(id: ValueTypeId) -> &'static str {
     &VALUE_TYPES.get(*id).unwrap().1
 }
 
+/// Makes backends store values of the value type `T` compressed in cells when
+/// they have at least `threshold` bytes. Registering it again replaces the
+/// threshold.
+pub fn register_cell_compression<T: CompressibleValue + Typed>(threshold: usize) {
+    CELL_COMPRESSIONS.insert(T::get_value_type_id(), CellCompression::new::<T>(threshold));
+}
+
+pub fn get_cell_compression(id: ValueTypeId) -> Option<CellCompression> {
+    CELL_COMPRESSIONS.get(&id).map(|x| *x)
+}
+
+/// Enables the equality cut-off for the cells of `T` with `cell = "new"` or
+/// a manual [PartialEq].
+pub fn register_cell_equality<T: PartialEq + Typed + Send + Sync + 'static>() {
+    CELL_EQUALITIES.insert(T::get_value_type_id(), Some(CellEquality::new::<T>()));
+}
+
+/// Disables the equality cut-off for the cells of `T`, so every assignment
+/// invalidates the tasks which read them.
+pub fn unregister_cell_equality<T: Typed>() {
+    CELL_EQUALITIES.insert(T::get_value_type_id(), None);
+}
+
+/// The [CellEquality] registered with [register_cell_equality].
+pub fn get_cell_equality(id: ValueTypeId) -> Option<CellEquality> {
+    CELL_EQUALITIES.get(&id).and_then(|equality| *equality)
+}
+
+/// Whether the equality cut-off was disabled with [unregister_cell_equality].
+pub fn is_cell_equality_disabled(id: ValueTypeId) -> bool {
+    matches!(CELL_EQUALITIES.get(&id).as_deref(), Some(None))
+}
+
+/// Shows the values of `T` with their [ConciseDebug] representation in
+/// diagnostics.
+pub fn register_concise_debug<T: ConciseDebug + Typed>() {
+    VALUE_DIAGNOSTICS.insert(T::get_value_type_id(), ValueDiagnostics::concise::<T>());
+}
+
+/// Marks the values of `T` as sensitive, e. g. secrets from env variables.
+/// They are redacted from diagnostics and never serialized, so persisted
+/// caches skip them.
+pub fn register_redacted<T: Typed>() {
+    VALUE_DIAGNOSTICS.insert(T::get_value_type_id(), ValueDiagnostics::Redacted);
+}
+
+pub fn is_redacted(id: ValueTypeId) -> bool {
+    matches!(get_value_diagnostics(id), Some(ValueDiagnostics::Redacted))
+}
+
+pub(crate) fn get_value_diagnostics(id: ValueTypeId) -> Option<ValueDiagnostics> {
+    VALUE_DIAGNOSTICS.get(&id).map(|x| *x)
+}
+
 pub fn register_trait_type(global_name: &str, ty: &'static TraitType) {
     register_thing(
         global_name,
@@ -131,7 +323,7 @@ pub fn register_trait_type(global_name: &str, ty: &'static TraitType) {
 }
 
 pub fn get_trait_type_id(func: &'static TraitType) -> TraitTypeId {
-    get_thing_id(func, &TRAIT_TYPES_BY_VALUE)
+    get_thing_id(func, &TRAIT_TYPES_BY_NAME, &TRAIT_TYPES_BY_VALUE)
 }
 
 pub fn get_trait_type_id_by_global_name(global_name: &str) -> Option<TraitTypeId> {
@@ -145,3 +337,23 @@ pub fn get_trait(id: TraitTypeId) -> &'static TraitType {
 pub fn get_trait_type_global_name(id: TraitTypeId) -> &'static str {
     &TRAIT_TYPES.get(*id).unwrap().1
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{edit_distance, short_name};
+
+    #[test]
+    fn test_short_name() {
+        assert_eq!(short_name("turbopack_core::asset::AssetVc"), "Asset");
+        assert_eq!(short_name("turbopack-core@0.1.0::asset::Asset"), "Asset");
+        assert_eq!(short_name("run"), "run");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("asset", "asset"), 0);
+        assert_eq!(edit_distance("asset", "assets"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+}
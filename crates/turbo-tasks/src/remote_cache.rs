@@ -0,0 +1,496 @@
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    collections::HashSet,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use turbo_tasks_hash::{encode_hex_string, hash_sha256};
+
+use crate::{
+    backend::{
+        Backend, BackendJobId, CellContent, PersistentTaskType, TaskExecutionSpec, TaskSnapshot,
+        TrackedRead, TransientTaskType,
+    },
+    event::EventListener,
+    manager::turbo_tasks,
+    with_task_id_mapping, CellId, FunctionId, IdMapping, RawVc, SharedReference, TaskId,
+    TaskIdProvider, TaskInput, TraitTypeId, TurboTasksBackendApi,
+};
+
+/// Prefixes the hashed cache keys, so results stored by an incompatible
+/// version of turbo-tasks or of the format of [CachedTaskResult] are never
+/// restored. Increment the last part when the format changes.
+const CACHE_KEY_NAMESPACE: &str = concat!("turbo-tasks@", env!("CARGO_PKG_VERSION"), "/1/");
+
+pub type CacheFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// A content addressed store for task results, e. g. an HTTP or gRPC service
+/// shared by CI and teammates. See `turbo_tasks_fetch::HttpRemoteCache` for an
+/// HTTP implementation.
+pub trait RemoteCache: Send + Sync + 'static {
+    /// Returns the entry stored for `key`, if any.
+    fn get(&self, key: &str) -> CacheFuture<'_, Option<Vec<u8>>>;
+
+    /// Stores `data` for `key`.
+    fn put(&self, key: String, data: Vec<u8>) -> CacheFuture<'_, ()>;
+}
+
+/// The result of a task execution as stored in the remote cache.
+#[derive(Serialize, Deserialize)]
+struct CachedTaskResult {
+    /// The key the result is stored for, which has to match when the result
+    /// is restored, so a misplaced entry is never restored for another task.
+    key: String,
+    output: CellId,
+    cells: Vec<(CellId, CellContent)>,
+}
+
+impl CachedTaskResult {
+    /// Checks a downloaded result before its cells are restored. The output
+    /// has to be one of the cells and every cell has to contain a value of
+    /// the type of the cell.
+    fn is_valid_for(&self, key: &str) -> bool {
+        self.key == key
+            && self.cells.iter().any(|(index, _)| *index == self.output)
+            && self.cells.iter().all(|(index, content)| match &content.0 {
+                Some(SharedReference(type_id, _)) => *type_id == Some(index.type_id),
+                None => true,
+            })
+    }
+}
+
+/// Wraps a [Backend] to share the results of pure tasks via a [RemoteCache].
+///
+/// Only tasks of the functions passed as `pure_functions` are cached. These
+/// must not read any state besides their arguments, as the dependencies of a
+/// task are not restored from the cache. A task is looked up by the hash of
+/// its function and arguments, so only tasks with serializable value
+/// arguments are cached. Results restored from the cache are not uploaded
+/// again. Results are only uploaded when the output of the
+/// task is one of its own cells and all its cells are serializable and don't
+/// reference other tasks.
+pub struct RemoteCacheBackend<B: Backend, C: RemoteCache> {
+    backend: B,
+    cache: Arc<C>,
+    pure_functions: HashSet<FunctionId>,
+    /// The cache key and the output cell of tasks which finished their
+    /// execution, but are not yet uploaded.
+    pending_uploads: DashMap<TaskId, (String, CellId)>,
+    /// The cache keys of tasks which are currently executing. Entries are
+    /// removed when the result was read from the cache.
+    executing: Arc<DashMap<TaskId, String>>,
+}
+
+impl<B: Backend, C: RemoteCache> RemoteCacheBackend<B, C> {
+    pub fn new(backend: B, cache: C, pure_functions: impl IntoIterator<Item = FunctionId>) -> Self {
+        Self {
+            backend,
+            cache: Arc::new(cache),
+            pure_functions: pure_functions.into_iter().collect(),
+            pending_uploads: DashMap::new(),
+            executing: Arc::new(DashMap::new()),
+        }
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// Returns the cache key of `task`, when its result can be cached. The
+    /// key is the SHA-256 hash of the [CACHE_KEY_NAMESPACE], the function and
+    /// the arguments.
+    fn cache_key(&self, task: TaskId) -> Option<String> {
+        let task_type = self.backend.get_persistent_task_type(task)?;
+        let (fn_id, inputs) = match &task_type {
+            PersistentTaskType::Native(fn_id, inputs) => (fn_id, inputs),
+            _ => return None,
+        };
+        if !self.pure_functions.contains(fn_id) || !inputs.iter().all(is_content_addressable) {
+            return None;
+        }
+        let mut key = CACHE_KEY_NAMESPACE.as_bytes().to_vec();
+        serde_json::to_writer(&mut key, &task_type).ok()?;
+        Some(encode_hex_string(&hash_sha256(&key)))
+    }
+
+    fn upload(&self, task: TaskId, turbo_tasks: &dyn TurboTasksBackendApi) {
+        let (key, output) = if let Some((_, entry)) = self.pending_uploads.remove(&task) {
+            entry
+        } else {
+            return;
+        };
+        let snapshot = if let Some(snapshot) = self.backend.snapshot_task(task, turbo_tasks) {
+            snapshot
+        } else {
+            return;
+        };
+        let result = CachedTaskResult {
+            key: key.clone(),
+            output,
+            cells: snapshot.cells,
+        };
+        let detect_task_ids = DetectTaskIds::default();
+        let data = with_task_id_mapping(&detect_task_ids, || serde_json::to_vec(&result));
+        let data = match data {
+            Ok(data) if !detect_task_ids.0.get() => data,
+            // Not all cells are serializable, or they reference other tasks.
+            _ => return,
+        };
+        let cache = self.cache.clone();
+        tokio::spawn(async move {
+            // Uploading is best effort, the result is still available locally.
+            let _ = cache.put(key, data).await;
+        });
+    }
+}
+
+fn is_content_addressable(input: &TaskInput) -> bool {
+    match input {
+        TaskInput::TaskOutput(_)
+        | TaskInput::TaskCell(_, _)
+        | TaskInput::TransientSharedValue(_) => false,
+        TaskInput::List(list) => list.iter().all(is_content_addressable),
+        _ => true,
+    }
+}
+
+/// Records whether a [TaskId] was serialized, as task ids are only valid
+/// within the current process.
+#[derive(Default)]
+struct DetectTaskIds(Cell<bool>);
+
+impl IdMapping<TaskId> for DetectTaskIds {
+    fn forward(&self, id: TaskId) -> usize {
+        self.0.set(true);
+        *id
+    }
+
+    fn backward(&self, id: usize) -> TaskId {
+        TaskId::from(id)
+    }
+}
+
+/// Restores a cached task result into the cells of the current task.
+async fn read_from_cache<C: RemoteCache>(cache: &C, key: &str, task: TaskId) -> Option<RawVc> {
+    let data = cache.get(key).await.ok()??;
+    let result: CachedTaskResult = serde_json::from_slice(&data).ok()?;
+    if !result.is_valid_for(key) {
+        return None;
+    }
+    let tt = turbo_tasks();
+    for (index, content) in result.cells {
+        tt.update_current_task_cell(index, content);
+    }
+    Some(RawVc::TaskCell(task, result.output))
+}
+
+impl<B: Backend, C: RemoteCache> Backend for RemoteCacheBackend<B, C> {
+    fn initialize(&mut self, task_id_provider: &dyn TaskIdProvider) {
+        self.backend.initialize(task_id_provider)
+    }
+
+    fn startup(&self, turbo_tasks: &dyn TurboTasksBackendApi) {
+        self.backend.startup(turbo_tasks)
+    }
+
+    fn stop(&self, turbo_tasks: &dyn TurboTasksBackendApi) {
+        self.backend.stop(turbo_tasks)
+    }
+
+    fn invalidate_task(&self, task: TaskId, turbo_tasks: &dyn TurboTasksBackendApi) {
+        self.backend.invalidate_task(task, turbo_tasks)
+    }
+
+    fn invalidate_tasks(&self, tasks: Vec<TaskId>, turbo_tasks: &dyn TurboTasksBackendApi) {
+        self.backend.invalidate_tasks(tasks, turbo_tasks)
+    }
+
+    fn get_task_description(&self, task: TaskId) -> String {
+        self.backend.get_task_description(task)
+    }
+
+    type ExecutionScopeFuture<T: Future<Output = Result<()>> + Send + 'static> =
+        B::ExecutionScopeFuture<T>;
+
+    fn execution_scope<T: Future<Output = Result<()>> + Send + 'static>(
+        &self,
+        task: TaskId,
+        future: T,
+    ) -> Self::ExecutionScopeFuture<T> {
+        self.backend.execution_scope(task, future)
+    }
+
+    fn try_start_task_execution(
+        &self,
+        task: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Option<TaskExecutionSpec> {
+        let spec = self.backend.try_start_task_execution(task, turbo_tasks)?;
+        let key = if let Some(key) = self.cache_key(task) {
+            key
+        } else {
+            return Some(spec);
+        };
+        self.executing.insert(task, key.clone());
+        let cache = self.cache.clone();
+        let executing = self.executing.clone();
+        let future = spec.future;
+        Some(TaskExecutionSpec {
+            future: Box::pin(async move {
+                if let Some(output) = read_from_cache(&*cache, &key, task).await {
+                    // The result is already in the cache, so it's not uploaded again.
+                    executing.remove(&task);
+                    return Ok(output);
+                }
+                future.await
+            }),
+            function: spec.function,
+        })
+    }
+
+    fn task_execution_result(
+        &self,
+        task: TaskId,
+        result: Result<Result<RawVc>, Option<Cow<'static, str>>>,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) {
+        if let Some((_, key)) = self.executing.remove(&task) {
+            if let Ok(Ok(RawVc::TaskCell(output_task, output))) = &result {
+                if *output_task == task {
+                    self.pending_uploads.insert(task, (key, *output));
+                }
+            }
+        }
+        self.backend
+            .task_execution_result(task, result, turbo_tasks)
+    }
+
+    fn task_execution_completed(
+        &self,
+        task: TaskId,
+        duration: Duration,
+        instant: Instant,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> bool {
+        let reexecute = self
+            .backend
+            .task_execution_completed(task, duration, instant, turbo_tasks);
+        if reexecute {
+            self.pending_uploads.remove(&task);
+        } else {
+            self.upload(task, turbo_tasks);
+        }
+        reexecute
+    }
+
+    fn run_backend_job<'a>(
+        &'a self,
+        id: BackendJobId,
+        turbo_tasks: &'a dyn TurboTasksBackendApi,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+        self.backend.run_backend_job(id, turbo_tasks)
+    }
+
+    fn try_read_task_output(
+        &self,
+        task: TaskId,
+        reader: TaskId,
+        strongly_consistent: bool,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Result<Result<RawVc, EventListener>> {
+        self.backend
+            .try_read_task_output(task, reader, strongly_consistent, turbo_tasks)
+    }
+
+    fn try_read_task_output_untracked(
+        &self,
+        task: TaskId,
+        strongly_consistent: bool,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Result<Result<RawVc, EventListener>> {
+        self.backend
+            .try_read_task_output_untracked(task, strongly_consistent, turbo_tasks)
+    }
+
+    fn track_read_task_output(
+        &self,
+        task: TaskId,
+        reader: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) {
+        self.backend
+            .track_read_task_output(task, reader, turbo_tasks)
+    }
+
+    fn try_read_task_cell(
+        &self,
+        task: TaskId,
+        index: CellId,
+        reader: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Result<Result<CellContent, EventListener>> {
+        self.backend
+            .try_read_task_cell(task, index, reader, turbo_tasks)
+    }
+
+    fn try_read_task_cell_untracked(
+        &self,
+        task: TaskId,
+        index: CellId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Result<Result<CellContent, EventListener>> {
+        self.backend
+            .try_read_task_cell_untracked(task, index, turbo_tasks)
+    }
+
+    fn try_read_own_task_cell_untracked(
+        &self,
+        current_task: TaskId,
+        index: CellId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Result<CellContent> {
+        self.backend
+            .try_read_own_task_cell_untracked(current_task, index, turbo_tasks)
+    }
+
+    fn track_read_task_cell(
+        &self,
+        task: TaskId,
+        index: CellId,
+        reader: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) {
+        self.backend
+            .track_read_task_cell(task, index, reader, turbo_tasks)
+    }
+
+    fn try_read_task_output_batched(
+        &self,
+        task: TaskId,
+        reader: TaskId,
+        strongly_consistent: bool,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Result<Result<(RawVc, TrackedRead), EventListener>> {
+        self.backend
+            .try_read_task_output_batched(task, reader, strongly_consistent, turbo_tasks)
+    }
+
+    fn try_read_task_cell_batched(
+        &self,
+        task: TaskId,
+        index: CellId,
+        reader: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Result<Result<(CellContent, TrackedRead), EventListener>> {
+        self.backend
+            .try_read_task_cell_batched(task, index, reader, turbo_tasks)
+    }
+
+    fn track_reads(
+        &self,
+        reader: TaskId,
+        reads: Vec<TrackedRead>,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) {
+        self.backend.track_reads(reader, reads, turbo_tasks)
+    }
+
+    fn try_read_task_collectibles(
+        &self,
+        task: TaskId,
+        trait_id: TraitTypeId,
+        reader: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Result<Result<HashSet<RawVc>, EventListener>> {
+        self.backend
+            .try_read_task_collectibles(task, trait_id, reader, turbo_tasks)
+    }
+
+    fn emit_collectible(
+        &self,
+        trait_type: TraitTypeId,
+        collectible: RawVc,
+        task: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) {
+        self.backend
+            .emit_collectible(trait_type, collectible, task, turbo_tasks)
+    }
+
+    fn unemit_collectible(
+        &self,
+        trait_type: TraitTypeId,
+        collectible: RawVc,
+        task: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) {
+        self.backend
+            .unemit_collectible(trait_type, collectible, task, turbo_tasks)
+    }
+
+    fn update_task_cell(
+        &self,
+        task: TaskId,
+        index: CellId,
+        content: CellContent,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) {
+        self.backend
+            .update_task_cell(task, index, content, turbo_tasks)
+    }
+
+    fn keep_task_cell(&self, task: TaskId, index: CellId) {
+        self.backend.keep_task_cell(task, index)
+    }
+
+    fn get_or_create_persistent_task(
+        &self,
+        task_type: PersistentTaskType,
+        parent_task: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> TaskId {
+        self.backend
+            .get_or_create_persistent_task(task_type, parent_task, turbo_tasks)
+    }
+
+    fn create_transient_task(
+        &self,
+        task_type: TransientTaskType,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> TaskId {
+        self.backend.create_transient_task(task_type, turbo_tasks)
+    }
+
+    fn dispose_root_task(&self, task: TaskId, turbo_tasks: &dyn TurboTasksBackendApi) {
+        self.backend.dispose_root_task(task, turbo_tasks)
+    }
+
+    fn get_persistent_task_type(&self, task: TaskId) -> Option<PersistentTaskType> {
+        self.backend.get_persistent_task_type(task)
+    }
+
+    fn snapshot_task(
+        &self,
+        task: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Option<TaskSnapshot> {
+        self.backend.snapshot_task(task, turbo_tasks)
+    }
+
+    fn restore_task(
+        &self,
+        snapshot: TaskSnapshot,
+        parent_task: Option<TaskId>,
+        turbo_tasks: &dyn TurboTasksBackendApi,
+    ) -> Option<TaskId> {
+        self.backend
+            .restore_task(snapshot, parent_task, turbo_tasks)
+    }
+}
@@ -0,0 +1,46 @@
+use std::{future::Future, time::Duration};
+
+use anyhow::Result;
+
+/// Retries a task function when it fails, e. g. because it depends on the
+/// network. Configured with `#[turbo_tasks::function(retry(max_attempts = 3,
+/// backoff_ms = 100))]`.
+///
+/// Only the error of the last attempt is returned. Like any failed task, the
+/// task is executed again, including its retries, when it's invalidated.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// How often the function is called before its error is returned.
+    pub max_attempts: u32,
+    /// The delay before the second attempt, which doubles for every further
+    /// attempt.
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub async fn run<T, F, Fut>(self, mut f: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut backoff = self.backoff;
+        let mut attempt = 1;
+        loop {
+            match f().await {
+                Ok(value) => return Ok(value),
+                Err(err) if attempt >= self.max_attempts => {
+                    return if attempt > 1 {
+                        Err(err.context(format!("failed after {attempt} attempts")))
+                    } else {
+                        Err(err)
+                    };
+                }
+                Err(_) => {
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use anyhow::Result;
+use futures::Stream;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::{TaskId, TurboTasksApi};
+
+/// The results of a root task created by
+/// [TurboTasks::run_once_with_updates](crate::TurboTasks::run_once_with_updates),
+/// one for every execution of the task.
+///
+/// Dropping it disposes the root task, so it's not executed again.
+pub struct RootUpdates<T> {
+    pub(crate) task: TaskId,
+    pub(crate) receiver: UnboundedReceiver<Result<T>>,
+    pub(crate) turbo_tasks: Arc<dyn TurboTasksApi>,
+}
+
+impl<T> RootUpdates<T> {
+    /// The root task computing the results.
+    pub fn task_id(&self) -> TaskId {
+        self.task
+    }
+
+    /// Waits for the next result.
+    pub async fn next_update(&mut self) -> Option<Result<T>> {
+        self.receiver.recv().await
+    }
+}
+
+impl<T> Stream for RootUpdates<T> {
+    type Item = Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl<T> Drop for RootUpdates<T> {
+    fn drop(&mut self) {
+        self.turbo_tasks.dispose_root_task(self.task);
+    }
+}
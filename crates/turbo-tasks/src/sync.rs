@@ -0,0 +1,199 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    ops::{Deref, DerefMut},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{bail, Result};
+use once_cell::sync::Lazy;
+
+use crate::{manager::try_current_task, TaskId};
+
+/// The locks held and awaited by tasks, to detect deadlocks before waiting.
+#[derive(Default)]
+struct WaitGraph {
+    /// The task holding each locked [TaskMutex], by the address of the mutex.
+    holders: HashMap<usize, TaskId>,
+    /// The [TaskMutex] each task is waiting for.
+    waiting: HashMap<TaskId, usize>,
+}
+
+impl WaitGraph {
+    /// Returns true when waiting for `mutex` would let `task` wait for itself.
+    fn would_deadlock(&self, task: TaskId, mut mutex: usize) -> bool {
+        while let Some(&holder) = self.holders.get(&mutex) {
+            if holder == task {
+                return true;
+            }
+            match self.waiting.get(&holder) {
+                Some(&next) => mutex = next,
+                None => return false,
+            }
+        }
+        false
+    }
+}
+
+static WAIT_GRAPH: Lazy<Mutex<WaitGraph>> = Lazy::new(Default::default);
+
+/// An async mutex which can be held across awaits in turbo-tasks functions.
+///
+/// Waiting for the mutex doesn't block a thread of the executor. Locking a
+/// mutex which the current task already holds, or which is held by a task
+/// waiting for a mutex held by the current task, fails with an error instead
+/// of deadlocking.
+///
+/// Waiting for a Vc while holding the mutex can still deadlock, when the task
+/// computing the Vc locks the same mutex.
+pub struct TaskMutex<T> {
+    inner: tokio::sync::Mutex<T>,
+}
+
+impl<T: Default> Default for TaskMutex<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> TaskMutex<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            inner: tokio::sync::Mutex::const_new(value),
+        }
+    }
+
+    pub async fn lock(&self) -> Result<TaskMutexGuard<'_, T>> {
+        let task = match try_current_task() {
+            Some(task) => task,
+            None => {
+                // Outside of tasks there is nothing to track
+                return Ok(TaskMutexGuard {
+                    mutex: self,
+                    task: None,
+                    guard: self.inner.lock().await,
+                });
+            }
+        };
+        let id = self.id();
+        {
+            let mut graph = WAIT_GRAPH.lock().unwrap();
+            if graph.would_deadlock(task, id) {
+                bail!("Locking the TaskMutex in {task} would deadlock");
+            }
+            graph.waiting.insert(task, id);
+        }
+        let guard = WaitingGuard { task }.wait(self.inner.lock()).await;
+        let mut graph = WAIT_GRAPH.lock().unwrap();
+        graph.waiting.remove(&task);
+        graph.holders.insert(id, task);
+        Ok(TaskMutexGuard {
+            mutex: self,
+            task: Some(task),
+            guard,
+        })
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+
+    /// The mutex can't move while it's locked or waited for, as it's borrowed,
+    /// so the address identifies it in the [WaitGraph].
+    fn id(&self) -> usize {
+        self as *const Self as usize
+    }
+}
+
+/// Removes the task from the waiting tasks when the lock future is dropped
+/// before the mutex was locked.
+struct WaitingGuard {
+    task: TaskId,
+}
+
+impl WaitingGuard {
+    async fn wait<F: std::future::Future>(self, future: F) -> F::Output {
+        let result = future.await;
+        std::mem::forget(self);
+        result
+    }
+}
+
+impl Drop for WaitingGuard {
+    fn drop(&mut self) {
+        WAIT_GRAPH.lock().unwrap().waiting.remove(&self.task);
+    }
+}
+
+pub struct TaskMutexGuard<'a, T> {
+    mutex: &'a TaskMutex<T>,
+    task: Option<TaskId>,
+    guard: tokio::sync::MutexGuard<'a, T>,
+}
+
+impl<'a, T> Deref for TaskMutexGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<'a, T> DerefMut for TaskMutexGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<'a, T> Drop for TaskMutexGuard<'a, T> {
+    fn drop(&mut self) {
+        if self.task.is_some() {
+            WAIT_GRAPH.lock().unwrap().holders.remove(&self.mutex.id());
+        }
+    }
+}
+
+/// Computes a value once per key, e. g. to download each Google Font only
+/// once. Concurrent callers with the same key wait for the first one, using a
+/// [TaskMutex] per key.
+///
+/// Errors are not stored, so the next caller retries the computation.
+pub struct TaskOnceMap<K, V> {
+    cells: Mutex<HashMap<K, Arc<TaskMutex<Option<V>>>>>,
+}
+
+impl<K: Hash + Eq, V: Clone> Default for TaskOnceMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq, V: Clone> TaskOnceMap<K, V> {
+    pub fn new() -> Self {
+        Self {
+            cells: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn get_or_try_init<F, Fut>(&self, key: K, init: F) -> Result<V>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<V>>,
+    {
+        let cell = self.cells.lock().unwrap().entry(key).or_default().clone();
+        let mut guard = cell.lock().await?;
+        if let Some(value) = &*guard {
+            return Ok(value.clone());
+        }
+        let value = init().await?;
+        *guard = Some(value.clone());
+        Ok(value)
+    }
+
+    /// Returns the value for `key`, when it was computed already.
+    pub fn get(&self, key: &K) -> Option<V> {
+        let cell = self.cells.lock().unwrap().get(key)?.clone();
+        let guard = cell.inner.try_lock().ok()?;
+        guard.clone()
+    }
+}
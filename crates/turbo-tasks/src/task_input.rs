@@ -1,6 +1,7 @@
 use std::{
     any::{type_name, Any},
     borrow::Cow,
+    collections::HashMap,
     fmt::{Debug, Display},
     future::Future,
     hash::Hash,
@@ -15,9 +16,10 @@ use crate::{
     backend::CellContent,
     id::{FunctionId, TraitTypeId},
     magic_any::MagicAny,
-    manager::{read_task_cell, read_task_output},
+    manager::{read_task_cell, read_task_output, read_task_output_untracked},
     registry, turbo_tasks,
     value::{TransientInstance, TransientValue, Value},
+    value_diagnostics::DiagnosticValue,
     value_type::TypedForInput,
     CellId, RawVc, TaskId, TraitType, Typed, ValueTypeId,
 };
@@ -66,10 +68,13 @@ impl Ord for SharedReference {
 }
 impl Debug for SharedReference {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_tuple("SharedReference")
-            .field(&self.0)
-            .field(&self.1)
-            .finish()
+        let mut t = f.debug_tuple("SharedReference");
+        t.field(&self.0);
+        match self.0.and_then(registry::get_value_diagnostics) {
+            Some(diagnostics) => t.field(&DiagnosticValue(diagnostics, &*self.1)),
+            None => t.field(&self.1),
+        };
+        t.finish()
     }
 }
 
@@ -80,7 +85,12 @@ impl Serialize for SharedReference {
     {
         if let SharedReference(Some(ty), arc) = self {
             let value_type = registry::get_value_type(*ty);
-            if let Some(serializable) = value_type.any_as_serializable(arc) {
+            if registry::is_redacted(*ty) {
+                Err(serde::ser::Error::custom(format!(
+                    "redacted values of type {} are not serializable",
+                    value_type.name
+                )))
+            } else if let Some(serializable) = value_type.any_as_serializable(arc) {
                 let mut t = serializer.serialize_tuple(2)?;
                 t.serialize_element(registry::get_value_type_global_name(*ty))?;
                 t.serialize_element(serializable)?;
@@ -204,9 +214,24 @@ impl<'de> Deserialize<'de> for TransientSharedValue {
     }
 }
 
-#[derive(Debug, Clone, PartialOrd, Ord)]
+#[derive(Clone, PartialOrd, Ord)]
 pub struct SharedValue(pub Option<ValueTypeId>, pub Arc<dyn MagicAny>);
 
+impl Debug for SharedValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut t = f.debug_tuple("SharedValue");
+        t.field(&self.0);
+        match self.0.and_then(registry::get_value_diagnostics) {
+            Some(diagnostics) => t.field(&DiagnosticValue(
+                diagnostics,
+                &*self.1.clone().magic_any_arc(),
+            )),
+            None => t.field(&self.1),
+        };
+        t.finish()
+    }
+}
+
 impl SharedValue {
     pub fn downcast<T: Any + Send + Sync>(self) -> Option<Arc<T>> {
         match Arc::downcast(self.1.magic_any_arc()) {
@@ -250,7 +275,12 @@ impl Serialize for SharedValue {
     {
         if let SharedValue(Some(ty), arc) = self {
             let value_type = registry::get_value_type(*ty);
-            if let Some(serializable) = value_type.magic_as_serializable(arc) {
+            if registry::is_redacted(*ty) {
+                Err(serde::ser::Error::custom(format!(
+                    "redacted values of type {} are not serializable",
+                    value_type.name
+                )))
+            } else if let Some(serializable) = value_type.magic_as_serializable(arc) {
                 let mut t = serializer.serialize_tuple(2)?;
                 t.serialize_element(registry::get_value_type_global_name(*ty))?;
                 t.serialize_element(serializable)?;
@@ -258,7 +288,7 @@ impl Serialize for SharedValue {
             } else {
                 Err(serde::ser::Error::custom(format!(
                     "{:?} is not serializable",
-                    arc
+                    self
                 )))
             }
         } else {
@@ -328,6 +358,7 @@ pub enum TaskInput {
     TaskCell(TaskId, CellId),
     List(Vec<TaskInput>),
     String(String),
+    Bytes(Vec<u8>),
     Bool(bool),
     Usize(usize),
     I32(i32),
@@ -468,6 +499,121 @@ impl TaskInput {
     pub fn is_nothing(&self) -> bool {
         matches!(self, TaskInput::Nothing)
     }
+
+    /// Whether the input contains a value with Vcs pointing to task outputs,
+    /// e. g. a `Value<T>` with an unresolved Vc field. Only serializable
+    /// values can be inspected.
+    pub fn has_unresolved_values(&self) -> bool {
+        match self {
+            TaskInput::SharedValue(value) => {
+                serde_json::to_value(value).map_or(false, |mut json| {
+                    let mut found = false;
+                    visit_task_outputs(&mut json, &mut |_, _| found = true);
+                    found
+                })
+            }
+            TaskInput::List(list) => list.iter().any(|i| i.has_unresolved_values()),
+            _ => false,
+        }
+    }
+
+    /// Resolves the Vcs pointing to task outputs in the values of the input,
+    /// like [TaskInput::resolve] does for Vc inputs. Values which can't be
+    /// serialized are returned as they are.
+    pub async fn resolve_values(self) -> Result<TaskInput> {
+        self.resolve_values_with(false).await
+    }
+
+    /// Like [TaskInput::resolve_values], but doesn't track the reads, e. g. to
+    /// inspect inputs outside of tasks.
+    ///
+    /// INVALIDATION: Be careful with this, it will not track dependencies, so
+    /// using it could break cache invalidation.
+    pub(crate) async fn resolve_values_untracked(self) -> Result<TaskInput> {
+        self.resolve_values_with(true).await
+    }
+
+    async fn resolve_values_with(self, untracked: bool) -> Result<TaskInput> {
+        match self {
+            TaskInput::SharedValue(value) => {
+                let mut json = match serde_json::to_value(&value) {
+                    Ok(json) => json,
+                    Err(_) => return Ok(TaskInput::SharedValue(value)),
+                };
+                let mut outputs = Vec::new();
+                visit_task_outputs(&mut json, &mut |_, task| outputs.push(task));
+                if outputs.is_empty() {
+                    return Ok(TaskInput::SharedValue(value));
+                }
+                let tt = turbo_tasks();
+                let mut resolved = HashMap::new();
+                for task in outputs {
+                    let mut current = RawVc::TaskOutput(task);
+                    while let RawVc::TaskOutput(task) = current {
+                        current = if untracked {
+                            read_task_output_untracked(&*tt, task, false).await?
+                        } else {
+                            read_task_output(&*tt, task, false).await?
+                        };
+                    }
+                    resolved.insert(task, serde_json::to_value(current)?);
+                }
+                visit_task_outputs(&mut json, &mut |json, task| {
+                    *json = resolved[&task].clone();
+                });
+                Ok(TaskInput::SharedValue(serde_json::from_value(json)?))
+            }
+            TaskInput::List(list) => {
+                fn resolve_all(
+                    list: Vec<TaskInput>,
+                    untracked: bool,
+                ) -> Pin<Box<dyn Future<Output = Result<Vec<TaskInput>>> + Send>> {
+                    use crate::TryJoinIterExt;
+                    Box::pin(
+                        list.into_iter()
+                            .map(|i| i.resolve_values_with(untracked))
+                            .try_join(),
+                    )
+                }
+                Ok(TaskInput::List(resolve_all(list, untracked).await?))
+            }
+            _ => Ok(self),
+        }
+    }
+
+    /// Checks that a Vc input which points to a cell has the type
+    /// `value_type`. This is used by the `FromTaskInput` implementations of
+    /// Vc types. Inputs which point to a task output can't be checked without
+    /// reading them.
+    pub fn ensure_value_type(&self, value_type: ValueTypeId, expected: &str) -> Result<()> {
+        if let TaskInput::TaskCell(_, CellId { type_id, .. }) = self {
+            if *type_id != value_type {
+                return Err(anyhow!(
+                    "invalid task input type, expected {} got a cell of {}",
+                    expected,
+                    registry::get_value_type(*type_id).name
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that a Vc input which points to a cell has a type which
+    /// implements `trait_type`. See [TaskInput::ensure_value_type].
+    pub fn ensure_trait_type(&self, trait_type: TraitTypeId, expected: &str) -> Result<()> {
+        if let TaskInput::TaskCell(_, CellId { type_id, .. }) = self {
+            let value_type = registry::get_value_type(*type_id);
+            if !value_type.has_trait(&trait_type) {
+                return Err(anyhow!(
+                    "invalid task input type, expected {} got a cell of {}, which doesn't \
+                     implement it",
+                    expected,
+                    value_type.name
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 pub trait FromTaskInput<'a>
@@ -479,6 +625,33 @@ where
     fn try_from(value: &'a TaskInput) -> Result<Self, Self::Error>;
 }
 
+/// Calls `visit` for every serialized [RawVc::TaskOutput] in the serialized
+/// value `json`.
+fn visit_task_outputs(
+    json: &mut serde_json::Value,
+    visit: &mut impl FnMut(&mut serde_json::Value, TaskId),
+) {
+    match json {
+        serde_json::Value::Object(map) => {
+            if let (1, Some(task)) = (map.len(), map.get("TaskOutput")) {
+                if let Ok(task) = serde_json::from_value(task.clone()) {
+                    visit(json, task);
+                    return;
+                }
+            }
+            for value in map.values_mut() {
+                visit_task_outputs(value, visit);
+            }
+        }
+        serde_json::Value::Array(list) => {
+            for value in list {
+                visit_task_outputs(value, visit);
+            }
+        }
+        _ => {}
+    }
+}
+
 impl From<RawVc> for TaskInput {
     fn from(raw_vc: RawVc) -> Self {
         match raw_vc {
@@ -511,13 +684,23 @@ impl Display for TaskInput {
                     .join(", ")
             ),
             TaskInput::String(s) => write!(f, "string {:?}", s),
+            TaskInput::Bytes(b) => write!(f, "{} bytes", b.len()),
             TaskInput::Bool(b) => write!(f, "bool {:?}", b),
             TaskInput::Usize(v) => write!(f, "usize {}", v),
             TaskInput::I32(v) => write!(f, "i32 {}", v),
             TaskInput::U32(v) => write!(f, "u32 {}", v),
             TaskInput::U64(v) => write!(f, "u64 {}", v),
             TaskInput::Nothing => write!(f, "nothing"),
-            TaskInput::SharedValue(_) => write!(f, "any value"),
+            TaskInput::SharedValue(SharedValue(ty, value)) => {
+                match ty.and_then(registry::get_value_diagnostics) {
+                    Some(diagnostics) => write!(
+                        f,
+                        "value {}",
+                        DiagnosticValue(diagnostics, &*value.clone().magic_any_arc())
+                    ),
+                    None => write!(f, "any value"),
+                }
+            }
             TaskInput::TransientSharedValue(_) => write!(f, "any transient value"),
             TaskInput::SharedReference(data) => {
                 write!(f, "shared reference with {}", data)
@@ -538,6 +721,12 @@ impl From<&str> for TaskInput {
     }
 }
 
+impl From<&[u8]> for TaskInput {
+    fn from(b: &[u8]) -> Self {
+        TaskInput::Bytes(b.to_vec())
+    }
+}
+
 impl From<bool> for TaskInput {
     fn from(b: bool) -> Self {
         TaskInput::Bool(b)
@@ -653,6 +842,43 @@ impl<'a> FromTaskInput<'a> for &'a str {
     }
 }
 
+/// Used by `#[turbo_tasks::function]` for `&str` arguments, so executions of
+/// the task share the string instead of cloning it.
+impl FromTaskInput<'_> for Arc<str> {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &TaskInput) -> Result<Self, Self::Error> {
+        match value {
+            TaskInput::String(str) => Ok(Arc::from(str.as_str())),
+            _ => Err(anyhow!("invalid task input type, expected string")),
+        }
+    }
+}
+
+impl<'a> FromTaskInput<'a> for &'a [u8] {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &'a TaskInput) -> Result<Self, Self::Error> {
+        match value {
+            TaskInput::Bytes(bytes) => Ok(bytes),
+            _ => Err(anyhow!("invalid task input type, expected bytes")),
+        }
+    }
+}
+
+/// Used by `#[turbo_tasks::function]` for `&[u8]` arguments, like
+/// [`Arc<str>`].
+impl FromTaskInput<'_> for Arc<[u8]> {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &TaskInput) -> Result<Self, Self::Error> {
+        match value {
+            TaskInput::Bytes(bytes) => Ok(Arc::from(bytes.as_slice())),
+            _ => Err(anyhow!("invalid task input type, expected bytes")),
+        }
+    }
+}
+
 impl FromTaskInput<'_> for bool {
     type Error = anyhow::Error;
 
@@ -0,0 +1,134 @@
+use std::{
+    any::Any,
+    backtrace::{Backtrace, BacktraceStatus},
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    future::Future,
+    panic::{set_hook, take_hook},
+    pin::Pin,
+    sync::Once,
+    task::{Context, Poll},
+};
+
+use pin_project_lite::pin_project;
+
+thread_local! {
+    /// The backtrace of the last panic of a task on this thread, captured by
+    /// the panic hook since it's no longer available once the panic is caught.
+    static PANIC_BACKTRACE: RefCell<Option<Backtrace>> = RefCell::new(None);
+
+    /// Whether a task is being executed on this thread.
+    static IN_TASK: Cell<bool> = Cell::new(false);
+}
+
+static INSTALL_HOOK: Once = Once::new();
+
+/// Installs a panic hook which captures the backtrace of panics during task
+/// execution, in addition to calling the previously installed hook. Like
+/// `std`, it only captures backtraces when `RUST_BACKTRACE` or
+/// `RUST_LIB_BACKTRACE` enable them. Only installed once per process.
+pub(crate) fn install_hook() {
+    INSTALL_HOOK.call_once(|| {
+        let previous_hook = take_hook();
+        set_hook(Box::new(move |info| {
+            if IN_TASK.with(|in_task| in_task.get()) {
+                let backtrace = Backtrace::capture();
+                if backtrace.status() == BacktraceStatus::Captured {
+                    PANIC_BACKTRACE.with(|cell| *cell.borrow_mut() = Some(backtrace));
+                }
+            }
+            previous_hook(info);
+        }));
+    });
+}
+
+pin_project! {
+    /// Executes a task, so the panic hook captures the backtraces of its
+    /// panics.
+    pub(crate) struct CaptureBacktrace<F> {
+        #[pin]
+        future: F,
+    }
+}
+
+impl<F> CaptureBacktrace<F> {
+    pub(crate) fn new(future: F) -> Self {
+        CaptureBacktrace { future }
+    }
+}
+
+impl<F: Future> Future for CaptureBacktrace<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        struct Reset(bool);
+
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                IN_TASK.with(|in_task| in_task.set(self.0));
+            }
+        }
+
+        let _reset = Reset(IN_TASK.with(|in_task| in_task.replace(true)));
+        self.project().future.poll(cx)
+    }
+}
+
+/// Describes a panic caught while executing the task described by
+/// `task_description`, including its message and backtrace, so a failed
+/// task can be reported like any other error while the process keeps
+/// running.
+pub(crate) fn describe_panic(
+    payload: Box<dyn Any + Send>,
+    task_description: &str,
+) -> Cow<'static, str> {
+    let message = match payload.downcast::<String>() {
+        Ok(owned) => Cow::Owned(*owned),
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(str) => Cow::Borrowed(*str),
+            Err(_) => Cow::Borrowed("unknown panic payload"),
+        },
+    };
+    let backtrace = PANIC_BACKTRACE.with(|backtrace| backtrace.borrow_mut().take());
+    match backtrace {
+        Some(backtrace) => {
+            format!("{message}\n  in {task_description}\n\nBacktrace:\n{backtrace}").into()
+        }
+        None => format!("{message}\n  in {task_description}").into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        backtrace::{Backtrace, BacktraceStatus},
+        panic::{catch_unwind, AssertUnwindSafe},
+    };
+
+    use futures::{executor::block_on, FutureExt};
+
+    use super::{describe_panic, install_hook, CaptureBacktrace};
+
+    #[test]
+    fn describes_task_panics() {
+        install_hook();
+        let payload = block_on(
+            AssertUnwindSafe(CaptureBacktrace::new(async { panic!("broken {}", 42) }))
+                .catch_unwind(),
+        )
+        .unwrap_err();
+        let description = describe_panic(payload, "my_task");
+        if Backtrace::capture().status() == BacktraceStatus::Captured {
+            assert!(description.starts_with("broken 42\n  in my_task\n\nBacktrace:\n"));
+        } else {
+            assert_eq!(description, "broken 42\n  in my_task");
+        }
+    }
+
+    #[test]
+    fn ignores_panics_outside_of_tasks() {
+        install_hook();
+        let payload = catch_unwind(|| panic!("broken")).unwrap_err();
+        assert_eq!(describe_panic(payload, "my_task"), "broken\n  in my_task");
+    }
+}
@@ -0,0 +1,128 @@
+//! Utilities for testing the incrementality of turbo-tasks functions.
+//!
+//! Tests should run on a current thread runtime (the default of
+//! `#[tokio::test]`) and avoid I/O, so tasks are scheduled in the same order
+//! on every run and get the same [TaskId](crate::TaskId)s.
+
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use anyhow::Result;
+
+use crate::{backend::Backend, get_invalidator, trace::TraceRawVcs, Invalidator, TurboTasks};
+
+/// Runs `future` as a root task in a new [TurboTasks] instance with the given
+/// backend and waits until all tasks scheduled by it have finished.
+pub async fn run_test<B: Backend + 'static, T: TraceRawVcs + Send + 'static>(
+    backend: B,
+    future: impl Future<Output = Result<T>> + Send + 'static,
+) -> Result<T> {
+    let tt = TurboTasks::new(backend);
+    let result = tt.run_once(future).await;
+    tt.stop_and_wait().await;
+    result
+}
+
+/// Counts how often the code calling [ExecutionCounter::count] is executed,
+/// e. g. to assert that a function isn't executed again when an unrelated
+/// input changes.
+///
+/// ```ignore
+/// static EXECUTIONS: ExecutionCounter = ExecutionCounter::new();
+///
+/// #[turbo_tasks::function]
+/// async fn double(input: U32Vc) -> Result<U32Vc> {
+///     EXECUTIONS.count();
+///     Ok(U32Vc::cell(*input.await? * 2))
+/// }
+/// ```
+pub struct ExecutionCounter {
+    count: AtomicUsize,
+}
+
+impl ExecutionCounter {
+    pub const fn new() -> Self {
+        Self {
+            count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Records an execution.
+    pub fn count(&self) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Returns the number of executions since the last call and resets it.
+    pub fn take(&self) -> usize {
+        self.count.swap(0, Ordering::SeqCst)
+    }
+
+    /// Asserts the number of executions since the last call and resets it.
+    #[track_caller]
+    pub fn assert_executions(&self, expected: usize) {
+        assert_eq!(self.take(), expected, "unexpected number of executions");
+    }
+}
+
+impl Default for ExecutionCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An input from outside of turbo-tasks, like the file system or the clock,
+/// which is controlled by the test. Changing it invalidates the tasks which
+/// read it.
+pub struct MockInput<T> {
+    inner: Mutex<MockInputInner<T>>,
+}
+
+struct MockInputInner<T> {
+    value: T,
+    invalidators: Vec<Invalidator>,
+}
+
+impl<T: Clone> MockInput<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            inner: Mutex::new(MockInputInner {
+                value,
+                invalidators: Vec::new(),
+            }),
+        }
+    }
+
+    /// Returns the current value. Must be called from a task, which is
+    /// invalidated when the value is changed.
+    pub fn get(&self) -> T {
+        let mut inner = self.inner.lock().unwrap();
+        inner.invalidators.push(get_invalidator());
+        inner.value.clone()
+    }
+
+    /// Changes the value and invalidates all tasks which have read it.
+    pub fn set(&self, value: T) {
+        for invalidator in self.replace(value) {
+            invalidator.invalidate();
+        }
+    }
+
+    /// Like [MockInput::set], with `reason` as the cause of the invalidations
+    /// for invalidation tracing.
+    pub fn set_with_reason(&self, value: T, reason: &str) {
+        for invalidator in self.replace(value) {
+            invalidator.invalidate_with_reason(reason);
+        }
+    }
+
+    fn replace(&self, value: T) -> Vec<Invalidator> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.value = value;
+        std::mem::take(&mut inner.invalidators)
+    }
+}
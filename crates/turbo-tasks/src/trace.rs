@@ -8,7 +8,7 @@ use std::{
 
 use indexmap::{IndexMap, IndexSet};
 
-use crate::RawVc;
+use crate::{Atom, RawVc};
 
 pub struct TraceRawVcsContext {
     list: Vec<RawVc>,
@@ -69,7 +69,7 @@ ignore!(
     AtomicBool,
     AtomicUsize
 );
-ignore!((), String, Duration, anyhow::Error);
+ignore!((), String, Atom, Duration, anyhow::Error);
 ignore!(Path, PathBuf);
 ignore!(serde_json::Value);
 
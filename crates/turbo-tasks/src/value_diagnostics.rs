@@ -0,0 +1,61 @@
+use std::{
+    any::{type_name, Any},
+    fmt::{self, Debug, Display, Formatter},
+};
+
+/// A value type with a concise representation in diagnostics, like task
+/// descriptions in traces and the graph viewer or error messages, e. g. to
+/// avoid printing large values in full. Enabled per value type with
+/// [crate::registry::register_concise_debug].
+pub trait ConciseDebug: Any + Send + Sync {
+    fn fmt_concise(&self, f: &mut Formatter<'_>) -> fmt::Result;
+}
+
+type FmtFn = fn(&(dyn Any + Send + Sync), &mut Formatter<'_>) -> fmt::Result;
+
+/// How the values of a value type are shown in diagnostics.
+#[derive(Clone, Copy)]
+pub(crate) enum ValueDiagnostics {
+    /// See [ConciseDebug].
+    Concise(FmtFn),
+    /// Sensitive values, e. g. secrets from env variables. They are shown as
+    /// `<redacted>` and can't be serialized, so persisted caches skip them.
+    Redacted,
+}
+
+fn any_fmt_concise<T: ConciseDebug>(
+    value: &(dyn Any + Send + Sync),
+    f: &mut Formatter<'_>,
+) -> fmt::Result {
+    if let Some(value) = value.downcast_ref::<T>() {
+        return value.fmt_concise(f);
+    }
+    panic!(
+        "any_fmt_concise::<{}> called with invalid type",
+        type_name::<T>()
+    );
+}
+
+impl ValueDiagnostics {
+    pub fn concise<T: ConciseDebug>() -> Self {
+        ValueDiagnostics::Concise(any_fmt_concise::<T>)
+    }
+}
+
+/// Shows a value with the [ValueDiagnostics] of its value type.
+pub(crate) struct DiagnosticValue<'a>(pub ValueDiagnostics, pub &'a (dyn Any + Send + Sync));
+
+impl Display for DiagnosticValue<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            ValueDiagnostics::Concise(fmt) => fmt(self.1, f),
+            ValueDiagnostics::Redacted => write!(f, "<redacted>"),
+        }
+    }
+}
+
+impl Debug for DiagnosticValue<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
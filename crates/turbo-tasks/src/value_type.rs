@@ -193,6 +193,12 @@ impl ValueType {
         }
     }
 
+    /// Returns true when values of this type can be serialized, e. g. to be
+    /// persisted or shared with other processes.
+    pub fn is_serializable(&self) -> bool {
+        self.any_serialization.is_some()
+    }
+
     pub fn get_magic_deserialize_seed(&self) -> Option<MagicAnyDeserializeSeed> {
         self.magic_serialization.map(|s| s.1)
     }
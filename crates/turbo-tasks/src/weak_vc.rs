@@ -0,0 +1,133 @@
+use std::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    manager::{read_task_cell_untracked, read_task_output_untracked},
+    trace::{TraceRawVcs, TraceRawVcsContext},
+    turbo_tasks, RawVc, TaskId,
+};
+
+/// A reference to a Vc which doesn't keep the referenced cell alive.
+///
+/// Reading through a [WeakVc] doesn't register a dependency, and it's skipped
+/// when tracing [RawVc]s, so it doesn't add an edge to the task graph. This
+/// allows observers like stats, tracing or graph visualization to reference
+/// tasks without preventing the memory held by them from being reclaimed.
+pub struct WeakVc<T> {
+    node: RawVc,
+    phantom: PhantomData<T>,
+}
+
+impl<T: From<RawVc> + Into<RawVc>> WeakVc<T> {
+    pub fn new(vc: T) -> Self {
+        Self {
+            node: vc.into(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// The task that owns the referenced cell or output.
+    pub fn task_id(&self) -> TaskId {
+        self.node.get_task_id()
+    }
+
+    /// Returns the referenced Vc when its cell still has content, or `None`
+    /// when it has been reclaimed.
+    ///
+    /// INVALIDATION: This doesn't track dependencies, so the caller won't be
+    /// invalidated when the cell is reclaimed or changes. Reading the returned
+    /// Vc registers a dependency as usual.
+    pub async fn upgrade(&self) -> Result<Option<T>> {
+        let tt = turbo_tasks();
+        tt.notify_scheduled_tasks();
+        let mut current = self.node;
+        loop {
+            match current {
+                RawVc::TaskOutput(task) => {
+                    current = read_task_output_untracked(&*tt, task, false).await?;
+                }
+                RawVc::TaskCell(task, index) => {
+                    let content = read_task_cell_untracked(&*tt, task, index).await?;
+                    return Ok(content.0.map(|_| self.node.into()));
+                }
+            }
+        }
+    }
+}
+
+impl<T: From<RawVc> + Into<RawVc>> From<T> for WeakVc<T> {
+    fn from(vc: T) -> Self {
+        Self::new(vc)
+    }
+}
+
+impl<T> Clone for WeakVc<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for WeakVc<T> {}
+
+impl<T> PartialEq for WeakVc<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.node == other.node
+    }
+}
+
+impl<T> Eq for WeakVc<T> {}
+
+impl<T> Hash for WeakVc<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.node.hash(state);
+    }
+}
+
+impl<T> Debug for WeakVc<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("WeakVc").field(&self.node).finish()
+    }
+}
+
+impl<T> TraceRawVcs for WeakVc<T> {
+    fn trace_raw_vcs(&self, _context: &mut TraceRawVcsContext) {
+        // Weak references are not followed, so they don't keep the referenced
+        // cell alive.
+    }
+}
+
+impl<T> Serialize for WeakVc<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.node.serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for WeakVc<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        Ok(Self {
+            node: RawVc::deserialize(deserializer)?,
+            phantom: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WeakVc;
+    use crate::{trace::TraceRawVcs, RawVc, TaskId};
+
+    #[test]
+    fn weak_references_are_not_traced() {
+        let strong = RawVc::TaskOutput(TaskId::from(1));
+        let weak = WeakVc::new(RawVc::TaskOutput(TaskId::from(2)));
+        // Only the strong reference marks its task as used, so the cell of the
+        // weak reference can be collected.
+        assert_eq!((strong, weak).get_raw_vcs(), vec![strong]);
+    }
+}
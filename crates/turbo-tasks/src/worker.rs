@@ -0,0 +1,256 @@
+//! Execution of CPU heavy work in a pool of worker processes.
+//!
+//! Worker processes are spawned from the current executable, so the same
+//! worker functions are available in both processes. Executables which use a
+//! [WorkerPool] need to register their worker functions and call
+//! [run_worker_if_requested] at the start of `main`, before any other work is
+//! done.
+//!
+//! Workers connect to the main process over a local TCP connection and
+//! authenticate with a random token, which is passed to them in the
+//! environment, so other processes can't pose as workers.
+//!
+//! Inputs and outputs of worker functions are serialized, so only work on
+//! plain data, e. g. transforming source code into source code, can be
+//! dispatched to workers. Values referencing tasks need to be read before.
+//!
+//! No turbopack functions are dispatched to workers yet: there is no minifier
+//! in this tree, and parse results keep the SWC `Globals`, `SourceMap` and
+//! comments of the parse, which can't be sent to another process.
+
+use std::{
+    env,
+    io::{Read, Write},
+    net::TcpStream,
+    process::{self, Stdio},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Serialize,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream as AsyncTcpStream},
+    process::{Child, Command},
+    select,
+    sync::Semaphore,
+    time::sleep,
+};
+
+/// The environment variable containing the port a worker process connects to.
+const WORKER_PORT_ENV: &str = "TURBO_TASKS_WORKER_PORT";
+
+/// The environment variable containing the token a worker process
+/// authenticates with.
+const WORKER_TOKEN_ENV: &str = "TURBO_TASKS_WORKER_TOKEN";
+
+const TOKEN_LEN: usize = 32;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+type WorkerFunction = Box<dyn Fn(&[u8]) -> Result<Vec<u8>> + Send + Sync>;
+
+static WORKER_FUNCTIONS: Lazy<DashMap<String, WorkerFunction>> = Lazy::new(DashMap::new);
+
+/// Registers a function which can be executed by worker processes. It must be
+/// registered under the same name in the main process and in the workers.
+pub fn register_worker_function<I: DeserializeOwned + 'static, O: Serialize + 'static>(
+    name: &str,
+    func: fn(I) -> Result<O>,
+) {
+    WORKER_FUNCTIONS.insert(
+        name.to_string(),
+        Box::new(move |request| {
+            let (_, input): (IgnoredAny, I) =
+                serde_json::from_slice(request).context("deserializing worker input")?;
+            serde_json::to_vec(&func(input)?).context("serializing worker output")
+        }),
+    );
+}
+
+/// Turns the current process into a worker when it was spawned by a
+/// [WorkerPool]. The worker executes requests until the main process closes
+/// the connection and then exits the process. Returns immediately otherwise,
+/// or when the worker fails.
+pub fn run_worker_if_requested() -> Result<()> {
+    let port = match env::var(WORKER_PORT_ENV) {
+        Ok(port) => port,
+        Err(_) => return Ok(()),
+    };
+    let token = env::var(WORKER_TOKEN_ENV).context("missing worker token")?;
+    run_worker(&port, &token).context("worker process failed")?;
+    process::exit(0)
+}
+
+fn run_worker(port: &str, token: &str) -> Result<()> {
+    let mut connection =
+        TcpStream::connect(format!("127.0.0.1:{port}")).context("connecting to main process")?;
+    write_packet(&mut connection, token.as_bytes())?;
+    loop {
+        let request = match read_packet(&mut connection) {
+            Ok(request) => request,
+            // The main process closed the connection.
+            Err(_) => return Ok(()),
+        };
+        let (name, _): (String, IgnoredAny) =
+            serde_json::from_slice(&request).context("deserializing worker request")?;
+        let result = match WORKER_FUNCTIONS.get(&name) {
+            Some(func) => (*func)(&request),
+            None => Err(anyhow!("worker function {} is not registered", name)),
+        };
+        let response = match result {
+            Ok(output) => [&[0u8][..], &output].concat(),
+            Err(err) => [&[1u8][..], format!("{:?}", err).as_bytes()].concat(),
+        };
+        write_packet(&mut connection, &response)?;
+    }
+}
+
+fn read_packet(connection: &mut TcpStream) -> Result<Vec<u8>> {
+    let mut len = [0; 4];
+    connection.read_exact(&mut len)?;
+    let mut packet = vec![0; u32::from_be_bytes(len) as usize];
+    connection.read_exact(&mut packet)?;
+    Ok(packet)
+}
+
+fn write_packet(connection: &mut TcpStream, packet: &[u8]) -> Result<()> {
+    let len: u32 = packet
+        .len()
+        .try_into()
+        .context("packet length does not fit into u32")?;
+    connection.write_all(&len.to_be_bytes())?;
+    connection.write_all(packet)?;
+    Ok(())
+}
+
+/// Accepts the first connection which authenticates with `token`. Other
+/// connections are closed.
+async fn accept_worker(listener: &TcpListener, token: &str) -> Result<AsyncTcpStream> {
+    loop {
+        let (mut connection, _) = listener.accept().await.context("accepting connection")?;
+        if authenticate(&mut connection, token).await {
+            return Ok(connection);
+        }
+    }
+}
+
+async fn authenticate(connection: &mut AsyncTcpStream, token: &str) -> bool {
+    match connection.read_u32().await {
+        Ok(len) if len as usize == token.len() => {}
+        _ => return false,
+    }
+    let mut received = vec![0; token.len()];
+    connection.read_exact(&mut received).await.is_ok() && received == token.as_bytes()
+}
+
+struct WorkerProcess {
+    // This is used for drop, which kills the process
+    #[allow(dead_code)]
+    child: Child,
+    connection: AsyncTcpStream,
+}
+
+impl WorkerProcess {
+    async fn spawn() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .context("binding to a port")?;
+        let port = listener.local_addr().context("getting port")?.port();
+        let token: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(TOKEN_LEN)
+            .map(char::from)
+            .collect();
+        let mut cmd = Command::new(env::current_exe().context("getting current executable")?);
+        cmd.env(WORKER_PORT_ENV, port.to_string());
+        cmd.env(WORKER_TOKEN_ENV, &token);
+        cmd.stdin(Stdio::null());
+        cmd.stdout(Stdio::inherit());
+        cmd.stderr(Stdio::inherit());
+        cmd.kill_on_drop(true);
+        let mut child = cmd.spawn().context("spawning worker process")?;
+        let (connection, _) = select! {
+            connection = accept_worker(&listener, &token) => connection?,
+            status = child.wait() => bail!("worker process exited before we could connect to it: {:?}", status),
+            _ = sleep(CONNECT_TIMEOUT) => bail!("timed out waiting for the worker process to connect ({:?} timeout)", CONNECT_TIMEOUT),
+        };
+        Ok(Self { child, connection })
+    }
+
+    async fn request(&mut self, request: &[u8]) -> Result<Vec<u8>> {
+        self.connection
+            .write_u32(
+                request
+                    .len()
+                    .try_into()
+                    .context("packet length does not fit into u32")?,
+            )
+            .await
+            .context("writing packet length")?;
+        self.connection
+            .write_all(request)
+            .await
+            .context("writing packet data")?;
+        let len = self
+            .connection
+            .read_u32()
+            .await
+            .context("reading packet length")?;
+        let mut response = vec![0; len as usize];
+        self.connection
+            .read_exact(&mut response)
+            .await
+            .context("reading packet data")?;
+        Ok(response)
+    }
+}
+
+/// A pool of worker processes executing registered worker functions.
+///
+/// The pool will spawn processes when needed and reuses idle ones. It will
+/// never spawn more than `concurrency` processes.
+pub struct WorkerPool {
+    processes: Arc<Mutex<Vec<WorkerProcess>>>,
+    semaphore: Arc<Semaphore>,
+}
+
+impl WorkerPool {
+    pub fn new(concurrency: usize) -> Self {
+        Self {
+            processes: Arc::new(Mutex::new(Vec::new())),
+            semaphore: Arc::new(Semaphore::new(concurrency)),
+        }
+    }
+
+    /// Executes the worker function registered as `name` with `input` in a
+    /// worker process.
+    pub async fn run<I: Serialize, O: DeserializeOwned>(&self, name: &str, input: I) -> Result<O> {
+        let request = serde_json::to_vec(&(name, input)).context("serializing worker input")?;
+        let _permit = self.semaphore.acquire().await?;
+        let popped = self.processes.lock().unwrap().pop();
+        let mut process = match popped {
+            Some(process) => process,
+            None => WorkerProcess::spawn().await?,
+        };
+        // A process which failed is not reused.
+        let response = process.request(&request).await?;
+        self.processes.lock().unwrap().push(process);
+        match response.split_first() {
+            Some((0, output)) => serde_json::from_slice(output).context("deserializing output"),
+            Some((_, error)) => bail!(
+                "worker function {} failed: {}",
+                name,
+                String::from_utf8_lossy(error)
+            ),
+            None => bail!("empty response from worker process"),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use anyhow::{bail, Result};
+use turbo_tasks::worker::{register_worker_function, run_worker_if_requested, WorkerPool};
+
+fn double(value: u32) -> Result<u32> {
+    Ok(value * 2)
+}
+
+fn fail(message: String) -> Result<()> {
+    bail!("{message}")
+}
+
+fn main() -> Result<()> {
+    register_worker_function("double", double);
+    register_worker_function("fail", fail);
+    run_worker_if_requested()?;
+
+    tokio::runtime::Runtime::new()?.block_on(async {
+        let pool = WorkerPool::new(2);
+        let results = futures::future::try_join_all(
+            (0..4u32).map(|value| pool.run::<_, u32>("double", value)),
+        )
+        .await?;
+        assert_eq!(results, vec![0, 2, 4, 6]);
+
+        let error = pool
+            .run::<_, ()>("fail", "broken".to_string())
+            .await
+            .unwrap_err();
+        assert!(format!("{error}").contains("broken"));
+
+        assert!(pool.run::<_, ()>("missing", ()).await.is_err());
+        // Failed functions don't break the worker processes.
+        assert_eq!(pool.run::<_, u32>("double", 21).await?, 42);
+        Ok(())
+    })
+}
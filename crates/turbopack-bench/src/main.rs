@@ -0,0 +1,240 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    net::{IpAddr, Ipv4Addr},
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use futures::{SinkExt, StreamExt};
+use next_dev::{register, NextDevServer, NextDevServerBuilder};
+use serde::Serialize;
+use tokio::time::timeout;
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use turbo_tasks::TurboTasks;
+use turbo_tasks_memory::MemoryBackend;
+use turbopack_create_test_app::test_app_builder::{PackageJsonConfig, TestAppBuilder};
+
+#[global_allocator]
+static ALLOC: turbo_malloc::TurboMalloc = turbo_malloc::TurboMalloc;
+
+/// How long the update stream must be silent before a change is made, so
+/// updates from the previous change are not attributed to the next one.
+const SETTLE_DURATION: Duration = Duration::from_millis(500);
+
+const UPDATE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// The number of modules in the generated app.
+    #[clap(short, long, value_parser, default_value_t = 1000)]
+    modules: usize,
+
+    /// How flat the component tree of the generated app is. Lower values
+    /// create deeper trees.
+    #[clap(short, long, value_parser, default_value_t = 5)]
+    flatness: usize,
+
+    /// The number of file changes to measure the update latency for.
+    #[clap(long, value_parser, default_value_t = 10)]
+    hmr_samples: usize,
+
+    /// The page that is requested and subscribed to.
+    #[clap(long, value_parser, default_value = "page")]
+    page: String,
+
+    /// Run `npm install` in the generated app, so its dependencies can be
+    /// resolved. Without it the measurements include resolve errors.
+    #[clap(long)]
+    install: bool,
+
+    /// Write the JSON report to this file instead of stdout.
+    #[clap(short, long, value_parser)]
+    output: Option<PathBuf>,
+}
+
+/// The measurements of a single run, emitted as JSON.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Report {
+    modules: usize,
+    flatness: usize,
+    /// From starting the server until the content source is compiled.
+    ready_ms: u64,
+    /// From starting the server until the page has been served.
+    cold_start_ms: u64,
+    /// From writing a change to a module until the first update for the page
+    /// was received, per change.
+    hmr_latency_ms: Vec<u64>,
+    /// The resident memory of the process after all measurements, if it can
+    /// be determined on this platform.
+    memory_bytes: Option<u64>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    register();
+
+    let app = TestAppBuilder {
+        module_count: args.modules,
+        directories_count: args.modules / 20,
+        flatness: args.flatness,
+        package_json: Some(PackageJsonConfig::default()),
+        ..Default::default()
+    }
+    .build()?;
+    if args.install {
+        npm_install(app.path())?;
+    }
+    let project_dir = app
+        .path()
+        .to_str()
+        .context("app directory contains invalid characters")?
+        .to_string();
+
+    let start = Instant::now();
+    let mut server = NextDevServerBuilder::new(
+        TurboTasks::new(MemoryBackend::new()),
+        project_dir.clone(),
+        project_dir,
+    )
+    .entry_request("src/index".into())
+    .hostname(IpAddr::V4(Ipv4Addr::LOCALHOST))
+    .port(0)
+    .build()
+    .await?;
+    server.wait_until_ready().await?;
+    let ready = start.elapsed();
+    let html = fetch(&server, &args.page).await?;
+    let cold_start = start.elapsed();
+
+    let mut updates = subscribe(&server, &args.page, &html).await?;
+    // A module in the middle of the tree, so the update is neither trivial nor
+    // the whole app.
+    let (changed_module, _) = &app.modules()[app.modules().len() / 2];
+    let mut hmr_latency = Vec::with_capacity(args.hmr_samples);
+    for i in 0..args.hmr_samples {
+        settle(&mut updates).await?;
+        let change_start = Instant::now();
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(changed_module)
+            .context("opening module to change")?;
+        writeln!(file, "// change {i}")?;
+        drop(file);
+        wait_for_update(&mut updates).await?;
+        hmr_latency.push(change_start.elapsed().as_millis() as u64);
+    }
+
+    let report = Report {
+        modules: args.modules,
+        flatness: args.flatness,
+        ready_ms: ready.as_millis() as u64,
+        cold_start_ms: cold_start.as_millis() as u64,
+        hmr_latency_ms: hmr_latency,
+        memory_bytes: resident_memory(),
+    };
+    let report = serde_json::to_string_pretty(&report)?;
+    match &args.output {
+        Some(output) => fs::write(output, report).context("writing report")?,
+        None => println!("{report}"),
+    }
+
+    server.shutdown();
+    server.wait().await?;
+    Ok(())
+}
+
+fn npm_install(dir: &Path) -> Result<()> {
+    let status = Command::new(if cfg!(windows) { "npm.cmd" } else { "npm" })
+        .args(["install", "--prefer-offline", "--loglevel=error"])
+        .current_dir(dir)
+        .status()
+        .context("running npm install")?;
+    if !status.success() {
+        bail!("npm install failed with {status}");
+    }
+    Ok(())
+}
+
+async fn fetch(server: &NextDevServer, page: &str) -> Result<String> {
+    let uri = format!("http://{}/{page}", server.addr()).parse()?;
+    let response = hyper::Client::new().get(uri).await?;
+    if !response.status().is_success() {
+        bail!("requesting /{page} failed with {}", response.status());
+    }
+    let body = hyper::body::to_bytes(response.into_body()).await?;
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+type Updates = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Subscribes to updates of the page and the scripts it loads, like the HMR
+/// client in the browser does.
+async fn subscribe(server: &NextDevServer, page: &str, html: &str) -> Result<Updates> {
+    let (mut updates, _) = connect_async(format!("ws://{}/turbopack-hmr", server.addr()))
+        .await
+        .context("connecting to the HMR endpoint")?;
+    for path in std::iter::once(page).chain(script_paths(html)) {
+        let message = serde_json::json!({ "type": "subscribe", "path": path });
+        updates.send(Message::Text(message.to_string())).await?;
+    }
+    Ok(updates)
+}
+
+/// Returns the paths of same origin scripts in `html`, without the leading
+/// slash.
+fn script_paths(html: &str) -> impl Iterator<Item = &str> {
+    html.split("<script").skip(1).filter_map(|tag| {
+        let tag = &tag[..tag.find('>')?];
+        let src = &tag[tag.find("src=\"")? + "src=\"".len()..];
+        let src = &src[..src.find('"')?];
+        src.strip_prefix('/').filter(|src| !src.starts_with('/'))
+    })
+}
+
+/// Drains updates until none has been received for [SETTLE_DURATION].
+async fn settle(updates: &mut Updates) -> Result<()> {
+    while let Ok(message) = timeout(SETTLE_DURATION, updates.next()).await {
+        if message.is_none() {
+            bail!("HMR connection closed");
+        }
+    }
+    Ok(())
+}
+
+/// Waits for an update which changes code. Updates which only report issues
+/// are skipped.
+async fn wait_for_update(updates: &mut Updates) -> Result<()> {
+    loop {
+        let message = timeout(UPDATE_TIMEOUT, updates.next())
+            .await
+            .context("timed out waiting for an update")?
+            .context("HMR connection closed")??;
+        if let Message::Text(text) = message {
+            let update: serde_json::Value = serde_json::from_str(&text)?;
+            if update["type"] != "issues" {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn resident_memory() -> Option<u64> {
+    // The second field of statm is the number of resident pages, which are
+    // 4 KiB on all common linux targets.
+    let statm = fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    Some(pages * 4096)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn resident_memory() -> Option<u64> {
+    None
+}
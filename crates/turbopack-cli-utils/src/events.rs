@@ -0,0 +1,116 @@
+use std::{
+    io::{stdout, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use serde::Serialize;
+use turbopack_core::issue::IssueSeverity;
+
+/// A machine readable event emitted by a dev server, so editor extensions and
+/// wrapper tools can integrate with it without scraping the log output.
+///
+/// Events are written as one JSON object per line, tagged by `type`.
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Event<'a> {
+    /// The server is listening for requests. Servers listening on a unix
+    /// domain socket have no URL.
+    Ready {
+        address: String,
+        url: Option<&'a str>,
+    },
+    /// The first compilation after starting the server finished.
+    #[serde(rename_all = "camelCase")]
+    InitialCompilation { duration_ms: u64 },
+    /// A recompilation caused by changes finished.
+    #[serde(rename_all = "camelCase")]
+    Updated {
+        duration_ms: u64,
+        tasks: usize,
+        errors: usize,
+        warnings: usize,
+    },
+    /// A new issue was reported.
+    Issue {
+        severity: IssueSeverity,
+        category: &'a str,
+        context: &'a str,
+        title: &'a str,
+        description: &'a str,
+        source: Option<EventSourcePos>,
+    },
+    /// A response was sent for a request. Of its duration, `compile_ms` were
+    /// spent waiting for the content to be computed and `io_ms` reading the
+    /// request.
+    #[serde(rename_all = "camelCase")]
+    RequestServed {
+        method: &'a str,
+        path: &'a str,
+        status: u16,
+        duration_ms: u64,
+        compile_ms: u64,
+        io_ms: u64,
+    },
+    /// An update was sent to a client subscribed to `path`.
+    HmrUpdate { path: &'a str, restart: bool },
+}
+
+/// A 1-based line and 0-based column in a source file.
+#[derive(Serialize, Debug)]
+pub struct EventSourcePos {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Writes [Event]s as JSON lines, to stdout or a unix socket.
+///
+/// Clones write to the same destination.
+#[derive(Clone)]
+pub struct EventSink {
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl EventSink {
+    pub fn stdout() -> Self {
+        Self::new(Box::new(stdout()))
+    }
+
+    /// Connects to a unix socket which is listened on by the consumer of the
+    /// events, e. g. an editor extension.
+    #[cfg(unix)]
+    pub fn connect_unix(path: &Path) -> Result<Self> {
+        use anyhow::Context;
+
+        let stream = std::os::unix::net::UnixStream::connect(path)
+            .with_context(|| format!("connecting to event socket {}", path.display()))?;
+        Ok(Self::new(Box::new(stream)))
+    }
+
+    #[cfg(not(unix))]
+    pub fn connect_unix(path: &Path) -> Result<Self> {
+        anyhow::bail!(
+            "event socket {} is not supported on this platform",
+            path.display()
+        )
+    }
+
+    fn new(writer: Box<dyn Write + Send>) -> Self {
+        Self {
+            writer: Arc::new(Mutex::new(writer)),
+        }
+    }
+
+    /// Writes an event. Failures are ignored, as a consumer going away must
+    /// not affect the server.
+    pub fn emit(&self, event: &Event<'_>) {
+        let mut line = match serde_json::to_vec(event) {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+        line.push(b'\n');
+        let mut writer = self.writer.lock().unwrap();
+        let _ = writer.write_all(&line).and_then(|_| writer.flush());
+    }
+}
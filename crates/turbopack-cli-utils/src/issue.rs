@@ -20,6 +20,8 @@ use turbopack_core::issue::{
     PlainIssueSource,
 };
 
+use crate::events::{Event, EventSink, EventSourcePos};
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct IssueSeverityCliOption(pub IssueSeverity);
 
@@ -294,6 +296,12 @@ pub struct LogOptions {
     pub show_all: bool,
     pub log_detail: bool,
     pub log_level: IssueSeverity,
+    /// Don't print issues as grouped, styled text, as they are only consumed
+    /// as [Event]s written to stdout.
+    pub json: bool,
+    /// Print every served request with its timings, instead of only failed
+    /// ones.
+    pub log_requests: bool,
 }
 
 /// Tracks the state of currently seen issues.
@@ -325,6 +333,9 @@ struct SeenIssues {
     /// "seen" and will not be relogged. Once the count reaches zero, the
     /// issue is removed and the next time its seen it will be considered new.
     issues_count: HashMap<u64, usize>,
+
+    /// The severity of every currently seen issue.
+    severities: HashMap<u64, IssueSeverity>,
 }
 
 impl SeenIssues {
@@ -334,16 +345,17 @@ impl SeenIssues {
 
     /// Synchronizes state between the issues previously pulled from this
     /// source, to the issues now pulled.
-    fn new_ids(&mut self, source: RawVc, issue_ids: HashSet<u64>) -> HashSet<u64> {
+    fn new_ids(&mut self, source: RawVc, issues: HashMap<u64, IssueSeverity>) -> HashSet<u64> {
         let old = self.source_to_issue_ids.entry(source).or_default();
 
         // difference is the issues that were never counted before.
-        let difference = issue_ids
+        let difference = issues
             .iter()
-            .filter(|id| match self.issues_count.entry(**id) {
+            .filter(|(id, severity)| match self.issues_count.entry(**id) {
                 Entry::Vacant(e) => {
                     // If the issue not currently counted, then it's new and should be logged.
                     e.insert(1);
+                    self.severities.insert(**id, **severity);
                     true
                 }
                 Entry::Occupied(mut e) => {
@@ -360,7 +372,7 @@ impl SeenIssues {
                     false
                 }
             })
-            .cloned()
+            .map(|(id, _)| *id)
             .collect::<HashSet<_>>();
 
         // Old now contains only the ids that were not present in the new issue_ids.
@@ -373,6 +385,7 @@ impl SeenIssues {
                         // If this was the last counter of the issue, then we need to prune the
                         // value to free memory.
                         e.remove();
+                        self.severities.remove(id);
                     } else {
                         // Another source counted the issue, and it must not be relogged until all
                         // sources remove it.
@@ -382,9 +395,31 @@ impl SeenIssues {
             }
         }
 
-        *old = issue_ids;
+        *old = issues.into_keys().collect();
         difference
     }
+
+    fn counts(&self) -> IssueCounts {
+        let mut counts = IssueCounts::default();
+        for severity in self.severities.values() {
+            match severity {
+                IssueSeverity::Bug | IssueSeverity::Fatal | IssueSeverity::Error => {
+                    counts.errors += 1
+                }
+                IssueSeverity::Warning => counts.warnings += 1,
+                _ => {}
+            }
+        }
+        counts
+    }
+}
+
+/// The number of currently unresolved issues, across all sources.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IssueCounts {
+    /// Bugs, fatal issues and errors.
+    pub errors: usize,
+    pub warnings: usize,
 }
 
 /// Logs emitted issues to console logs, deduplicating issues between peeks of
@@ -397,6 +432,9 @@ pub struct ConsoleUi {
 
     #[turbo_tasks(trace_ignore, debug_ignore)]
     seen: Arc<Mutex<SeenIssues>>,
+
+    #[turbo_tasks(trace_ignore, debug_ignore)]
+    events: Option<EventSink>,
 }
 
 impl PartialEq for ConsoleUi {
@@ -410,8 +448,29 @@ impl ConsoleUi {
         ConsoleUi {
             options,
             seen: Arc::new(Mutex::new(SeenIssues::new())),
+            events: None,
         }
     }
+
+    /// Also emits new issues as [Event]s to `events`.
+    pub fn with_events(mut self, events: EventSink) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    pub fn events(&self) -> Option<&EventSink> {
+        self.events.as_ref()
+    }
+
+    pub fn log_requests(&self) -> bool {
+        self.options.log_requests
+    }
+
+    /// Returns the number of issues which were reported and are not resolved
+    /// yet.
+    pub fn issue_counts(&self) -> IssueCounts {
+        self.seen.lock().unwrap().counts()
+    }
 }
 
 #[turbo_tasks::value(transparent)]
@@ -438,6 +497,7 @@ impl ConsoleUiVc {
             show_all,
             log_detail,
             log_level,
+            json,
             ..
         } = &this.options;
         let mut grouped_issues: GroupedIssues = HashMap::new();
@@ -455,8 +515,8 @@ impl ConsoleUiVc {
 
         let issue_ids = issues
             .iter()
-            .map(|(_, _, _, id)| *id)
-            .collect::<HashSet<_>>();
+            .map(|(plain_issue, _, _, id)| (*id, plain_issue.severity))
+            .collect::<HashMap<_, _>>();
         let mut new_ids = this.seen.lock().unwrap().new_ids(source, issue_ids);
 
         let mut has_fatal = false;
@@ -473,6 +533,24 @@ impl ConsoleUiVc {
             let category = &plain_issue.category;
             let title = &plain_issue.title;
             has_fatal = severity == IssueSeverity::Fatal;
+            if let Some(events) = &this.events {
+                if severity <= log_level {
+                    events.emit(&Event::Issue {
+                        severity,
+                        category,
+                        context: &context_path,
+                        title,
+                        description: &plain_issue.description,
+                        source: plain_issue.source.as_ref().map(|source| EventSourcePos {
+                            line: source.start.line + 1,
+                            column: source.start.column,
+                        }),
+                    });
+                }
+            }
+            if json {
+                continue;
+            }
             let severity_map = grouped_issues
                 .entry(severity)
                 .or_insert_with(Default::default);
@@ -2,6 +2,7 @@
 #![feature(min_specialization)]
 #![feature(round_char_boundary)]
 
+pub mod events;
 pub mod issue;
 
 pub fn register() {
@@ -0,0 +1,50 @@
+use anyhow::Result;
+use turbo_tasks_fs::glob::GlobVc;
+
+/// Configures how assets are split into chunks.
+#[turbo_tasks::value(shared)]
+#[derive(Debug, Clone)]
+pub struct ChunkingConfig {
+    /// Places assets from `node_modules` into separate vendor chunks, as these
+    /// are less likely to change than application code.
+    pub split_vendor: bool,
+    /// When two chunks of a chunk group share at least this number of chunk
+    /// items, these are moved into a shared chunk instead of merging the
+    /// chunks, so that shared modules are only included once.
+    pub min_shared_chunk_items: Option<usize>,
+    /// Assets matching one of these globs are placed into chunks together with
+    /// other assets matching the same glob only, similar to webpack's
+    /// `cacheGroups`. Globs are matched against the path relative to the
+    /// context path of the chunking context. The first matching glob wins.
+    pub cache_groups: Vec<GlobVc>,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        ChunkingConfig {
+            split_vendor: true,
+            min_shared_chunk_items: None,
+            cache_groups: Vec::new(),
+        }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkingConfigVc {
+    #[turbo_tasks::function]
+    pub fn default() -> Self {
+        ChunkingConfig::default().cell()
+    }
+}
+
+impl ChunkingConfig {
+    /// Returns the index of the cache group the asset at `path` belongs to.
+    pub async fn cache_group(&self, path: &str) -> Result<Option<usize>> {
+        for (i, glob) in self.cache_groups.iter().enumerate() {
+            if glob.await?.execute(path) {
+                return Ok(Some(i));
+            }
+        }
+        Ok(None)
+    }
+}
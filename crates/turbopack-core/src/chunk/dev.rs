@@ -6,7 +6,7 @@ use turbo_tasks::{
 use turbo_tasks_fs::FileSystemPathVc;
 use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64};
 
-use super::{ChunkingContext, ChunkingContextVc};
+use super::{config::ChunkingConfigVc, ChunkingContext, ChunkingContextVc};
 use crate::asset::AssetVc;
 
 pub struct DevChunkingContextBuilder {
@@ -24,6 +24,11 @@ impl DevChunkingContextBuilder {
         self
     }
 
+    pub fn chunking_config(mut self, config: ChunkingConfigVc) -> Self {
+        self.context.chunking_config = Some(config);
+        self
+    }
+
     pub fn css_chunk_root_path(mut self, path: FileSystemPathVc) -> Self {
         self.context.css_chunk_root_path = Some(path);
         self
@@ -56,6 +61,8 @@ pub struct DevChunkingContext {
     layer: Option<String>,
     /// Enable HMR for this chunking
     enable_hot_module_replacement: bool,
+    /// Configures how assets are split into chunks
+    chunking_config: Option<ChunkingConfigVc>,
 }
 
 impl DevChunkingContextVc {
@@ -74,6 +81,7 @@ impl DevChunkingContextVc {
                 asset_root_path,
                 layer: None,
                 enable_hot_module_replacement: false,
+                chunking_config: None,
             },
         }
     }
@@ -158,12 +166,37 @@ impl ChunkingContext for DevChunkingContext {
     }
 
     #[turbo_tasks::function]
-    async fn can_be_in_same_chunk(&self, asset_a: AssetVc, asset_b: AssetVc) -> Result<BoolVc> {
+    async fn can_be_in_same_chunk(
+        self_vc: DevChunkingContextVc,
+        asset_a: AssetVc,
+        asset_b: AssetVc,
+    ) -> Result<BoolVc> {
+        let config = self_vc.chunking_config().await?;
+
+        if !config.cache_groups.is_empty() {
+            let context_path = self_vc.await?.context_path.await?;
+            let mut cache_groups = Vec::with_capacity(2);
+            for asset in [asset_a, asset_b] {
+                let path = asset.path().await?;
+                cache_groups.push(if let Some(rel_path) = context_path.get_path_to(&path) {
+                    config.cache_group(rel_path).await?
+                } else {
+                    None
+                });
+            }
+            match (cache_groups[0], cache_groups[1]) {
+                (None, None) => {}
+                (a, b) => return Ok(BoolVc::cell(a == b)),
+            }
+        }
+
         let parent_dir = asset_a.path().parent().await?;
 
         let path = asset_b.path().await?;
         if let Some(rel_path) = parent_dir.get_path_to(&path) {
-            if !rel_path.starts_with("node_modules/") && !rel_path.contains("/node_modules/") {
+            if !config.split_vendor
+                || (!rel_path.starts_with("node_modules/") && !rel_path.contains("/node_modules/"))
+            {
                 return Ok(BoolVc::cell(true));
             }
         }
@@ -177,6 +210,18 @@ impl ChunkingContext for DevChunkingContext {
             .join(&format!("{content_hash}.{extension}"))
     }
 
+    #[turbo_tasks::function]
+    async fn runtime_chunk_path(self_vc: DevChunkingContextVc) -> Result<FileSystemPathVc> {
+        let this = self_vc.await?;
+        Ok(self_vc.chunk_path(this.context_path.join("turbopack-runtime.js"), ".js"))
+    }
+
+    #[turbo_tasks::function]
+    fn chunking_config(&self) -> ChunkingConfigVc {
+        self.chunking_config
+            .unwrap_or_else(ChunkingConfigVc::default)
+    }
+
     #[turbo_tasks::function]
     fn is_hot_module_replacement_enabled(&self) -> BoolVc {
         BoolVc::cell(self.enable_hot_module_replacement)
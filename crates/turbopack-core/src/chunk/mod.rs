@@ -1,3 +1,4 @@
+pub mod config;
 pub mod dev;
 pub mod optimize;
 
@@ -10,12 +11,12 @@ use turbo_tasks::{
     debug::ValueDebugFormat,
     primitives::{BoolVc, StringVc},
     trace::TraceRawVcs,
-    ValueToString, ValueToStringVc,
+    Atom, ValueToString, ValueToStringVc,
 };
 use turbo_tasks_fs::FileSystemPathVc;
 use turbo_tasks_hash::{encode_hex, DeterministicHash, Xxh3Hash64Hasher};
 
-use self::optimize::optimize;
+use self::{config::ChunkingConfigVc, optimize::optimize};
 use crate::{
     asset::{Asset, AssetVc, AssetsVc},
     reference::{AssetReference, AssetReferenceVc, AssetReferencesVc},
@@ -28,7 +29,7 @@ use crate::{
 #[serde(untagged)]
 pub enum ModuleId {
     Number(u32),
-    String(String),
+    String(Atom),
 }
 
 impl ModuleId {
@@ -57,6 +58,15 @@ pub trait ChunkingContext {
 
     fn asset_path(&self, content_hash: &str, extension: &str) -> FileSystemPathVc;
 
+    /// The path of the chunk containing the runtime, which is shared by all
+    /// evaluated chunks of this context.
+    fn runtime_chunk_path(&self) -> FileSystemPathVc;
+
+    /// Configures how assets are split into chunks.
+    fn chunking_config(&self) -> ChunkingConfigVc {
+        ChunkingConfigVc::default()
+    }
+
     fn is_hot_module_replacement_enabled(&self) -> BoolVc {
         BoolVc::cell(false)
     }
@@ -197,6 +207,13 @@ pub trait ChunkableAssetReference: AssetReference + ValueToString {
     fn chunking_type(&self, _context: ChunkingContextVc) -> ChunkingTypeOptionVc {
         ChunkingTypeOptionVc::cell(Some(ChunkingType::default()))
     }
+
+    /// The assets that are placed into chunks for this reference. These are
+    /// the primary assets of the resolved reference by default, but side
+    /// effect free assets which are not needed at runtime can be left out.
+    fn chunkable_assets(&self, _context: ChunkingContextVc) -> AssetsVc {
+        self.resolve_reference().primary_assets()
+    }
 }
 
 /// A reference to a [Chunk]. Can be loaded in parallel, see [Chunk].
@@ -373,7 +390,7 @@ async fn chunk_content_internal<I: FromChunkableAsset>(
                     if let Some(pc) = ChunkableAssetReferenceVc::resolve_from(r).await? {
                         if let Some(chunking_type) = *pc.chunking_type(context).await? {
                             queue.push_back(ChunkContentWorkItem::Assets {
-                                assets: r.resolve_reference().primary_assets(),
+                                assets: pc.chunkable_assets(context),
                                 reference: *r,
                                 chunking_type,
                             });
@@ -76,9 +76,10 @@ impl EnvironmentVc {
         Ok(match this.execution {
             ExecutionEnvironment::NodeJsBuildTime(node_env, ..)
             | ExecutionEnvironment::NodeJsLambda(node_env) => node_env.await?.compile_target,
-            ExecutionEnvironment::Browser(_) => CompileTargetVc::unknown(),
-            ExecutionEnvironment::EdgeFunction(_) => todo!(),
-            ExecutionEnvironment::Custom(_) => todo!(),
+            // Neither browsers nor edge functions can load native addons.
+            ExecutionEnvironment::Browser(_)
+            | ExecutionEnvironment::EdgeFunction(_)
+            | ExecutionEnvironment::Custom(_) => CompileTargetVc::unknown(),
         })
     }
 
@@ -87,15 +88,17 @@ impl EnvironmentVc {
         let this = self.await?;
         Ok(match this.execution {
             ExecutionEnvironment::NodeJsBuildTime(node_env, ..)
-            | ExecutionEnvironment::NodeJsLambda(node_env) => node_env.runtime_versions(),
+            | ExecutionEnvironment::NodeJsLambda(node_env)
+            | ExecutionEnvironment::EdgeFunction(node_env) => node_env.runtime_versions(),
             ExecutionEnvironment::Browser(browser_env) => {
                 RuntimeVersionsVc::cell(Versions::parse_versions(browserslist::resolve(
                     browser_env.await?.browserslist_query.split(','),
                     &browserslist::Opts::new(),
                 )?)?)
             }
-            ExecutionEnvironment::EdgeFunction(_) => todo!(),
-            ExecutionEnvironment::Custom(_) => todo!(),
+            // Nothing is known about the runtime, so no language features are
+            // assumed to be supported.
+            ExecutionEnvironment::Custom(_) => RuntimeVersionsVc::cell(Versions::default()),
         })
     }
 
@@ -106,9 +109,9 @@ impl EnvironmentVc {
             ExecutionEnvironment::NodeJsBuildTime(..) | ExecutionEnvironment::NodeJsLambda(_) => {
                 BoolVc::cell(true)
             }
-            ExecutionEnvironment::Browser(_) => BoolVc::cell(false),
-            ExecutionEnvironment::EdgeFunction(_) => BoolVc::cell(false),
-            ExecutionEnvironment::Custom(_) => todo!(),
+            ExecutionEnvironment::Browser(_)
+            | ExecutionEnvironment::EdgeFunction(_)
+            | ExecutionEnvironment::Custom(_) => BoolVc::cell(false),
         })
     }
 
@@ -123,10 +126,9 @@ impl EnvironmentVc {
                     ".json".to_string(),
                 ])
             }
-            ExecutionEnvironment::EdgeFunction(_) | ExecutionEnvironment::Browser(_) => {
-                StringsVc::empty()
-            }
-            ExecutionEnvironment::Custom(_) => todo!(),
+            ExecutionEnvironment::EdgeFunction(_)
+            | ExecutionEnvironment::Browser(_)
+            | ExecutionEnvironment::Custom(_) => StringsVc::empty(),
         })
     }
 
@@ -137,10 +139,9 @@ impl EnvironmentVc {
             ExecutionEnvironment::NodeJsBuildTime(..) | ExecutionEnvironment::NodeJsLambda(_) => {
                 BoolVc::cell(true)
             }
-            ExecutionEnvironment::EdgeFunction(_) | ExecutionEnvironment::Browser(_) => {
-                BoolVc::cell(false)
-            }
-            ExecutionEnvironment::Custom(_) => todo!(),
+            ExecutionEnvironment::EdgeFunction(_)
+            | ExecutionEnvironment::Browser(_)
+            | ExecutionEnvironment::Custom(_) => BoolVc::cell(false),
         })
     }
 
@@ -151,10 +152,9 @@ impl EnvironmentVc {
             ExecutionEnvironment::NodeJsBuildTime(..) | ExecutionEnvironment::NodeJsLambda(_) => {
                 StringsVc::cell(vec!["node".to_string()])
             }
-            ExecutionEnvironment::EdgeFunction(_) | ExecutionEnvironment::Browser(_) => {
-                StringsVc::empty()
-            }
-            ExecutionEnvironment::Custom(_) => todo!(),
+            ExecutionEnvironment::EdgeFunction(_)
+            | ExecutionEnvironment::Browser(_)
+            | ExecutionEnvironment::Custom(_) => StringsVc::empty(),
         })
     }
 
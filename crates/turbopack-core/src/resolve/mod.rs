@@ -15,6 +15,7 @@ use turbo_tasks::{
     TryJoinIterExt, Value, ValueToString, ValueToStringVc,
 };
 use turbo_tasks_fs::{
+    from_sys_path,
     util::{normalize_path, normalize_request},
     FileJsonContent, FileJsonContentVc, FileSystemEntryType, FileSystemPathVc, RealPathResult,
 };
@@ -48,6 +49,8 @@ pub mod options;
 pub mod origin;
 pub mod parse;
 pub mod pattern;
+pub mod pnp;
+pub mod side_effects;
 
 pub use alias_map::{
     AliasMap, AliasMapIntoIter, AliasMapLookupIterator, AliasMatch, AliasPattern, AliasTemplate,
@@ -447,6 +450,12 @@ async fn find_package(
                 let root = &*root_vc.await?;
                 while context_value.is_inside(root) {
                     for name in names.iter() {
+                        // Like Node.js, don't look into `node_modules/node_modules`. This
+                        // avoids lots of lookups in deeply nested package directories, like
+                        // in the `node_modules/.pnpm` virtual store of pnpm.
+                        if context_value.file_name() == name {
+                            continue;
+                        }
                         let fs_path = context.join(name);
                         if let Some(fs_path) = dir_exists(fs_path, &mut references).await? {
                             let fs_path = fs_path.join(&package_name);
@@ -463,6 +472,16 @@ async fn find_package(
                     context_value = new_context_value;
                 }
             }
+            ResolveModules::Pnp(manifest) => {
+                references.push(AffectingResolvingAssetReferenceVc::new(*manifest).into());
+                if let Some(package_dir) =
+                    pnp::find_pnp_package(*manifest, context, &package_name).await?
+                {
+                    if dir_exists(package_dir, &mut references).await?.is_some() {
+                        packages.push(package_dir);
+                    }
+                }
+            }
             ResolveModules::Path(context) => {
                 let package_dir = context.join(&package_name);
                 if dir_exists(package_dir, &mut references).await?.is_some() {
@@ -574,7 +593,7 @@ pub async fn resolve(
 
     // Apply import mappings if provided
     if let Some(import_map) = &options_value.import_map {
-        let result_ref = import_map.lookup(request).await?;
+        let result_ref = import_map.lookup(context, request).await?;
         let result = &*result_ref;
         if !matches!(result, ImportMapResult::NoEntry) {
             let resolve_result_vc =
@@ -669,18 +688,35 @@ pub async fn resolve(
 
             resolve(context.root(), relative, options)
         }
-        Request::Windows { path: _ } => {
-            let issue: ResolvingIssueVc = ResolvingIssue {
-                request_type: "windows import: not implemented yet".to_string(),
-                request,
-                context,
-                resolve_options: options,
-                error_message: Some("windows imports are not implemented yet".to_string()),
-            }
-            .into();
-            issue.as_issue().emit();
+        Request::Windows { path } => {
+            // Absolute Windows paths are resolved relative to the root of the
+            // file system they point into.
+            let fs_path = if let Pattern::Constant(path) = path {
+                from_sys_path(context.fs(), path).await?
+            } else {
+                None
+            };
+            if let Some(fs_path) = fs_path {
+                let relative = RequestVc::relative(
+                    Value::new(format!("./{}", fs_path.await?.path).into()),
+                    true,
+                );
+                resolve(context.root(), relative, options)
+            } else {
+                let issue: ResolvingIssueVc = ResolvingIssue {
+                    request_type: "windows import".to_string(),
+                    request,
+                    context,
+                    resolve_options: options,
+                    error_message: Some(
+                        "the absolute path is outside of the project's file system".to_string(),
+                    ),
+                }
+                .into();
+                issue.as_issue().emit();
 
-            ResolveResult::unresolveable().into()
+                ResolveResult::unresolveable().into()
+            }
         }
         Request::Empty => ResolveResult::unresolveable().into(),
         Request::PackageInternal { path: _ } => {
@@ -720,7 +756,7 @@ pub async fn resolve(
     // Apply fallback import mappings if provided
     if let Some(import_map) = &options_value.fallback_import_map {
         if *result.is_unresolveable().await? {
-            let result_ref = import_map.lookup(request).await?;
+            let result_ref = import_map.lookup(context, request).await?;
             let result = &*result_ref;
             if !matches!(result, ImportMapResult::NoEntry) {
                 let resolve_result_vc =
@@ -1021,7 +1057,7 @@ async fn resolved(
         }
     }
     if let Some(resolved_map) = resolved_map {
-        let result = resolved_map.lookup(*path).await?;
+        let result = resolved_map.lookup(*path, original_request).await?;
         if !matches!(&*result, ImportMapResult::NoEntry) {
             return resolve_import_map_result(
                 &result,
@@ -28,6 +28,9 @@ pub enum ResolveModules {
     Nested(FileSystemPathVc, Vec<String>),
     /// look into that directory
     Path(FileSystemPathVc),
+    /// lookup packages in the dependency tree of the Yarn Plug'n'Play
+    /// manifest at that path
+    Pnp(FileSystemPathVc),
     /// lookup versions based on lockfile in the registry filesystem
     /// registry filesystem is assumed to have structure like
     /// @scope/module/version/<path-in-package>
@@ -92,6 +95,19 @@ pub enum ImportMapping {
     Ignore,
     Empty,
     Alternatives(Vec<ImportMappingVc>),
+    /// A mapping which is computed for every request, e. g. to create a module
+    /// from the request.
+    Dynamic(ImportMappingReplacementVc),
+}
+
+/// Computes an [ImportMapping::Dynamic] for a request.
+#[turbo_tasks::value_trait]
+pub trait ImportMappingReplacement {
+    /// Returns the mapping for a wildcard alias, where `capture` is the part
+    /// of the request which matched the wildcard.
+    fn replace(&self, capture: &str) -> ImportMappingVc;
+    /// Returns the result of `request`, which is resolved in `context`.
+    fn result(&self, context: FileSystemPathVc, request: RequestVc) -> ImportMapResultVc;
 }
 
 impl ImportMapping {
@@ -140,6 +156,7 @@ impl AliasTemplate for ImportMappingVc {
                         .try_join()
                         .await?,
                 ),
+                ImportMapping::Dynamic(replacement) => return Ok(replacement.replace(capture)),
             }
             .cell())
         })
@@ -236,7 +253,11 @@ pub enum ImportMapResult {
     NoEntry,
 }
 
-async fn import_mapping_to_result(mapping: ImportMappingVc) -> Result<ImportMapResult> {
+async fn import_mapping_to_result(
+    mapping: ImportMappingVc,
+    context: FileSystemPathVc,
+    request: RequestVc,
+) -> Result<ImportMapResult> {
     Ok(match &*mapping.await? {
         ImportMapping::Direct(result) => ImportMapResult::Result(*result),
         ImportMapping::External(name) => ImportMapResult::Result(
@@ -262,10 +283,13 @@ async fn import_mapping_to_result(mapping: ImportMappingVc) -> Result<ImportMapR
         }
         ImportMapping::Alternatives(list) => ImportMapResult::Alternatives(
             list.iter()
-                .map(|mapping| import_mapping_to_result_boxed(*mapping))
+                .map(|mapping| import_mapping_to_result_boxed(*mapping, context, request))
                 .try_join()
                 .await?,
         ),
+        ImportMapping::Dynamic(replacement) => {
+            (*replacement.result(context, request).await?).clone()
+        }
     })
 }
 
@@ -275,20 +299,28 @@ async fn import_mapping_to_result(mapping: ImportMappingVc) -> Result<ImportMapR
 //     `resolve::options::import_mapping_to_result::{opaque#0}`
 fn import_mapping_to_result_boxed(
     mapping: ImportMappingVc,
+    context: FileSystemPathVc,
+    request: RequestVc,
 ) -> Pin<Box<dyn Future<Output = Result<ImportMapResult>> + Send>> {
-    Box::pin(async move { import_mapping_to_result(mapping).await })
+    Box::pin(async move { import_mapping_to_result(mapping, context, request).await })
 }
 
 #[turbo_tasks::value_impl]
 impl ImportMapVc {
     #[turbo_tasks::function]
-    pub async fn lookup(self, request: RequestVc) -> Result<ImportMapResultVc> {
+    pub async fn lookup(
+        self,
+        context: FileSystemPathVc,
+        request: RequestVc,
+    ) -> Result<ImportMapResultVc> {
         let this = self.await?;
         // TODO lookup pattern
         if let Some(request_string) = request.await?.request() {
             if let Some(result) = this.direct.lookup(&request_string).next() {
                 return Ok(import_mapping_to_result(
                     result.try_join_into_self().await?.into_owned(),
+                    context,
+                    request,
                 )
                 .await?
                 .into());
@@ -300,7 +332,9 @@ impl ImportMapVc {
             };
             for (glob, mapping) in this.by_glob.iter() {
                 if glob.execute(request_string_without_slash) {
-                    return Ok(import_mapping_to_result(*mapping).await?.into());
+                    return Ok(import_mapping_to_result(*mapping, context, request)
+                        .await?
+                        .into());
                 }
             }
         }
@@ -311,14 +345,21 @@ impl ImportMapVc {
 #[turbo_tasks::value_impl]
 impl ResolvedMapVc {
     #[turbo_tasks::function]
-    pub async fn lookup(self, resolved: FileSystemPathVc) -> Result<ImportMapResultVc> {
+    pub async fn lookup(
+        self,
+        resolved: FileSystemPathVc,
+        request: RequestVc,
+    ) -> Result<ImportMapResultVc> {
         let this = self.await?;
+        let context = resolved.parent();
         let resolved = resolved.await?;
         for (root, glob, mapping) in this.by_glob.iter() {
             let root = root.await?;
             if let Some(path) = root.get_path_to(&resolved) {
                 if glob.await?.execute(path) {
-                    return Ok(import_mapping_to_result(*mapping).await?.into());
+                    return Ok(import_mapping_to_result(*mapping, context, request)
+                        .await?
+                        .into());
                 }
             }
         }
@@ -368,6 +409,23 @@ impl ResolveOptionsVc {
         Ok(resolve_options.into())
     }
 
+    /// Returns a new [ResolveOptionsVc] which looks up packages with the Yarn
+    /// Plug'n'Play `manifest` before looking into `node_modules` directories.
+    #[turbo_tasks::function]
+    pub async fn with_pnp_manifest(self, manifest: FileSystemPathVc) -> Result<Self> {
+        let mut resolve_options = self.await?.clone_value();
+        if let Some(i) = resolve_options
+            .modules
+            .iter()
+            .position(|modules| matches!(modules, ResolveModules::Nested(..)))
+        {
+            resolve_options
+                .modules
+                .insert(i, ResolveModules::Pnp(manifest));
+        }
+        Ok(resolve_options.into())
+    }
+
     /// Returns a new [ResolveOptionsVc] with its fallback import map extended
     /// to include the given import map.
     #[turbo_tasks::function]
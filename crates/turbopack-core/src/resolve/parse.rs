@@ -1,7 +1,9 @@
 use anyhow::Result;
 use lazy_static::lazy_static;
 use regex::Regex;
-use turbo_tasks::{primitives::StringVc, TryJoinIterExt, Value, ValueToString, ValueToStringVc};
+use turbo_tasks::{
+    primitives::StringVc, Atom, TryJoinIterExt, Value, ValueToString, ValueToStringVc,
+};
 
 use super::pattern::Pattern;
 
@@ -17,7 +19,7 @@ pub enum Request {
         force_in_context: bool,
     },
     Module {
-        module: String,
+        module: Atom,
         path: Pattern,
     },
     ServerRelative {
@@ -118,7 +120,7 @@ impl Request {
                     if let Some(caps) = MODULE_PATH.captures(r) {
                         if let (Some(module), Some(path)) = (caps.get(1), caps.get(2)) {
                             return Request::Module {
-                                module: module.as_str().to_string(),
+                                module: module.as_str().into(),
                                 path: path.as_str().to_string().into(),
                             };
                         }
@@ -207,7 +209,7 @@ impl RequestVc {
     #[turbo_tasks::function]
     pub fn module(module: String, path: Value<Pattern>) -> Self {
         Self::cell(Request::Module {
-            module,
+            module: module.into(),
             path: path.into_value(),
         })
     }
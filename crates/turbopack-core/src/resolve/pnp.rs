@@ -0,0 +1,383 @@
+//! Support for [Yarn Plug'n'Play], which resolves packages with the
+//! dependency tree of the `.pnp.cjs` manifest instead of `node_modules`
+//! directories. Packages are usually stored as zip archives in the Yarn cache,
+//! which are read with a [ZipFileSystem](turbo_tasks_fs::zip::ZipFileSystem).
+//!
+//! [Yarn Plug'n'Play]: https://yarnpkg.com/features/pnp
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use turbo_tasks::trace::TraceRawVcs;
+use turbo_tasks_fs::{
+    util::{join_path, normalize_path},
+    zip::ZipFileSystemVc,
+    FileContent, FileJsonContent, FileSystemPath, FileSystemPathOptionVc, FileSystemPathVc,
+};
+
+use super::{find_context_file, FindContextFileResult};
+
+/// The names of the manifest, `.pnp.js` is used by Yarn 2.
+const MANIFEST_NAMES: [&str; 2] = [".pnp.cjs", ".pnp.js"];
+
+#[derive(PartialEq, Eq, Clone, Debug, TraceRawVcs, Serialize, Deserialize)]
+struct PnpDependency {
+    /// The name of the package, which differs from the name of the dependency
+    /// for aliases.
+    name: String,
+    reference: String,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, TraceRawVcs, Serialize, Deserialize)]
+struct PnpPackage {
+    /// `None` for the top-level package.
+    name: Option<String>,
+    reference: Option<String>,
+    /// The /-separated location of the package, relative to the directory of
+    /// the manifest.
+    ///
+    /// Virtual locations, which Yarn uses for packages with peer
+    /// dependencies, are mapped to the physical location of the package.
+    /// All virtual instances of a package are found at that location and the
+    /// first one is used for its dependencies.
+    location: String,
+    /// `None` marks a missing peer dependency.
+    dependencies: HashMap<String, Option<PnpDependency>>,
+}
+
+#[turbo_tasks::value(shared)]
+#[derive(Default)]
+pub struct PnpManifest {
+    packages: Vec<PnpPackage>,
+    enable_top_level_fallback: bool,
+    fallback_pool: HashMap<String, PnpDependency>,
+}
+
+/// Finds the Yarn Plug'n'Play manifest of the project containing `context`.
+///
+/// Inside of a zip archive, the manifest is searched from the location of the
+/// archive.
+#[turbo_tasks::function]
+pub async fn find_pnp_manifest(context: FileSystemPathVc) -> Result<FileSystemPathOptionVc> {
+    let context = match ZipFileSystemVc::resolve_from(context.fs()).await? {
+        Some(zip) => zip.archive().parent(),
+        None => context,
+    };
+    for name in MANIFEST_NAMES {
+        if let FindContextFileResult::Found(path, _) = &*find_context_file(context, name).await? {
+            return Ok(FileSystemPathOptionVc::cell(Some(*path)));
+        }
+    }
+    Ok(FileSystemPathOptionVc::cell(None))
+}
+
+/// Reads the runtime state of the manifest, from a `.pnp.data.json` next to it
+/// or inlined in the manifest, which is the default.
+#[turbo_tasks::function]
+async fn pnp_manifest(manifest: FileSystemPathVc) -> Result<PnpManifestVc> {
+    let data_json = manifest.parent().join(".pnp.data.json").read_json();
+    if let FileJsonContent::Content(data) = &*data_json.await? {
+        return Ok(PnpManifest::parse(data).cell());
+    }
+    if let FileContent::Content(file) = &*manifest.read().await? {
+        if let Some(data) = extract_runtime_state(&file.content().to_str()?) {
+            if let Ok(data) = serde_json::from_str(&data) {
+                return Ok(PnpManifest::parse(&data).cell());
+            }
+        }
+    }
+    Ok(PnpManifest::default().cell())
+}
+
+/// Finds the directory of the package `package_name` as a dependency of the
+/// package containing `context`, using the Plug'n'Play `manifest`.
+pub(super) async fn find_pnp_package(
+    manifest: FileSystemPathVc,
+    context: FileSystemPathVc,
+    package_name: &str,
+) -> Result<Option<FileSystemPathVc>> {
+    let manifest_dir = manifest.parent();
+    let manifest_dir_value = manifest_dir.await?;
+    let issuer = match issuer_location(&manifest_dir_value, context).await? {
+        Some(issuer) => issuer,
+        None => return Ok(None),
+    };
+    let pnp = pnp_manifest(manifest).await?;
+    Ok(pnp
+        .resolve_dependency(&issuer, package_name)
+        .map(|location| location_path(manifest_dir, location)))
+}
+
+/// Returns the /-separated location of `context` relative to the manifest
+/// directory, which continues into zip archives.
+async fn issuer_location(
+    manifest_dir: &FileSystemPath,
+    context: FileSystemPathVc,
+) -> Result<Option<String>> {
+    let context_value = context.await?;
+    if let Some(zip) = ZipFileSystemVc::resolve_from(context_value.fs).await? {
+        let archive = zip.archive().await?;
+        return Ok(path_inside(manifest_dir, &archive)
+            .and_then(|archive| join_path(archive, &context_value.path)));
+    }
+    Ok(path_inside(manifest_dir, &context_value).map(str::to_string))
+}
+
+fn path_inside<'a>(dir: &FileSystemPath, path: &'a FileSystemPath) -> Option<&'a str> {
+    if dir == path {
+        Some("")
+    } else {
+        dir.get_path_to(path)
+    }
+}
+
+/// Converts a package location into a path, where locations inside of a zip
+/// archive are mapped onto a
+/// [ZipFileSystem](turbo_tasks_fs::zip::ZipFileSystem).
+fn location_path(manifest_dir: FileSystemPathVc, location: &str) -> FileSystemPathVc {
+    let archive_end = location
+        .find(".zip/")
+        .map(|i| i + ".zip".len())
+        .or_else(|| location.ends_with(".zip").then_some(location.len()));
+    match archive_end {
+        Some(end) => {
+            let archive = manifest_dir.join(&location[..end]);
+            let inner = location[end..].trim_start_matches('/');
+            ZipFileSystemVc::new(archive)
+                .as_file_system()
+                .root()
+                .join(inner)
+        }
+        None => manifest_dir.join(location),
+    }
+}
+
+impl PnpManifest {
+    fn parse(data: &JsonValue) -> Self {
+        let mut packages = Vec::new();
+        for entry in json_array(&data["packageRegistryData"]) {
+            let name = entry[0].as_str();
+            for instance in json_array(&entry[1]) {
+                let reference = instance[0].as_str();
+                let info = &instance[1];
+                let location = match info["packageLocation"].as_str().and_then(physical_location) {
+                    Some(location) => location,
+                    None => continue,
+                };
+                let dependencies = json_array(&info["packageDependencies"])
+                    .filter_map(|dependency| {
+                        let dependency_name = dependency[0].as_str()?;
+                        Some((
+                            dependency_name.to_string(),
+                            parse_dependency(dependency_name, &dependency[1]),
+                        ))
+                    })
+                    .collect();
+                packages.push(PnpPackage {
+                    name: name.map(str::to_string),
+                    reference: reference.map(str::to_string),
+                    location,
+                    dependencies,
+                });
+            }
+        }
+        let fallback_pool = json_array(&data["fallbackPool"])
+            .filter_map(|dependency| {
+                let name = dependency[0].as_str()?;
+                Some((name.to_string(), parse_dependency(name, &dependency[1])?))
+            })
+            .collect();
+        PnpManifest {
+            packages,
+            enable_top_level_fallback: data["enableTopLevelFallback"].as_bool().unwrap_or(false),
+            fallback_pool,
+        }
+    }
+
+    /// Returns the location of the package `name` as a dependency of the
+    /// package at the location `issuer`.
+    fn resolve_dependency(&self, issuer: &str, name: &str) -> Option<&str> {
+        let issuer_package = self
+            .packages
+            .iter()
+            .filter(|package| is_inside(issuer, &package.location))
+            .max_by_key(|package| package.location.len());
+        let dependency = match issuer_package.and_then(|package| package.dependencies.get(name)) {
+            Some(dependency) => dependency.as_ref()?,
+            None if self.enable_top_level_fallback => self
+                .packages
+                .iter()
+                .find(|package| package.name.is_none())
+                .and_then(|package| package.dependencies.get(name)?.as_ref())
+                .or_else(|| self.fallback_pool.get(name))?,
+            None => return None,
+        };
+        self.packages
+            .iter()
+            .find(|package| {
+                package.name.as_deref() == Some(&dependency.name)
+                    && package.reference.as_deref() == Some(&dependency.reference)
+            })
+            .map(|package| package.location.as_str())
+    }
+}
+
+fn json_array(value: &JsonValue) -> impl Iterator<Item = &JsonValue> {
+    value.as_array().into_iter().flatten()
+}
+
+/// Parses the reference of a dependency, which is either a string or a
+/// `[name, reference]` pair for aliases.
+fn parse_dependency(name: &str, value: &JsonValue) -> Option<PnpDependency> {
+    match value {
+        JsonValue::String(reference) => Some(PnpDependency {
+            name: name.to_string(),
+            reference: reference.clone(),
+        }),
+        JsonValue::Array(alias) => Some(PnpDependency {
+            name: alias.first()?.as_str()?.to_string(),
+            reference: alias.get(1)?.as_str()?.to_string(),
+        }),
+        _ => None,
+    }
+}
+
+fn is_inside(path: &str, dir: &str) -> bool {
+    dir.is_empty()
+        || path
+            .strip_prefix(dir)
+            .map_or(false, |rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Normalizes a package location and maps virtual locations like
+/// `.yarn/__virtual__/<hash>/<depth>/<path>` to the physical location, which
+/// is `<path>` relative to `<depth>` directories above the parent of the
+/// virtual folder.
+fn physical_location(location: &str) -> Option<String> {
+    let segments: Vec<&str> = location.split('/').collect();
+    match segments
+        .iter()
+        .position(|segment| *segment == "__virtual__" || *segment == "$$virtual")
+    {
+        Some(i) => {
+            let depth: usize = segments.get(i + 2)?.parse().ok()?;
+            let mut physical = segments[..i].to_vec();
+            physical.extend(std::iter::repeat("..").take(depth));
+            physical.extend(&segments[i + 3..]);
+            normalize_path(&physical.join("/"))
+        }
+        None => normalize_path(location),
+    }
+}
+
+/// Extracts the JSON of the runtime state, which is inlined in the manifest
+/// as a string literal assigned to `RAW_RUNTIME_STATE`.
+fn extract_runtime_state(code: &str) -> Option<String> {
+    let start = code.find("RAW_RUNTIME_STATE")?;
+    let code = &code[start..];
+    let code = code[code.find('=')? + 1..].trim_start();
+    let mut chars = code.chars();
+    let quote = chars.next().filter(|c| *c == '\'' || *c == '"')?;
+    let mut state = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                // A line continuation
+                '\n' => {}
+                'n' => state.push('\n'),
+                c => state.push(c),
+            },
+            c if c == quote => return Some(state),
+            c => state.push(c),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{extract_runtime_state, physical_location, PnpManifest};
+
+    #[test]
+    fn extracts_runtime_state() {
+        let code = "#!/usr/bin/env node\n/* eslint-disable */\n\"use strict\";\n\nconst \
+                    RAW_RUNTIME_STATE =\n'{\\\n  \"__info\": [\\\n    \"It\\'s generated\"\\\n  \
+                    ]\\\n}';\n";
+        assert_eq!(
+            extract_runtime_state(code).as_deref(),
+            Some("{  \"__info\": [    \"It's generated\"  ]}")
+        );
+    }
+
+    #[test]
+    fn maps_virtual_locations() {
+        assert_eq!(
+            physical_location(
+                "./.yarn/__virtual__/styled-jsx-virtual-1a2b3c/0/cache/styled-jsx-npm-5.1.0-abc.\
+                 zip/node_modules/styled-jsx/"
+            )
+            .as_deref(),
+            Some(".yarn/cache/styled-jsx-npm-5.1.0-abc.zip/node_modules/styled-jsx")
+        );
+        assert_eq!(
+            physical_location("./.yarn/__virtual__/a-virtual-1/1/packages/a/").as_deref(),
+            Some("packages/a")
+        );
+        assert_eq!(physical_location("./").as_deref(), Some(""));
+    }
+
+    #[test]
+    fn resolves_dependencies() {
+        let manifest = PnpManifest::parse(&json!({
+            "enableTopLevelFallback": true,
+            "fallbackPool": [["lodash", "npm:4.17.21"]],
+            "packageRegistryData": [
+                [null, [[null, {
+                    "packageLocation": "./",
+                    "packageDependencies": [["react", "npm:18.2.0"]],
+                }]]],
+                ["app", [["workspace:packages/app", {
+                    "packageLocation": "./packages/app/",
+                    "packageDependencies": [
+                        ["react", "npm:18.2.0"],
+                        ["preact-compat", ["react", "npm:18.2.0"]],
+                        ["react-dom", null],
+                    ],
+                }]]],
+                ["react", [["npm:18.2.0", {
+                    "packageLocation": "./.yarn/cache/react-npm-18.2.0-1eae08fee2.zip/node_modules/react/",
+                    "packageDependencies": [["react", "npm:18.2.0"]],
+                }]]],
+                ["lodash", [["npm:4.17.21", {
+                    "packageLocation": "./.yarn/cache/lodash-npm-4.17.21-6382451519.zip/node_modules/lodash/",
+                    "packageDependencies": [],
+                }]]],
+            ],
+        }));
+        let react = Some(".yarn/cache/react-npm-18.2.0-1eae08fee2.zip/node_modules/react");
+        assert_eq!(
+            manifest.resolve_dependency("packages/app/src", "react"),
+            react
+        );
+        assert_eq!(
+            manifest.resolve_dependency("packages/app", "preact-compat"),
+            react
+        );
+        assert_eq!(
+            manifest.resolve_dependency("packages/app", "react-dom"),
+            None
+        );
+        assert_eq!(
+            manifest.resolve_dependency("packages/app", "lodash"),
+            Some(".yarn/cache/lodash-npm-4.17.21-6382451519.zip/node_modules/lodash")
+        );
+        assert_eq!(
+            manifest.resolve_dependency("packages/application", "react"),
+            react
+        );
+    }
+}
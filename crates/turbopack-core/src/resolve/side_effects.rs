@@ -0,0 +1,138 @@
+use anyhow::{anyhow, Result};
+use serde_json::Value as JsonValue;
+use turbo_tasks::primitives::BoolVc;
+use turbo_tasks_fs::{glob::Glob, FileJsonContent, FileSystemPathVc};
+
+use super::{find_context_file, FindContextFileResult};
+use crate::issue::package_json::{PackageJsonIssue, PackageJsonIssueVc};
+
+/// The `sideEffects` field of a package.json.
+#[turbo_tasks::value(shared)]
+#[derive(Debug)]
+pub enum PackageSideEffects {
+    /// All modules of the package might have side effects. This is the
+    /// default when the field is missing.
+    All,
+    /// No module of the package has side effects.
+    None,
+    /// Only modules matching one of the globs have side effects. Globs are
+    /// matched against the path relative to the package root.
+    Globs(Vec<Glob>),
+}
+
+impl TryFrom<&JsonValue> for PackageSideEffects {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &JsonValue) -> Result<Self> {
+        match value {
+            JsonValue::Null => Ok(PackageSideEffects::All),
+            JsonValue::Bool(true) => Ok(PackageSideEffects::All),
+            JsonValue::Bool(false) => Ok(PackageSideEffects::None),
+            JsonValue::String(glob) => Ok(PackageSideEffects::Globs(vec![parse_glob(glob)?])),
+            JsonValue::Array(globs) => Ok(PackageSideEffects::Globs(
+                globs
+                    .iter()
+                    .map(|glob| {
+                        glob.as_str()
+                            .ok_or_else(|| anyhow!("sideEffects globs must be strings"))
+                            .and_then(parse_glob)
+                    })
+                    .collect::<Result<_>>()?,
+            )),
+            _ => Err(anyhow!(
+                "sideEffects must be a boolean or a list of globs, but is {value}"
+            )),
+        }
+    }
+}
+
+/// Globs without a `/` match on the file name in any directory, like webpack
+/// does.
+fn parse_glob(glob: &str) -> Result<Glob> {
+    let glob = glob.trim_start_matches("./");
+    if glob.contains('/') {
+        Glob::parse(glob)
+    } else {
+        Glob::parse(&format!("**/{glob}"))
+    }
+}
+
+impl PackageSideEffects {
+    /// Checks if a module at `path` (relative to the package root) is side
+    /// effect free.
+    pub fn is_side_effect_free(&self, path: &str) -> bool {
+        match self {
+            PackageSideEffects::All => false,
+            PackageSideEffects::None => true,
+            PackageSideEffects::Globs(globs) => !globs.iter().any(|glob| glob.execute(path)),
+        }
+    }
+}
+
+/// Reads the `sideEffects` field of a package.json. Invalid values are
+/// reported as issue and treated as if all modules have side effects.
+#[turbo_tasks::function]
+pub async fn package_side_effects(package_json: FileSystemPathVc) -> Result<PackageSideEffectsVc> {
+    let content = package_json.read_json().await?;
+    let side_effects = if let FileJsonContent::Content(content) = &*content {
+        &content["sideEffects"]
+    } else {
+        return Ok(PackageSideEffects::All.cell());
+    };
+    Ok(match PackageSideEffects::try_from(side_effects) {
+        Ok(side_effects) => side_effects.cell(),
+        Err(err) => {
+            let issue: PackageJsonIssueVc = PackageJsonIssue {
+                path: package_json,
+                error_message: err.to_string(),
+            }
+            .into();
+            issue.as_issue().emit();
+            PackageSideEffects::All.cell()
+        }
+    })
+}
+
+/// Checks if the module at `path` is declared as side effect free by the
+/// closest package.json.
+#[turbo_tasks::function]
+pub async fn is_side_effect_free(path: FileSystemPathVc) -> Result<BoolVc> {
+    let package_json = match &*find_context_file(path.parent(), "package.json").await? {
+        FindContextFileResult::Found(package_json, _) => *package_json,
+        FindContextFileResult::NotFound(_) => return Ok(BoolVc::cell(false)),
+    };
+    let side_effects = package_side_effects(package_json).await?;
+    let package_path = package_json.parent().await?;
+    let path = path.await?;
+    Ok(BoolVc::cell(
+        if let Some(rel_path) = package_path.get_path_to(&path) {
+            side_effects.is_side_effect_free(rel_path)
+        } else {
+            false
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::PackageSideEffects;
+
+    #[test]
+    fn side_effects_field() {
+        let all = PackageSideEffects::try_from(&json!(null)).unwrap();
+        assert!(!all.is_side_effect_free("index.js"));
+
+        let none = PackageSideEffects::try_from(&json!(false)).unwrap();
+        assert!(none.is_side_effect_free("index.js"));
+
+        let globs = PackageSideEffects::try_from(&json!(["./src/polyfill.js", "*.css"])).unwrap();
+        assert!(globs.is_side_effect_free("src/index.js"));
+        assert!(!globs.is_side_effect_free("src/polyfill.js"));
+        assert!(!globs.is_side_effect_free("src/styles/button.css"));
+
+        assert!(PackageSideEffects::try_from(&json!(42)).is_err());
+        assert!(PackageSideEffects::try_from(&json!([42])).is_err());
+    }
+}
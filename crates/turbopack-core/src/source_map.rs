@@ -4,9 +4,16 @@ use anyhow::Result;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use sourcemap::SourceMap as CrateMap;
 use turbo_tasks::TryJoinIterExt;
-use turbo_tasks_fs::rope::{Rope, RopeBuilder, RopeVc};
+use turbo_tasks_fs::{
+    rope::{Rope, RopeBuilder, RopeVc},
+    util::join_path,
+    FileContent, FileSystemPathVc,
+};
 
-use crate::source_pos::SourcePos;
+use crate::{
+    asset::{AssetContent, AssetVc},
+    source_pos::SourcePos,
+};
 
 /// Generates a usable source map, capable of both tracing and stringifying.
 #[turbo_tasks::value_trait]
@@ -59,6 +66,9 @@ pub struct OriginalToken {
 #[turbo_tasks::value(transparent)]
 pub struct OptionToken(Option<Token>);
 
+#[turbo_tasks::value(transparent)]
+pub struct OptionSourceMap(Option<SourceMapVc>);
+
 impl<'a> From<sourcemap::Token<'a>> for Token {
     fn from(t: sourcemap::Token) -> Self {
         if t.has_source() {
@@ -305,3 +315,163 @@ impl SourceMapSection {
         Self { offset, map }
     }
 }
+
+/// Loads the source map of `source` when it was compiled by another tool,
+/// e. g. the output of Babel or a published package. The map is referenced by
+/// a `sourceMappingURL` comment at the end of the file, either as a file
+/// relative to the source or inline as a base64 data URL.
+///
+/// The `sources` of the map are resolved to `/`-prefixed paths, like the
+/// ones of maps generated by turbopack. `None` is returned when there is no
+/// map or it can't be read.
+#[turbo_tasks::function]
+pub async fn input_source_map(source: AssetVc) -> Result<OptionSourceMapVc> {
+    let content = source.content().await?;
+    let file = match &*content {
+        AssetContent::File(file) => file.await?,
+        AssetContent::Redirect { .. } => return Ok(OptionSourceMapVc::cell(None)),
+    };
+    let text = match &*file {
+        FileContent::Content(file) => match file.content().to_str() {
+            Ok(text) => text.into_owned(),
+            Err(_) => return Ok(OptionSourceMapVc::cell(None)),
+        },
+        FileContent::NotFound => return Ok(OptionSourceMapVc::cell(None)),
+    };
+    let url = match source_mapping_url(&text) {
+        Some(url) => url,
+        None => return Ok(OptionSourceMapVc::cell(None)),
+    };
+    let (bytes, map_dir) = if let Some(data_url) = url.strip_prefix("data:") {
+        let data = match data_url.split_once(',') {
+            Some((media_type, data)) if media_type.ends_with(";base64") => base64::decode(data),
+            _ => return Ok(OptionSourceMapVc::cell(None)),
+        };
+        match data {
+            Ok(data) => (data, source.path().parent()),
+            Err(_) => return Ok(OptionSourceMapVc::cell(None)),
+        }
+    } else if url.contains("://") {
+        return Ok(OptionSourceMapVc::cell(None));
+    } else {
+        let map_path = match &*source.path().parent().try_join(url).await? {
+            Some(map_path) => *map_path,
+            None => return Ok(OptionSourceMapVc::cell(None)),
+        };
+        match &*map_path.read().await? {
+            FileContent::Content(file) => {
+                (file.content().to_bytes().into_owned(), map_path.parent())
+            }
+            FileContent::NotFound => return Ok(OptionSourceMapVc::cell(None)),
+        }
+    };
+    let mut map = match CrateMap::from_slice(&bytes) {
+        Ok(map) => map,
+        Err(_) => return Ok(OptionSourceMapVc::cell(None)),
+    };
+    resolve_sources(&mut map, map_dir).await?;
+    Ok(OptionSourceMapVc::cell(Some(SourceMapVc::new_regular(map))))
+}
+
+/// Returns the URL of the last `sourceMappingURL` comment in `code`.
+fn source_mapping_url(code: &str) -> Option<&str> {
+    let index = code.rfind("sourceMappingURL=")?;
+    let prefix = code[..index].trim_end_matches(' ');
+    if !(prefix.ends_with("//#")
+        || prefix.ends_with("//@")
+        || prefix.ends_with("/*#")
+        || prefix.ends_with("/*@"))
+    {
+        return None;
+    }
+    let url = code[index + "sourceMappingURL=".len()..]
+        .split(|c: char| c.is_whitespace())
+        .next()?
+        .trim_end_matches("*/");
+    (!url.is_empty()).then_some(url)
+}
+
+/// Returns the URL of a `sourceMappingURL` comment, given the text of the
+/// comment without its delimiters, e. g. `# sourceMappingURL=index.js.map`.
+pub fn source_mapping_url_of_comment(text: &str) -> Option<&str> {
+    let url = text
+        .trim_start()
+        .strip_prefix(['#', '@'])?
+        .trim_start()
+        .strip_prefix("sourceMappingURL=")?
+        .split(char::is_whitespace)
+        .next()?;
+    (!url.is_empty()).then_some(url)
+}
+
+/// Resolves the relative `sources` of `map`, which is located in `map_dir`.
+/// The `sourceRoot` has already been prepended to them when parsing.
+async fn resolve_sources(map: &mut CrateMap, map_dir: FileSystemPathVc) -> Result<()> {
+    let map_dir = map_dir.await?;
+    for index in 0..map.get_source_count() {
+        let source = match map.get_source(index) {
+            Some(source) => source,
+            None => continue,
+        };
+        if source.contains("://") || source.starts_with('/') {
+            continue;
+        }
+        if let Some(path) = join_path(&map_dir.path, source) {
+            map.set_source(index, &format!("/{path}"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{source_mapping_url, source_mapping_url_of_comment};
+
+    #[test]
+    fn finds_the_source_mapping_url() {
+        assert_eq!(
+            source_mapping_url("a();\n//# sourceMappingURL=index.js.map\n"),
+            Some("index.js.map")
+        );
+        assert_eq!(
+            source_mapping_url("a();\n//@ sourceMappingURL=index.js.map"),
+            Some("index.js.map")
+        );
+        assert_eq!(
+            source_mapping_url("a {}\n/*# sourceMappingURL=index.css.map */"),
+            Some("index.css.map")
+        );
+        assert_eq!(
+            source_mapping_url("/*# sourceMappingURL=data:application/json;base64,e30=*/"),
+            Some("data:application/json;base64,e30=")
+        );
+        assert_eq!(
+            source_mapping_url("//# sourceMappingURL=a.js.map\n//# sourceMappingURL=b.js.map"),
+            Some("b.js.map")
+        );
+    }
+
+    #[test]
+    fn ignores_urls_outside_of_comments() {
+        assert_eq!(source_mapping_url("a();"), None);
+        assert_eq!(
+            source_mapping_url("const a = \"sourceMappingURL=index.js.map\";"),
+            None
+        );
+        assert_eq!(source_mapping_url("//# sourceMappingURL="), None);
+    }
+
+    #[test]
+    fn finds_the_source_mapping_url_of_comments() {
+        assert_eq!(
+            source_mapping_url_of_comment("# sourceMappingURL=index.js.map"),
+            Some("index.js.map")
+        );
+        assert_eq!(
+            source_mapping_url_of_comment("@ sourceMappingURL=index.css.map "),
+            Some("index.css.map")
+        );
+        assert_eq!(source_mapping_url_of_comment(" a comment"), None);
+        assert_eq!(source_mapping_url_of_comment("# sourceMappingURL="), None);
+    }
+}
@@ -16,6 +16,11 @@ use turbopack_core::{
     reference::{AssetReference, AssetReferencesVc},
     resolve::origin::{ResolveOrigin, ResolveOriginVc},
 };
+use turbopack_ecmascript::{
+    chunk::EcmascriptChunkPlaceableVc,
+    css_module_script::{CssModuleScriptable, CssModuleScriptableVc},
+    ParseResultSourceMap,
+};
 
 use crate::{
     chunk::{
@@ -27,7 +32,7 @@ use crate::{
     path_visitor::ApplyVisitors,
     references::{analyze_css_stylesheet, import::ImportAssetReferenceVc},
     transform::CssInputTransformsVc,
-    CssModuleAssetType,
+    CssModuleAssetType, CssModuleScriptAssetVc,
 };
 
 #[turbo_tasks::value]
@@ -120,6 +125,14 @@ impl CssChunkPlaceable for CssModuleAsset {
     }
 }
 
+#[turbo_tasks::value_impl]
+impl CssModuleScriptable for CssModuleAsset {
+    #[turbo_tasks::function]
+    fn as_css_module_script(self_vc: CssModuleAssetVc) -> EcmascriptChunkPlaceableVc {
+        CssModuleScriptAssetVc::new(self_vc).into()
+    }
+}
+
 #[turbo_tasks::value_impl]
 impl ResolveOrigin for CssModuleAsset {
     #[turbo_tasks::function]
@@ -207,7 +220,12 @@ impl CssChunkItem for ModuleChunkItem {
 
         let parsed = self.module.parse().await?;
 
-        if let ParseResult::Ok { stylesheet, .. } = &*parsed {
+        if let ParseResult::Ok {
+            stylesheet,
+            source_map,
+            ..
+        } = &*parsed
+        {
             let mut stylesheet = stylesheet.clone();
 
             let globals = Globals::new();
@@ -235,18 +253,25 @@ impl CssChunkItem for ModuleChunkItem {
             });
 
             let mut code_string = format!("/* {} */\n", self.module.path().to_string().await?);
+            let mut srcmap = vec![];
 
-            // TODO: pass sourcemap somehow (second param in the css writer)?
             let mut code_gen = CodeGenerator::new(
-                BasicCssWriter::new(&mut code_string, None, Default::default()),
+                BasicCssWriter::new(&mut code_string, Some(&mut srcmap), Default::default()),
                 Default::default(),
             );
 
             code_gen.emit(&stylesheet)?;
 
+            // The writer doesn't know about the comment line at the top.
+            for (_, pos) in srcmap.iter_mut() {
+                pos.line += 1;
+            }
+            let srcmap = ParseResultSourceMap::new(source_map.clone(), srcmap).cell();
+
             Ok(CssChunkItemContent {
                 inner_code: code_string,
                 imports,
+                source_map: Some(srcmap.into()),
             }
             .into())
         } else {
@@ -256,6 +281,7 @@ impl CssChunkItem for ModuleChunkItem {
                     self.module.path().to_string().await?
                 ),
                 imports: vec![],
+                source_map: None,
             }
             .into())
         }
@@ -1,7 +1,7 @@
 pub(crate) mod optimize;
 mod writer;
 
-use std::fmt::Write;
+use std::io::Write;
 
 use anyhow::{anyhow, Result};
 use indexmap::IndexSet;
@@ -16,10 +16,12 @@ use turbopack_core::{
         Chunk, ChunkContentResult, ChunkGroupReferenceVc, ChunkGroupVc, ChunkItem, ChunkItemVc,
         ChunkReferenceVc, ChunkVc, ChunkableAssetVc, ChunkingContextVc, FromChunkableAsset,
     },
+    code_builder::{CodeBuilder, CodeVc},
     reference::{AssetReferenceVc, AssetReferencesVc},
+    source_map::{GenerateSourceMap, GenerateSourceMapVc, SourceMapVc},
 };
 use turbopack_ecmascript::utils::FormatIter;
-use writer::{expand_imports, WriterWithIndent};
+use writer::expand_imports;
 
 use self::optimize::CssChunkOptimizerVc;
 use crate::{embed::CssEmbeddableVc, util::stringify_str, ImportAssetReferenceVc};
@@ -175,6 +177,39 @@ impl ValueToString for CssChunk {
     }
 }
 
+#[turbo_tasks::value_impl]
+impl CssChunkVc {
+    /// Assembles the code of the chunk from the code of its chunk items. The
+    /// source maps of the chunk items are kept along the way.
+    #[turbo_tasks::function]
+    async fn code(self) -> Result<CodeVc> {
+        let this = self.await?;
+
+        let chunk_name = self.path().to_string();
+
+        let mut body = CodeBuilder::default();
+        let mut external_imports = IndexSet::new();
+        for entry in this.main_entries.await?.iter() {
+            let entry_placeable = CssChunkPlaceableVc::cast_from(entry);
+            let entry_content = entry_placeable.as_chunk_item(this.context).content();
+
+            for external_import in expand_imports(&mut body, entry_content).await? {
+                external_imports.insert(external_import.await?.to_owned());
+            }
+        }
+
+        let mut code = CodeBuilder::default();
+        writeln!(code, "/* chunk {} */", chunk_name.await?)?;
+        for external_import in external_imports {
+            writeln!(code, "@import {};", stringify_str(&external_import))?;
+        }
+
+        code.push_code(&body.build());
+
+        Ok(code.build().cell())
+    }
+}
+
 #[turbo_tasks::value_impl]
 impl Asset for CssChunk {
     #[turbo_tasks::function]
@@ -205,31 +240,8 @@ impl Asset for CssChunk {
 
     #[turbo_tasks::function]
     async fn content(self_vc: CssChunkVc) -> Result<AssetContentVc> {
-        let this = self_vc.await?;
-
-        let path = self_vc.path();
-        let chunk_name = path.to_string();
-
-        let mut body = "".to_owned();
-        let mut writer = WriterWithIndent::new(&mut body);
-        let mut external_imports = IndexSet::new();
-        for entry in this.main_entries.await?.iter() {
-            let entry_placeable = CssChunkPlaceableVc::cast_from(entry);
-            let entry_content = entry_placeable.as_chunk_item(this.context).content();
-
-            for external_import in expand_imports(&mut writer, entry_content).await? {
-                external_imports.insert(external_import.await?.to_owned());
-            }
-        }
-
-        let mut code = format!("/* chunk {} */\n", chunk_name.await?);
-        for external_import in external_imports {
-            writeln!(code, "@import {};", stringify_str(&external_import))?;
-        }
-
-        code += &body;
-
-        Ok(File::from(code).into())
+        let code = self_vc.code().await?;
+        Ok(File::from(code.source_code().clone()).into())
     }
 
     #[turbo_tasks::function]
@@ -287,11 +299,12 @@ pub enum CssImport {
 pub struct CssChunkItemContent {
     pub inner_code: String,
     pub imports: Vec<CssImport>,
+    /// The source map of `inner_code`.
+    pub source_map: Option<GenerateSourceMapVc>,
 }
 
 #[turbo_tasks::value_trait]
 pub trait CssChunkItem: ChunkItem + ValueToString {
-    // TODO handle Source Maps
     fn content(&self) -> CssChunkItemContentVc;
 }
 
@@ -311,3 +324,11 @@ impl FromChunkableAsset for CssChunkItemVc {
         Ok(None)
     }
 }
+
+#[turbo_tasks::value_impl]
+impl GenerateSourceMap for CssChunk {
+    #[turbo_tasks::function]
+    fn generate_source_map(self_vc: CssChunkVc) -> SourceMapVc {
+        self_vc.code().generate_source_map()
+    }
+}
@@ -1,14 +1,20 @@
-use std::{collections::VecDeque, fmt::Write};
+use std::{collections::VecDeque, io::Write};
 
+use anyhow::Result;
 use turbo_tasks::{primitives::StringVc, ValueToString};
+use turbo_tasks_fs::rope::Rope;
+use turbopack_core::code_builder::{CodeBuilder, CodeVc};
 
 use super::CssImport;
 use crate::CssChunkItemContentVc;
 
-pub async fn expand_imports<T: Write>(
-    writer: &mut WriterWithIndent<T>,
+/// Writes the content of a chunk item and all its internal imports into the
+/// `code`. Returns the external imports, which need to be placed at the top of
+/// the chunk.
+pub async fn expand_imports(
+    code: &mut CodeBuilder,
     content_vc: CssChunkItemContentVc,
-) -> anyhow::Result<Vec<StringVc>> {
+) -> Result<Vec<StringVc>> {
     let content = &*content_vc.await?;
     let mut stack = vec![(
         content_vc,
@@ -16,19 +22,19 @@ pub async fn expand_imports<T: Write>(
         (0, "".to_string()),
     )];
     let mut external_imports = vec![];
+    let mut indent = 0;
 
-    while let Some((content_vc, imports, (indent, close))) = stack.last_mut() {
+    while let Some((content_vc, imports, (inner_indent, close))) = stack.last_mut() {
         match imports.pop_front() {
             Some(CssImport::Internal(import, imported_chunk_item)) => {
                 let (open, inner_indent, close) = import.await?.attributes.await?.print_block()?;
 
                 let id = &*imported_chunk_item.to_string().await?;
 
-                writeln!(writer, "/* import({}) */", id)?;
-                writeln!(writer, "{}", open)?;
+                write_indented(code, indent, &format!("/* import({}) */\n{}\n", id, open))?;
                 let imported_content_vc = imported_chunk_item.content();
                 let imported_content = &*imported_content_vc.await?;
-                writer.push_indent(inner_indent)?;
+                indent += inner_indent;
                 stack.push((
                     imported_content_vc,
                     imported_content.imports.iter().cloned().collect(),
@@ -40,9 +46,17 @@ pub async fn expand_imports<T: Write>(
             }
             None => {
                 let content = &*(*content_vc).await?;
-                writeln!(writer, "{}", content.inner_code)?;
-                writer.pop_indent(*indent)?;
-                writeln!(writer, "{}", close)?;
+                if indent == 0 {
+                    // Unindented code can be pushed as is, which keeps its source map valid.
+                    code.push_code(&*chunk_item_code(*content_vc).await?);
+                    *code += "\n";
+                } else {
+                    // Indenting the code shifts all columns, so its source map would no longer
+                    // match.
+                    write_indented(code, indent, &format!("{}\n", content.inner_code))?;
+                }
+                indent = indent.saturating_sub(*inner_indent);
+                write_indented(code, indent, &format!("{}\n", close))?;
                 stack.pop();
             }
         }
@@ -51,58 +65,26 @@ pub async fn expand_imports<T: Write>(
     Ok(external_imports)
 }
 
-pub struct WriterWithIndent<T: Write> {
-    writer: T,
-    indent_str: String,
-    needs_indent: bool,
-}
-
-impl<T: Write> WriterWithIndent<T> {
-    pub fn new(buffer: T) -> Self {
-        Self {
-            writer: buffer,
-            indent_str: "".to_string(),
-            needs_indent: true,
+/// Writes `text` to `code`, prefixing every non-empty line with `indent`
+/// spaces.
+fn write_indented(code: &mut CodeBuilder, indent: usize, text: &str) -> Result<()> {
+    let indent_str = " ".repeat(indent);
+    for line in text.split_inclusive('\n') {
+        if indent > 0 && line != "\n" {
+            code.write_all(indent_str.as_bytes())?;
         }
+        code.write_all(line.as_bytes())?;
     }
-
-    pub fn push_indent(&mut self, indent: usize) -> std::fmt::Result {
-        self.indent_str += &" ".repeat(indent);
-
-        Ok(())
-    }
-
-    pub fn pop_indent(&mut self, indent: usize) -> std::fmt::Result {
-        self.indent_str = " ".repeat(self.indent_str.len().saturating_sub(indent));
-
-        Ok(())
-    }
+    Ok(())
 }
 
-impl<T: Write> Write for WriterWithIndent<T> {
-    #[inline]
-    fn write_str(&mut self, s: &str) -> std::fmt::Result {
-        for c in s.chars() {
-            self.write_char(c)?;
-        }
-
-        Ok(())
-    }
-
-    #[inline]
-    fn write_char(&mut self, c: char) -> std::fmt::Result {
-        if c == '\n' {
-            self.writer.write_char('\n')?;
-            self.needs_indent = true;
-
-            return Ok(());
-        }
-
-        if self.needs_indent {
-            self.writer.write_str(&self.indent_str)?;
-            self.needs_indent = false;
-        }
-
-        self.writer.write_char(c)
-    }
+/// The code of a chunk item is stored in its own cell, so assembling a chunk
+/// after a change to a single chunk item only concatenates the code of the
+/// other chunk items without copying it.
+#[turbo_tasks::function]
+async fn chunk_item_code(content: CssChunkItemContentVc) -> Result<CodeVc> {
+    let content = content.await?;
+    let mut code = CodeBuilder::default();
+    code.push_source(&Rope::from(content.inner_code.clone()), content.source_map);
+    Ok(code.build().cell())
 }
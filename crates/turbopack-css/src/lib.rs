@@ -9,6 +9,7 @@ pub mod chunk;
 mod code_gen;
 pub mod embed;
 mod module_asset;
+mod module_script;
 pub(crate) mod parse;
 mod path_visitor;
 pub(crate) mod references;
@@ -18,6 +19,7 @@ pub(crate) mod util;
 use anyhow::Result;
 pub use asset::CssModuleAssetVc;
 pub use module_asset::ModuleCssModuleAssetVc;
+pub use module_script::CssModuleScriptAssetVc;
 pub use transform::{CssInputTransform, CssInputTransformsVc};
 
 use crate::{chunk::CssChunkItemContentVc, references::import::ImportAssetReferenceVc};
@@ -0,0 +1,202 @@
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    asset::{Asset, AssetContentVc},
+    chunk::{ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset, ChunkableAssetVc, ChunkingContextVc},
+    issue::{code_gen::CodeGenerationIssue, IssueSeverity},
+    reference::AssetReferencesVc,
+};
+use turbopack_ecmascript::{
+    chunk::{
+        EcmascriptChunkItem, EcmascriptChunkItemContent, EcmascriptChunkItemContentVc,
+        EcmascriptChunkItemVc, EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc,
+        EcmascriptChunkVc, EcmascriptExports, EcmascriptExportsVc,
+    },
+    utils::stringify_str,
+};
+
+use crate::{
+    chunk::CssChunkPlaceableVc, embed::CssEmbeddableVc, references::url::UrlAssetReferenceVc,
+    CssModuleAssetVc,
+};
+
+/// A CSS module script, i. e. a stylesheet which is imported with
+/// `assert { type: "css" }`. Its default export is a constructable
+/// `CSSStyleSheet`, and unlike other imported CSS it isn't applied to the
+/// document.
+#[turbo_tasks::value]
+pub struct CssModuleScriptAsset {
+    inner: CssModuleAssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl CssModuleScriptAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(inner: CssModuleAssetVc) -> Self {
+        Self::cell(CssModuleScriptAsset { inner })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for CssModuleScriptAsset {
+    /// The stylesheet itself might be imported without an assertion too, so
+    /// the module script needs a path of its own.
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.inner.path().append(".module-script.js")
+    }
+
+    #[turbo_tasks::function]
+    fn content(&self) -> AssetContentVc {
+        self.inner.content()
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        self.inner.references()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableAsset for CssModuleScriptAsset {
+    #[turbo_tasks::function]
+    fn as_chunk(self_vc: CssModuleScriptAssetVc, context: ChunkingContextVc) -> ChunkVc {
+        EcmascriptChunkVc::new(context, self_vc.into()).into()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptChunkPlaceable for CssModuleScriptAsset {
+    #[turbo_tasks::function]
+    fn as_chunk_item(&self, context: ChunkingContextVc) -> EcmascriptChunkItemVc {
+        ModuleScriptChunkItem {
+            module: self.inner,
+            context,
+        }
+        .cell()
+        .into()
+    }
+
+    #[turbo_tasks::function]
+    fn get_exports(&self) -> EcmascriptExportsVc {
+        EcmascriptExports::Value.cell()
+    }
+}
+
+#[turbo_tasks::value]
+struct ModuleScriptChunkItem {
+    module: CssModuleAssetVc,
+    context: ChunkingContextVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for ModuleScriptChunkItem {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "{} (css module script)",
+            self.module.await?.source.path().to_string().await?
+        )))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkItem for ModuleScriptChunkItem {
+    /// Only the assets of `url()`s are needed at runtime. `@import`s are not
+    /// supported by constructable stylesheets.
+    #[turbo_tasks::function]
+    async fn references(&self) -> Result<AssetReferencesVc> {
+        let mut references = Vec::new();
+        for reference in self.module.references().await?.iter() {
+            if UrlAssetReferenceVc::resolve_from(reference)
+                .await?
+                .is_none()
+            {
+                continue;
+            }
+            for asset in reference.resolve_reference().primary_assets().await?.iter() {
+                if let Some(embeddable) = CssEmbeddableVc::resolve_from(asset).await? {
+                    let embed = embeddable.as_css_embed(self.context);
+                    references.extend(embed.references().await?.iter());
+                }
+            }
+        }
+        Ok(AssetReferencesVc::cell(references))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptChunkItem for ModuleScriptChunkItem {
+    #[turbo_tasks::function]
+    fn chunking_context(&self) -> ChunkingContextVc {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<EcmascriptChunkItemContentVc> {
+        let content = CssChunkPlaceableVc::from(self.module)
+            .as_chunk_item(self.context)
+            .content()
+            .await?;
+        if !content.imports.is_empty() {
+            CodeGenerationIssue {
+                severity: IssueSeverity::Warning.into(),
+                title: StringVc::cell("@import in CSS module script".to_string()),
+                message: StringVc::cell(
+                    "@import rules are ignored when a stylesheet is imported with `assert { type: \
+                     \"css\" }`"
+                        .to_string(),
+                ),
+                path: self.module.path(),
+            }
+            .cell()
+            .as_issue()
+            .emit();
+        }
+
+        // `url()`s are rewritten relative to the CSS chunk the stylesheet
+        // would be placed in, which becomes the base URL of the stylesheet.
+        let chunk_path = self.context.chunk_path(self.module.path(), ".css");
+        let chunk_dir = chunk_path.parent().await?;
+        let base_path = base_path(self.context.output_root().await?.get_path_to(&chunk_dir));
+
+        // Only browsers can construct stylesheets. Other environments, e. g.
+        // server rendering, get `null`, as the stylesheet can't be applied
+        // there anyway.
+        Ok(EcmascriptChunkItemContent {
+            inner_code: format!(
+                "const sheet = typeof CSSStyleSheet === \"function\" ? new CSSStyleSheet({{ \
+                 baseURL: new URL({base_path}, location.href).href }}) : null;\nif (sheet) \
+                 sheet.replaceSync({css});\n__turbopack_export_value__(sheet);",
+                base_path = stringify_str(&base_path),
+                css = stringify_str(&content.inner_code)
+            )
+            .into(),
+            ..Default::default()
+        }
+        .into())
+    }
+}
+
+/// The server path of a directory of the output, which is the root when it's
+/// not inside of the output root.
+fn base_path(path_in_output: Option<&str>) -> String {
+    match path_in_output {
+        Some(path) if !path.is_empty() => format!("/{path}/"),
+        _ => "/".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::base_path;
+
+    #[test]
+    fn base_path_is_a_directory() {
+        assert_eq!(base_path(Some("_chunks")), "/_chunks/");
+        assert_eq!(base_path(Some("a/b")), "/a/b/");
+        assert_eq!(base_path(Some("")), "/");
+        assert_eq!(base_path(None), "/");
+    }
+}
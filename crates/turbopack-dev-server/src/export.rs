@@ -0,0 +1,360 @@
+use std::collections::{HashSet, VecDeque};
+
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, Value};
+use turbo_tasks_fs::{FileContent, FileSystemPathVc};
+use turbopack_cli_utils::issue::ConsoleUiVc;
+use turbopack_core::issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc};
+
+use crate::{
+    get_from_source, handle_issues, resource_to_data,
+    source::{ContentSourceData, ContentSourceVc},
+    update::protocol::ResourceIdentifier,
+    GetFromSourceResult,
+};
+
+/// The outcome of exporting a single route.
+#[turbo_tasks::value(shared)]
+#[derive(Debug)]
+pub enum ExportedRoute {
+    /// An HTML page was written. `references` are the same origin routes and
+    /// assets it links to.
+    Page {
+        references: Vec<String>,
+    },
+    /// Any other file was written.
+    Asset,
+    /// The route can only be served by a running server. An issue was
+    /// emitted for it.
+    NeedsServer,
+    NotFound,
+}
+
+/// The files written by [export].
+#[derive(Debug, Default)]
+pub struct ExportReport {
+    pub pages: Vec<String>,
+    pub assets: Vec<String>,
+    pub needs_server: Vec<String>,
+    pub not_found: Vec<String>,
+}
+
+/// Writes a fully static version of `source` to `output_dir`.
+///
+/// Starting from `routes`, every route is requested like a browser without
+/// any headers would do it, and the content is written to the output
+/// directory. HTML pages are written as `<route>/index.html` and the routes
+/// and assets they reference (`href="/..."` and `src="/..."`) are exported
+/// too. Routes which can't be served statically, like API routes, are
+/// reported as issues and skipped.
+///
+/// Must be called within a turbo-tasks context.
+pub async fn export(
+    source: ContentSourceVc,
+    output_dir: FileSystemPathVc,
+    routes: Vec<String>,
+    console_ui: ConsoleUiVc,
+) -> Result<ExportReport> {
+    let source = source.resolve_strongly_consistent().await?;
+    let mut report = ExportReport::default();
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+    for route in routes {
+        if seen.insert(route.clone()) {
+            queue.push_back(route);
+        }
+    }
+    while let Some(route) = queue.pop_front() {
+        let exported = export_route(source, &route, output_dir);
+        handle_issues(exported, &route, "export", console_ui).await?;
+        match &*exported.strongly_consistent().await? {
+            ExportedRoute::Page { references } => {
+                for reference in references {
+                    if seen.insert(reference.clone()) {
+                        queue.push_back(reference.clone());
+                    }
+                }
+                report.pages.push(route);
+            }
+            ExportedRoute::Asset => report.assets.push(route),
+            ExportedRoute::NeedsServer => report.needs_server.push(route),
+            ExportedRoute::NotFound => report.not_found.push(route),
+        }
+    }
+    Ok(report)
+}
+
+#[turbo_tasks::function]
+async fn export_route(
+    source: ContentSourceVc,
+    route: &str,
+    output_dir: FileSystemPathVc,
+) -> Result<ExportedRouteVc> {
+    let mut source = source;
+    let mut path = route.to_string();
+    let mut data = ContentSourceData::default();
+    loop {
+        let result = get_from_source(source, &path, Value::new(data)).await?;
+        match &*result {
+            GetFromSourceResult::NeedData {
+                source: needed_source,
+                path: needed_path,
+                vary,
+            } => {
+                // The body and a cache buster are only requested by content
+                // which is computed for every request.
+                if vary.body || vary.cache_buster {
+                    return Ok(needs_server(route, output_dir, "depends on the request"));
+                }
+                source = *needed_source;
+                path = needed_path.clone();
+                data = resource_to_data(
+                    ResourceIdentifier {
+                        path: path.clone(),
+                        headers: None,
+                    },
+                    vary,
+                );
+            }
+            GetFromSourceResult::Static { content, .. } => {
+                let file = if let FileContent::Content(file) = &**content {
+                    file
+                } else {
+                    return Ok(ExportedRoute::NotFound.cell());
+                };
+                let is_html = file.content_type().map_or_else(
+                    || mime_guess::from_path(route).first_raw() == Some("text/html"),
+                    |content_type| content_type.subtype() == mime::HTML,
+                );
+                let file_path = if is_html && !route.ends_with(".html") {
+                    let route = route.trim_end_matches('/');
+                    if route.is_empty() {
+                        "index.html".to_string()
+                    } else {
+                        format!("{route}/index.html")
+                    }
+                } else {
+                    route.to_string()
+                };
+                output_dir
+                    .join(&file_path)
+                    .write(FileContent::Content(file.clone()).cell())
+                    .await?;
+                return Ok(if is_html {
+                    let html = file.content().to_str()?;
+                    ExportedRoute::Page {
+                        references: references(&html).into_iter().map(str::to_string).collect(),
+                    }
+                } else {
+                    ExportedRoute::Asset
+                }
+                .cell());
+            }
+            GetFromSourceResult::HttpProxy(_) => {
+                return Ok(needs_server(route, output_dir, "is proxied to a server"));
+            }
+            GetFromSourceResult::WebSocketProxy(_) => {
+                return Ok(needs_server(route, output_dir, "is a WebSocket"));
+            }
+            GetFromSourceResult::NotFound => return Ok(ExportedRoute::NotFound.cell()),
+        }
+    }
+}
+
+fn needs_server(route: &str, output_dir: FileSystemPathVc, reason: &str) -> ExportedRouteVc {
+    ExportIssue {
+        path: output_dir.join(route),
+        route: route.to_string(),
+        reason: reason.to_string(),
+    }
+    .cell()
+    .as_issue()
+    .emit();
+    ExportedRoute::NeedsServer.cell()
+}
+
+/// Returns the same origin routes and assets referenced by the `href`, `src`
+/// and `srcset` attributes in `html`, without the leading slash.
+fn references(html: &str) -> Vec<&str> {
+    let mut references = Vec::new();
+    for (name, value) in attributes(html) {
+        match &*name {
+            "href" | "src" => references.extend(same_origin_path(value)),
+            "srcset" => references.extend(
+                value
+                    .split(',')
+                    .filter_map(|candidate| candidate.split_whitespace().next())
+                    .filter_map(same_origin_path),
+            ),
+            _ => {}
+        }
+    }
+    references
+}
+
+/// The path of a same origin URL without the leading slash, query and
+/// fragment.
+fn same_origin_path(url: &str) -> Option<&str> {
+    let path = url.trim().split(['#', '?']).next()?;
+    if !path.starts_with('/') || path.starts_with("//") {
+        return None;
+    }
+    Some(&path[1..])
+}
+
+/// The attributes of the start tags in `html`, with lowercase names. Values
+/// can be double-quoted, single-quoted or unquoted. Comments and the contents
+/// of `<script>` and `<style>` elements are skipped.
+fn attributes(html: &str) -> Vec<(String, &str)> {
+    let mut attributes = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        if let Some(comment) = rest.strip_prefix("!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        if !rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            continue;
+        }
+        let tag_end = rest
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(rest.len());
+        let tag = rest[..tag_end].to_ascii_lowercase();
+        rest = &rest[tag_end..];
+        loop {
+            rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '/');
+            if rest.is_empty() {
+                break;
+            }
+            if let Some(after) = rest.strip_prefix('>') {
+                rest = after;
+                break;
+            }
+            let name_end = rest
+                .find(|c: char| c.is_ascii_whitespace() || matches!(c, '=' | '>' | '/'))
+                .unwrap_or(rest.len());
+            if name_end == 0 {
+                // A stray `=`.
+                rest = &rest[1..];
+                continue;
+            }
+            let name = rest[..name_end].to_ascii_lowercase();
+            rest = rest[name_end..].trim_start_matches(|c: char| c.is_ascii_whitespace());
+            rest = if let Some(after) = rest.strip_prefix('=') {
+                after.trim_start_matches(|c: char| c.is_ascii_whitespace())
+            } else {
+                // An attribute without a value.
+                continue;
+            };
+            let value =
+                if let Some(quote) = rest.chars().next().filter(|&c| matches!(c, '"' | '\'')) {
+                    let quoted = &rest[1..];
+                    let end = quoted.find(quote).unwrap_or(quoted.len());
+                    rest = quoted.get(end + 1..).unwrap_or("");
+                    &quoted[..end]
+                } else {
+                    let end = rest
+                        .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                        .unwrap_or(rest.len());
+                    let value = &rest[..end];
+                    rest = &rest[end..];
+                    value
+                };
+            attributes.push((name, value));
+        }
+        if tag == "script" || tag == "style" {
+            // Only ASCII is lowercased, so the indices are the same.
+            rest = rest
+                .to_ascii_lowercase()
+                .find(&format!("</{tag}"))
+                .map_or("", |end| &rest[end..]);
+        }
+    }
+    attributes
+}
+
+#[turbo_tasks::value(shared)]
+struct ExportIssue {
+    path: FileSystemPathVc,
+    route: String,
+    reason: String,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for ExportIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Warning.into()
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("export".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Route requires a server".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(format!(
+            "The route /{} {} and can't be exported statically. It was skipped.",
+            self.route, self.reason
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::references;
+
+    #[test]
+    fn finds_quoted_and_unquoted_references() {
+        assert_eq!(
+            references(
+                r#"<a href="/about">About</a><link rel=stylesheet href='/style.css'>
+                <IMG SRC=/logo.png alt=logo><a href = "/docs?page=2#intro">"#
+            ),
+            ["about", "style.css", "logo.png", "docs"]
+        );
+    }
+
+    #[test]
+    fn finds_srcset_candidates() {
+        assert_eq!(
+            references(r#"<img srcset="/small.png 1x, /large.png 2x, https://cdn/x.png 3x">"#),
+            ["small.png", "large.png"]
+        );
+    }
+
+    #[test]
+    fn skips_other_origins_and_attributes() {
+        assert_eq!(
+            references(
+                r#"<a href="https://example.com/a"></a><a href="//cdn/b"></a>
+                <a href="relative"></a><a data-href="/data"></a><a title="src=/title"></a>
+                <input disabled value=x>"#
+            ),
+            Vec::<&str>::new()
+        );
+    }
+
+    #[test]
+    fn skips_comments_and_scripts() {
+        assert_eq!(
+            references(
+                r#"<!-- <a href="/commented"> --><script>"<a href='/script'>"</script>
+                <STYLE>a { background: url(/style.png) }</STYLE><a href="/">Home</a>"#
+            ),
+            [""]
+        );
+    }
+}
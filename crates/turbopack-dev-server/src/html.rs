@@ -1,4 +1,5 @@
 use anyhow::{anyhow, Result};
+use indexmap::IndexSet;
 use mime_guess::mime::TEXT_HTML_UTF_8;
 use turbo_tasks::{debug::ValueDebug, primitives::StringVc};
 use turbo_tasks_fs::{File, FileSystemPathVc};
@@ -102,17 +103,22 @@ impl DevHtmlAssetVc {
         let this = self.await?;
         let context_path = this.path.parent().await?;
 
-        let mut chunk_paths = vec![];
+        // Chunks shared between chunk groups, e.g. the runtime chunk, must only be
+        // included once per document.
+        let mut chunk_paths = IndexSet::new();
         for chunk_group in &this.chunk_groups {
             for chunk in chunk_group.chunks().await?.iter() {
                 let chunk_path = &*chunk.path().await?;
                 if let Some(relative_path) = context_path.get_path_to(chunk_path) {
-                    chunk_paths.push(format!("/{relative_path}"));
+                    chunk_paths.insert(format!("/{relative_path}"));
                 }
             }
         }
 
-        Ok(DevHtmlAssetContentVc::new(chunk_paths, this.body.clone()))
+        Ok(DevHtmlAssetContentVc::new(
+            chunk_paths.into_iter().collect(),
+            this.body.clone(),
+        ))
     }
 }
 
@@ -1,7 +1,7 @@
 use std::{collections::HashSet, fmt::Display};
 
 use anyhow::Result;
-use turbo_tasks::{primitives::StringVc, TryJoinIterExt};
+use turbo_tasks::{primitives::StringVc, TryJoinIterExt, WeakVc};
 use turbo_tasks_fs::{File, FileContent};
 use turbopack_core::{
     asset::AssetContent,
@@ -15,7 +15,23 @@ use crate::source::{
 
 #[turbo_tasks::value(shared)]
 pub struct IntrospectionSource {
-    pub roots: HashSet<IntrospectableVc>,
+    /// The roots are referenced weakly, so introspecting them doesn't keep
+    /// them alive.
+    #[turbo_tasks(debug_ignore)]
+    pub roots: HashSet<WeakVc<IntrospectableVc>>,
+}
+
+impl IntrospectionSource {
+    /// The roots which weren't collected yet.
+    async fn live_roots(&self) -> Result<Vec<IntrospectableVc>> {
+        let mut roots = Vec::new();
+        for root in &self.roots {
+            if let Some(root) = root.upgrade().await? {
+                roots.push(root);
+            }
+        }
+        Ok(roots)
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -31,9 +47,15 @@ impl Introspectable for IntrospectionSource {
     }
 
     #[turbo_tasks::function]
-    fn children(&self) -> IntrospectableChildrenVc {
+    async fn children(&self) -> Result<IntrospectableChildrenVc> {
         let name = StringVc::cell("root".to_string());
-        IntrospectableChildrenVc::cell(self.roots.iter().map(|root| (name, *root)).collect())
+        Ok(IntrospectableChildrenVc::cell(
+            self.live_roots()
+                .await?
+                .into_iter()
+                .map(|root| (name, root))
+                .collect(),
+        ))
     }
 }
 
@@ -79,9 +101,9 @@ impl ContentSource for IntrospectionSource {
         _data: turbo_tasks::Value<ContentSourceData>,
     ) -> Result<ContentSourceResultVc> {
         let introspectable = if path.is_empty() {
-            let roots = &self_vc.await?.roots;
+            let roots = self_vc.await?.live_roots().await?;
             if roots.len() == 1 {
-                *roots.iter().next().unwrap()
+                roots[0]
             } else {
                 self_vc.as_introspectable()
             }
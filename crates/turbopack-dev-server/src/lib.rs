@@ -2,17 +2,23 @@
 #![feature(trait_alias)]
 #![feature(array_chunks)]
 
+pub mod export;
 pub mod fs;
 pub mod html;
 pub mod introspect;
+pub mod security;
 pub mod source;
 pub mod update;
+pub mod warmup;
 
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
 use std::{
     borrow::Cow,
     collections::{btree_map::Entry, BTreeMap},
+    fmt::{self, Display, Formatter},
     future::Future,
-    net::SocketAddr,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
     pin::Pin,
     sync::{
         atomic::{AtomicU64, Ordering},
@@ -22,27 +28,45 @@ use std::{
 };
 
 use anyhow::{bail, Context, Result};
-use futures::{StreamExt, TryStreamExt};
+use futures::{future::try_join, StreamExt, TryStreamExt};
 use hyper::{
     header::HeaderName,
+    server::{accept, conn::AddrStream},
     service::{make_service_fn, service_fn},
-    Request, Response, Server,
+    Method, Request, Response, Server, StatusCode,
 };
 use mime_guess::mime;
+use socket2::{Domain, Protocol, Socket, Type};
 use source::{Body, Bytes};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+use tokio::{io::copy_bidirectional, sync::Notify};
 use turbo_tasks::{
-    run_once, trace::TraceRawVcs, util::FormatDuration, RawVc, TransientValue, TurboTasksApi, Value,
+    primitives::{StringReadRef, StringVc},
+    run_once,
+    trace::TraceRawVcs,
+    util::FormatDuration,
+    with_priority, RawVc, TaskPriority, TransientValue, TurboTasksApi, Value,
+};
+use turbo_tasks_fs::{FileContent, FileContentReadRef, FileSystemPathVc};
+use turbopack_cli_utils::{
+    events::Event,
+    issue::{ConsoleUi, ConsoleUiVc},
+};
+use turbopack_core::{
+    asset::AssetContent,
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
 };
-use turbo_tasks_fs::{FileContent, FileContentReadRef};
-use turbopack_cli_utils::issue::{ConsoleUi, ConsoleUiVc};
-use turbopack_core::asset::AssetContent;
 
 use self::{
+    fs::DevServerFileSystemVc,
+    security::{add_cors_headers, preflight_response, SecurityOptions},
     source::{
         query::Query, ContentSourceContent, ContentSourceDataVary, ContentSourceResultVc,
-        ContentSourceVc, ProxyResultReadRef,
+        ContentSourceVc, ProxyResult, ProxyResultReadRef,
     },
     update::{protocol::ResourceIdentifier, UpdateServer},
+    warmup::{Warmup, WarmupOptions, EXECUTED_MODULES_PATH},
 };
 use crate::source::{ContentSourceData, HeaderValue};
 
@@ -64,12 +88,77 @@ where
     }
 }
 
+/// Where a [DevServer] accepts connections.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ServerAddr {
+    Tcp(SocketAddr),
+    /// A unix domain socket, e. g. for a reverse proxy on the same machine.
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl ServerAddr {
+    pub fn tcp(&self) -> Option<SocketAddr> {
+        match self {
+            ServerAddr::Tcp(addr) => Some(*addr),
+            #[cfg(unix)]
+            ServerAddr::Unix(_) => None,
+        }
+    }
+
+    /// The URL to open the server with in a browser on the same machine.
+    /// Browsers can't connect to unix domain sockets.
+    pub fn url(&self) -> Option<String> {
+        let addr = self.tcp()?;
+        let ip = addr.ip();
+        Some(
+            if ip.is_unspecified() || ip == IpAddr::V4(Ipv4Addr::LOCALHOST) {
+                format!("http://localhost:{}", addr.port())
+            } else {
+                format!("http://{addr}")
+            },
+        )
+    }
+}
+
+impl From<SocketAddr> for ServerAddr {
+    fn from(addr: SocketAddr) -> Self {
+        ServerAddr::Tcp(addr)
+    }
+}
+
+impl Display for ServerAddr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerAddr::Tcp(addr) => write!(f, "{addr}"),
+            #[cfg(unix)]
+            ServerAddr::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
 #[derive(TraceRawVcs)]
 pub struct DevServer {
     #[turbo_tasks(trace_ignore)]
-    pub addr: SocketAddr,
+    pub addr: ServerAddr,
     #[turbo_tasks(trace_ignore)]
     pub future: Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>,
+    #[turbo_tasks(trace_ignore)]
+    pub shutdown: ShutdownHandle,
+}
+
+/// Stops a [DevServer] gracefully.
+#[derive(Clone, Default)]
+pub struct ShutdownHandle {
+    notify: Arc<Notify>,
+}
+
+impl ShutdownHandle {
+    /// Stops accepting connections. The [DevServer]'s future resolves once all
+    /// in-flight requests have been answered.
+    pub fn shutdown(&self) {
+        self.notify.notify_one();
+    }
 }
 
 // Just print issues to console for now...
@@ -91,8 +180,17 @@ async fn handle_issues<T: Into<RawVc>>(
 
 #[turbo_tasks::value(serialization = "none")]
 enum GetFromSourceResult {
-    Static(FileContentReadRef),
+    Static {
+        content: FileContentReadRef,
+        /// The id of the version of the content, empty when it's not
+        /// versioned.
+        version: StringReadRef,
+        /// Additional response headers, arranged as contiguous (name, value)
+        /// pairs.
+        headers: Vec<String>,
+    },
     HttpProxy(ProxyResultReadRef),
+    WebSocketProxy(String),
     NeedData {
         source: ContentSourceVc,
         path: String,
@@ -107,46 +205,164 @@ async fn get_from_source(
     path: &str,
     data: Value<ContentSourceData>,
 ) -> Result<GetFromSourceResultVc> {
-    let content = source.get(path, data).await?.content.await?;
+    let content = match async { source.get(path, data).await?.content.await }.await {
+        Ok(content) => content,
+        Err(err) => {
+            // Report the failure, e. g. a panic in a task, with the other issues,
+            // while the request fails.
+            ServeErrorIssue {
+                path: DevServerFileSystemVc::new()
+                    .as_file_system()
+                    .root()
+                    .join(path),
+                error: StringVc::cell(format!("{err:?}")),
+            }
+            .cell()
+            .as_issue()
+            .emit();
+            return Err(err);
+        }
+    };
+    let mut content = content;
+    let mut headers = Vec::new();
+    while let ContentSourceContent::WithHeaders {
+        content: inner,
+        headers: inner_headers,
+    } = &*content
+    {
+        headers.extend(inner_headers.iter().cloned());
+        content = inner.await?;
+    }
     Ok(match &*content {
         ContentSourceContent::Static(content_vc) => {
             if let AssetContent::File(file) = &*content_vc.content().await? {
-                GetFromSourceResult::Static(file.await?)
+                GetFromSourceResult::Static {
+                    content: file.await?,
+                    version: content_vc.version().id().await?,
+                    headers,
+                }
             } else {
                 GetFromSourceResult::NotFound
             }
         }
+        ContentSourceContent::HttpProxy(proxy) if !headers.is_empty() => {
+            let proxy = proxy.await?;
+            GetFromSourceResult::HttpProxy(
+                ProxyResult {
+                    status: proxy.status,
+                    headers: proxy.headers.iter().cloned().chain(headers).collect(),
+                    body: proxy.body.clone(),
+                }
+                .cell()
+                .await?,
+            )
+        }
         ContentSourceContent::HttpProxy(proxy) => GetFromSourceResult::HttpProxy(proxy.await?),
+        ContentSourceContent::WebSocketProxy(target) => {
+            GetFromSourceResult::WebSocketProxy(target.clone())
+        }
         ContentSourceContent::NeedData { source, path, vary } => GetFromSourceResult::NeedData {
             source: source.resolve().await?,
             path: path.clone(),
             vary: vary.clone(),
         },
         ContentSourceContent::NotFound => GetFromSourceResult::NotFound,
+        ContentSourceContent::WithHeaders { .. } => unreachable!(),
     }
     .cell())
 }
 
+#[turbo_tasks::value(shared)]
+struct ServeErrorIssue {
+    path: FileSystemPathVc,
+    error: StringVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for ServeErrorIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Error.into()
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("serve".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell("Failed to compile the requested content".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.path
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        self.error
+    }
+}
+
+/// Where the time to serve a request was spent.
+#[derive(Default)]
+struct RequestTiming {
+    /// Waiting for turbo-tasks to compute the content.
+    compile: Duration,
+    /// Reading the request.
+    io: Duration,
+}
+
+impl RequestTiming {
+    async fn compile<T>(&mut self, future: impl Future<Output = T>) -> T {
+        measure(&mut self.compile, future).await
+    }
+
+    async fn io<T>(&mut self, future: impl Future<Output = T>) -> T {
+        measure(&mut self.io, future).await
+    }
+}
+
+async fn measure<T>(duration: &mut Duration, future: impl Future<Output = T>) -> T {
+    let start = Instant::now();
+    let result = future.await;
+    *duration += start.elapsed();
+    result
+}
+
 async fn process_request_with_content_source(
     path: &str,
     mut resolved_source: ContentSourceVc,
     mut asset_path: Cow<'_, str>,
     mut request: Request<hyper::Body>,
     console_ui: ConsoleUiVc,
+    warmup: Option<&Warmup>,
+    timing: &mut RequestTiming,
 ) -> Result<Response<hyper::Body>> {
     let mut data = ContentSourceData::default();
     loop {
         let content_source_result = get_from_source(resolved_source, &asset_path, Value::new(data));
-        handle_issues(
-            content_source_result,
-            path,
-            "get content from source",
-            console_ui,
-        )
-        .await?;
-        match &*content_source_result.strongly_consistent().await? {
-            GetFromSourceResult::Static(file) => {
-                if let FileContent::Content(content) = &**file {
+        let content_source_result = timing
+            .compile(async {
+                handle_issues(
+                    content_source_result,
+                    path,
+                    "get content from source",
+                    console_ui,
+                )
+                .await?;
+                content_source_result.strongly_consistent().await
+            })
+            .await?;
+        match &*content_source_result {
+            GetFromSourceResult::Static {
+                content,
+                version,
+                headers,
+            } => {
+                if let FileContent::Content(content) = &**content {
                     let content_type = content.content_type().map_or_else(
                         || {
                             let guess =
@@ -167,13 +383,47 @@ async fn process_request_with_content_source(
                         |m| m.to_string(),
                     );
 
+                    let cache_control = if is_versioned_url(&request, version) {
+                        // The URL changes with the content, so the content of a URL never
+                        // changes.
+                        "public, max-age=31536000, immutable"
+                    } else {
+                        // Browsers may cache the content, but have to check with the ETag
+                        // whether it's still the current version.
+                        "no-cache"
+                    };
+                    let etag = (!version.is_empty()).then(|| format!("\"{}\"", **version));
+                    if let Some(etag) = &etag {
+                        if matches_etag(&request, etag) {
+                            return Ok(Response::builder()
+                                .status(304)
+                                .header("ETag", etag)
+                                .header("Cache-Control", cache_control)
+                                .body(hyper::Body::empty())?);
+                        }
+                    }
+
                     let content = content.content();
+                    if let Some(warmup) = warmup {
+                        if content_type.starts_with("text/html") {
+                            if let Ok(html) = content.to_str() {
+                                warmup.discover_links(&html);
+                            }
+                        }
+                    }
                     let bytes = content.read();
-                    return Ok(Response::builder()
+                    let mut response = Response::builder()
                         .status(200)
                         .header("Content-Type", content_type)
                         .header("Content-Length", content.len().to_string())
-                        .body(hyper::Body::wrap_stream(bytes))?);
+                        .header("Cache-Control", cache_control);
+                    if let Some(etag) = etag {
+                        response = response.header("ETag", etag);
+                    }
+                    for [name, value] in headers.array_chunks() {
+                        response = response.header(name, value);
+                    }
+                    return Ok(response.body(hyper::Body::wrap_stream(bytes))?);
                 }
             }
             GetFromSourceResult::HttpProxy(proxy_result) => {
@@ -189,10 +439,16 @@ async fn process_request_with_content_source(
 
                 return Ok(response.body(hyper::Body::wrap_stream(proxy_result.body.read()))?);
             }
+            GetFromSourceResult::WebSocketProxy(_) => {
+                return Ok(Response::builder()
+                    .status(426)
+                    .header("Upgrade", "websocket")
+                    .body(hyper::Body::empty())?);
+            }
             GetFromSourceResult::NeedData { source, path, vary } => {
                 resolved_source = *source;
                 asset_path = Cow::Owned(path.to_string());
-                data = request_to_data(&mut request, vary).await?;
+                data = timing.io(request_to_data(&mut request, vary)).await?;
                 continue;
             }
             GetFromSourceResult::NotFound => {}
@@ -201,24 +457,159 @@ async fn process_request_with_content_source(
     }
 }
 
+/// Passes the WebSocket connection of an upgrade request through to the server
+/// at `target`. The handshake is forwarded as is, so the other server decides
+/// about the upgrade, and then all data is copied in both directions.
+async fn proxy_websocket(
+    mut request: Request<hyper::Body>,
+    target: &str,
+) -> Result<Response<hyper::Body>> {
+    let path_and_query = request
+        .uri()
+        .path_and_query()
+        .map_or("/", |path_and_query| path_and_query.as_str());
+    let mut proxy_request = Request::new(hyper::Body::empty());
+    *proxy_request.method_mut() = request.method().clone();
+    *proxy_request.uri_mut() = format!("http://{target}{path_and_query}").parse()?;
+    *proxy_request.headers_mut() = request.headers().clone();
+    proxy_request
+        .headers_mut()
+        .insert(hyper::header::HOST, target.parse()?);
+    let mut proxy_response = hyper::Client::new()
+        .request(proxy_request)
+        .await
+        .with_context(|| format!("connecting to WebSocket server at {target}"))?;
+
+    if proxy_response.status() != StatusCode::SWITCHING_PROTOCOLS {
+        // The other server refused the upgrade, the client gets its reason.
+        return Ok(proxy_response);
+    }
+
+    let mut response = Response::new(hyper::Body::empty());
+    *response.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+    *response.headers_mut() = proxy_response.headers().clone();
+    let upgrades = try_join(
+        hyper::upgrade::on(&mut request),
+        hyper::upgrade::on(&mut proxy_response),
+    );
+    tokio::spawn(async move {
+        match upgrades.await {
+            Ok((mut client, mut server)) => {
+                // Errors only mean that one side closed the connection.
+                let _ = copy_bidirectional(&mut client, &mut server).await;
+            }
+            Err(e) => println!("WebSocket upgrade failed: {e}"),
+        }
+    });
+    Ok(response)
+}
+
+/// Version ids shorter than this aren't considered to be hashes, which are
+/// unlikely to appear in a URL by accident.
+const MIN_VERSION_HASH_LENGTH: usize = 8;
+
+/// Whether the URL of the request contains the version of the content, e. g.
+/// as a query parameter. Such URLs refer to a single version of the content.
+fn is_versioned_url(request: &Request<hyper::Body>, version: &str) -> bool {
+    version.len() >= MIN_VERSION_HASH_LENGTH
+        && request
+            .uri()
+            .path_and_query()
+            .map_or(false, |url| url.as_str().contains(version))
+}
+
+/// Whether the `If-None-Match` header of the request matches the ETag, so the
+/// client already has the current version of the content.
+fn matches_etag(request: &Request<hyper::Body>, etag: &str) -> bool {
+    request
+        .headers()
+        .get_all(hyper::header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// Whether the decoded path of a request could leave the root of the content
+/// sources, e. g. `/%2e%2e/secret`. Browsers resolve `..` segments in URLs
+/// before sending them, so only malicious requests contain them.
+fn leaves_root(asset_path: &str) -> bool {
+    asset_path.split(['/', '\\']).any(|segment| segment == "..")
+}
+
 impl DevServer {
     pub fn listen(
         turbo_tasks: Arc<dyn TurboTasksApi>,
         source_provider: impl SourceProvider + Clone + Send + Sync,
-        addr: SocketAddr,
+        addr: ServerAddr,
         console_ui: Arc<ConsoleUi>,
+        warmup: Option<WarmupOptions>,
+        security: SecurityOptions,
     ) -> Result<Self, anyhow::Error> {
-        let make_svc = make_service_fn(move |_| {
+        let warmup = warmup.map(Warmup::new);
+        let security = Arc::new(security);
+        let warmup_run = warmup.clone().map(|warmup| {
+            let turbo_tasks = turbo_tasks.clone();
+            let source_provider = source_provider.clone();
+            move || async move {
+                let persist = async {
+                    // The profile only speeds up the next startup, so serving
+                    // continues without it.
+                    if let Err(err) = warmup.clone().persist_profile().await {
+                        println!("[warmup] {err:#}");
+                    }
+                };
+                futures::join!(warmup.clone().run(turbo_tasks, source_provider), persist);
+            }
+        });
+        let make_service = move || {
             let tt = turbo_tasks.clone();
             let source_provider = source_provider.clone();
             let console_ui = console_ui.clone();
+            let warmup = warmup.clone();
+            let security = security.clone();
             async move {
                 let handler = move |request: Request<hyper::Body>| {
                     let console_ui = console_ui.clone();
                     let start = Instant::now();
                     let tt = tt.clone();
                     let source_provider = source_provider.clone();
+                    let warmup = warmup.clone();
+                    let security = security.clone();
+                    let cors_origin = security.cors_origin(&request);
+                    let cors = cors_origin.is_some();
                     let future = async move {
+                        if let Err(reason) = security.check(&request) {
+                            println!("[403] {} ({reason})", request.uri().path());
+                            return Ok(Response::builder()
+                                .status(403)
+                                .body(hyper::Body::empty())?);
+                        }
+                        if cors {
+                            if let Some(response) = preflight_response(&request) {
+                                return Ok(response);
+                            }
+                        }
+
+                        if request.method() == Method::POST
+                            && request.uri().path() == EXECUTED_MODULES_PATH
+                        {
+                            let body = hyper::body::to_bytes(request.into_body()).await?;
+                            let status = match serde_json::from_slice(&body) {
+                                Ok(executed) => {
+                                    if let Some(warmup) = &warmup {
+                                        warmup.modules_executed(executed);
+                                    }
+                                    StatusCode::NO_CONTENT
+                                }
+                                Err(_) => StatusCode::BAD_REQUEST,
+                            };
+                            return Ok(Response::builder()
+                                .status(status)
+                                .body(hyper::Body::empty())?);
+                        }
+
                         if hyper_tungstenite::is_upgrade_request(&request) {
                             let uri = request.uri();
                             let path = uri.path();
@@ -226,11 +617,39 @@ impl DevServer {
                             if path == "/turbopack-hmr" {
                                 let (response, websocket) =
                                     hyper_tungstenite::upgrade(request, None)?;
-                                let update_server = UpdateServer::new(source_provider);
+                                let update_server = UpdateServer::new(
+                                    source_provider,
+                                    console_ui.events().cloned(),
+                                );
                                 update_server.run(&*tt, websocket);
                                 return Ok(response);
                             }
 
+                            // Other upgrades are handled by the content source of the route.
+                            let target = {
+                                let path = path[1..].to_string();
+                                let source_provider = source_provider.clone();
+                                run_once(tt, async move {
+                                    let source = source_provider.get_source();
+                                    let result = get_from_source(
+                                        source,
+                                        &path,
+                                        Value::new(ContentSourceData::default()),
+                                    );
+                                    Ok(match &*result.strongly_consistent().await? {
+                                        GetFromSourceResult::WebSocketProxy(target) => {
+                                            Some(target.clone())
+                                        }
+                                        _ => None,
+                                    })
+                                })
+                                .await?
+                            };
+                            if let Some(target) = target {
+                                return proxy_websocket(request, &target).await;
+                            }
+
+                            let path = request.uri().path();
                             println!("[404] {} (WebSocket)", path);
                             if path == "/_next/webpack-hmr" {
                                 // Special-case requests to webpack-hmr as these are made by Next.js
@@ -248,43 +667,83 @@ impl DevServer {
                                 .body(hyper::Body::empty())?);
                         }
 
-                        run_once(tt, async move {
+                        // The browser is waiting for the response, so this takes precedence
+                        // over background work.
+                        let future = run_once(tt, async move {
+                            let events = console_ui.events().cloned();
+                            let log_requests = console_ui.log_requests();
                             let console_ui = (*console_ui).clone().cell();
                             let uri = request.uri();
                             let path = uri.path();
                             // Remove leading slash.
                             let path = &path[1..].to_string();
+                            if let Some(warmup) = &warmup {
+                                warmup.request_started(path);
+                            }
                             let asset_path = urlencoding::decode(path)?;
+                            if leaves_root(&asset_path) {
+                                println!("[400] /{path} (path leaves the root)");
+                                return Ok(Response::builder()
+                                    .status(400)
+                                    .body(hyper::Body::empty())?);
+                            }
+                            let method = request.method().to_string();
+                            let mut timing = RequestTiming::default();
                             let source = source_provider.get_source();
-                            handle_issues(source, path, "get source", console_ui).await?;
-                            let resolved_source = source.resolve_strongly_consistent().await?;
+                            let resolved_source = timing
+                                .compile(async {
+                                    handle_issues(source, path, "get source", console_ui).await?;
+                                    source.resolve_strongly_consistent().await
+                                })
+                                .await?;
                             let response = process_request_with_content_source(
                                 path,
                                 resolved_source,
                                 asset_path,
                                 request,
                                 console_ui,
+                                warmup.as_deref(),
+                                &mut timing,
                             )
                             .await?;
                             let status = response.status().as_u16();
                             let success = response.status().is_success();
                             let elapsed = start.elapsed();
+                            if let Some(events) = events {
+                                events.emit(&Event::RequestServed {
+                                    method: &method,
+                                    path,
+                                    status,
+                                    duration_ms: elapsed.as_millis() as u64,
+                                    compile_ms: timing.compile.as_millis() as u64,
+                                    io_ms: timing.io.as_millis() as u64,
+                                });
+                            }
                             if !success
+                                || log_requests
                                 || (cfg!(feature = "log_request_stats")
                                     && elapsed > Duration::from_secs(1))
                             {
                                 println!(
-                                    "[{status}] /{path} ({duration})",
-                                    duration = FormatDuration(elapsed)
+                                    "[{status}] {method} /{path} ({duration}, compile {compile}, \
+                                     io {io})",
+                                    duration = FormatDuration(elapsed),
+                                    compile = FormatDuration(timing.compile),
+                                    io = FormatDuration(timing.io),
                                 );
                             }
                             Ok(response)
-                        })
-                        .await
+                        });
+                        with_priority(TaskPriority::Interactive, future).await
                     };
                     async move {
                         match future.await {
-                            Ok(r) => Ok::<_, hyper::http::Error>(r),
+                            Ok(mut r) => {
+                                if let Some(origin) = &cors_origin {
+                                    add_cors_headers(&mut r, origin);
+                                }
+                                Ok::<_, hyper::http::Error>(r)
+                            }
                             Err(e) => {
                                 println!(
                                     "[500] error: {:?} ({})",
@@ -300,21 +759,111 @@ impl DevServer {
                 };
                 anyhow::Ok(service_fn(handler))
             }
-        });
-        let server = Server::try_bind(&addr)
-            .context("Not able to start server")?
-            .serve(make_svc);
+        };
+        let shutdown = ShutdownHandle::default();
+        let shutdown_signal = {
+            let notify = shutdown.notify.clone();
+            async move { notify.notified().await }
+        };
+        let (addr, server): (ServerAddr, Pin<Box<dyn Future<Output = Result<()>> + Send>>) =
+            match addr {
+                ServerAddr::Tcp(addr) => {
+                    let listener = bind_tcp(addr).context("Not able to start server")?;
+                    let server = Server::from_tcp(listener)?
+                        .serve(make_service_fn(move |_: &AddrStream| make_service()));
+                    let addr = server.local_addr();
+                    let server = server.with_graceful_shutdown(shutdown_signal);
+                    (
+                        ServerAddr::Tcp(addr),
+                        Box::pin(async move { Ok(server.await?) }),
+                    )
+                }
+                #[cfg(unix)]
+                ServerAddr::Unix(path) => {
+                    let listener = bind_unix(&path).context("Not able to start server")?;
+                    let incoming = accept::poll_fn(move |cx| {
+                        listener
+                            .poll_accept(cx)
+                            .map(|result| Some(result.map(|(stream, _)| stream)))
+                    });
+                    let server = Server::builder(incoming)
+                        .serve(make_service_fn(move |_: &UnixStream| make_service()))
+                        .with_graceful_shutdown(shutdown_signal);
+                    let socket_path = path.clone();
+                    (
+                        ServerAddr::Unix(path),
+                        Box::pin(async move {
+                            let result = server.await;
+                            // Nobody is listening on the socket anymore.
+                            let _ = std::fs::remove_file(socket_path);
+                            Ok(result?)
+                        }),
+                    )
+                }
+            };
+        let warmup_handle = warmup_run.map(|run| tokio::spawn(run()));
 
         Ok(Self {
-            addr: server.local_addr(),
+            addr,
+            shutdown,
             future: Box::pin(async move {
-                server.await?;
-                Ok(())
+                let result = server.await;
+                if let Some(warmup_handle) = warmup_handle {
+                    warmup_handle.abort();
+                }
+                result
             }),
         })
     }
 }
 
+/// Binds a TCP listener like [std::net::TcpListener::bind], except that the
+/// unspecified IPv6 address accepts IPv4 connections, too. Whether it does by
+/// default depends on the OS.
+fn bind_tcp(addr: SocketAddr) -> std::io::Result<std::net::TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    if addr.is_ipv6() && addr.ip().is_unspecified() {
+        socket.set_only_v6(false)?;
+    }
+    // Allows restarting the server right away, while connections of the
+    // previous process are still in the TIME_WAIT state.
+    #[cfg(not(windows))]
+    socket.set_reuse_address(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(1024)?;
+    Ok(socket.into())
+}
+
+/// Binds a unix domain socket at `path`. A socket file left behind by a
+/// server which didn't shut down cleanly is replaced, but one which still
+/// accepts connections isn't.
+#[cfg(unix)]
+fn bind_unix(path: &Path) -> std::io::Result<UnixListener> {
+    use std::os::unix::fs::FileTypeExt;
+
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} exists and is no socket", path.display()),
+            ));
+        }
+        match std::os::unix::net::UnixStream::connect(path) {
+            Ok(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AddrInUse,
+                    format!("{} is in use by another server", path.display()),
+                ))
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::ConnectionRefused => {
+                std::fs::remove_file(path)?;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    UnixListener::bind(path)
+}
+
 static CACHE_BUSTER: AtomicU64 = AtomicU64::new(0);
 
 async fn request_to_data(
@@ -427,3 +976,113 @@ pub fn register() {
     turbopack_ecmascript::register();
     include!(concat!(env!("OUT_DIR"), "/register.rs"));
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{future::Future, net::SocketAddr};
+
+    use anyhow::Result;
+    use hyper::{
+        header::{CONNECTION, UPGRADE},
+        service::{make_service_fn, service_fn},
+        Body, Request, Response, Server, StatusCode,
+    };
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+
+    use super::{leaves_root, proxy_websocket};
+
+    /// Starts a server on a free port, which handles requests with `handler`.
+    fn serve<F, Fut>(handler: F) -> SocketAddr
+    where
+        F: Fn(Request<Body>) -> Fut + Clone + Send + Sync + 'static,
+        Fut: Future<Output = Result<Response<Body>>> + Send + 'static,
+    {
+        let make_service = make_service_fn(move |_| {
+            let handler = handler.clone();
+            async move { anyhow::Ok(service_fn(handler)) }
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        addr
+    }
+
+    /// Upgrades the connection and echoes the first 4 bytes.
+    async fn echo(mut request: Request<Body>) -> Result<Response<Body>> {
+        tokio::spawn(async move {
+            if let Ok(mut upgraded) = hyper::upgrade::on(&mut request).await {
+                let mut buf = [0; 4];
+                if upgraded.read_exact(&mut buf).await.is_ok() {
+                    let _ = upgraded.write_all(&buf).await;
+                }
+            }
+        });
+        Ok(Response::builder()
+            .status(StatusCode::SWITCHING_PROTOCOLS)
+            .header(UPGRADE, "websocket")
+            .header(CONNECTION, "Upgrade")
+            .body(Body::empty())?)
+    }
+
+    async fn refuse(_request: Request<Body>) -> Result<Response<Body>> {
+        Ok(Response::builder()
+            .status(StatusCode::FORBIDDEN)
+            .body(Body::empty())?)
+    }
+
+    fn serve_proxy(target: SocketAddr) -> SocketAddr {
+        serve(move |request| async move { proxy_websocket(request, &target.to_string()).await })
+    }
+
+    /// Sends an upgrade request and returns the connection with the head of
+    /// the response.
+    async fn handshake(addr: SocketAddr) -> Result<(TcpStream, String)> {
+        let mut stream = TcpStream::connect(addr).await?;
+        stream
+            .write_all(
+                b"GET /socket HTTP/1.1\r\nHost: localhost\r\nConnection: Upgrade\r\nUpgrade: \
+                  websocket\r\n\r\n",
+            )
+            .await?;
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            head.push(stream.read_u8().await?);
+        }
+        Ok((stream, String::from_utf8(head)?))
+    }
+
+    #[tokio::test]
+    async fn passes_upgrades_through() -> Result<()> {
+        let proxy = serve_proxy(serve(echo));
+        let (mut stream, head) = handshake(proxy).await?;
+        assert!(head.starts_with("HTTP/1.1 101"), "{head}");
+        assert!(head.to_lowercase().contains("upgrade: websocket"), "{head}");
+
+        stream.write_all(b"ping").await?;
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).await?;
+        assert_eq!(&buf, b"ping");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn passes_refused_upgrades_through() -> Result<()> {
+        let proxy = serve_proxy(serve(refuse));
+        let (_, head) = handshake(proxy).await?;
+        assert!(head.starts_with("HTTP/1.1 403"), "{head}");
+        Ok(())
+    }
+
+    #[test]
+    fn leaves_root_of_decoded_paths() {
+        assert!(leaves_root("../secret"));
+        assert!(leaves_root("a/../../secret"));
+        assert!(leaves_root("a\\..\\secret"));
+        assert!(!leaves_root("a/..b/c"));
+        assert!(!leaves_root("a/.../c"));
+        assert!(!leaves_root("index.html"));
+    }
+}
@@ -0,0 +1,320 @@
+use std::net::IpAddr;
+
+use hyper::{
+    header::{
+        HeaderValue, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+        ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD,
+        HOST, ORIGIN, VARY,
+    },
+    Method, Request, Response, StatusCode,
+};
+
+/// Protects the dev server from other websites opened in the browser of the
+/// developer.
+#[derive(Clone, Debug, Default)]
+pub struct SecurityOptions {
+    /// Host names the server may be requested with, in addition to
+    /// `localhost` and IP addresses. A leading `.` allows all subdomains,
+    /// e. g. `.example.com`.
+    pub allowed_hosts: Vec<String>,
+    /// Adds CORS headers to responses for requests from other local origins,
+    /// e. g. a second dev server, so they can load assets from this one.
+    pub cors: bool,
+    /// Disables the checks of the `Host` and `Origin` headers, e. g. when the
+    /// server is reached through a proxy which rewrites them.
+    pub disable_host_check: bool,
+}
+
+impl SecurityOptions {
+    /// Checks where a request comes from. Returns why it's rejected.
+    ///
+    /// With DNS rebinding, a website makes its domain resolve to the address
+    /// of the server, so the browser considers the server the same origin.
+    /// This is prevented by only allowing hosts which the website can't
+    /// control. Requests from other origins are rejected, too, as
+    /// WebSockets, e. g. the HMR connection, aren't protected by CORS.
+    pub(crate) fn check(&self, request: &Request<hyper::Body>) -> Result<(), String> {
+        if self.disable_host_check {
+            return Ok(());
+        }
+        let host = request
+            .headers()
+            .get(HOST)
+            .and_then(|host| host.to_str().ok());
+        if let Some(host) = host {
+            let name = host_name(host);
+            if name.parse::<IpAddr>().is_err() && !self.is_trusted(name) {
+                return Err(format!("host {host} is not allowed"));
+            }
+        }
+        if let Some(origin) = request.headers().get(ORIGIN) {
+            let origin_host = origin.to_str().ok().and_then(origin_host);
+            let allowed = origin_host.map_or(false, |origin_host| {
+                host.map_or(false, |host| origin_host.eq_ignore_ascii_case(host))
+                    || self.is_trusted(host_name(origin_host))
+            });
+            if !allowed {
+                return Err(format!("origin {origin:?} is not allowed"));
+            }
+        }
+        Ok(())
+    }
+
+    /// The origin to allow in the CORS headers of the response, when CORS is
+    /// enabled and the request comes from a local origin.
+    pub(crate) fn cors_origin(&self, request: &Request<hyper::Body>) -> Option<HeaderValue> {
+        if !self.cors {
+            return None;
+        }
+        let origin = request.headers().get(ORIGIN)?;
+        let name = host_name(origin_host(origin.to_str().ok()?)?);
+        (self.disable_host_check || self.is_trusted(name)).then(|| origin.clone())
+    }
+
+    fn is_trusted(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        let name = name.trim_end_matches('.');
+        if name == "localhost" || name.ends_with(".localhost") {
+            return true;
+        }
+        if let Ok(ip) = name.parse::<IpAddr>() {
+            return ip.is_loopback();
+        }
+        self.allowed_hosts.iter().any(|allowed| {
+            let allowed = allowed.to_ascii_lowercase();
+            match allowed.strip_prefix('.') {
+                Some(domain) => name == domain || name.ends_with(&allowed),
+                None => name == allowed,
+            }
+        })
+    }
+}
+
+/// Answers a CORS preflight request, which asks whether a request from
+/// another origin is allowed, without passing it to the content source. The
+/// origin is allowed by [add_cors_headers], like for all other responses.
+pub(crate) fn preflight_response(request: &Request<hyper::Body>) -> Option<Response<hyper::Body>> {
+    if request.method() != Method::OPTIONS {
+        return None;
+    }
+    let method = request.headers().get(ACCESS_CONTROL_REQUEST_METHOD)?;
+    let mut response = Response::new(hyper::Body::empty());
+    *response.status_mut() = StatusCode::NO_CONTENT;
+    let headers = response.headers_mut();
+    headers.insert(ACCESS_CONTROL_ALLOW_METHODS, method.clone());
+    if let Some(request_headers) = request.headers().get(ACCESS_CONTROL_REQUEST_HEADERS) {
+        headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, request_headers.clone());
+    }
+    Some(response)
+}
+
+pub(crate) fn add_cors_headers(response: &mut Response<hyper::Body>, origin: &HeaderValue) {
+    let headers = response.headers_mut();
+    headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+    // The response differs between origins, so it must not be cached for all
+    // of them.
+    headers.append(VARY, HeaderValue::from_static("Origin"));
+}
+
+/// The host name of a `Host` header, without the port and the brackets of
+/// IPv6 addresses.
+fn host_name(host: &str) -> &str {
+    if let Some(address) = host.strip_prefix('[') {
+        return address.split(']').next().unwrap_or(address);
+    }
+    host.split(':').next().unwrap_or(host)
+}
+
+/// The host of an `Origin` header, including the port, e. g. `localhost:3000`
+/// for `http://localhost:3000`. Opaque origins (`null`) have none.
+fn origin_host(origin: &str) -> Option<&str> {
+    let (_, host) = origin.split_once("://")?;
+    Some(host.split('/').next().unwrap_or(host))
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::{
+        header::{HOST, ORIGIN},
+        Request,
+    };
+
+    use super::{host_name, origin_host, SecurityOptions};
+
+    fn request(host: Option<&str>, origin: Option<&str>) -> Request<hyper::Body> {
+        let mut builder = Request::builder().uri("/");
+        if let Some(host) = host {
+            builder = builder.header(HOST, host);
+        }
+        if let Some(origin) = origin {
+            builder = builder.header(ORIGIN, origin);
+        }
+        builder.body(hyper::Body::empty()).unwrap()
+    }
+
+    fn options(allowed_hosts: &[&str]) -> SecurityOptions {
+        SecurityOptions {
+            allowed_hosts: allowed_hosts.iter().map(|host| host.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn host_names() {
+        assert_eq!(host_name("localhost"), "localhost");
+        assert_eq!(host_name("localhost:3000"), "localhost");
+        assert_eq!(host_name("127.0.0.1:3000"), "127.0.0.1");
+        assert_eq!(host_name("[::1]"), "::1");
+        assert_eq!(host_name("[::1]:3000"), "::1");
+    }
+
+    #[test]
+    fn origin_hosts() {
+        assert_eq!(origin_host("http://localhost:3000"), Some("localhost:3000"));
+        assert_eq!(origin_host("https://example.com"), Some("example.com"));
+        assert_eq!(origin_host("http://[::1]:3000"), Some("[::1]:3000"));
+        assert_eq!(origin_host("null"), None);
+    }
+
+    #[test]
+    fn trusted_hosts() {
+        let options = options(&["example.com", ".dev.test"]);
+        assert!(options.is_trusted("localhost"));
+        assert!(options.is_trusted("LocalHost"));
+        assert!(options.is_trusted("localhost."));
+        assert!(options.is_trusted("app.localhost"));
+        assert!(options.is_trusted("127.0.0.1"));
+        assert!(options.is_trusted("::1"));
+        assert!(options.is_trusted("EXAMPLE.COM"));
+        assert!(options.is_trusted("dev.test"));
+        assert!(options.is_trusted("app.dev.test"));
+
+        assert!(!options.is_trusted("192.168.0.1"));
+        assert!(!options.is_trusted("localhost.evil.com"));
+        assert!(!options.is_trusted("evillocalhost"));
+        assert!(!options.is_trusted("sub.example.com"));
+        assert!(!options.is_trusted("evildev.test"));
+    }
+
+    #[test]
+    fn allows_local_requests() {
+        let options = SecurityOptions::default();
+        for host in [
+            "localhost:3000",
+            "LOCALHOST:3000",
+            "localhost.:3000",
+            "127.0.0.1:3000",
+            "[::1]:3000",
+            // Other addresses of the machine, e. g. to test on a phone.
+            "192.168.0.1:3000",
+            "[fe80::1]:3000",
+        ] {
+            assert_eq!(options.check(&request(Some(host), None)), Ok(()), "{host}");
+        }
+        assert_eq!(options.check(&request(None, None)), Ok(()));
+        assert_eq!(
+            options.check(&request(
+                Some("localhost:3000"),
+                Some("http://localhost:3000")
+            )),
+            Ok(())
+        );
+        assert_eq!(
+            options.check(&request(Some("[::1]:3000"), Some("http://[::1]:3000"))),
+            Ok(())
+        );
+        assert_eq!(
+            options.check(&request(
+                Some("192.168.0.1:3000"),
+                Some("http://192.168.0.1:3000")
+            )),
+            Ok(())
+        );
+        // Another local dev server.
+        assert_eq!(
+            options.check(&request(
+                Some("localhost:3000"),
+                Some("http://localhost:4000")
+            )),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn rejects_dns_rebinding() {
+        let options = SecurityOptions::default();
+        // The website made its own domain resolve to the server.
+        assert!(options
+            .check(&request(
+                Some("evil.com:3000"),
+                Some("http://evil.com:3000")
+            ))
+            .is_err());
+        assert!(options
+            .check(&request(Some("localhost.evil.com:3000"), None))
+            .is_err());
+        assert!(options
+            .check(&request(Some("127.0.0.1.nip.io:3000"), None))
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_other_origins() {
+        let options = SecurityOptions::default();
+        for origin in ["https://evil.com", "http://192.168.0.2:3000", "null"] {
+            assert!(
+                options
+                    .check(&request(Some("localhost:3000"), Some(origin)))
+                    .is_err(),
+                "{origin}"
+            );
+        }
+    }
+
+    #[test]
+    fn allows_configured_hosts() {
+        let options = options(&[".dev.test"]);
+        assert_eq!(
+            options.check(&request(Some("app.dev.test"), Some("https://app.dev.test"))),
+            Ok(())
+        );
+        assert!(options
+            .check(&request(Some("app.dev.test.evil.com"), None))
+            .is_err());
+    }
+
+    #[test]
+    fn disabled_host_check() {
+        let options = SecurityOptions {
+            disable_host_check: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            options.check(&request(Some("evil.com"), Some("null"))),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn cors_origin() {
+        let options = SecurityOptions {
+            cors: true,
+            ..Default::default()
+        };
+        let local = request(Some("localhost:3000"), Some("http://localhost:4000"));
+        assert_eq!(
+            options
+                .cors_origin(&local)
+                .as_ref()
+                .map(|origin| origin.to_str().unwrap()),
+            Some("http://localhost:4000")
+        );
+        assert!(options
+            .cors_origin(&request(Some("localhost:3000"), Some("https://evil.com")))
+            .is_none());
+        assert!(options
+            .cors_origin(&request(Some("localhost:3000"), Some("null")))
+            .is_none());
+        assert!(SecurityOptions::default().cors_origin(&local).is_none());
+    }
+}
@@ -2,10 +2,12 @@ pub mod asset_graph;
 pub mod combined;
 pub mod conditional;
 pub mod lazy_instatiated;
+pub mod prefix;
 pub mod query;
 pub mod router;
 pub mod specificity;
 pub mod static_assets;
+pub mod websocket_proxy;
 
 use std::{
     collections::{BTreeMap, HashSet},
@@ -72,6 +74,16 @@ pub enum ContentSourceContent {
     NotFound,
     Static(VersionedContentVc),
     HttpProxy(ProxyResultVc),
+    /// Passes WebSocket connections through to the server at the address
+    /// (`host:port`). The request path is kept. Only upgrade requests can be
+    /// served with this.
+    WebSocketProxy(String),
+    /// The `content` with additional response headers, arranged as contiguous
+    /// (name, value) pairs. The content must not need data.
+    WithHeaders {
+        content: ContentSourceContentVc,
+        headers: Vec<String>,
+    },
     NeedData {
         source: ContentSourceVc,
         path: String,
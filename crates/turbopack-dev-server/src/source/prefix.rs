@@ -0,0 +1,93 @@
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, Value};
+use turbopack_core::introspect::{Introspectable, IntrospectableChildrenVc, IntrospectableVc};
+
+use super::{ContentSource, ContentSourceData, ContentSourceResultVc, ContentSourceVc};
+
+/// Serves a [ContentSource] below a subpath. The prefix is stripped from the
+/// path before it's passed to the inner source, and all paths outside of the
+/// prefix are not found. The subpath itself, e. g. `foo` for the prefix
+/// `foo/`, is served by the root of the inner source.
+///
+/// Unlike the [super::router::RouterContentSource], it has no fallback, so it
+/// can be combined with other sources, e. g. to mount a source at a base
+/// path in a [super::combined::CombinedContentSource].
+#[turbo_tasks::value(shared)]
+pub struct PrefixedContentSource {
+    pub prefix: String,
+    pub source: ContentSourceVc,
+}
+
+#[turbo_tasks::value_impl]
+impl PrefixedContentSourceVc {
+    #[turbo_tasks::function]
+    pub fn new(prefix: String, source: ContentSourceVc) -> PrefixedContentSourceVc {
+        let mut prefix = prefix;
+        if !prefix.is_empty() && !prefix.ends_with('/') {
+            prefix.push('/');
+        }
+        PrefixedContentSource { prefix, source }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for PrefixedContentSource {
+    #[turbo_tasks::function]
+    fn get(&self, path: &str, data: Value<ContentSourceData>) -> ContentSourceResultVc {
+        match strip_prefix(&self.prefix, path) {
+            Some(path) => self.source.get(path, data),
+            None => ContentSourceResultVc::not_found(),
+        }
+    }
+}
+
+fn strip_prefix<'a>(prefix: &str, path: &'a str) -> Option<&'a str> {
+    if prefix.strip_suffix('/') == Some(path) {
+        return Some("");
+    }
+    path.strip_prefix(prefix)
+}
+
+#[turbo_tasks::function]
+fn introspectable_type() -> StringVc {
+    StringVc::cell("prefixed content source".to_string())
+}
+
+#[turbo_tasks::value_impl]
+impl Introspectable for PrefixedContentSource {
+    #[turbo_tasks::function]
+    fn ty(&self) -> StringVc {
+        introspectable_type()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(self.prefix.clone())
+    }
+
+    #[turbo_tasks::function]
+    async fn children(&self) -> Result<IntrospectableChildrenVc> {
+        Ok(IntrospectableChildrenVc::cell(
+            IntrospectableVc::resolve_from(self.source)
+                .await?
+                .map(|source| (StringVc::cell(self.prefix.clone()), source))
+                .into_iter()
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::strip_prefix;
+
+    #[test]
+    fn strips_prefix() {
+        assert_eq!(strip_prefix("foo/", "foo/bar"), Some("bar"));
+        assert_eq!(strip_prefix("foo/", "foo/"), Some(""));
+        assert_eq!(strip_prefix("foo/", "foo"), Some(""));
+        assert_eq!(strip_prefix("foo/", "foobar"), None);
+        assert_eq!(strip_prefix("foo/", "bar/foo/"), None);
+        assert_eq!(strip_prefix("", "foo"), Some("foo"));
+    }
+}
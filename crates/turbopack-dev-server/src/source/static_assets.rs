@@ -42,16 +42,19 @@ impl ContentSource for StaticAssetsContentSource {
     ) -> Result<ContentSourceResultVc> {
         if !path.is_empty() {
             if let Some(path) = path.strip_prefix(&self.prefix) {
-                let path = self.dir.join(path);
-                let ty = path.get_type().await?;
-                if matches!(
-                    &*ty,
-                    FileSystemEntryType::File | FileSystemEntryType::Symlink
-                ) {
-                    let content = SourceAssetVc::new(path).as_asset().content();
-                    return Ok(ContentSourceResultVc::exact(
-                        ContentSourceContent::Static(content.into()).cell(),
-                    ));
+                // Only files inside of the directory are served, symlinks to
+                // other places aren't followed.
+                if let Some(path) = *self.dir.try_join_inside_real(path).await? {
+                    let ty = path.get_type().await?;
+                    if matches!(
+                        &*ty,
+                        FileSystemEntryType::File | FileSystemEntryType::Symlink
+                    ) {
+                        let content = SourceAssetVc::new(path).as_asset().content();
+                        return Ok(ContentSourceResultVc::exact(
+                            ContentSourceContent::Static(content.into()).cell(),
+                        ));
+                    }
                 }
             }
         }
@@ -0,0 +1,53 @@
+use std::collections::HashSet;
+
+use turbo_tasks::{primitives::StringVc, Value};
+use turbopack_core::introspect::{Introspectable, IntrospectableChildrenVc, IntrospectableVc};
+
+use super::{
+    ContentSource, ContentSourceContent, ContentSourceData, ContentSourceResultVc, ContentSourceVc,
+};
+
+/// Passes all WebSocket connections through to another server, e. g. a
+/// backend API in development. Mount it with a
+/// [super::router::RouterContentSource] to handle the upgrades of a route.
+#[turbo_tasks::value(shared)]
+pub struct WebSocketProxyContentSource {
+    /// The address of the server, as `host:port`.
+    pub target: String,
+}
+
+#[turbo_tasks::value_impl]
+impl WebSocketProxyContentSourceVc {
+    #[turbo_tasks::function]
+    pub fn new(target: String) -> WebSocketProxyContentSourceVc {
+        WebSocketProxyContentSource { target }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for WebSocketProxyContentSource {
+    #[turbo_tasks::function]
+    fn get(&self, _path: &str, _data: Value<ContentSourceData>) -> ContentSourceResultVc {
+        ContentSourceResultVc::exact(
+            ContentSourceContent::WebSocketProxy(self.target.clone()).cell(),
+        )
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Introspectable for WebSocketProxyContentSource {
+    #[turbo_tasks::function]
+    fn ty(&self) -> StringVc {
+        StringVc::cell("websocket proxy content source".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(self.target.clone())
+    }
+
+    #[turbo_tasks::function]
+    fn children(&self) -> IntrospectableChildrenVc {
+        IntrospectableChildrenVc::cell(HashSet::new())
+    }
+}
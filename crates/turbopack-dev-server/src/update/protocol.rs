@@ -149,6 +149,8 @@ impl<'a> From<&'a PlainIssue> for Issue<'a> {
                     show_all: true,
                     log_detail: true,
                     log_level: IssueSeverity::Info,
+                    json: false,
+                    log_requests: false,
                 },
             ),
         }
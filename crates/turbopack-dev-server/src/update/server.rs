@@ -11,6 +11,7 @@ use pin_project_lite::pin_project;
 use tokio::select;
 use tokio_stream::StreamMap;
 use turbo_tasks::{TransientInstance, TurboTasksApi, Value};
+use turbopack_cli_utils::events::{Event, EventSink};
 use turbopack_core::version::Update;
 
 use super::{
@@ -22,12 +23,17 @@ use crate::{update::stream::UpdateStreamItem, SourceProvider};
 /// A server that listens for updates and sends them to connected clients.
 pub(crate) struct UpdateServer<P: SourceProvider> {
     source_provider: P,
+    events: Option<EventSink>,
 }
 
 impl<P: SourceProvider + Clone + Send + Sync> UpdateServer<P> {
     /// Create a new update server with the given websocket and content source.
-    pub fn new(source_provider: P) -> Self {
-        Self { source_provider }
+    /// Updates sent to clients are also emitted to `events`.
+    pub fn new(source_provider: P, events: Option<EventSink>) -> Self {
+        Self {
+            source_provider,
+            events,
+        }
     }
 
     /// Run the update server loop.
@@ -68,6 +74,15 @@ impl<P: SourceProvider + Clone + Send + Sync> UpdateServer<P> {
                     }
                 }
                 Some((resource, update)) = streams.next() => {
+                    if let Some(events) = &self.events {
+                        match &*update.update {
+                            Update::Partial(_) | Update::Total(_) => events.emit(&Event::HmrUpdate {
+                                path: &resource.path,
+                                restart: matches!(&*update.update, Update::Total(_)),
+                            }),
+                            Update::None => {}
+                        }
+                    }
                     Self::send_update(&mut client, resource, &update).await?;
                 }
                 else => break
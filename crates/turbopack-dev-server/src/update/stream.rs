@@ -57,12 +57,21 @@ async fn get_content_wrapper(
 async fn resolve_static_content(
     content_source_result: ContentSourceResultVc,
 ) -> Result<Option<VersionedContentVc>> {
-    Ok(match *content_source_result.await?.content.await? {
+    let mut content = content_source_result.await?.content.await?;
+    // Headers don't change the content, so updates are sent without them.
+    while let ContentSourceContent::WithHeaders { content: inner, .. } = &*content {
+        content = inner.await?;
+    }
+    Ok(match *content {
         ContentSourceContent::NotFound => None,
         ContentSourceContent::HttpProxy(_) => {
             panic!("HTTP proxying is not supported in UpdateStream")
         }
+        ContentSourceContent::WebSocketProxy(_) => {
+            bail!("WebSocket proxying is not supported in UpdateStream")
+        }
         ContentSourceContent::Static(content) => Some(content),
+        ContentSourceContent::WithHeaders { .. } => unreachable!(),
         ContentSourceContent::NeedData { .. } => {
             bail!("this might only happen temporary as get_content_wrapper resolves the data")
         }
@@ -0,0 +1,464 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::{sync::Notify, task::spawn_blocking, time::sleep};
+use turbo_tasks::{run_once, with_priority, TaskPriority, TurboTasksApi, Value};
+
+use crate::{
+    get_from_source, resource_to_data,
+    source::{ContentSourceData, ContentSourceVc},
+    update::protocol::ResourceIdentifier,
+    GetFromSourceResult, SourceProvider,
+};
+
+/// Configures the speculative compilation of routes.
+#[derive(Clone, Debug)]
+pub struct WarmupOptions {
+    /// The maximum number of routes which are compiled speculatively in a
+    /// session. This bounds the memory used for routes which might never be
+    /// requested. Requested routes don't count against it.
+    pub max_routes: usize,
+    /// The time without any request after which the server is considered
+    /// idle.
+    pub idle_delay: Duration,
+    /// A file in which the modules executed by the runtime are recorded
+    /// across restarts. On startup, the pages which executed modules in
+    /// earlier sessions are compiled first, the ones executing the modules
+    /// executed in the most sessions first. Other pages are only compiled
+    /// when they are linked or requested.
+    pub profile: Option<PathBuf>,
+}
+
+/// The path the runtime reports the modules it executed to.
+pub const EXECUTED_MODULES_PATH: &str = "/__turbopack_executed__";
+
+/// The time changes to the profile are collected before it's written.
+const PROFILE_WRITE_DELAY: Duration = Duration::from_secs(1);
+
+/// The modules the runtime executed on a page, as reported to
+/// [EXECUTED_MODULES_PATH].
+#[derive(Deserialize)]
+pub struct ExecutedModules {
+    /// The `location.pathname` of the page.
+    pub page: String,
+    /// The ids of the executed modules.
+    pub modules: Vec<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Profile {
+    /// The number of sessions in which each module was executed.
+    modules: HashMap<String, u32>,
+    /// The modules executed on each page, by the route of the page.
+    pages: HashMap<String, BTreeSet<String>>,
+}
+
+impl Profile {
+    /// The routes of the pages which executed modules, hottest first. A page
+    /// is as hot as the sum of the sessions its modules were executed in.
+    fn hot_routes(&self) -> Vec<&str> {
+        let mut routes = self
+            .pages
+            .iter()
+            .map(|(route, modules)| {
+                let heat = modules
+                    .iter()
+                    .filter_map(|module| self.modules.get(module))
+                    .map(|&sessions| u64::from(sessions))
+                    .sum::<u64>();
+                (route.as_str(), heat)
+            })
+            .collect::<Vec<_>>();
+        routes.sort_by(|(a, a_heat), (b, b_heat)| b_heat.cmp(a_heat).then(a.cmp(b)));
+        routes.into_iter().map(|(route, _)| route).collect()
+    }
+}
+
+impl Default for WarmupOptions {
+    fn default() -> Self {
+        WarmupOptions {
+            max_routes: 50,
+            idle_delay: Duration::from_secs(1),
+            profile: None,
+        }
+    }
+}
+
+/// Speculatively compiles routes which are linked from served pages while the
+/// server is idle, so they are ready when they are requested.
+///
+/// Routes are compiled one at a time with background priority, so a request
+/// takes precedence over them.
+pub struct Warmup {
+    options: WarmupOptions,
+    queue: Mutex<VecDeque<String>>,
+    /// The routes which are queued or were compiled speculatively, which is
+    /// at most `max_routes`.
+    seen: Mutex<HashSet<String>>,
+    last_request: Mutex<Instant>,
+    notify: Notify,
+    profile: Mutex<Profile>,
+    /// The modules which were executed in this session.
+    executed_modules: Mutex<HashSet<String>>,
+    profile_changed: Notify,
+}
+
+impl Warmup {
+    pub fn new(options: WarmupOptions) -> Arc<Self> {
+        // A missing or broken profile only means that nothing is known yet.
+        let profile = options
+            .profile
+            .as_deref()
+            .and_then(|path| read_profile(path).ok())
+            .unwrap_or_default();
+        let queue = profile
+            .hot_routes()
+            .into_iter()
+            .take(options.max_routes)
+            .map(|route| route.to_string())
+            .collect::<VecDeque<_>>();
+        Arc::new(Warmup {
+            options,
+            seen: Mutex::new(queue.iter().cloned().collect()),
+            queue: Mutex::new(queue),
+            last_request: Mutex::new(Instant::now()),
+            notify: Notify::new(),
+            profile: Mutex::new(profile),
+            executed_modules: Mutex::new(HashSet::new()),
+            profile_changed: Notify::new(),
+        })
+    }
+
+    /// Marks the server as busy.
+    pub fn request_started(&self, path: &str) {
+        *self.last_request.lock().unwrap() = Instant::now();
+        // Requested routes are compiled anyway, so a queued one doesn't need to be
+        // warmed and frees its place.
+        let mut seen = self.seen.lock().unwrap();
+        let mut queue = self.queue.lock().unwrap();
+        if let Some(index) = queue.iter().position(|queued| queued == path) {
+            queue.remove(index);
+            seen.remove(path);
+        }
+    }
+
+    /// Queues the routes linked from a served HTML page.
+    pub fn discover_links(&self, html: &str) {
+        let mut seen = self.seen.lock().unwrap();
+        let mut queue = self.queue.lock().unwrap();
+        for link in links(html) {
+            if seen.len() >= self.options.max_routes {
+                break;
+            }
+            if seen.insert(link.to_string()) {
+                queue.push_back(link.to_string());
+            }
+        }
+        if !queue.is_empty() {
+            self.notify.notify_one();
+        }
+    }
+
+    /// Records the modules the runtime executed on a page, so the page is
+    /// compiled early in the next session. The profile is written by
+    /// [Warmup::persist_profile].
+    pub fn modules_executed(&self, executed: ExecutedModules) {
+        if self.options.profile.is_none() {
+            return;
+        }
+        let route = executed
+            .page
+            .strip_prefix('/')
+            .unwrap_or(&executed.page)
+            .to_string();
+        let mut executed_modules = self.executed_modules.lock().unwrap();
+        let mut profile = self.profile.lock().unwrap();
+        let Profile { modules, pages } = &mut *profile;
+        let page = pages.entry(route).or_default();
+        let mut changed = false;
+        for module in executed.modules {
+            // Modules are counted once per session, however often they are
+            // executed.
+            if executed_modules.insert(module.clone()) {
+                *modules.entry(module.clone()).or_default() += 1;
+                changed = true;
+            }
+            changed |= page.insert(module);
+        }
+        if changed {
+            self.profile_changed.notify_one();
+        }
+    }
+
+    /// Writes the profile whenever it changed, collecting the changes of
+    /// [PROFILE_WRITE_DELAY], until the returned future is dropped. Stops
+    /// at the first failure.
+    pub(crate) async fn persist_profile(self: Arc<Self>) -> Result<()> {
+        let path = if let Some(path) = &self.options.profile {
+            path.clone()
+        } else {
+            return Ok(());
+        };
+        loop {
+            self.profile_changed.notified().await;
+            sleep(PROFILE_WRITE_DELAY).await;
+            let profile = serde_json::to_vec(&*self.profile.lock().unwrap())?;
+            let path = path.clone();
+            spawn_blocking(move || write_profile(&path, &profile)).await??;
+        }
+    }
+
+    /// Compiles queued routes until the returned future is dropped.
+    pub(crate) async fn run(
+        self: Arc<Self>,
+        turbo_tasks: Arc<dyn TurboTasksApi>,
+        source_provider: impl SourceProvider + Sync,
+    ) {
+        loop {
+            let path = self.queue.lock().unwrap().pop_front();
+            let path = if let Some(path) = path {
+                path
+            } else {
+                self.notify.notified().await;
+                continue;
+            };
+            self.wait_for_idle().await;
+            let source_provider = source_provider.clone();
+            let future = run_once(turbo_tasks.clone(), async move {
+                compile_route(source_provider.get_source(), path).await
+            });
+            // Failures are reported when the route is actually requested.
+            let _ = with_priority(TaskPriority::Background, future).await;
+        }
+    }
+
+    async fn wait_for_idle(&self) {
+        loop {
+            let idle_for = self.last_request.lock().unwrap().elapsed();
+            if idle_for >= self.options.idle_delay {
+                return;
+            }
+            sleep(self.options.idle_delay - idle_for).await;
+        }
+    }
+}
+
+fn read_profile(path: &Path) -> Result<Profile> {
+    Ok(serde_json::from_slice(&fs::read(path)?)?)
+}
+
+fn write_profile(path: &Path, profile: &[u8]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, profile)
+        .with_context(|| format!("writing the warmup profile to {}", path.display()))
+}
+
+/// Compiles the content served for `path` without sending it anywhere. Errors
+/// of the compilation are returned, like they would fail a request.
+pub async fn compile_route(source: ContentSourceVc, path: String) -> Result<()> {
+    let mut source = source.resolve_strongly_consistent().await?;
+    let mut path = path;
+    let mut data = ContentSourceData::default();
+    loop {
+        let result = get_from_source(source, &path, Value::new(data))
+            .strongly_consistent()
+            .await?;
+        if let GetFromSourceResult::NeedData {
+            source: needed_source,
+            path: needed_path,
+            vary,
+        } = &*result
+        {
+            source = *needed_source;
+            path = needed_path.clone();
+            data = resource_to_data(
+                ResourceIdentifier {
+                    path: path.clone(),
+                    headers: None,
+                },
+                vary,
+            );
+        } else {
+            return Ok(());
+        }
+    }
+}
+
+/// Returns the paths of same origin links (`<a href="/...">`) and prefetch
+/// hints (`<link rel="prefetch" href="/...">`) in `html`, without the leading
+/// slash, which is how the server names routes.
+fn links(html: &str) -> impl Iterator<Item = &str> {
+    html.split('<').skip(1).filter_map(|tag| {
+        let tag = &tag[..tag.find('>')?];
+        if !tag.starts_with("a ") && !(tag.starts_with("link ") && tag.contains("rel=\"prefetch\""))
+        {
+            return None;
+        }
+        let href = &tag[tag.find("href=\"")? + "href=\"".len()..];
+        let href = &href[..href.find('"')?];
+        let href = href.split(['#', '?']).next()?;
+        if !href.starts_with('/') || href.starts_with("//") {
+            return None;
+        }
+        Some(&href[1..])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeSet, HashMap},
+        path::Path,
+        sync::Arc,
+        time::Duration,
+    };
+
+    use anyhow::Result;
+
+    use super::{
+        links, read_profile, write_profile, ExecutedModules, Profile, Warmup, WarmupOptions,
+    };
+
+    fn queued(warmup: &Warmup) -> Vec<String> {
+        warmup.queue.lock().unwrap().iter().cloned().collect()
+    }
+
+    fn warmup(max_routes: usize) -> Arc<Warmup> {
+        Warmup::new(WarmupOptions {
+            max_routes,
+            ..Default::default()
+        })
+    }
+
+    fn profiled(profile: &Path, max_routes: usize) -> Arc<Warmup> {
+        Warmup::new(WarmupOptions {
+            max_routes,
+            profile: Some(profile.to_path_buf()),
+            ..Default::default()
+        })
+    }
+
+    fn executed(page: &str, modules: &[&str]) -> ExecutedModules {
+        ExecutedModules {
+            page: page.to_string(),
+            modules: modules.iter().map(|module| module.to_string()).collect(),
+        }
+    }
+
+    fn modules(modules: &[&str]) -> BTreeSet<String> {
+        modules.iter().map(|module| module.to_string()).collect()
+    }
+
+    #[test]
+    fn requested_routes_dont_count_against_the_limit() {
+        let warmup = warmup(2);
+        for i in 0..10 {
+            warmup.request_started(&format!("page{i}"));
+        }
+        warmup.discover_links(r#"<a href="/a">A</a> <a href="/b">B</a> <a href="/c">C</a>"#);
+        assert_eq!(queued(&warmup), ["a", "b"]);
+        assert_eq!(warmup.seen.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn requested_routes_are_removed_from_the_queue() {
+        let warmup = warmup(2);
+        warmup.discover_links(r#"<a href="/a">A</a> <a href="/b">B</a>"#);
+        warmup.request_started("a");
+        assert_eq!(queued(&warmup), ["b"]);
+
+        warmup.discover_links(r#"<a href="/b">B</a> <a href="/c">C</a> <a href="/d">D</a>"#);
+        assert_eq!(queued(&warmup), ["b", "c"]);
+    }
+
+    #[test]
+    fn finds_same_origin_links() {
+        let html = r#"
+            <a href="/about?ref=home#team">About</a>
+            <link rel="prefetch" href="/blog">
+            <link rel="stylesheet" href="/style.css">
+            <a href="https://example.com/">External</a>
+            <a href="//cdn.example.com/">Protocol relative</a>
+            <a href="relative">Relative</a>
+        "#;
+        assert_eq!(links(html).collect::<Vec<_>>(), ["about", "blog"]);
+    }
+
+    #[test]
+    fn counts_executed_modules_once_per_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let warmup = profiled(&dir.path().join("warmup.json"), 50);
+        warmup.modules_executed(executed("/", &["a", "shared"]));
+        warmup.modules_executed(executed("/about", &["shared", "b"]));
+        warmup.modules_executed(executed("/about", &["b"]));
+
+        let profile = warmup.profile.lock().unwrap();
+        assert_eq!(profile.modules["a"], 1);
+        assert_eq!(profile.modules["b"], 1);
+        assert_eq!(profile.modules["shared"], 1);
+        assert_eq!(profile.pages[""], modules(&["a", "shared"]));
+        assert_eq!(profile.pages["about"], modules(&["b", "shared"]));
+    }
+
+    #[test]
+    fn ignores_executed_modules_without_profile() {
+        let warmup = warmup(50);
+        warmup.modules_executed(executed("/", &["a"]));
+        assert!(warmup.profile.lock().unwrap().pages.is_empty());
+    }
+
+    #[test]
+    fn compiles_hot_pages_first() -> Result<()> {
+        let profile = Profile {
+            modules: HashMap::from([
+                ("a".to_string(), 1),
+                ("b".to_string(), 3),
+                ("c".to_string(), 1),
+            ]),
+            pages: HashMap::from([
+                ("".to_string(), modules(&["a"])),
+                ("about".to_string(), modules(&["a", "c"])),
+                ("blog".to_string(), modules(&["b"])),
+            ]),
+        };
+        assert_eq!(profile.hot_routes(), ["blog", "about", ""]);
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("warmup.json");
+        write_profile(&path, &serde_json::to_vec(&profile)?)?;
+        // Cold pages are only compiled when they are linked.
+        assert_eq!(queued(&profiled(&path, 2)), ["blog", "about"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn persists_the_profile() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("cache/warmup.json");
+        let warmup = profiled(&path, 50);
+        let persist = tokio::spawn(warmup.clone().persist_profile());
+        warmup.modules_executed(executed("/blog", &["b"]));
+        let profile = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                if let Ok(profile) = read_profile(&path) {
+                    return profile;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await?;
+        persist.abort();
+        assert_eq!(profile.pages["blog"], modules(&["b"]));
+        assert_eq!(queued(&profiled(&path, 50)), ["blog"]);
+        Ok(())
+    }
+}
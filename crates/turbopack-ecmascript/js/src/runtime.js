@@ -6,10 +6,14 @@
   // When the runtime executes, it will pick up and register all pending chunks,
   // and replace the list of pending chunks with itself so later chunks can
   // register directly with it.
+  //
+  // The runtime is loaded from its own chunk, which might execute before any
+  // other chunk was pushed. When it was already loaded on this page, it must
+  // not be initialized again.
 
   /* eslint-disable @next/next/no-assign-module-variable */
 
-  if (!Array.isArray(self.TURBOPACK)) {
+  if (self.TURBOPACK != null && !Array.isArray(self.TURBOPACK)) {
     return;
   }
 
@@ -42,7 +46,7 @@
   /** @typedef {import('../types/runtime').ModuleEffect} ModuleEffect */
 
   /** @type {ChunkRegistration[]} */
-  const chunksToRegister = self.TURBOPACK;
+  const chunksToRegister = self.TURBOPACK || [];
   /** @type {Array<Runnable>} */
   let runnable = [];
   /** @type {Object.<ModuleId, ModuleFactory>} */
@@ -182,6 +186,33 @@
     return getOrInstantiateModuleFromParent(id, sourceModule).exports;
   }
 
+  /**
+   * Runs the body of a module which uses top-level await, or statically
+   * imports such a module. The body only continues after its imports were
+   * fully evaluated, and `module.async` resolves to the exports once the body
+   * completed, so the module's importers can wait for it in turn.
+   *
+   * @param {Module} module
+   * @param {(handleAsyncDependencies: () => Promise<void>) => Promise<void>} body
+   */
+  function asyncModule(module, body) {
+    module.async = body(() => handleAsyncDependencies(module)).then(
+      () => module.exports
+    );
+  }
+
+  /**
+   * Waits for all asynchronous modules imported by `module`. The imports of a
+   * module which are part of an import cycle might not have started their
+   * evaluation yet, and are not waited for.
+   *
+   * @param {Module} module
+   * @returns {Promise<void>}
+   */
+  async function handleAsyncDependencies(module) {
+    await Promise.all(module.children.map((id) => moduleCache[id]?.async));
+  }
+
   function externalRequire(id) {
     let raw;
     try {
@@ -360,6 +391,7 @@
         r: commonJsRequire.bind(null, module),
         x: externalRequire,
         i: esmImport.bind(null, module),
+        a: asyncModule.bind(null, module),
         s: esm.bind(null, module.exports),
         v: exportValue.bind(null, module),
         m: module,
@@ -376,10 +408,49 @@
       // in case of a circular dependency: cjs1 -> esm2 -> cjs1
       interopEsm(module.exports, module.interopNamespace);
     }
+    reportExecutedModule(id);
 
     return module;
   }
 
+  /**
+   * The modules executed since they were last reported.
+   *
+   * @type {Set<ModuleId>}
+   */
+  const executedModules = new Set();
+  let executedModulesTimeout = null;
+
+  /**
+   * Reports the executed modules to the dev server, which compiles the pages
+   * executing them first on its next startup. Modules are reported in
+   * batches, once the page settled.
+   *
+   * @param {ModuleId} id
+   */
+  function reportExecutedModule(id) {
+    if (typeof document === "undefined" || typeof fetch !== "function") {
+      return;
+    }
+    executedModules.add(id);
+    if (executedModulesTimeout != null) {
+      return;
+    }
+    executedModulesTimeout = setTimeout(() => {
+      const modules = Array.from(executedModules);
+      executedModules.clear();
+      executedModulesTimeout = null;
+      fetch("/__turbopack_executed__", {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({ page: location.pathname, modules }),
+        keepalive: true,
+      }).catch(() => {
+        // The profile only speeds up the next startup of the dev server.
+      });
+    }, 1000);
+  }
+
   /**
    * NOTE(alexkirsz) Webpack has an "module execution" interception hook that
    * Next.js' React Refresh runtime hooks into to add module context to the
@@ -35,6 +35,7 @@ interface Module {
   children: ModuleId[];
   parents: ModuleId[];
   interopNamespace?: EsmInteropNamespace;
+  async?: Promise<Exports>;
 }
 
 type ModuleCache = Record<ModuleId, Module>;
@@ -49,12 +50,17 @@ type EsmImport = (
 type EsmExport = (exportGetters: Record<string, () => any>) => void;
 type ExportValue = (value: any) => void;
 
+type AsyncModule = (
+  body: (handleAsyncDependencies: () => Promise<void>) => Promise<void>
+) => void;
+
 type LoadChunk = (chunkPath: ChunkPath) => Promise<any> | undefined;
 
 interface TurbopackContext {
   e: Module["exports"];
   r: CommonJsRequire;
   i: EsmImport;
+  a: AsyncModule;
   s: EsmExport;
   v: ExportValue;
   m: Module;
@@ -125,6 +125,8 @@ pub fn replace_builtin(value: &mut JsValue) -> bool {
                         | JsValue::WellKnownObject(_)
                         | JsValue::Argument(_)
                         | JsValue::WellKnownFunction(_)
+                        | JsValue::Tenary(..)
+                        | JsValue::Binary(..)
                         | JsValue::Module(..) => {
                             // keep the member infact since it might be handled later
                             return false;
@@ -228,6 +230,8 @@ pub fn replace_builtin(value: &mut JsValue) -> bool {
                         | JsValue::WellKnownObject(_)
                         | JsValue::Argument(_)
                         | JsValue::WellKnownFunction(_)
+                        | JsValue::Tenary(..)
+                        | JsValue::Binary(..)
                         | JsValue::Module(..) => {
                             // keep the member intact since it might be handled later
                             debug_assert!(prop.has_placeholder());
@@ -243,6 +247,8 @@ pub fn replace_builtin(value: &mut JsValue) -> bool {
                 | JsValue::WellKnownObject(_)
                 | JsValue::Argument(_)
                 | JsValue::WellKnownFunction(_)
+                | JsValue::Tenary(..)
+                | JsValue::Binary(..)
                 | JsValue::Module(..) => {
                     // keep the member intact since it might be handled later
                     debug_assert!(obj.has_placeholder());
@@ -443,6 +449,8 @@ pub fn replace_builtin(value: &mut JsValue) -> bool {
                 | JsValue::WellKnownObject(_)
                 | JsValue::Argument(_)
                 | JsValue::WellKnownFunction(_)
+                | JsValue::Tenary(..)
+                | JsValue::Binary(..)
                 | JsValue::Module(..) => {
                     // keep the call infact since it might be handled later
                     debug_assert!(callee.has_placeholder());
@@ -450,6 +458,36 @@ pub fn replace_builtin(value: &mut JsValue) -> bool {
                 }
             }
         }
+        JsValue::Tenary(_, box ref mut test, box ref mut cons, box ref mut alt) => {
+            match test.is_truthy() {
+                Some(true) => {
+                    *value = take(cons);
+                    true
+                }
+                Some(false) => {
+                    *value = take(alt);
+                    true
+                }
+                // keep the tenary intact since the test might be decided later
+                None if test.has_placeholder() => false,
+                None => {
+                    *value = JsValue::alternatives(vec![take(cons), take(alt)]);
+                    true
+                }
+            }
+        }
+        JsValue::Binary(_, box ref left, op, box ref right) => {
+            if let Some(result) = op.apply(left, right) {
+                *value = result.into();
+                true
+            } else if left.has_placeholder() || right.has_placeholder() {
+                // keep the comparison intact since it might be decided later
+                false
+            } else {
+                value.make_unknown("comparison of unknown values");
+                true
+            }
+        }
         JsValue::Object(_, parts) => {
             if parts
                 .iter()
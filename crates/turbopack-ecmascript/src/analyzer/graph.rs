@@ -9,12 +9,30 @@ use swc_core::{
     },
 };
 
-use super::{ConstantNumber, ConstantValue, ImportMap, JsValue, ObjectPart, WellKnownFunctionKind};
+use super::{
+    BinaryOperator, ConstantNumber, ConstantValue, ImportMap, JsValue, ObjectPart,
+    WellKnownFunctionKind, WellKnownObjectKind,
+};
 use crate::{
     analyzer::{is_unresolved, FreeVarKind},
     utils::unparen,
 };
 
+/// Whether `value` refers to something which is replaced by a known value when
+/// linking, like `process` or imported modules.
+fn depends_on_environment(value: &JsValue) -> bool {
+    let mut result = false;
+    value.visit(&mut |value| {
+        if matches!(
+            value,
+            JsValue::FreeVar(FreeVarKind::NodeProcess | FreeVarKind::Require) | JsValue::Module(..)
+        ) {
+            result = true;
+        }
+    });
+    result
+}
+
 #[derive(Debug, Clone)]
 pub enum Effect {
     Call {
@@ -203,6 +221,47 @@ impl EvalContext {
         JsValue::concat(values)
     }
 
+    /// Evaluates the test of a conditional, which might be a comparison.
+    fn eval_condition(&self, test: &Expr) -> JsValue {
+        match test {
+            Expr::Paren(ParenExpr { expr, .. }) => self.eval_condition(expr),
+            Expr::Unary(UnaryExpr {
+                op: op!("!"), arg, ..
+            }) => {
+                let arg = self.eval_condition(arg);
+                match arg.is_truthy() {
+                    Some(truthy) => (!truthy).into(),
+                    None => match arg {
+                        JsValue::Binary(_, left, op, right) => {
+                            JsValue::binary(left, op.negate(), right)
+                        }
+                        arg => JsValue::Unknown(Some(Arc::new(arg)), "negation is not supported"),
+                    },
+                }
+            }
+            Expr::Bin(BinExpr {
+                op: op @ (op!("==") | op!("!=") | op!("===") | op!("!==")),
+                left,
+                right,
+                ..
+            }) => {
+                let op = match op {
+                    op!("==") => BinaryOperator::Equal,
+                    op!("!=") => BinaryOperator::NotEqual,
+                    op!("===") => BinaryOperator::StrictEqual,
+                    _ => BinaryOperator::StrictNotEqual,
+                };
+                let left = self.eval(left);
+                let right = self.eval(right);
+                match op.apply(&left, &right) {
+                    Some(result) => result.into(),
+                    None => JsValue::binary(box left, op, box right),
+                }
+            }
+            _ => self.eval(test),
+        }
+    }
+
     pub fn eval(&self, e: &Expr) -> JsValue {
         match e {
             Expr::Lit(e) => JsValue::Constant(e.clone().into()),
@@ -245,17 +304,51 @@ impl EvalContext {
             }
 
             Expr::Bin(BinExpr {
-                op: op!("||") | op!("??"),
+                op: op!("||"),
                 left,
                 right,
                 ..
-            }) => JsValue::alternatives(vec![self.eval(left), self.eval(right)]),
+            }) => {
+                let left = self.eval(left);
+                match left.is_truthy() {
+                    Some(true) => left,
+                    Some(false) => self.eval(right),
+                    None => JsValue::alternatives(vec![left, self.eval(right)]),
+                }
+            }
+
+            Expr::Bin(BinExpr {
+                op: op!("??"),
+                left,
+                right,
+                ..
+            }) => {
+                let left = self.eval(left);
+                match &left {
+                    JsValue::Constant(c) if c.is_nullish() => self.eval(right),
+                    JsValue::Constant(_) => left,
+                    _ => JsValue::alternatives(vec![left, self.eval(right)]),
+                }
+            }
 
             &Expr::Cond(CondExpr {
+                box ref test,
                 box ref cons,
                 box ref alt,
                 ..
-            }) => JsValue::alternatives(vec![self.eval(cons), self.eval(alt)]),
+            }) => {
+                let test = self.eval_condition(test);
+                match test.is_truthy() {
+                    Some(true) => self.eval(cons),
+                    Some(false) => self.eval(alt),
+                    // Conditions on the environment might be decided when
+                    // linking, e. g. `process.platform === "win32"`.
+                    None if depends_on_environment(&test) => {
+                        JsValue::tenary(box test, box self.eval(cons), box self.eval(alt))
+                    }
+                    None => JsValue::alternatives(vec![self.eval(cons), self.eval(alt)]),
+                }
+            }
 
             Expr::Tpl(e) => self.eval_tpl(e, false),
 
@@ -411,6 +504,11 @@ impl EvalContext {
                 )
             }
 
+            Expr::MetaProp(MetaPropExpr {
+                kind: MetaPropKind::ImportMeta,
+                ..
+            }) => JsValue::WellKnownObject(WellKnownObjectKind::ImportMeta),
+
             _ => JsValue::Unknown(None, "unsupported expression"),
         }
     }
@@ -2,13 +2,16 @@ use std::{collections::BTreeMap, fmt::Display, mem::take};
 
 use indexmap::{IndexMap, IndexSet};
 use once_cell::sync::Lazy;
-use swc_core::ecma::{
-    ast::*,
-    atoms::{js_word, JsWord},
-    visit::{Visit, VisitWith},
+use swc_core::{
+    common::{Span, Spanned},
+    ecma::{
+        ast::*,
+        atoms::{js_word, JsWord},
+        visit::{Visit, VisitWith},
+    },
 };
 
-use super::{JsValue, ModuleValue};
+use super::{ConstantValue, JsValue, ModuleValue, ObjectPart};
 use crate::utils::unparen;
 
 #[turbo_tasks::value(serialization = "auto_for_input")]
@@ -25,6 +28,13 @@ static ANNOTATION_TRANSITION: Lazy<JsWord> = Lazy::new(|| "transition".into());
 /// Changes the chunking type for the annotated import
 static ANNOTATION_CHUNKING_TYPE: Lazy<JsWord> = Lazy::new(|| "chunking-type".into());
 
+/// The module type required by an import assertion, e.g.
+/// `assert { type: "json" }`
+static ANNOTATION_MODULE_TYPE: Lazy<JsWord> = Lazy::new(|| "type".into());
+
+/// The module types which can be required by import assertions
+const MODULE_TYPES: &[&str] = &["json", "css"];
+
 impl ImportAnnotations {
     fn insert(&mut self, key: JsWord, value: Option<JsWord>) {
         self.map.insert(key, value);
@@ -47,6 +57,13 @@ impl ImportAnnotations {
             .get(&ANNOTATION_CHUNKING_TYPE)
             .and_then(|w| w.as_ref().map(|w| &**w))
     }
+
+    /// Returns the module type required by the import assertion
+    pub fn module_type(&self) -> Option<&str> {
+        self.map
+            .get(&ANNOTATION_MODULE_TYPE)
+            .and_then(|w| w.as_ref().map(|w| &**w))
+    }
 }
 
 impl Display for ImportAnnotations {
@@ -99,6 +116,9 @@ pub(crate) struct ImportMap {
 
     /// True, when the module has exports
     has_exports: bool,
+
+    /// List of (span, message) of invalid import assertions
+    invalid_assertions: Vec<(Span, String)>,
 }
 
 impl ImportMap {
@@ -146,6 +166,36 @@ impl ImportMap {
         self.reexports.iter().map(|(i, r)| (*i, r))
     }
 
+    pub fn invalid_assertions(&self) -> impl Iterator<Item = (Span, &str)> {
+        self.invalid_assertions.iter().map(|(s, m)| (*s, &**m))
+    }
+
+    /// Returns the symbols imported from the reference at index `i` when the
+    /// module is only accessed via named imports. Returns `None` when the
+    /// namespace of the module might be accessed as a whole, e.g. by namespace
+    /// imports or re-exports.
+    pub fn imported_symbols(&self, i: usize) -> Option<Vec<String>> {
+        if self.namespace_imports.values().any(|&j| j == i)
+            || self.reexports.iter().any(|&(j, _)| j == i)
+        {
+            return None;
+        }
+        let mut symbols = Vec::new();
+        for (j, symbol) in self.imports.values() {
+            if *j != i {
+                continue;
+            }
+            // The module id is a special import, which can't be followed.
+            if &**symbol == "__turbopack_module_id__" {
+                return None;
+            }
+            if !symbols.iter().any(|s| s == &**symbol) {
+                symbols.push(symbol.to_string());
+            }
+        }
+        Some(symbols)
+    }
+
     /// Analyze ES import
     pub(super) fn analyze(m: &Program) -> Self {
         let mut data = ImportMap::default();
@@ -164,7 +214,104 @@ struct Analyzer<'a> {
     current_annotations: ImportAnnotations,
 }
 
+fn unsupported_module_type(module_type: &str) -> String {
+    format!(
+        "Unsupported module type \"{}\" in import assertion, supported types are: {}",
+        module_type,
+        MODULE_TYPES.join(", ")
+    )
+}
+
+/// Returns the module type required by the options of a dynamic `import()`,
+/// e.g. `import("./data.json", { assert: { type: "json" } })`. Options which
+/// can't be analysed or require an unsupported type are an error.
+pub(crate) fn dynamic_import_module_type(options: &JsValue) -> Result<Option<String>, String> {
+    let parts = match options {
+        JsValue::Object(_, parts) => parts,
+        JsValue::Constant(ConstantValue::Undefined) => return Ok(None),
+        _ => return Err("The options of import() can't be analysed statically".to_string()),
+    };
+    let mut module_type = None;
+    for part in parts {
+        match part {
+            ObjectPart::KeyValue(key, value) if key.as_str() == Some("assert") => {
+                let assertions = match value {
+                    JsValue::Object(_, assertions) => assertions,
+                    _ => {
+                        return Err("The import assertions of import() can't be analysed \
+                                    statically"
+                            .to_string())
+                    }
+                };
+                for assertion in assertions {
+                    match assertion {
+                        ObjectPart::KeyValue(key, value) if key.as_str() == Some("type") => {
+                            match value.as_str() {
+                                Some(ty) if MODULE_TYPES.contains(&ty) => {
+                                    module_type = Some(ty.to_string())
+                                }
+                                Some(ty) => return Err(unsupported_module_type(ty)),
+                                None => {
+                                    return Err("The type of an import assertion must be a string"
+                                        .to_string())
+                                }
+                            }
+                        }
+                        ObjectPart::KeyValue(key, _) if key.as_str().is_some() => {}
+                        _ => {
+                            return Err("The import assertions of import() can't be analysed \
+                                        statically"
+                                .to_string())
+                        }
+                    }
+                }
+            }
+            ObjectPart::KeyValue(key, _) if key.as_str().is_some() => {}
+            _ => return Err("The options of import() can't be analysed statically".to_string()),
+        }
+    }
+    Ok(module_type)
+}
+
 impl<'a> Analyzer<'a> {
+    /// Adds the module type of an import assertion to the annotations of the
+    /// next reference.
+    fn add_assertions(&mut self, asserts: Option<&ObjectLit>) {
+        let asserts = if let Some(asserts) = asserts {
+            asserts
+        } else {
+            return;
+        };
+        for prop in &asserts.props {
+            let (key, value) = match prop {
+                PropOrSpread::Prop(box Prop::KeyValue(KeyValueProp { key, value })) => (key, value),
+                _ => continue,
+            };
+            let is_type = match key {
+                PropName::Ident(ident) => &*ident.sym == "type",
+                PropName::Str(str) => &*str.value == "type",
+                _ => false,
+            };
+            if !is_type {
+                continue;
+            }
+            match &**value {
+                Expr::Lit(Lit::Str(str)) if MODULE_TYPES.contains(&&*str.value) => {
+                    self.current_annotations
+                        .insert(ANNOTATION_MODULE_TYPE.clone(), Some(str.value.clone()));
+                }
+                Expr::Lit(Lit::Str(str)) => self
+                    .data
+                    .invalid_assertions
+                    .push((str.span, unsupported_module_type(&str.value))),
+                value => self.data.invalid_assertions.push((
+                    value.span(),
+                    "The type of an import assertion must be a string".to_string(),
+                )),
+            }
+        }
+    }
+
     fn ensure_reference(&mut self, module_path: JsWord) -> usize {
         let tuple = (module_path, take(&mut self.current_annotations));
         if let Some(i) = self.data.references.get_index_of(&tuple) {
@@ -220,6 +367,7 @@ impl Visit for Analyzer<'_> {
     }
 
     fn visit_import_decl(&mut self, import: &ImportDecl) {
+        self.add_assertions(import.asserts.as_deref());
         let i = self.ensure_reference(import.src.value.clone());
         for s in &import.specifiers {
             let (local, orig_sym) = match s {
@@ -242,6 +390,7 @@ impl Visit for Analyzer<'_> {
 
     fn visit_export_all(&mut self, export: &ExportAll) {
         self.data.has_exports = true;
+        self.add_assertions(export.asserts.as_deref());
         let i = self.ensure_reference(export.src.value.clone());
         self.data.reexports.push((i, Reexport::Star));
     }
@@ -249,6 +398,7 @@ impl Visit for Analyzer<'_> {
     fn visit_named_export(&mut self, export: &NamedExport) {
         self.data.has_exports = true;
         if let Some(ref src) = export.src {
+            self.add_assertions(export.asserts.as_deref());
             let i = self.ensure_reference(src.value.clone());
             for spec in export.specifiers.iter() {
                 match spec {
@@ -303,3 +453,121 @@ fn orig_name(n: &ModuleExportName) -> JsWord {
         ModuleExportName::Str(v) => v.value.clone(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{FileName, SourceMap},
+        ecma::{
+            ast::{EsVersion, Program},
+            parser::{parse_file_as_module, EsConfig, Syntax},
+        },
+    };
+
+    use super::{dynamic_import_module_type, ImportMap};
+    use crate::analyzer::{ConstantValue, JsValue, ObjectPart};
+
+    fn parse(code: &str) -> Program {
+        let cm = SourceMap::default();
+        let fm = cm.new_source_file(FileName::Anon, code.to_string());
+        Program::Module(
+            parse_file_as_module(
+                &fm,
+                Syntax::Es(EsConfig {
+                    import_assertions: true,
+                    ..Default::default()
+                }),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn static_import_assertions() {
+        let map = ImportMap::analyze(&parse(
+            r#"
+            import a from "./a.json" assert { type: "json" };
+            import b from "./b.css" assert { type: "css" };
+            export * from "./c.css" assert { type: "css" };
+            import d from "./d.js";
+            "#,
+        ));
+        let module_types: Vec<_> = map
+            .references()
+            .map(|(module, annotations)| (&**module, annotations.module_type()))
+            .collect();
+        assert_eq!(
+            module_types,
+            vec![
+                ("./a.json", Some("json")),
+                ("./b.css", Some("css")),
+                ("./c.css", Some("css")),
+                ("./d.js", None),
+            ]
+        );
+        assert_eq!(map.invalid_assertions().count(), 0);
+    }
+
+    #[test]
+    fn invalid_static_import_assertions() {
+        let map = ImportMap::analyze(&parse(
+            r#"
+            import a from "./a.wasm" assert { type: "webassembly" };
+            "#,
+        ));
+        assert_eq!(map.references().next().unwrap().1.module_type(), None);
+        assert_eq!(map.invalid_assertions().count(), 1);
+    }
+
+    fn options(assertions: Vec<ObjectPart>) -> JsValue {
+        JsValue::object(vec![ObjectPart::KeyValue(
+            "assert".into(),
+            JsValue::object(assertions),
+        )])
+    }
+
+    fn assertion(key: &str, value: JsValue) -> ObjectPart {
+        ObjectPart::KeyValue(key.into(), value)
+    }
+
+    #[test]
+    fn dynamic_import_without_assertions() {
+        assert_eq!(
+            dynamic_import_module_type(&JsValue::Constant(ConstantValue::Undefined)),
+            Ok(None)
+        );
+        assert_eq!(
+            dynamic_import_module_type(&JsValue::object(vec![])),
+            Ok(None)
+        );
+        assert_eq!(dynamic_import_module_type(&options(vec![])), Ok(None));
+    }
+
+    #[test]
+    fn dynamic_import_with_module_type() {
+        assert_eq!(
+            dynamic_import_module_type(&options(vec![assertion("type", "css".into())])),
+            Ok(Some("css".to_string()))
+        );
+        assert_eq!(
+            dynamic_import_module_type(&options(vec![assertion("type", "json".into())])),
+            Ok(Some("json".to_string()))
+        );
+    }
+
+    #[test]
+    fn dynamic_import_with_invalid_assertions() {
+        assert!(
+            dynamic_import_module_type(&options(vec![assertion("type", "wasm".into())])).is_err()
+        );
+        assert!(dynamic_import_module_type(&options(vec![assertion(
+            "type",
+            JsValue::Unknown(None, "")
+        )]))
+        .is_err());
+        assert!(dynamic_import_module_type(&JsValue::Unknown(None, "")).is_err());
+    }
+}
@@ -9,7 +9,7 @@ use std::{
 };
 
 use indexmap::IndexSet;
-use num_bigint::BigInt;
+use num_bigint::{BigInt, Sign};
 use swc_core::{
     common::Mark,
     ecma::{
@@ -97,6 +97,58 @@ impl ConstantValue {
             _ => None,
         }
     }
+
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Self::Undefined | Self::False | Self::Null => false,
+            Self::True | Self::Regex(..) => true,
+            Self::StrWord(s) => !s.is_empty(),
+            Self::StrAtom(s) => !s.is_empty(),
+            Self::Num(ConstantNumber(n)) => *n != 0.0 && !n.is_nan(),
+            Self::BigInt(n) => n.sign() != Sign::NoSign,
+        }
+    }
+
+    fn is_nullish(&self) -> bool {
+        matches!(self, Self::Undefined | Self::Null)
+    }
+
+    /// The result of `typeof` for the value.
+    fn type_of(&self) -> &'static str {
+        match self {
+            Self::Undefined => "undefined",
+            Self::StrWord(_) | Self::StrAtom(_) => "string",
+            Self::Num(_) => "number",
+            Self::True | Self::False => "boolean",
+            Self::BigInt(_) => "bigint",
+            Self::Null | Self::Regex(..) => "object",
+        }
+    }
+
+    /// The result of `self === other`.
+    fn strict_equals(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Num(ConstantNumber(l)), Self::Num(ConstantNumber(r))) => l == r,
+            // Every regex literal creates a new object.
+            (Self::Regex(..), Self::Regex(..)) => false,
+            _ => match (self.as_str(), other.as_str()) {
+                (Some(l), Some(r)) => l == r,
+                _ => self == other,
+            },
+        }
+    }
+
+    /// The result of `self == other`, or `None` when it depends on type
+    /// coercion.
+    fn loose_equals(&self, other: &Self) -> Option<bool> {
+        if self.is_nullish() || other.is_nullish() {
+            Some(self.is_nullish() && other.is_nullish())
+        } else if self.type_of() == other.type_of() {
+            Some(self.strict_equals(other))
+        } else {
+            None
+        }
+    }
 }
 
 impl Default for ConstantValue {
@@ -201,6 +253,56 @@ pub enum JsValue {
     Function(usize, Box<JsValue>),
 
     Argument(usize),
+
+    /// `(test, cons, alt)` of `test ? cons : alt`, when the test might only be
+    /// decided after linking, e. g. `process.platform === "win32"`.
+    Tenary(usize, Box<JsValue>, Box<JsValue>, Box<JsValue>),
+
+    /// `(left, op, right)` of a comparison.
+    Binary(usize, Box<JsValue>, BinaryOperator, Box<JsValue>),
+}
+
+/// The comparison operators which can be evaluated by the analyzer.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Equal,
+    NotEqual,
+    StrictEqual,
+    StrictNotEqual,
+}
+
+impl BinaryOperator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::StrictEqual => "===",
+            BinaryOperator::StrictNotEqual => "!==",
+        }
+    }
+
+    /// The operator of `!(left op right)`.
+    pub fn negate(&self) -> Self {
+        match self {
+            BinaryOperator::Equal => BinaryOperator::NotEqual,
+            BinaryOperator::NotEqual => BinaryOperator::Equal,
+            BinaryOperator::StrictEqual => BinaryOperator::StrictNotEqual,
+            BinaryOperator::StrictNotEqual => BinaryOperator::StrictEqual,
+        }
+    }
+
+    /// Evaluates `left op right`. Returns `None` when the operands are not
+    /// known or the result depends on type coercion.
+    pub fn apply(&self, left: &JsValue, right: &JsValue) -> Option<bool> {
+        let left = left.as_constant()?;
+        let right = right.as_constant()?;
+        Some(match self {
+            BinaryOperator::Equal => left.loose_equals(&right)?,
+            BinaryOperator::NotEqual => !left.loose_equals(&right)?,
+            BinaryOperator::StrictEqual => left.strict_equals(&right),
+            BinaryOperator::StrictNotEqual => !left.strict_equals(&right),
+        })
+    }
 }
 
 impl From<&'_ str> for JsValue {
@@ -245,6 +347,16 @@ impl From<swc_core::ecma::ast::Str> for JsValue {
     }
 }
 
+impl From<bool> for JsValue {
+    fn from(v: bool) -> Self {
+        if v {
+            ConstantValue::True.into()
+        } else {
+            ConstantValue::False.into()
+        }
+    }
+}
+
 impl From<ConstantValue> for JsValue {
     fn from(v: ConstantValue) -> Self {
         JsValue::Constant(v)
@@ -350,6 +462,10 @@ impl Display for JsValue {
                 write!(f, "Function(return = {:?})", return_value)
             }
             JsValue::Argument(index) => write!(f, "arguments[{}]", index),
+            JsValue::Tenary(_, test, cons, alt) => write!(f, "({} ? {} : {})", test, cons, alt),
+            JsValue::Binary(_, left, op, right) => {
+                write!(f, "({} {} {})", left, op.as_str(), right)
+            }
         }
     }
 }
@@ -455,6 +571,60 @@ impl JsValue {
         Self::Member(1 + o.total_nodes() + p.total_nodes(), o, p)
     }
 
+    pub fn tenary(test: Box<JsValue>, cons: Box<JsValue>, alt: Box<JsValue>) -> Self {
+        Self::Tenary(
+            1 + test.total_nodes() + cons.total_nodes() + alt.total_nodes(),
+            test,
+            cons,
+            alt,
+        )
+    }
+
+    pub fn binary(left: Box<JsValue>, op: BinaryOperator, right: Box<JsValue>) -> Self {
+        Self::Binary(
+            1 + left.total_nodes() + right.total_nodes(),
+            left,
+            op,
+            right,
+        )
+    }
+
+    /// The constant value, with the global `undefined` treated as constant.
+    fn as_constant(&self) -> Option<ConstantValue> {
+        match self {
+            JsValue::Constant(c) => Some(c.clone()),
+            JsValue::FreeVar(FreeVarKind::Other(name)) if &**name == "undefined" => {
+                Some(ConstantValue::Undefined)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the value is truthy, or `None` when that is not known.
+    pub fn is_truthy(&self) -> Option<bool> {
+        match self {
+            JsValue::Constant(c) => Some(c.is_truthy()),
+            JsValue::FreeVar(FreeVarKind::Other(name)) if &**name == "undefined" => Some(false),
+            JsValue::Array(..)
+            | JsValue::Object(..)
+            | JsValue::Url(..)
+            | JsValue::Function(..)
+            | JsValue::WellKnownObject(_)
+            | JsValue::WellKnownFunction(_) => Some(true),
+            JsValue::Concat(_, list) => list
+                .iter()
+                .any(|v| v.as_str().map_or(false, |s| !s.is_empty()))
+                .then_some(true),
+            JsValue::Alternatives(_, list) => {
+                let first = list.first()?.is_truthy()?;
+                list.iter()
+                    .all(|v| v.is_truthy() == Some(first))
+                    .then_some(first)
+            }
+            _ => None,
+        }
+    }
+
     pub fn total_nodes(&self) -> usize {
         match self {
             JsValue::Constant(_)
@@ -475,7 +645,9 @@ impl JsValue {
             | JsValue::Call(c, _, _)
             | JsValue::MemberCall(c, _, _, _)
             | JsValue::Member(c, _, _)
-            | JsValue::Function(c, _) => *c,
+            | JsValue::Function(c, _)
+            | JsValue::Tenary(c, _, _, _)
+            | JsValue::Binary(c, _, _, _) => *c,
         }
     }
 
@@ -519,6 +691,12 @@ impl JsValue {
             JsValue::Function(c, r) => {
                 *c = 1 + r.total_nodes();
             }
+            JsValue::Tenary(c, t, l, r) => {
+                *c = 1 + t.total_nodes() + l.total_nodes() + r.total_nodes();
+            }
+            JsValue::Binary(c, l, _, r) => {
+                *c = 1 + l.total_nodes() + r.total_nodes();
+            }
         }
     }
 
@@ -573,6 +751,14 @@ impl JsValue {
                 JsValue::Function(_, r) => {
                     r.make_unknown_without_content("node limit reached");
                 }
+                JsValue::Tenary(_, t, l, r) => {
+                    make_max_unknown([&mut **t, &mut **l, &mut **r].into_iter());
+                    self.update_total_nodes();
+                }
+                JsValue::Binary(_, l, _, r) => {
+                    make_max_unknown([&mut **l, &mut **r].into_iter());
+                    self.update_total_nodes();
+                }
             }
         }
     }
@@ -879,6 +1065,10 @@ impl JsValue {
                         "require.cache",
                         "The CommonJS require.cache object: https://nodejs.org/api/modules.html#requirecache"
                     ),
+                    WellKnownObjectKind::ImportMeta => (
+                        "import.meta",
+                        "The import.meta object from the ESM specification: https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Operators/import.meta"
+                    ),
                 };
                 if depth > 0 {
                     let i = hints.len();
@@ -912,6 +1102,7 @@ impl JsValue {
                     ),
                     WellKnownFunctionKind::Require => ("require".to_string(), "The require method from CommonJS"),
                     WellKnownFunctionKind::RequireResolve => ("require.resolve".to_string(), "The require.resolve method from CommonJS"),
+                    WellKnownFunctionKind::ImportMetaResolve => ("import.meta.resolve".to_string(), "The import.meta.resolve method from the ESM specification: https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Operators/import.meta/resolve"),
                     WellKnownFunctionKind::Define => ("define".to_string(), "The define method from AMD"),
                     WellKnownFunctionKind::FsReadMethod(name) => (
                         format!("fs.{name}"),
@@ -1005,6 +1196,18 @@ impl JsValue {
                     "(...) => ...".to_string()
                 }
             }
+            JsValue::Tenary(_, test, cons, alt) => format!(
+                "({} ? {} : {})",
+                test.explain_internal_inner(hints, indent_depth, depth, unknown_depth),
+                cons.explain_internal_inner(hints, indent_depth, depth, unknown_depth),
+                alt.explain_internal_inner(hints, indent_depth, depth, unknown_depth)
+            ),
+            JsValue::Binary(_, left, op, right) => format!(
+                "({} {} {})",
+                left.explain_internal_inner(hints, indent_depth, depth, unknown_depth),
+                op.as_str(),
+                right.explain_internal_inner(hints, indent_depth, depth, unknown_depth)
+            ),
         }
     }
 
@@ -1037,7 +1240,9 @@ impl JsValue {
             | JsValue::Object(..)
             | JsValue::Alternatives(..)
             | JsValue::Concat(..)
-            | JsValue::Add(..) => {
+            | JsValue::Add(..)
+            | JsValue::Tenary(..)
+            | JsValue::Binary(..) => {
                 let mut result = false;
                 self.for_each_children(&mut |child| {
                     result = result || child.has_placeholder();
@@ -1146,6 +1351,24 @@ macro_rules! for_each_children_async {
                 $value.update_total_nodes();
                 ($value, m1 || m2)
             }
+            JsValue::Tenary(_, box test, box cons, box alt) => {
+                let (v, m1) = $visit_fn(take(test), $($args),+).await?;
+                *test = v;
+                let (v, m2) = $visit_fn(take(cons), $($args),+).await?;
+                *cons = v;
+                let (v, m3) = $visit_fn(take(alt), $($args),+).await?;
+                *alt = v;
+                $value.update_total_nodes();
+                ($value, m1 || m2 || m3)
+            }
+            JsValue::Binary(_, box left, _, box right) => {
+                let (v, m1) = $visit_fn(take(left), $($args),+).await?;
+                *left = v;
+                let (v, m2) = $visit_fn(take(right), $($args),+).await?;
+                *right = v;
+                $value.update_total_nodes();
+                ($value, m1 || m2)
+            }
             JsValue::Constant(_)
             | JsValue::FreeVar(_)
             | JsValue::Variable(_)
@@ -1360,6 +1583,19 @@ impl JsValue {
                 self.update_total_nodes();
                 m1 || m2
             }
+            JsValue::Tenary(_, test, cons, alt) => {
+                let m1 = visitor(test);
+                let m2 = visitor(cons);
+                let m3 = visitor(alt);
+                self.update_total_nodes();
+                m1 || m2 || m3
+            }
+            JsValue::Binary(_, left, _, right) => {
+                let m1 = visitor(left);
+                let m2 = visitor(right);
+                self.update_total_nodes();
+                m1 || m2
+            }
             JsValue::Constant(_)
             | JsValue::FreeVar(_)
             | JsValue::Variable(_)
@@ -1420,6 +1656,15 @@ impl JsValue {
                 visitor(obj);
                 visitor(prop);
             }
+            JsValue::Tenary(_, test, cons, alt) => {
+                visitor(test);
+                visitor(cons);
+                visitor(alt);
+            }
+            JsValue::Binary(_, left, _, right) => {
+                visitor(left);
+                visitor(right);
+            }
             JsValue::Constant(_)
             | JsValue::FreeVar(_)
             | JsValue::Variable(_)
@@ -1458,6 +1703,8 @@ impl JsValue {
             JsValue::Add(_, v) => v.iter().any(|v| v.is_string()),
 
             JsValue::Alternatives(_, v) => v.iter().all(|v| v.is_string()),
+            JsValue::Tenary(_, _, cons, alt) => cons.is_string() && alt.is_string(),
+            JsValue::Binary(..) => false,
 
             JsValue::Variable(_) | JsValue::Unknown(..) | JsValue::Argument(..) => false,
 
@@ -1746,6 +1993,15 @@ impl JsValue {
                 lc == rc && l.similar(r, depth - 1)
             }
             (JsValue::Argument(l), JsValue::Argument(r)) => l == r,
+            (JsValue::Tenary(lc, lt, ll, lr), JsValue::Tenary(rc, rt, rl, rr)) => {
+                lc == rc
+                    && lt.similar(rt, depth - 1)
+                    && ll.similar(rl, depth - 1)
+                    && lr.similar(rr, depth - 1)
+            }
+            (JsValue::Binary(lc, ll, lo, lr), JsValue::Binary(rc, rl, ro, rr)) => {
+                lc == rc && lo == ro && ll.similar(rl, depth - 1) && lr.similar(rr, depth - 1)
+            }
             _ => false,
         }
     }
@@ -1814,6 +2070,16 @@ impl JsValue {
             JsValue::Unknown(_, v) => Hash::hash(v, state),
             JsValue::Function(_, v) => v.similar_hash(state, depth - 1),
             JsValue::Argument(v) => Hash::hash(v, state),
+            JsValue::Tenary(_, t, l, r) => {
+                t.similar_hash(state, depth - 1);
+                l.similar_hash(state, depth - 1);
+                r.similar_hash(state, depth - 1);
+            }
+            JsValue::Binary(_, l, o, r) => {
+                l.similar_hash(state, depth - 1);
+                Hash::hash(o, state);
+                r.similar_hash(state, depth - 1);
+            }
         }
     }
 }
@@ -1880,6 +2146,7 @@ pub enum WellKnownObjectKind {
     NodeExpressApp,
     NodeProtobufLoader,
     RequireCache,
+    ImportMeta,
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -1892,6 +2159,7 @@ pub enum WellKnownFunctionKind {
     Import,
     Require,
     RequireResolve,
+    ImportMetaResolve,
     Define,
     FsReadMethod(JsWord),
     PathToFileUrl,
@@ -1997,7 +2265,7 @@ mod tests {
     use super::{
         graph::{create_graph, EvalContext},
         linker::{link, LinkCache},
-        JsValue,
+        BinaryOperator, ConstantValue, FreeVarKind, JsValue,
     };
 
     #[fixture("tests/analyzer/graph/**/input.js")]
@@ -2144,4 +2412,40 @@ mod tests {
         })
         .unwrap();
     }
+    #[test]
+    fn evaluates_comparisons() {
+        let platform = JsValue::from("linux");
+        assert_eq!(
+            BinaryOperator::StrictEqual.apply(&platform, &"win32".into()),
+            Some(false)
+        );
+        assert_eq!(
+            BinaryOperator::StrictNotEqual.apply(&platform, &"win32".into()),
+            Some(true)
+        );
+        assert_eq!(
+            BinaryOperator::Equal.apply(
+                &ConstantValue::Null.into(),
+                &JsValue::FreeVar(FreeVarKind::Other("undefined".into()))
+            ),
+            Some(true)
+        );
+        assert_eq!(BinaryOperator::Equal.apply(&1f64.into(), &"1".into()), None);
+        assert_eq!(
+            BinaryOperator::StrictEqual.apply(&platform, &JsValue::Variable(Default::default())),
+            None
+        );
+    }
+
+    #[test]
+    fn evaluates_truthiness() {
+        assert_eq!(JsValue::from("").is_truthy(), Some(false));
+        assert_eq!(JsValue::from(0f64).is_truthy(), Some(false));
+        assert_eq!(JsValue::array(vec![]).is_truthy(), Some(true));
+        assert_eq!(
+            JsValue::concat(vec!["./".into(), JsValue::Unknown(None, "")]).is_truthy(),
+            Some(true)
+        );
+        assert_eq!(JsValue::Unknown(None, "").is_truthy(), None);
+    }
 }
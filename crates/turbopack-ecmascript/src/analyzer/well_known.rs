@@ -405,6 +405,7 @@ pub async fn well_known_object_member(
         WellKnownObjectKind::NodePreGyp => node_pre_gyp(prop),
         WellKnownObjectKind::NodeExpressApp => express(prop),
         WellKnownObjectKind::NodeProtobufLoader => protobuf_loader(prop),
+        WellKnownObjectKind::ImportMeta => import_meta_member(prop),
         #[allow(unreachable_patterns)]
         _ => JsValue::Unknown(
             Some(Arc::new(JsValue::member(
@@ -416,6 +417,19 @@ pub async fn well_known_object_member(
     })
 }
 
+fn import_meta_member(prop: JsValue) -> JsValue {
+    match prop.as_str() {
+        Some("resolve") => JsValue::WellKnownFunction(WellKnownFunctionKind::ImportMetaResolve),
+        _ => JsValue::Unknown(
+            Some(Arc::new(JsValue::member(
+                box JsValue::WellKnownObject(WellKnownObjectKind::ImportMeta),
+                box prop,
+            ))),
+            "unsupported property on import.meta",
+        ),
+    }
+}
+
 fn global_object(prop: JsValue) -> JsValue {
     match prop.as_str() {
         Some("assign") => JsValue::WellKnownFunction(WellKnownFunctionKind::ObjectAssign),
@@ -105,7 +105,8 @@ impl EcmascriptChunkItem for ManifestLoaderItem {
         let item_id = &*self.manifest.as_chunk_item(self.context).id().await?;
 
         // Finally, we need the id of the module that we're actually trying to
-        // dynamically import.
+        // dynamically import. When it's an async module, the import resolves once
+        // its evaluation completed.
         let placeable = EcmascriptChunkPlaceableVc::resolve_from(asset)
             .await?
             .ok_or_else(|| anyhow!("asset is not placeable in ecmascript chunk"))?;
@@ -118,7 +119,8 @@ impl EcmascriptChunkItem for ManifestLoaderItem {
 __turbopack_export_value__((__turbopack_import__) => {{
     return __turbopack_load__({chunk_server_path}).then(() => {{
         return __turbopack_require__({item_id});
-    }}).then(() => __turbopack_import__({dynamic_id}));
+    }}).then(() => __turbopack_import__({dynamic_id})).then((ns) => \
+             __turbopack_cache__[{dynamic_id}].async ?? ns);
 }});",
             chunk_server_path = stringify_str(chunk_server_path),
             item_id = stringify_module_id(item_id),
@@ -1,5 +1,6 @@
 pub mod loader;
 pub(crate) mod optimize;
+pub mod runtime;
 pub mod source_map;
 
 use std::{fmt::Write, io::Write as _, slice::Iter};
@@ -12,9 +13,7 @@ use turbo_tasks::{
     trace::TraceRawVcs,
     TryJoinIterExt, ValueToString, ValueToStringVc,
 };
-use turbo_tasks_fs::{
-    embed_file, rope::Rope, File, FileContent, FileSystemPathOptionVc, FileSystemPathVc,
-};
+use turbo_tasks_fs::{rope::Rope, File, FileSystemPathOptionVc, FileSystemPathVc};
 use turbo_tasks_hash::{encode_hex, hash_xxh3_hash64, Xxh3Hash64Hasher};
 use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc},
@@ -41,6 +40,7 @@ use turbopack_core::{
 use self::{
     loader::{ManifestChunkAssetVc, ManifestLoaderItemVc},
     optimize::EcmascriptChunkOptimizerVc,
+    runtime::EcmascriptChunkRuntimeVc,
     source_map::EcmascriptChunkSourceMapAssetReferenceVc,
 };
 use crate::{
@@ -54,6 +54,9 @@ pub struct EcmascriptChunk {
     context: ChunkingContextVc,
     main_entries: EcmascriptChunkPlaceablesVc,
     omit_entries: Option<EcmascriptChunkPlaceablesVc>,
+    /// Only chunk items which are also reachable from these entries are
+    /// included, so the chunk contains what two chunks have in common.
+    shared_entries: Option<EcmascriptChunkPlaceablesVc>,
     evaluate: Option<EcmascriptChunkEvaluateVc>,
 }
 
@@ -70,11 +73,30 @@ impl EcmascriptChunkVc {
             context,
             main_entries,
             omit_entries,
+            shared_entries: None,
             evaluate,
         }
         .cell()
     }
 
+    /// Creates a chunk with the chunk items which the chunks of `main_entries`
+    /// and `shared_entries` have in common.
+    #[turbo_tasks::function]
+    fn new_shared(
+        context: ChunkingContextVc,
+        main_entries: EcmascriptChunkPlaceablesVc,
+        shared_entries: EcmascriptChunkPlaceablesVc,
+    ) -> Self {
+        EcmascriptChunk {
+            context,
+            main_entries,
+            omit_entries: None,
+            shared_entries: Some(shared_entries),
+            evaluate: None,
+        }
+        .cell()
+    }
+
     #[turbo_tasks::function]
     pub fn new(context: ChunkingContextVc, main_entry: EcmascriptChunkPlaceableVc) -> Self {
         Self::new_normalized(
@@ -143,8 +165,10 @@ impl EcmascriptChunkVc {
         let a = left.await?;
         let b = right.await?;
 
-        let a = ecmascript_chunk_content(a.context, a.main_entries, a.omit_entries);
-        let b = ecmascript_chunk_content(b.context, b.main_entries, b.omit_entries);
+        let a =
+            ecmascript_chunk_content(a.context, a.main_entries, a.omit_entries, a.shared_entries);
+        let b =
+            ecmascript_chunk_content(b.context, b.main_entries, b.omit_entries, b.shared_entries);
 
         let a = a.await?.chunk_items.to_set();
         let b = b.await?.chunk_items.to_set();
@@ -179,7 +203,7 @@ pub struct EcmascriptChunkComparison {
     right_chunk_items: usize,
 }
 
-/// Whether the ES chunk should include and evaluate a runtime.
+/// Whether the ES chunk should load the runtime chunk and evaluate entries.
 #[turbo_tasks::value]
 pub struct EcmascriptChunkEvaluate {
     /// Entries that will be executed in that order only all chunks are ready.
@@ -241,10 +265,59 @@ pub struct EcmascriptChunkContentResult {
 
 #[turbo_tasks::value_impl]
 impl EcmascriptChunkContentResultVc {
+    /// Removes everything which is also part of `other`.
     #[turbo_tasks::function]
-    fn filter(self, _other: EcmascriptChunkContentResultVc) -> EcmascriptChunkContentResultVc {
-        todo!()
+    async fn filter(self, other: EcmascriptChunkContentResultVc) -> Result<Self> {
+        retain_content(self, other, false).await
     }
+
+    /// Keeps only what this content has in common with `other`.
+    #[turbo_tasks::function]
+    async fn intersect(self, other: EcmascriptChunkContentResultVc) -> Result<Self> {
+        retain_content(self, other, true).await
+    }
+}
+
+/// Keeps the parts of `content` which are also part of `other` when `shared`
+/// is true, or the parts which are not otherwise.
+async fn retain_content(
+    content: EcmascriptChunkContentResultVc,
+    other: EcmascriptChunkContentResultVc,
+    shared: bool,
+) -> Result<EcmascriptChunkContentResultVc> {
+    fn retain<T: PartialEq + Copy>(list: &[T], other: &[T], shared: bool) -> Vec<T> {
+        list.iter()
+            .filter(|item| other.contains(item) == shared)
+            .copied()
+            .collect()
+    }
+
+    let content = content.await?;
+    let other = other.await?;
+    let other_chunk_items = other.chunk_items.to_set().await?;
+    let chunk_items = content
+        .chunk_items
+        .to_set()
+        .await?
+        .iter()
+        .filter(|item| other_chunk_items.contains(*item) == shared)
+        .copied()
+        .collect::<Vec<_>>();
+    Ok(EcmascriptChunkContentResult {
+        chunk_items: EcmascriptChunkItemsVc::cell(EcmascriptChunkItems::make_chunks(&chunk_items)),
+        chunks: retain(&content.chunks, &other.chunks, shared),
+        async_chunk_groups: retain(
+            &content.async_chunk_groups,
+            &other.async_chunk_groups,
+            shared,
+        ),
+        external_asset_references: retain(
+            &content.external_asset_references,
+            &other.external_asset_references,
+            shared,
+        ),
+    }
+    .cell())
 }
 
 impl From<ChunkContentResult<EcmascriptChunkItemVc>> for EcmascriptChunkContentResult {
@@ -264,8 +337,13 @@ fn ecmascript_chunk_content(
     context: ChunkingContextVc,
     main_entries: EcmascriptChunkPlaceablesVc,
     omit_entries: Option<EcmascriptChunkPlaceablesVc>,
+    shared_entries: Option<EcmascriptChunkPlaceablesVc>,
 ) -> EcmascriptChunkContentResultVc {
     let mut chunk_content = ecmascript_chunk_content_internal(context, main_entries);
+    if let Some(shared_entries) = shared_entries {
+        let shared_chunk_content = ecmascript_chunk_content_internal(context, shared_entries);
+        chunk_content = chunk_content.intersect(shared_chunk_content);
+    }
     if let Some(omit_entries) = omit_entries {
         let omit_chunk_content = ecmascript_chunk_content_internal(context, omit_entries);
         chunk_content = chunk_content.filter(omit_chunk_content);
@@ -438,12 +516,14 @@ impl EcmascriptChunkContentVc {
         context: ChunkingContextVc,
         main_entries: EcmascriptChunkPlaceablesVc,
         omit_entries: Option<EcmascriptChunkPlaceablesVc>,
+        shared_entries: Option<EcmascriptChunkPlaceablesVc>,
         chunk_path: FileSystemPathVc,
         evaluate: Option<EcmascriptChunkContentEvaluateVc>,
     ) -> Result<Self> {
         // TODO(alexkirsz) All of this should be done in a transition, otherwise we run
         // the risks of values not being strongly consistent with each other.
-        let chunk_content = ecmascript_chunk_content(context, main_entries, omit_entries);
+        let chunk_content =
+            ecmascript_chunk_content(context, main_entries, omit_entries, shared_entries);
         let chunk_content = chunk_content.await?;
         let module_factories = chunk_content.chunk_items.to_entry_snapshot().await?;
         let output_root = context.output_root();
@@ -500,6 +580,9 @@ impl EcmascriptChunkContentEntryVc {
     }
 }
 
+/// The module factory of a chunk item is stored in its own cell, so a change
+/// to a single chunk item only regenerates its factory and the chunk is
+/// assembled from the cached factories of the other chunk items.
 #[turbo_tasks::function]
 async fn module_factory(content: EcmascriptChunkItemContentVc) -> Result<CodeVc> {
     let content = content.await?;
@@ -522,6 +605,9 @@ async fn module_factory(content: EcmascriptChunkItemContentVc) -> Result<CodeVc>
     if content.options.exports {
         args.push("e: exports");
     }
+    if content.options.async_module {
+        args.push("a: __turbopack_async_module__");
+    }
     let mut code = CodeBuilder::default();
     let args = FormatIter(|| args.iter().copied().intersperse(", "));
     if content.options.this {
@@ -624,13 +710,6 @@ impl EcmascriptChunkContentVc {
             )?;
         }
         code += "]);\n";
-        if this.evaluate.is_some() {
-            let runtime_code = embed_file!("js/src/runtime.js").await?;
-            match &*runtime_code {
-                FileContent::NotFound => return Err(anyhow!("runtime code is not found")),
-                FileContent::Content(file) => code.push_source(file.content(), None),
-            };
-        }
 
         if code.has_source_map() {
             let filename = chunk_path.file_name();
@@ -838,9 +917,16 @@ impl ValueToString for EcmascriptChunk {
         let entry_strs = || entry_strings.iter().map(|s| s.as_str()).intersperse(" + ");
         let omit_entry_strings = entries_to_string(self.omit_entries).await?;
         let omit_entry_strs = || omit_entry_strings.iter().flat_map(|s| [" - ", s.as_str()]);
+        let shared_entry_strings = entries_to_string(self.shared_entries).await?;
+        let shared_entry_strs = || {
+            shared_entry_strings
+                .iter()
+                .flat_map(|s| [" & ", s.as_str()])
+        };
         Ok(StringVc::cell(format!(
-            "chunk {}{}{}",
+            "chunk {}{}{}{}",
             FormatIter(entry_strs),
+            FormatIter(shared_entry_strs),
             FormatIter(omit_entry_strs),
             suffix
         )))
@@ -856,6 +942,7 @@ impl EcmascriptChunkVc {
             this.context,
             this.main_entries,
             this.omit_entries,
+            this.shared_entries,
         ))
     }
 
@@ -886,6 +973,7 @@ impl EcmascriptChunkVc {
             this.context,
             this.main_entries,
             this.omit_entries,
+            this.shared_entries,
             chunk_path,
             evaluate,
         );
@@ -918,6 +1006,16 @@ impl Asset for EcmascriptChunk {
                 need_hash = true;
             }
         }
+        // Omitted and shared entries only contribute to the hashed info too
+        for (marker, entries) in [("omit", this.omit_entries), ("shared", this.shared_entries)] {
+            if let Some(entries) = entries {
+                hasher.write_value(marker.as_bytes());
+                for entry in entries.await?.iter() {
+                    hasher.write_value(entry.path().to_string().await?);
+                }
+                need_hash = true;
+            }
+        }
         let main_entries = this.main_entries.await?;
         // If there is only a single entry we can used that for the named info.
         // If there are multiple entries we hash them and use the common parent as named
@@ -960,8 +1058,13 @@ impl Asset for EcmascriptChunk {
     #[turbo_tasks::function]
     async fn references(self_vc: EcmascriptChunkVc) -> Result<AssetReferencesVc> {
         let this = self_vc.await?;
-        let content =
-            ecmascript_chunk_content(this.context, this.main_entries, this.omit_entries).await?;
+        let content = ecmascript_chunk_content(
+            this.context,
+            this.main_entries,
+            this.omit_entries,
+            this.shared_entries,
+        )
+        .await?;
         let mut references = Vec::new();
         for r in content.external_asset_references.iter() {
             references.push(*r);
@@ -972,6 +1075,12 @@ impl Asset for EcmascriptChunk {
         for chunk_group in content.async_chunk_groups.iter() {
             references.push(ChunkGroupReferenceVc::new(*chunk_group).into());
         }
+        if this.evaluate.is_some() {
+            references.push(
+                ChunkReferenceVc::new_parallel(EcmascriptChunkRuntimeVc::new(this.context).into())
+                    .into(),
+            );
+        }
 
         references.push(
             EcmascriptChunkSourceMapAssetReferenceVc::new(
@@ -1017,8 +1126,13 @@ impl Introspectable for EcmascriptChunk {
         let content = content_to_details(self_vc.content());
         let mut details = String::new();
         let this = self_vc.await?;
-        let chunk_content =
-            ecmascript_chunk_content(this.context, this.main_entries, this.omit_entries).await?;
+        let chunk_content = ecmascript_chunk_content(
+            this.context,
+            this.main_entries,
+            this.omit_entries,
+            this.shared_entries,
+        )
+        .await?;
         let chunk_items = chunk_content.chunk_items.await?;
         details += "Chunk items:\n\n";
         for chunk in chunk_items.iter() {
@@ -1081,7 +1195,7 @@ impl EcmascriptChunkContextVc {
                 write!(s, " ({layer})")?;
             }
         }
-        Ok(ModuleId::String(s).cell())
+        Ok(ModuleId::String(s.into()).cell())
     }
 }
 
@@ -1124,6 +1238,8 @@ pub struct EcmascriptChunkItemOptions {
     pub module: bool,
     pub exports: bool,
     pub this: bool,
+    /// Whether the module uses top-level await or imports such a module.
+    pub async_module: bool,
     pub placeholder_for_future_extensions: (),
 }
 
@@ -1165,7 +1281,7 @@ pub struct EcmascriptChunkItems(Vec<EcmascriptChunkItemsChunkVc>);
 
 impl EcmascriptChunkItems {
     pub fn make_chunks(list: &[EcmascriptChunkItemVc]) -> Vec<EcmascriptChunkItemsChunkVc> {
-        let size = list.len().div_ceil(100);
+        let size = list.len().div_ceil(100).max(1);
         let chunk_items = list
             .chunks(size)
             .map(|chunk| EcmascriptChunkItemsChunkVc::cell(chunk.to_vec()))
@@ -6,7 +6,7 @@ use turbo_tasks::TryJoinIterExt;
 use turbo_tasks_fs::FileSystemPathOptionVc;
 use turbopack_core::chunk::{
     optimize::{optimize_by_common_parent, ChunkOptimizer, ChunkOptimizerVc},
-    ChunkGroupVc, ChunkVc, ChunkingContextVc, ChunksVc,
+    ChunkGroupVc, ChunkVc, ChunkingContext, ChunkingContextVc, ChunksVc,
 };
 
 use super::{EcmascriptChunkPlaceablesVc, EcmascriptChunkVc};
@@ -27,8 +27,9 @@ impl EcmascriptChunkOptimizerVc {
 impl ChunkOptimizer for EcmascriptChunkOptimizer {
     #[turbo_tasks::function]
     async fn optimize(&self, chunks: ChunksVc, chunk_group: ChunkGroupVc) -> Result<ChunksVc> {
+        let context = self.0;
         optimize_by_common_parent(chunks, get_common_parent, |local, children| {
-            optimize_ecmascript(local, children, chunk_group)
+            optimize_ecmascript(local, children, chunk_group, context)
         })
         .await
     }
@@ -87,10 +88,14 @@ const TOTAL_CHUNK_MERGE_THRESHOLD: usize = 20;
 /// Max number of chunk items per chunk to merge.
 const MAX_CHUNK_ITEMS_PER_CHUNK: usize = 3000;
 
+/// Merges chunks with a lot of duplication and returns the new number of
+/// unoptimized chunks. Chunks which share at least `min_shared_chunk_items`
+/// chunk items are not merged, as these are extracted instead.
 async fn merge_duplicated_and_contained(
     chunks: &mut Vec<EcmascriptChunkVc>,
     mut unoptimized_count: usize,
-) -> Result<()> {
+    min_shared_chunk_items: Option<usize>,
+) -> Result<usize> {
     struct Comparison {
         /// Index of chunk in the `chunks` vec
         index: usize,
@@ -225,6 +230,7 @@ async fn merge_duplicated_and_contained(
                 &Comparison {
                     index: j,
                     other,
+                    shared,
                     right,
                     ..
                 },
@@ -234,8 +240,11 @@ async fn merge_duplicated_and_contained(
                 .map(|cmp| (cmp, cmp.duplication_factor()))
                 .max_by_key(|&(_, f)| FloatOrd(f))
             {
-                // Merge when there is a lot of duplication
-                if duplication_factor > DUPLICATION_THRESHOLD {
+                // Merge when there is a lot of duplication, unless the shared chunk items
+                // are extracted into a chunk of their own
+                if duplication_factor > DUPLICATION_THRESHOLD
+                    && !min_shared_chunk_items.map_or(false, |min| shared >= min)
+                {
                     merge(j, other, Some((right, &mut comparisons)));
                     // Continue looking for more candidates
                     continue;
@@ -266,6 +275,81 @@ async fn merge_duplicated_and_contained(
             i += 1;
         }
     }
+    Ok(unoptimized_count)
+}
+
+/// Whether the chunk items a chunk shares with another chunk can be moved
+/// into a chunk of their own.
+async fn is_extractable(chunk: EcmascriptChunkVc) -> Result<bool> {
+    let chunk = chunk.await?;
+    // The chunk items of split chunks are no longer described by their entries
+    // alone
+    if chunk.omit_entries.is_some() || chunk.shared_entries.is_some() {
+        return Ok(false);
+    }
+    // Evaluated chunks need to wait for the shared chunk, which they only do
+    // when they wait for the whole chunk group
+    Ok(match chunk.evaluate {
+        Some(evaluate) => evaluate.await?.chunk_group.is_some(),
+        None => true,
+    })
+}
+
+/// Moves the chunk items which two chunks have in common into a shared chunk,
+/// when there are at least `min_shared_chunk_items` of them. Like for merging,
+/// unoptimized chunks are compared with the following chunks.
+async fn extract_shared_chunk_items(
+    chunks: &mut Vec<EcmascriptChunkVc>,
+    unoptimized_count: usize,
+    min_shared_chunk_items: usize,
+) -> Result<()> {
+    let mut extractable = chunks
+        .iter()
+        .map(|&chunk| is_extractable(chunk))
+        .try_join()
+        .await?;
+    let mut shared_chunks = Vec::new();
+    for i in 0..unoptimized_count {
+        if !extractable[i] {
+            continue;
+        }
+        for j in (i + 1..chunks.len()).take(COMPARE_WITH_COUNT) {
+            if !extractable[j] {
+                continue;
+            }
+            let compare = EcmascriptChunkVc::compare(chunks[i], chunks[j]).await?;
+            // Chunks which are contained in the other chunk are merged instead
+            if compare.shared_chunk_items < min_shared_chunk_items.max(1)
+                || compare.left_chunk_items == 0
+                || compare.right_chunk_items == 0
+            {
+                continue;
+            }
+            let left = chunks[i].await?;
+            let right = chunks[j].await?;
+            shared_chunks.push(EcmascriptChunkVc::new_shared(
+                left.context,
+                left.main_entries,
+                right.main_entries,
+            ));
+            chunks[i] = EcmascriptChunkVc::new_normalized(
+                left.context,
+                left.main_entries,
+                Some(right.main_entries),
+                left.evaluate,
+            );
+            chunks[j] = EcmascriptChunkVc::new_normalized(
+                right.context,
+                right.main_entries,
+                Some(left.main_entries),
+                right.evaluate,
+            );
+            extractable[i] = false;
+            extractable[j] = false;
+            break;
+        }
+    }
+    chunks.append(&mut shared_chunks);
     Ok(())
 }
 
@@ -311,7 +395,9 @@ async fn optimize_ecmascript(
     local: Option<ChunksVc>,
     children: Option<ChunksVc>,
     chunk_group: ChunkGroupVc,
+    context: ChunkingContextVc,
 ) -> Result<ChunksVc> {
+    let config = context.chunking_config().await?;
     let mut chunks = Vec::new();
     // TODO optimize
     let mut unoptimized_count = 0;
@@ -351,9 +437,19 @@ async fn optimize_ecmascript(
 
     // Merge chunks that have a lot duplication between them. children will never
     // have duplication, but there might be duplication within local chunks or
-    // between local chunks and children.
+    // between local chunks and children. When configured, chunk items shared
+    // by two chunks are extracted into a shared chunk instead.
     if unoptimized_count > 0 && chunks.len() > 1 {
-        merge_duplicated_and_contained(&mut chunks, unoptimized_count).await?;
+        let unoptimized_count = merge_duplicated_and_contained(
+            &mut chunks,
+            unoptimized_count,
+            config.min_shared_chunk_items,
+        )
+        .await?;
+        if let Some(min_shared_chunk_items) = config.min_shared_chunk_items {
+            extract_shared_chunk_items(&mut chunks, unoptimized_count, min_shared_chunk_items)
+                .await?;
+        }
     }
 
     // If chunks share chunk items they might be removed from one of them. The
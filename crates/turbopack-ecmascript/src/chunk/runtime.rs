@@ -0,0 +1,60 @@
+use anyhow::{anyhow, Result};
+use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks_fs::{embed_file, File, FileContent, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetContentVc, AssetVc},
+    chunk::{Chunk, ChunkVc, ChunkingContextVc},
+    reference::AssetReferencesVc,
+};
+
+/// The chunk containing the runtime, i. e. the module registry, chunk loading
+/// and HMR handling.
+///
+/// It's referenced by all evaluated chunks of a chunking context, so it is
+/// emitted once and shared between all pages instead of being embedded into
+/// every evaluated chunk.
+#[turbo_tasks::value]
+pub struct EcmascriptChunkRuntime {
+    context: ChunkingContextVc,
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptChunkRuntimeVc {
+    #[turbo_tasks::function]
+    pub fn new(context: ChunkingContextVc) -> Self {
+        EcmascriptChunkRuntime { context }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for EcmascriptChunkRuntime {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.context.runtime_chunk_path()
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let runtime_code = embed_file!("js/src/runtime.js").await?;
+        match &*runtime_code {
+            FileContent::NotFound => Err(anyhow!("runtime code is not found")),
+            FileContent::Content(file) => Ok(File::from(file.content().clone()).into()),
+        }
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Chunk for EcmascriptChunkRuntime {}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for EcmascriptChunkRuntime {
+    #[turbo_tasks::function]
+    fn to_string(&self) -> StringVc {
+        StringVc::cell("runtime chunk".to_string())
+    }
+}
@@ -0,0 +1,59 @@
+use anyhow::Result;
+use turbo_tasks::primitives::StringVc;
+use turbopack_core::{
+    asset::{Asset, AssetVc},
+    issue::{analyze::AnalyzeIssue, IssueSeverity},
+    resolve::{origin::ResolveOriginVc, ResolveResultVc},
+};
+
+use crate::{chunk::EcmascriptChunkPlaceableVc, errors};
+
+/// An asset which can be imported as a CSS module script, i. e. with
+/// `assert { type: "css" }`. It's implemented by the CSS assets, as this
+/// crate doesn't know how to process CSS.
+#[turbo_tasks::value_trait]
+pub trait CssModuleScriptable: Asset {
+    /// The module script of the stylesheet. Its default export is a
+    /// constructable `CSSStyleSheet`, and unlike other imported CSS it isn't
+    /// applied to the document.
+    fn as_css_module_script(&self) -> EcmascriptChunkPlaceableVc;
+}
+
+/// Replaces the resolved assets of an import with `assert { type: "css" }`
+/// by their CSS module scripts. Assets which are no stylesheets are reported,
+/// like browsers would refuse to load them.
+#[turbo_tasks::function]
+pub(crate) async fn css_module_script_resolve(
+    origin: ResolveOriginVc,
+    result: ResolveResultVc,
+) -> Result<ResolveResultVc> {
+    Ok(result
+        .await?
+        .map(
+            |asset| async move {
+                if let Some(stylesheet) = CssModuleScriptableVc::resolve_from(asset).await? {
+                    return Ok(stylesheet.as_css_module_script().into());
+                }
+                AnalyzeIssue {
+                    code: Some(errors::failed_to_analyse::ecmascript::IMPORT_ASSERTION.to_string()),
+                    category: StringVc::cell("analyze".to_string()),
+                    message: StringVc::cell(format!(
+                        "{} is imported with `assert {{ type: \"css\" }}`, but it's not a \
+                         stylesheet",
+                        asset.path().to_string().await?
+                    )),
+                    path: origin.origin_path(),
+                    severity: IssueSeverity::Error.into(),
+                    source: None,
+                    title: StringVc::cell("unexpected module type".to_string()),
+                }
+                .cell()
+                .as_issue()
+                .emit();
+                Ok(asset)
+            },
+            |reference| async move { Ok(reference) },
+        )
+        .await?
+        .into())
+}
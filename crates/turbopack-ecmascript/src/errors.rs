@@ -14,5 +14,6 @@ pub mod failed_to_analyse {
         pub const NODE_PROTOBUF_LOADER: &str = "TP1105";
         pub const AMD_DEFINE: &str = "TP1200";
         pub const IMPORT_META: &str = "TP1106";
+        pub const IMPORT_ASSERTION: &str = "TP1107";
     }
 }
@@ -10,8 +10,10 @@ pub mod analyzer;
 pub mod chunk;
 pub mod chunk_group_files_asset;
 pub mod code_gen;
+pub mod css_module_script;
 mod errors;
 pub mod magic_identifier;
+pub mod native_addon;
 pub(crate) mod parse;
 mod path_visitor;
 pub(crate) mod references;
@@ -22,12 +24,14 @@ pub mod typescript;
 pub mod utils;
 pub mod webpack;
 
+use std::collections::{HashMap, HashSet};
+
 use anyhow::Result;
 use chunk::{
     EcmascriptChunkItem, EcmascriptChunkItemVc, EcmascriptChunkPlaceablesVc, EcmascriptChunkVc,
 };
 use code_gen::CodeGenerateableVc;
-use parse::{parse, ParseResult, ParseResultSourceMap};
+pub use parse::{parse, ParseResult, ParseResultSourceMap, ParseResultSourceMapVc, ParseResultVc};
 use path_visitor::ApplyVisitors;
 use references::AnalyzeEcmascriptModuleResult;
 use swc_core::{
@@ -37,8 +41,15 @@ use swc_core::{
         visit::{VisitMutWith, VisitMutWithPath},
     },
 };
-pub use transform::{EcmascriptInputTransform, EcmascriptInputTransformsVc};
-use turbo_tasks::{primitives::StringVc, TryJoinIterExt, Value, ValueToString, ValueToStringVc};
+pub use transform::{
+    CustomTransform, CustomTransformVc, CustomTransformer, EcmascriptInputTransform,
+    EcmascriptInputTransformsVc, EmotionTransformConfig, EmotionTransformConfigVc,
+    StyledComponentsTransformConfig, StyledComponentsTransformConfigVc, TransformContext,
+};
+use turbo_tasks::{
+    primitives::{BoolVc, StringVc},
+    TryJoinIterExt, Value, ValueToString, ValueToStringVc,
+};
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
     asset::{Asset, AssetContentVc, AssetVc},
@@ -54,11 +65,14 @@ use self::{
         EcmascriptChunkItemContent, EcmascriptChunkItemContentVc, EcmascriptChunkItemOptions,
         EcmascriptExportsVc,
     },
-    references::AnalyzeEcmascriptModuleResultVc,
+    references::{
+        async_module::wrap_async_module, esm::EsmAssetReferenceVc, AnalyzeEcmascriptModuleResultVc,
+    },
 };
 use crate::{
     chunk::{EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc},
     references::analyze_ecmascript_module,
+    utils::stringify_str,
 };
 
 #[turbo_tasks::value(serialization = "auto_for_input")]
@@ -79,6 +93,9 @@ pub struct EcmascriptModuleAsset {
     pub environment: EnvironmentVc,
 }
 
+#[turbo_tasks::value(transparent)]
+pub struct EcmascriptModuleAssets(Vec<EcmascriptModuleAssetVc>);
+
 #[turbo_tasks::value_impl]
 impl EcmascriptModuleAssetVc {
     #[turbo_tasks::function]
@@ -118,6 +135,95 @@ impl EcmascriptModuleAssetVc {
             this.environment,
         ))
     }
+
+    /// Parses the module with its transforms. Analysis and code generation
+    /// share this result.
+    #[turbo_tasks::function]
+    pub async fn parse(self) -> Result<ParseResultVc> {
+        let this = self.await?;
+        Ok(parse(this.source, Value::new(this.ty), this.transforms))
+    }
+
+    /// The ecmascript modules which are statically imported by the module.
+    #[turbo_tasks::function]
+    pub async fn esm_imports(self) -> Result<EcmascriptModuleAssetsVc> {
+        let mut imports = Vec::new();
+        for reference in self.analyze().await?.references.await?.iter() {
+            if EsmAssetReferenceVc::resolve_from(reference)
+                .await?
+                .is_none()
+            {
+                continue;
+            }
+            for asset in reference.resolve_reference().primary_assets().await?.iter() {
+                if let Some(imported) = EcmascriptModuleAssetVc::resolve_from(asset).await? {
+                    imports.push(imported);
+                }
+            }
+        }
+        Ok(EcmascriptModuleAssetsVc::cell(imports))
+    }
+
+    /// The modules which are part of an import cycle with the module,
+    /// including the module itself.
+    #[turbo_tasks::function]
+    async fn import_cycle(self) -> Result<EcmascriptModuleAssetsVc> {
+        let mut imports = HashMap::new();
+        let mut queue = vec![self];
+        while let Some(module) = queue.pop() {
+            if imports.contains_key(&module) {
+                continue;
+            }
+            let module_imports = module.esm_imports().await?;
+            queue.extend(module_imports.iter().copied());
+            imports.insert(module, module_imports);
+        }
+
+        let mut importers: HashMap<_, Vec<_>> = HashMap::new();
+        for (&module, module_imports) in imports.iter() {
+            for &imported in module_imports.iter() {
+                importers.entry(imported).or_default().push(module);
+            }
+        }
+        let mut cycle = vec![self];
+        let mut visited = HashSet::from([self]);
+        let mut queue = vec![self];
+        while let Some(module) = queue.pop() {
+            for &importer in importers.get(&module).into_iter().flatten() {
+                if visited.insert(importer) {
+                    cycle.push(importer);
+                    queue.push(importer);
+                }
+            }
+        }
+        Ok(EcmascriptModuleAssetsVc::cell(cycle))
+    }
+
+    /// Whether the module has to be evaluated asynchronously, because it uses
+    /// top-level await or statically imports a module which does.
+    #[turbo_tasks::function]
+    pub async fn is_async_module(self) -> Result<BoolVc> {
+        if self.analyze().await?.has_top_level_await {
+            return Ok(BoolVc::cell(true));
+        }
+        // All modules of an import cycle are async when one of them is. They
+        // are checked together, as asking each other would never finish.
+        let cycle = self.import_cycle().await?;
+        for module in cycle.iter() {
+            if module.analyze().await?.has_top_level_await {
+                return Ok(BoolVc::cell(true));
+            }
+        }
+        let members: HashSet<_> = cycle.iter().copied().collect();
+        for module in cycle.iter() {
+            for imported in module.esm_imports().await?.iter() {
+                if !members.contains(imported) && *imported.is_async_module().await? {
+                    return Ok(BoolVc::cell(true));
+                }
+            }
+        }
+        Ok(BoolVc::cell(false))
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -245,7 +351,7 @@ impl EcmascriptChunkItem for ModuleChunkItem {
         }
 
         let module = self.module.await?;
-        let parsed = parse(module.source, Value::new(module.ty), module.transforms).await?;
+        let parsed = self.module.parse().await?;
 
         if let ParseResult::Ok {
             program,
@@ -256,6 +362,7 @@ impl EcmascriptChunkItem for ModuleChunkItem {
         } = &*parsed
         {
             let mut program = program.clone();
+            let async_module = eval_context.is_esm() && *self.module.is_async_module().await?;
 
             GLOBALS.set(globals, || {
                 if !visitors.is_empty() {
@@ -267,6 +374,9 @@ impl EcmascriptChunkItem for ModuleChunkItem {
                 for visitor in root_visitors {
                     program.visit_mut_with(&mut visitor.create());
                 }
+                if async_module {
+                    wrap_async_module(&mut program);
+                }
                 program.visit_mut_with(&mut swc_core::ecma::transforms::base::fixer::fixer(None));
             });
 
@@ -287,13 +397,16 @@ impl EcmascriptChunkItem for ModuleChunkItem {
 
             emitter.emit_program(&program)?;
 
-            let srcmap = ParseResultSourceMap::new(source_map.clone(), srcmap).cell();
+            let srcmap = ParseResultSourceMap::new(source_map.clone(), srcmap)
+                .with_source(module.source)
+                .cell();
 
             Ok(EcmascriptChunkItemContent {
                 inner_code: bytes.into(),
                 source_map: Some(srcmap),
                 options: if eval_context.is_esm() {
                     EcmascriptChunkItemOptions {
+                        async_module,
                         ..Default::default()
                     }
                 } else {
@@ -311,9 +424,11 @@ impl EcmascriptChunkItem for ModuleChunkItem {
         } else {
             Ok(EcmascriptChunkItemContent {
                 inner_code: format!(
-                    "const e = new Error(\"Could not parse module '{path}'\");\ne.code = \
-                     'MODULE_UNPARSEABLE';\nthrow e;",
-                    path = self.module.path().to_string().await?
+                    "const e = new Error({message});\ne.code = 'MODULE_UNPARSEABLE';\nthrow e;",
+                    message = stringify_str(&format!(
+                        "Could not parse module '{path}'",
+                        path = self.module.path().to_string().await?
+                    ))
                 )
                 .into(),
                 ..Default::default()
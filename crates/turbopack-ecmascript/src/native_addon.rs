@@ -0,0 +1,207 @@
+//! Support for Node.js native addons (`.node` files).
+//!
+//! Native addons can't be bundled. They are copied next to the chunks of the
+//! chunking context and loaded with the native `require` of the chunk, which
+//! resolves the relative path at runtime.
+
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks_fs::FileSystemPathVc;
+use turbopack_core::{
+    asset::{Asset, AssetContentVc, AssetVc},
+    chunk::{ChunkItem, ChunkItemVc, ChunkVc, ChunkableAsset, ChunkableAssetVc, ChunkingContextVc},
+    reference::{AssetReference, AssetReferenceVc, AssetReferencesVc},
+    resolve::{ResolveResult, ResolveResultVc},
+};
+
+use crate::{
+    chunk::{
+        EcmascriptChunkItem, EcmascriptChunkItemContent, EcmascriptChunkItemContentVc,
+        EcmascriptChunkItemVc, EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc,
+        EcmascriptChunkVc, EcmascriptExports, EcmascriptExportsVc,
+    },
+    utils::stringify_str,
+};
+
+#[turbo_tasks::value]
+#[derive(Clone)]
+pub struct NativeAddonModuleAsset {
+    pub source: AssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl NativeAddonModuleAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(source: AssetVc) -> Self {
+        Self::cell(NativeAddonModuleAsset { source })
+    }
+
+    #[turbo_tasks::function]
+    async fn copy_asset(
+        self_vc: NativeAddonModuleAssetVc,
+        context: ChunkingContextVc,
+    ) -> Result<NativeAddonCopyAssetVc> {
+        Ok(NativeAddonCopyAssetVc::cell(NativeAddonCopyAsset {
+            context,
+            source: self_vc.await?.source,
+        }))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for NativeAddonModuleAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.source.path()
+    }
+
+    #[turbo_tasks::function]
+    fn content(&self) -> AssetContentVc {
+        self.source.content()
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableAsset for NativeAddonModuleAsset {
+    #[turbo_tasks::function]
+    fn as_chunk(self_vc: NativeAddonModuleAssetVc, context: ChunkingContextVc) -> ChunkVc {
+        EcmascriptChunkVc::new(context, self_vc.as_ecmascript_chunk_placeable()).into()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptChunkPlaceable for NativeAddonModuleAsset {
+    #[turbo_tasks::function]
+    fn as_chunk_item(
+        self_vc: NativeAddonModuleAssetVc,
+        context: ChunkingContextVc,
+    ) -> EcmascriptChunkItemVc {
+        NativeAddonChunkItemVc::cell(NativeAddonChunkItem {
+            module: self_vc,
+            context,
+            copy_asset: self_vc.copy_asset(context),
+        })
+        .into()
+    }
+
+    #[turbo_tasks::function]
+    fn get_exports(&self) -> EcmascriptExportsVc {
+        EcmascriptExports::Value.into()
+    }
+}
+
+/// The copy of a native addon in the chunk directory of a chunking context.
+#[turbo_tasks::value]
+struct NativeAddonCopyAsset {
+    context: ChunkingContextVc,
+    source: AssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for NativeAddonCopyAsset {
+    #[turbo_tasks::function]
+    fn path(&self) -> FileSystemPathVc {
+        self.context.chunk_path(self.source.path(), ".node")
+    }
+
+    #[turbo_tasks::function]
+    fn content(&self) -> AssetContentVc {
+        self.source.content()
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+/// A reference to an asset which is copied to the output as is, without
+/// being placed in a chunk.
+#[turbo_tasks::value]
+pub struct CopyAssetReference {
+    asset: AssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl CopyAssetReferenceVc {
+    #[turbo_tasks::function]
+    pub fn new(asset: AssetVc) -> Self {
+        Self::cell(CopyAssetReference { asset })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl AssetReference for CopyAssetReference {
+    #[turbo_tasks::function]
+    fn resolve_reference(&self) -> ResolveResultVc {
+        ResolveResult::Single(self.asset, Vec::new()).cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for CopyAssetReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "copy {}",
+            self.asset.path().to_string().await?
+        )))
+    }
+}
+
+#[turbo_tasks::value]
+struct NativeAddonChunkItem {
+    module: NativeAddonModuleAssetVc,
+    context: ChunkingContextVc,
+    copy_asset: NativeAddonCopyAssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for NativeAddonChunkItem {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "{} (native addon)",
+            self.module.await?.source.path().to_string().await?
+        )))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkItem for NativeAddonChunkItem {
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::cell(vec![
+            CopyAssetReferenceVc::new(self.copy_asset.into()).into()
+        ])
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptChunkItem for NativeAddonChunkItem {
+    #[turbo_tasks::function]
+    fn chunking_context(&self) -> ChunkingContextVc {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<EcmascriptChunkItemContentVc> {
+        // Chunks and the copied addon are placed in the same directory, so the
+        // native `require` of the chunk can load it by its file name.
+        let path = self.copy_asset.path().await?;
+        Ok(EcmascriptChunkItemContent {
+            inner_code: format!(
+                "__turbopack_export_value__(require({path}));",
+                path = stringify_str(&format!("./{}", path.file_name()))
+            )
+            .into(),
+            ..Default::default()
+        }
+        .into())
+    }
+}
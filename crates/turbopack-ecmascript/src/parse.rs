@@ -1,4 +1,4 @@
-use std::{future::Future, sync::Arc};
+use std::{future::Future, sync::Arc, time::Instant};
 
 use anyhow::Result;
 use swc_core::{
@@ -19,19 +19,26 @@ use swc_core::{
         visit::VisitMutWith,
     },
 };
-use turbo_tasks::{primitives::U64Vc, Value};
+use turbo_tasks::{
+    primitives::{StringVc, U64Vc},
+    TryJoinIterExt, Value,
+};
 use turbo_tasks_fs::{FileContent, FileSystemPath, FileSystemPathVc};
 use turbo_tasks_hash::{DeterministicHasher, Xxh3Hash64Hasher};
 use turbopack_core::{
     asset::{AssetContent, AssetVc},
-    source_map::{GenerateSourceMap, GenerateSourceMapVc, SourceMapVc},
+    issue::{analyze::AnalyzeIssue, IssueSeverity},
+    source_map::{
+        input_source_map, source_mapping_url_of_comment, GenerateSourceMap, GenerateSourceMapVc,
+        SourceMap as TurbopackSourceMap, SourceMapVc,
+    },
 };
 use turbopack_swc_utils::emitter::IssueEmitter;
 
 use super::EcmascriptModuleAssetType;
 use crate::{
     analyzer::graph::EvalContext,
-    transform::{EcmascriptInputTransformsVc, TransformContext},
+    transform::{EcmascriptInputTransforms, EcmascriptInputTransformsVc, TransformContext},
     utils::WrapFuture,
     EcmascriptInputTransform,
 };
@@ -76,11 +83,18 @@ pub struct ParseResultSourceMap {
     /// SourceMap.
     #[turbo_tasks(debug_ignore, trace_ignore)]
     mappings: Vec<(BytePos, LineCol)>,
+
+    /// The parsed source. When it was generated by another tool and has a
+    /// source map of its own, the generated map is chained through it, so it
+    /// points to the sources the other tool was given.
+    source: Option<AssetVc>,
 }
 
 impl PartialEq for ParseResultSourceMap {
     fn eq(&self, other: &Self) -> bool {
-        Arc::ptr_eq(&self.source_map, &other.source_map) && self.mappings == other.mappings
+        Arc::ptr_eq(&self.source_map, &other.source_map)
+            && self.mappings == other.mappings
+            && self.source == other.source
     }
 }
 
@@ -89,20 +103,42 @@ impl ParseResultSourceMap {
         ParseResultSourceMap {
             source_map,
             mappings,
+            source: None,
         }
     }
+
+    /// Chains the generated map through the source map of `source`, if it has
+    /// one. It's only read when the map is generated.
+    pub fn with_source(mut self, source: AssetVc) -> Self {
+        self.source = Some(source);
+        self
+    }
 }
 
 #[turbo_tasks::value_impl]
 impl GenerateSourceMap for ParseResultSourceMap {
     #[turbo_tasks::function]
-    fn generate_source_map(&self) -> SourceMapVc {
+    async fn generate_source_map(&self) -> Result<SourceMapVc> {
+        let input_source_map = match self.source {
+            Some(source) => *input_source_map(source).await?,
+            None => None,
+        };
+        let original_source_map = match input_source_map {
+            Some(input_source_map) => Some(input_source_map.await?),
+            None => None,
+        };
+        let original = match original_source_map.as_deref() {
+            Some(TurbopackSourceMap::Regular(original)) => Some(&****original),
+            // Sectioned maps can't be chained, the generated map points to
+            // the parsed source instead.
+            _ => None,
+        };
         let map = self.source_map.build_source_map_with_config(
             &self.mappings,
-            None,
+            original,
             InlineSourcesContentConfig {},
         );
-        SourceMapVc::new_regular(map)
+        Ok(SourceMapVc::new_regular(map))
     }
 }
 
@@ -125,11 +161,36 @@ impl SourceMapGenConfig for InlineSourcesContentConfig {
     }
 }
 
+/// Parses and transforms the ecmascript `source`.
+///
+/// Every module is parsed in its own task, so modules are parsed in parallel
+/// and a module is only parsed again when its content or its transforms
+/// change. Analysis and code generation read the cached result.
+///
+/// The parse task is keyed by the value of the resolved transforms. When the
+/// same source is processed for several environments, e. g. for the client
+/// and the server, modules with identical transforms share one parse result.
 #[turbo_tasks::function]
 pub async fn parse(
     source: AssetVc,
     ty: Value<EcmascriptModuleAssetType>,
     transforms: EcmascriptInputTransformsVc,
+) -> Result<ParseResultVc> {
+    let transforms = transforms
+        .await?
+        .iter()
+        .map(|transform| transform.resolve())
+        .try_join()
+        .await?;
+    let transforms = EcmascriptInputTransforms(transforms);
+    Ok(parse_with_transforms(source, ty, Value::new(transforms)))
+}
+
+#[turbo_tasks::function]
+async fn parse_with_transforms(
+    source: AssetVc,
+    ty: Value<EcmascriptModuleAssetType>,
+    transforms: Value<EcmascriptInputTransforms>,
 ) -> Result<ParseResultVc> {
     let content = source.content();
     let fs_path = &*source.path().await?;
@@ -140,25 +201,47 @@ pub async fn parse(
             FileContent::NotFound => ParseResult::NotFound.cell(),
             FileContent::Content(file) => match file.content().to_str() {
                 Ok(string) => {
-                    let transforms = &*transforms.await?;
                     parse_content(
                         string.into_owned(),
                         fs_path,
                         file_path_hash,
                         source,
                         ty,
-                        transforms,
+                        &transforms.0,
                     )
                     .await?
                 }
-                // FIXME: report error
-                Err(_) => ParseResult::Unparseable.cell(),
+                Err(err) => {
+                    emit_issue(
+                        source,
+                        "Reading ecmascript source code failed",
+                        err.to_string(),
+                    );
+                    ParseResult::Unparseable.cell()
+                }
             },
         },
         AssetContent::Redirect { .. } => ParseResult::Unparseable.cell(),
     })
 }
 
+/// Reports why a module is unparseable. The module throws when it's executed,
+/// but the rest of the module graph stays usable.
+fn emit_issue(source: AssetVc, title: &str, message: String) {
+    AnalyzeIssue {
+        severity: IssueSeverity::Error.cell(),
+        category: StringVc::cell("parse".to_string()),
+        path: source.path(),
+        title: StringVc::cell(title.to_string()),
+        message: StringVc::cell(message),
+        code: None,
+        source: None,
+    }
+    .cell()
+    .as_issue()
+    .emit();
+}
+
 async fn parse_content(
     string: String,
     fs_path: &FileSystemPath,
@@ -167,6 +250,9 @@ async fn parse_content(
     ty: EcmascriptModuleAssetType,
     transforms: &[EcmascriptInputTransform],
 ) -> Result<ParseResultVc> {
+    // The source map, comments and globals can't be taken from a pool and
+    // reused for the next parse, as they are owned by the parse result and
+    // live as long as the module is cached.
     let source_map: Arc<SourceMap> = Default::default();
     let handler = Handler::with_emitter(
         true,
@@ -187,7 +273,8 @@ async fn parse_content(
             })
         },
         async {
-            let file_name = FileName::Custom(fs_path.path.clone());
+            let start = Instant::now();
+            let file_name = FileName::Custom(fs_path.path.to_string());
             let fm = source_map.new_source_file(file_name.clone(), string);
 
             let comments = SwcComments::default();
@@ -246,6 +333,15 @@ async fn parse_content(
                     }
                 }
             };
+            let parse_time = start.elapsed();
+
+            // The source map of the source is chained into the generated one,
+            // so its URL must not end up in the output.
+            for comments in [&comments.leading, &comments.trailing] {
+                for mut entry in comments.iter_mut() {
+                    entry.retain(|comment| source_mapping_url_of_comment(&comment.text).is_none());
+                }
+            }
 
             let unresolved_mark = Mark::new();
             let top_level_mark = Mark::new();
@@ -267,14 +363,32 @@ async fn parse_content(
                 top_level_mark,
                 unresolved_mark,
                 file_name_str: fs_path.file_name(),
+                file_path_str: &fs_path.path,
                 file_name_hash: file_path_hash,
+                source,
             };
             for transform in transforms.iter() {
-                transform.apply(&mut parsed_program, &context).await?;
+                // A failing transform only breaks this module, like a syntax error.
+                if let Err(err) = transform.apply(&mut parsed_program, &context).await {
+                    emit_issue(
+                        source,
+                        "Transforming ecmascript source code failed",
+                        format!("{err:?}"),
+                    );
+                    return Ok(ParseResult::Unparseable);
+                }
             }
 
             let eval_context = EvalContext::new(&parsed_program, unresolved_mark);
 
+            tracing::debug!(
+                target: "turbopack::parse",
+                path = %fs_path.path,
+                parse_time = ?parse_time,
+                total_time = ?start.elapsed(),
+                "parsed ecmascript"
+            );
+
             Ok::<ParseResult, anyhow::Error>(ParseResult::Ok {
                 program: parsed_program,
                 comments,
@@ -304,3 +418,98 @@ async fn hash_file_path(file_path_vc: FileSystemPathVc) -> Result<U64Vc> {
     hasher.write_bytes(file_path.file_name().as_bytes());
     Ok(U64Vc::cell(hasher.finish()))
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use turbo_tasks::{TurboTasks, Value};
+    use turbo_tasks_fs::DiskFileSystemVc;
+    use turbo_tasks_memory::MemoryBackend;
+    use turbopack_core::{
+        asset::AssetVc,
+        environment::{
+            BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment,
+        },
+        source_asset::SourceAssetVc,
+    };
+
+    use super::{parse, ParseResult};
+    use crate::{EcmascriptInputTransform, EcmascriptInputTransformsVc, EcmascriptModuleAssetType};
+
+    #[tokio::test]
+    async fn shares_parse_result_of_equal_transforms() -> Result<()> {
+        crate::register();
+        let tt = TurboTasks::new(MemoryBackend::new());
+        tt.run_once(async {
+            let fs = DiskFileSystemVc::new(
+                "project".to_string(),
+                env!("CARGO_MANIFEST_DIR").to_string(),
+            );
+            let source: AssetVc =
+                SourceAssetVc::new(fs.root().join("tests/analyzer/graph/member-call/input.js"))
+                    .into();
+            let environment = EnvironmentVc::new(
+                Value::new(ExecutionEnvironment::Browser(
+                    BrowserEnvironment {
+                        dom: true,
+                        web_worker: false,
+                        service_worker: false,
+                        browserslist_query: "last 1 Chrome versions".to_string(),
+                    }
+                    .into(),
+                )),
+                Value::new(EnvironmentIntention::Client),
+            );
+            // One environment is passed unresolved, the other one resolved, like
+            // when it's created by different transitions.
+            let unresolved =
+                EcmascriptInputTransformsVc::cell(vec![EcmascriptInputTransform::PresetEnv(
+                    environment,
+                )]);
+            let resolved =
+                EcmascriptInputTransformsVc::cell(vec![EcmascriptInputTransform::PresetEnv(
+                    environment.resolve().await?,
+                )]);
+            let ty = Value::new(EcmascriptModuleAssetType::Ecmascript);
+            assert_eq!(
+                parse(source, ty, unresolved).resolve().await?,
+                parse(source, ty, resolved).resolve().await?
+            );
+            Ok(())
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn strips_source_mapping_url() -> Result<()> {
+        crate::register();
+        let tt = TurboTasks::new(MemoryBackend::new());
+        tt.run_once(async {
+            let fs = DiskFileSystemVc::new(
+                "project".to_string(),
+                env!("CARGO_MANIFEST_DIR").to_string(),
+            );
+            let source: AssetVc =
+                SourceAssetVc::new(fs.root().join("tests/input-source-map/input.js")).into();
+            let parsed = parse(
+                source,
+                Value::new(EcmascriptModuleAssetType::Ecmascript),
+                EcmascriptInputTransformsVc::cell(Vec::new()),
+            )
+            .await?;
+            let comments = match &*parsed {
+                ParseResult::Ok { comments, .. } => comments,
+                _ => panic!("input.js can't be parsed"),
+            };
+            for comments in [&comments.leading, &comments.trailing] {
+                for entry in comments.iter() {
+                    assert!(entry
+                        .iter()
+                        .all(|comment| !comment.text.contains("sourceMappingURL")));
+                }
+            }
+            Ok(())
+        })
+        .await
+    }
+}
@@ -0,0 +1,147 @@
+use std::mem::take;
+
+use swc_core::{
+    ecma::{
+        ast::{
+            ArrowExpr, AwaitExpr, BlockStmtOrExpr, ClassProp, Constructor, Expr, ForOfStmt,
+            Function, Module, ModuleItem, PrivateProp, Program, Stmt,
+        },
+        visit::{Visit, VisitWith},
+    },
+    quote,
+};
+
+use super::esm::base::is_hoisting_location;
+
+/// Whether the module uses `await` outside of functions, which makes it an
+/// async module.
+pub(crate) fn has_top_level_await(program: &Program) -> bool {
+    let mut finder = TopLevelAwaitFinder { found: false };
+    program.visit_with(&mut finder);
+    finder.found
+}
+
+struct TopLevelAwaitFinder {
+    found: bool,
+}
+
+impl Visit for TopLevelAwaitFinder {
+    fn visit_await_expr(&mut self, _: &AwaitExpr) {
+        self.found = true;
+    }
+
+    fn visit_for_of_stmt(&mut self, stmt: &ForOfStmt) {
+        if stmt.is_await {
+            self.found = true;
+        } else {
+            stmt.visit_children_with(self);
+        }
+    }
+
+    // Everything below introduces a new function scope, so `await` in there
+    // doesn't affect the module.
+
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+
+    fn visit_constructor(&mut self, _: &Constructor) {}
+
+    fn visit_class_prop(&mut self, _: &ClassProp) {}
+
+    fn visit_private_prop(&mut self, _: &PrivateProp) {}
+}
+
+/// Wraps the code of an async module in a `__turbopack_async_module__` call.
+/// Its body waits for the async modules it imports, after the imports were
+/// instantiated and before any other code runs.
+///
+/// This has to run after all other code generation, as it moves the module
+/// level statements into a function.
+pub(crate) fn wrap_async_module(program: &mut Program) {
+    if let Program::Module(Module { body, .. }) = program {
+        let mut stmts = Vec::with_capacity(body.len() + 1);
+        for item in take(body) {
+            match item {
+                ModuleItem::Stmt(stmt) => stmts.push(stmt),
+                // Code generation already replaced all imports and exports, so
+                // this can't happen.
+                item @ ModuleItem::ModuleDecl(_) => body.push(item),
+            }
+        }
+
+        let mut function = quote!(
+            "async (__turbopack_handle_async_dependencies__) => {
+                await __turbopack_handle_async_dependencies__();
+            }" as Expr
+        );
+        if let Expr::Arrow(ArrowExpr {
+            body: BlockStmtOrExpr::BlockStmt(block),
+            ..
+        }) = &mut function
+        {
+            let imports_end = stmts
+                .iter()
+                .position(is_hoisting_location)
+                .map_or(0, |pos| pos + 1);
+            let rest = stmts.split_off(imports_end);
+            stmts.append(&mut block.stmts);
+            stmts.extend(rest);
+            block.stmts = stmts;
+        }
+
+        body.push(ModuleItem::Stmt(quote!(
+            "__turbopack_async_module__($function);" as Stmt,
+            function: Expr = function
+        )));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use swc_core::{
+        common::{FileName, SourceMap},
+        ecma::{
+            ast::{EsVersion, Program},
+            parser::parse_file_as_module,
+        },
+    };
+
+    use super::has_top_level_await;
+
+    fn parse(code: &str) -> Program {
+        let cm = SourceMap::default();
+        let fm = cm.new_source_file(FileName::Anon, code.to_string());
+        Program::Module(
+            parse_file_as_module(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_has_top_level_await() {
+        for code in [
+            "await import('./a.js');",
+            "const a = await fetch('/a');",
+            "if (a) { await a; }",
+            "for await (const a of b) {}",
+        ] {
+            assert!(has_top_level_await(&parse(code)), "{code}");
+        }
+        for code in [
+            "async function a() { await b; }",
+            "const a = async () => { await b; };",
+            "class A { async b() { await c; } }",
+            "const a = { async b() { await c; } };",
+            "for (const a of b) {}",
+        ] {
+            assert!(!has_top_level_await(&parse(code)), "{code}");
+        }
+    }
+}
@@ -1,28 +1,38 @@
+use std::collections::HashSet;
+
 use anyhow::{anyhow, Result};
+use indexmap::{IndexMap, IndexSet};
 use lazy_static::lazy_static;
 use swc_core::{
     common::DUMMY_SP,
     ecma::ast::{Expr, ExprStmt, Ident, Lit, Module, ModuleItem, Program, Script, Stmt},
     quote,
 };
-use turbo_tasks::{primitives::StringVc, Value, ValueToString, ValueToStringVc};
+use turbo_tasks::{
+    primitives::{StringVc, StringsVc},
+    Value, ValueToString, ValueToStringVc,
+};
 use turbopack_core::{
-    asset::Asset,
+    asset::{Asset, AssetVc, AssetsVc},
     chunk::{
         ChunkableAssetReference, ChunkableAssetReferenceVc, ChunkingContextVc, ChunkingType,
         ChunkingTypeOptionVc, ModuleId,
     },
     reference::{AssetReference, AssetReferenceVc},
     resolve::{
-        origin::ResolveOriginVc, parse::RequestVc, ResolveResult, ResolveResultVc, SpecialType,
+        origin::ResolveOriginVc, parse::RequestVc, side_effects::is_side_effect_free,
+        ResolveResult, ResolveResultVc, SpecialType,
     },
 };
 
+use super::export::{expand_star_exports, EsmExport};
 use crate::{
     analyzer::imports::ImportAnnotations,
-    chunk::EcmascriptChunkPlaceableVc,
+    chunk::{EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc, EcmascriptExports},
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
-    create_visitor, magic_identifier,
+    create_visitor,
+    css_module_script::css_module_script_resolve,
+    magic_identifier,
     references::util::{request_to_string, throw_module_not_found_expr},
     resolve::esm_resolve,
 };
@@ -58,6 +68,9 @@ pub struct EsmAssetReference {
     pub origin: ResolveOriginVc,
     pub request: RequestVc,
     pub annotations: ImportAnnotations,
+    /// The symbols imported from the referenced module, when it's only
+    /// accessed via named imports.
+    pub imported_symbols: Option<StringsVc>,
 }
 
 impl EsmAssetReference {
@@ -68,6 +81,18 @@ impl EsmAssetReference {
         }
         origin
     }
+
+    /// Resolves the request. Stylesheets which are imported with
+    /// `assert { type: "css" }` are replaced by their CSS module scripts.
+    fn resolve_module(&self) -> ResolveResultVc {
+        let origin = self.get_origin();
+        let result = esm_resolve(origin, self.request);
+        if self.annotations.module_type() == Some("css") {
+            css_module_script_resolve(origin, result)
+        } else {
+            result
+        }
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -75,7 +100,7 @@ impl EsmAssetReferenceVc {
     #[turbo_tasks::function]
     pub(super) async fn get_referenced_asset(self) -> Result<ReferencedAssetVc> {
         let this = self.await?;
-        let resolve_result = esm_resolve(this.get_origin(), this.request);
+        let resolve_result = this.resolve_module();
         match &*resolve_result.await? {
             ResolveResult::Special(SpecialType::OriginalReferenceExternal, _) => {
                 if let Some(request) = this.request.await?.request() {
@@ -98,25 +123,130 @@ impl EsmAssetReferenceVc {
         Ok(ReferencedAssetVc::cell(ReferencedAsset::None))
     }
 
+    /// Finds the assets that provide the imported symbols. When the referenced
+    /// asset is side effect free and only re-exports all imported symbols, it
+    /// doesn't need to be included and the symbols are imported from the
+    /// re-exported assets instead.
+    #[turbo_tasks::function]
+    pub(super) async fn get_referenced_symbols(self) -> Result<ReferencedSymbolsVc> {
+        let this = self.await?;
+        let imported_symbols = if let Some(imported_symbols) = this.imported_symbols {
+            imported_symbols.await?
+        } else {
+            return Ok(ReferencedSymbols::Asset.cell());
+        };
+        let asset = if let ReferencedAsset::Some(asset) = &*self.get_referenced_asset().await? {
+            *asset
+        } else {
+            return Ok(ReferencedSymbols::Asset.cell());
+        };
+        if !*is_side_effect_free(asset.path()).await? {
+            return Ok(ReferencedSymbols::Asset.cell());
+        }
+        let mut symbols = IndexMap::new();
+        for symbol in imported_symbols.iter() {
+            let (target, export) = follow_reexports(asset, symbol.clone()).await?;
+            if target == asset {
+                return Ok(ReferencedSymbols::Asset.cell());
+            }
+            symbols.insert(symbol.clone(), (target, export));
+        }
+        Ok(ReferencedSymbols::Reexported(symbols).cell())
+    }
+
     #[turbo_tasks::function]
     pub fn new(
         origin: ResolveOriginVc,
         request: RequestVc,
         annotations: Value<ImportAnnotations>,
+        imported_symbols: Option<StringsVc>,
     ) -> Self {
         Self::cell(EsmAssetReference {
             origin,
             request,
             annotations: annotations.into_value(),
+            imported_symbols,
         })
     }
 }
 
+/// The assets providing the symbols imported by an [EsmAssetReference].
+#[turbo_tasks::value(shared)]
+pub enum ReferencedSymbols {
+    /// The symbols are imported from the referenced asset.
+    Asset,
+    /// The symbols are imported from the assets re-exported by the referenced
+    /// asset. Maps each imported symbol to the asset providing it and the
+    /// export name in that asset. No export name refers to the namespace of
+    /// the asset.
+    Reexported(IndexMap<String, (EcmascriptChunkPlaceableVc, Option<String>)>),
+}
+
+/// Follows re-exports of `export` through side effect free modules, which
+/// don't need to be evaluated to provide the export. Returns the asset that
+/// provides the export and the name of the export in that asset.
+async fn follow_reexports(
+    mut asset: EcmascriptChunkPlaceableVc,
+    mut export: String,
+) -> Result<(EcmascriptChunkPlaceableVc, Option<String>)> {
+    let mut visited = HashSet::new();
+    while visited.insert((asset, export.clone())) {
+        if !*is_side_effect_free(asset.path()).await? {
+            break;
+        }
+        let exports = asset.get_exports().await?;
+        let exports = if let EcmascriptExports::EsmExports(exports) = &*exports {
+            exports.await?
+        } else {
+            break;
+        };
+        let reexport = match exports.exports.get(&export) {
+            Some(EsmExport::ImportedBinding(esm_ref, name)) => Some((*esm_ref, Some(name.clone()))),
+            Some(EsmExport::ImportedNamespace(esm_ref)) => Some((*esm_ref, None)),
+            Some(EsmExport::LocalBinding(_) | EsmExport::Error) => None,
+            // `export *` doesn't re-export the default export
+            None if export == "default" => None,
+            None => {
+                let mut reexport = None;
+                for esm_ref in exports.star_exports.iter() {
+                    if let ReferencedAsset::Some(star_asset) =
+                        &*esm_ref.get_referenced_asset().await?
+                    {
+                        if expand_star_exports(*star_asset).await?.contains(&export) {
+                            reexport = Some((*esm_ref, Some(export.clone())));
+                            break;
+                        }
+                    }
+                }
+                reexport
+            }
+        };
+        let (esm_ref, name) = if let Some(reexport) = reexport {
+            reexport
+        } else {
+            break;
+        };
+        let target = if let ReferencedAsset::Some(target) = &*esm_ref.get_referenced_asset().await?
+        {
+            *target
+        } else {
+            break;
+        };
+        if let Some(name) = name {
+            asset = target;
+            export = name;
+        } else {
+            return Ok((target, None));
+        }
+    }
+    Ok((asset, Some(export)))
+}
+
 #[turbo_tasks::value_impl]
 impl AssetReference for EsmAssetReference {
     #[turbo_tasks::function]
     fn resolve_reference(&self) -> ResolveResultVc {
-        esm_resolve(self.get_origin(), self.request)
+        self.resolve_module()
     }
 }
 
@@ -148,6 +278,23 @@ impl ChunkableAssetReference for EsmAssetReference {
             },
         )
     }
+
+    #[turbo_tasks::function]
+    async fn chunkable_assets(
+        self_vc: EsmAssetReferenceVc,
+        _context: ChunkingContextVc,
+    ) -> Result<AssetsVc> {
+        Ok(match &*self_vc.get_referenced_symbols().await? {
+            ReferencedSymbols::Asset => self_vc.resolve_reference().primary_assets(),
+            ReferencedSymbols::Reexported(symbols) => {
+                let assets: IndexSet<AssetVc> = symbols
+                    .values()
+                    .map(|(asset, _)| asset.as_asset())
+                    .collect();
+                AssetsVc::cell(assets.into_iter().collect())
+            }
+        })
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -181,6 +328,31 @@ impl CodeGenerateable for EsmAssetReference {
 
         // separate chunks can't be imported as the modules are not available
         if !matches!(*chunking_type, None | Some(ChunkingType::Separate)) {
+            if let ReferencedSymbols::Reexported(symbols) =
+                &*self_vc.get_referenced_symbols().await?
+            {
+                let assets: IndexSet<EcmascriptChunkPlaceableVc> =
+                    symbols.values().map(|(asset, _)| *asset).collect();
+                for asset in assets {
+                    let ident = ReferencedAsset::Some(asset).get_ident().await?;
+                    let id = asset.as_chunk_item(context).id().await?;
+                    if let Some(ident) = ident {
+                        visitors.push(create_visitor!(visit_mut_program(program: &mut Program) {
+                            let stmt = quote!(
+                                "var $name = __turbopack_import__($id);" as Stmt,
+                                name = Ident::new(ident.clone().into(), DUMMY_SP),
+                                id: Expr = Expr::Lit(match &*id {
+                                    ModuleId::String(s) => s.to_string().into(),
+                                    ModuleId::Number(n) => (*n as f64).into(),
+                                })
+                            );
+                            insert_hoisted_stmt(program, stmt);
+                        }));
+                    }
+                }
+                return Ok(CodeGeneration { visitors }.into());
+            }
+
             let referenced_asset = self_vc.get_referenced_asset().await?;
             if let Some(ident) = referenced_asset.get_ident().await? {
                 match &*referenced_asset {
@@ -191,7 +363,7 @@ impl CodeGenerateable for EsmAssetReference {
                                 "var $name = __turbopack_import__($id);" as Stmt,
                                 name = Ident::new(ident.clone().into(), DUMMY_SP),
                                 id: Expr = Expr::Lit(match &*id {
-                                    ModuleId::String(s) => s.clone().into(),
+                                    ModuleId::String(s) => s.to_string().into(),
                                     ModuleId::Number(n) => (*n as f64).into(),
                                 })
                             );
@@ -219,22 +391,52 @@ impl CodeGenerateable for EsmAssetReference {
     }
 }
 
+/// Returns the identifier of the imported module and the export name to access
+/// the `export` imported via `reference`. Takes re-exports of side effect free
+/// modules into account, which are skipped.
+pub(super) async fn get_imported_export(
+    reference: EsmAssetReferenceVc,
+    export: Option<&str>,
+) -> Result<(Option<String>, Option<String>)> {
+    if let Some(export) = export {
+        if let ReferencedSymbols::Reexported(symbols) = &*reference.get_referenced_symbols().await?
+        {
+            if let Some((asset, name)) = symbols.get(export) {
+                let ident = ReferencedAsset::Some(*asset).get_ident().await?;
+                return Ok((ident, name.clone()));
+            }
+        }
+    }
+    let ident = reference.get_referenced_asset().await?.get_ident().await?;
+    Ok((ident, export.map(|export| export.to_string())))
+}
+
 lazy_static! {
     static ref ESM_HOISTING_LOCATION: &'static str = Box::leak(Box::new(magic_identifier::encode(
         "ecmascript hoisting location"
     )));
 }
 
+/// Whether `stmt` is the marker which follows all statements inserted by
+/// [insert_hoisted_stmt].
+pub(crate) fn is_hoisting_location(stmt: &Stmt) -> bool {
+    if let Stmt::Expr(ExprStmt {
+        expr: box Expr::Lit(Lit::Str(s)),
+        ..
+    }) = stmt
+    {
+        &*s.value == *ESM_HOISTING_LOCATION
+    } else {
+        false
+    }
+}
+
 pub(crate) fn insert_hoisted_stmt(program: &mut Program, stmt: Stmt) {
     match program {
         Program::Module(Module { body, .. }) => {
             let pos = body.iter().position(|item| {
-                if let ModuleItem::Stmt(Stmt::Expr(ExprStmt {
-                    expr: box Expr::Lit(Lit::Str(s)),
-                    ..
-                })) = item
-                {
-                    &*s.value == *ESM_HOISTING_LOCATION
+                if let ModuleItem::Stmt(stmt) = item {
+                    is_hoisting_location(stmt)
                 } else {
                     false
                 }
@@ -253,17 +455,7 @@ pub(crate) fn insert_hoisted_stmt(program: &mut Program, stmt: Stmt) {
             }
         }
         Program::Script(Script { body, .. }) => {
-            let pos = body.iter().position(|item| {
-                if let Stmt::Expr(ExprStmt {
-                    expr: box Expr::Lit(Lit::Str(s)),
-                    ..
-                }) = item
-                {
-                    &*s.value == *ESM_HOISTING_LOCATION
-                } else {
-                    false
-                }
-            });
+            let pos = body.iter().position(is_hoisting_location);
             if let Some(pos) = pos {
                 body.insert(pos, stmt);
             } else {
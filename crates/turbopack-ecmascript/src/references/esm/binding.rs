@@ -11,7 +11,7 @@ use swc_core::{
 };
 use turbopack_core::chunk::ChunkingContextVc;
 
-use super::EsmAssetReferenceVc;
+use super::{base::get_imported_export, EsmAssetReferenceVc};
 use crate::{
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
     create_visitor,
@@ -52,7 +52,6 @@ impl CodeGenerateable for EsmBinding {
     ) -> Result<CodeGenerationVc> {
         let this = self_vc.await?;
         let mut visitors = Vec::new();
-        let imported_module = this.reference.get_referenced_asset();
 
         fn make_expr(imported_module: &str, export: Option<&str>) -> Expr {
             if let Some(export) = export {
@@ -74,7 +73,8 @@ impl CodeGenerateable for EsmBinding {
         }
 
         let mut ast_path = this.ast_path.await?.clone_value();
-        let imported_module = imported_module.await?.get_ident().await?;
+        let (imported_module, export) =
+            get_imported_export(this.reference, this.export.as_deref()).await?;
 
         loop {
             match ast_path.last() {
@@ -83,7 +83,7 @@ impl CodeGenerateable for EsmBinding {
                     visitors.push(
                         create_visitor!(exact ast_path, visit_mut_expr(expr: &mut Expr) {
                             if let Some(ident) = imported_module.as_deref() {
-                              *expr = make_expr(ident, this.export.as_deref());
+                              *expr = make_expr(ident, export.as_deref());
                             }
                             // If there's no identifier for the imported module,
                             // resolution failed and will insert code that throws
@@ -99,7 +99,7 @@ impl CodeGenerateable for EsmBinding {
                             if let Prop::Shorthand(ident) = prop {
                               // TODO: Merge with the above condition when https://rust-lang.github.io/rfcs/2497-if-let-chains.html lands.
                               if let Some(imported_ident) = imported_module.as_deref() {
-                                *prop = Prop::KeyValue(KeyValueProp { key: PropName::Ident(ident.clone()), value: box make_expr(imported_ident, this.export.as_deref())});
+                                *prop = Prop::KeyValue(KeyValueProp { key: PropName::Ident(ident.clone()), value: box make_expr(imported_ident, export.as_deref())});
                               }
                             }
                         }),
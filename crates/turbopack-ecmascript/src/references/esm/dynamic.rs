@@ -17,6 +17,7 @@ use super::super::pattern_mapping::{PatternMapping, PatternMappingVc, ResolveTyp
 use crate::{
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
     create_visitor,
+    css_module_script::css_module_script_resolve,
     references::AstPathVc,
     resolve::esm_resolve,
 };
@@ -27,25 +28,45 @@ pub struct EsmAsyncAssetReference {
     pub origin: ResolveOriginVc,
     pub request: RequestVc,
     pub path: AstPathVc,
+    /// The module type required by the import assertion, e.g. `css` for
+    /// `import("./a.css", { assert: { type: "css" } })`.
+    pub module_type: Option<String>,
 }
 
 #[turbo_tasks::value_impl]
 impl EsmAsyncAssetReferenceVc {
     #[turbo_tasks::function]
-    pub fn new(origin: ResolveOriginVc, request: RequestVc, path: AstPathVc) -> Self {
+    pub fn new(
+        origin: ResolveOriginVc,
+        request: RequestVc,
+        path: AstPathVc,
+        module_type: Option<String>,
+    ) -> Self {
         Self::cell(EsmAsyncAssetReference {
             origin,
             request,
             path,
+            module_type,
         })
     }
 }
 
+impl EsmAsyncAssetReference {
+    fn resolve_module(&self) -> ResolveResultVc {
+        let result = esm_resolve(self.origin, self.request);
+        if self.module_type.as_deref() == Some("css") {
+            css_module_script_resolve(self.origin, result)
+        } else {
+            result
+        }
+    }
+}
+
 #[turbo_tasks::value_impl]
 impl AssetReference for EsmAsyncAssetReference {
     #[turbo_tasks::function]
     fn resolve_reference(&self) -> ResolveResultVc {
-        esm_resolve(self.origin, self.request)
+        self.resolve_module()
     }
 }
 
@@ -76,7 +97,7 @@ impl CodeGenerateable for EsmAsyncAssetReference {
             self.request,
             self.origin,
             context,
-            esm_resolve(self.origin, self.request),
+            self.resolve_module(),
             Value::new(EsmAsync),
         )
         .await?;
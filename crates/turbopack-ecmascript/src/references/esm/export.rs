@@ -24,7 +24,10 @@ use turbopack_core::{
     issue::{analyze::AnalyzeIssue, IssueSeverity},
 };
 
-use super::{base::ReferencedAsset, EsmAssetReferenceVc};
+use super::{
+    base::{get_imported_export, ReferencedAsset},
+    EsmAssetReferenceVc,
+};
 use crate::{
     chunk::{EcmascriptChunkPlaceableVc, EcmascriptExports},
     code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
@@ -40,7 +43,9 @@ pub enum EsmExport {
 }
 
 #[turbo_tasks::function]
-async fn expand_star_exports(root_asset: EcmascriptChunkPlaceableVc) -> Result<StringsVc> {
+pub(super) async fn expand_star_exports(
+    root_asset: EcmascriptChunkPlaceableVc,
+) -> Result<StringsVc> {
     let mut set = HashSet::new();
     let mut checked_assets = HashSet::new();
     checked_assets.insert(root_asset);
@@ -159,23 +164,26 @@ impl CodeGenerateable for EsmExports {
                     local = Ident::new((name as &str).into(), DUMMY_SP)
                 )),
                 EsmExport::ImportedBinding(esm_ref, name) => {
-                    let referenced_asset = esm_ref.get_referenced_asset().await?;
-                    referenced_asset.get_ident().await?.map(|ident| {
-                        quote!(
-                            "(() => $expr)" as Expr,
-                            expr: Expr = Expr::Member(MemberExpr {
+                    let (ident, name) = get_imported_export(*esm_ref, Some(name)).await?;
+                    ident.map(|ident| {
+                        let imported = Expr::Ident(Ident::new(ident.into(), DUMMY_SP));
+                        let expr = if let Some(name) = name {
+                            Expr::Member(MemberExpr {
                                 span: DUMMY_SP,
-                                obj: box Expr::Ident(Ident::new(ident.into(), DUMMY_SP)),
+                                obj: box imported,
                                 prop: MemberProp::Computed(ComputedPropName {
                                     span: DUMMY_SP,
                                     expr: box Expr::Lit(Lit::Str(Str {
                                         span: DUMMY_SP,
-                                        value: (name as &str).into(),
+                                        value: name.into(),
                                         raw: None,
-                                    }))
-                                })
+                                    })),
+                                }),
                             })
-                        )
+                        } else {
+                            imported
+                        };
+                        quote!("(() => $expr)" as Expr, expr: Expr = expr)
                     })
                 }
                 EsmExport::ImportedNamespace(esm_ref) => {
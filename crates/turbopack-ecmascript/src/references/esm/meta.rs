@@ -0,0 +1,296 @@
+use anyhow::Result;
+use swc_core::{
+    common::DUMMY_SP,
+    ecma::ast::{Expr, Ident, Lit, Stmt},
+    quote,
+};
+use turbo_tasks::{primitives::StringVc, ValueToString, ValueToStringVc};
+use turbo_tasks_fs::{to_sys_path, FileSystemPathVc};
+use turbopack_core::{
+    chunk::ChunkingContextVc,
+    environment::EnvironmentVc,
+    reference::{AssetReference, AssetReferenceVc},
+    resolve::{
+        origin::ResolveOriginVc, parse::RequestVc, ResolveResult, ResolveResultVc, SpecialType,
+    },
+};
+use url::Url;
+
+use crate::{
+    chunk::{EcmascriptChunkPlaceable, EcmascriptChunkPlaceableVc},
+    code_gen::{CodeGenerateable, CodeGenerateableVc, CodeGeneration, CodeGenerationVc},
+    create_visitor,
+    references::{
+        esm::base::insert_hoisted_stmt,
+        util::{request_to_string, throw_module_not_found_expr},
+        AstPathVc,
+    },
+    resolve::esm_resolve,
+    utils::module_id_to_lit,
+};
+
+/// The name of the module level binding which replaces all `import.meta`
+/// expressions of a module.
+const IMPORT_META_BINDING: &str = "__turbopack_import_meta__";
+
+/// Declares the `import.meta` object of a module. It's only added once per
+/// module, and only for modules which use `import.meta`.
+#[turbo_tasks::value]
+#[derive(Hash, Debug)]
+pub struct ImportMetaBinding {
+    origin: ResolveOriginVc,
+    environment: EnvironmentVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ImportMetaBindingVc {
+    #[turbo_tasks::function]
+    pub fn new(origin: ResolveOriginVc, environment: EnvironmentVc) -> Self {
+        ImportMetaBinding {
+            origin,
+            environment,
+        }
+        .cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl CodeGenerateable for ImportMetaBinding {
+    #[turbo_tasks::function]
+    async fn code_generation(&self, context: ChunkingContextVc) -> Result<CodeGenerationVc> {
+        let url = module_url(self.origin.origin_path(), self.environment)
+            .await?
+            .to_expr();
+        // The runtime adds the module to the cache before its factory is called,
+        // so its hot module API is already available here.
+        let hot = match EcmascriptChunkPlaceableVc::resolve_from(self.origin).await? {
+            Some(module) => {
+                let id = module.as_chunk_item(context).id().await?;
+                quote!(
+                    "__turbopack_cache__[$id].hot" as Expr,
+                    id: Expr = module_id_to_lit(&id)
+                )
+            }
+            None => quote!("undefined" as Expr),
+        };
+
+        // Requests which can be resolved at build time are replaced by
+        // `ImportMetaResolveAssetReference`, so `resolve` is only called for
+        // dynamic requests.
+        let mut visitors = Vec::new();
+        visitors.push(
+            create_visitor!(visit_mut_program(program: &mut Program) {
+                let stmt = quote!(
+                    "const $name = {
+                        url: $url,
+                        resolve: (specifier) => {
+                            throw new Error(\"import.meta.resolve(\" + JSON.stringify(specifier) + \") \
+                             is not statically analyse-able\");
+                        },
+                        hot: $hot,
+                    };" as Stmt,
+                    name = Ident::new(IMPORT_META_BINDING.into(), DUMMY_SP),
+                    url: Expr = url.clone(),
+                    hot: Expr = hot.clone()
+                );
+                insert_hoisted_stmt(program, stmt);
+            }),
+        );
+
+        Ok(CodeGeneration { visitors }.into())
+    }
+}
+
+/// The URL of a module, as seen by `import.meta.url` and returned by
+/// `import.meta.resolve`.
+enum ModuleUrl {
+    /// The `file:` URL of the module on disk.
+    File(Url),
+    /// An absolute path, which is resolved against the location of the page
+    /// at runtime.
+    Path(String),
+}
+
+/// Computes the URL of a module. Node.js code gets the `file:` URL of the
+/// module on disk, like it would without bundling. Other targets get a URL
+/// relative to the project root, as the location of the source code on the
+/// build machine is meaningless there and shouldn't be leaked to clients.
+async fn module_url(path: FileSystemPathVc, environment: EnvironmentVc) -> Result<ModuleUrl> {
+    if *environment.node_externals().await? {
+        if let Some(sys_path) = &*to_sys_path(path).await? {
+            if let Ok(url) = Url::from_file_path(sys_path) {
+                return Ok(ModuleUrl::File(url));
+            }
+        }
+    }
+    Ok(ModuleUrl::Path(url_path(&path.await?.path)?))
+}
+
+/// Turns a project relative path into a percent-encoded absolute URL path.
+fn url_path(path: &str) -> Result<String> {
+    let mut url = Url::parse("file:///")?;
+    url.set_path(path);
+    Ok(url.path().to_string())
+}
+
+impl ModuleUrl {
+    fn to_expr(&self) -> Expr {
+        match self {
+            ModuleUrl::File(url) => Expr::Lit(Lit::Str(url.as_str().into())),
+            // `location` is missing in workers without a document and in
+            // non-browser runtimes, where the path is kept as a `file:` URL.
+            ModuleUrl::Path(path) => quote!(
+                "new URL($path, typeof location !== \"undefined\" ? location.href : \"file:///\").href"
+                    as Expr,
+                path: Expr = Expr::Lit(Lit::Str(path.as_str().into()))
+            ),
+        }
+    }
+}
+
+/// An `import.meta` expression, which is replaced by the binding declared by
+/// [ImportMetaBinding].
+#[turbo_tasks::value(shared)]
+#[derive(Hash, Debug)]
+pub struct ImportMetaRef {
+    pub path: AstPathVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ImportMetaRefVc {
+    #[turbo_tasks::function]
+    pub fn new(path: AstPathVc) -> Self {
+        ImportMetaRef { path }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl CodeGenerateable for ImportMetaRef {
+    #[turbo_tasks::function]
+    async fn code_generation(&self, _context: ChunkingContextVc) -> Result<CodeGenerationVc> {
+        let path = &self.path.await?;
+        let mut visitors = Vec::new();
+        visitors.push(create_visitor!(path, visit_mut_expr(expr: &mut Expr) {
+            if let Expr::MetaProp(_) = expr {
+                *expr = Expr::Ident(Ident::new(IMPORT_META_BINDING.into(), DUMMY_SP));
+            }
+            // ImportMetaRef will only be used for Expr::MetaProp.
+            // Due to eventual consistency the path might match something else,
+            // but we can ignore that as it will be recomputed anyway.
+        }));
+
+        Ok(CodeGeneration { visitors }.into())
+    }
+}
+
+/// A `import.meta.resolve()` call, which is replaced by the URL of the
+/// resolved module. The module itself isn't loaded, so the reference isn't
+/// chunkable.
+#[turbo_tasks::value]
+#[derive(Hash, Debug)]
+pub struct ImportMetaResolveAssetReference {
+    pub origin: ResolveOriginVc,
+    pub request: RequestVc,
+    pub path: AstPathVc,
+    pub environment: EnvironmentVc,
+}
+
+#[turbo_tasks::value_impl]
+impl ImportMetaResolveAssetReferenceVc {
+    #[turbo_tasks::function]
+    pub fn new(
+        origin: ResolveOriginVc,
+        request: RequestVc,
+        path: AstPathVc,
+        environment: EnvironmentVc,
+    ) -> Self {
+        Self::cell(ImportMetaResolveAssetReference {
+            origin,
+            request,
+            path,
+            environment,
+        })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl AssetReference for ImportMetaResolveAssetReference {
+    #[turbo_tasks::function]
+    fn resolve_reference(&self) -> ResolveResultVc {
+        esm_resolve(self.origin, self.request)
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for ImportMetaResolveAssetReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<StringVc> {
+        Ok(StringVc::cell(format!(
+            "import.meta.resolve {}",
+            self.request.to_string().await?,
+        )))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl CodeGenerateable for ImportMetaResolveAssetReference {
+    #[turbo_tasks::function]
+    async fn code_generation(&self, _context: ChunkingContextVc) -> Result<CodeGenerationVc> {
+        let result = esm_resolve(self.origin, self.request).await?;
+        let asset = match &*result {
+            ResolveResult::Single(asset, _) => Some(*asset),
+            ResolveResult::Alternatives(assets, _) => assets.first().copied(),
+            _ => None,
+        };
+        let url = match (asset, &*result) {
+            (Some(asset), _) => Some(module_url(asset.path(), self.environment).await?.to_expr()),
+            // Externals keep their request, like `node:fs`.
+            (None, ResolveResult::Special(SpecialType::OriginalReferenceExternal, _)) => {
+                Some(Expr::Lit(Lit::Str(
+                    request_to_string(self.request).await?.as_str().into(),
+                )))
+            }
+            (None, ResolveResult::Special(SpecialType::OriginalReferenceTypeExternal(s), _)) => {
+                Some(Expr::Lit(Lit::Str(s.as_str().into())))
+            }
+            _ => None,
+        };
+        let request_string = self.request.to_string().await?.clone();
+
+        let path = &self.path.await?;
+        let mut visitors = Vec::new();
+        visitors.push(create_visitor!(path, visit_mut_expr(expr: &mut Expr) {
+            if let Expr::Call(_) = expr {
+                *expr = match &url {
+                    Some(url) => url.clone(),
+                    // Like in browsers, an import.meta.resolve call that cannot be resolved will
+                    // throw an error.
+                    None => throw_module_not_found_expr(&request_string),
+                };
+            }
+            // ImportMetaResolveAssetReference will only be used for Expr::Call.
+            // Due to eventual consistency the path might match something else,
+            // but we can ignore that as it will be recomputed anyway.
+        }));
+
+        Ok(CodeGeneration { visitors }.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::url_path;
+
+    #[test]
+    fn url_path_is_absolute() {
+        assert_eq!(url_path("src/index.js").unwrap(), "/src/index.js");
+        assert_eq!(url_path("").unwrap(), "/");
+    }
+
+    #[test]
+    fn url_path_is_percent_encoded() {
+        assert_eq!(url_path("src/a b.js").unwrap(), "/src/a%20b.js");
+        assert_eq!(url_path("src/a#b?.js").unwrap(), "/src/a%23b%3F.js");
+        assert_eq!(url_path("pages/[id].js").unwrap(), "/pages/[id].js");
+    }
+}
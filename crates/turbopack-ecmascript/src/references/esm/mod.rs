@@ -2,6 +2,7 @@ pub(crate) mod base;
 pub(crate) mod binding;
 pub(crate) mod dynamic;
 pub(crate) mod export;
+pub(crate) mod meta;
 pub(crate) mod module_id;
 pub(crate) mod module_item;
 
@@ -10,5 +11,9 @@ pub use self::{
     binding::{EsmBinding, EsmBindingVc},
     dynamic::{EsmAsyncAssetReference, EsmAsyncAssetReferenceVc},
     export::{EsmExports, EsmExportsVc},
+    meta::{
+        ImportMetaBinding, ImportMetaBindingVc, ImportMetaRef, ImportMetaRefVc,
+        ImportMetaResolveAssetReference, ImportMetaResolveAssetReferenceVc,
+    },
     module_item::{EsmModuleItem, EsmModuleItemVc},
 };
@@ -70,7 +70,7 @@ impl CodeGenerateable for EsmModuleIdAssetReference {
             visitors.push(
                 create_visitor!(self.ast_path.await?, visit_mut_expr(expr: &mut Expr) {
                     *expr = Expr::Lit(match &*id {
-                        ModuleId::String(s) => s.clone().into(),
+                        ModuleId::String(s) => s.to_string().into(),
                         ModuleId::Number(n) => (*n as f64).into(),
                     })
                 }),
@@ -1,4 +1,5 @@
 pub mod amd;
+pub(crate) mod async_module;
 pub mod cjs;
 pub mod esm;
 pub mod node;
@@ -30,7 +31,7 @@ use swc_core::{
         visit::{AstParentKind, AstParentNodeRef, VisitAstPath, VisitWithPath},
     },
 };
-use turbo_tasks::{TryJoinIterExt, Value};
+use turbo_tasks::{primitives::StringsVc, TryJoinIterExt, Value};
 use turbo_tasks_fs::FileSystemPathVc;
 use turbopack_core::{
     asset::AssetVc,
@@ -48,6 +49,7 @@ use self::{
         AmdDefineAssetReferenceVc, AmdDefineDependencyElement, AmdDefineFactoryType,
         AmdDefineWithDependenciesCodeGenVc,
     },
+    async_module::has_top_level_await,
     cjs::CjsAssetReferenceVc,
     esm::{
         export::EsmExport, EsmAssetReferenceVc, EsmAsyncAssetReferenceVc, EsmExports,
@@ -80,7 +82,11 @@ use super::{
     EcmascriptModuleAssetType,
 };
 use crate::{
-    analyzer::{graph::EvalContext, imports::Reexport, ModuleValue},
+    analyzer::{
+        graph::EvalContext,
+        imports::{dynamic_import_module_type, Reexport},
+        ModuleValue,
+    },
     chunk::{EcmascriptExports, EcmascriptExportsVc},
     code_gen::{CodeGenerateableVc, CodeGenerateablesVc},
     magic_identifier,
@@ -88,7 +94,10 @@ use crate::{
         cjs::{
             CjsRequireAssetReferenceVc, CjsRequireCacheAccess, CjsRequireResolveAssetReferenceVc,
         },
-        esm::{module_id::EsmModuleIdAssetReferenceVc, EsmBindingVc, EsmExportsVc},
+        esm::{
+            module_id::EsmModuleIdAssetReferenceVc, EsmBindingVc, EsmExportsVc,
+            ImportMetaBindingVc, ImportMetaRefVc, ImportMetaResolveAssetReferenceVc,
+        },
     },
     EcmascriptInputTransformsVc,
 };
@@ -98,6 +107,8 @@ pub struct AnalyzeEcmascriptModuleResult {
     pub references: AssetReferencesVc,
     pub code_generation: CodeGenerateablesVc,
     pub exports: EcmascriptExportsVc,
+    /// Whether the module uses top-level await.
+    pub has_top_level_await: bool,
 }
 
 /// A temporary analysis result builder to pass around, to be turned into an
@@ -106,6 +117,7 @@ pub(crate) struct AnalyzeEcmascriptModuleResultBuilder {
     references: Vec<AssetReferenceVc>,
     code_gens: Vec<CodeGenerateableVc>,
     exports: EcmascriptExports,
+    has_top_level_await: bool,
 }
 
 impl AnalyzeEcmascriptModuleResultBuilder {
@@ -114,6 +126,7 @@ impl AnalyzeEcmascriptModuleResultBuilder {
             references: Vec::new(),
             code_gens: Vec::new(),
             exports: EcmascriptExports::None,
+            has_top_level_await: false,
         }
     }
 
@@ -138,12 +151,18 @@ impl AnalyzeEcmascriptModuleResultBuilder {
         self.exports = exports;
     }
 
+    /// Sets whether the module uses top-level await.
+    pub fn set_top_level_await(&mut self, has_top_level_await: bool) {
+        self.has_top_level_await = has_top_level_await;
+    }
+
     /// Builds the final analysis result.
     pub fn build(self) -> AnalyzeEcmascriptModuleResultVc {
         AnalyzeEcmascriptModuleResultVc::cell(AnalyzeEcmascriptModuleResult {
             references: AssetReferencesVc::cell(self.references),
             code_generation: CodeGenerateablesVc::cell(self.code_gens),
             exports: self.exports.into(),
+            has_top_level_await: self.has_top_level_await,
         })
     }
 }
@@ -284,16 +303,30 @@ pub(crate) async fn analyze_ecmascript_module(
                 GLOBALS.set(globals, || {
                     let var_graph = create_graph(program, eval_context);
 
-                    for (src, annotations) in eval_context.imports.references() {
+                    for (i, (src, annotations)) in eval_context.imports.references().enumerate() {
                         let r = EsmAssetReferenceVc::new(
                             origin,
                             RequestVc::parse(Value::new(src.to_string().into())),
                             Value::new(annotations.clone()),
+                            eval_context
+                                .imports
+                                .imported_symbols(i)
+                                .map(StringsVc::cell),
                         );
                         import_references.push(r);
                         analysis.add_reference(r);
                     }
 
+                    for (span, message) in eval_context.imports.invalid_assertions() {
+                        handler.span_err_with_code(
+                            span,
+                            message,
+                            DiagnosticId::Error(
+                                errors::failed_to_analyse::ecmascript::IMPORT_ASSERTION.to_string(),
+                            ),
+                        );
+                    }
+
                     // TODO migrate to effects
                     let mut visitor = AssetReferencesVisitor::new(
                         eval_context,
@@ -395,6 +428,7 @@ pub(crate) async fn analyze_ecmascript_module(
             };
 
             analysis.set_exports(exports);
+            analysis.set_top_level_await(has_top_level_await(program));
 
             fn handle_call_boxed<
                 'a,
@@ -502,7 +536,7 @@ pub(crate) async fn analyze_ecmascript_module(
                     }
                     JsValue::WellKnownFunction(WellKnownFunctionKind::Import) => {
                         let args = linked_args().await?;
-                        if args.len() == 1 {
+                        if args.len() == 1 || args.len() == 2 {
                             let pat = js_value_to_pattern(&args[0]);
                             if !pat.has_constant_parts() {
                                 let (args, hints) = explain_args(&args);
@@ -515,10 +549,26 @@ pub(crate) async fn analyze_ecmascript_module(
                                     ),
                                 )
                             }
+                            let module_type = match args.get(1).map(dynamic_import_module_type) {
+                                Some(Ok(module_type)) => module_type,
+                                Some(Err(message)) => {
+                                    handler.span_err_with_code(
+                                        span,
+                                        &message,
+                                        DiagnosticId::Error(
+                                            errors::failed_to_analyse::ecmascript::IMPORT_ASSERTION
+                                                .to_string(),
+                                        ),
+                                    );
+                                    None
+                                }
+                                None => None,
+                            };
                             analysis.add_reference(EsmAsyncAssetReferenceVc::new(
                                 origin,
                                 RequestVc::parse(Value::new(pat)),
                                 AstPathVc::cell(ast_path.to_vec()),
+                                module_type,
                             ));
                             return Ok(());
                         }
@@ -606,6 +656,34 @@ pub(crate) async fn analyze_ecmascript_module(
                         )
                     }
 
+                    JsValue::WellKnownFunction(WellKnownFunctionKind::ImportMetaResolve) => {
+                        let args = linked_args().await?;
+                        if args.len() == 1 {
+                            let pat = js_value_to_pattern(&args[0]);
+                            // Only constant requests have a single URL.
+                            if let Pattern::Constant(_) = pat {
+                                analysis.add_reference(ImportMetaResolveAssetReferenceVc::new(
+                                    origin,
+                                    RequestVc::parse(Value::new(pat)),
+                                    AstPathVc::cell(ast_path.to_vec()),
+                                    environment,
+                                ));
+                                return Ok(());
+                            }
+                        }
+                        // The call is left to the runtime, which throws an error.
+                        let (args, hints) = explain_args(&args);
+                        handler.span_warn_with_code(
+                            span,
+                            &format!(
+                                "import.meta.resolve({args}) is not statically analyse-able{hints}",
+                            ),
+                            DiagnosticId::Error(
+                                errors::failed_to_analyse::ecmascript::IMPORT_META.to_string(),
+                            ),
+                        )
+                    }
+
                     JsValue::WellKnownFunction(WellKnownFunctionKind::FsReadMethod(name)) => {
                         let args = linked_args().await?;
                         if !args.is_empty() {
@@ -1062,6 +1140,7 @@ pub(crate) async fn analyze_ecmascript_module(
             let linker = |value| value_visitor(source, origin, value, environment);
             let effects = take(&mut var_graph.effects);
             let link_value = |value| link(&var_graph, value, &linker, &cache);
+            let mut import_meta_used = false;
 
             for effect in effects.into_iter() {
                 match effect {
@@ -1157,14 +1236,12 @@ pub(crate) async fn analyze_ecmascript_module(
                             }
                         }
                     }
-                    Effect::ImportMeta { span, ast_path: _ } => {
-                        handler.span_warn_with_code(
-                            span,
-                            "import.meta is not yet supported",
-                            DiagnosticId::Error(
-                                errors::failed_to_analyse::ecmascript::IMPORT_META.to_string(),
-                            ),
-                        );
+                    Effect::ImportMeta { span: _, ast_path } => {
+                        if !import_meta_used {
+                            import_meta_used = true;
+                            analysis.add_code_gen(ImportMetaBindingVc::new(origin, environment));
+                        }
+                        analysis.add_code_gen(ImportMetaRefVc::new(AstPathVc::cell(ast_path)));
                     }
                 }
             }
@@ -78,12 +78,13 @@ impl PatternMapping {
                 quote!("undefined" as Expr)
             }
             PatternMapping::Single(module_id) => module_id_to_lit(module_id),
-            PatternMapping::Map(_) => {
-                todo!("emit an error for this case: Complex expression can't be transformed");
-            }
-            PatternMapping::OriginalReferenceExternal => {
-                todo!("emit an error for this case: apply need to be used");
-            }
+            PatternMapping::Map(_) => quote!(
+                "(() => {throw new Error(\"Complex expressions can't be transformed\")})()"
+                    as Expr
+            ),
+            PatternMapping::OriginalReferenceExternal => quote!(
+                "(() => {throw new Error(\"The original reference needs the request                  expression\")})()" as Expr
+            ),
             PatternMapping::OriginalReferenceTypeExternal(s) => {
                 Expr::Lit(Lit::Str(s.as_str().into()))
             }
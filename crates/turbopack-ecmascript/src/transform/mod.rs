@@ -17,7 +17,7 @@ use swc_core::{
     },
 };
 use turbo_tasks::primitives::StringVc;
-use turbopack_core::environment::EnvironmentVc;
+use turbopack_core::{asset::AssetVc, environment::EnvironmentVc};
 
 use self::server_to_client_proxy::{create_proxy_module, is_client_module};
 mod next_ssg;
@@ -27,8 +27,9 @@ mod next_ssg;
 pub enum EcmascriptInputTransform {
     ClientDirective(StringVc),
     CommonJs,
-    Custom,
-    Emotion,
+    /// A transform which is implemented outside of this crate.
+    Custom(CustomTransformVc),
+    Emotion(EmotionTransformConfigVc),
     /// This enables the Next SSG transform, which will eliminate
     /// `getStaticProps`/`getServerSideProps`/etc. exports from the output, as
     /// well as any imports that are only used by those exports.
@@ -40,14 +41,109 @@ pub enum EcmascriptInputTransform {
         #[serde(default)]
         refresh: bool,
     },
-    StyledComponents,
+    StyledComponents(StyledComponentsTransformConfigVc),
     StyledJsx,
     TypeScript,
 }
 
+/// Options of the emotion transform, like `compiler.emotion` in
+/// `next.config.js`.
+#[turbo_tasks::value(shared)]
+#[derive(Default, Clone, Debug)]
+pub struct EmotionTransformConfig {
+    pub sourcemap: Option<bool>,
+    /// Adds the name of the variable to the generated class names.
+    pub auto_label: Option<bool>,
+    /// The format of the label, e. g. `[local]` or `[filename]--[local]`.
+    pub label_format: Option<String>,
+}
+
+#[turbo_tasks::value_impl]
+impl EmotionTransformConfigVc {
+    #[turbo_tasks::function]
+    pub fn default() -> Self {
+        Self::cell(Default::default())
+    }
+}
+
+impl Default for EmotionTransformConfigVc {
+    fn default() -> Self {
+        Self::default()
+    }
+}
+
+/// Options of the styled-components transform, like
+/// `compiler.styledComponents` in `next.config.js`.
+#[turbo_tasks::value(shared)]
+#[derive(Clone, Debug)]
+pub struct StyledComponentsTransformConfig {
+    pub display_name: bool,
+    /// Adds ids to the components, so the class names generated on the server
+    /// match the ones on the client.
+    pub ssr: bool,
+    pub file_name: bool,
+    pub top_level_import_paths: Vec<String>,
+    pub meaningless_file_names: Vec<String>,
+    pub css_prop: bool,
+    pub namespace: Option<String>,
+}
+
+impl Default for StyledComponentsTransformConfig {
+    fn default() -> Self {
+        StyledComponentsTransformConfig {
+            display_name: true,
+            ssr: true,
+            file_name: true,
+            top_level_import_paths: Vec::new(),
+            meaningless_file_names: vec!["index".to_string()],
+            css_prop: true,
+            namespace: None,
+        }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl StyledComponentsTransformConfigVc {
+    #[turbo_tasks::function]
+    pub fn default() -> Self {
+        Self::cell(Default::default())
+    }
+}
+
+impl Default for StyledComponentsTransformConfigVc {
+    fn default() -> Self {
+        Self::default()
+    }
+}
+
 #[turbo_tasks::value(transparent, serialization = "auto_for_input")]
 #[derive(Debug, PartialOrd, Ord, Hash, Clone)]
-pub struct EcmascriptInputTransforms(Vec<EcmascriptInputTransform>);
+pub struct EcmascriptInputTransforms(pub Vec<EcmascriptInputTransform>);
+
+impl EcmascriptInputTransform {
+    /// Resolves the options of the transform, so equal transforms compare
+    /// equal no matter whether their options were passed around resolved.
+    pub async fn resolve(self) -> Result<Self> {
+        Ok(match self {
+            EcmascriptInputTransform::ClientDirective(transition_name) => {
+                EcmascriptInputTransform::ClientDirective(transition_name.resolve().await?)
+            }
+            EcmascriptInputTransform::Custom(transform) => {
+                EcmascriptInputTransform::Custom(transform.resolve().await?)
+            }
+            EcmascriptInputTransform::Emotion(config) => {
+                EcmascriptInputTransform::Emotion(config.resolve().await?)
+            }
+            EcmascriptInputTransform::PresetEnv(environment) => {
+                EcmascriptInputTransform::PresetEnv(environment.resolve().await?)
+            }
+            EcmascriptInputTransform::StyledComponents(config) => {
+                EcmascriptInputTransform::StyledComponents(config.resolve().await?)
+            }
+            transform => transform,
+        })
+    }
+}
 
 #[turbo_tasks::value_impl]
 impl EcmascriptInputTransformsVc {
@@ -59,28 +155,51 @@ impl EcmascriptInputTransformsVc {
     }
 }
 
+/// A transform which is implemented outside of this crate, e. g. by Next.js.
+pub trait CustomTransformer: Send + Sync {
+    fn transform(&self, program: &mut Program, context: &TransformContext<'_>) -> Result<()>;
+}
+
+#[turbo_tasks::value(shared, serialization = "none", eq = "manual", cell = "new")]
+pub struct CustomTransform {
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    pub transformer: Box<dyn CustomTransformer>,
+}
+
+impl CustomTransformVc {
+    pub fn new(transformer: impl CustomTransformer + 'static) -> Self {
+        CustomTransform {
+            transformer: Box::new(transformer),
+        }
+        .cell()
+    }
+}
+
 pub struct TransformContext<'a> {
     pub comments: &'a SwcComments,
     pub top_level_mark: Mark,
     pub unresolved_mark: Mark,
     pub source_map: &'a Arc<SourceMap>,
     pub file_name_str: &'a str,
+    /// The path of the transformed file in its file system.
+    pub file_path_str: &'a str,
     pub file_name_hash: u128,
+    /// The asset which is transformed, e. g. to report issues.
+    pub source: AssetVc,
 }
 
 impl EcmascriptInputTransform {
-    pub async fn apply(
-        &self,
-        program: &mut Program,
-        &TransformContext {
+    pub async fn apply(&self, program: &mut Program, context: &TransformContext<'_>) -> Result<()> {
+        let &TransformContext {
             comments,
             source_map,
             top_level_mark,
             unresolved_mark,
             file_name_str,
+            file_path_str,
             file_name_hash,
-        }: &TransformContext<'_>,
-    ) -> Result<()> {
+            ..
+        } = context;
         match *self {
             EcmascriptInputTransform::React { refresh } => {
                 program.visit_mut_with(&mut react(
@@ -115,10 +234,17 @@ impl EcmascriptInputTransform {
                     Some(comments.clone()),
                 ));
             }
-            EcmascriptInputTransform::Emotion => {
+            EcmascriptInputTransform::Emotion(config) => {
+                let config = config.await?;
                 let p = std::mem::replace(program, Program::Module(Module::dummy()));
                 *program = p.fold_with(&mut swc_emotion::emotion(
-                    Default::default(),
+                    swc_emotion::EmotionOptions {
+                        enabled: Some(true),
+                        sourcemap: config.sourcemap,
+                        auto_label: config.auto_label,
+                        label_format: config.label_format.clone(),
+                        ..Default::default()
+                    },
                     Path::new(file_name_str),
                     source_map.clone(),
                     comments.clone(),
@@ -145,11 +271,23 @@ impl EcmascriptInputTransform {
                     inject_helpers()
                 ));
             }
-            EcmascriptInputTransform::StyledComponents => {
+            EcmascriptInputTransform::StyledComponents(config) => {
+                let config = config.await?;
+                // The options of the transform are deserialized like the ones of
+                // the Babel plugin.
+                let config = serde_json::from_value(serde_json::json!({
+                    "displayName": config.display_name,
+                    "ssr": config.ssr,
+                    "fileName": config.file_name,
+                    "topLevelImportPaths": config.top_level_import_paths,
+                    "meaninglessFileNames": config.meaningless_file_names,
+                    "cssProp": config.css_prop,
+                    "namespace": config.namespace.clone().unwrap_or_default(),
+                }))?;
                 program.visit_mut_with(&mut styled_components::styled_components(
-                    FileName::Anon,
+                    FileName::Real(file_path_str.into()),
                     file_name_hash,
-                    serde_json::from_str("{}")?,
+                    config,
                 ));
             }
             EcmascriptInputTransform::StyledJsx => {
@@ -180,7 +318,9 @@ impl EcmascriptInputTransform {
 
                 *program = module_program.fold_with(&mut next_ssg(eliminated_packages));
             }
-            EcmascriptInputTransform::Custom => todo!(),
+            EcmascriptInputTransform::Custom(transform) => {
+                transform.await?.transformer.transform(program, context)?;
+            }
         }
         Ok(())
     }
@@ -62,13 +62,7 @@ pub async fn read_tsconfigs(
                 configs.push((data, tsconfig));
                 if let Some(extends) = json["extends"].as_str() {
                     let context = tsconfig.path().parent();
-                    let result = resolve(
-                        context,
-                        RequestVc::parse(Value::new(extends.to_string().into())),
-                        resolve_options,
-                    )
-                    .await?;
-                    if let ResolveResult::Single(asset, _) = *result {
+                    if let Some(asset) = resolve_extends(context, extends, resolve_options).await? {
                         data = asset.content().parse_json_with_comments();
                         tsconfig = asset;
                     } else {
@@ -91,6 +85,35 @@ pub async fn read_tsconfigs(
     Ok(configs)
 }
 
+/// Resolves `extends` like TypeScript does: A `.json` extension is optional,
+/// and a package can be extended by its `tsconfig.json`, which is common for
+/// shared configs in workspaces.
+async fn resolve_extends(
+    context: FileSystemPathVc,
+    extends: &str,
+    resolve_options: ResolveOptionsVc,
+) -> Result<Option<AssetVc>> {
+    let mut requests = vec![extends.to_string()];
+    if !extends.ends_with(".json") {
+        requests.push(format!("{extends}.json"));
+    }
+    if !extends.starts_with('.') && !extends.starts_with('/') {
+        requests.push(format!("{}/tsconfig.json", extends.trim_end_matches('/')));
+    }
+    for request in requests {
+        let result = resolve(
+            context,
+            RequestVc::parse(Value::new(request.into())),
+            resolve_options,
+        )
+        .await?;
+        if let ResolveResult::Single(asset, _) = *result {
+            return Ok(Some(asset));
+        }
+    }
+    Ok(None)
+}
+
 pub async fn read_from_tsconfigs<T>(
     configs: &[(FileJsonContentVc, AssetVc)],
     accessor: impl Fn(&JsonValue, AssetVc) -> Option<T>,
@@ -239,7 +262,7 @@ pub async fn type_resolve(origin: ResolveOriginVc, request: RequestVc) -> Result
         let m = if let Some(stripped) = m.strip_prefix('@') {
             stripped.replace('/', "__")
         } else {
-            m.clone()
+            m.to_string()
         };
         Some(RequestVc::module(
             format!("@types/{m}"),
@@ -39,6 +39,9 @@ pub fn js_value_to_pattern(value: &JsValue) -> Pattern {
         JsValue::Concat(_, parts) => {
             Pattern::Concatenation(parts.iter().map(js_value_to_pattern).collect())
         }
+        JsValue::Tenary(_, _, cons, alt) => {
+            Pattern::Alternatives(vec![js_value_to_pattern(cons), js_value_to_pattern(alt)])
+        }
         JsValue::Add(..) => {
             // TODO do we need to handle that here
             // or is that already covered by normalization of JsValue
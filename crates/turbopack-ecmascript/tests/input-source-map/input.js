@@ -0,0 +1,2 @@
+console.log("pre-compiled");
+//# sourceMappingURL=input.js.map
@@ -20,7 +20,10 @@ use turbo_tasks_fs::{
 use turbo_tasks_hash::encode_hex;
 use turbo_tasks_memory::MemoryBackend;
 use turbopack::{
-    ecmascript::{chunk::EcmascriptChunkPlaceablesVc, EcmascriptModuleAssetVc},
+    ecmascript::{
+        chunk::EcmascriptChunkPlaceablesVc, EcmascriptModuleAssetVc, EmotionTransformConfigVc,
+        StyledComponentsTransformConfigVc,
+    },
     module_options::ModuleOptionsContext,
     resolve_options_context::ResolveOptionsContext,
     transition::TransitionsByNameVc,
@@ -158,8 +161,8 @@ async fn run_test(resource: String) -> Result<FileSystemPathVc> {
         TransitionsByNameVc::cell(HashMap::new()),
         env,
         ModuleOptionsContext {
-            enable_emotion: true,
-            enable_styled_components: true,
+            enable_emotion: Some(EmotionTransformConfigVc::default()),
+            enable_styled_components: Some(StyledComponentsTransformConfigVc::default()),
             preset_env_versions: Some(env),
             ..Default::default()
         }
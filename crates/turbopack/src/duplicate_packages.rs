@@ -0,0 +1,179 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use anyhow::Result;
+use turbo_tasks::{primitives::StringVc, CompletionVc, ValueToString};
+use turbo_tasks_fs::{FileJsonContent, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetVc},
+    issue::{Issue, IssueSeverity, IssueSeverityVc, IssueVc},
+    reference::all_referenced_assets,
+};
+
+use crate::lockfile::{find_lockfile, lockfile_versions};
+
+/// Packages which break when more than one copy of them is loaded, because
+/// they keep global state like React contexts or a registry of styles.
+const SINGLETON_PACKAGES: [&str; 3] = ["react", "react-dom", "styled-components"];
+
+/// Emits an issue for each package in [SINGLETON_PACKAGES] which is loaded
+/// from more than one location in the module graph of `entry`. The issue lists
+/// how each copy is imported and the versions of the package in the lockfile.
+#[turbo_tasks::function]
+pub async fn check_duplicate_packages(entry: AssetVc) -> Result<CompletionVc> {
+    let mut parents = HashMap::new();
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    visited.insert(entry);
+    queue.push_back(entry);
+    // For each package, the first asset reached in each copy of it.
+    let mut copies: BTreeMap<&str, BTreeMap<String, AssetVc>> = BTreeMap::new();
+    while let Some(asset) = queue.pop_front() {
+        let path = asset.path().await?;
+        if let Some((name, dir)) = singleton_package(&path.path) {
+            copies
+                .entry(name)
+                .or_default()
+                .entry(dir.to_string())
+                .or_insert(asset);
+        }
+        for referenced in all_referenced_assets(asset).await?.iter() {
+            if visited.insert(*referenced) {
+                parents.insert(*referenced, asset);
+                queue.push_back(*referenced);
+            }
+        }
+    }
+
+    let lockfile = *find_lockfile(entry.path().parent()).await?;
+    for (name, copies) in copies {
+        if copies.len() < 2 {
+            continue;
+        }
+        let mut description = format!(
+            "The package {name} is loaded from {} locations. It doesn't work correctly when more \
+             than one copy of it is used.\n",
+            copies.len()
+        );
+        for (dir, asset) in copies {
+            let package_dir = asset.path().root().join(&dir);
+            let version = match &*package_dir.join("package.json").read_json().await? {
+                FileJsonContent::Content(json) => json["version"].as_str().map(|v| v.to_string()),
+                _ => None,
+            };
+            let mut chain = vec![asset.path().to_string().await?.clone_value()];
+            let mut current = asset;
+            while let Some(parent) = parents.get(&current) {
+                chain.push(parent.path().to_string().await?.clone_value());
+                current = *parent;
+            }
+            chain.reverse();
+            description.push_str(&format!(
+                "\n{} ({})\n  imported by {}\n",
+                package_dir.to_string().await?,
+                version.as_deref().unwrap_or("unknown version"),
+                chain.join("\n  -> ")
+            ));
+        }
+        if let Some(lockfile) = lockfile {
+            if let Some(versions) = lockfile_versions(lockfile).await?.get(name) {
+                description.push_str(&format!(
+                    "\n{} contains the versions {} of {name}. Deduplicating them, e. g. with \
+                     overrides or resolutions in package.json, fixes this.",
+                    lockfile.to_string().await?,
+                    versions.join(", ")
+                ));
+            }
+        }
+        DuplicatePackageIssue {
+            context: entry.path(),
+            package: name.to_string(),
+            description,
+        }
+        .cell()
+        .as_issue()
+        .emit();
+    }
+    Ok(CompletionVc::new())
+}
+
+/// Returns the singleton package and its directory if `path` is inside of a
+/// copy of it in a `node_modules` directory.
+fn singleton_package(path: &str) -> Option<(&'static str, &str)> {
+    const NODE_MODULES: &str = "node_modules/";
+    let (index, _) = path.rmatch_indices(NODE_MODULES).next()?;
+    let start = index + NODE_MODULES.len();
+    let rest = &path[start..];
+    SINGLETON_PACKAGES.into_iter().find_map(|name| {
+        rest.strip_prefix(name)
+            .filter(|rest| rest.starts_with('/'))
+            .map(|_| (name, &path[..start + name.len()]))
+    })
+}
+
+#[turbo_tasks::value(shared)]
+pub struct DuplicatePackageIssue {
+    pub context: FileSystemPathVc,
+    pub package: String,
+    pub description: String,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for DuplicatePackageIssue {
+    #[turbo_tasks::function]
+    fn severity(&self) -> IssueSeverityVc {
+        IssueSeverity::Warning.into()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> StringVc {
+        StringVc::cell(format!("Multiple copies of {} are loaded", self.package))
+    }
+
+    #[turbo_tasks::function]
+    fn category(&self) -> StringVc {
+        StringVc::cell("resolve".to_string())
+    }
+
+    #[turbo_tasks::function]
+    fn context(&self) -> FileSystemPathVc {
+        self.context
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> StringVc {
+        StringVc::cell(self.description.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::singleton_package;
+
+    #[test]
+    fn finds_singleton_packages() {
+        assert_eq!(
+            singleton_package("node_modules/react/index.js"),
+            Some(("react", "node_modules/react"))
+        );
+        assert_eq!(
+            singleton_package("node_modules/lib/node_modules/react-dom/cjs/react-dom.js"),
+            Some(("react-dom", "node_modules/lib/node_modules/react-dom"))
+        );
+        assert_eq!(
+            singleton_package(
+                "node_modules/.pnpm/styled-components@5.3.6/node_modules/styled-components/dist/\
+                 index.js"
+            ),
+            Some((
+                "styled-components",
+                "node_modules/.pnpm/styled-components@5.3.6/node_modules/styled-components"
+            ))
+        );
+        assert_eq!(
+            singleton_package("node_modules/react/node_modules/loose-envify/index.js"),
+            None
+        );
+        assert_eq!(singleton_package("node_modules/react-is/index.js"), None);
+        assert_eq!(singleton_package("src/react/index.js"), None);
+    }
+}
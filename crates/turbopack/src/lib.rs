@@ -16,8 +16,8 @@ use std::{
 use anyhow::Result;
 use css::{CssModuleAssetVc, ModuleCssModuleAssetVc};
 use ecmascript::{
-    typescript::resolve::TypescriptTypesAssetReferenceVc, EcmascriptModuleAssetType,
-    EcmascriptModuleAssetVc,
+    native_addon::NativeAddonModuleAssetVc, typescript::resolve::TypescriptTypesAssetReferenceVc,
+    EcmascriptModuleAssetType, EcmascriptModuleAssetVc,
 };
 use graph::{aggregate, AggregatedGraphNodeContent, AggregatedGraphVc};
 use lazy_static::lazy_static;
@@ -45,8 +45,11 @@ use turbopack_core::{
     },
 };
 
+pub mod duplicate_packages;
 mod graph;
+pub mod lockfile;
 pub mod module_options;
+pub mod nft_json;
 pub mod rebase;
 pub mod resolve;
 pub mod resolve_options_context;
@@ -200,6 +203,18 @@ async fn module(source: AssetVc, context: ModuleAssetContextVc) -> Result<AssetV
             ModuleCssModuleAssetVc::new(source, context.into(), *transforms).into()
         }
         ModuleType::Static => StaticModuleAssetVc::new(source, context.into()).into(),
+        ModuleType::NodeNativeAddon => {
+            if context
+                .await?
+                .resolve_options_context
+                .await?
+                .enable_node_native_modules
+            {
+                NativeAddonModuleAssetVc::new(source).into()
+            } else {
+                source
+            }
+        }
         ModuleType::Custom(_) => todo!(),
     })
 }
@@ -278,6 +293,23 @@ impl ModuleAssetContextVc {
             resolve_options_context,
         ))
     }
+
+    /// Returns a context which builds the same sources for another
+    /// environment, e. g. to build an entry for both modern and legacy
+    /// browsers. Resolving and transforming follow the new environment, and
+    /// the modules of both contexts are separate graphs, which are chunked
+    /// separately.
+    #[turbo_tasks::function]
+    pub async fn with_environment(self, environment: EnvironmentVc) -> Result<Self> {
+        let this = self.await?;
+        Ok(Self::cell(ModuleAssetContext {
+            transitions: this.transitions,
+            environment,
+            module_options_context: this.module_options_context.with_environment(environment),
+            resolve_options_context: this.resolve_options_context.with_environment(environment),
+            transition: this.transition,
+        }))
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -523,10 +555,10 @@ async fn warn_on_unsupported_modules(
 ) -> Result<()> {
     if let Request::Module { module, path } = &*request.await? {
         // Warn if the package is known not to be supported by Turbopack at the moment.
-        if UNSUPPORTED_PACKAGES.contains(module) {
+        if UNSUPPORTED_PACKAGES.contains(module.as_str()) {
             UnsupportedModuleIssue {
                 context: origin_path,
-                package: module.into(),
+                package: module.to_string(),
                 package_path: None,
             }
             .cell()
@@ -538,7 +570,7 @@ async fn warn_on_unsupported_modules(
             if UNSUPPORTED_PACKAGE_PATHS.contains(&(module.to_string(), path.to_owned())) {
                 UnsupportedModuleIssue {
                     context: origin_path,
-                    package: module.into(),
+                    package: module.to_string(),
                     package_path: Some(path.to_owned()),
                 }
                 .cell()
@@ -562,3 +594,253 @@ pub fn register() {
     turbopack_static::register();
     include!(concat!(env!("OUT_DIR"), "/register.rs"));
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use anyhow::{Context, Result};
+    use turbo_tasks::{TryJoinIterExt, TurboTasks, Value};
+    use turbo_tasks_fs::{DiskFileSystemVc, FileContent};
+    use turbo_tasks_memory::MemoryBackend;
+    use turbopack_core::{
+        asset::{Asset, AssetContent, AssetVc},
+        chunk::{
+            config::ChunkingConfig, dev::DevChunkingContextVc, optimize::optimize, ChunkGroupVc,
+            ChunkVc, ChunksVc,
+        },
+        environment::{
+            BrowserEnvironment, EnvironmentIntention, EnvironmentVc, ExecutionEnvironment,
+            NodeJsEnvironment,
+        },
+        source_asset::SourceAssetVc,
+    };
+
+    use super::{
+        ecmascript::{
+            chunk::{EcmascriptChunkPlaceableVc, EcmascriptChunkVc},
+            EcmascriptModuleAssetVc,
+        },
+        module_options::ModuleOptionsContext,
+        resolve_options_context::ResolveOptionsContext,
+        transition::TransitionsByNameVc,
+        ModuleAssetContextVc,
+    };
+
+    #[tokio::test]
+    async fn custom_environment() -> Result<()> {
+        super::register();
+        let tt = TurboTasks::new(MemoryBackend::new());
+        tt.run_once(async {
+            let env = EnvironmentVc::new(
+                Value::new(ExecutionEnvironment::Custom(0)),
+                Value::new(EnvironmentIntention::Custom(0)),
+            );
+            assert!(!*env.node_externals().await?);
+            assert!(!*env.resolve_node_modules().await?);
+            assert!(env.resolve_extensions().await?.is_empty());
+            assert!(env.resolve_conditions().await?.is_empty());
+            let versions = env.runtime_versions().await?;
+            assert!(versions.node.is_none() && versions.chrome.is_none());
+            assert!(env.cwd().await?.is_none());
+            env.compile_target().await?;
+            Ok(())
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn with_environment_builds_another_graph() -> Result<()> {
+        super::register();
+        let tt = TurboTasks::new(MemoryBackend::new());
+        tt.run_once(async {
+            let node = EnvironmentVc::new(
+                Value::new(ExecutionEnvironment::NodeJsLambda(
+                    NodeJsEnvironment::default().into(),
+                )),
+                Value::new(EnvironmentIntention::Api),
+            )
+            .resolve()
+            .await?;
+            let browser = EnvironmentVc::new(
+                Value::new(ExecutionEnvironment::Browser(
+                    BrowserEnvironment {
+                        dom: true,
+                        web_worker: false,
+                        service_worker: false,
+                        browserslist_query: "last 1 Chrome versions".to_string(),
+                    }
+                    .into(),
+                )),
+                Value::new(EnvironmentIntention::Client),
+            )
+            .resolve()
+            .await?;
+            let context = ModuleAssetContextVc::new(
+                TransitionsByNameVc::cell(HashMap::new()),
+                node,
+                ModuleOptionsContext {
+                    preset_env_versions: Some(node),
+                    ..Default::default()
+                }
+                .cell(),
+                ResolveOptionsContext {
+                    emulate_environment: Some(node),
+                    ..Default::default()
+                }
+                .cell(),
+            );
+            let browser_context = context.with_environment(browser);
+
+            let this = browser_context.await?;
+            assert_eq!(this.environment.resolve().await?, browser);
+            assert_eq!(
+                this.module_options_context.await?.preset_env_versions,
+                Some(browser)
+            );
+            assert_eq!(
+                this.resolve_options_context.await?.emulate_environment,
+                Some(browser)
+            );
+
+            let fs = DiskFileSystemVc::new(
+                "project".to_string(),
+                env!("CARGO_MANIFEST_DIR").to_string(),
+            );
+            let source: AssetVc = SourceAssetVc::new(
+                fs.root()
+                    .join("tests/node-file-trace/integration/apollo.js"),
+            )
+            .into();
+            let node_module = context.process(source).resolve().await?;
+            let browser_module = browser_context.process(source).resolve().await?;
+            assert_eq!(context.process(source).resolve().await?, node_module);
+            assert_ne!(node_module, browser_module);
+            Ok(())
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn top_level_await_makes_importers_async() -> Result<()> {
+        super::register();
+        let tt = TurboTasks::new(MemoryBackend::new());
+        tt.run_once(async {
+            let env = EnvironmentVc::new(
+                Value::new(ExecutionEnvironment::NodeJsLambda(
+                    NodeJsEnvironment::default().into(),
+                )),
+                Value::new(EnvironmentIntention::Api),
+            );
+            let context = ModuleAssetContextVc::new(
+                TransitionsByNameVc::cell(HashMap::new()),
+                env,
+                ModuleOptionsContext::default().cell(),
+                ResolveOptionsContext::default().cell(),
+            );
+            let fs = DiskFileSystemVc::new(
+                "project".to_string(),
+                env!("CARGO_MANIFEST_DIR").to_string(),
+            );
+            let dir = fs.root().join("tests/top-level-await");
+            for (file, is_async) in [
+                ("index.js", true),
+                ("a.js", true),
+                ("b.js", true),
+                ("cycle-a.js", true),
+                ("cycle-b.js", true),
+                ("sync-a.js", false),
+                ("sync-b.js", false),
+            ] {
+                let module = context.process(SourceAssetVc::new(dir.join(file)).into());
+                let module = EcmascriptModuleAssetVc::resolve_from(module)
+                    .await?
+                    .unwrap();
+                assert_eq!(*module.is_async_module().await?, is_async, "{file}");
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn extracts_shared_chunk_items() -> Result<()> {
+        super::register();
+        let tt = TurboTasks::new(MemoryBackend::new());
+        tt.run_once(async {
+            let env = EnvironmentVc::new(
+                Value::new(ExecutionEnvironment::NodeJsLambda(
+                    NodeJsEnvironment::default().into(),
+                )),
+                Value::new(EnvironmentIntention::Api),
+            );
+            let context = ModuleAssetContextVc::new(
+                TransitionsByNameVc::cell(HashMap::new()),
+                env,
+                ModuleOptionsContext::default().cell(),
+                ResolveOptionsContext::default().cell(),
+            );
+            let fs = DiskFileSystemVc::new(
+                "project".to_string(),
+                env!("CARGO_MANIFEST_DIR").to_string(),
+            );
+            let dir = fs.root().join("tests/shared-chunks");
+            // Both chunks contain shared.js and shared-dep.js. They are merged
+            // by default, and split into three chunks with a shared chunk when
+            // configured.
+            for (config, chunk_count) in [
+                (ChunkingConfig::default(), 1),
+                (
+                    ChunkingConfig {
+                        min_shared_chunk_items: Some(2),
+                        ..Default::default()
+                    },
+                    3,
+                ),
+            ] {
+                let chunking_context =
+                    DevChunkingContextVc::builder(dir, dir, dir.join("chunks"), dir.join("assets"))
+                        .chunking_config(config.cell())
+                        .build();
+                let chunks: Vec<ChunkVc> = ["a.js", "b.js"]
+                    .into_iter()
+                    .map(|file| async move {
+                        let module = context.process(SourceAssetVc::new(dir.join(file)).into());
+                        let placeable = EcmascriptChunkPlaceableVc::resolve_from(module)
+                            .await?
+                            .context("not an ecmascript module")?;
+                        Ok(EcmascriptChunkVc::new(chunking_context, placeable).into())
+                    })
+                    .try_join()
+                    .await?;
+                let chunk_group = ChunkGroupVc::from_chunk(chunks[0]);
+                let chunks = optimize(ChunksVc::cell(chunks), chunk_group).await?;
+                assert_eq!(chunks.len(), chunk_count);
+
+                let mut contents = Vec::new();
+                for chunk in chunks.iter() {
+                    let content = match &*chunk.content().await? {
+                        AssetContent::File(file) => file.await?,
+                        _ => panic!("chunks are files"),
+                    };
+                    match &*content {
+                        FileContent::Content(file) => {
+                            contents.push(file.content().to_str()?.into_owned())
+                        }
+                        FileContent::NotFound => panic!("chunks have content"),
+                    }
+                }
+                // Every module is included exactly once.
+                for module in ["module a", "module b", "module shared", "module shared-dep"] {
+                    let count = contents
+                        .iter()
+                        .filter(|content| content.contains(&format!("\"{module}\"")))
+                        .count();
+                    assert_eq!(count, 1, "{module}");
+                }
+            }
+            Ok(())
+        })
+        .await
+    }
+}
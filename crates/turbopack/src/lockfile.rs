@@ -0,0 +1,284 @@
+//! Reading the locked package versions from the lockfile of npm, yarn or pnpm.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde_json::Value as JsonValue;
+use turbo_tasks_fs::{FileContent, FileSystemPathOptionVc, FileSystemPathVc};
+use turbopack_core::resolve::{find_context_file, FindContextFileResult};
+
+const LOCKFILE_NAMES: [&str; 3] = ["pnpm-lock.yaml", "yarn.lock", "package-lock.json"];
+
+/// Finds the lockfile of the package manager, in `context` or one of its
+/// parent directories, e. g. the root of a workspace.
+#[turbo_tasks::function]
+pub async fn find_lockfile(context: FileSystemPathVc) -> Result<FileSystemPathOptionVc> {
+    for name in LOCKFILE_NAMES {
+        if let FindContextFileResult::Found(path, _) = &*find_context_file(context, name).await? {
+            return Ok(FileSystemPathOptionVc::cell(Some(*path)));
+        }
+    }
+    Ok(FileSystemPathOptionVc::cell(None))
+}
+
+/// The versions of each package in a lockfile, sorted and without duplicates.
+#[turbo_tasks::value(transparent)]
+pub struct LockfileVersions(HashMap<String, Vec<String>>);
+
+/// Reads the versions of all packages from a lockfile. The lockfile format is
+/// determined by its file name.
+#[turbo_tasks::function]
+pub async fn lockfile_versions(lockfile: FileSystemPathVc) -> Result<LockfileVersionsVc> {
+    let mut versions = HashMap::new();
+    if let FileContent::Content(file) = &*lockfile.read().await? {
+        let content = file.content().to_str()?;
+        match lockfile.await?.file_name() {
+            "package-lock.json" => parse_package_lock(&content, &mut versions),
+            "yarn.lock" => parse_yarn_lock(&content, &mut versions),
+            "pnpm-lock.yaml" => parse_pnpm_lock(&content, &mut versions),
+            _ => {}
+        }
+    }
+    for list in versions.values_mut() {
+        list.sort();
+        list.dedup();
+    }
+    Ok(LockfileVersionsVc::cell(versions))
+}
+
+fn insert(versions: &mut HashMap<String, Vec<String>>, name: &str, version: &str) {
+    if !name.is_empty() && !version.is_empty() {
+        versions
+            .entry(name.to_string())
+            .or_default()
+            .push(version.to_string());
+    }
+}
+
+/// `package-lock.json` lists packages by their install location since
+/// version 2, and as a tree of `dependencies` before.
+fn parse_package_lock(content: &str, versions: &mut HashMap<String, Vec<String>>) {
+    let json: JsonValue = match serde_json::from_str(content) {
+        Ok(json) => json,
+        Err(_) => return,
+    };
+    if let JsonValue::Object(packages) = &json["packages"] {
+        for (location, package) in packages {
+            let name = match package["name"].as_str() {
+                Some(name) => name,
+                None => match location.rsplit_once("node_modules/") {
+                    Some((_, name)) => name,
+                    None => continue,
+                },
+            };
+            if let Some(version) = package["version"].as_str() {
+                insert(versions, name, version);
+            }
+        }
+    } else {
+        fn visit(dependencies: &JsonValue, versions: &mut HashMap<String, Vec<String>>) {
+            if let JsonValue::Object(dependencies) = dependencies {
+                for (name, dependency) in dependencies {
+                    if let Some(version) = dependency["version"].as_str() {
+                        insert(versions, name, version);
+                    }
+                    visit(&dependency["dependencies"], versions);
+                }
+            }
+        }
+        visit(&json["dependencies"], versions);
+    }
+}
+
+/// `yarn.lock` lists entries for one or more descriptors, e. g.
+/// `"react@^18.0.0", react@^18.2.0:`, followed by indented fields. The version
+/// is `version "1.0.0"` in yarn 1 and `version: 1.0.0` in later versions.
+fn parse_yarn_lock(content: &str, versions: &mut HashMap<String, Vec<String>>) {
+    let mut names = Vec::new();
+    for line in content.lines() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) {
+            names = line
+                .trim_end_matches(':')
+                .split(", ")
+                .filter_map(|descriptor| descriptor_name(descriptor.trim_matches('"')))
+                .collect::<Vec<_>>();
+            names.dedup();
+            continue;
+        }
+        if let Some(version) = line.trim().strip_prefix("version") {
+            let version = version.trim_start_matches(':').trim().trim_matches('"');
+            for name in names.iter() {
+                insert(versions, name, version);
+            }
+        }
+    }
+}
+
+/// The package name of a descriptor like `@scope/name@^1.0.0`.
+fn descriptor_name(descriptor: &str) -> Option<&str> {
+    let index = descriptor.get(1..)?.find('@')? + 1;
+    Some(&descriptor[..index])
+}
+
+/// `pnpm-lock.yaml` lists packages in the `packages` section by keys like
+/// `/name/1.0.0_peer@1.0.0` (version 5), `/name@1.0.0(peer@1.0.0)` (version 6)
+/// or `name@1.0.0` (version 9).
+fn parse_pnpm_lock(content: &str, versions: &mut HashMap<String, Vec<String>>) {
+    let mut in_packages = false;
+    for line in content.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) {
+            in_packages = line.trim_end() == "packages:";
+            continue;
+        }
+        if !in_packages {
+            continue;
+        }
+        // Only the keys of the packages are relevant, not their fields.
+        let key = match line.strip_prefix("  ") {
+            Some(key) if !key.starts_with(char::is_whitespace) => key.trim_end(),
+            _ => continue,
+        };
+        let key = match key.strip_suffix(':') {
+            Some(key) => key.trim_matches(|c| c == '\'' || c == '"'),
+            None => continue,
+        };
+        if let Some((name, version)) = pnpm_package(key) {
+            insert(versions, name, version);
+        }
+    }
+}
+
+fn pnpm_package(key: &str) -> Option<(&str, &str)> {
+    let key = key.trim_start_matches('/');
+    let key = key.split('(').next().unwrap_or_default();
+    let name_start = if key.starts_with('@') {
+        key.find('/')? + 1
+    } else {
+        0
+    };
+    let separator = name_start + key[name_start..].find(|c| c == '@' || c == '/')?;
+    let name = &key[..separator];
+    let version = &key[separator + 1..];
+    if key[separator..].starts_with('/') {
+        // Peer dependencies are appended with `_` in version 5.
+        Some((name, version.split('_').next().unwrap_or_default()))
+    } else {
+        Some((name, version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{parse_package_lock, parse_pnpm_lock, parse_yarn_lock};
+
+    fn versions_of(
+        parse: fn(&str, &mut HashMap<String, Vec<String>>),
+        content: &str,
+    ) -> HashMap<String, Vec<String>> {
+        let mut versions = HashMap::new();
+        parse(content, &mut versions);
+        for list in versions.values_mut() {
+            list.sort();
+        }
+        versions
+    }
+
+    #[test]
+    fn parses_package_lock() {
+        let versions = versions_of(
+            parse_package_lock,
+            r#"{
+                "lockfileVersion": 3,
+                "packages": {
+                    "": { "name": "app" },
+                    "node_modules/react": { "version": "18.2.0" },
+                    "node_modules/@emotion/react": { "version": "11.10.5" },
+                    "node_modules/lib/node_modules/react": { "version": "17.0.2" }
+                }
+            }"#,
+        );
+        assert_eq!(versions["react"], vec!["17.0.2", "18.2.0"]);
+        assert_eq!(versions["@emotion/react"], vec!["11.10.5"]);
+        assert!(!versions.contains_key("app"));
+
+        let versions = versions_of(
+            parse_package_lock,
+            r#"{
+                "lockfileVersion": 1,
+                "dependencies": {
+                    "react": { "version": "18.2.0" },
+                    "lib": {
+                        "version": "1.0.0",
+                        "dependencies": { "react": { "version": "17.0.2" } }
+                    }
+                }
+            }"#,
+        );
+        assert_eq!(versions["react"], vec!["17.0.2", "18.2.0"]);
+    }
+
+    #[test]
+    fn parses_yarn_lock() {
+        let versions = versions_of(
+            parse_yarn_lock,
+            r#"# yarn lockfile v1
+
+"@babel/core@^7.0.0", "@babel/core@^7.1.0":
+  version "7.20.5"
+  dependencies:
+    react "^17.0.0"
+
+react@^17.0.0:
+  version "17.0.2"
+
+"react@npm:^18.2.0":
+  version: 18.2.0
+"#,
+        );
+        assert_eq!(versions["@babel/core"], vec!["7.20.5"]);
+        assert_eq!(versions["react"], vec!["17.0.2", "18.2.0"]);
+    }
+
+    #[test]
+    fn parses_pnpm_lock() {
+        let versions = versions_of(
+            parse_pnpm_lock,
+            r#"lockfileVersion: 5.4
+
+importers:
+  .:
+    specifiers:
+      react: ^18.2.0
+
+packages:
+
+  /react-dom/17.0.2_react@17.0.2:
+    resolution: {integrity: sha512-abc}
+    dependencies:
+      react: 17.0.2
+
+  /@babel/core/7.20.5:
+    resolution: {integrity: sha512-abc}
+
+  /react@18.2.0(typescript@4.9.4):
+    resolution: {integrity: sha512-abc}
+
+  '@emotion/react@11.10.5':
+    resolution: {integrity: sha512-abc}
+"#,
+        );
+        assert_eq!(versions["react-dom"], vec!["17.0.2"]);
+        assert_eq!(versions["@babel/core"], vec!["7.20.5"]);
+        assert_eq!(versions["react"], vec!["18.2.0"]);
+        assert_eq!(versions["@emotion/react"], vec!["11.10.5"]);
+        assert!(!versions.contains_key("typescript"));
+    }
+}
@@ -41,11 +41,11 @@ impl ModuleOptionsVc {
         if enable_styled_jsx {
             transforms.push(EcmascriptInputTransform::StyledJsx)
         }
-        if enable_emotion {
-            transforms.push(EcmascriptInputTransform::Emotion)
+        if let Some(config) = enable_emotion {
+            transforms.push(EcmascriptInputTransform::Emotion(config))
         }
-        if enable_styled_components {
-            transforms.push(EcmascriptInputTransform::StyledComponents)
+        if let Some(config) = enable_styled_components {
+            transforms.push(EcmascriptInputTransform::StyledComponents(config))
         }
         transforms.push(EcmascriptInputTransform::React {
             refresh: enable_react_refresh,
@@ -181,6 +181,10 @@ impl ModuleOptionsVc {
                 ]),
                 vec![ModuleRuleEffect::ModuleType(ModuleType::Static)],
             ),
+            ModuleRule::new(
+                ModuleRuleCondition::ResourcePathEndsWith(".node".to_string()),
+                vec![ModuleRuleEffect::ModuleType(ModuleType::NodeNativeAddon)],
+            ),
             ModuleRule::new(
                 ModuleRuleCondition::ResourcePathHasNoExtension,
                 vec![ModuleRuleEffect::ModuleType(ModuleType::Ecmascript(
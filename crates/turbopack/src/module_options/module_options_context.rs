@@ -1,14 +1,17 @@
+use anyhow::Result;
 use turbopack_core::environment::EnvironmentVc;
-use turbopack_ecmascript::EcmascriptInputTransform;
+use turbopack_ecmascript::{
+    EcmascriptInputTransform, EmotionTransformConfigVc, StyledComponentsTransformConfigVc,
+};
 
 use super::ModuleRule;
 
 #[turbo_tasks::value(shared)]
 #[derive(Default, Clone)]
 pub struct ModuleOptionsContext {
-    pub enable_emotion: bool,
+    pub enable_emotion: Option<EmotionTransformConfigVc>,
     pub enable_react_refresh: bool,
-    pub enable_styled_components: bool,
+    pub enable_styled_components: Option<StyledComponentsTransformConfigVc>,
     pub enable_styled_jsx: bool,
     pub enable_typescript_transform: bool,
     pub preset_env_versions: Option<EnvironmentVc>,
@@ -25,6 +28,17 @@ impl ModuleOptionsContextVc {
     pub fn default() -> Self {
         Self::cell(Default::default())
     }
+
+    /// Returns a new [ModuleOptionsContextVc] which transforms for
+    /// `environment` instead, when it transforms for an environment at all.
+    #[turbo_tasks::function]
+    pub async fn with_environment(self, environment: EnvironmentVc) -> Result<Self> {
+        let mut clone = self.await?.clone_value();
+        if clone.preset_env_versions.is_some() {
+            clone.preset_env_versions = Some(environment);
+        }
+        Ok(Self::cell(clone))
+    }
 }
 
 impl Default for ModuleOptionsContextVc {
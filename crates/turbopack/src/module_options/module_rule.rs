@@ -102,6 +102,9 @@ pub enum ModuleType {
     Css(CssInputTransformsVc),
     CssModule(CssInputTransformsVc),
     Static,
+    /// A Node.js native addon (`.node` file), which is copied to the output
+    /// when native modules are enabled for resolving.
+    NodeNativeAddon,
     // TODO allow custom function when we support function pointers
     Custom(u8),
 }
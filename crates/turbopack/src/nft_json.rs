@@ -0,0 +1,129 @@
+use std::path::{Component, Path};
+
+use anyhow::Result;
+use serde_json::json;
+use turbo_tasks_fs::{to_sys_path, File, FileSystemPathVc};
+use turbopack_core::{
+    asset::{Asset, AssetContentVc, AssetVc},
+    reference::{all_assets, AssetReferencesVc},
+};
+
+/// A `{entry}.nft.json` file in the format of `@vercel/nft`, which lists all
+/// files reachable from `entry`, relative to the directory of the entry.
+///
+/// Files on a different file system than the entry are listed relative to
+/// the entry on disk, e. g. `node_modules` of the project for an entry in the
+/// output directory.
+#[turbo_tasks::value(shared)]
+pub struct NftJsonAsset {
+    entry: AssetVc,
+}
+
+#[turbo_tasks::value_impl]
+impl NftJsonAssetVc {
+    #[turbo_tasks::function]
+    pub fn new(entry: AssetVc) -> Self {
+        Self::cell(NftJsonAsset { entry })
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for NftJsonAsset {
+    #[turbo_tasks::function]
+    async fn path(&self) -> Result<FileSystemPathVc> {
+        let path = self.entry.path().await?;
+        Ok(path.fs.root().join(&format!("{}.nft.json", path.path)))
+    }
+
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<AssetContentVc> {
+        let context_vc = self.entry.path().parent();
+        let context = context_vc.await?;
+        let context_sys_path = to_sys_path(context_vc).await?;
+        let entry_path = self.entry.path().await?;
+        let mut result = Vec::new();
+        let set = all_assets(self.entry);
+        for asset in set.await?.iter() {
+            let path_vc = asset.path();
+            let path = path_vc.await?;
+            if *path == *entry_path {
+                continue;
+            }
+            if let Some(rel_path) = context.get_relative_path_to(&path) {
+                result.push(rel_path);
+            } else if let (Some(from), Some(to)) = (&context_sys_path, to_sys_path(path_vc).await?)
+            {
+                if let Some(rel_path) = relative_sys_path(from, &to) {
+                    result.push(rel_path);
+                }
+            }
+        }
+        result.sort();
+        result.dedup();
+        let json = json!({
+          "version": 1,
+          "files": result
+        });
+
+        Ok(File::from(json.to_string()).into())
+    }
+
+    #[turbo_tasks::function]
+    fn references(&self) -> AssetReferencesVc {
+        AssetReferencesVc::empty()
+    }
+}
+
+/// Returns the /-separated path of `to` relative to the directory `from`, or
+/// `None` when they don't share a root, like paths on different drives.
+fn relative_sys_path(from: &Path, to: &Path) -> Option<String> {
+    let mut from = from.components().peekable();
+    let mut to = to.components().peekable();
+    while from.peek().is_some() && from.peek() == to.peek() {
+        from.next();
+        to.next();
+    }
+    let mut segments = Vec::new();
+    for component in from {
+        if let Component::Normal(_) = component {
+            segments.push("..".to_string());
+        }
+    }
+    for component in to {
+        match component {
+            Component::Normal(segment) => segments.push(segment.to_str()?.to_string()),
+            _ => return None,
+        }
+    }
+    if segments.is_empty() {
+        return Some(".".to_string());
+    }
+    Some(segments.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::relative_sys_path;
+
+    #[test]
+    fn relativizes_sys_paths() {
+        assert_eq!(
+            relative_sys_path(
+                Path::new("/project/.next/server/pages"),
+                Path::new("/project/node_modules/react/index.js")
+            )
+            .as_deref(),
+            Some("../../../node_modules/react/index.js")
+        );
+        assert_eq!(
+            relative_sys_path(Path::new("/project"), Path::new("/project/a.js")).as_deref(),
+            Some("a.js")
+        );
+        assert_eq!(
+            relative_sys_path(Path::new("/project"), Path::new("/project")).as_deref(),
+            Some(".")
+        );
+    }
+}
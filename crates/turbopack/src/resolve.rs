@@ -8,6 +8,7 @@ use turbopack_core::resolve::{
         ConditionValue, ImportMap, ImportMapping, ResolveInPackage, ResolveIntoPackage,
         ResolveModules, ResolveOptions, ResolveOptionsVc,
     },
+    pnp::find_pnp_manifest,
     AliasMap, AliasPattern, FindContextFileResult,
 };
 use turbopack_ecmascript::{
@@ -17,7 +18,8 @@ use turbopack_ecmascript::{
 
 use crate::resolve_options_context::ResolveOptionsContextVc;
 
-const NODE_EXTERNALS: [&str; 51] = [
+/// The built-in modules of Node.js, which are resolved as externals.
+pub const NODE_EXTERNALS: [&str; 51] = [
     "assert",
     "async_hooks",
     "buffer",
@@ -233,6 +235,11 @@ pub async fn resolve_options(
 ) -> Result<ResolveOptionsVc> {
     let resolve_options = base_resolve_options(context, options_context);
 
+    let resolve_options = match *find_pnp_manifest(context).await? {
+        Some(manifest) => resolve_options.with_pnp_manifest(manifest),
+        None => resolve_options,
+    };
+
     let options_context = options_context.await?;
     let resolve_options = if options_context.enable_typescript {
         let tsconfig = find_context_file(context, "tsconfig.json").await?;
@@ -51,6 +51,17 @@ impl ResolveOptionsContextVc {
         Ok(Self::cell(clone))
     }
 
+    /// Returns a new [ResolveOptionsContextVc] which emulates `environment`
+    /// instead, when it emulates an environment at all.
+    #[turbo_tasks::function]
+    pub async fn with_environment(self, environment: EnvironmentVc) -> Result<Self> {
+        let mut clone = self.await?.clone_value();
+        if clone.emulate_environment.is_some() {
+            clone.emulate_environment = Some(environment);
+        }
+        Ok(Self::cell(clone))
+    }
+
     /// Returns a new [ResolveOptionsContextVc] with its import map extended to
     /// include the given import map.
     #[turbo_tasks::function]
@@ -0,0 +1,3 @@
+console.log("module shared-dep");
+
+export const dep = 1;
@@ -0,0 +1,5 @@
+import { dep } from "./shared-dep.js";
+
+console.log("module shared");
+
+export const shared = dep + 1;
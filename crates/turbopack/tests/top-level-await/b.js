@@ -0,0 +1 @@
+export const b = await Promise.resolve(1);
@@ -0,0 +1,3 @@
+import { cycleB } from "./cycle-b.js";
+
+export const cycleA = () => cycleB;
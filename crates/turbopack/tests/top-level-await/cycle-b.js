@@ -0,0 +1,4 @@
+import { cycleA } from "./cycle-a.js";
+import { b } from "./b.js";
+
+export const cycleB = () => cycleA() + b;
@@ -0,0 +1,5 @@
+import { a } from "./a.js";
+import { cycleA } from "./cycle-a.js";
+import { syncA } from "./sync-a.js";
+
+console.log(a, cycleA, syncA);
@@ -0,0 +1,3 @@
+import { syncB } from "./sync-b.js";
+
+export const syncA = () => syncB;
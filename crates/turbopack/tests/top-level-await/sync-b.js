@@ -0,0 +1,3 @@
+import { syncA } from "./sync-a.js";
+
+export const syncB = () => syncA;